@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+# Dump the programs and accounts tests/localnet_e2e.rs loads into
+# solana-test-validator. Needs the solana CLI, curl and jq.
+#
+#   RPC_URL=<mainnet rpc> scripts/fetch_localnet_fixtures.sh
+#   cargo test --features localnet-e2e --test localnet_e2e
+set -euo pipefail
+
+RPC_URL="${RPC_URL:-https://api.mainnet-beta.solana.com}"
+OUT="${LOCALNET_FIXTURES_DIR:-$(cd "$(dirname "$0")/.." && pwd)/fixtures/localnet}"
+
+EXECUTOR=MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz
+RAYDIUM_CP=CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C
+CREATE_POOL_FEE=DNXgeM9EiiaAbaWvwjHj9fQQLAX5ZsfHyxmYUNRAftam
+AMM_CONFIG_SIZE=236 # discriminator, bump, disable_create_pool, index, four fees, two owners, padding
+
+mkdir -p "$OUT/accounts"
+solana program dump -u "$RPC_URL" "$EXECUTOR" "$OUT/executor.so"
+solana program dump -u "$RPC_URL" "$RAYDIUM_CP" "$OUT/raydium_cp.so"
+
+# Two AMM configs that still allow pool creation (disable_create_pool, at offset 9, is 0)
+mapfile -t configs < <(
+  curl -sf "$RPC_URL" -X POST -H 'Content-Type: application/json' -d "{
+    \"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"getProgramAccounts\",
+    \"params\": [\"$RAYDIUM_CP\", {
+      \"encoding\": \"base64\",
+      \"dataSlice\": {\"offset\": 0, \"length\": 0},
+      \"filters\": [{\"dataSize\": $AMM_CONFIG_SIZE}, {\"memcmp\": {\"offset\": 9, \"bytes\": \"1\"}}]
+    }]
+  }" | jq -r '.result[].pubkey' | sort | head -n 2
+)
+if (( ${#configs[@]} < 2 )); then
+  echo "found ${#configs[@]} CP-Swap AMM configs open for pool creation, need 2" >&2
+  exit 1
+fi
+
+for account in "${configs[@]}" "$CREATE_POOL_FEE"; do
+  solana account -u "$RPC_URL" --output json -o "$OUT/accounts/$account.json" "$account"
+done
+echo "localnet fixtures written to $OUT"
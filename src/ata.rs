@@ -65,19 +65,36 @@ fn ensure_ata_exists(
     }
 }
 
-/// Ensures all base token ATAs (WSOL, USDC, USD1) exist.
+/// Name a base mint is logged under
+fn base_mint_name(mint: &Pubkey) -> String {
+    if *mint == sol_mint() {
+        "WSOL".to_string()
+    } else if *mint == usdc_mint() {
+        "USDC".to_string()
+    } else if *mint == usd1_mint() {
+        "USD1".to_string()
+    } else {
+        mint.to_string()
+    }
+}
+
+/// Ensures the ATA of every base mint cycles start in exists, so each mint's
+/// budget is checked against its own token account.
 /// This should be called during bot initialization before processing pools.
-pub fn ensure_base_atas_exist(rpc_client: &RpcClient, wallet_kp: &Keypair) -> Result<()> {
+pub fn ensure_base_atas_exist(rpc_client: &RpcClient, wallet_kp: &Keypair, base_mints: &[Pubkey]) -> Result<()> {
     info!("Verifying base token ATAs...");
 
-    let wsol_ata = ensure_ata_exists(rpc_client, wallet_kp, &sol_mint(), "WSOL")?;
-    let usdc_ata = ensure_ata_exists(rpc_client, wallet_kp, &usdc_mint(), "USDC")?;
-    let usd1_ata = ensure_ata_exists(rpc_client, wallet_kp, &usd1_mint(), "USD1")?;
+    let mut atas = Vec::with_capacity(base_mints.len());
+    for mint in base_mints {
+        let name = base_mint_name(mint);
+        let ata = ensure_ata_exists(rpc_client, wallet_kp, mint, &name)?;
+        atas.push((name, ata));
+    }
 
     info!("All base token ATAs verified/created successfully");
-    info!("  WSOL ATA: {}", wsol_ata);
-    info!("  USDC ATA: {}", usdc_ata);
-    info!("  USD1 ATA: {}", usd1_ata);
+    for (name, ata) in atas {
+        info!("  {} ATA: {}", name, ata);
+    }
 
     Ok(())
 }
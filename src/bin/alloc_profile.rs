@@ -0,0 +1,225 @@
+//! Allocation profile of the refresh/detect hot path.
+//!
+//! Runs a fixed number of iterations over a synthetic graph (the same shape
+//! as `latency-bench`: N token mints with two SOL pools each) and reports,
+//! per stage, allocations, bytes allocated, peak live-byte growth and time.
+//! Detection runs twice, once building fresh distance/predecessor maps every
+//! pass and once reusing a `DetectorScratch` as `IncrementalDetector` does, so
+//! the report shows what the reuse saves. Run the same seed on two branches
+//! to compare them.
+//!
+//! Run with:
+//! `cargo run --release --features profiling --bin alloc-profile -- --profile-iterations 200`
+
+use clap::{App, Arg};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_onchain_arbitrage_bot::constants::sol_mint;
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_onchain_arbitrage_bot::profiling::{rss_kb, StageMark, StageProfile};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+const STAGES: [&str; 3] = ["edge-update", "detection", "optimize"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DetectorMaps {
+    Fresh,
+    Reused,
+}
+
+impl DetectorMaps {
+    fn label(&self) -> &'static str {
+        match self {
+            DetectorMaps::Fresh => "fresh detector maps",
+            DetectorMaps::Reused => "reused detector scratch",
+        }
+    }
+}
+
+/// Per-stage sums over all iterations of one run
+struct RunTotals {
+    maps: DetectorMaps,
+    stages: Vec<StageProfile>,
+    cycles: usize,
+}
+
+impl RunTotals {
+    fn add(&mut self, stage: usize, profile: StageProfile) {
+        let total = &mut self.stages[stage];
+        total.allocations += profile.allocations;
+        total.allocated_bytes += profile.allocated_bytes;
+        total.peak_growth_bytes = total.peak_growth_bytes.max(profile.peak_growth_bytes);
+        total.elapsed += profile.elapsed;
+        total.rss_kb = profile.rss_kb;
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let matches = App::new("alloc-profile")
+        .about("Report allocations and timing per stage of the refresh/detect hot path")
+        .arg(
+            Arg::with_name("profile-iterations")
+                .long("profile-iterations")
+                .value_name("N")
+                .help("Iterations to run per detector variant")
+                .takes_value(true)
+                .default_value("100"),
+        )
+        .arg(
+            Arg::with_name("mints")
+                .long("mints")
+                .value_name("N")
+                .help("Number of token mints, each with two SOL pools")
+                .takes_value(true)
+                .default_value("50"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for the synthetic price moves")
+                .takes_value(true)
+                .default_value("42"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("Write the report as markdown instead of printing it")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let iterations: usize = matches.value_of("profile-iterations").unwrap().parse()?;
+    let mints: usize = matches.value_of("mints").unwrap().parse()?;
+    let seed: u64 = matches.value_of("seed").unwrap().parse()?;
+
+    let runs: Vec<RunTotals> = [DetectorMaps::Fresh, DetectorMaps::Reused]
+        .iter()
+        .map(|&maps| run(maps, iterations, mints, seed))
+        .collect();
+
+    let report = render_report(&runs, iterations, mints, seed);
+    match matches.value_of("output") {
+        Some(path) => {
+            std::fs::write(path, &report)?;
+            println!("Wrote allocation profile to {}", path);
+        }
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+fn run(maps: DetectorMaps, iterations: usize, mint_count: usize, seed: u64) -> RunTotals {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let sol = sol_mint();
+    let pools: Vec<(Pubkey, Pubkey, f64)> = (0..mint_count)
+        .flat_map(|_| {
+            let mint = Pubkey::new_unique();
+            let price = rng.gen_range(0.5..2.0);
+            [(mint, Pubkey::new_unique(), price), (mint, Pubkey::new_unique(), price)]
+        })
+        .collect();
+
+    let graph = Arc::new(PriceGraph::new());
+    let optimizer = AmountOptimizer::new(graph.clone(), 1, 0.0);
+    let threshold = ProfitThreshold::flat(0);
+    let limits = CapitalLimits {
+        max_capital: 2_000_000_000,
+        per_cycle_percent: 20,
+        min_profit: 0,
+        min_input_fraction: 0.0,
+        max_input_fraction: 1.0,
+    };
+    let mut scratch = DetectorScratch::default();
+    let mut totals = RunTotals {
+        maps,
+        stages: STAGES.iter().map(|_| StageProfile::default()).collect(),
+        cycles: 0,
+    };
+
+    for _ in 0..iterations {
+        let mark = StageMark::start();
+        graph.begin_generation();
+        for (mint, pool, base_price) in &pools {
+            let price = base_price * rng.gen_range(0.99..1.01);
+            apply_update(&graph, sol, *mint, *pool, price);
+        }
+        totals.add(0, mark.finish());
+
+        let mark = StageMark::start();
+        let pass = match maps {
+            DetectorMaps::Fresh => CycleDetector::detect_with_near_misses(&graph, sol, 2, 5, threshold, 0),
+            DetectorMaps::Reused => CycleDetector::detect_with_scratch(&mut scratch, &graph, sol, 2, 5, threshold, 0),
+        };
+        totals.add(1, mark.finish());
+
+        let mark = StageMark::start();
+        let mut cycles = pass.cycles;
+        for cycle in cycles.iter_mut() {
+            std::hint::black_box(optimizer.optimize_amount(cycle, &limits, threshold));
+        }
+        totals.add(2, mark.finish());
+        totals.cycles += cycles.len();
+    }
+    totals
+}
+
+fn apply_update(graph: &PriceGraph, sol: Pubkey, token_mint: Pubkey, pool: Pubkey, price: f64) {
+    let edge = |to_mint: Pubkey, price: f64| {
+        PoolEdge::new(pool, to_mint, DexType::RaydiumCp, price, 100_000.0, 25, spl_token::id())
+    };
+    graph.add_edge(token_mint, sol, edge(sol, price));
+    graph.add_edge(sol, token_mint, edge(token_mint, 1.0 / price));
+}
+
+fn per_iteration(total: u64, iterations: usize) -> f64 {
+    total as f64 / iterations.max(1) as f64
+}
+
+fn render_report(runs: &[RunTotals], iterations: usize, mints: usize, seed: u64) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Allocation Profile\n");
+    let _ = writeln!(
+        out,
+        "Generated by `alloc-profile` ({} mints, {} iterations per variant, seed {}). Figures are per iteration; peak is the largest live-byte growth within a stage.\n",
+        mints, iterations, seed
+    );
+
+    for run in runs {
+        let _ = writeln!(out, "## {} ({} cycles detected)\n", run.maps.label(), run.cycles);
+        let _ = writeln!(out, "| stage | allocs | KiB | peak KiB | ms | RSS KiB |");
+        let _ = writeln!(out, "|---|---|---|---|---|---|");
+        for (name, stage) in STAGES.iter().zip(&run.stages) {
+            let _ = writeln!(
+                out,
+                "| {} | {:.1} | {:.1} | {:.1} | {:.3} | {} |",
+                name,
+                per_iteration(stage.allocations, iterations),
+                per_iteration(stage.allocated_bytes, iterations) / 1024.0,
+                stage.peak_growth_bytes as f64 / 1024.0,
+                stage.elapsed.as_secs_f64() / iterations.max(1) as f64 * 1e3,
+                stage.rss_kb.map_or_else(|| "n/a".to_string(), |kb| kb.to_string())
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    if let [fresh, reused] = runs {
+        let (before, after) = (fresh.stages[1].allocations, reused.stages[1].allocations);
+        let saved = before.saturating_sub(after);
+        let _ = writeln!(
+            out,
+            "Reusing the detector scratch saves {:.1} allocations per detection pass ({:.1}% of the stage), {:.1} KiB allocated, {:.3} ms.\n",
+            per_iteration(saved, iterations),
+            if before > 0 { saved as f64 / before as f64 * 100.0 } else { 0.0 },
+            (per_iteration(fresh.stages[1].allocated_bytes, iterations) - per_iteration(reused.stages[1].allocated_bytes, iterations)) / 1024.0,
+            (fresh.stages[1].elapsed.as_secs_f64() - reused.stages[1].elapsed.as_secs_f64()) / iterations.max(1) as f64 * 1e3
+        );
+    }
+    let _ = writeln!(out, "Process peak RSS: {} KiB", rss_kb(true).unwrap_or(0));
+    out
+}
@@ -0,0 +1,353 @@
+//! Latency benchmark harness.
+//!
+//! Replays a synthetic burst of pool price updates through the engine at a
+//! fixed rate and measures each pipeline stage with execution stubbed at the
+//! signing step:
+//!
+//! - ingest: update produced -> picked up by the pipeline (queueing delay)
+//! - edge-update: applying the update to the price graph
+//! - detection: incremental cycle detection after a batch of updates
+//! - optimize: amount optimization of the detected cycles
+//! - sign: signing a transaction for the best cycle
+//!
+//! Prices jitter by up to 1% around each mint's base price, which no cycle
+//! clears after fees. A fraction of updates (`--dislocation-rate`) instead
+//! moves one pool `--dislocation-bps` away from its twin, so some batches
+//! carry a cycle that survives optimization and the sign stage is measured.
+//!
+//! Limitation: the stream is synthetic. Updates are generated in-process, not
+//! replayed from a recorded session, so detection and optimization see a
+//! uniform two-pools-per-mint graph instead of mainnet's shape, and the
+//! numbers are a relative baseline rather than a production latency.
+//!
+//! Run with:
+//! `cargo run --release --features bench-harness --bin latency-bench -- --rates 100,1000,5000`
+
+use clap::{App, Arg};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_onchain_arbitrage_bot::constants::sol_mint;
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::fmt::Write as _;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const STAGES: [&str; 5] = ["ingest", "edge-update", "detection", "optimize", "sign"];
+
+struct Update {
+    produced_at: Instant,
+    token_mint: Pubkey,
+    pool: Pubkey,
+    price: f64,
+}
+
+#[derive(Default)]
+struct StageSamples {
+    samples: Vec<Duration>,
+}
+
+impl StageSamples {
+    fn record(&mut self, d: Duration) {
+        self.samples.push(d);
+    }
+
+    fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let idx = ((sorted.len() - 1) as f64 * p).round() as usize;
+        Some(sorted[idx])
+    }
+
+    fn total(&self) -> Duration {
+        self.samples.iter().sum()
+    }
+}
+
+/// Share of updates that move a pool far enough from its twin to clear
+#[derive(Clone, Copy)]
+struct Dislocation {
+    rate: f64,
+    bps: u64,
+}
+
+struct RunReport {
+    rate: u64,
+    updates: usize,
+    batches: usize,
+    stages: Vec<StageSamples>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let matches = App::new("latency-bench")
+        .about("Measure fetch-to-send latency of the arbitrage pipeline under load")
+        .arg(
+            Arg::with_name("rates")
+                .long("rates")
+                .value_name("LIST")
+                .help("Comma-separated update rates (updates per second)")
+                .takes_value(true)
+                .default_value("100,1000,5000"),
+        )
+        .arg(
+            Arg::with_name("duration")
+                .long("duration")
+                .value_name("SECS")
+                .help("Seconds to run each rate")
+                .takes_value(true)
+                .default_value("5"),
+        )
+        .arg(
+            Arg::with_name("mints")
+                .long("mints")
+                .value_name("N")
+                .help("Number of token mints, each with two SOL pools")
+                .takes_value(true)
+                .default_value("50"),
+        )
+        .arg(
+            Arg::with_name("dislocation-rate")
+                .long("dislocation-rate")
+                .value_name("FRACTION")
+                .help("Fraction of updates that dislocate a pool enough to clear")
+                .takes_value(true)
+                .default_value("0.05"),
+        )
+        .arg(
+            Arg::with_name("dislocation-bps")
+                .long("dislocation-bps")
+                .value_name("BPS")
+                .help("How far a dislocated pool moves from its base price")
+                .takes_value(true)
+                .default_value("300"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for the synthetic update stream")
+                .takes_value(true)
+                .default_value("42"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("Write the report as markdown performance notes")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let rates: Vec<u64> = matches
+        .value_of("rates")
+        .unwrap()
+        .split(',')
+        .map(|r| r.trim().parse::<u64>())
+        .collect::<Result<_, _>>()?;
+    let duration = Duration::from_secs(matches.value_of("duration").unwrap().parse()?);
+    let mints: usize = matches.value_of("mints").unwrap().parse()?;
+    let seed: u64 = matches.value_of("seed").unwrap().parse()?;
+    let dislocation = Dislocation {
+        rate: matches.value_of("dislocation-rate").unwrap().parse::<f64>()?.clamp(0.0, 1.0),
+        bps: matches.value_of("dislocation-bps").unwrap().parse()?,
+    };
+
+    let reports: Vec<RunReport> = rates
+        .iter()
+        .map(|&rate| run_rate(rate, duration, mints, seed, dislocation))
+        .collect();
+
+    let notes = render_report(&reports, duration, mints, seed, dislocation);
+    match matches.value_of("output") {
+        Some(path) => {
+            std::fs::write(path, &notes)?;
+            println!("Wrote performance notes to {}", path);
+        }
+        None => print!("{}", notes),
+    }
+    Ok(())
+}
+
+fn run_rate(rate: u64, duration: Duration, mint_count: usize, seed: u64, dislocation: Dislocation) -> RunReport {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let sol = sol_mint();
+    let pools: Vec<(Pubkey, Pubkey, f64)> = (0..mint_count)
+        .flat_map(|_| {
+            let mint = Pubkey::new_unique();
+            let price = rng.gen_range(0.5..2.0);
+            [(mint, Pubkey::new_unique(), price), (mint, Pubkey::new_unique(), price)]
+        })
+        .collect();
+
+    let graph = Arc::new(PriceGraph::new());
+    graph.begin_generation();
+    for (mint, pool, price) in &pools {
+        apply_update(&graph, sol, *mint, *pool, *price);
+    }
+
+    // Producer: emit updates at a fixed rate, stamped when produced
+    let (tx, rx) = mpsc::channel::<Update>();
+    let producer_pools = pools.clone();
+    let producer = thread::spawn(move || {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(rate));
+        let interval = Duration::from_secs_f64(1.0 / rate.max(1) as f64);
+        let start = Instant::now();
+        let mut sent: u64 = 0;
+        while start.elapsed() < duration {
+            let due = start + interval.mul_f64(sent as f64);
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+            let (token_mint, pool, base_price) = producer_pools[rng.gen_range(0..producer_pools.len())];
+            let price = if rng.gen_bool(dislocation.rate) {
+                let shift = dislocation.bps as f64 / 10_000.0;
+                base_price * if rng.gen_bool(0.5) { 1.0 + shift } else { 1.0 - shift }
+            } else {
+                base_price * rng.gen_range(0.99..1.01)
+            };
+            if tx.send(Update { produced_at: Instant::now(), token_mint, pool, price }).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+    });
+
+    let optimizer = AmountOptimizer::new(graph.clone(), 1, 0.0);
+    let mut detector = IncrementalDetector::new(10);
+    let threshold = ProfitThreshold::flat(0);
+    let limits = CapitalLimits {
+        max_capital: 2_000_000_000,
+        per_cycle_percent: 20,
+        min_profit: 0,
+        min_input_fraction: 0.0,
+        max_input_fraction: 1.0,
+    };
+    let signer = Keypair::new();
+    let mut stages: Vec<StageSamples> = STAGES.iter().map(|_| StageSamples::default()).collect();
+    let mut updates = 0;
+    let mut batches = 0;
+
+    // Consumer: drain everything queued, then run detection -> optimize -> sign once per batch
+    while let Ok(first) = rx.recv() {
+        graph.begin_generation();
+        let mut batch = vec![first];
+        batch.extend(rx.try_iter());
+
+        for update in batch {
+            stages[0].record(update.produced_at.elapsed());
+            let started = Instant::now();
+            apply_update(&graph, sol, update.token_mint, update.pool, update.price);
+            stages[1].record(started.elapsed());
+            updates += 1;
+        }
+        batches += 1;
+
+        let started = Instant::now();
+        let mut cycles = detector.detect(&graph, sol, 2, 5, threshold);
+        stages[2].record(started.elapsed());
+
+        let started = Instant::now();
+        let best = cycles
+            .iter_mut()
+            .filter_map(|cycle| {
+                optimizer
+                    .optimize_amount(cycle, &limits, threshold)
+                    .map(|amount| (cycle.estimated_profit_lamports, amount))
+            })
+            .max();
+        stages[3].record(started.elapsed());
+
+        if let Some((_, amount)) = best {
+            let started = Instant::now();
+            let ix = system_instruction::transfer(&signer.pubkey(), &signer.pubkey(), amount);
+            let tx = Transaction::new_signed_with_payer(&[ix], Some(&signer.pubkey()), &[&signer], Hash::default());
+            std::hint::black_box(tx);
+            stages[4].record(started.elapsed());
+        }
+    }
+    producer.join().ok();
+
+    RunReport { rate, updates, batches, stages }
+}
+
+fn apply_update(graph: &PriceGraph, sol: Pubkey, token_mint: Pubkey, pool: Pubkey, price: f64) {
+    let edge = |to_mint: Pubkey, price: f64| {
+        PoolEdge::new(pool, to_mint, DexType::RaydiumCp, price, 100_000.0, 25, spl_token::id())
+    };
+    graph.add_edge(token_mint, sol, edge(sol, price));
+    graph.add_edge(sol, token_mint, edge(token_mint, 1.0 / price));
+}
+
+fn fmt_duration(d: Option<Duration>) -> String {
+    match d {
+        Some(d) => format!("{:.1}", d.as_secs_f64() * 1e6),
+        None => "n/a".to_string(),
+    }
+}
+
+fn render_report(reports: &[RunReport], duration: Duration, mints: usize, seed: u64, dislocation: Dislocation) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Performance Notes\n");
+    let _ = writeln!(
+        out,
+        "Generated by `latency-bench` ({} mints, {}s per rate, seed {}, {:.0}% of updates dislocated by {} bps). Latencies in microseconds.\n",
+        mints,
+        duration.as_secs(),
+        seed,
+        dislocation.rate * 100.0,
+        dislocation.bps
+    );
+    let _ = writeln!(
+        out,
+        "The update stream is synthetic: generated in-process over two pools per mint, not replayed from a recorded session. Treat these numbers as a relative baseline between changes, not as production latency.\n"
+    );
+
+    for report in reports {
+        let _ = writeln!(
+            out,
+            "## {} updates/s ({} updates in {} batches)\n",
+            report.rate, report.updates, report.batches
+        );
+        let _ = writeln!(out, "| stage | samples | p50 | p90 | p99 | max | total ms |");
+        let _ = writeln!(out, "|---|---|---|---|---|---|---|");
+        for (name, samples) in STAGES.iter().zip(&report.stages) {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} | {:.1} |",
+                name,
+                samples.samples.len(),
+                fmt_duration(samples.percentile(0.50)),
+                fmt_duration(samples.percentile(0.90)),
+                fmt_duration(samples.percentile(0.99)),
+                fmt_duration(samples.percentile(1.0)),
+                samples.total().as_secs_f64() * 1e3
+            );
+        }
+
+        // Ingest is queueing delay caused by the other stages, so the bottleneck
+        // is the processing stage with the most total time.
+        let bottleneck = STAGES
+            .iter()
+            .zip(&report.stages)
+            .skip(1)
+            .max_by_key(|(_, samples)| samples.total())
+            .map(|(name, _)| *name)
+            .unwrap_or("n/a");
+        let _ = writeln!(out, "\nBottleneck: **{}**\n", bottleneck);
+        if report.stages[4].samples.is_empty() {
+            let _ = writeln!(out, "No cycle survived optimization, so the sign stage was not exercised.\n");
+        }
+    }
+    out
+}
@@ -1,170 +1,1075 @@
 use crate::ata::ensure_base_atas_exist;
-use crate::config::Config;
-use crate::constants::sol_mint;
-use crate::discovery::{DiscoveryEngine, DiscoveryConfig};
+use crate::clock::system_clock;
+use crate::bundle::{BundleSender, JitoClient};
+use crate::config::{Config, DiscoveryRole, EngineConfig};
+use crate::dex::selftest::decoder_selftest;
+use crate::discovery::{DiscoveredPools, DiscoveryEngine, DiscoveryConfig};
 use crate::engine::*;
-use crate::refresh::initialize_pools_from_markets;
+use crate::confirm::{cycle_report, Admission, ConfirmationGate, ConfirmationState, VersionFingerprint};
+use crate::execute::{LiveExecutor, PostTradeReview};
+use crate::health::{run_health_server, HealthState};
+use crate::journal::ExecutionJournal;
+use crate::ledger::{load_ledger, PnlReport};
+use crate::mint_registry::MintRegistry;
+use crate::persist::atomic_write_json;
+use crate::partition::Partition;
+use crate::refresh::{initialize_pools_from_markets, refresh_pools_in_place};
 use anyhow::Context;
 use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio::time::{interval, sleep};
+use tokio::time::{interval, interval_at, sleep, Instant, MissedTickBehavior};
 use tracing::{error, info, warn, debug};
 
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(60 * 15);
+/// How often a discovery follower checks whether the leader rewrote the output file
+const DISCOVERY_FOLLOW_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Shared bot state for dynamic market updates
 pub struct BotState {
     markets: Arc<RwLock<Vec<String>>>,
     discovery_engine: Option<DiscoveryEngine>,
+    partition: Option<Partition>,
+}
+
+impl BotState {
+    /// Pools of the discovered tokens this instance trades
+    fn discovered_markets(&self, results: &DiscoveredPools) -> Vec<String> {
+        match &self.partition {
+            Some(partition) => DiscoveryEngine::convert_to_markets(&partition.filter_discovered(results)),
+            None => DiscoveryEngine::convert_to_markets(results),
+        }
+    }
 }
 
-pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
+pub async fn run_bot(config_path: &str, seed: Option<u64>, strict: bool) -> anyhow::Result<()> {
     let config = Config::load(config_path)?;
     info!("Configuration loaded successfully");
 
+    let strict_mode = strict || config.bot.strict_mode;
+    if strict_mode {
+        warn!("🧪 Strict mode: the first parser or price anomaly stops the bot");
+    }
+
+    // Seed for the components that legitimately randomize (CU jitter, fee account choice)
+    let seed = seed.unwrap_or_else(rand::random);
+    info!("🎲 Random seed: {} (pass --seed {} to reproduce)", seed, seed);
+
+    // Fail fast if an offset-based decoder no longer matches its bundled snapshots
+    let selftest_config = config.selftest.clone().unwrap_or_default();
+    if selftest_config.enabled {
+        decoder_selftest(&selftest_config.fixtures_file).await?;
+    }
+
     let rpc_client = Arc::new(RpcClient::new(config.rpc.url.clone()));
+    if config.rpc.send_url.is_some() {
+        info!("📡 Reads use the data RPC, transaction submissions use the dedicated send RPC");
+    }
     let wallet_kp = load_keypair(&config.wallet.private_key)?;
     info!("Wallet loaded: {}", wallet_kp.pubkey());
 
+    // Single time source for every staleness window, TTL and timestamp
+    let clock = system_clock();
+
+    // Share of the universe this instance trades when several instances run side by side
+    let partition = config
+        .partition
+        .as_ref()
+        .filter(|p| p.enabled)
+        .map(|p| Partition::new(p.instance_index, p.instance_count, p.share_base_mints))
+        .transpose()?;
+    if let Some(partition) = &partition {
+        info!("🔀 Partitioned universe: trading the mints assigned to {}", partition);
+    }
+
     // Initialize shared bot state
     let bot_state = Arc::new(BotState {
         markets: Arc::new(RwLock::new(Vec::new())),
         discovery_engine: None,
+        partition,
     });
 
     // Setup and run discovery if enabled in config
     if let Some(discovery_config) = config.discovery.as_ref().filter(|d| d.enabled) {
+        let discovery_role = discovery_config.discovery_role;
         // Convert config::DiscoveryConfig to discovery::DiscoveryConfig
-        let discovery_config = DiscoveryConfig {
-            enabled: discovery_config.enabled,
-            interval_minutes: discovery_config.interval_minutes,
-            min_liquidity_usd: discovery_config.min_liquidity_usd,
-            min_volume_h24: discovery_config.min_volume_h24,
-            output_file: discovery_config.output_file.clone(),
-        };
+        let discovery_config = DiscoveryConfig::from(discovery_config);
         
-        let discovery_engine = DiscoveryEngine::new(config.rpc.url.clone(), discovery_config);
+        let discovery_engine = DiscoveryEngine::new(config.rpc.url.clone(), discovery_config).with_clock(clock.clone());
         
-        // Run initial discovery on startup
-        info!("🔄 Running initial pool discovery...");
-        match discovery_engine.run_discovery().await {
-            Ok(results) => {
-                // Save results to JSON file
-                if let Err(e) = discovery_engine.save_results(&results).await {
-                    error!("Failed to save discovery results: {}", e);
+        // Reuse the last saved discovery while it is fresh; otherwise run discovery on startup
+        let saved = discovery_engine.load_results().await.unwrap_or_else(|e| {
+            warn!("⚠️ Ignoring saved discovery results: {}", e);
+            None
+        });
+        if discovery_role == DiscoveryRole::Follower {
+            // Another instance runs discovery; this one only consumes its output
+            info!("👂 Discovery follower: using the results another instance writes to {}", discovery_engine.output_file());
+            let last_timestamp = match &saved {
+                Some(results) => {
+                    *bot_state.markets.write().await = bot_state.discovered_markets(results);
+                    Some(results.timestamp)
                 }
-                
-                // Load markets from discovery and update state
-                let markets = DiscoveryEngine::convert_to_markets(&results);
-                *bot_state.markets.write().await = markets;
-            }
-            Err(e) => {
-                error!("❌ Initial discovery failed: {}, falling back to config markets", e);
-                // Fallback to config markets if discovery fails
-                *bot_state.markets.write().await = config.routing.markets.markets.clone();
-            }
+                None => {
+                    warn!("⚠️ No discovery results yet, using config markets until the leader writes them");
+                    *bot_state.markets.write().await = config.routing.markets.markets.clone();
+                    None
+                }
+            };
+            let state_clone = Arc::new(BotState {
+                markets: bot_state.markets.clone(),
+                discovery_engine: Some(discovery_engine),
+                partition,
+            });
+            tokio::spawn(async move {
+                follow_discovery(state_clone, last_timestamp).await;
+            });
+        } else {
+            let (results, first_scheduled_run) = discovery_engine.startup_results(saved, DISCOVERY_INTERVAL).await;
+            *bot_state.markets.write().await = match &results {
+                Some(results) => bot_state.discovered_markets(results),
+                None => {
+                    error!("❌ No discovery results, falling back to config markets");
+                    config.routing.markets.markets.clone()
+                }
+            };
+
+            // Store engine and start background thread
+            // We need to clone the Arc to move into the spawn
+            let state_clone = Arc::new(BotState {
+                markets: bot_state.markets.clone(),
+                discovery_engine: Some(discovery_engine),
+                partition,
+            });
+
+            tokio::spawn(async move {
+                run_background_discovery(state_clone, first_scheduled_run).await;
+            });
         }
-        
-        // Store engine and start background thread
-        // We need to clone the Arc to move into the spawn
-        let state_clone = Arc::new(BotState {
-            markets: bot_state.markets.clone(),
-            discovery_engine: Some(discovery_engine),
-        });
-        
-        tokio::spawn(async move {
-            run_background_discovery(state_clone).await;
-        });
     } else {
         // Discovery disabled - use static markets from config
         info!("📋 Using static markets from config (discovery disabled)");
         *bot_state.markets.write().await = config.routing.markets.markets.clone();
     }
 
+    // Stamped on the status file and every ledger record this run writes
+    let fingerprint = VersionFingerprint::new(&std::fs::read(config_path)?);
+
+    // Hold the first executions after a code or config change for manual confirmation
+    let confirmation_gate = match config.confirmation.as_ref().filter(|c| c.enabled) {
+        Some(confirmation_config) => {
+            let (state, armed) = ConfirmationState::load_or_arm(
+                &confirmation_config.state_file,
+                fingerprint.clone(),
+                confirmation_config.first_executions,
+            )
+            .await?;
+            if armed {
+                warn!("✋ Version {} is new: the first {} executions need POST /confirm/{{cycle_id}}", state.fingerprint, state.remaining);
+            } else if state.remaining > 0 {
+                warn!("✋ Version {}: {} executions still need confirmation", state.fingerprint, state.remaining);
+            } else {
+                info!("🔖 Version {}", state.fingerprint);
+            }
+            if config.health.as_ref().map_or(true, |h| !h.enabled) && state.remaining > 0 {
+                warn!("⚠️ The health endpoint is disabled, so held cycles cannot be confirmed and will expire");
+            }
+            Some(Arc::new(std::sync::Mutex::new(ConfirmationGate::new(
+                state,
+                Duration::from_secs(confirmation_config.max_hold_secs),
+            ))))
+        }
+        None => None,
+    };
+    let confirmation_state_file = config.confirmation.as_ref().map(|c| c.state_file.clone()).unwrap_or_default();
+
+    // Start health endpoint if enabled in config
+    let health_state = config.health.as_ref().filter(|h| h.enabled).map(|health_config| {
+        let mut state = HealthState::with_clock(
+            Duration::from_secs(health_config.max_update_age_secs),
+            clock.clone(),
+        );
+        if let Some(gate) = &confirmation_gate {
+            let api_token = config.confirmation.as_ref().and_then(|c| c.api_token.clone());
+            if api_token.is_none() {
+                info!("🔒 No confirmation api_token set: /confirm and /confirmations only answer loopback clients");
+            }
+            state = state.with_confirmations(gate.clone(), api_token);
+        }
+        let state = Arc::new(state);
+        let bind_address = health_config.bind_address.clone();
+        let server_state = state.clone();
+        let server_rpc = rpc_client.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_health_server(bind_address, server_state, server_rpc).await {
+                error!("❌ Health endpoint stopped: {}", e);
+            }
+        });
+        state
+    });
+
     // Initialize engine components for arbitrage detection
-    let price_graph = Arc::new(PriceGraph::new());
-    let amount_optimizer = AmountOptimizer::new(price_graph.clone());
+    let engine_config = config.engine.clone().unwrap_or_default();
+    let oracle_config = config.oracle.clone().unwrap_or_default();
+    let mut sol_price_sources = Vec::new();
+    if let Some(account) = &oracle_config.pyth_price_account {
+        let account = account
+            .parse::<Pubkey>()
+            .map_err(|e| anyhow::anyhow!("Invalid oracle pyth_price_account {}: {}", account, e))?;
+        sol_price_sources.push(SolPriceSource::Pyth { account, rpc_client: rpc_client.clone() });
+    }
+    sol_price_sources.push(SolPriceSource::CoinGecko { url: oracle_config.url.clone() });
+    if let Some(url) = &oracle_config.jupiter_url {
+        sol_price_sources.push(SolPriceSource::Jupiter { url: url.clone() });
+    }
+    let sol_price_oracle = SolPriceOracle::new(sol_price_sources, oracle_config.bootstrap_price_usd)
+        .with_ttl(Duration::from_secs(oracle_config.ttl_secs));
+    let quote_mints = oracle_config
+        .quote_mints
+        .iter()
+        .map(|mint| mint.parse::<Pubkey>().map_err(|e| anyhow::anyhow!("Invalid oracle quote mint {}: {}", mint, e)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let quote_price_oracle = QuotePriceOracle::new(oracle_config.quote_price_url.clone(), quote_mints)
+        .with_ttl(Duration::from_secs(oracle_config.ttl_secs));
+    let price_graph = Arc::new(PriceGraph::with_bootstrap_sol_price(oracle_config.bootstrap_price_usd).with_clock(clock.clone()));
+    price_graph.set_liquidity_filter(
+        engine_config.min_pool_liquidity_usd,
+        oracle_config.unconfirmed_liquidity_multiplier,
+    );
+    price_graph.set_strict_mode(strict_mode);
+    price_graph.set_rpc_token_balances(engine_config.rpc_token_balances);
+    price_graph.set_max_slot_lag(engine_config.max_slot_lag);
+    let mint_registry = match &engine_config.mint_registry_file {
+        Some(path) => {
+            let registry = MintRegistry::from_file(path)?;
+            info!("🏷️ Loaded {} mints from registry {}", registry.len(), path);
+            registry
+        }
+        None => MintRegistry::default(),
+    };
+    price_graph.set_mint_registry(&mint_registry);
+    let mut amount_optimizer = AmountOptimizer::new(
+        price_graph.clone(),
+        engine_config.input_granularity_lamports,
+        engine_config.realized_slippage_penalty_weight,
+    );
+    if let Some(path) = &engine_config.realized_slippage_file {
+        match amount_optimizer.load_realized_slippage(path).await {
+            Ok(pools) => info!("📉 Loaded realized slippage for {} pools from {}", pools, path),
+            Err(e) => warn!("⚠️ Ignoring realized slippage: {}", e),
+        }
+    }
+    let mut dry_spell = DrySpellDiagnostic::new(engine_config.dry_spell_iterations);
+    let mut capital_book = CapitalBook::new(engine_config
+        .capital
+        .iter()
+        .map(|(mint, c)| {
+            let mint = mint
+                .parse::<Pubkey>()
+                .map_err(|e| anyhow::anyhow!("Invalid capital base mint {}: {}", mint, e))?;
+            Ok((mint, CapitalLimits {
+                max_capital: c.max_capital,
+                per_cycle_percent: c.per_cycle_percent,
+                min_profit: c.min_profit,
+                min_input_fraction: c.min_input_fraction,
+                max_input_fraction: c.max_input_fraction,
+            }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?);
+    if capital_book.base_mints().is_empty() {
+        warn!("⚠️ No base mint has capital configured in engine.capital, so no cycle will be detected");
+    }
+    // Cycles are detected from every base mint with a budget, each detector keeping its own incremental state
+    let mut cycle_detectors: Vec<(Pubkey, IncrementalDetector)> = capital_book
+        .base_mints()
+        .into_iter()
+        .map(|base_mint| {
+            let detector = IncrementalDetector::new(engine_config.full_detection_interval)
+                .with_near_misses(if dry_spell.enabled() { DRY_SPELL_NEAR_MISSES } else { 0 })
+                .with_min_distinct_mints(engine_config.min_distinct_mints);
+            (base_mint, detector)
+        })
+        .collect();
+    let profit_threshold = ProfitThreshold {
+        base_bps: engine_config.min_profit_bps,
+        per_hop_increment_bps: engine_config.per_hop_increment_bps,
+    };
+    let mut compute_units = match ComputeUnitTable::load(&engine_config.compute_profile_file).await {
+        Ok(table) => table,
+        Err(e) => {
+            warn!("⚠️ Ignoring compute profile: {}", e);
+            ComputeUnitTable::default()
+        }
+    };
+    compute_units.log();
+    let tx_cost_model = TxCostModel {
+        compute_unit_price: config.spam.as_ref().map_or(0, |spam| spam.compute_unit_price),
+        fallback_compute_unit_limit: config.bot.compute_unit_limit,
+        tip_lamports: engine_config.tip_lamports,
+        ata_rent_lamports: engine_config.ata_rent_lamports,
+    };
+    let dex_filter = DexPairFilter::from_config(&engine_config.required_dex_pairs)?;
+    let quote_haircut_overrides: HashMap<DexType, u64> = engine_config
+        .quote_haircut_bps
+        .iter()
+        .map(|(name, bps)| {
+            DexType::ALL
+                .iter()
+                .find(|dex_type| format!("{:?}", dex_type) == *name)
+                .map(|dex_type| (*dex_type, *bps))
+                .ok_or_else(|| anyhow::anyhow!("Unknown DEX {} in quote_haircut_bps", name))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let mut capabilities = CapabilityRegistry::from_registered_builders();
+    capabilities.apply_quote_haircuts(engine_config.approximate_quote_haircut_bps, &quote_haircut_overrides);
+    capabilities.log_capabilities();
+    if let Some(path) = &engine_config.status_file {
+        EngineStatus::new(&capabilities, &compute_units, &fingerprint, clock.now_unix()).with_partition(partition.as_ref()).save(path).await?;
+        info!("💾 Wrote venue status to {}", path);
+    }
+    amount_optimizer.set_quote_haircuts(capabilities.quote_haircuts());
+    let slippage_bounds: HashMap<DexType, SlippageBounds> = engine_config
+        .slippage
+        .iter()
+        .map(|(name, slippage)| {
+            let dex_type = DexType::ALL
+                .iter()
+                .find(|dex_type| format!("{:?}", dex_type) == *name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown DEX {} in slippage", name))?;
+            if slippage.base_bps > slippage.cap_bps || slippage.cap_bps > 10_000 {
+                anyhow::bail!("Invalid slippage for {}: base_bps {} must not exceed cap_bps {} (at most 10000)", name, slippage.base_bps, slippage.cap_bps);
+            }
+            Ok((*dex_type, SlippageBounds { base_bps: slippage.base_bps, cap_bps: slippage.cap_bps }))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    amount_optimizer.set_slippage_bounds(slippage_bounds);
+    amount_optimizer.set_near_miss_bps(engine_config.record_near_misses.then_some(engine_config.near_miss_bps));
+    let mut opportunity_tracker = OpportunityTracker::with_clock(clock.clone());
+    let simulator = Simulator;
+    let mut simulation_cache = SimulationCache::with_clock(
+        Duration::from_secs(engine_config.simulation_cache_ttl_secs),
+        engine_config.simulation_cache_amount_bucket_lamports,
+        clock.clone(),
+    );
+    let mut spread_monitor = config
+        .spread_monitor
+        .as_ref()
+        .filter(|s| s.enabled)
+        .map(|s| SpreadMonitor::new(s.threshold_bps, s.persist_iterations, s.window));
+
+    let account_stream = match config.account_stream.as_ref().filter(|s| s.enabled) {
+        Some(_) if !engine_config.prefetch_accounts => {
+            warn!("⚠️ account_stream is served through the account prefetch; enable engine.prefetch_accounts to use it");
+            None
+        }
+        Some(s) => {
+            info!("📡 Streaming pool accounts, re-read over RPC after {}s without an update", s.max_age_secs);
+            Some(AccountStream::spawn(
+                s.ws_url.clone(),
+                rpc_client.commitment(),
+                Duration::from_secs(s.max_age_secs),
+                Duration::from_secs(s.max_backoff_secs),
+            ))
+        }
+        None => None,
+    };
+
+    // Main bot loop: ticks at the detection cadence, refreshing pools and the graph when due.
+    // A staggered refresh covers one slice of the mints per sub-interval.
+    let mut stagger = RefreshStagger::new(engine_config.refresh_batches);
+    let mut schedule = LoopSchedule::new(
+        Duration::from_secs(engine_config.refresh_interval_secs) / stagger.batches() as u32,
+        Duration::from_millis(engine_config.detection_interval_ms),
+        clock.clone(),
+    );
+    if stagger.batches() > 1 {
+        info!("🪜 Staggering the pool refresh over {} slices, one every {:?}", stagger.batches(), schedule.refresh_backoff());
+    }
+    if schedule.detections_per_refresh() > 1 {
+        info!("⏲️  Detecting every {:?}, {} passes per pool refresh", schedule.tick_interval(), schedule.detections_per_refresh());
+    }
+    let mut forecaster = config
+        .forecast
+        .as_ref()
+        .filter(|f| f.enabled)
+        .map(|f| (YieldForecaster::new(f.tokens_per_refresh, f.exploration_floor, f.smoothing), f.log_top));
+    let mut priced_mints: Vec<Pubkey> = Vec::new();
+    let mut pool_cooldown = PoolCooldown::new(engine_config.min_pool_cooldown_slots);
+    let mut settle_cooldown = SettleCooldown::new(Duration::from_millis(engine_config.post_execution_cooldown_ms));
+    let execution_limiter = ExecutionLimiter::new(engine_config.max_concurrent_executions);
+    let ledger_file = config.ledger.clone().unwrap_or_default().file;
+    let mut paper = config.paper.as_ref().filter(|p| p.enabled).map(|p| {
+        info!("📝 Paper trading: cleared cycles fill against a simulated market and are recorded in {}, nothing is sent", ledger_file);
+        (MarketSimulator::new(p.shock_probability, p.shock_bps, seed), p.fee_lamports)
+    });
+    let mut paper_fills = 0u64;
+    // This run's fills, per base mint
+    let mut pnl = PnlReport::default();
+    // Every fill, live or on paper, is reconciled against its cycle before the next one
+    let execution_config = config.execution.clone().unwrap_or_default();
+    let journal = ExecutionJournal::new(execution_config.journal_file.clone());
+    let kill_switch = KillSwitch::load(&execution_config.kill_switch_file).await?;
+    if let Some(reason) = kill_switch.reason() {
+        error!("🛑 Kill switch is tripped ({}): nothing executes until {} is removed", reason, execution_config.kill_switch_file);
+    }
+    let mut post_trade = PostTradeReview::new(
+        engine_config.reconcile_tolerance_lamports,
+        engine_config.max_unexplained_loss_lamports,
+        kill_switch,
+        ledger_file.clone(),
+        journal.clone(),
+        &fingerprint,
+    );
+    let mut live_executor = match execution_config.enabled {
+        true if paper.is_some() => {
+            warn!("⚠️ Paper trading is enabled, so execution.enabled is ignored and nothing is sent");
+            None
+        }
+        true => {
+            warn!("🚀 Live execution: cleared cycles are sent from {}", wallet_kp.pubkey());
+            ensure_base_atas_exist(&rpc_client, &wallet_kp, &capital_book.base_mints())?;
+            let executor = LiveExecutor::new(
+                &config,
+                rpc_client.as_ref(),
+                Duration::from_secs(execution_config.confirm_timeout_secs),
+                journal,
+                seed,
+            )?;
+            match config.jito.as_ref().filter(|jito| jito.enabled) {
+                Some(jito) => {
+                    info!("📦 Cycles are sent as Jito bundles through {}", jito.block_engine_url);
+                    let engine = Arc::new(JitoClient::new(jito.block_engine_url.clone()));
+                    Some(executor.with_bundles(BundleSender::new(jito, engine)?))
+                }
+                None => Some(executor),
+            }
+        }
+        false => None,
+    };
+    let mut dex_reliability = match config.dex_reliability.as_ref().filter(|r| r.enabled) {
+        Some(r) => {
+            let mut reliability = DexReliability::new(r.window, r.min_samples, r.min_success_rate, r.penalty_bps);
+            reliability.record_ledger(&load_ledger(&ledger_file).await?);
+            reliability.log();
+            Some((reliability, r.filter))
+        }
+        None => None,
+    };
+    let mut main_interval = interval(schedule.tick_interval());
+    main_interval.set_missed_tick_behavior(MissedTickBehavior::Delay); // No burst of detection passes after a slow refresh
+    let mut mint_pool_data = HashMap::new();
+    let mut initialized_markets: Vec<String> = Vec::new(); // Markets `mint_pool_data` was built from
+    let mut refreshes_since_init = 0u64;
+    let mut graph_stale = false;
+    let instance_label = partition.map_or_else(String::new, |partition| format!(" ({})", partition));
+    let mut last_update = (UpdateStats::default(), 0); // Stats and unreachable mints of the last refresh
+    let session_started = clock.now_instant();
+    let mut session = SessionStats::new(clock.now_unix());
+    // Created once so a Ctrl-C that arrives mid-iteration is seen at the next tick
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
 
-    // Main bot loop
-    let mut main_interval = interval(Duration::from_secs(60));
-    
     loop {
-        main_interval.tick().await;
-        
-        // Get current markets (may be updated by discovery)
-        let markets = bot_state.markets.read().await.clone();
-        if markets.is_empty() {
-            warn!("⚠️ No markets configured, skipping cycle");
-            continue;
+        tokio::select! {
+            _ = main_interval.tick() => {}
+            _ = &mut shutdown => {
+                info!("🛑 Shutdown requested");
+                break;
+            }
         }
 
-        info!("🔍 Processing {} markets", markets.len());
+        let refreshed = schedule.begin_tick();
+        #[cfg(feature = "profiling")]
+        let mut iteration_profile = crate::profiling::IterationProfile::default();
+        #[cfg(feature = "profiling")]
+        let stage = crate::profiling::StageMark::start();
+        if refreshed {
+            // Get current markets (may be updated by discovery)
+            let markets = bot_state.markets.read().await.clone();
+            if markets.is_empty() {
+                warn!("⚠️ No markets configured, skipping cycle");
+                continue;
+            }
+
+            info!("🔍 Processing {} markets", markets.len());
 
-        // Initialize pools from current markets
-        let mint_pool_data = match initialize_pools_from_markets(
-            &crate::config::MarketsConfig { 
-                markets: markets.clone(),  // Use the Vec<String>, not MarketsConfig
-                lookup_table_accounts: config.routing.markets.lookup_table_accounts.clone(), 
-                process_delay: config.routing.markets.process_delay 
-            },
-            &wallet_kp.pubkey(),
-            rpc_client.clone(),
-        ).await {
-            Ok(data) => data,
-            Err(e) => {
-                error!("❌ Failed to initialize pools: {}", e);
+            // Known pools are refreshed in place; the full initialization reruns when the market set changes
+            let reinit_due = engine_config.pool_reinit_interval > 0 && refreshes_since_init >= engine_config.pool_reinit_interval;
+            let mut refresh_batch = None; // Mints the in-place refresh covered; every mint after an initialization
+            if markets == initialized_markets && !mint_pool_data.is_empty() && !reinit_due {
+                refreshes_since_init += 1;
+                let mut known_mints: Vec<_> = mint_pool_data.keys().copied().collect();
+                known_mints.sort();
+                let batch = stagger.select(&known_mints);
+                let failed = refresh_pools_in_place(&mut mint_pool_data, &batch, &rpc_client);
+                info!("♻️ Refreshed {} of {} mints in place ({} failed)", batch.len(), mint_pool_data.len(), failed);
+                refresh_batch = Some(batch);
+            } else {
+                // Initialize pools from current markets
+                mint_pool_data = match initialize_pools_from_markets(
+                    &crate::config::MarketsConfig { 
+                        markets: markets.clone(),  // Use the Vec<String>, not MarketsConfig
+                        lookup_table_accounts: config.routing.markets.lookup_table_accounts.clone(), 
+                        process_delay: config.routing.markets.process_delay,
+                        init_concurrency: config.routing.markets.init_concurrency,
+                        init_batch_size: config.routing.markets.init_batch_size,
+                        allow_transfer_hook_mints: config.routing.markets.allow_transfer_hook_mints,
+                        pump_bonding_curve_mints: config.routing.markets.pump_bonding_curve_mints.clone(),
+                    },
+                    &wallet_kp.pubkey(),
+                    rpc_client.clone(),
+                    strict_mode,
+                ).await {
+                    Ok(mut data) => {
+                        // Static config markets are not partitioned before initialization
+                        if let Some(partition) = &bot_state.partition {
+                            data.retain(|mint, _| partition.owns(mint));
+                        }
+                        data
+                    }
+                    Err(e) if strict_mode => return Err(e),
+                    Err(e) => {
+                        error!("❌ Failed to initialize pools: {}", e);
+                        continue;
+                    }
+                };
+
+                info!("✅ Initialized {} mints from markets", mint_pool_data.len());
+                initialized_markets = markets;
+                refreshes_since_init = 0;
+            }
+
+            // Refresh SOL price; falls back to the bootstrap price until a live fetch succeeds
+            let sol_price = sol_price_oracle.refresh().await;
+            price_graph.set_sol_price(sol_price, sol_price_oracle.is_confirmed());
+            for (mint, price) in quote_price_oracle.refresh().await {
+                price_graph.set_quote_price(mint, price);
+            }
+
+            // Build price graph from pool data
+            price_graph.begin_generation();
+            let mut mints: Vec<_> = refresh_batch.unwrap_or_else(|| mint_pool_data.keys().copied().collect());
+            mints.sort();
+            if let Some((forecaster, log_top)) = forecaster.as_mut() {
+                forecaster.log(&mints, *log_top);
+                mints = forecaster.select(&mints);
+            }
+            let mut prefetch_rpc_calls = 0;
+            if engine_config.prefetch_accounts {
+                let mut planner = PrefetchPlanner::new(engine_config.prefetch_auxiliary_accounts);
+                for mint in &mints {
+                    planner.add_mint(&mint_pool_data[mint]);
+                }
+                // Accounts the stream delivered recently are not fetched again
+                let mut streamed = PrefetchedAccounts::default();
+                if let Some(stream) = &account_stream {
+                    let mut tracked = PrefetchPlanner::new(engine_config.prefetch_auxiliary_accounts);
+                    for pool_data in mint_pool_data.values() {
+                        tracked.add_mint(pool_data);
+                    }
+                    stream.track(tracked.accounts().clone());
+                    streamed = stream.fresh(planner.accounts());
+                    planner.retain_accounts(|key| !streamed.accounts.contains_key(key));
+                }
+                match planner.fetch(&*rpc_client) {
+                    Ok(mut prefetched) => {
+                        info!(
+                            "📦 Prefetched {} accounts ({} dependencies, {} missing) in {} RPC calls",
+                            prefetched.accounts.len(),
+                            prefetched.requested,
+                            prefetched.missing.len(),
+                            prefetched.rpc_calls
+                        );
+                        if let Some(stream) = &account_stream {
+                            info!("📡 Account stream served {} accounts ({} disconnects so far)", streamed.accounts.len(), stream.disconnects());
+                            stream.seed(&prefetched);
+                        }
+                        prefetch_rpc_calls = prefetched.rpc_calls;
+                        prefetched.merge(streamed);
+                        price_graph.set_prefetched(prefetched);
+                    }
+                    Err(e) => {
+                        warn!("⚠️ Account prefetch failed, falling back to per-pool reads: {}", e);
+                        price_graph.set_prefetched(streamed);
+                    }
+                }
+            }
+            let mut update_stats = UpdateStats::default();
+            let mut unreachable_mints = 0;
+            for mint in &mints {
+                match price_graph.try_update_from_mint_pool_data(&mint_pool_data[mint], &*rpc_client) {
+                    Ok(stats) => update_stats.merge(&stats),
+                    Err(e) => {
+                        debug!("Graph update failed: {}", e);
+                        unreachable_mints += 1;
+                    }
+                }
+                if let Some(anomaly) = price_graph.take_anomaly() {
+                    anyhow::bail!("Strict mode: anomaly while pricing mint {}: {}", mint, anomaly);
+                }
+            }
+            price_graph.clear_prefetched();
+            let reads = price_graph.take_account_reads();
+            info!(
+                "📡 Account reads: {} from the prefetch in {} RPC calls, {} individual; {} RPC calls saved",
+                reads.prefetched,
+                prefetch_rpc_calls,
+                reads.individual,
+                reads.prefetched.saturating_sub(prefetch_rpc_calls as u64)
+            );
+            info!(
+                "🔄 Graph update: {} edges from {} pools, {} pools failed, {} skipped, {} mints unreachable",
+                update_stats.edges_added,
+                update_stats.pools_priced,
+                update_stats.pools_failed,
+                update_stats.pools_skipped,
+                unreachable_mints
+            );
+            last_update = (update_stats, unreachable_mints);
+            graph_stale = !mints.is_empty() && unreachable_mints == mints.len();
+            schedule.record_refresh(!graph_stale);
+            if graph_stale {
+                // Every pool read failed: the graph only holds the previous refresh's prices
+                warn!("⚠️ Graph update reached no pool, skipping detection and retrying in {:?}", schedule.refresh_backoff());
                 continue;
             }
-        };
+            priced_mints = mints;
+            if let Some(health_state) = &health_state {
+                health_state.record_update();
+            }
+        }
+        if mint_pool_data.is_empty() || graph_stale {
+            continue;
+        }
+
+        // Mints traded through moments ago: re-read their pools rather than detect on pre-trade prices
+        if !refreshed {
+            let settling = settle_cooldown.refresh_settling(&price_graph, &mint_pool_data, &*rpc_client, clock.now_instant())?;
+            if !settling.is_empty() {
+                debug!("🧊 Re-read {} mints still settling after an execution", settling.len());
+            }
+        }
+        #[cfg(feature = "profiling")]
+        iteration_profile.record(if refreshed { "refresh" } else { "idle" }, stage.finish());
 
-        info!("✅ Initialized {} mints from markets", mint_pool_data.len());
+        // Paper trading: the simulated market owns the prices of every pool it has taken over
+        if let Some((market, _)) = paper.as_mut() {
+            if !refreshed {
+                price_graph.begin_generation();
+            }
+            market.seed_from_graph(&price_graph);
+            let shocked = market.shock();
+            market.apply_to_graph(&price_graph);
+            debug!("📝 Paper market: {} pools, {} shocked this pass", market.pool_count(), shocked);
+        }
 
-        // Build price graph from pool data
-        for (_, pool_data) in mint_pool_data.iter() {
-            price_graph.update_from_mint_pool_data(pool_data, &rpc_client);
+        if let Some(gate) = &confirmation_gate {
+            for cycle_id in gate.lock().unwrap().expire(clock.now_instant()) {
+                info!("⌛ Held cycle {:016x} expired without confirmation", cycle_id);
+            }
         }
 
-        // Run detection cycle (existing logic)
-        let cycles = CycleDetector::find_negative_cycles(
-            &price_graph,
-            sol_mint(),
-            2,  // min hops
-            5,  // max hops
-            50, // min profit bps
-        );
+        // Run detection, re-evaluating only cycles touching changed mints between full passes
+        #[cfg(feature = "profiling")]
+        let stage = crate::profiling::StageMark::start();
+        // The same loop entered from two base mints shares a cycle id; it is kept from the first
+        let mut cycles: Vec<ArbitrageCycle> = Vec::new();
+        let mut detected_ids: HashSet<u64> = HashSet::new();
+        for (base_mint, cycle_detector) in cycle_detectors.iter_mut() {
+            let detected = cycle_detector.detect(
+                &price_graph,
+                *base_mint,
+                2,  // min hops
+                5,  // max hops
+                profit_threshold,
+            );
+            cycles.extend(detected.into_iter().filter(|cycle| detected_ids.insert(cycle.cycle_id())));
+        }
 
-        let mut profitable_cycles = 0;
+        capabilities.classify(&mut cycles);
+
+        let mut rejections: Vec<CycleRejection> = cycles
+            .iter()
+            .filter(|cycle| !dex_filter.allows(cycle))
+            .map(|cycle| CycleRejection::new(cycle, RejectionStage::Venue, "venue combination not in required_dex_pairs"))
+            .collect();
+        let mut attempted_pools: HashSet<Pubkey> = HashSet::new();
+        dex_filter.apply(&mut cycles);
+
+        let mut opened = 0;
+        for event in opportunity_tracker.update(&cycles) {
+            opened += matches!(event, OpportunityEvent::Opened { .. }) as usize;
+            event.log();
+        }
+        session.record_pass(cycles.len(), opened);
+        let detected = if refreshed && forecaster.is_some() { cycles.clone() } else { Vec::new() };
+
+        // Slot the pool cooldown is measured against; unknown slots skip the check
+        let current_slot = if pool_cooldown.enabled() {
+            rpc_client
+                .get_slot()
+                .map_err(|e| warn!("⚠️ Failed to fetch slot, pool cooldown not applied this pass: {}", e))
+                .ok()
+        } else {
+            None
+        };
+
+        #[cfg(feature = "profiling")]
+        iteration_profile.record("detect", stage.finish());
+        #[cfg(feature = "profiling")]
+        let stage = crate::profiling::StageMark::start();
+
+        let mut cleared: Vec<(ArbitrageCycle, u64)> = Vec::new();
         for mut cycle in cycles {
-            if let Some(amount) = amount_optimizer.optimize_amount(
-                &mut cycle,
-                2_000_000_000, // $2000 in lamports
-                20,            // 20% capital per cycle
-                500_000,       // 0.005 SOL min profit
-            ) {
-                profitable_cycles += 1;
-                info!("💰 Cycle: {} hops, {} bps, {} SOL profit, {} SOL input",
+            if let Some((pool, used_slot)) = current_slot.and_then(|slot| pool_cooldown.cooling_pool(&cycle, slot)) {
+                rejections.push(CycleRejection::new(&cycle, RejectionStage::Risk, format!("cooldown: pool {} used in slot {}", pool, used_slot)));
+                continue;
+            }
+            if let Some((reliability, filter)) = &dex_reliability {
+                let unreliable = reliability.unreliable_venues(&cycle);
+                if !unreliable.is_empty() && *filter {
+                    rejections.push(CycleRejection::new(&cycle, RejectionStage::Risk, format!("unreliable venues: {:?}", unreliable)));
+                    continue;
+                }
+                cycle.priority_penalty_bps = reliability.penalty_bps(&cycle);
+            }
+            if cycle.observational {
+                rejections.push(CycleRejection::new(&cycle, RejectionStage::Venue, "observational: no executor for a leg"));
+                debug!("👀 Observational cycle (no executor for a leg): {} hops, {} bps",
+                    cycle.total_hops,
+                    cycle.total_profit_bps
+                );
+                continue;
+            }
+
+            // Capital limits are resolved by the base mint the cycle starts and ends in
+            let limits = match capital_book.limits_for(&cycle) {
+                Some(limits) => limits,
+                None => {
+                    rejections.push(CycleRejection::new(&cycle, RejectionStage::Config, format!("no capital configured for base mint {}", cycle.base_mint())));
+                    debug!("No capital configured for base mint {}, skipping cycle", cycle.base_mint());
+                    continue;
+                }
+            };
+
+            if let Some(amount) = amount_optimizer.optimize_amount(&mut cycle, limits, profit_threshold) {
+                cycle.estimated_tx_cost_lamports = tx_cost_model.estimate(&cycle, &compute_units).total();
+                opportunity_tracker.record_sizing(&cycle);
+                cleared.push((cycle, amount));
+            } else if let Some(shortfall_bps) = amount_optimizer.near_miss_shortfall(&mut cycle, limits, profit_threshold) {
+                cycle.estimated_tx_cost_lamports = tx_cost_model.estimate(&cycle, &compute_units).total();
+                opportunity_tracker.record_near_miss(&cycle, shortfall_bps);
+                info!("🎯 Near miss {:016x} {}: {} bps short of the {}-hop threshold net of fees and slippage, {} SOL profit on {} SOL input",
+                    cycle.cycle_id(),
+                    mint_registry.route(&cycle.legs),
+                    shortfall_bps,
                     cycle.total_hops,
-                    cycle.total_profit_bps,
                     cycle.estimated_profit_lamports as f64 / 1e9,
-                    amount as f64 / 1e9
+                    cycle.legs.first().map_or(0, |leg| leg.amount_in) as f64 / 1e9
                 );
+                rejections.push(CycleRejection::new(&cycle, RejectionStage::Cost, format!("optimizer: near miss, {} bps short of the profit threshold net of fees and slippage", shortfall_bps)));
+            } else {
+                rejections.push(CycleRejection::new(&cycle, RejectionStage::Cost, "optimizer: no input amount clears the profit threshold net of fees and slippage"));
+            }
+        }
+
+        let optimized_cycles = cleared.len();
+        #[cfg(feature = "profiling")]
+        {
+            iteration_profile.record("optimize", stage.finish());
+            iteration_profile.log();
+        }
+
+        // Cycles cleared in the same tick share their base mint's capital, capped by what the wallet holds
+        if live_executor.is_some() {
+            if let Err(e) = capital_book.refresh_balances(rpc_client.as_ref(), &wallet_kp.pubkey()) {
+                warn!("⚠️ Wallet balance refresh failed, budgeting with the last known balances: {}", e);
+            }
+        }
+        let (allocations, unfunded) = amount_optimizer.allocate_by_base_mint(cleared, &capital_book, profit_threshold);
+        for cycle in &unfunded {
+            rejections.push(CycleRejection::new(cycle, RejectionStage::Cost, "capital: budget committed to higher-value cycles this tick"));
+        }
+        let mut profitable_cycles = 0;
+        // Executions settle within the pass today, so permits are released when it ends
+        let mut in_flight: Vec<ExecutionPermit> = Vec::new();
+        let mut captured: Vec<&ArbitrageCycle> = Vec::new();
+        for CapitalAllocation { cycle, amount } in &allocations {
+            if let Some(reason) = post_trade.halted() {
+                rejections.push(CycleRejection::new(cycle, RejectionStage::Risk, format!("kill switch: {}", reason)));
+                continue;
+            }
+            // A cycle cleared earlier in this pass may have just claimed one of its pools
+            if let Some((pool, _)) = current_slot.and_then(|slot| pool_cooldown.cooling_pool(cycle, slot)) {
+                rejections.push(CycleRejection::new(cycle, RejectionStage::Risk, format!("cooldown: pool {} used by another cycle this pass", pool)));
+                continue;
+            }
+            // Pre-send simulation, reused while none of the cycle's edges changed
+            let simulation = simulation_cache.get_or_simulate(&price_graph, cycle, *amount, || {
+                simulator.simulate_transaction(cycle, &rpc_client)
+            });
+            match simulation {
+                Ok(result) if result.success => {}
+                Ok(result) => {
+                    let reason = result.error.unwrap_or_else(|| "unknown".to_string());
+                    rejections.push(CycleRejection::new(cycle, RejectionStage::Risk, format!("simulation failed: {}", reason)));
+                    continue;
+                }
+                Err(e) => {
+                    rejections.push(CycleRejection::new(cycle, RejectionStage::Risk, format!("simulation error: {}", e)));
+                    continue;
+                }
+            }
+
+            if let Some(gate) = &confirmation_gate {
+                let admission = gate.lock().unwrap().admit(cycle, *amount, clock.now_instant());
+                match admission {
+                    Admission::Send => {}
+                    Admission::Confirmed => {
+                        let state = gate.lock().unwrap().state().clone();
+                        info!("✅ Sending confirmed cycle {:016x}, {} confirmations remaining", cycle.cycle_id(), state.remaining);
+                        if state.remaining == 0 {
+                            info!("🔓 Confirmation mode complete for version {}, resuming unattended execution", state.fingerprint);
+                        }
+                        if let Err(e) = state.save(&confirmation_state_file).await {
+                            warn!("⚠️ Failed to save confirmation state: {}", e);
+                        }
+                    }
+                    Admission::Held { first_time } => {
+                        if first_time {
+                            warn!("✋ Holding {}", cycle_report(cycle, *amount));
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            // Allocations come in order of expected value, so the cap drops the least valuable
+            match execution_limiter.try_acquire() {
+                Some(permit) => in_flight.push(permit),
+                None => {
+                    rejections.push(CycleRejection::new(
+                        cycle,
+                        RejectionStage::Risk,
+                        format!("execution cap: {} executions in flight", execution_limiter.in_flight()),
+                    ));
+                    continue;
+                }
+            }
+
+            profitable_cycles += 1;
+            captured.push(cycle);
+            session.record_profitable(cycle);
+            if let Some(slot) = current_slot {
+                pool_cooldown.record(cycle, slot);
+            }
+            settle_cooldown.record(cycle, clock.now_instant());
+            info!("💰 Cycle {:016x} (epoch {}) {}: {} hops, {} bps, {} SOL profit, {} SOL est. tx cost, {} SOL net, {} SOL input, {} bps quote haircut",
+                cycle.cycle_id(),
+                opportunity_tracker.epoch_for(cycle.cycle_id()).unwrap_or(0),
+                mint_registry.route(&cycle.legs),
+                cycle.total_hops,
+                cycle.total_profit_bps,
+                cycle.estimated_profit_lamports as f64 / 1e9,
+                cycle.estimated_tx_cost_lamports as f64 / 1e9,
+                cycle.net_profit_lamports() as f64 / 1e9,
+                *amount as f64 / 1e9,
+                cycle.quote_haircut_bps()
+            );
+
+            if let Some((market, fee_lamports)) = paper.as_mut() {
+                match market.execute(cycle, *amount) {
+                    Ok(fill) => {
+                        opportunity_tracker.record_attempt(cycle);
+                        attempted_pools.extend(cycle.legs.iter().map(|leg| leg.pool_pubkey));
+                        // The executor reverts a cycle that does not return more than it took
+                        if let Some((reliability, _)) = dex_reliability.as_mut() {
+                            reliability.record(cycle.legs.iter().map(|leg| leg.dex_type), fill.base_delta() > 0);
+                        }
+                        paper_fills += 1;
+                        let record = fill.ledger_record(paper_fills, cycle, &cycle.base_mint(), current_slot.unwrap_or(0), *fee_lamports, clock.now_unix());
+                        info!("📝 Paper fill {}: {} {} before {} lamports of fees", paper_fills, record.base_delta, record.base_mint, record.fee_lamports);
+                        pnl.add(&record);
+                        pnl.log(pnl.usd_total(|mint| price_graph.usd_per_unit(mint)));
+                        let (pre, post) = fill.balance_snapshots(cycle, *fee_lamports);
+                        post_trade.review(cycle, &pre, &post, *fee_lamports, &price_graph, record, clock.now_unix()).await;
+                        amount_optimizer.record_fill(cycle, &fill.leg_outputs);
+                        save_realized_slippage(&amount_optimizer, &engine_config).await;
+                    }
+                    Err(e) => warn!("⚠️ Paper fill for cycle {:016x} failed: {}", cycle.cycle_id(), e),
+                }
+            } else if let Some(executor) = live_executor.as_mut() {
+                // A bundle that missed its window is re-quoted on freshly read pools before it is re-tipped
+                let min_profit = capital_book.limits_for(cycle).map_or(0, |limits| limits.min_profit);
+                let mut requote = || {
+                    if post_trade.halted().is_some() {
+                        return None;
+                    }
+                    price_graph.begin_generation();
+                    for pool_data in cycle.legs.iter().filter_map(|leg| mint_pool_data.get(&leg.to_mint)) {
+                        price_graph.try_update_from_mint_pool_data(pool_data, &*rpc_client).ok()?;
+                    }
+                    amount_optimizer.expected_profit(cycle, *amount).filter(|profit| *profit >= min_profit)
+                };
+                match executor
+                    .execute(&wallet_kp, &config, cycle, *amount, &mint_pool_data, &compute_units, rpc_client.as_ref(), clock.now_unix(), &mut requote)
+                    .await
+                {
+                    Ok(Some(fill)) => {
+                        opportunity_tracker.record_attempt(cycle);
+                        attempted_pools.extend(cycle.legs.iter().map(|leg| leg.pool_pubkey));
+                        if let Some(profile) = &fill.compute {
+                            compute_units.record(profile);
+                            if let Err(e) = compute_units.save(&engine_config.compute_profile_file).await {
+                                warn!("⚠️ Failed to save compute profile: {}", e);
+                            }
+                            if let Some(path) = &engine_config.status_file {
+                                let status = EngineStatus::new(&capabilities, &compute_units, &fingerprint, clock.now_unix()).with_partition(partition.as_ref());
+                                if let Err(e) = status.save(path).await {
+                                    warn!("⚠️ Failed to write status file {}: {}", path, e);
+                                }
+                            }
+                        }
+                        if let Some((reliability, _)) = dex_reliability.as_mut() {
+                            reliability.record(cycle.legs.iter().map(|leg| leg.dex_type), fill.success);
+                        }
+                        // A reverted transaction should only have cost its fee
+                        let expected = if fill.success {
+                            cycle.clone()
+                        } else {
+                            ArbitrageCycle { estimated_profit_lamports: 0, ..cycle.clone() }
+                        };
+                        let record = fill.ledger_record(cycle, &cycle.base_mint(), clock.now_unix());
+                        pnl.add(&record);
+                        pnl.log(pnl.usd_total(|mint| price_graph.usd_per_unit(mint)));
+                        if fill.success {
+                            let amount_out = (*amount as i128 + record.base_delta).max(0) as u64;
+                            amount_optimizer.record_cycle_fill(cycle, *amount, amount_out);
+                            save_realized_slippage(&amount_optimizer, &engine_config).await;
+                        }
+                        post_trade
+                            .review(&expected, &fill.pre, &fill.post, cycle.estimated_tx_cost_lamports, &price_graph, record, clock.now_unix())
+                            .await;
+                    }
+                    // Sent but unconfirmed still counts as an attempt on its pools
+                    Ok(None) => {
+                        opportunity_tracker.record_attempt(cycle);
+                        attempted_pools.extend(cycle.legs.iter().map(|leg| leg.pool_pubkey));
+                    }
+                    Err(e) => warn!("⚠️ Failed to execute cycle {:016x}: {}", cycle.cycle_id(), e),
+                }
+            }
+        }
+        drop(in_flight);
+        if let Some((forecaster, _)) = forecaster.as_mut().filter(|_| refreshed) {
+            forecaster.record(&priced_mints, &detected, &captured);
+        }
+        if !unfunded.is_empty() {
+            debug!("{} cleared cycles left unfunded by the capital budget", unfunded.len());
+        }
+
+        if let Some(spread_monitor) = spread_monitor.as_mut() {
+            for alert in spread_monitor.observe(&price_graph, &attempted_pools, &rejections) {
+                alert.log();
             }
         }
 
         if profitable_cycles == 0 {
             debug!("No profitable cycles detected this iteration");
         }
+        let below_threshold = cycle_detectors.iter().map(|(_, detector)| detector.below_threshold()).sum();
+        let near_misses: Vec<NearMiss> = cycle_detectors.iter().flat_map(|(_, detector)| detector.near_misses().iter().cloned()).collect();
+        let iteration_rejections = RejectionCounts::from_iteration(below_threshold, &rejections);
+        if dry_spell.observe(optimized_cycles, iteration_rejections, &near_misses) {
+            let (update_stats, unreachable_mints) = last_update;
+            dry_spell
+                .report(&price_graph, &capabilities, update_stats, unreachable_mints, profit_threshold.for_hops(2))
+                .log();
+        }
+
+        // Heartbeat once per refresh; detection-only passes log at debug
+        let (simulation_hits, simulation_misses) = simulation_cache.stats();
+        if refreshed {
+            info!("⏱️  Bot heartbeat{}: {} active mints, {} cycles, simulation cache {} hits / {} misses, {} legs rejected for unrepresentable amounts",
+                instance_label, mint_pool_data.len(), profitable_cycles, simulation_hits, simulation_misses, amount_optimizer.conversion_rejections());
+        } else {
+            debug!("Detection pass: {} cycles, simulation cache {} hits / {} misses",
+                profitable_cycles, simulation_hits, simulation_misses);
+        }
+    }
+
+    let summary = session.summary(clock.now_unix(), clock.elapsed_since(session_started));
+    info!(
+        "📊 Session: {:.0}s, {} detection passes, {} opportunities, {} profitable cycles, {} SOL projected ({} SOL net)",
+        summary.runtime_secs,
+        summary.detection_passes,
+        summary.opportunities_opened,
+        summary.profitable_cycles,
+        summary.projected_profit_lamports as f64 / 1e9,
+        summary.projected_net_profit_lamports as f64 / 1e9
+    );
+    if pnl.records > 0 {
+        pnl.log(pnl.usd_total(|mint| price_graph.usd_per_unit(mint)));
+    }
+    if let Some(path) = &engine_config.session_summary_file {
+        atomic_write_json(path, &summary).await?;
+        info!("💾 Saved session summary to {}", path);
+    }
+    Ok(())
+}
+
+/// Persist the optimizer's realized slippage averages after a fill, if configured
+async fn save_realized_slippage(amount_optimizer: &AmountOptimizer, engine_config: &EngineConfig) {
+    if let Some(path) = &engine_config.realized_slippage_file {
+        if let Err(e) = amount_optimizer.save_realized_slippage(path).await {
+            warn!("⚠️ Failed to save realized slippage to {}: {}", path, e);
+        }
+    }
+}
+
+/// Discovery follower: reload the leader's output file whenever its timestamp changes
+async fn follow_discovery(state: Arc<BotState>, mut last_timestamp: Option<u64>) {
+    let engine = state.discovery_engine.as_ref().unwrap();
+    let mut follow_interval = interval_at(Instant::now() + DISCOVERY_FOLLOW_INTERVAL, DISCOVERY_FOLLOW_INTERVAL);
+    let mut last_modified = None;
+
+    loop {
+        follow_interval.tick().await;
+        // Only parse the file once the leader has rewritten it
+        let modified = tokio::fs::metadata(engine.output_file()).await.and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+        let results = match engine.load_results().await {
+            Ok(Some(results)) => results,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("⚠️ Failed to read discovery results: {}", e);
+                continue;
+            }
+        };
+        if last_timestamp == Some(results.timestamp) {
+            continue;
+        }
+        last_timestamp = Some(results.timestamp);
 
-        info!("⏱️  Bot heartbeat: {} active mints, {} cycles", mint_pool_data.len(), profitable_cycles);
+        let new_markets = state.discovered_markets(&results);
+        let old_count = state.markets.read().await.len();
+        *state.markets.write().await = new_markets.clone();
+        info!("📈 Markets updated from the leader's discovery: {} → {} pools", old_count, new_markets.len());
     }
 }
 
-/// Background discovery thread - runs every 15 minutes
-async fn run_background_discovery(state: Arc<BotState>) {
+/// Background discovery thread - runs every 15 minutes, the first time after `first_run`
+async fn run_background_discovery(state: Arc<BotState>, first_run: Duration) {
     let engine = state.discovery_engine.as_ref().unwrap();
-    let mut discovery_interval = interval(Duration::from_secs(60 * 15)); // 15 minutes
+    let mut discovery_interval = interval_at(Instant::now() + first_run, DISCOVERY_INTERVAL);
     
     info!("🤖 Background discovery thread started (15 min interval)");
     
@@ -172,7 +1077,7 @@ async fn run_background_discovery(state: Arc<BotState>) {
         discovery_interval.tick().await;
         info!("🔄 Running scheduled pool discovery...");
         
-        match engine.run_discovery().await {
+        match engine.run_discovery_with_fallback().await {
             Ok(results) => {
                 // Save to JSON file
                 if let Err(e) = engine.save_results(&results).await {
@@ -181,7 +1086,7 @@ async fn run_background_discovery(state: Arc<BotState>) {
                 }
                 
                 // Update markets in bot state
-                let new_markets = crate::discovery::DiscoveryEngine::convert_to_markets(&results);
+                let new_markets = state.discovered_markets(&results);
                 let old_count = state.markets.read().await.len();
                 *state.markets.write().await = new_markets.clone();
                 
@@ -195,7 +1100,7 @@ async fn run_background_discovery(state: Arc<BotState>) {
 }
 
 /// Load keypair from base58 string or file path
-fn load_keypair(private_key: &str) -> anyhow::Result<Keypair> {
+pub fn load_keypair(private_key: &str) -> anyhow::Result<Keypair> {
     if let Ok(keypair) = bs58::decode(private_key)
         .into_vec()
         .map_err(|e| anyhow::anyhow!("Failed to decode base58: {}", e))
@@ -0,0 +1,371 @@
+use crate::config::{Config, JitoConfig};
+use crate::engine::compute_profile::ComputeUnitTable;
+use crate::engine::split::CycleSplitter;
+use crate::engine::tip::{TipDecision, TipEscalation};
+use crate::engine::types::ArbitrageCycle;
+use crate::ledger::BundleAttempt;
+use crate::pools::MintPoolData;
+use crate::rpc::RpcLike;
+use crate::transaction::build_bundle_txs;
+use anyhow::{Context, Result};
+use base64::Engine;
+use rand::Rng;
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::clock::Slot;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Jito's mainnet tip accounts, one of which is paid per bundle
+const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// The part of a block engine's bundle API the sender uses, so the tip
+/// escalation can be driven by a mock instead of Jito
+pub trait BlockEngine: Send + Sync {
+    /// Submit `txs` as one all-or-nothing bundle, returning its bundle id
+    fn send_bundle(&self, txs: &[VersionedTransaction]) -> Result<String>;
+
+    /// Slot the bundle landed in, `None` while it has not been seen landing
+    fn bundle_landed_slot(&self, bundle_id: &str) -> Result<Option<Slot>>;
+}
+
+/// Jito block engine over its JSON-RPC bundle endpoint
+pub struct JitoClient {
+    client: RpcClient,
+}
+
+impl JitoClient {
+    pub fn new(block_engine_url: String) -> Self {
+        Self { client: RpcClient::new(block_engine_url) }
+    }
+}
+
+impl BlockEngine for JitoClient {
+    fn send_bundle(&self, txs: &[VersionedTransaction]) -> Result<String> {
+        let encoded = txs
+            .iter()
+            .map(|tx| Ok(base64::engine::general_purpose::STANDARD.encode(bincode::serialize(tx)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let bundle_id: String = self
+            .client
+            .send(RpcRequest::Custom { method: "sendBundle" }, json!([encoded, { "encoding": "base64" }]))
+            .context("sendBundle failed")?;
+        Ok(bundle_id)
+    }
+
+    fn bundle_landed_slot(&self, bundle_id: &str) -> Result<Option<Slot>> {
+        let response: Value = self
+            .client
+            .send(RpcRequest::Custom { method: "getBundleStatuses" }, json!([[bundle_id]]))
+            .context("getBundleStatuses failed")?;
+        let status = &response["value"][0];
+        let landed = matches!(status["confirmation_status"].as_str(), Some("confirmed" | "finalized"));
+        Ok(status["slot"].as_u64().filter(|_| landed))
+    }
+}
+
+/// How a bundle submission ended: landed in a slot, or abandoned with a reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleOutcome {
+    pub landed_slot: Option<Slot>,
+    pub abandoned: Option<String>,
+    pub attempts: Vec<BundleAttempt>,
+}
+
+/// Submit a bundle and escalate its tip while it fails to land. `build`
+/// signs the bundle for a tip; `requote` re-checks staleness and risk and
+/// returns the profit now expected, or `None` to abandon the bundle. It is
+/// asked once per expired inclusion window, before every resubmission.
+pub async fn submit_with_escalation(
+    engine: &dyn BlockEngine,
+    escalation: &TipEscalation,
+    initial_tip: u64,
+    poll_interval: Duration,
+    current_slot: &mut (dyn FnMut() -> Result<Slot> + Send),
+    build: &mut (dyn FnMut(u64) -> Result<Vec<VersionedTransaction>> + Send),
+    requote: &mut (dyn FnMut() -> Option<u64> + Send),
+) -> Result<BundleOutcome> {
+    let mut attempts: Vec<BundleAttempt> = Vec::new();
+    let mut tip = initial_tip;
+    loop {
+        let bundle_id = engine.send_bundle(&build(tip)?)?;
+        let sent_slot = current_slot()?;
+        let attempt = attempts.len() as u32 + 1;
+        info!("📦 Bundle {} sent (attempt {}, {} lamports tip) at slot {}", bundle_id, attempt, tip, sent_slot);
+        attempts.push(BundleAttempt { attempt, bundle_id: bundle_id.clone(), tip_lamports: tip, sent_slot });
+
+        loop {
+            tokio::time::sleep(poll_interval).await;
+            if let Some(slot) = engine.bundle_landed_slot(&bundle_id)? {
+                return Ok(BundleOutcome { landed_slot: Some(slot), abandoned: None, attempts });
+            }
+            let slot = current_slot()?;
+            if slot.saturating_sub(sent_slot) < escalation.inclusion_slots {
+                continue;
+            }
+            let Some(expected_profit) = requote() else {
+                let reason = "re-quote failed the staleness or risk checks".to_string();
+                return Ok(BundleOutcome { landed_slot: None, abandoned: Some(reason), attempts });
+            };
+            match escalation.decide(attempt, tip, sent_slot, slot, expected_profit) {
+                TipDecision::Wait => continue,
+                TipDecision::Resubmit { tip: escalated } => {
+                    tip = escalated;
+                    break;
+                }
+                TipDecision::GiveUp => {
+                    let reason = format!(
+                        "not included after {} attempts, next tip over {} lamports ({} of {} expected profit) or out of attempts",
+                        attempt,
+                        escalation.tip_cap(expected_profit),
+                        escalation.max_profit_fraction,
+                        expected_profit
+                    );
+                    return Ok(BundleOutcome { landed_slot: None, abandoned: Some(reason), attempts });
+                }
+            }
+        }
+    }
+}
+
+/// Sends cycles as Jito bundles: one transaction within the compute budget,
+/// tipped in its last instruction, and re-tipped per `TipEscalation` while
+/// it misses the auction
+pub struct BundleSender {
+    engine: Arc<dyn BlockEngine>,
+    escalation: TipEscalation,
+    splitter: CycleSplitter,
+    initial_tip: u64,
+    tip_accounts: Vec<Pubkey>,
+    poll_interval: Duration,
+}
+
+impl BundleSender {
+    pub fn new(config: &JitoConfig, engine: Arc<dyn BlockEngine>) -> Result<Self> {
+        let tip_accounts = if config.tip_accounts.is_empty() {
+            JITO_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
+        } else {
+            config.tip_accounts.clone()
+        };
+        let tip_accounts = tip_accounts
+            .iter()
+            .map(|account| account.parse::<Pubkey>().map_err(|e| anyhow::anyhow!("Invalid jito tip account {}: {}", account, e)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            engine,
+            escalation: TipEscalation {
+                inclusion_slots: config.inclusion_slots,
+                multiplier: config.tip_multiplier,
+                max_profit_fraction: config.max_tip_profit_fraction,
+                max_attempts: config.max_attempts,
+            },
+            splitter: CycleSplitter {
+                max_compute_units: config.max_compute_units_per_tx,
+                max_legs_per_tx: config.max_legs_per_tx,
+                unmeasured_leg_units: config.unmeasured_leg_units,
+            },
+            initial_tip: config.tip_lamports,
+            tip_accounts,
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+        })
+    }
+
+    /// Build `cycle` into a bundle and submit it until it lands or is
+    /// abandoned. The transaction's signature identifies the fill.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send(
+        &self,
+        wallet_kp: &Keypair,
+        config: &Config,
+        cycle: &ArbitrageCycle,
+        mint_pool_data: &MintPoolData,
+        compute_units: &ComputeUnitTable,
+        rpc_client: &dyn RpcLike,
+        blockhash: Hash,
+        address_lookup_table_accounts: &[AddressLookupTableAccount],
+        rng: &mut (impl Rng + Send),
+        requote: &mut (dyn FnMut() -> Option<u64> + Send),
+    ) -> Result<(BundleOutcome, Vec<VersionedTransaction>)> {
+        let tip_account = self.tip_accounts[rng.gen_range(0..self.tip_accounts.len())];
+        let mut last_bundle = Vec::new();
+        let outcome = submit_with_escalation(
+            self.engine.as_ref(),
+            &self.escalation,
+            self.initial_tip,
+            self.poll_interval,
+            &mut || Ok(rpc_client.get_slot()?),
+            &mut |tip| {
+                let tip_ix = system_instruction::transfer(&wallet_kp.pubkey(), &tip_account, tip);
+                let txs = build_bundle_txs(
+                    wallet_kp,
+                    config,
+                    cycle,
+                    mint_pool_data,
+                    compute_units,
+                    &self.splitter,
+                    rpc_client,
+                    blockhash,
+                    address_lookup_table_accounts,
+                    Some(tip_ix),
+                    rng,
+                )?;
+                last_bundle = txs.clone();
+                Ok(txs)
+            },
+            requote,
+        )
+        .await?;
+        if let Some(reason) = &outcome.abandoned {
+            warn!("⚠️ Cycle {:016x}: bundle abandoned, {}", cycle.cycle_id(), reason);
+        }
+        Ok((outcome, last_bundle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records every bundle sent to it and reports the `lands_on`-th one as
+    /// landed, or none at all
+    struct MockBlockEngine {
+        lands_on: Option<usize>,
+        sent: Mutex<Vec<usize>>, // Transaction count of each bundle
+    }
+
+    impl MockBlockEngine {
+        fn new(lands_on: Option<usize>) -> Self {
+            Self { lands_on, sent: Mutex::new(Vec::new()) }
+        }
+    }
+
+    impl BlockEngine for MockBlockEngine {
+        fn send_bundle(&self, txs: &[VersionedTransaction]) -> Result<String> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(txs.len());
+            Ok(format!("bundle-{}", sent.len()))
+        }
+
+        fn bundle_landed_slot(&self, bundle_id: &str) -> Result<Option<Slot>> {
+            let landed = self.lands_on.is_some_and(|n| bundle_id == format!("bundle-{}", n));
+            Ok(landed.then_some(1_000))
+        }
+    }
+
+    fn escalation(max_attempts: u32) -> TipEscalation {
+        TipEscalation { inclusion_slots: 2, multiplier: 2.0, max_profit_fraction: 0.5, max_attempts }
+    }
+
+    /// Run a submission where every slot read advances the chain by one and
+    /// every re-quote answers `expected_profit`; returns the outcome, the
+    /// tips the bundle was built with and how often it was re-quoted
+    async fn submit(engine: &MockBlockEngine, escalation: &TipEscalation, expected_profit: Option<u64>) -> (BundleOutcome, Vec<u64>, usize) {
+        let mut slot = 100;
+        let mut tips = Vec::new();
+        let mut requotes = 0;
+        let outcome = submit_with_escalation(
+            engine,
+            escalation,
+            1_000,
+            Duration::ZERO,
+            &mut || {
+                slot += 1;
+                Ok(slot)
+            },
+            &mut |tip| {
+                tips.push(tip);
+                Ok(vec![VersionedTransaction::default(), VersionedTransaction::default()])
+            },
+            &mut || {
+                requotes += 1;
+                expected_profit
+            },
+        )
+        .await
+        .unwrap();
+        (outcome, tips, requotes)
+    }
+
+    #[tokio::test]
+    async fn a_missed_bundle_is_resent_with_an_escalating_tip_until_out_of_attempts() {
+        let engine = MockBlockEngine::new(None);
+
+        let (outcome, tips, requotes) = submit(&engine, &escalation(3), Some(1_000_000)).await;
+
+        assert_eq!(tips, vec![1_000, 2_000, 4_000]);
+        assert_eq!(*engine.sent.lock().unwrap(), vec![2, 2, 2]);
+        assert_eq!(requotes, 3);
+        assert_eq!(outcome.landed_slot, None);
+        assert!(outcome.abandoned.is_some());
+        let recorded: Vec<(u32, u64)> = outcome.attempts.iter().map(|a| (a.attempt, a.tip_lamports)).collect();
+        assert_eq!(recorded, vec![(1, 1_000), (2, 2_000), (3, 4_000)]);
+        assert_eq!(outcome.attempts[1].bundle_id, "bundle-2");
+        assert!(outcome.attempts.windows(2).all(|pair| pair[1].sent_slot >= pair[0].sent_slot + 2));
+    }
+
+    #[tokio::test]
+    async fn the_tip_never_escalates_past_its_share_of_the_requoted_profit() {
+        let engine = MockBlockEngine::new(None);
+
+        // Half of 10_000 caps the tip at 5_000, so 8_000 is never sent
+        let (outcome, tips, _) = submit(&engine, &escalation(10), Some(10_000)).await;
+
+        assert_eq!(tips, vec![1_000, 2_000, 4_000]);
+        assert_eq!(outcome.attempts.len(), 3);
+        let reason = outcome.abandoned.unwrap();
+        assert!(reason.contains("5000 lamports"), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn a_bundle_that_lands_stops_the_escalation() {
+        let engine = MockBlockEngine::new(Some(2));
+
+        let (outcome, tips, requotes) = submit(&engine, &escalation(5), Some(1_000_000)).await;
+
+        assert_eq!(outcome.landed_slot, Some(1_000));
+        assert_eq!(outcome.abandoned, None);
+        assert_eq!(tips, vec![1_000, 2_000]);
+        assert_eq!(requotes, 1);
+    }
+
+    #[tokio::test]
+    async fn a_failed_requote_abandons_the_bundle_without_resending() {
+        let engine = MockBlockEngine::new(None);
+
+        let (outcome, tips, requotes) = submit(&engine, &escalation(5), None).await;
+
+        assert_eq!(tips, vec![1_000]);
+        assert_eq!(requotes, 1);
+        assert_eq!(outcome.attempts.len(), 1);
+        assert!(outcome.abandoned.unwrap().contains("re-quote"));
+    }
+
+    #[test]
+    fn the_sender_defaults_to_the_mainnet_tip_accounts() {
+        let config: JitoConfig = toml::from_str("enabled = true").unwrap();
+        let sender = BundleSender::new(&config, Arc::new(MockBlockEngine::new(None))).unwrap();
+
+        assert_eq!(sender.tip_accounts.len(), JITO_TIP_ACCOUNTS.len());
+        assert_eq!(sender.initial_tip, 10_000);
+        assert_eq!(sender.escalation.max_attempts, 3);
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of time for staleness windows, TTLs and timestamps, so components
+/// that age state can be driven deterministically.
+pub trait Clock: Send + Sync {
+    fn now_instant(&self) -> Instant;
+
+    /// Seconds since the unix epoch
+    fn now_unix(&self) -> u64;
+
+    fn elapsed_since(&self, earlier: Instant) -> Duration {
+        self.now_instant().saturating_duration_since(earlier)
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real clock
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now_unix(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to
+#[derive(Debug)]
+pub struct MockClock {
+    base: Instant,
+    unix_base: u64,
+    offset: Mutex<Duration>,
+}
+
+impl MockClock {
+    pub fn new(unix_base: u64) -> Self {
+        Self {
+            base: Instant::now(),
+            unix_base,
+            offset: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.offset.lock().unwrap() += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now_instant(&self) -> Instant {
+        self.base + *self.offset.lock().unwrap()
+    }
+
+    fn now_unix(&self) -> u64 {
+        self.unix_base + self.offset.lock().unwrap().as_secs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_mock_clock_moves_both_readings_only_when_advanced() {
+        let clock = MockClock::new(1_700_000_000);
+        let start = clock.now_instant();
+        assert_eq!(clock.now_instant(), start);
+        assert_eq!(clock.now_unix(), 1_700_000_000);
+
+        clock.advance(Duration::from_millis(1_500));
+        assert_eq!(clock.elapsed_since(start), Duration::from_millis(1_500));
+        assert_eq!(clock.now_unix(), 1_700_000_001);
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(clock.now_instant() - start, Duration::from_secs(2));
+        assert_eq!(clock.now_unix(), 1_700_000_002);
+    }
+
+    #[test]
+    fn an_instant_ahead_of_the_clock_has_no_elapsed_time() {
+        let clock = MockClock::new(0);
+        let later = clock.now_instant() + Duration::from_secs(3);
+        assert_eq!(clock.elapsed_since(later), Duration::ZERO);
+    }
+
+    #[test]
+    fn the_system_clock_reads_the_wall_clock() {
+        let clock = system_clock();
+        let before = Instant::now();
+        let now = clock.now_instant();
+        assert!(now >= before && now <= Instant::now());
+
+        let wall = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!(clock.now_unix().abs_diff(wall) <= 1);
+        assert!(clock.elapsed_since(before) <= before.elapsed());
+    }
+}
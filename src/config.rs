@@ -1,4 +1,5 @@
 use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::{env, fs::File, io::Read};
 
 #[derive(Debug, Deserialize, Clone)]
@@ -10,11 +11,27 @@ pub struct Config {
     pub wallet: WalletConfig,
     pub flashloan: Option<FlashloanConfig>,
     pub discovery: Option<DiscoveryConfig>,
+    pub engine: Option<EngineConfig>,
+    pub health: Option<HealthConfig>,
+    pub oracle: Option<OracleConfig>,
+    pub spread_monitor: Option<SpreadMonitorConfig>,
+    pub forecast: Option<ForecastConfig>,
+    pub paper: Option<PaperConfig>,
+    pub dex_reliability: Option<DexReliabilityConfig>,
+    pub selftest: Option<SelftestConfig>,
+    pub ledger: Option<LedgerConfig>,
+    pub partition: Option<PartitionConfig>,
+    pub confirmation: Option<ConfirmationConfig>,
+    pub account_stream: Option<AccountStreamConfig>,
+    pub execution: Option<ExecutionConfig>,
+    pub jito: Option<JitoConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct BotConfig {
     pub compute_unit_limit: u32,
+    #[serde(default)]
+    pub strict_mode: bool, // Fail on the first parser/price anomaly instead of skipping the pool
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -27,12 +44,28 @@ pub struct MarketsConfig {
     pub markets: Vec<String>,
     pub lookup_table_accounts: Option<Vec<String>>,
     pub process_delay: u64,
+    #[serde(default = "default_init_concurrency")]
+    pub init_concurrency: usize,
+    #[serde(default = "default_init_batch_size")]
+    pub init_batch_size: usize,
+    #[serde(default)]
+    pub allow_transfer_hook_mints: bool, // Route through Token-2022 mints with a transfer hook instead of excluding them
+    #[serde(default)]
+    pub pump_bonding_curve_mints: Vec<String>, // Pre-graduation pump.fun tokens; their bonding curve is derived from the mint
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RpcConfig {
     #[serde(deserialize_with = "serde_string_or_env")]
-    pub url: String,
+    pub url: String, // Data endpoint: every read goes here
+    #[serde(default, deserialize_with = "serde_option_string_or_env")]
+    pub send_url: Option<String>, // Submission endpoint, e.g. a staked RPC; defaults to `url`
+}
+
+impl RpcConfig {
+    pub fn send_url(&self) -> &str {
+        self.send_url.as_deref().unwrap_or(&self.url)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -64,23 +97,488 @@ pub struct DiscoveryConfig {
     pub min_volume_h24: f64,
     #[serde(default = "default_output_file")]
     pub output_file: String,
+    #[serde(default = "default_archive_dir")]
+    pub archive_dir: String,
+    #[serde(default = "default_archive_retention")]
+    pub archive_retention: usize,
+    #[serde(default = "default_max_results_age_minutes")]
+    pub max_results_age_minutes: u64, // Reuse output_file at startup while younger than this
+    #[serde(default = "default_exclusions_file")]
+    pub exclusions_file: String,
+    #[serde(default)]
+    pub discovery_role: DiscoveryRole,
+    #[serde(default)]
+    pub onchain_fallback: bool, // Re-verify the saved results on-chain when the discovery APIs fail
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoveryRole {
+    #[default]
+    Leader,   // Runs discovery and writes output_file
+    Follower, // Only reads output_file written by a leader instance
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EngineConfig {
+    #[serde(default = "default_full_detection_interval")]
+    pub full_detection_interval: u64,
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    #[serde(default)]
+    pub detection_interval_ms: u64, // 0: detect once per refresh
+    #[serde(default)]
+    pub refresh_batches: u64, // Slices the steady-state refresh is spread over; 0 or 1: all mints at once
+    #[serde(default = "default_input_granularity_lamports")]
+    pub input_granularity_lamports: u64,
+    #[serde(default = "default_reconcile_tolerance_lamports")]
+    pub reconcile_tolerance_lamports: u64,
+    #[serde(default = "default_max_unexplained_loss_lamports")]
+    pub max_unexplained_loss_lamports: u64,
+    #[serde(default)]
+    pub realized_slippage_penalty_weight: f64,
+    #[serde(default)]
+    pub realized_slippage_file: Option<String>, // Per-pool realized slippage averages, kept across restarts
+    #[serde(default = "default_min_profit_bps")]
+    pub min_profit_bps: i64,
+    #[serde(default)]
+    pub per_hop_increment_bps: i64,
+    #[serde(default)]
+    pub min_pool_liquidity_usd: f64,
+    #[serde(default = "default_capital")]
+    pub capital: HashMap<String, CapitalConfig>, // Key: base mint, amounts in the mint's native units
+    #[serde(default = "default_compute_profile_file")]
+    pub compute_profile_file: String,
+    #[serde(default = "default_min_distinct_mints")]
+    pub min_distinct_mints: usize,
+    #[serde(default)]
+    pub required_dex_pairs: Vec<Vec<String>>, // Allowed venue combinations, e.g. [["raydium", "meteora"]]
+    #[serde(default = "default_approximate_quote_haircut_bps")]
+    pub approximate_quote_haircut_bps: u64,
+    #[serde(default)]
+    pub quote_haircut_bps: HashMap<String, u64>, // Key: DexType name, e.g. "Heaven"
+    #[serde(default)]
+    pub slippage: HashMap<String, SlippageConfig>, // Key: DexType name
+    #[serde(default = "default_simulation_cache_ttl_secs")]
+    pub simulation_cache_ttl_secs: u64,
+    #[serde(default = "default_simulation_cache_amount_bucket_lamports")]
+    pub simulation_cache_amount_bucket_lamports: u64,
+    #[serde(default = "default_prefetch_accounts")]
+    pub prefetch_accounts: bool,
+    #[serde(default)]
+    pub prefetch_auxiliary_accounts: bool, // Also warm configs, observations, tick and bin arrays
+    #[serde(default)]
+    pub min_pool_cooldown_slots: u64, // 0: no cooldown
+    #[serde(default)]
+    pub post_execution_cooldown_ms: u64, // 0: no forced re-read after an execution
+    #[serde(default)]
+    pub pool_reinit_interval: u64, // Refreshes between full pool re-initializations; 0: only when markets change
+    #[serde(default)]
+    pub max_slot_lag: u64, // 0: no slot filter
+    #[serde(default)]
+    pub record_near_misses: bool, // Report cycles the optimizer rejected within `near_miss_bps` of the threshold
+    #[serde(default = "default_near_miss_bps")]
+    pub near_miss_bps: u64,
+    #[serde(default = "default_dry_spell_iterations")]
+    pub dry_spell_iterations: u64, // 0: no diagnostic
+    #[serde(default)]
+    pub rpc_token_balances: bool, // Read vault balances with getTokenAccountBalance
+    #[serde(default)]
+    pub max_concurrent_executions: usize, // 0: no cap
+    #[serde(default)]
+    pub mint_registry_file: Option<String>, // Preloaded mint symbols and decimals
+    #[serde(default)]
+    pub tip_lamports: u64, // Included in each cycle's estimated transaction cost
+    #[serde(default = "default_ata_rent_lamports")]
+    pub ata_rent_lamports: u64, // Per intermediate-mint token account a cycle creates
+    #[serde(default)]
+    pub session_summary_file: Option<String>, // JSON summary of the run, written on Ctrl-C
+    #[serde(default)]
+    pub status_file: Option<String>, // Per-venue capabilities, written at startup
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CapitalConfig {
+    pub max_capital: u64,
+    pub per_cycle_percent: u64,
+    pub min_profit: u64,
+    #[serde(default)]
+    pub min_input_fraction: f64, // Smallest input as a fraction of max_capital
+    #[serde(default = "default_max_input_fraction")]
+    pub max_input_fraction: f64,
+}
+
+/// Modeled slippage for one venue, see `AmountOptimizer::set_slippage_bounds`
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct SlippageConfig {
+    #[serde(default = "default_slippage_base_bps")]
+    pub base_bps: u64,
+    #[serde(default = "default_slippage_cap_bps")]
+    pub cap_bps: u64,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            full_detection_interval: default_full_detection_interval(),
+            refresh_interval_secs: default_refresh_interval_secs(),
+            detection_interval_ms: 0,
+            refresh_batches: 0,
+            input_granularity_lamports: default_input_granularity_lamports(),
+            reconcile_tolerance_lamports: default_reconcile_tolerance_lamports(),
+            max_unexplained_loss_lamports: default_max_unexplained_loss_lamports(),
+            realized_slippage_penalty_weight: 0.0,
+            realized_slippage_file: None,
+            min_profit_bps: default_min_profit_bps(),
+            per_hop_increment_bps: 0,
+            min_pool_liquidity_usd: 0.0,
+            capital: default_capital(),
+            compute_profile_file: default_compute_profile_file(),
+            min_distinct_mints: default_min_distinct_mints(),
+            required_dex_pairs: Vec::new(),
+            approximate_quote_haircut_bps: default_approximate_quote_haircut_bps(),
+            quote_haircut_bps: HashMap::new(),
+            slippage: HashMap::new(),
+            simulation_cache_ttl_secs: default_simulation_cache_ttl_secs(),
+            simulation_cache_amount_bucket_lamports: default_simulation_cache_amount_bucket_lamports(),
+            prefetch_accounts: default_prefetch_accounts(),
+            prefetch_auxiliary_accounts: false,
+            min_pool_cooldown_slots: 0,
+            post_execution_cooldown_ms: 0,
+            pool_reinit_interval: 0,
+            max_slot_lag: 0,
+            record_near_misses: false,
+            near_miss_bps: default_near_miss_bps(),
+            dry_spell_iterations: default_dry_spell_iterations(),
+            rpc_token_balances: false,
+            max_concurrent_executions: 0,
+            mint_registry_file: None,
+            tip_lamports: 0,
+            ata_rent_lamports: default_ata_rent_lamports(),
+            session_summary_file: None,
+            status_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct OracleConfig {
+    #[serde(default = "default_oracle_url")]
+    pub url: String,
+    #[serde(default = "default_bootstrap_price_usd")]
+    pub bootstrap_price_usd: f64,
+    #[serde(default = "default_unconfirmed_liquidity_multiplier")]
+    pub unconfirmed_liquidity_multiplier: f64,
+    #[serde(default = "default_oracle_ttl_secs")]
+    pub ttl_secs: u64, // 0: fetch on every refresh
+    #[serde(default)]
+    pub pyth_price_account: Option<String>, // SOL/USD PriceUpdateV2 account, tried before `url`
+    #[serde(default)]
+    pub jupiter_url: Option<String>, // Tried after `url`
+    #[serde(default = "default_quote_mints")]
+    pub quote_mints: Vec<String>, // Non-SOL quote mints priced for liquidity values
+    #[serde(default = "default_quote_price_url")]
+    pub quote_price_url: String, // Jupiter price API v3; the quote mints are passed as `ids`
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            url: default_oracle_url(),
+            bootstrap_price_usd: default_bootstrap_price_usd(),
+            unconfirmed_liquidity_multiplier: default_unconfirmed_liquidity_multiplier(),
+            ttl_secs: default_oracle_ttl_secs(),
+            pyth_price_account: None,
+            jupiter_url: None,
+            quote_mints: default_quote_mints(),
+            quote_price_url: default_quote_price_url(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SpreadMonitorConfig {
+    pub enabled: bool,
+    #[serde(default = "default_spread_threshold_bps")]
+    pub threshold_bps: f64,
+    #[serde(default = "default_spread_persist_iterations")]
+    pub persist_iterations: u64,
+    #[serde(default = "default_spread_window")]
+    pub window: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PartitionConfig {
+    pub enabled: bool,
+    pub instance_index: u64,
+    pub instance_count: u64,
+    #[serde(default)]
+    pub share_base_mints: bool, // Trade USDC/USD1 pairs on every instance instead of only instance 0
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccountStreamConfig {
+    pub enabled: bool,
+    #[serde(deserialize_with = "serde_string_or_env")]
+    pub ws_url: String,
+    #[serde(default = "default_account_stream_max_age_secs")]
+    pub max_age_secs: u64, // Streamed state older than this is read over RPC again
+    #[serde(default = "default_account_stream_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ConfirmationConfig {
+    pub enabled: bool,
+    #[serde(default = "default_confirm_first_executions")]
+    pub first_executions: u32, // Executions held for confirmation after a code or config change
+    #[serde(default = "default_confirm_max_hold_secs")]
+    pub max_hold_secs: u64,
+    #[serde(default = "default_confirm_state_file")]
+    pub state_file: String,
+    #[serde(default)]
+    pub api_token: Option<String>, // Bearer token for the confirmation routes; without one they only answer loopback clients
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExecutionConfig {
+    #[serde(default)]
+    pub enabled: bool, // Send cleared cycles; otherwise they are only logged (or paper filled)
+    #[serde(default = "default_confirm_timeout_secs")]
+    pub confirm_timeout_secs: u64,
+    #[serde(default = "default_journal_file")]
+    pub journal_file: String,
+    #[serde(default = "default_kill_switch_file")]
+    pub kill_switch_file: String, // Written when the kill switch trips; delete it to resume
+}
+
+/// Bundle submission through a Jito block engine, see `bundle::BundleSender`
+#[derive(Debug, Deserialize, Clone)]
+pub struct JitoConfig {
+    pub enabled: bool,
+    #[serde(default = "default_block_engine_url", deserialize_with = "serde_string_or_env")]
+    pub block_engine_url: String,
+    #[serde(default)]
+    pub tip_accounts: Vec<String>, // Empty: Jito's mainnet tip accounts
+    #[serde(default = "default_jito_tip_lamports")]
+    pub tip_lamports: u64, // Tip of the first submission
+    #[serde(default = "default_inclusion_slots")]
+    pub inclusion_slots: u64, // Slots a bundle may go unseen before it counts as lost
+    #[serde(default = "default_tip_multiplier")]
+    pub tip_multiplier: f64,
+    #[serde(default = "default_max_tip_profit_fraction")]
+    pub max_tip_profit_fraction: f64, // Of the re-quoted profit
+    #[serde(default = "default_max_bundle_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_bundle_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    #[serde(default = "default_max_compute_units_per_tx")]
+    pub max_compute_units_per_tx: u64,
+    #[serde(default = "default_max_legs_per_tx")]
+    pub max_legs_per_tx: usize,
+    #[serde(default = "default_unmeasured_leg_units")]
+    pub unmeasured_leg_units: u64,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            confirm_timeout_secs: default_confirm_timeout_secs(),
+            journal_file: default_journal_file(),
+            kill_switch_file: default_kill_switch_file(),
+        }
+    }
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct ForecastConfig {
+    pub enabled: bool,
+    pub tokens_per_refresh: usize,
+    #[serde(default = "default_forecast_exploration_floor")]
+    pub exploration_floor: f64, // Fraction of the budget spread evenly across all tokens
+    #[serde(default = "default_forecast_smoothing")]
+    pub smoothing: f64,
+    #[serde(default = "default_forecast_log_top")]
+    pub log_top: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PaperConfig {
+    pub enabled: bool,
+    #[serde(default = "default_paper_shock_probability")]
+    pub shock_probability: f64, // Chance per pool per pass of a random price move
+    #[serde(default = "default_paper_shock_bps")]
+    pub shock_bps: f64,
+    #[serde(default = "default_paper_fee_lamports")]
+    pub fee_lamports: u64, // Network fee charged to each paper fill
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DexReliabilityConfig {
+    pub enabled: bool,
+    #[serde(default = "default_reliability_window")]
+    pub window: usize, // Recent attempts per venue the success rate is taken over
+    #[serde(default = "default_reliability_min_samples")]
+    pub min_samples: usize,
+    #[serde(default = "default_reliability_min_success_rate")]
+    pub min_success_rate: f64,
+    #[serde(default = "default_reliability_penalty_bps")]
+    pub penalty_bps: u64, // Per unreliable venue on a cycle
+    #[serde(default)]
+    pub filter: bool, // Drop cycles through unreliable venues instead of penalizing them
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SelftestConfig {
+    #[serde(default = "default_selftest_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_decoder_fixtures_file")]
+    pub fixtures_file: String,
+    #[serde(default = "default_decoder_accounts_file")]
+    pub accounts_file: String, // Known pools the capture subcommand snapshots
+}
+
+impl Default for SelftestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_selftest_enabled(),
+            fixtures_file: default_decoder_fixtures_file(),
+            accounts_file: default_decoder_accounts_file(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LedgerConfig {
+    #[serde(default = "default_ledger_file")]
+    pub file: String,
+}
+
+impl Default for LedgerConfig {
+    fn default() -> Self {
+        Self {
+            file: default_ledger_file(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HealthConfig {
+    pub enabled: bool,
+    #[serde(default = "default_health_bind_address")]
+    pub bind_address: String,
+    #[serde(default = "default_max_update_age_secs")]
+    pub max_update_age_secs: u64,
+}
+
+fn default_init_concurrency() -> usize { 4 }
+fn default_init_batch_size() -> usize { 100 }
 fn default_min_liquidity() -> f64 { 5000.0 }
 fn default_min_volume() -> f64 { 1000.0 }
 fn default_output_file() -> String { "discovered_pools.json".to_string() }
+fn default_archive_dir() -> String { "discovery_archive".to_string() }
+fn default_archive_retention() -> usize { 96 }
+fn default_max_results_age_minutes() -> u64 { 15 }
+fn default_exclusions_file() -> String { "discovery_exclusions.json".to_string() }
+fn default_confirm_first_executions() -> u32 { 5 }
+fn default_confirm_max_hold_secs() -> u64 { 30 }
+fn default_confirm_state_file() -> String { "confirmation_state.json".to_string() }
+fn default_full_detection_interval() -> u64 { 10 }
+fn default_refresh_interval_secs() -> u64 { 60 }
+fn default_input_granularity_lamports() -> u64 { 1 }
+fn default_reconcile_tolerance_lamports() -> u64 { 10_000 }
+fn default_max_unexplained_loss_lamports() -> u64 { 1_000_000 }
+fn default_min_profit_bps() -> i64 { 50 }
+fn default_dry_spell_iterations() -> u64 { 100 }
+fn default_capital() -> HashMap<String, CapitalConfig> {
+    HashMap::from([(
+        crate::constants::SOL_MINT.to_string(),
+        CapitalConfig {
+            max_capital: 2_000_000_000,
+            per_cycle_percent: 20,
+            min_profit: 500_000,
+            min_input_fraction: 0.0,
+            max_input_fraction: default_max_input_fraction(),
+        },
+    )])
+}
+fn default_max_input_fraction() -> f64 { 1.0 }
+fn default_spread_threshold_bps() -> f64 { 100.0 }
+fn default_spread_persist_iterations() -> u64 { 5 }
+fn default_spread_window() -> usize { 10 }
+fn default_forecast_exploration_floor() -> f64 { 0.1 }
+fn default_forecast_smoothing() -> f64 { 0.2 }
+fn default_forecast_log_top() -> usize { 5 }
+fn default_paper_shock_probability() -> f64 { 0.05 }
+fn default_paper_shock_bps() -> f64 { 50.0 }
+fn default_paper_fee_lamports() -> u64 { 5_000 }
+fn default_reliability_window() -> usize { 50 }
+fn default_reliability_min_samples() -> usize { 10 }
+fn default_reliability_min_success_rate() -> f64 { 0.5 }
+fn default_reliability_penalty_bps() -> u64 { 5_000 }
+fn default_selftest_enabled() -> bool { true }
+fn default_prefetch_accounts() -> bool { true }
+fn default_decoder_fixtures_file() -> String { "fixtures/decoders.json".to_string() }
+fn default_decoder_accounts_file() -> String { "fixtures/decoder_accounts.json".to_string() }
+fn default_approximate_quote_haircut_bps() -> u64 { 30 }
+fn default_slippage_base_bps() -> u64 { 10 }
+fn default_slippage_cap_bps() -> u64 { 100 }
+fn default_ledger_file() -> String { "ledger.jsonl".to_string() }
+fn default_confirm_timeout_secs() -> u64 { 30 }
+fn default_journal_file() -> String { "journal.jsonl".to_string() }
+fn default_kill_switch_file() -> String { "kill_switch.json".to_string() }
+fn default_simulation_cache_ttl_secs() -> u64 { 30 }
+fn default_simulation_cache_amount_bucket_lamports() -> u64 { 10_000_000 }
+fn default_compute_profile_file() -> String { "compute_profile.json".to_string() }
+fn default_near_miss_bps() -> u64 { 10 }
+fn default_min_distinct_mints() -> usize { 2 }
+fn default_ata_rent_lamports() -> u64 { crate::engine::TOKEN_ACCOUNT_RENT_LAMPORTS }
+fn default_health_bind_address() -> String { "0.0.0.0:8080".to_string() }
+fn default_max_update_age_secs() -> u64 { 180 }
+fn default_oracle_url() -> String {
+    "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd".to_string()
+}
+fn default_bootstrap_price_usd() -> f64 { 150.0 }
+fn default_unconfirmed_liquidity_multiplier() -> f64 { 2.0 }
+fn default_oracle_ttl_secs() -> u64 { 60 }
+fn default_quote_mints() -> Vec<String> { vec![crate::constants::USDC_MINT.to_string(), crate::constants::USD1_MINT.to_string()] }
+fn default_quote_price_url() -> String { "https://lite-api.jup.ag/price/v3".to_string() }
+fn default_account_stream_max_age_secs() -> u64 { 30 }
+fn default_account_stream_max_backoff_secs() -> u64 { 30 }
+fn default_block_engine_url() -> String { "https://mainnet.block-engine.jito.wtf/api/v1/bundles".to_string() }
+fn default_jito_tip_lamports() -> u64 { 10_000 }
+fn default_inclusion_slots() -> u64 { 4 }
+fn default_tip_multiplier() -> f64 { 2.0 }
+fn default_max_tip_profit_fraction() -> f64 { 0.5 }
+fn default_max_bundle_attempts() -> u32 { 3 }
+fn default_bundle_poll_interval_ms() -> u64 { 400 }
+fn default_max_compute_units_per_tx() -> u64 { 1_400_000 }
+fn default_max_legs_per_tx() -> usize { 4 }
+fn default_unmeasured_leg_units() -> u64 { 150_000 }
 
 pub fn serde_string_or_env<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
 {
     let value_or_env = String::deserialize(deserializer)?;
-    let value = match value_or_env.chars().next() {
+    Ok(resolve_env(value_or_env))
+}
+
+pub fn serde_option_string_or_env<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value_or_env = Option::<String>::deserialize(deserializer)?;
+    Ok(value_or_env.map(resolve_env))
+}
+
+fn resolve_env(value_or_env: String) -> String {
+    match value_or_env.chars().next() {
         Some('$') => env::var(&value_or_env[1..])
             .unwrap_or_else(|_| panic!("reading `{}` from env", &value_or_env[1..])),
         _ => value_or_env,
-    };
-    Ok(value)
+    }
 }
 
 impl Config {
@@ -0,0 +1,285 @@
+use crate::engine::types::ArbitrageCycle;
+use crate::persist::atomic_write_json;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Identifies the code and config a run executes with. Builds stamped with
+/// `GIT_COMMIT` use the commit, others fall back to the crate version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionFingerprint {
+    pub code: String,
+    pub config: String, // SHA-256 of the config file, hex
+}
+
+impl VersionFingerprint {
+    pub fn new(config_contents: &[u8]) -> Self {
+        let code = option_env!("GIT_COMMIT").unwrap_or(env!("CARGO_PKG_VERSION")).to_string();
+        let config = solana_sdk::hash::hash(config_contents).to_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        Self { code, config }
+    }
+}
+
+impl fmt::Display for VersionFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.code, &self.config[..self.config.len().min(12)])
+    }
+}
+
+/// Persisted between runs so a restart without changes does not re-arm the gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfirmationState {
+    pub fingerprint: VersionFingerprint,
+    pub remaining: u32, // Executions that still need a manual confirmation
+}
+
+impl ConfirmationState {
+    /// State for this run: the saved one when the fingerprint matches,
+    /// otherwise `required` confirmations for the new fingerprint
+    pub async fn load_or_arm(path: &str, fingerprint: VersionFingerprint, required: u32) -> anyhow::Result<(Self, bool)> {
+        let saved = match tokio::fs::read_to_string(path).await {
+            Ok(content) => Some(
+                serde_json::from_str::<ConfirmationState>(&content).context(format!("Failed to parse {}", path))?,
+            ),
+            Err(_) => None,
+        };
+        match saved {
+            Some(state) if state.fingerprint == fingerprint => Ok((state, false)),
+            _ => {
+                let state = Self { fingerprint, remaining: required };
+                state.save(path).await?;
+                Ok((state, true))
+            }
+        }
+    }
+
+    pub async fn save(&self, path: &str) -> anyhow::Result<()> {
+        atomic_write_json(path, self).await
+    }
+}
+
+/// What to do with a cycle that is ready to execute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Send,                      // Gate inactive
+    Confirmed,                 // Confirmed: send, and persist the lower count
+    Held { first_time: bool }, // Waiting for POST /confirm/{cycle_id}
+}
+
+struct HeldCycle {
+    report: String,
+    held_at: Instant,
+    confirmed: bool,
+}
+
+/// Holds would-be executions for manual confirmation while `remaining` is
+/// above zero. A held cycle is sent the next time it is detected after being
+/// confirmed, so it goes out on a fresh quote; an unconfirmed hold expires
+/// after `max_hold`.
+pub struct ConfirmationGate {
+    state: ConfirmationState,
+    max_hold: Duration,
+    held: HashMap<u64, HeldCycle>, // Key: cycle_id
+}
+
+impl ConfirmationGate {
+    pub fn new(state: ConfirmationState, max_hold: Duration) -> Self {
+        Self { state, max_hold, held: HashMap::new() }
+    }
+
+    pub fn active(&self) -> bool {
+        self.state.remaining > 0
+    }
+
+    pub fn state(&self) -> &ConfirmationState {
+        &self.state
+    }
+
+    pub fn admit(&mut self, cycle: &ArbitrageCycle, amount: u64, now: Instant) -> Admission {
+        if !self.active() {
+            return Admission::Send;
+        }
+        let cycle_id = cycle.cycle_id();
+        match self.held.get(&cycle_id) {
+            Some(held) if held.confirmed => {
+                self.held.remove(&cycle_id);
+                self.state.remaining -= 1;
+                if !self.active() {
+                    self.held.clear();
+                }
+                Admission::Confirmed
+            }
+            Some(_) => Admission::Held { first_time: false },
+            None => {
+                let report = cycle_report(cycle, amount);
+                self.held.insert(cycle_id, HeldCycle { report, held_at: now, confirmed: false });
+                Admission::Held { first_time: true }
+            }
+        }
+    }
+
+    /// Confirm a held cycle; it is sent the next time it clears every check
+    pub fn confirm(&mut self, cycle_id: u64) -> Result<(), String> {
+        match self.held.get_mut(&cycle_id) {
+            Some(held) => {
+                held.confirmed = true;
+                Ok(())
+            }
+            None => Err(format!("no held cycle {:016x}", cycle_id)),
+        }
+    }
+
+    /// Drop holds older than `max_hold`, returning their cycle ids
+    pub fn expire(&mut self, now: Instant) -> Vec<u64> {
+        let max_hold = self.max_hold;
+        let expired: Vec<u64> = self
+            .held
+            .iter()
+            .filter(|(_, held)| now.saturating_duration_since(held.held_at) > max_hold)
+            .map(|(cycle_id, _)| *cycle_id)
+            .collect();
+        for cycle_id in &expired {
+            self.held.remove(cycle_id);
+        }
+        expired
+    }
+
+    /// Held cycles with their reports, as served by the health endpoint
+    pub fn pending_report(&self) -> String {
+        let mut ids: Vec<&u64> = self.held.keys().collect();
+        ids.sort();
+        let mut out = format!(
+            "{}: {} confirmations remaining, {} held\n",
+            self.state.fingerprint,
+            self.state.remaining,
+            ids.len()
+        );
+        for cycle_id in ids {
+            let held = &self.held[cycle_id];
+            out.push_str(&format!("{}{}\n", held.report, if held.confirmed { " [confirmed]" } else { "" }));
+        }
+        out
+    }
+}
+
+/// One-line description of a cycle ready to execute, with every leg
+pub fn cycle_report(cycle: &ArbitrageCycle, amount: u64) -> String {
+    let legs: Vec<String> = cycle
+        .legs
+        .iter()
+        .map(|leg| format!("{} -> {} via {:?} {}", leg.from_mint, leg.to_mint, leg.dex_type, leg.pool_pubkey))
+        .collect();
+    format!(
+        "{:016x}: {} hops, {} bps, {} lamports profit on {} lamports input, {} bps quote haircut; {}",
+        cycle.cycle_id(),
+        cycle.total_hops,
+        cycle.total_profit_bps,
+        cycle.estimated_profit_lamports,
+        amount,
+        cycle.quote_haircut_bps(),
+        legs.join("; ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::{DexType, SwapLeg};
+    use solana_sdk::pubkey::Pubkey;
+
+    fn cycle() -> ArbitrageCycle {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 1_000,
+            estimated_amount_out: 1_000,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg(sol, token), leg(token, sol)],
+            total_profit_bps: 30,
+            estimated_profit_lamports: 3,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn gate(remaining: u32) -> ConfirmationGate {
+        let state = ConfirmationState { fingerprint: VersionFingerprint::new(b"config"), remaining };
+        ConfirmationGate::new(state, Duration::from_secs(30))
+    }
+
+    #[tokio::test]
+    async fn a_changed_config_rearms_the_gate_and_an_unchanged_one_resumes_it() {
+        let path = std::env::temp_dir().join(format!("{}_confirmation.json", Pubkey::new_unique())).display().to_string();
+        let (state, armed) = ConfirmationState::load_or_arm(&path, VersionFingerprint::new(b"v1"), 3).await.unwrap();
+        assert!(armed);
+        assert_eq!(state.remaining, 3);
+
+        ConfirmationState { remaining: 1, ..state }.save(&path).await.unwrap();
+        let (state, armed) = ConfirmationState::load_or_arm(&path, VersionFingerprint::new(b"v1"), 3).await.unwrap();
+        assert!(!armed, "a restart without changes keeps its count");
+        assert_eq!(state.remaining, 1);
+
+        let (state, armed) = ConfirmationState::load_or_arm(&path, VersionFingerprint::new(b"v2"), 3).await.unwrap();
+        assert!(armed);
+        assert_eq!((state.remaining, state.fingerprint), (3, VersionFingerprint::new(b"v2")));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn a_held_cycle_is_sent_only_once_confirmed() {
+        let mut gate = gate(2);
+        let (cycle, now) = (cycle(), Instant::now());
+        assert_eq!(gate.admit(&cycle, 1_000, now), Admission::Held { first_time: true });
+        assert_eq!(gate.admit(&cycle, 1_000, now), Admission::Held { first_time: false });
+        assert!(gate.pending_report().contains(&format!("{:016x}", cycle.cycle_id())));
+
+        assert!(gate.confirm(cycle.cycle_id() ^ 1).is_err());
+        gate.confirm(cycle.cycle_id()).unwrap();
+        assert!(gate.pending_report().contains("[confirmed]"));
+        assert_eq!(gate.admit(&cycle, 1_000, now), Admission::Confirmed);
+        assert_eq!(gate.state().remaining, 1);
+        // Confirmation is spent: detected again, the cycle is held anew
+        assert_eq!(gate.admit(&cycle, 1_000, now), Admission::Held { first_time: true });
+    }
+
+    #[test]
+    fn an_unconfirmed_hold_expires_after_max_hold() {
+        let mut gate = gate(1);
+        let (cycle, held_at) = (cycle(), Instant::now());
+        gate.admit(&cycle, 1_000, held_at);
+        assert!(gate.expire(held_at + Duration::from_secs(30)).is_empty());
+        assert_eq!(gate.expire(held_at + Duration::from_secs(31)), vec![cycle.cycle_id()]);
+        assert!(gate.confirm(cycle.cycle_id()).is_err());
+        assert_eq!(gate.state().remaining, 1, "an expired hold does not count");
+    }
+
+    #[test]
+    fn the_gate_turns_off_after_the_last_confirmation() {
+        let mut gate = gate(2);
+        let now = Instant::now();
+        let (first, second, waiting) = (cycle(), cycle(), cycle());
+        for cycle in [&first, &second, &waiting] {
+            gate.admit(cycle, 1_000, now);
+        }
+        for cycle in [&first, &second] {
+            gate.confirm(cycle.cycle_id()).unwrap();
+            assert_eq!(gate.admit(cycle, 1_000, now), Admission::Confirmed);
+        }
+        assert!(!gate.active());
+        assert_eq!(gate.state().remaining, 0);
+        // Holds left over are dropped, and everything is sent from now on
+        assert!(gate.pending_report().contains("0 held"));
+        assert_eq!(gate.admit(&waiting, 1_000, now), Admission::Send);
+        assert_eq!(gate.admit(&cycle(), 1_000, now), Admission::Send);
+    }
+}
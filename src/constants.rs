@@ -5,8 +5,10 @@ pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
 pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
 pub const USD1_MINT: &str = "USD1ttGY1N17NEEHLmELoaybftRBUSErhqYiQzvEmuB";
 
+/// Same key as `SOL_MINT`, without parsing it: this is read per leg on the
+/// optimizer's sizing path, which must not allocate
 pub fn sol_mint() -> Pubkey {
-    Pubkey::from_str(SOL_MINT).unwrap()
+    spl_token::native_mint::id()
 }
 
 pub fn usdc_mint() -> Pubkey {
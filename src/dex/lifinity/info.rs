@@ -0,0 +1,169 @@
+use anyhow::Result;
+use solana_program::pubkey::Pubkey;
+
+// Byte offsets for the Lifinity v2 `Amm` account (Anchor, 8-byte discriminator)
+// Reference: lifinity-amm-v2 IDL
+const TOKEN_A_ACCOUNT_OFFSET: usize = 158;
+const TOKEN_B_ACCOUNT_OFFSET: usize = 190;
+const TOKEN_A_MINT_OFFSET: usize = 254;
+const TOKEN_B_MINT_OFFSET: usize = 286;
+const ORACLE_MAIN_OFFSET: usize = 350;
+const TRADE_FEE_NUMERATOR_OFFSET: usize = 446;
+const TRADE_FEE_DENOMINATOR_OFFSET: usize = 454;
+const LAST_PRICE_OFFSET: usize = 519;
+const CONFIG_DENOMINATOR_OFFSET: usize = 535;
+
+pub struct LifinityPoolInfo {
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub oracle_main: Pubkey,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub last_price: u64,         // Last oracle price, scaled by `config_denominator`
+    pub config_denominator: u64,
+}
+
+impl LifinityPoolInfo {
+    pub const DISCRIMINATOR: [u8; 8] = [143, 245, 200, 17, 74, 214, 196, 135];
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < CONFIG_DENOMINATOR_OFFSET + 8 {
+            return Err(anyhow::anyhow!("Invalid data length for LifinityPoolInfo"));
+        }
+        if data[0..8] != Self::DISCRIMINATOR {
+            return Err(anyhow::anyhow!("Invalid discriminator for LifinityPoolInfo"));
+        }
+
+        Ok(Self {
+            token_a_mint: read_pubkey(data, TOKEN_A_MINT_OFFSET),
+            token_b_mint: read_pubkey(data, TOKEN_B_MINT_OFFSET),
+            token_a_vault: read_pubkey(data, TOKEN_A_ACCOUNT_OFFSET),
+            token_b_vault: read_pubkey(data, TOKEN_B_ACCOUNT_OFFSET),
+            oracle_main: read_pubkey(data, ORACLE_MAIN_OFFSET),
+            trade_fee_numerator: read_u64(data, TRADE_FEE_NUMERATOR_OFFSET),
+            trade_fee_denominator: read_u64(data, TRADE_FEE_DENOMINATOR_OFFSET),
+            last_price: read_u64(data, LAST_PRICE_OFFSET),
+            config_denominator: read_u64(data, CONFIG_DENOMINATOR_OFFSET),
+        })
+    }
+
+    /// Trade fee in basis points, 0 when the pool reports no denominator
+    pub fn fee_bps(&self) -> u64 {
+        if self.trade_fee_denominator == 0 {
+            return 0;
+        }
+        self.trade_fee_numerator.saturating_mul(10_000) / self.trade_fee_denominator
+    }
+}
+
+// Callers check the length up front
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An `Amm` account written field by field in the IDL's order, so the
+    /// offsets above are checked against the layout rather than echoed
+    struct AmmAccount {
+        token_a_account: Pubkey,
+        token_b_account: Pubkey,
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        oracle_main: Pubkey,
+        trade_fee: (u64, u64),
+        last_price: u64,
+        config_denominator: u64,
+    }
+
+    impl AmmAccount {
+        fn encode(&self) -> Vec<u8> {
+            let mut data = LifinityPoolInfo::DISCRIMINATOR.to_vec();
+            let key = |data: &mut Vec<u8>, key: Pubkey| data.extend_from_slice(key.as_ref());
+            key(&mut data, Pubkey::new_unique()); // initializer_key
+            key(&mut data, Pubkey::new_unique()); // initializer_deposit_token_account
+            key(&mut data, Pubkey::new_unique()); // initializer_receive_token_account
+            data.extend_from_slice(&7u64.to_le_bytes()); // initializer_amount
+            data.extend_from_slice(&9u64.to_le_bytes()); // taker_amount
+            data.extend_from_slice(&[1, 255, 0, 0, 0, 9]); // is_initialized, bump_seed, freeze flags, base_decimals
+            key(&mut data, spl_token::id()); // token_program_id
+            key(&mut data, self.token_a_account);
+            key(&mut data, self.token_b_account);
+            key(&mut data, Pubkey::new_unique()); // pool_mint
+            key(&mut data, self.token_a_mint);
+            key(&mut data, self.token_b_mint);
+            key(&mut data, Pubkey::new_unique()); // fee_account
+            key(&mut data, self.oracle_main);
+            key(&mut data, Pubkey::new_unique()); // oracle_sub_account
+            key(&mut data, Pubkey::new_unique()); // oracle_pc_account
+            // AmmFees: trade, owner trade, owner withdraw and host fee fractions
+            for value in [self.trade_fee.0, self.trade_fee.1, 1, 10_000, 0, 0, 0, 0] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            data.push(2); // AmmCurve::curve_type
+            data.extend_from_slice(&0u64.to_le_bytes()); // AmmCurve::curve_parameters
+            data.extend_from_slice(&self.last_price.to_le_bytes()); // AmmConfig::last_price
+            data.extend_from_slice(&(self.last_price + 1).to_le_bytes()); // last_balanced_price
+            data.extend_from_slice(&self.config_denominator.to_le_bytes());
+            data.resize(912, 0); // Rest of AmmConfig and padding
+            data
+        }
+    }
+
+    fn amm_account() -> AmmAccount {
+        AmmAccount {
+            token_a_account: Pubkey::new_unique(),
+            token_b_account: Pubkey::new_unique(),
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            oracle_main: Pubkey::new_unique(),
+            trade_fee: (2, 10_000),
+            last_price: 151_230_000,
+            config_denominator: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn decodes_an_amm_laid_out_in_idl_order() {
+        let amm = amm_account();
+        let info = LifinityPoolInfo::load_checked(&amm.encode()).unwrap();
+        assert_eq!(info.token_a_vault, amm.token_a_account);
+        assert_eq!(info.token_b_vault, amm.token_b_account);
+        assert_eq!(info.token_a_mint, amm.token_a_mint);
+        assert_eq!(info.token_b_mint, amm.token_b_mint);
+        assert_eq!(info.oracle_main, amm.oracle_main);
+        assert_eq!((info.trade_fee_numerator, info.trade_fee_denominator), (2, 10_000));
+        assert_eq!(info.last_price, 151_230_000);
+        assert_eq!(info.config_denominator, 1_000_000);
+        assert_eq!(info.fee_bps(), 2);
+    }
+
+    #[test]
+    fn rejects_a_foreign_or_truncated_account() {
+        let mut data = amm_account().encode();
+        assert!(LifinityPoolInfo::load_checked(&data[..CONFIG_DENOMINATOR_OFFSET + 7]).is_err());
+        data[0] ^= 1;
+        assert!(LifinityPoolInfo::load_checked(&data).is_err());
+    }
+
+    #[test]
+    fn fee_bps_is_zero_without_a_denominator() {
+        let mut amm = amm_account();
+        amm.trade_fee = (25, 0);
+        assert_eq!(LifinityPoolInfo::load_checked(&amm.encode()).unwrap().fee_bps(), 0);
+        amm.trade_fee = (3, 1_000);
+        assert_eq!(LifinityPoolInfo::load_checked(&amm.encode()).unwrap().fee_bps(), 30);
+    }
+}
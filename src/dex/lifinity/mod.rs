@@ -0,0 +1,10 @@
+pub mod info;
+
+pub use info::*;
+
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub fn lifinity_program_id() -> Pubkey {
+    Pubkey::from_str("2wT8Yq49kHgDzXuPxZSaeLaH1qbmGXtEyPy64bL7aD3c").unwrap()
+}
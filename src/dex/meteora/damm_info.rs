@@ -0,0 +1,238 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Meteora DAMM v1 (dynamic AMM) pool. Unlike v2, reserves are not held in
+/// token accounts owned by the pool: each side is a share of a Meteora vault,
+/// tracked by the pool's LP token account for that vault.
+pub struct MeteoraDammInfo {
+    pub lp_mint: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub a_vault: Pubkey,
+    pub b_vault: Pubkey,
+    pub a_vault_lp: Pubkey,
+    pub b_vault_lp: Pubkey,
+    pub enabled: bool,
+    pub admin_token_a_fee: Pubkey,
+    pub admin_token_b_fee: Pubkey,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+}
+
+impl MeteoraDammInfo {
+    /// Discriminator through `fees`
+    pub const MIN_LEN: usize = 362;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::MIN_LEN {
+            anyhow::bail!("Meteora DAMM pool data too short: {} < {}", data.len(), Self::MIN_LEN);
+        }
+        Ok(Self {
+            lp_mint: read_pubkey(data, 8),
+            token_a_mint: read_pubkey(data, 40),
+            token_b_mint: read_pubkey(data, 72),
+            a_vault: read_pubkey(data, 104),
+            b_vault: read_pubkey(data, 136),
+            a_vault_lp: read_pubkey(data, 168),
+            b_vault_lp: read_pubkey(data, 200),
+            enabled: data[233] != 0,
+            admin_token_a_fee: read_pubkey(data, 234),
+            admin_token_b_fee: read_pubkey(data, 266),
+            trade_fee_numerator: read_u64(data, 330),
+            trade_fee_denominator: read_u64(data, 338),
+        })
+    }
+
+    /// Trade fee in basis points, rounded up
+    pub fn fee_bps(&self) -> u64 {
+        if self.trade_fee_denominator == 0 {
+            return 0;
+        }
+        (self.trade_fee_numerator as u128 * 10_000).div_ceil(self.trade_fee_denominator as u128) as u64
+    }
+}
+
+/// Scale of `locked_profit_degradation`: the fraction of the last reported
+/// profit released per second
+pub const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+/// Meteora vault backing one side of a DAMM v1 pool
+pub struct MeteoraVaultInfo {
+    pub enabled: bool,
+    pub total_amount: u64,
+    pub token_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub last_updated_locked_profit: u64,
+    pub last_report: u64, // Unix seconds
+    pub locked_profit_degradation: u64,
+}
+
+impl MeteoraVaultInfo {
+    /// Discriminator through `locked_profit_tracker`
+    pub const MIN_LEN: usize = 1227;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::MIN_LEN {
+            anyhow::bail!("Meteora vault data too short: {} < {}", data.len(), Self::MIN_LEN);
+        }
+        Ok(Self {
+            enabled: data[8] != 0,
+            total_amount: read_u64(data, 11),
+            token_vault: read_pubkey(data, 19),
+            token_mint: read_pubkey(data, 83),
+            lp_mint: read_pubkey(data, 115),
+            // After the 30 strategies, base, admin and operator
+            last_updated_locked_profit: read_u64(data, 1203),
+            last_report: read_u64(data, 1211),
+            locked_profit_degradation: read_u64(data, 1219),
+        })
+    }
+
+    /// Profit from the last strategy report still being released at `now_unix`
+    pub fn locked_profit(&self, now_unix: u64) -> u64 {
+        let elapsed = now_unix.saturating_sub(self.last_report) as u128;
+        let released = elapsed.saturating_mul(self.locked_profit_degradation as u128);
+        if released > LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+            return 0;
+        }
+        (self.last_updated_locked_profit as u128 * (LOCKED_PROFIT_DEGRADATION_DENOMINATOR - released)
+            / LOCKED_PROFIT_DEGRADATION_DENOMINATOR) as u64
+    }
+
+    /// Tokens LP holders can withdraw at `now_unix`: the total less locked profit
+    pub fn unlocked_amount(&self, now_unix: u64) -> u64 {
+        self.total_amount.saturating_sub(self.locked_profit(now_unix))
+    }
+
+    /// Tokens of this vault attributable to `lp_amount` of its `lp_supply` at
+    /// `now_unix`, as the vault program values a withdrawal
+    pub fn share_of(&self, lp_amount: u64, lp_supply: u64, now_unix: u64) -> u64 {
+        if lp_supply == 0 {
+            return 0;
+        }
+        (self.unlocked_amount(now_unix) as u128 * lp_amount as u128 / lp_supply as u128) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+    const SIX_HOURS_DEGRADATION: u64 = 46_296_296; // Releases locked profit over 21,600 s
+
+    fn push_key(data: &mut Vec<u8>, key: Pubkey) {
+        data.extend_from_slice(key.as_ref());
+    }
+
+    /// A DAMM v1 `Pool` written field by field in the IDL's order, keys
+    /// `[lp_mint, token_a_mint, token_b_mint, a_vault, b_vault, a_vault_lp,
+    /// b_vault_lp, admin_token_a_fee, admin_token_b_fee]`
+    fn pool_account(keys: &[Pubkey; 9], enabled: bool, trade_fee: (u64, u64)) -> Vec<u8> {
+        let mut data = vec![241, 154, 109, 4, 17, 177, 109, 188]; // Anchor discriminator of `Pool`
+        for &key in &keys[..7] {
+            push_key(&mut data, key);
+        }
+        data.push(254); // a_vault_lp_bump
+        data.push(enabled as u8);
+        push_key(&mut data, keys[7]);
+        push_key(&mut data, keys[8]);
+        push_key(&mut data, Pubkey::new_unique()); // admin
+        // PoolFees: trade and protocol trade fee fractions
+        for value in [trade_fee.0, trade_fee.1, 20, 100] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize(944, 0); // pool_type, stake, bootstrapping, curve_type and padding
+        data
+    }
+
+    /// A Meteora `Vault` written field by field in the IDL's order
+    fn vault_account(total_amount: u64, token_vault: Pubkey, token_mint: Pubkey, lp_mint: Pubkey, locked_profit: u64) -> Vec<u8> {
+        let mut data = vec![211, 8, 232, 43, 2, 152, 117, 119]; // Anchor discriminator of `Vault`
+        data.extend_from_slice(&[1, 255, 254]); // enabled, vault_bump, token_vault_bump
+        data.extend_from_slice(&total_amount.to_le_bytes());
+        push_key(&mut data, token_vault);
+        push_key(&mut data, Pubkey::new_unique()); // fee_vault
+        push_key(&mut data, token_mint);
+        push_key(&mut data, lp_mint);
+        for _ in 0..30 {
+            push_key(&mut data, Pubkey::default()); // strategies
+        }
+        for _ in 0..3 {
+            push_key(&mut data, Pubkey::new_unique()); // base, admin, operator
+        }
+        // LockedProfitTracker, reported at NOW
+        for value in [locked_profit, NOW, SIX_HOURS_DEGRADATION] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn decodes_a_pool_laid_out_in_idl_order() {
+        let keys: [Pubkey; 9] = std::array::from_fn(|_| Pubkey::new_unique());
+        let info = MeteoraDammInfo::load_checked(&pool_account(&keys, true, (250, 100_000))).unwrap();
+        assert_eq!(
+            [info.lp_mint, info.token_a_mint, info.token_b_mint, info.a_vault, info.b_vault, info.a_vault_lp, info.b_vault_lp],
+            keys[..7]
+        );
+        assert_eq!((info.admin_token_a_fee, info.admin_token_b_fee), (keys[7], keys[8]));
+        assert!(info.enabled);
+        assert_eq!((info.trade_fee_numerator, info.trade_fee_denominator), (250, 100_000));
+        assert_eq!(info.fee_bps(), 25);
+
+        let disabled = MeteoraDammInfo::load_checked(&pool_account(&keys, false, (1, 3))).unwrap();
+        assert!(!disabled.enabled);
+        assert_eq!(disabled.fee_bps(), 3334); // Rounded up
+        assert!(MeteoraDammInfo::load_checked(&pool_account(&keys, true, (0, 0))[..MeteoraDammInfo::MIN_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn decodes_a_vault_laid_out_in_idl_order() {
+        let (token_vault, token_mint, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let data = vault_account(10_000_000, token_vault, token_mint, lp_mint, 1_000_000);
+        assert_eq!(data.len(), MeteoraVaultInfo::MIN_LEN);
+        let info = MeteoraVaultInfo::load_checked(&data).unwrap();
+        assert!(info.enabled);
+        assert_eq!(info.total_amount, 10_000_000);
+        assert_eq!((info.token_vault, info.token_mint, info.lp_mint), (token_vault, token_mint, lp_mint));
+        assert_eq!(
+            (info.last_updated_locked_profit, info.last_report, info.locked_profit_degradation),
+            (1_000_000, NOW, SIX_HOURS_DEGRADATION)
+        );
+        assert!(MeteoraVaultInfo::load_checked(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn locked_profit_is_released_linearly_after_a_report() {
+        let data = vault_account(10_000_000, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), 1_000_000);
+        let vault = MeteoraVaultInfo::load_checked(&data).unwrap();
+        assert_eq!(vault.locked_profit(NOW), 1_000_000);
+        assert_eq!(vault.locked_profit(NOW - 60), 1_000_000); // A clock behind the report
+        assert_eq!(vault.locked_profit(NOW + 10_800), 500_000);
+        assert_eq!(vault.locked_profit(NOW + 21_600), 0);
+        assert_eq!(vault.locked_profit(NOW + 86_400), 0);
+        assert_eq!(vault.unlocked_amount(NOW), 9_000_000);
+        assert_eq!(vault.unlocked_amount(NOW + 10_800), 9_500_000);
+    }
+
+    #[test]
+    fn a_share_is_valued_at_the_unlocked_amount_not_the_total() {
+        let data = vault_account(10_000_000, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), 1_000_000);
+        let vault = MeteoraVaultInfo::load_checked(&data).unwrap();
+        // A quarter of the supply right after a report: 9,000,000 withdrawable, not 10,000,000
+        assert_eq!(vault.share_of(250, 1_000, NOW), 2_250_000);
+        assert_eq!(vault.share_of(250, 1_000, NOW + 21_600), 2_500_000);
+        assert_eq!(vault.share_of(1, 3, NOW), 3_000_000);
+        assert_eq!(vault.share_of(250, 0, NOW), 0);
+    }
+}
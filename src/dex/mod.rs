@@ -2,9 +2,12 @@ pub mod byreal;
 pub mod futarchy;
 pub mod heaven;
 pub mod humidifi;
+pub mod lifinity;
 pub mod meteora;
 pub mod pancakeswap;
+pub mod phoenix;
 pub mod pump;
 pub mod raydium;
+pub mod selftest;
 pub mod vertigo;
 pub mod whirlpool;
@@ -0,0 +1,282 @@
+use anyhow::Result;
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+// Byte offsets for the Phoenix market account: a 576-byte `MarketHeader`
+// followed by the `FIFOMarket` and its bid and ask trees
+// Reference: phoenix-v1 program, src/program/accounts.rs and src/state/markets/fifo.rs
+const BIDS_SIZE_OFFSET: usize = 16;
+const ASKS_SIZE_OFFSET: usize = 24;
+const BASE_DECIMALS_OFFSET: usize = 40;
+const BASE_MINT_OFFSET: usize = 48;
+const BASE_VAULT_OFFSET: usize = 80;
+const BASE_LOT_SIZE_OFFSET: usize = 112;
+const QUOTE_DECIMALS_OFFSET: usize = 120;
+const QUOTE_MINT_OFFSET: usize = 128;
+const QUOTE_VAULT_OFFSET: usize = 160;
+const TICK_SIZE_OFFSET: usize = 200; // Quote atoms per base unit
+const RAW_BASE_UNITS_PER_BASE_UNIT_OFFSET: usize = 312;
+const HEADER_SIZE: usize = 576;
+const TAKER_FEE_BPS_OFFSET: usize = HEADER_SIZE + 280;
+const BIDS_OFFSET: usize = HEADER_SIZE + 304;
+
+// Red-black tree: root index, padding and the node allocator header, then
+// 64-byte nodes of four u32 registers (left, right, parent, color), the
+// order id (price in ticks, sequence number) and the resting order
+const TREE_HEADER_SIZE: usize = 32;
+const NODE_SIZE: usize = 64;
+const NODE_LEFT: usize = 0;
+const NODE_RIGHT: usize = 4;
+const NODE_PRICE_IN_TICKS: usize = 16;
+const NODE_BASE_LOTS: usize = 40;
+
+/// Levels on each side counted toward a market's visible depth
+pub const TOP_OF_BOOK_LEVELS: usize = 5;
+
+/// Resting size at one price, summed over its orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookLevel {
+    pub price_in_ticks: u64,
+    pub base_lots: u64,
+}
+
+pub struct PhoenixMarketInfo {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub base_decimals: u32,
+    pub quote_decimals: u32,
+    pub base_lot_size: u64,
+    pub tick_size_in_quote_atoms_per_base_unit: u64,
+    pub raw_base_units_per_base_unit: u32,
+    pub taker_fee_bps: u64,
+    pub bids: Vec<BookLevel>, // Best (highest) first
+    pub asks: Vec<BookLevel>, // Best (lowest) first
+}
+
+impl PhoenixMarketInfo {
+    /// Decode the header and both sides of the book. Order expiry is not
+    /// checked, so an order past its last valid slot still counts until the
+    /// program evicts it.
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < BIDS_OFFSET {
+            return Err(anyhow::anyhow!("Invalid data length for PhoenixMarketInfo"));
+        }
+
+        let bids_size = read_u64(data, BIDS_SIZE_OFFSET) as usize;
+        let asks_size = read_u64(data, ASKS_SIZE_OFFSET) as usize;
+        let bids_len = TREE_HEADER_SIZE + bids_size.saturating_mul(NODE_SIZE);
+        let asks_offset = BIDS_OFFSET + bids_len;
+        let asks_len = TREE_HEADER_SIZE + asks_size.saturating_mul(NODE_SIZE);
+        if data.len() < asks_offset + asks_len {
+            return Err(anyhow::anyhow!(
+                "Phoenix market data of {} bytes is too short for {} bids and {} asks",
+                data.len(),
+                bids_size,
+                asks_size
+            ));
+        }
+
+        let mut bids = read_levels(&data[BIDS_OFFSET..asks_offset], bids_size);
+        bids.reverse();
+        let asks = read_levels(&data[asks_offset..asks_offset + asks_len], asks_size);
+
+        Ok(Self {
+            base_mint: read_pubkey(data, BASE_MINT_OFFSET),
+            quote_mint: read_pubkey(data, QUOTE_MINT_OFFSET),
+            base_vault: read_pubkey(data, BASE_VAULT_OFFSET),
+            quote_vault: read_pubkey(data, QUOTE_VAULT_OFFSET),
+            base_decimals: read_u32(data, BASE_DECIMALS_OFFSET),
+            quote_decimals: read_u32(data, QUOTE_DECIMALS_OFFSET),
+            base_lot_size: read_u64(data, BASE_LOT_SIZE_OFFSET),
+            tick_size_in_quote_atoms_per_base_unit: read_u64(data, TICK_SIZE_OFFSET),
+            raw_base_units_per_base_unit: read_u32(data, RAW_BASE_UNITS_PER_BASE_UNIT_OFFSET),
+            taker_fee_bps: read_u64(data, TAKER_FEE_BPS_OFFSET),
+            bids,
+            asks,
+        })
+    }
+
+    /// Quote atoms per base atom at `price_in_ticks`
+    pub fn price(&self, price_in_ticks: u64) -> f64 {
+        let base_atoms_per_base_unit =
+            self.raw_base_units_per_base_unit.max(1) as f64 * 10f64.powi(self.base_decimals as i32);
+        price_in_ticks as f64 * self.tick_size_in_quote_atoms_per_base_unit as f64 / base_atoms_per_base_unit
+    }
+
+    pub fn best_bid(&self) -> Option<f64> {
+        self.bids.first().map(|level| self.price(level.price_in_ticks))
+    }
+
+    pub fn best_ask(&self) -> Option<f64> {
+        self.asks.first().map(|level| self.price(level.price_in_ticks))
+    }
+
+    /// Base atoms resting in the best `levels` levels of `side`
+    pub fn depth_base_atoms(&self, side: &[BookLevel], levels: usize) -> u64 {
+        side.iter()
+            .take(levels)
+            .map(|level| level.base_lots.saturating_mul(self.base_lot_size))
+            .fold(0u64, u64::saturating_add)
+    }
+
+    /// Quote atoms resting in the best `levels` levels of `side`
+    pub fn depth_quote_atoms(&self, side: &[BookLevel], levels: usize) -> f64 {
+        side.iter()
+            .take(levels)
+            .map(|level| level.base_lots.saturating_mul(self.base_lot_size) as f64 * self.price(level.price_in_ticks))
+            .sum()
+    }
+}
+
+/// Walk the tree from its root and sum the resting lots per price, lowest
+/// price first. The walk visits at most `capacity` nodes, so a corrupt tree
+/// cannot loop forever.
+fn read_levels(tree: &[u8], capacity: usize) -> Vec<BookLevel> {
+    let node_offset = |index: u32| TREE_HEADER_SIZE + (index as usize - 1) * NODE_SIZE;
+    let mut levels: BTreeMap<u64, u64> = BTreeMap::new();
+    let mut stack = vec![read_u32(tree, 0)];
+    let mut visited = 0;
+
+    while let Some(index) = stack.pop() {
+        if index == 0 || index as usize > capacity {
+            continue;
+        }
+        visited += 1;
+        if visited > capacity {
+            break;
+        }
+        let offset = node_offset(index);
+        let base_lots = read_u64(tree, offset + NODE_BASE_LOTS);
+        if base_lots > 0 {
+            *levels.entry(read_u64(tree, offset + NODE_PRICE_IN_TICKS)).or_default() += base_lots;
+        }
+        stack.push(read_u32(tree, offset + NODE_LEFT));
+        stack.push(read_u32(tree, offset + NODE_RIGHT));
+    }
+
+    levels
+        .into_iter()
+        .map(|(price_in_ticks, base_lots)| BookLevel { price_in_ticks, base_lots })
+        .collect()
+}
+
+// Callers check the length up front
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Resting order node of a hand-built tree: left and right children by
+/// 1-based index (0 for none), price in ticks and base lots
+#[cfg(test)]
+pub(crate) type TestNode = (u32, u32, u64, u64);
+
+/// A market account with 6-decimal base, 9-decimal quote, 1,000-atom lots
+/// and ticks, a 5 bps taker fee and the given trees, each rooted at node 1.
+/// A tick is then 0.001 quote atoms per base atom.
+#[cfg(test)]
+pub(crate) fn test_market_data(base_mint: Pubkey, quote_mint: Pubkey, bids: &[TestNode], asks: &[TestNode]) -> Vec<u8> {
+    fn tree(nodes: &[TestNode]) -> Vec<u8> {
+        let mut tree = vec![0u8; TREE_HEADER_SIZE + nodes.len() * NODE_SIZE];
+        if !nodes.is_empty() {
+            tree[0..4].copy_from_slice(&1u32.to_le_bytes());
+        }
+        for (i, &(left, right, price_in_ticks, base_lots)) in nodes.iter().enumerate() {
+            let node = TREE_HEADER_SIZE + i * NODE_SIZE;
+            tree[node + NODE_LEFT..node + NODE_LEFT + 4].copy_from_slice(&left.to_le_bytes());
+            tree[node + NODE_RIGHT..node + NODE_RIGHT + 4].copy_from_slice(&right.to_le_bytes());
+            tree[node + NODE_PRICE_IN_TICKS..node + NODE_PRICE_IN_TICKS + 8].copy_from_slice(&price_in_ticks.to_le_bytes());
+            tree[node + NODE_BASE_LOTS..node + NODE_BASE_LOTS + 8].copy_from_slice(&base_lots.to_le_bytes());
+        }
+        tree
+    }
+
+    let mut data = vec![0u8; BIDS_OFFSET];
+    let mut put = |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    put(BIDS_SIZE_OFFSET, &(bids.len() as u64).to_le_bytes());
+    put(ASKS_SIZE_OFFSET, &(asks.len() as u64).to_le_bytes());
+    put(BASE_DECIMALS_OFFSET, &6u32.to_le_bytes());
+    put(BASE_MINT_OFFSET, base_mint.as_ref());
+    put(BASE_LOT_SIZE_OFFSET, &1_000u64.to_le_bytes());
+    put(QUOTE_DECIMALS_OFFSET, &9u32.to_le_bytes());
+    put(QUOTE_MINT_OFFSET, quote_mint.as_ref());
+    put(TICK_SIZE_OFFSET, &1_000u64.to_le_bytes());
+    put(RAW_BASE_UNITS_PER_BASE_UNIT_OFFSET, &1u32.to_le_bytes());
+    put(TAKER_FEE_BPS_OFFSET, &5u64.to_le_bytes());
+    data.extend(tree(bids));
+    data.extend(tree(asks));
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Several levels deep, with two orders at 149, a filled order and a free
+    // slot that is not linked into the tree
+    const BIDS: [TestNode; 7] = [
+        (2, 3, 149, 40),
+        (4, 0, 148, 100),
+        (5, 6, 150, 10),
+        (0, 0, 140, 500),
+        (0, 0, 149, 25),
+        (0, 0, 151, 0),
+        (0, 0, 200, 999),
+    ];
+    const ASKS: [TestNode; 4] = [(2, 3, 153, 30), (0, 4, 152, 20), (0, 0, 160, 70), (0, 0, 152, 5)];
+
+    #[test]
+    fn reads_each_side_of_a_multi_level_book_best_first() {
+        let (base_mint, quote_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let info = PhoenixMarketInfo::load_checked(&test_market_data(base_mint, quote_mint, &BIDS, &ASKS)).unwrap();
+        assert_eq!((info.base_mint, info.quote_mint), (base_mint, quote_mint));
+        assert_eq!(info.taker_fee_bps, 5);
+
+        let level = |price_in_ticks, base_lots| BookLevel { price_in_ticks, base_lots };
+        assert_eq!(info.bids, vec![level(150, 10), level(149, 65), level(148, 100), level(140, 500)]);
+        assert_eq!(info.asks, vec![level(152, 25), level(153, 30), level(160, 70)]);
+        assert!((info.best_bid().unwrap() - 0.150).abs() < 1e-12);
+        assert!((info.best_ask().unwrap() - 0.152).abs() < 1e-12);
+
+        assert_eq!(info.depth_base_atoms(&info.bids, 2), 75_000);
+        assert_eq!(info.depth_base_atoms(&info.bids, TOP_OF_BOOK_LEVELS), 675_000);
+        let ask_depth = 25_000.0 * 0.152 + 30_000.0 * 0.153 + 70_000.0 * 0.160;
+        assert!((info.depth_quote_atoms(&info.asks, TOP_OF_BOOK_LEVELS) - ask_depth).abs() < 1e-6);
+    }
+
+    #[test]
+    fn an_empty_side_has_no_best_price() {
+        let info = PhoenixMarketInfo::load_checked(&test_market_data(Pubkey::new_unique(), Pubkey::new_unique(), &BIDS, &[])).unwrap();
+        assert!(info.best_ask().is_none());
+        assert_eq!(info.depth_base_atoms(&info.asks, TOP_OF_BOOK_LEVELS), 0);
+    }
+
+    #[test]
+    fn a_tree_that_links_back_to_itself_is_walked_at_most_capacity_times() {
+        let data = test_market_data(Pubkey::new_unique(), Pubkey::new_unique(), &[(1, 1, 150, 10)], &ASKS);
+        let info = PhoenixMarketInfo::load_checked(&data).unwrap();
+        assert_eq!(info.bids, vec![BookLevel { price_in_ticks: 150, base_lots: 10 }]);
+    }
+
+    #[test]
+    fn a_book_shorter_than_its_declared_trees_is_rejected() {
+        let data = test_market_data(Pubkey::new_unique(), Pubkey::new_unique(), &BIDS, &ASKS);
+        assert!(PhoenixMarketInfo::load_checked(&data[..data.len() - 1]).is_err());
+        assert!(PhoenixMarketInfo::load_checked(&data[..BIDS_OFFSET - 1]).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod market;
+
+pub use market::*;
+
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub fn phoenix_program_id() -> Pubkey {
+    Pubkey::from_str("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY").unwrap()
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use solana_program::pubkey::Pubkey;
+
+use super::constants::pump_bonding_curve_program_id;
+
+const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+
+// Byte offsets for the pump.fun `BondingCurve` account (Anchor, 8-byte discriminator)
+const VIRTUAL_TOKEN_RESERVES_OFFSET: usize = 8;
+const VIRTUAL_SOL_RESERVES_OFFSET: usize = 16;
+const REAL_TOKEN_RESERVES_OFFSET: usize = 24;
+const REAL_SOL_RESERVES_OFFSET: usize = 32;
+const TOKEN_TOTAL_SUPPLY_OFFSET: usize = 40;
+const COMPLETE_OFFSET: usize = 48;
+
+/// Tokens a new curve holds for sale; the curve completes once they are sold
+pub const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+/// Fee the bonding curve program takes in SOL on buys and sells
+pub const BONDING_CURVE_FEE_BPS: u64 = 100;
+
+#[derive(Debug)]
+pub struct BondingCurveInfo {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64, // Tokens left for sale before the curve completes
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool, // Graduated: trading moved to a PumpSwap pool
+}
+
+impl BondingCurveInfo {
+    pub const DISCRIMINATOR: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < COMPLETE_OFFSET + 1 {
+            return Err(anyhow::anyhow!("Invalid data length for BondingCurveInfo"));
+        }
+        if data[0..8] != Self::DISCRIMINATOR {
+            return Err(anyhow::anyhow!("Invalid discriminator for BondingCurveInfo"));
+        }
+
+        Ok(Self {
+            virtual_token_reserves: read_u64(data, VIRTUAL_TOKEN_RESERVES_OFFSET),
+            virtual_sol_reserves: read_u64(data, VIRTUAL_SOL_RESERVES_OFFSET),
+            real_token_reserves: read_u64(data, REAL_TOKEN_RESERVES_OFFSET),
+            real_sol_reserves: read_u64(data, REAL_SOL_RESERVES_OFFSET),
+            token_total_supply: read_u64(data, TOKEN_TOTAL_SUPPLY_OFFSET),
+            complete: data[COMPLETE_OFFSET] != 0,
+        })
+    }
+
+    /// Share of the tokens for sale already sold, in basis points
+    pub fn graduation_progress_bps(&self) -> u64 {
+        let remaining = self.real_token_reserves.min(INITIAL_REAL_TOKEN_RESERVES) as u128;
+        let sold = INITIAL_REAL_TOKEN_RESERVES as u128 - remaining;
+        (sold * 10_000 / INITIAL_REAL_TOKEN_RESERVES as u128) as u64
+    }
+}
+
+/// Bonding curve account of `mint`
+pub fn bonding_curve_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], &pump_bonding_curve_program_id()).0
+}
+
+// Callers check the length up front
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
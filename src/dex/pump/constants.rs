@@ -2,6 +2,7 @@ use solana_program::pubkey::Pubkey;
 use std::str::FromStr;
 
 pub const PUMP_PROGRAM_ID: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+pub const PUMP_BONDING_CURVE_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
 pub const PUMP_FEE_WALLET: &str = "JCRGumoE9Qi5BBgULTgdgTLjSgkCMSbF62ZZfGs84JeU";
 pub const PUMP_MAYHEM_FEE_WALLET: &str = "GesfTA3X2arioaHp8bbKdjG9vJtskViWACZoYvxp4twS";
 
@@ -9,6 +10,10 @@ pub fn pump_program_id() -> Pubkey {
     Pubkey::from_str(PUMP_PROGRAM_ID).unwrap()
 }
 
+pub fn pump_bonding_curve_program_id() -> Pubkey {
+    Pubkey::from_str(PUMP_BONDING_CURVE_PROGRAM_ID).unwrap()
+}
+
 pub fn pump_fee_wallet() -> Pubkey {
     Pubkey::from_str(PUMP_FEE_WALLET).unwrap()
 }
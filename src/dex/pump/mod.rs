@@ -1,5 +1,7 @@
 pub mod amm_info;
+pub mod bonding_curve;
 pub mod constants;
 
 pub use amm_info::PumpAmmInfo;
+pub use bonding_curve::*;
 pub use constants::*;
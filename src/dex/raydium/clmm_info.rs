@@ -103,7 +103,7 @@ pub struct PoolState {
 
 impl PoolState {
     pub fn load_checked(data: &[u8]) -> Result<Self> {
-        if data.len() < 8 + 32 + 32 + 32 + 32 + 32 + 32 + 4 + 2 {
+        if data.len() < 8 + 1 + 32 * 7 + 2 + 2 + 16 + 16 + 4 {
             return Err(anyhow::anyhow!(
                 "Invalid data length for RaydiumClmmPoolState"
             ));
@@ -146,6 +146,8 @@ impl PoolState {
         let observation_key = Pubkey::new_from_array(observation_key);
         offset += 32;
 
+        let mint_decimals_0 = data[offset];
+        let mint_decimals_1 = data[offset + 1];
         offset += 2;
 
         let mut tick_spacing_bytes = [0u8; 2];
@@ -153,15 +155,15 @@ impl PoolState {
         let tick_spacing = u16::from_le_bytes(tick_spacing_bytes);
         offset += 2;
 
+        let liquidity = u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
         offset += 16;
 
-        // Skip sqrt_price_x64
+        let sqrt_price_x64 = u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
         offset += 16;
 
         let mut tick_current_bytes = [0u8; 4];
         tick_current_bytes.copy_from_slice(&data[offset..offset + 4]);
         let tick_current = i32::from_le_bytes(tick_current_bytes);
-        offset += 4;
 
         Ok(Self {
             amm_config,
@@ -170,7 +172,11 @@ impl PoolState {
             token_vault_0,
             token_vault_1,
             observation_key,
+            mint_decimals_0,
+            mint_decimals_1,
             tick_spacing,
+            liquidity,
+            sqrt_price_x64,
             tick_current,
             ..Default::default()
         })
@@ -0,0 +1,475 @@
+use crate::dex::futarchy::FutarchyInfo;
+use crate::dex::heaven::HeavenPoolState;
+use crate::dex::humidifi::HumidifiInfo;
+use crate::dex::lifinity::LifinityPoolInfo;
+use crate::dex::meteora::damm_info::MeteoraDammInfo;
+use crate::dex::meteora::dammv2_info::MeteoraDAmmV2Info;
+use crate::dex::meteora::dlmm_info::DlmmInfo;
+use crate::dex::phoenix::PhoenixMarketInfo;
+use crate::dex::pump::PumpAmmInfo;
+use crate::dex::raydium::{PoolState, RaydiumAmmInfo, RaydiumCpAmmInfo};
+use crate::dex::vertigo::VertigoInfo;
+use crate::dex::whirlpool::state::Whirlpool;
+use crate::rpc::RpcLike;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+pub const DECODERS: [&str; 14] = [
+    "raydium_amm",
+    "raydium_cp",
+    "raydium_clmm",
+    "pump_amm",
+    "meteora_dlmm",
+    "meteora_damm",
+    "meteora_damm_v2",
+    "whirlpool",
+    "vertigo",
+    "heaven",
+    "futarchy",
+    "humidifi",
+    "lifinity",
+    "phoenix",
+];
+
+/// `source` prefix of a fixture written at the decoder's own offsets rather
+/// than captured; a capture of the same decoder supersedes it
+pub const LAYOUT_ENCODED_SOURCE: &str = "layout-encoded";
+
+/// A raw pool account snapshot and the mints/vaults it is known to contain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoderFixture {
+    pub decoder: String,
+    pub account: String,
+    #[serde(default)]
+    pub source: String, // Where the snapshot came from: a mainnet capture or an account encoded from the layout
+    pub data_base58: String,
+    pub expected: BTreeMap<String, String>,
+}
+
+/// A mainnet pool and the mints/vaults it is published to hold. The values
+/// come from the venue, never from these decoders, so a capture checks the
+/// decoder instead of restating it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownAccount {
+    pub decoder: String,
+    pub account: String,
+    pub source: String, // Where the expected values were published
+    pub expected: BTreeMap<String, String>,
+}
+
+/// Mint and vault fields extracted by the named decoder
+pub fn decode_fields(decoder: &str, account: &Pubkey, data: &[u8]) -> Result<BTreeMap<String, Pubkey>> {
+    let fields: Vec<(&str, Pubkey)> = match decoder {
+        "raydium_amm" => {
+            let info = RaydiumAmmInfo::load_checked(data)?;
+            vec![
+                ("coin_mint", info.coin_mint),
+                ("pc_mint", info.pc_mint),
+                ("coin_vault", info.coin_vault),
+                ("pc_vault", info.pc_vault),
+            ]
+        }
+        "raydium_cp" => {
+            let info = RaydiumCpAmmInfo::load_checked(data)?;
+            vec![
+                ("token_0_mint", info.token_0_mint),
+                ("token_1_mint", info.token_1_mint),
+                ("token_0_vault", info.token_0_vault),
+                ("token_1_vault", info.token_1_vault),
+            ]
+        }
+        "raydium_clmm" => {
+            let info = PoolState::load_checked(data)?;
+            vec![
+                ("token_mint_0", info.token_mint_0),
+                ("token_mint_1", info.token_mint_1),
+                ("token_vault_0", info.token_vault_0),
+                ("token_vault_1", info.token_vault_1),
+            ]
+        }
+        "pump_amm" => {
+            let info = PumpAmmInfo::load_checked(data)?;
+            vec![
+                ("base_mint", info.base_mint),
+                ("quote_mint", info.quote_mint),
+                ("pool_base_token_account", info.pool_base_token_account),
+                ("pool_quote_token_account", info.pool_quote_token_account),
+            ]
+        }
+        "meteora_dlmm" => {
+            let info = DlmmInfo::load_checked(data)?;
+            vec![
+                ("token_x_mint", info.token_x_mint),
+                ("token_y_mint", info.token_y_mint),
+                ("token_x_vault", info.token_x_vault),
+                ("token_y_vault", info.token_y_vault),
+            ]
+        }
+        "meteora_damm" => {
+            let info = MeteoraDammInfo::load_checked(data)?;
+            vec![
+                ("token_a_mint", info.token_a_mint),
+                ("token_b_mint", info.token_b_mint),
+                ("a_vault", info.a_vault),
+                ("b_vault", info.b_vault),
+                ("a_vault_lp", info.a_vault_lp),
+                ("b_vault_lp", info.b_vault_lp),
+            ]
+        }
+        "meteora_damm_v2" => {
+            let info = MeteoraDAmmV2Info::load_checked(data)?;
+            vec![
+                ("base_mint", info.base_mint),
+                ("quote_mint", info.quote_mint),
+                ("base_vault", info.base_vault),
+                ("quote_vault", info.quote_vault),
+            ]
+        }
+        "whirlpool" => {
+            let info = Whirlpool::try_deserialize(data)?;
+            vec![
+                ("token_mint_a", info.token_mint_a),
+                ("token_mint_b", info.token_mint_b),
+                ("token_vault_a", info.token_vault_a),
+                ("token_vault_b", info.token_vault_b),
+            ]
+        }
+        "vertigo" => {
+            let info = VertigoInfo::load_checked(data, account)?;
+            vec![("mint_a", info.mint_a), ("mint_b", info.mint_b)]
+        }
+        "heaven" => {
+            let info = HeavenPoolState::parse(data).ok_or_else(|| anyhow::anyhow!("Failed to parse Heaven pool"))?;
+            vec![
+                ("mint_a", info.mint_a),
+                ("mint_b", info.mint_b),
+                ("vault_a", info.vault_a),
+                ("vault_b", info.vault_b),
+            ]
+        }
+        "futarchy" => {
+            let info = FutarchyInfo::load_checked(data)?;
+            vec![
+                ("base_mint", info.base_mint),
+                ("quote_mint", info.quote_mint),
+                ("base_vault", info.base_vault),
+                ("quote_vault", info.quote_vault),
+            ]
+        }
+        "humidifi" => {
+            let info = HumidifiInfo::load_checked(data)?;
+            vec![
+                ("base_mint", info.base_mint),
+                ("quote_mint", info.quote_mint),
+                ("base_vault", info.base_vault),
+                ("quote_vault", info.quote_vault),
+            ]
+        }
+        "lifinity" => {
+            let info = LifinityPoolInfo::load_checked(data)?;
+            vec![
+                ("token_a_mint", info.token_a_mint),
+                ("token_b_mint", info.token_b_mint),
+                ("token_a_vault", info.token_a_vault),
+                ("token_b_vault", info.token_b_vault),
+                ("oracle_main", info.oracle_main),
+            ]
+        }
+        "phoenix" => {
+            let info = PhoenixMarketInfo::load_checked(data)?;
+            vec![
+                ("base_mint", info.base_mint),
+                ("quote_mint", info.quote_mint),
+                ("base_vault", info.base_vault),
+                ("quote_vault", info.quote_vault),
+            ]
+        }
+        other => anyhow::bail!("Unknown decoder {}, expected one of {:?}", other, DECODERS),
+    };
+    Ok(fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+}
+
+/// Decode `fixture` and list every field that differs from its known-good value
+pub fn check_fixture(fixture: &DecoderFixture) -> Result<Vec<String>> {
+    let account = fixture
+        .account
+        .parse::<Pubkey>()
+        .map_err(|e| anyhow::anyhow!("Invalid fixture account {}: {}", fixture.account, e))?;
+    let data = bs58::decode(&fixture.data_base58)
+        .into_vec()
+        .context(format!("Invalid fixture data for {}", fixture.account))?;
+    let decoded = decode_fields(&fixture.decoder, &account, &data)?;
+
+    let mut mismatches = Vec::new();
+    for (field, expected) in &fixture.expected {
+        match decoded.get(field) {
+            Some(actual) if actual.to_string() == *expected => {}
+            Some(actual) => mismatches.push(format!("{}: expected {}, decoded {}", field, expected, actual)),
+            None => mismatches.push(format!("{}: not produced by decoder", field)),
+        }
+    }
+    Ok(mismatches)
+}
+
+pub async fn load_fixtures(path: &str) -> Result<Vec<DecoderFixture>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .context(format!("Failed to read decoder fixtures {}", path))?;
+    serde_json::from_str(&content).context(format!("Failed to parse decoder fixtures {}", path))
+}
+
+/// Decode every bundled account snapshot and fail if any extracted mint or
+/// vault drifted from its known-good value. A missing fixtures file only warns.
+pub async fn decoder_selftest(fixtures_path: &str) -> Result<()> {
+    if !Path::new(fixtures_path).exists() {
+        warn!("⚠️ Decoder fixtures {} not found, skipping decoder self-test", fixtures_path);
+        return Ok(());
+    }
+
+    let fixtures = load_fixtures(fixtures_path).await?;
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        match check_fixture(fixture) {
+            Ok(mismatches) if mismatches.is_empty() => {}
+            Ok(mismatches) => failures.push(format!(
+                "{} decoder on {}: {}",
+                fixture.decoder,
+                fixture.account,
+                mismatches.join("; ")
+            )),
+            Err(e) => failures.push(format!("{} decoder on {}: {}", fixture.decoder, fixture.account, e)),
+        }
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!(
+            "Decoder self-test failed, account layout may have drifted:\n  {}",
+            failures.join("\n  ")
+        );
+    }
+    info!("✅ Decoder self-test passed ({} fixtures)", fixtures.len());
+    Ok(())
+}
+
+pub async fn load_known_accounts(path: &str) -> Result<Vec<KnownAccount>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .context(format!("Failed to read known decoder accounts {}", path))?;
+    serde_json::from_str(&content).context(format!("Failed to parse known decoder accounts {}", path))
+}
+
+/// `capture-decoder-fixture` subcommand: snapshot every known account (or
+/// just `only`) into the fixtures file
+pub async fn run_capture_decoder_fixtures(rpc_url: &str, fixtures_path: &str, accounts_path: &str, only: Option<&str>) -> Result<()> {
+    let rpc_client = RpcClient::new(rpc_url.to_string());
+    capture_decoder_fixtures(&rpc_client, fixtures_path, accounts_path, only).await?;
+    Ok(())
+}
+
+/// Fetch each known account and store it as a fixture expecting the known
+/// values. Nothing is written if any decoder disagrees with them, since that
+/// is the drift the self-test exists to catch. A capture replaces the
+/// layout-encoded fixture of its decoder. Returns the number captured.
+pub async fn capture_decoder_fixtures(
+    rpc_client: &dyn RpcLike,
+    fixtures_path: &str,
+    accounts_path: &str,
+    only: Option<&str>,
+) -> Result<usize> {
+    let known = load_known_accounts(accounts_path).await?;
+    let selected: Vec<&KnownAccount> = known.iter().filter(|k| only.is_none_or(|account| k.account == account)).collect();
+    if selected.is_empty() {
+        anyhow::bail!("No known account {} in {}", only.unwrap_or("at all"), accounts_path);
+    }
+
+    let mut captured = Vec::new();
+    for known in selected {
+        let pubkey = known
+            .account
+            .parse::<Pubkey>()
+            .map_err(|e| anyhow::anyhow!("Invalid account {}: {}", known.account, e))?;
+        let response = rpc_client
+            .get_account_with_commitment(&pubkey, CommitmentConfig::confirmed())
+            .context(format!("Failed to fetch account {}", known.account))?;
+        let account = response.value.ok_or_else(|| anyhow::anyhow!("Account {} not found", known.account))?;
+        let fixture = DecoderFixture {
+            decoder: known.decoder.clone(),
+            account: known.account.clone(),
+            source: format!("mainnet getAccountInfo at slot {}; expected values: {}", response.context.slot, known.source),
+            data_base58: bs58::encode(&account.data).into_string(),
+            expected: known.expected.clone(),
+        };
+        let mismatches = check_fixture(&fixture)?;
+        if !mismatches.is_empty() {
+            anyhow::bail!(
+                "{} decoder disagrees with the known values of {}, layout may have drifted: {}",
+                known.decoder,
+                known.account,
+                mismatches.join("; ")
+            );
+        }
+        info!("   {} {} at slot {}", known.decoder, known.account, response.context.slot);
+        captured.push(fixture);
+    }
+
+    let mut fixtures = if Path::new(fixtures_path).exists() {
+        load_fixtures(fixtures_path).await?
+    } else {
+        Vec::new()
+    };
+    for fixture in &captured {
+        fixtures.retain(|f| {
+            f.account != fixture.account && !(f.decoder == fixture.decoder && f.source.starts_with(LAYOUT_ENCODED_SOURCE))
+        });
+    }
+    let count = captured.len();
+    fixtures.extend(captured);
+    crate::persist::atomic_write_json(fixtures_path, &fixtures).await?;
+    info!("💾 Captured {} decoder fixtures into {}", count, fixtures_path);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLED: &str = include_str!("../../fixtures/decoders.json");
+
+    fn bundled() -> Vec<DecoderFixture> {
+        serde_json::from_str(BUNDLED).unwrap()
+    }
+
+    #[test]
+    fn every_decoder_has_a_bundled_fixture() {
+        let fixtures = bundled();
+        for decoder in DECODERS {
+            assert!(fixtures.iter().any(|f| f.decoder == decoder), "no fixture for {}", decoder);
+        }
+        assert!(fixtures.iter().all(|f| !f.source.is_empty()), "every fixture says where it came from");
+    }
+
+    #[test]
+    fn bundled_fixtures_decode_to_their_expected_mints_and_vaults() {
+        for fixture in bundled() {
+            assert!(!fixture.expected.is_empty(), "{} fixture asserts nothing", fixture.decoder);
+            let mismatches = check_fixture(&fixture).unwrap();
+            assert!(mismatches.is_empty(), "{} decoder: {:?}", fixture.decoder, mismatches);
+        }
+    }
+
+    #[test]
+    fn a_shifted_layout_is_reported_field_by_field() {
+        for mut fixture in bundled() {
+            // One extra byte up front moves every field, as a layout change would
+            let mut data = bs58::decode(&fixture.data_base58).into_vec().unwrap();
+            data.insert(0, 0);
+            fixture.data_base58 = bs58::encode(&data).into_string();
+            // Decoders that check a discriminator or an exact size reject it outright
+            if let Ok(mismatches) = check_fixture(&fixture) {
+                assert!(!mismatches.is_empty(), "{} decoder missed the shift", fixture.decoder);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn the_selftest_fails_fast_on_a_drifted_layout() {
+        let path = std::env::temp_dir().join(format!("{}-decoders.json", Pubkey::new_unique()));
+        let path = path.to_str().unwrap().to_string();
+        let mut fixtures = bundled();
+        decoder_selftest(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/decoders.json")).await.unwrap();
+
+        let fixture = fixtures.iter_mut().find(|f| f.decoder == "raydium_amm").unwrap();
+        fixture.expected.insert("coin_vault".to_string(), Pubkey::new_unique().to_string());
+        crate::persist::atomic_write_json(&path, &fixtures).await.unwrap();
+        let error = decoder_selftest(&path).await.unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("layout may have drifted"), "{}", error);
+        assert!(error.contains("raydium_amm decoder"), "{}", error);
+        assert!(error.contains("coin_vault: expected"), "{}", error);
+    }
+
+    #[test]
+    fn known_accounts_name_a_decoder_and_real_pubkeys() {
+        let known: Vec<KnownAccount> = serde_json::from_str(include_str!("../../fixtures/decoder_accounts.json")).unwrap();
+        assert!(!known.is_empty());
+        for account in &known {
+            assert!(DECODERS.contains(&account.decoder.as_str()), "unknown decoder {}", account.decoder);
+            assert!(account.account.parse::<Pubkey>().is_ok(), "{}", account.account);
+            assert!(!account.source.is_empty() && !account.expected.is_empty(), "{}", account.account);
+            for value in account.expected.values() {
+                assert!(value.parse::<Pubkey>().is_ok(), "{}: {}", account.account, value);
+            }
+        }
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("{}_{}", Pubkey::new_unique(), name)).to_str().unwrap().to_string()
+    }
+
+    /// The bundled raydium_amm snapshot served at its account, and a
+    /// manifest holding it with `expected`
+    async fn capture_setup(expected: BTreeMap<String, String>) -> (crate::rpc::MockRpc, String, String) {
+        use solana_sdk::account::Account;
+
+        let fixture = bundled().into_iter().find(|f| f.decoder == "raydium_amm").unwrap();
+        let rpc = crate::rpc::MockRpc::new().with_slot(250_000_000);
+        let data = bs58::decode(&fixture.data_base58).into_vec().unwrap();
+        let owner = Pubkey::new_unique();
+        rpc.set_account(fixture.account.parse().unwrap(), Account { lamports: 1, data, owner, executable: false, rent_epoch: 0 });
+
+        let (fixtures_path, accounts_path) = (temp_path("decoders.json"), temp_path("decoder_accounts.json"));
+        crate::persist::atomic_write_json(&fixtures_path, &bundled()).await.unwrap();
+        let known = vec![KnownAccount {
+            decoder: fixture.decoder,
+            account: fixture.account,
+            source: "test manifest".to_string(),
+            expected,
+        }];
+        crate::persist::atomic_write_json(&accounts_path, &known).await.unwrap();
+        (rpc, fixtures_path, accounts_path)
+    }
+
+    #[tokio::test]
+    async fn a_capture_expects_the_known_values_and_replaces_the_layout_encoded_fixture() {
+        let bundled_amm = bundled().into_iter().find(|f| f.decoder == "raydium_amm").unwrap();
+        let mut expected = bundled_amm.expected.clone();
+        expected.remove("coin_vault"); // Only what the manifest knows is asserted
+        let (rpc, fixtures_path, accounts_path) = capture_setup(expected.clone()).await;
+
+        assert_eq!(capture_decoder_fixtures(&rpc, &fixtures_path, &accounts_path, None).await.unwrap(), 1);
+        let fixtures = load_fixtures(&fixtures_path).await.unwrap();
+        let amm: Vec<&DecoderFixture> = fixtures.iter().filter(|f| f.decoder == "raydium_amm").collect();
+        assert_eq!(amm.len(), 1);
+        assert!(amm[0].source.starts_with("mainnet getAccountInfo at slot 250000000; expected values: test manifest"), "{}", amm[0].source);
+        assert_eq!(amm[0].expected, expected);
+        assert_eq!(amm[0].data_base58, bundled_amm.data_base58);
+        // The other decoders keep their fixtures
+        assert_eq!(fixtures.len(), DECODERS.len());
+        decoder_selftest(&fixtures_path).await.unwrap();
+
+        let error = capture_decoder_fixtures(&rpc, &fixtures_path, &accounts_path, Some("11111111111111111111111111111111")).await.unwrap_err();
+        assert!(error.to_string().contains("No known account"), "{}", error);
+        std::fs::remove_file(&fixtures_path).unwrap();
+        std::fs::remove_file(&accounts_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_capture_the_decoder_disagrees_with_writes_nothing() {
+        let mut expected = bundled().into_iter().find(|f| f.decoder == "raydium_amm").unwrap().expected;
+        expected.insert("pc_vault".to_string(), Pubkey::new_unique().to_string());
+        let (rpc, fixtures_path, accounts_path) = capture_setup(expected).await;
+        let before = std::fs::read_to_string(&fixtures_path).unwrap();
+
+        let error = capture_decoder_fixtures(&rpc, &fixtures_path, &accounts_path, None).await.unwrap_err().to_string();
+        assert!(error.contains("raydium_amm decoder disagrees") && error.contains("pc_vault: expected"), "{}", error);
+        assert_eq!(std::fs::read_to_string(&fixtures_path).unwrap(), before);
+        std::fs::remove_file(&fixtures_path).unwrap();
+        std::fs::remove_file(&accounts_path).unwrap();
+    }
+}
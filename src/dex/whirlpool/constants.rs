@@ -4,6 +4,8 @@ use std::str::FromStr;
 pub const WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
 pub const MAX_TICK_INDEX: i32 = 443636;
 pub const MIN_TICK_INDEX: i32 = -443636;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055; // At MAX_TICK_INDEX
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016; // At MIN_TICK_INDEX
 
 pub fn whirlpool_program_id() -> Pubkey {
     Pubkey::from_str(WHIRLPOOL_PROGRAM_ID).unwrap()
@@ -0,0 +1,404 @@
+//! The Whirlpool program's swap math: Q64.64 sqrt prices, amounts rounded
+//! the way the program rounds them, so a quote returns what the same swap
+//! returns on-chain. `None` wherever the program would fail the swap.
+
+use crate::dex::whirlpool::constants::{MAX_SQRT_PRICE_X64, MAX_TICK_INDEX, MIN_SQRT_PRICE_X64, MIN_TICK_INDEX};
+
+/// `fee_rate` is in hundredths of a basis point
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+const MASK_64: u128 = u64::MAX as u128;
+
+/// Q32.96 `sqrt(1.0001)^(2^i)`, floored; the factors of a positive tick
+const POSITIVE_TICK_FACTORS: [u128; 19] = [
+    79232123823359799118286999567,
+    79236085330515764027303304731,
+    79244008939048815603706035061,
+    79259858533276714757314932305,
+    79291567232598584799939703904,
+    79355022692464371645785046466,
+    79482085999252804386437311141,
+    79736823300114093921829183326,
+    80248749790819932309965073892,
+    81282483887344747381513967011,
+    83390072131320151908154831281,
+    87770609709833776024991924138,
+    97234110755111693312479820773,
+    119332217159966728226237229890,
+    179736315981702064433883588727,
+    407748233172238350107850275304,
+    2098478828474011932436660412517,
+    55581415166113811149459800483533,
+    38992368544603139932233054999993551,
+];
+
+/// Q64.64 `1 / sqrt(1.0001)^(2^i)`, floored; the factors of a negative tick
+const NEGATIVE_TICK_FACTORS: [u128; 19] = [
+    18445821805675392311,
+    18444899583751176498,
+    18443055278223354162,
+    18439367220385604838,
+    18431993317065449817,
+    18417254355718160513,
+    18387811781193591352,
+    18329067761203520168,
+    18212142134806087854,
+    17980523815641551639,
+    17526086738831147013,
+    16651378430235024244,
+    15030750278693429944,
+    12247334978882834399,
+    8131365268884726200,
+    3584323654723342297,
+    696457651847595233,
+    26294789957452057,
+    37481735321082,
+];
+
+/// Q64.64 sqrt price at `tick`, clamped to the tick range
+pub fn sqrt_price_from_tick_index(tick: i32) -> u128 {
+    let tick = tick.clamp(MIN_TICK_INDEX, MAX_TICK_INDEX);
+    let bits = tick.unsigned_abs();
+    if tick >= 0 {
+        let mut ratio = if bits & 1 != 0 { POSITIVE_TICK_FACTORS[0] } else { 1u128 << 96 };
+        for (bit, factor) in POSITIVE_TICK_FACTORS.iter().enumerate().skip(1) {
+            if bits & (1 << bit) != 0 {
+                ratio = U256::mul(ratio, *factor).shr(96).lo;
+            }
+        }
+        ratio >> 32
+    } else {
+        let mut ratio = if bits & 1 != 0 { NEGATIVE_TICK_FACTORS[0] } else { 1u128 << 64 };
+        for (bit, factor) in NEGATIVE_TICK_FACTORS.iter().enumerate().skip(1) {
+            if bits & (1 << bit) != 0 {
+                ratio = (ratio * factor) >> 64;
+            }
+        }
+        ratio
+    }
+}
+
+/// Token A moved between two sqrt prices: `L * (upper - lower) / (upper * lower)`
+pub fn get_amount_delta_a(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    let (lower, upper) = (sqrt_price_0.min(sqrt_price_1), sqrt_price_0.max(sqrt_price_1));
+    if liquidity == 0 || lower == upper {
+        return Some(0);
+    }
+    let numerator = U256::mul(liquidity, upper - lower).checked_shl(64)?;
+    let denominator = U256::mul(upper, lower);
+    let (quotient, remainder) = numerator.div_rem(denominator)?;
+    let quotient = quotient.as_u128()? + (round_up && remainder != U256::ZERO) as u128;
+    u64::try_from(quotient).ok()
+}
+
+/// Token B moved between two sqrt prices: `L * (upper - lower)`
+pub fn get_amount_delta_b(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    let (lower, upper) = (sqrt_price_0.min(sqrt_price_1), sqrt_price_0.max(sqrt_price_1));
+    let product = U256::mul(liquidity, upper - lower);
+    let amount = product.shr(64).as_u128()? + (round_up && product.lo & MASK_64 != 0) as u128;
+    u64::try_from(amount).ok()
+}
+
+/// Sqrt price after `amount` of the input (or output) token moves through
+/// `liquidity`, rounded so the pool never gives away more than it takes
+pub fn get_next_sqrt_price(sqrt_price: u128, liquidity: u128, amount: u64, amount_specified_is_input: bool, a_to_b: bool) -> Option<u128> {
+    if amount_specified_is_input == a_to_b {
+        next_sqrt_price_from_a_round_up(sqrt_price, liquidity, amount, amount_specified_is_input)
+    } else {
+        next_sqrt_price_from_b_round_down(sqrt_price, liquidity, amount, amount_specified_is_input)
+    }
+}
+
+/// `L * p / (L ± amount * p)`, rounded up
+fn next_sqrt_price_from_a_round_up(sqrt_price: u128, liquidity: u128, amount: u64, add: bool) -> Option<u128> {
+    if amount == 0 {
+        return Some(sqrt_price);
+    }
+    let product = U256::mul(sqrt_price, amount as u128);
+    let numerator = U256::mul(liquidity, sqrt_price).checked_shl(64)?;
+    let liquidity_x64 = U256::from(liquidity).checked_shl(64)?;
+    let denominator = if add { liquidity_x64.checked_add(product)? } else { liquidity_x64.checked_sub(product)? };
+    let (quotient, remainder) = numerator.div_rem(denominator)?;
+    let next = quotient.as_u128()?.checked_add((remainder != U256::ZERO) as u128)?;
+    (MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&next).then_some(next)
+}
+
+/// `p ± amount / L`, with the price move rounded down
+fn next_sqrt_price_from_b_round_down(sqrt_price: u128, liquidity: u128, amount: u64, add: bool) -> Option<u128> {
+    if liquidity == 0 {
+        return None;
+    }
+    let amount_x64 = (amount as u128) << 64;
+    // Rounding the delta up when subtracting keeps the next price low
+    let delta = amount_x64 / liquidity + (!add && !amount_x64.is_multiple_of(liquidity)) as u128;
+    let next = if add { sqrt_price.checked_add(delta)? } else { sqrt_price.checked_sub(delta)? };
+    (MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&next).then_some(next)
+}
+
+/// One exact-input step toward `sqrt_price_target` within a liquidity range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub amount_in: u64, // Fee excluded
+    pub amount_out: u64,
+    pub next_sqrt_price: u128,
+    pub fee_amount: u64,
+}
+
+/// Swap up to `amount_remaining` (fee included) from `sqrt_price_current`
+/// toward `sqrt_price_target`, stopping early once the input runs out. A
+/// step that reaches its target pays the fee on what it used; one that
+/// stops short keeps all of the remainder as fee, as the program does.
+pub fn compute_swap_step_exact_in(
+    amount_remaining: u64,
+    fee_rate: u16,
+    liquidity: u128,
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    a_to_b: bool,
+) -> Option<SwapStep> {
+    let fee_rate = fee_rate as u64;
+    let amount_in_to = |next: u128| {
+        if a_to_b {
+            get_amount_delta_a(sqrt_price_current, next, liquidity, true)
+        } else {
+            get_amount_delta_b(sqrt_price_current, next, liquidity, true)
+        }
+    };
+    let net_remaining = (amount_remaining as u128 * (FEE_RATE_DENOMINATOR - fee_rate) as u128 / FEE_RATE_DENOMINATOR as u128) as u64;
+    // Moving to the target may need more than a u64, in which case it is out of reach
+    let to_target = amount_in_to(sqrt_price_target);
+    let next_sqrt_price = match to_target {
+        Some(amount) if amount <= net_remaining => sqrt_price_target,
+        _ => get_next_sqrt_price(sqrt_price_current, liquidity, net_remaining, true, a_to_b)?,
+    };
+    let reached = next_sqrt_price == sqrt_price_target;
+    let amount_in = match to_target {
+        Some(amount) if reached => amount,
+        _ => amount_in_to(next_sqrt_price)?,
+    };
+    let amount_out = if a_to_b {
+        get_amount_delta_b(sqrt_price_current, next_sqrt_price, liquidity, false)?
+    } else {
+        get_amount_delta_a(sqrt_price_current, next_sqrt_price, liquidity, false)?
+    };
+    let fee_amount = if reached {
+        let denominator = (FEE_RATE_DENOMINATOR - fee_rate) as u128;
+        u64::try_from((amount_in as u128 * fee_rate as u128).div_ceil(denominator)).ok()?
+    } else {
+        amount_remaining.checked_sub(amount_in)?
+    };
+    Some(SwapStep { amount_in, amount_out, next_sqrt_price, fee_amount })
+}
+
+/// Unsigned 256-bit integer, just wide enough for the products the swap
+/// math divides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct U256 {
+    hi: u128, // Compared first
+    lo: u128,
+}
+
+impl U256 {
+    const ZERO: Self = Self { hi: 0, lo: 0 };
+
+    fn from(lo: u128) -> Self {
+        Self { hi: 0, lo }
+    }
+
+    /// Full product of two `u128`s
+    fn mul(a: u128, b: u128) -> Self {
+        let (a_hi, a_lo) = (a >> 64, a & MASK_64);
+        let (b_hi, b_lo) = (b >> 64, b & MASK_64);
+        let (lo_lo, hi_lo, lo_hi, hi_hi) = (a_lo * b_lo, a_hi * b_lo, a_lo * b_hi, a_hi * b_hi);
+        let middle = (lo_lo >> 64) + (hi_lo & MASK_64) + (lo_hi & MASK_64);
+        Self {
+            hi: hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64),
+            lo: (middle << 64) | (lo_lo & MASK_64),
+        }
+    }
+
+    fn bits(self) -> u32 {
+        if self.hi != 0 {
+            256 - self.hi.leading_zeros()
+        } else {
+            128 - self.lo.leading_zeros()
+        }
+    }
+
+    fn as_u128(self) -> Option<u128> {
+        (self.hi == 0).then_some(self.lo)
+    }
+
+    fn shl(self, n: u32) -> Self {
+        match n {
+            0 => self,
+            1..=127 => Self { hi: (self.hi << n) | (self.lo >> (128 - n)), lo: self.lo << n },
+            128..=255 => Self { hi: self.lo << (n - 128), lo: 0 },
+            _ => Self::ZERO,
+        }
+    }
+
+    fn shr(self, n: u32) -> Self {
+        match n {
+            0 => self,
+            1..=127 => Self { hi: self.hi >> n, lo: (self.lo >> n) | (self.hi << (128 - n)) },
+            128..=255 => Self { hi: 0, lo: self.hi >> (n - 128) },
+            _ => Self::ZERO,
+        }
+    }
+
+    fn checked_shl(self, n: u32) -> Option<Self> {
+        (self.bits() + n <= 256).then(|| self.shl(n))
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        let (lo, carry) = self.lo.overflowing_add(other.lo);
+        let hi = self.hi.checked_add(other.hi)?.checked_add(carry as u128)?;
+        Some(Self { hi, lo })
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        let hi = self.hi.checked_sub(other.hi)?.checked_sub(borrow as u128)?;
+        Some(Self { hi, lo })
+    }
+
+    /// Quotient and remainder by shift-and-subtract; `None` for a zero divisor
+    fn div_rem(self, divisor: Self) -> Option<(Self, Self)> {
+        if divisor == Self::ZERO {
+            return None;
+        }
+        if self < divisor {
+            return Some((Self::ZERO, self));
+        }
+        let shift = self.bits() - divisor.bits();
+        let mut shifted = divisor.shl(shift);
+        let (mut quotient, mut remainder) = (Self::ZERO, self);
+        for bit in (0..=shift).rev() {
+            if remainder >= shifted {
+                remainder = remainder.checked_sub(shifted)?;
+                quotient = quotient.checked_add(Self::from(1).shl(bit))?;
+            }
+            shifted = shifted.shr(1);
+        }
+        Some((quotient, remainder))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::whirlpool::constants::{MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64};
+
+    const LIQUIDITY: u128 = 3_000_000_000_000;
+    // Ticks -3000, -2950 and -2900 as the table computes them
+    const LOWER: u128 = 15877378835270155397;
+    const CURRENT: u128 = 15917119951032901241;
+    const UPPER: u128 = 15956960538899856095;
+
+    #[test]
+    fn the_tick_table_spans_the_program_bounds() {
+        assert_eq!(sqrt_price_from_tick_index(0), 1u128 << 64);
+        assert_eq!(sqrt_price_from_tick_index(443636), MAX_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_from_tick_index(-443636), MIN_SQRT_PRICE_X64);
+        // Out-of-range ticks clamp to the bounds
+        assert_eq!(sqrt_price_from_tick_index(500_000), MAX_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_from_tick_index(-500_000), MIN_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_from_tick_index(-3000), LOWER);
+        assert_eq!(sqrt_price_from_tick_index(-2950), CURRENT);
+        assert_eq!(sqrt_price_from_tick_index(-2900), UPPER);
+    }
+
+    #[test]
+    fn the_tick_table_tracks_the_exact_powers_of_one_point_zero_zero_zero_one() {
+        // Exact floor(2^64 * 1.0001^(tick / 2)); the table's per-bit rounding may leave it a few units low
+        let exact: [(i32, u128); 10] = [
+            (1, 18447666387855959850),
+            (-1, 18445821805675392311),
+            (64, 18505865242158250041),
+            (-64, 18387811781193591352),
+            (5632, 24446176000275556722),
+            (-5632, 13919656265139497015),
+            (100000, 2737055259406582257880),
+            (-100000, 124324258982887574),
+            (300000, 60257519765924248467716150),
+            (-300000, 5647135299341),
+        ];
+        for (tick, expected) in exact {
+            let price = sqrt_price_from_tick_index(tick);
+            assert!(price <= expected && expected - price <= 8, "tick {}: {} vs {}", tick, price, expected);
+        }
+        let mut previous = 0;
+        for tick in (-443636..=443636).step_by(997) {
+            let price = sqrt_price_from_tick_index(tick);
+            assert!(price > previous, "tick {}", tick);
+            previous = price;
+        }
+    }
+
+    #[test]
+    fn amount_deltas_round_in_the_requested_direction() {
+        assert_eq!(get_amount_delta_a(LOWER, UPPER, LIQUIDITY, false), Some(17383020044));
+        assert_eq!(get_amount_delta_a(LOWER, UPPER, LIQUIDITY, true), Some(17383020045));
+        assert_eq!(get_amount_delta_b(LOWER, UPPER, LIQUIDITY, false), Some(12942398394));
+        assert_eq!(get_amount_delta_b(LOWER, UPPER, LIQUIDITY, true), Some(12942398395));
+        // The order of the prices does not matter
+        assert_eq!(get_amount_delta_a(UPPER, LOWER, LIQUIDITY, true), Some(17383020045));
+        assert_eq!(get_amount_delta_b(UPPER, LOWER, LIQUIDITY, false), Some(12942398394));
+        assert_eq!(get_amount_delta_a(LOWER, LOWER, LIQUIDITY, true), Some(0));
+        // A delta past u64 is not an amount a swap can move
+        assert_eq!(get_amount_delta_b(MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64, u128::MAX >> 1, false), None);
+    }
+
+    #[test]
+    fn next_sqrt_price_moves_toward_the_output_and_rounds_against_the_swapper() {
+        let amount = 1_234_567_890;
+        assert_eq!(get_next_sqrt_price(CURRENT, LIQUIDITY, amount, true, true), Some(15911469946556175035));
+        assert_eq!(get_next_sqrt_price(CURRENT, LIQUIDITY, amount, false, false), Some(15922773969455554570));
+        assert_eq!(get_next_sqrt_price(CURRENT, LIQUIDITY, amount, true, false), Some(15924711203669051109));
+        assert_eq!(get_next_sqrt_price(CURRENT, LIQUIDITY, amount, false, true), Some(15909528698396751372));
+        assert_eq!(get_next_sqrt_price(CURRENT, LIQUIDITY, 0, true, true), Some(CURRENT));
+        // Taking out more than the range holds is rejected rather than wrapping
+        assert_eq!(get_next_sqrt_price(CURRENT, 1, u64::MAX, false, true), None);
+    }
+
+    #[test]
+    fn a_swap_step_stops_short_of_the_target_when_the_input_runs_out() {
+        let a_to_b = compute_swap_step_exact_in(1_000_000_000, 3000, LIQUIDITY, CURRENT, LOWER, true);
+        assert_eq!(
+            a_to_b,
+            Some(SwapStep { amount_in: 997_000_000, amount_out: 742_096_137, next_sqrt_price: 15912556865191251724, fee_amount: 3_000_000 })
+        );
+        let b_to_a = compute_swap_step_exact_in(1_000_000_000, 3000, LIQUIDITY, CURRENT, UPPER, false);
+        assert_eq!(
+            b_to_a,
+            Some(SwapStep { amount_in: 997_000_000, amount_out: 1_338_561_683, next_sqrt_price: 15923250418980064048, fee_amount: 3_000_000 })
+        );
+    }
+
+    #[test]
+    fn a_swap_step_that_reaches_the_target_charges_fee_on_what_it_used() {
+        let a_to_b = compute_swap_step_exact_in(10_000_000_000_000, 3000, LIQUIDITY, CURRENT, LOWER, true);
+        assert_eq!(
+            a_to_b,
+            Some(SwapStep { amount_in: 8_702_373_861, amount_out: 6_463_110_607, next_sqrt_price: LOWER, fee_amount: 26_185_679 })
+        );
+        let b_to_a = compute_swap_step_exact_in(10_000_000_000_000, 3000, LIQUIDITY, CURRENT, UPPER, false);
+        assert_eq!(
+            b_to_a,
+            Some(SwapStep { amount_in: 6_479_287_788, amount_out: 8_680_646_183, next_sqrt_price: UPPER, fee_amount: 19_496_353 })
+        );
+    }
+
+    #[test]
+    fn wide_products_divide_exactly() {
+        let product = U256::mul(u128::MAX, u128::MAX);
+        assert_eq!(product.bits(), 256);
+        let (quotient, remainder) = product.div_rem(U256::from(u128::MAX)).unwrap();
+        assert_eq!((quotient.as_u128(), remainder.as_u128()), (Some(u128::MAX), Some(0)));
+        let (quotient, remainder) = U256::mul(1 << 100, 12345).checked_add(U256::from(7)).unwrap().div_rem(U256::from(1 << 100)).unwrap();
+        assert_eq!((quotient.as_u128(), remainder.as_u128()), (Some(12345), Some(7)));
+        assert_eq!(U256::from(1).div_rem(U256::from(0)), None);
+        assert_eq!(U256::from(1).checked_shl(256), None);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod constants;
+pub mod math;
+pub mod quote;
 pub mod state;
 
 use crate::dex::whirlpool::state::{Whirlpool, TICK_ARRAY_SIZE};
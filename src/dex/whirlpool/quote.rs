@@ -0,0 +1,391 @@
+use crate::dex::whirlpool::constants::whirlpool_program_id;
+use crate::dex::whirlpool::math::{compute_swap_step_exact_in, sqrt_price_from_tick_index};
+use crate::dex::whirlpool::state::{Tick, TickArray, Whirlpool, NUM_REWARDS, TICK_ARRAY_SIZE};
+use crate::dex::whirlpool::{derive_next_start_tick_in_seq, derive_tick_array_start_indexes, get_tick_array_address};
+use solana_program::pubkey::Pubkey;
+use std::io::{Error, ErrorKind, Result};
+
+/// Tick arrays passed to a plain swap
+pub const STANDARD_SWAP_TICK_ARRAYS: usize = 3;
+/// swap_v2 accepts up to three supplemental tick arrays on top of the standard three
+pub const MAX_SWAP_TICK_ARRAYS: usize = 6;
+
+const TICK_LEN: usize = 1 + 16 + 16 + 16 + 16 + 16 * NUM_REWARDS;
+
+impl TickArray {
+    pub const LEN: usize = 8 + 4 + TICK_LEN * TICK_ARRAY_SIZE + 32;
+
+    pub fn try_deserialize(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::LEN {
+            return Err(Error::new(ErrorKind::InvalidData, "data too short for TickArray"));
+        }
+        let data = &data[8..];
+        let read_u128 = |offset: usize| u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
+
+        let start_tick_index = i32::from_le_bytes(data[0..4].try_into().unwrap());
+        let mut ticks = [Tick::default(); TICK_ARRAY_SIZE];
+        for (i, tick) in ticks.iter_mut().enumerate() {
+            let offset = 4 + i * TICK_LEN;
+            tick.initialized = data[offset] != 0;
+            tick.liquidity_net = read_u128(offset + 1) as i128;
+            tick.liquidity_gross = read_u128(offset + 17);
+            tick.fee_growth_outside_a = read_u128(offset + 33);
+            tick.fee_growth_outside_b = read_u128(offset + 49);
+            for (r, growth) in tick.reward_growths_outside.iter_mut().enumerate() {
+                *growth = read_u128(offset + 65 + r * 16);
+            }
+        }
+        let whirlpool_offset = 4 + TICK_LEN * TICK_ARRAY_SIZE;
+        let whirlpool = Pubkey::new_from_array(data[whirlpool_offset..whirlpool_offset + 32].try_into().unwrap());
+
+        Ok(Self { start_tick_index, ticks, whirlpool })
+    }
+}
+
+/// Result of walking the tick arrays for an exact-input swap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhirlpoolQuote {
+    pub amount_in: u64, // Input consumed, fee included; less than requested when exhausted
+    pub amount_out: u64,
+    pub tick_arrays_used: usize, // Arrays the walk reached, counting the one holding the current tick
+    pub exhausted: bool,         // The walk ran off the last supplied array before the input was consumed
+}
+
+impl WhirlpoolQuote {
+    /// Largest input the supplied arrays can price, if the walk ran out of them
+    pub fn input_cap(&self) -> Option<u64> {
+        self.exhausted.then_some(self.amount_in)
+    }
+}
+
+/// Walk state between tick crossings, in the program's integer math
+struct Walk {
+    a_to_b: bool,
+    fee_rate: u16,
+    sqrt_price: u128,
+    liquidity: u128,
+    remaining: u64, // Fee included
+    amount_out: u64,
+}
+
+impl Walk {
+    /// Move toward the sqrt price of `tick`. Returns false once the input is
+    /// used up short of it, or the step fails as it would on-chain.
+    fn step_to(&mut self, tick: i32) -> bool {
+        let target = sqrt_price_from_tick_index(tick);
+        let behind = if self.a_to_b { target > self.sqrt_price } else { target < self.sqrt_price };
+        if behind {
+            return true;
+        }
+        if self.remaining == 0 {
+            return false;
+        }
+        let Some(step) = compute_swap_step_exact_in(self.remaining, self.fee_rate, self.liquidity, self.sqrt_price, target, self.a_to_b) else {
+            return false;
+        };
+        self.remaining = self.remaining.saturating_sub(step.amount_in.saturating_add(step.fee_amount));
+        self.amount_out = self.amount_out.saturating_add(step.amount_out);
+        self.sqrt_price = step.next_sqrt_price;
+        step.next_sqrt_price == target
+    }
+
+    fn cross(&mut self, liquidity_net: i128) {
+        let liquidity = i128::try_from(self.liquidity).unwrap_or(i128::MAX);
+        let next = if self.a_to_b { liquidity.checked_sub(liquidity_net) } else { liquidity.checked_add(liquidity_net) };
+        self.liquidity = next.unwrap_or(0).max(0) as u128;
+    }
+}
+
+/// Quote an exact-input swap by walking initialized ticks. `tick_arrays`
+/// must be ordered in the swap direction, starting with the array that holds
+/// the current tick.
+pub fn quote_exact_in(whirlpool: &Whirlpool, tick_arrays: &[TickArray], amount_in: u64, a_to_b: bool) -> WhirlpoolQuote {
+    let mut walk = Walk {
+        a_to_b,
+        fee_rate: whirlpool.fee_rate,
+        sqrt_price: whirlpool.sqrt_price,
+        liquidity: whirlpool.liquidity,
+        remaining: amount_in,
+        amount_out: 0,
+    };
+    let spacing = whirlpool.tick_spacing as i32;
+    let current = whirlpool.tick_current_index;
+    let mut tick_arrays_used = 0;
+
+    'arrays: for (idx, array) in tick_arrays.iter().enumerate() {
+        tick_arrays_used = idx + 1;
+        let mut ticks: Vec<(i32, &Tick)> = array
+            .ticks
+            .iter()
+            .enumerate()
+            .map(|(i, tick)| (array.start_tick_index + i as i32 * spacing, tick))
+            .filter(|(index, tick)| tick.initialized && if a_to_b { *index <= current } else { *index > current })
+            .collect();
+        if a_to_b {
+            ticks.reverse();
+        }
+
+        for (index, tick) in ticks {
+            if !walk.step_to(index) {
+                break 'arrays;
+            }
+            walk.cross(tick.liquidity_net);
+        }
+
+        // Continue to the far edge of this array before moving on to the next
+        let edge = if a_to_b {
+            array.start_tick_index
+        } else {
+            array.start_tick_index + TICK_ARRAY_SIZE as i32 * spacing
+        };
+        if !walk.step_to(edge) {
+            break;
+        }
+    }
+
+    WhirlpoolQuote {
+        amount_in: amount_in - walk.remaining,
+        amount_out: walk.amount_out,
+        tick_arrays_used,
+        exhausted: walk.remaining > 0,
+    }
+}
+
+/// Like `quote_exact_in`, but when the walk runs off the supplied arrays it
+/// fetches the next one in sequence, up to `fetch_budget` extra fetches and
+/// `MAX_SWAP_TICK_ARRAYS` in total. Fetched arrays are appended to
+/// `tick_arrays`. A quote that is still exhausted carries the input cap.
+pub fn quote_exact_in_with_fetch(
+    whirlpool: &Whirlpool,
+    whirlpool_pk: &Pubkey,
+    tick_arrays: &mut Vec<TickArray>,
+    amount_in: u64,
+    a_to_b: bool,
+    mut fetch_budget: usize,
+    mut fetch: impl FnMut(&Pubkey) -> Option<TickArray>,
+) -> WhirlpoolQuote {
+    loop {
+        let quote = quote_exact_in(whirlpool, tick_arrays, amount_in, a_to_b);
+        if !quote.exhausted || fetch_budget == 0 || tick_arrays.len() >= MAX_SWAP_TICK_ARRAYS {
+            return quote;
+        }
+        let next_start = match tick_arrays.last() {
+            Some(last) => derive_next_start_tick_in_seq(last.start_tick_index, whirlpool.tick_spacing, a_to_b),
+            None => Some(derive_tick_array_start_indexes(whirlpool.tick_current_index, whirlpool.tick_spacing, a_to_b).0),
+        };
+        let Some(next_start) = next_start else {
+            return quote;
+        };
+        fetch_budget -= 1;
+        match fetch(&get_tick_array_address(whirlpool_pk, next_start, &whirlpool_program_id())) {
+            Some(array) => tick_arrays.push(array),
+            None => return quote,
+        }
+    }
+}
+
+/// Tick arrays to pass for a swap in one direction, covering `count` arrays
+/// (at least the standard three, at most `MAX_SWAP_TICK_ARRAYS`). Past the
+/// end of the tick range the last array is repeated, as the program expects.
+pub fn swap_tick_array_addresses(
+    whirlpool: &Whirlpool,
+    whirlpool_pk: &Pubkey,
+    program_id: &Pubkey,
+    a_to_b: bool,
+    count: usize,
+) -> Vec<Pubkey> {
+    let count = count.clamp(STANDARD_SWAP_TICK_ARRAYS, MAX_SWAP_TICK_ARRAYS);
+    let mut start = derive_tick_array_start_indexes(whirlpool.tick_current_index, whirlpool.tick_spacing, a_to_b).0;
+    let mut addresses = Vec::with_capacity(count);
+    for _ in 0..count {
+        addresses.push(get_tick_array_address(whirlpool_pk, start, program_id));
+        start = derive_next_start_tick_in_seq(start, whirlpool.tick_spacing, a_to_b).unwrap_or(start);
+    }
+    addresses
+}
+
+/// Pool state and the tick arrays a swap in each direction walks, as read on
+/// the last refresh. Arrays are ordered in the swap direction, starting with
+/// the one holding the current tick.
+#[derive(Clone, Debug)]
+pub struct WhirlpoolTicks {
+    pub whirlpool: Whirlpool,
+    pub a_to_b: Vec<TickArray>,
+    pub b_to_a: Vec<TickArray>,
+}
+
+impl WhirlpoolTicks {
+    /// Output of an exact-input swap of `amount_in`, fee included. 0 when the
+    /// loaded arrays cannot fill it, since the swap would fail past them.
+    pub fn whirlpool_quote(&self, amount_in: u64, a_to_b: bool) -> u64 {
+        let tick_arrays = if a_to_b { &self.a_to_b } else { &self.b_to_a };
+        let quote = quote_exact_in(&self.whirlpool, tick_arrays, amount_in, a_to_b);
+        if quote.exhausted { 0 } else { quote.amount_out }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::MintPoolData;
+    use std::collections::HashMap;
+
+    const SPACING: u16 = 64;
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+
+    /// Real-valued sqrt price, for the closed-form references
+    fn sqrt_price_at_tick(tick: i32) -> f64 {
+        1.0001f64.powf(tick as f64 / 2.0)
+    }
+
+    /// Price 1 at tick 0 with constant liquidity and no initialized ticks, so
+    /// a b-to-a walk only ends at the edge of the last supplied array
+    fn whirlpool() -> Whirlpool {
+        let mut whirlpool = Whirlpool::try_deserialize(&[0u8; Whirlpool::LEN]).unwrap();
+        whirlpool.tick_spacing = SPACING;
+        whirlpool.fee_rate = 3000;
+        whirlpool.liquidity = LIQUIDITY;
+        whirlpool.sqrt_price = 1u128 << 64;
+        whirlpool.tick_current_index = 0;
+        whirlpool
+    }
+
+    fn tick_array(pool: &Pubkey, start_tick_index: i32) -> TickArray {
+        TickArray { start_tick_index, ticks: [Tick::default(); TICK_ARRAY_SIZE], whirlpool: *pool }
+    }
+
+    /// Every array a b-to-a swap can walk, keyed by address
+    fn on_chain(pool: &Pubkey) -> HashMap<Pubkey, TickArray> {
+        let span = TICK_ARRAY_SIZE as i32 * SPACING as i32;
+        (0..MAX_SWAP_TICK_ARRAYS as i32)
+            .map(|i| (get_tick_array_address(pool, i * span, &whirlpool_program_id()), tick_array(pool, i * span)))
+            .collect()
+    }
+
+    fn standard_arrays(pool: &Pubkey) -> Vec<TickArray> {
+        let span = TICK_ARRAY_SIZE as i32 * SPACING as i32;
+        (0..STANDARD_SWAP_TICK_ARRAYS as i32).map(|i| tick_array(pool, i * span)).collect()
+    }
+
+    // Three arrays take the sqrt price from 1 to ~2.33, a fourth to ~3.08:
+    // 1.7e12 in only fits once the fourth array is walked
+    const INTO_FOURTH_ARRAY: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn an_amount_past_the_standard_arrays_is_capped_at_their_edge() {
+        let (whirlpool, pool) = (whirlpool(), Pubkey::new_unique());
+        let arrays = standard_arrays(&pool);
+
+        let quote = quote_exact_in(&whirlpool, &arrays, INTO_FOURTH_ARRAY, false);
+        assert!(quote.exhausted);
+        assert_eq!(quote.tick_arrays_used, 3);
+        let cap = quote.input_cap().unwrap();
+        // L * (sqrt price at tick 16896 - 1), grossed up by the 0.3% fee
+        let expected = LIQUIDITY as f64 * (1.0001f64.powf(16896.0 / 2.0) - 1.0) / 0.997;
+        assert!((cap as f64 - expected).abs() < 1e3, "{} vs {}", cap, expected);
+
+        // The cap itself fits, and the cached quote refuses what does not
+        let capped = quote_exact_in(&whirlpool, &arrays, cap, false);
+        assert!(!capped.exhausted);
+        assert!(capped.input_cap().is_none());
+        let ticks = WhirlpoolTicks { whirlpool, a_to_b: Vec::new(), b_to_a: arrays };
+        assert_eq!(ticks.whirlpool_quote(INTO_FOURTH_ARRAY, false), 0);
+        assert_eq!(ticks.whirlpool_quote(cap, false), capped.amount_out);
+    }
+
+    #[test]
+    fn fetching_the_fourth_array_fills_the_amount_and_sizes_the_swap_accounts() {
+        let (whirlpool, pool) = (whirlpool(), Pubkey::new_unique());
+        let chain = on_chain(&pool);
+        let mut fetched = Vec::new();
+
+        // Without budget the quote stays capped
+        let mut arrays = standard_arrays(&pool);
+        let capped = quote_exact_in_with_fetch(&whirlpool, &pool, &mut arrays, INTO_FOURTH_ARRAY, false, 0, |_| None);
+        assert!(capped.exhausted);
+        assert_eq!(arrays.len(), 3);
+
+        let quote = quote_exact_in_with_fetch(&whirlpool, &pool, &mut arrays, INTO_FOURTH_ARRAY, false, 2, |address| {
+            fetched.push(*address);
+            chain.get(address).cloned()
+        });
+        assert!(!quote.exhausted);
+        assert_eq!(quote.amount_in, INTO_FOURTH_ARRAY);
+        assert_eq!(quote.tick_arrays_used, 4);
+        assert!(quote.amount_out > capped.amount_out);
+        assert_eq!(arrays.len(), 4);
+        assert_eq!(fetched.len(), 1, "only the array the amount reaches is fetched");
+
+        // The swap passes the fetched array after the standard three
+        let addresses = swap_tick_array_addresses(&whirlpool, &pool, &whirlpool_program_id(), false, quote.tick_arrays_used);
+        assert_eq!(addresses.len(), 4);
+        assert_eq!(addresses[..3], swap_tick_array_addresses(&whirlpool, &pool, &whirlpool_program_id(), false, 1)[..]);
+        assert_eq!(addresses[3], fetched[0]);
+        assert_eq!(swap_tick_array_addresses(&whirlpool, &pool, &whirlpool_program_id(), false, 10).len(), MAX_SWAP_TICK_ARRAYS);
+
+        let mut pools = MintPoolData::new(Pubkey::new_unique(), &Pubkey::new_unique(), spl_token::id());
+        pools.add_whirlpool_pool(
+            pool,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            addresses[..3].to_vec(),
+            None,
+            pools.mint,
+            whirlpool.token_mint_b,
+        );
+        assert!(pools.set_whirlpool_swap_tick_arrays(&pool, addresses.clone()));
+        assert_eq!(pools.whirlpool_pools[0].tick_arrays, addresses);
+        assert!(!pools.set_whirlpool_swap_tick_arrays(&Pubkey::new_unique(), addresses));
+    }
+
+    /// Closed-form output of a swap that stays inside one liquidity range
+    fn in_range_out(liquidity: f64, sqrt_price: f64, net_in: f64, a_to_b: bool) -> f64 {
+        if a_to_b {
+            liquidity * (sqrt_price - 1.0 / (1.0 / sqrt_price + net_in / liquidity))
+        } else {
+            liquidity * (1.0 / sqrt_price - 1.0 / (sqrt_price + net_in / liquidity))
+        }
+    }
+
+    #[test]
+    fn quotes_match_the_concentrated_liquidity_formulas() {
+        let pool = Pubkey::new_unique();
+        let span = TICK_ARRAY_SIZE as i32 * SPACING as i32;
+        let net = |amount_in: u64, fee_rate: u32| amount_in as f64 * (1.0 - fee_rate as f64 / 1_000_000.0);
+
+        // B to A within the current range at price 1 and the default 0.3% fee
+        let ticks = WhirlpoolTicks { whirlpool: whirlpool(), a_to_b: Vec::new(), b_to_a: standard_arrays(&pool) };
+        let expected = in_range_out(LIQUIDITY as f64, 1.0, net(10_000_000_000, 3000), false);
+        assert!((ticks.whirlpool_quote(10_000_000_000, false) as f64 - expected).abs() <= 1.0, "{}", expected);
+
+        // A to B from tick -3000 with a 0.05% fee, arrays ordered downward from the current one
+        let mut lower = whirlpool();
+        lower.fee_rate = 500;
+        lower.tick_current_index = -3000;
+        lower.sqrt_price = (sqrt_price_at_tick(-3000) * (1u128 << 64) as f64) as u128;
+        let current_start = (-3000i32).div_euclid(span) * span;
+        let a_to_b: Vec<TickArray> = (0..3).map(|i| tick_array(&pool, current_start - i * span)).collect();
+        let sqrt_price = lower.sqrt_price as f64 / (1u128 << 64) as f64;
+        let ticks = WhirlpoolTicks { whirlpool: lower, a_to_b, b_to_a: Vec::new() };
+        let expected = in_range_out(LIQUIDITY as f64, sqrt_price, net(25_000_000_000, 500), true);
+        assert!((ticks.whirlpool_quote(25_000_000_000, true) as f64 - expected).abs() <= 1.0, "{}", expected);
+
+        // B to A across tick 640, where liquidity doubles
+        let mut arrays = standard_arrays(&pool);
+        arrays[0].ticks[10] = Tick { initialized: true, liquidity_net: LIQUIDITY as i128, liquidity_gross: LIQUIDITY, ..Tick::default() };
+        let ticks = WhirlpoolTicks { whirlpool: whirlpool(), a_to_b: Vec::new(), b_to_a: arrays };
+        let crossing = sqrt_price_at_tick(640);
+        let to_crossing = LIQUIDITY as f64 * (crossing - 1.0);
+        let amount_in = 100_000_000_000;
+        assert!(net(amount_in, 3000) > to_crossing, "the swap must reach the initialized tick");
+        let after = in_range_out(2.0 * LIQUIDITY as f64, crossing, net(amount_in, 3000) - to_crossing, false);
+        let expected = LIQUIDITY as f64 * (1.0 - 1.0 / crossing) + after;
+        // Each step rounds the input up and the output down, so two steps may land a few units short
+        let quoted = ticks.whirlpool_quote(amount_in, false) as f64;
+        assert!(quoted <= expected && expected - quoted <= 3.0, "{} {}", expected, quoted);
+        // Without the extra liquidity the same input moves the price further for less
+        let thin = in_range_out(LIQUIDITY as f64, 1.0, net(amount_in, 3000), false);
+        assert!(expected > thin);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::discovery::types::*;
+use crate::persist::{atomic_write, atomic_write_json, read_to_string, Compression};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const ARCHIVE_PREFIX: &str = "discovered_pools_";
+const ARCHIVE_SUFFIX: &str = ".json";
+
+/// Store a completed discovery run in `archive_dir`, compressed with
+/// `compression`, and prune the oldest runs beyond `retention`.
+pub async fn archive_results(
+    archive_dir: &str,
+    retention: usize,
+    compression: Compression,
+    results: &DiscoveredPools,
+) -> Result<()> {
+    tokio::fs::create_dir_all(archive_dir)
+        .await
+        .context(format!("Failed to create archive dir {}", archive_dir))?;
+
+    let path = Path::new(archive_dir).join(format!(
+        "{}{}{}{}",
+        ARCHIVE_PREFIX,
+        results.timestamp,
+        ARCHIVE_SUFFIX,
+        compression.extension()
+    ));
+    atomic_write_json(&path, results).await?;
+    info!("🗄️ Archived discovery run to {}", path.display());
+
+    prune_archive(archive_dir, retention).await
+}
+
+/// Archived runs sorted oldest first, as `(timestamp, path)`
+pub async fn list_archive(archive_dir: &str) -> Result<Vec<(u64, PathBuf)>> {
+    let mut entries = tokio::fs::read_dir(archive_dir)
+        .await
+        .context(format!("Failed to read archive dir {}", archive_dir))?;
+
+    let mut runs = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let timestamp = name
+            .strip_prefix(ARCHIVE_PREFIX)
+            .and_then(|rest| rest.strip_suffix(Compression::from_path(&name).extension()))
+            .and_then(|rest| rest.strip_suffix(ARCHIVE_SUFFIX))
+            .and_then(|ts| ts.parse::<u64>().ok());
+        if let Some(timestamp) = timestamp {
+            runs.push((timestamp, entry.path()));
+        }
+    }
+    runs.sort();
+    Ok(runs)
+}
+
+pub async fn prune_archive(archive_dir: &str, retention: usize) -> Result<()> {
+    let runs = list_archive(archive_dir).await?;
+    if runs.len() <= retention {
+        return Ok(());
+    }
+
+    for (_, path) in &runs[..runs.len() - retention] {
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            warn!("Failed to prune archived run {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Load every archived run, oldest first. Unreadable files are skipped.
+pub async fn load_archive(archive_dir: &str) -> Result<Vec<DiscoveredPools>> {
+    let mut runs = Vec::new();
+    for (_, path) in list_archive(archive_dir).await? {
+        let parsed = read_to_string(&path)
+            .await
+            .and_then(|content| serde_json::from_str::<DiscoveredPools>(&content).map_err(anyhow::Error::from));
+        match parsed {
+            Ok(run) => runs.push(run),
+            Err(e) => warn!("Skipping archived run {}: {}", path.display(), e),
+        }
+    }
+    Ok(runs)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniverseSnapshot {
+    pub timestamp: u64,
+    pub token_count: usize,
+    pub pool_count: usize,
+    pub total_liquidity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlapCandidate {
+    pub token_address: String,
+    pub token_symbol: String,
+    pub entries: usize,
+    pub exits: usize,
+    pub runs_present: usize,
+}
+
+/// Aggregate universe size and liquidity per run
+pub fn universe_trend(runs: &[DiscoveredPools]) -> Vec<UniverseSnapshot> {
+    runs.iter()
+        .map(|run| UniverseSnapshot {
+            timestamp: run.timestamp,
+            token_count: run.tokens.len(),
+            pool_count: run.tokens.iter().map(|t| t.pools.len()).sum(),
+            total_liquidity: run.tokens.iter().map(|t| t.total_liquidity).sum(),
+        })
+        .collect()
+}
+
+/// Per-token liquidity for each run the token appeared in: `token -> [(timestamp, liquidity)]`
+pub fn liquidity_trajectories(runs: &[DiscoveredPools]) -> BTreeMap<String, Vec<(u64, f64)>> {
+    let mut trajectories: BTreeMap<String, Vec<(u64, f64)>> = BTreeMap::new();
+    for run in runs {
+        for token in &run.tokens {
+            trajectories
+                .entry(token.token_address.clone())
+                .or_default()
+                .push((run.timestamp, token.total_liquidity));
+        }
+    }
+    trajectories
+}
+
+/// Tokens that entered or left the universe at least `min_transitions` times.
+/// Presence in the first run counts as neither an entry nor an exit.
+pub fn flap_candidates(runs: &[DiscoveredPools], min_transitions: usize) -> Vec<FlapCandidate> {
+    let mut symbols: BTreeMap<String, String> = BTreeMap::new();
+    for token in runs.iter().flat_map(|run| &run.tokens) {
+        symbols.insert(token.token_address.clone(), token.token_symbol.clone());
+    }
+
+    let presence: Vec<HashSet<&str>> = runs
+        .iter()
+        .map(|run| run.tokens.iter().map(|t| t.token_address.as_str()).collect())
+        .collect();
+
+    let mut candidates: Vec<FlapCandidate> = symbols
+        .into_iter()
+        .filter_map(|(token_address, token_symbol)| {
+            let present: Vec<bool> = presence.iter().map(|p| p.contains(token_address.as_str())).collect();
+            let entries = present.windows(2).filter(|w| !w[0] && w[1]).count();
+            let exits = present.windows(2).filter(|w| w[0] && !w[1]).count();
+            let runs_present = present.iter().filter(|p| **p).count();
+
+            (entries + exits >= min_transitions).then(|| FlapCandidate {
+                token_address,
+                token_symbol,
+                entries,
+                exits,
+                runs_present,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| (b.entries + b.exits).cmp(&(a.entries + a.exits)));
+    candidates
+}
+
+pub fn universe_csv(snapshots: &[UniverseSnapshot]) -> String {
+    let mut csv = String::from("timestamp,token_count,pool_count,total_liquidity_usd\n");
+    for s in snapshots {
+        csv.push_str(&format!("{},{},{},{:.2}\n", s.timestamp, s.token_count, s.pool_count, s.total_liquidity));
+    }
+    csv
+}
+
+pub fn liquidity_csv(trajectories: &BTreeMap<String, Vec<(u64, f64)>>) -> String {
+    let mut csv = String::from("token_address,timestamp,total_liquidity_usd\n");
+    for (token, points) in trajectories {
+        for (timestamp, liquidity) in points {
+            csv.push_str(&format!("{},{},{:.2}\n", token, timestamp, liquidity));
+        }
+    }
+    csv
+}
+
+pub fn flaps_csv(candidates: &[FlapCandidate]) -> String {
+    let mut csv = String::from("token_address,token_symbol,entries,exits,runs_present\n");
+    for c in candidates {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            c.token_address,
+            c.token_symbol.replace(',', " "),
+            c.entries,
+            c.exits,
+            c.runs_present
+        ));
+    }
+    csv
+}
+
+/// `discovery-trends` subcommand: print or write one CSV report over the archive
+pub async fn run_discovery_trends(
+    archive_dir: &str,
+    report: &str,
+    min_transitions: usize,
+    output: Option<&str>,
+) -> Result<()> {
+    let runs = load_archive(archive_dir).await?;
+    info!("📂 Loaded {} archived discovery runs from {}", runs.len(), archive_dir);
+
+    let csv = match report {
+        "universe" => universe_csv(&universe_trend(&runs)),
+        "liquidity" => liquidity_csv(&liquidity_trajectories(&runs)),
+        "flaps" => flaps_csv(&flap_candidates(&runs, min_transitions)),
+        other => anyhow::bail!("Unknown report {}, expected universe, liquidity or flaps", other),
+    };
+
+    match output {
+        Some(path) => {
+            atomic_write(path, csv).await?;
+            info!("💾 Wrote {} report to {}", report, path);
+        }
+        None => print!("{}", csv),
+    }
+    Ok(())
+}
+
+/// `discovery-rank` subcommand: rank tokens in a discovery output by arbitrage potential
+pub async fn run_discovery_rank(input: &str, top: usize, output: Option<&str>) -> Result<()> {
+    let content = read_to_string(input).await?;
+    let pools: DiscoveredPools = serde_json::from_str(&content).context("Failed to parse discovered pools JSON")?;
+
+    let mut csv = String::from("rank,token_address,token_symbol,pool_count,max_weighted_spread_bps,score\n");
+    for (i, p) in pools.rank_by_arb_potential().iter().take(top).enumerate() {
+        csv.push_str(&format!(
+            "{},{},{},{},{:.2},{:.2}\n",
+            i + 1,
+            p.token_address,
+            p.token_symbol.replace(',', " "),
+            p.pool_count,
+            p.max_weighted_spread_bps,
+            p.score
+        ));
+    }
+
+    match output {
+        Some(path) => {
+            atomic_write(path, csv).await?;
+            info!("💾 Wrote arbitrage potential ranking to {}", path);
+        }
+        None => print!("{}", csv),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn token(address: &str, liquidity: f64, pools: usize) -> DiscoveredToken {
+        DiscoveredToken {
+            token_address: address.to_string(),
+            token_name: address.to_string(),
+            token_symbol: address.to_uppercase(),
+            total_liquidity: liquidity,
+            pools: (0..pools)
+                .map(|i| DiscoveredPool {
+                    pool_address: format!("{}_pool_{}", address, i),
+                    dex_type: "raydium_amm_v4".to_string(),
+                    program_id: String::new(),
+                    liquidity_usd: liquidity / pools as f64,
+                    volume_h24: 0.0,
+                    sol_side: "quote".to_string(),
+                    price_sol: 0.0,
+                })
+                .collect(),
+            transfer_hook_program: None,
+        }
+    }
+
+    fn run(timestamp: u64, tokens: Vec<DiscoveredToken>) -> DiscoveredPools {
+        DiscoveredPools { timestamp, token_count: tokens.len(), tokens }
+    }
+
+    /// Four runs: `a` stays throughout, `b` leaves and comes back, `c` only joins at the end
+    fn synthetic_runs() -> Vec<DiscoveredPools> {
+        vec![
+            run(100, vec![token("a", 1000.0, 2), token("b", 500.0, 1)]),
+            run(200, vec![token("a", 1200.0, 2)]),
+            run(300, vec![token("a", 900.0, 3), token("b", 400.0, 1)]),
+            run(400, vec![token("a", 1100.0, 3), token("c", 50.0, 1)]),
+        ]
+    }
+
+    #[tokio::test]
+    async fn archiving_keeps_only_the_newest_runs_within_retention() {
+        let dir = std::env::temp_dir().join(format!("{}_archive", Pubkey::new_unique()));
+        let dir = dir.to_str().unwrap();
+
+        for (i, results) in synthetic_runs().iter().enumerate() {
+            let compression = if i % 2 == 0 { Compression::None } else { Compression::Gzip };
+            archive_results(dir, 3, compression, results).await.unwrap();
+        }
+
+        let timestamps: Vec<u64> = list_archive(dir).await.unwrap().into_iter().map(|(ts, _)| ts).collect();
+        assert_eq!(timestamps, vec![200, 300, 400]);
+
+        // Compressed and plain runs load back alike, oldest first
+        let loaded = load_archive(dir).await.unwrap();
+        assert_eq!(loaded.iter().map(|r| r.timestamp).collect::<Vec<_>>(), vec![200, 300, 400]);
+
+        prune_archive(dir, 1).await.unwrap();
+        assert_eq!(list_archive(dir).await.unwrap().len(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn trends_aggregate_each_run_and_follow_every_token() {
+        let runs = synthetic_runs();
+
+        let universe = universe_trend(&runs);
+        assert_eq!(
+            universe[2],
+            UniverseSnapshot { timestamp: 300, token_count: 2, pool_count: 4, total_liquidity: 1300.0 }
+        );
+        assert_eq!(universe.iter().map(|s| s.pool_count).collect::<Vec<_>>(), vec![3, 2, 4, 4]);
+
+        let trajectories = liquidity_trajectories(&runs);
+        assert_eq!(trajectories["a"], vec![(100, 1000.0), (200, 1200.0), (300, 900.0), (400, 1100.0)]);
+        assert_eq!(trajectories["b"], vec![(100, 500.0), (300, 400.0)]);
+        assert_eq!(trajectories["c"], vec![(400, 50.0)]);
+
+        assert_eq!(universe_csv(&universe).lines().nth(1), Some("100,2,3,1500.00"));
+    }
+
+    #[test]
+    fn a_token_that_leaves_and_returns_is_a_flap_candidate() {
+        let runs = synthetic_runs();
+
+        // b exits at 200, re-enters at 300 and exits again at 400; c enters once
+        let candidates = flap_candidates(&runs, 2);
+        assert_eq!(
+            candidates,
+            vec![FlapCandidate {
+                token_address: "b".to_string(),
+                token_symbol: "B".to_string(),
+                entries: 1,
+                exits: 2,
+                runs_present: 2,
+            }]
+        );
+
+        let all = flap_candidates(&runs, 1);
+        assert_eq!(all.iter().map(|c| c.token_address.as_str()).collect::<Vec<_>>(), vec!["b", "c"]);
+        assert_eq!((all[1].entries, all[1].exits), (1, 0));
+    }
+}
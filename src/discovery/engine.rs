@@ -1,3 +1,7 @@
+use crate::clock::{system_clock, SharedClock};
+use crate::discovery::archive::archive_results;
+use crate::persist::{atomic_write_json, read_to_string, Compression};
+use crate::token_extensions::{token_2022_program_id, transfer_hook_program};
 use crate::discovery::types::*;
 use anyhow::{Context, Result};
 use reqwest::Client;
@@ -18,6 +22,7 @@ use tracing::{error, info, warn};
 // Constants
 const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
 const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const IGNORED_MINTS: [&str; 3] = [SOL_MINT, USDC_MINT, "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"];
 
 // Whitelisted DEX Program IDs
 const RAYDIUM_V4_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
@@ -34,6 +39,7 @@ const DEXSCREENER_API_BASE: &str = "https://api.dexscreener.com/token-pairs/v1";
 const SOLANA_NETWORK: &str = "solana";
 const CONCURRENT_RPC_CHECKS: usize = 5;
 const RPC_RATE_LIMIT_MS: u64 = 200;
+const MAX_EXCLUSIONS: usize = 10_000; // Cap on records written to the exclusions file
 
 // API Response Structures
 #[derive(Deserialize, Debug)]
@@ -94,6 +100,8 @@ struct DexscreenerPair {
     base_token: Option<TokenInfo>,
     #[serde(rename = "quoteToken")]
     quote_token: Option<TokenInfo>,
+    #[serde(rename = "priceNative", default)]
+    price_native: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -129,11 +137,26 @@ fn identify_specific_dex_type(owner: &Pubkey) -> Option<(String, String)> {
     }
 }
 
-/// Verify pool on-chain with rate limiting
+/// Transfer hook program of a Token-2022 mint, None for plain mints or on fetch failure
+fn fetch_transfer_hook_program(rpc_client: &RpcClient, token_addr: &str) -> Option<String> {
+    let mint = Pubkey::from_str(token_addr).ok()?;
+    match rpc_client.get_account(&mint) {
+        Ok(account) if account.owner == token_2022_program_id() => {
+            transfer_hook_program(&account.data).map(|program| program.to_string())
+        }
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Failed to fetch mint {}: {}", token_addr, e);
+            None
+        }
+    }
+}
+
+/// Owner program of a pool account, fetched with rate limiting
 async fn verify_pool_on_chain(
     rpc_client: &RpcClient,
     pool_address: &str,
-) -> Result<Option<(String, String)>> {
+) -> Result<Pubkey> {
     let pubkey = Pubkey::from_str(pool_address)
         .context("Invalid pool address format")?;
     
@@ -141,12 +164,31 @@ async fn verify_pool_on_chain(
         Ok(acc) => acc,
         Err(e) => {
             warn!("Failed to fetch account {}: {}", pool_address, e);
-            return Ok(None);
+            return Err(e.into());
         }
     };
 
     sleep(Duration::from_millis(RPC_RATE_LIMIT_MS)).await;
-    Ok(identify_specific_dex_type(&account.owner))
+    Ok(account.owner)
+}
+
+/// Candidate token mints of the pools, minus quote tokens which are excluded
+fn candidate_tokens(pools: &[GeckoPoolData]) -> (HashSet<String>, Vec<Exclusion>) {
+    let mut candidates = HashSet::new();
+    let mut exclusions = Vec::new();
+    for addr in pools
+        .iter()
+        .filter_map(|pool| pool.relationships.as_ref().map(|r| r.base_token.data.id.replace("solana_", "")))
+    {
+        if IGNORED_MINTS.contains(&addr.as_str()) {
+            if !exclusions.iter().any(|e: &Exclusion| e.token_address == addr) {
+                exclusions.push(Exclusion::token(&addr, ExclusionStage::IgnoredMint, "quote token, not an arbitrage candidate"));
+            }
+        } else {
+            candidates.insert(addr);
+        }
+    }
+    (candidates, exclusions)
 }
 
 /// Discovery engine implementation
@@ -154,6 +196,9 @@ pub struct DiscoveryEngine {
     http_client: Client,
     rpc_client: Arc<RpcClient>,
     config: DiscoveryConfig,
+    clock: SharedClock,
+    gecko_api_base: String,
+    dexscreener_api_base: String,
 }
 
 impl DiscoveryEngine {
@@ -173,100 +218,221 @@ impl DiscoveryEngine {
             http_client,
             rpc_client,
             config,
+            clock: system_clock(),
+            gecko_api_base: GECKO_API_BASE.to_string(),
+            dexscreener_api_base: DEXSCREENER_API_BASE.to_string(),
         }
     }
 
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_gecko_api_base(mut self, gecko_api_base: impl Into<String>) -> Self {
+        self.gecko_api_base = gecko_api_base.into();
+        self
+    }
+
+    pub fn with_dexscreener_api_base(mut self, dexscreener_api_base: impl Into<String>) -> Self {
+        self.dexscreener_api_base = dexscreener_api_base.into();
+        self
+    }
+
     /// Run discovery and return results
     pub async fn run_discovery(&self) -> Result<DiscoveredPools> {
         info!("🚀 Starting Pool Discovery...");
         info!("⏱️  RPC Rate Limit: {} ms/req", RPC_RATE_LIMIT_MS);
 
         // Get tokens from GeckoTerminal
-        let mut initial_pools = Vec::new();
-        
-        info!("📡 Fetching trending pools...");
-        let mut trending = self.fetch_gecko_pools(&format!("networks/{}/trending_pools", SOLANA_NETWORK)).await?;
-        initial_pools.append(&mut trending);
-        
-        info!("📡 Fetching top pools...");
-        let mut top = self.fetch_gecko_pools(&format!("networks/{}/pools", SOLANA_NETWORK)).await?;
-        initial_pools.append(&mut top);
+        let initial_pools = self.fetch_candidate_pools().await?;
 
         // Extract unique tokens
-        let ignored_mints = [SOL_MINT, USDC_MINT, "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"];
-        
-        let discovered_tokens: HashSet<String> = initial_pools
-            .iter()
-            .filter_map(|pool| {
-                pool.relationships.as_ref().map(|r| {
-                    r.base_token.data.id.replace("solana_", "")
-                })
-            })
-            .filter(|addr| !ignored_mints.contains(&addr.as_str()))
-            .collect();
+        let (discovered_tokens, mut exclusions) = candidate_tokens(&initial_pools);
 
         info!("💎 Found {} unique tokens", discovered_tokens.len());
         
         let discovered_tokens: Vec<String> = discovered_tokens.into_iter().collect();
-        let total_tokens = discovered_tokens.len();
         
         info!("🔍 Verifying pools on-chain (rate limited)...\n");
 
         // Process with concurrency limit
         let semaphore = Arc::new(Semaphore::new(CONCURRENT_RPC_CHECKS));
+        let (mut all_results, mut token_exclusions, interrupted) = self.verify_tokens(discovered_tokens, semaphore).await;
+        exclusions.append(&mut token_exclusions);
+
+        all_results.sort_by(|a, b| b.total_liquidity.partial_cmp(&a.total_liquidity).unwrap());
+
+        let output = DiscoveredPools {
+            timestamp: self.clock.now_unix(),
+            token_count: all_results.len(),
+            tokens: all_results,
+        };
+
+        if interrupted {
+            info!("🏁 Discovery stopped early: {} tokens with >= 2 verified SOL pools (partial)", output.token_count);
+        } else {
+            info!("🏆 Discovery complete! Found {} tokens with >= 2 verified SOL pools", output.token_count);
+        }
+
+        // Diagnostics only, a failed write must not discard the run
+        let exclusions = DiscoveryExclusions::new(output.timestamp, exclusions, MAX_EXCLUSIONS);
+        match atomic_write_json(&self.config.exclusions_file, &exclusions).await {
+            Ok(()) => info!("💾 Saved {} exclusions to {}", exclusions.total, self.config.exclusions_file),
+            Err(e) => warn!("⚠️ Failed to save discovery exclusions: {}", e),
+        }
+        Ok(output)
+    }
+
+    /// Check each token with at most as many in flight as `semaphore` has
+    /// permits. If the semaphore is closed (shutdown), no further token is
+    /// scheduled and the checks already running are awaited; the flag reports
+    /// whether that happened.
+    async fn verify_tokens(&self, tokens: Vec<String>, semaphore: Arc<Semaphore>) -> (Vec<DiscoveredToken>, Vec<Exclusion>, bool) {
+        let total_tokens = tokens.len();
         let mut futures = FuturesUnordered::new();
 
-        for (idx, token_addr) in discovered_tokens.into_iter().enumerate() {
-            let permit = semaphore.clone().acquire_owned().await?;
+        let mut interrupted = false;
+        for (idx, token_addr) in tokens.into_iter().enumerate() {
+            // A closed semaphore means shutdown: stop scheduling and keep what is in flight
+            let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                warn!("⚠️ Discovery interrupted after scheduling {} of {} tokens, finishing in-flight checks", idx, total_tokens);
+                interrupted = true;
+                break;
+            };
             let rpc_client = self.rpc_client.clone();
             let config = self.config.clone();
+            let dexscreener_api_base = self.dexscreener_api_base.clone();
             
             let future = tokio::spawn(async move {
                 let _permit = permit;
-                Self::process_token(idx, total_tokens, &rpc_client, &config, &token_addr).await
+                Self::process_token(idx, total_tokens, &rpc_client, &config, &dexscreener_api_base, &token_addr).await
             });
             
             futures.push(future);
         }
 
         let mut all_results: Vec<DiscoveredToken> = Vec::new();
+        let mut exclusions = Vec::new();
         while let Some(result) = futures.next().await {
             match result {
-                Ok(Ok(Some(token_group))) => {
-                    if token_group.pools.len() >= 2 {
+                Ok(Ok((token_group, mut token_exclusions))) => {
+                    exclusions.append(&mut token_exclusions);
+                    if let Some(token_group) = token_group.filter(|t| t.pools.len() >= 2) {
                         all_results.push(token_group);
                     }
                 }
-                Ok(Ok(None)) => {}
                 Ok(Err(e)) => error!("Task error: {}", e),
                 Err(e) => error!("Join error: {}", e),
             }
         }
+        (all_results, exclusions, interrupted)
+    }
 
-        all_results.sort_by(|a, b| b.total_liquidity.partial_cmp(&a.total_liquidity).unwrap());
-
-        let output = DiscoveredPools {
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)?
-                .as_secs(),
-            token_count: all_results.len(),
-            tokens: all_results,
+    /// `run_discovery`, falling back when it fails (typically an API outage)
+    /// to re-verifying the last saved results on-chain when `onchain_fallback`
+    /// is set. The fallback keeps the saved timestamp, since the pool set is
+    /// as old as the API data it came from.
+    pub async fn run_discovery_with_fallback(&self) -> Result<DiscoveredPools> {
+        let error = match self.run_discovery().await {
+            Ok(results) => return Ok(results),
+            Err(e) => e,
         };
+        if !self.config.onchain_fallback {
+            return Err(error);
+        }
+        let Some(previous) = self.load_results().await.ok().flatten().filter(|results| !results.tokens.is_empty()) else {
+            return Err(error.context("no saved discovery results to fall back to"));
+        };
+        warn!("🔁 API discovery failed ({}), falling back to on-chain re-verification of {} saved tokens", error, previous.token_count);
+        Ok(self.reverify_on_chain(&previous).await)
+    }
 
-        info!("🏆 Discovery complete! Found {} tokens with >= 2 verified SOL pools", output.token_count);
-        Ok(output)
+    /// Saved results minus pools whose account no longer exists or is no
+    /// longer owned by the recorded program, and tokens left with fewer than
+    /// two pools
+    async fn reverify_on_chain(&self, previous: &DiscoveredPools) -> DiscoveredPools {
+        let mut tokens = Vec::new();
+        let mut dropped = 0;
+        for token in &previous.tokens {
+            let mut pools = Vec::new();
+            for pool in &token.pools {
+                match verify_pool_on_chain(&self.rpc_client, &pool.pool_address).await {
+                    Ok(owner) if owner.to_string() == pool.program_id => pools.push(pool.clone()),
+                    Ok(owner) => {
+                        warn!("Pool {} is now owned by {}, not {}", pool.pool_address, owner, pool.program_id);
+                        dropped += 1;
+                    }
+                    Err(_) => dropped += 1,
+                }
+            }
+            if pools.len() >= 2 {
+                tokens.push(DiscoveredToken {
+                    total_liquidity: pools.iter().map(|pool| pool.liquidity_usd).sum(),
+                    pools,
+                    ..token.clone()
+                });
+            }
+        }
+        info!("⛓️ On-chain fallback kept {} of {} tokens ({} pools dropped)", tokens.len(), previous.token_count, dropped);
+        DiscoveredPools {
+            timestamp: previous.timestamp,
+            token_count: tokens.len(),
+            tokens,
+        }
+    }
+
+    /// Run the pipeline for a single token, logging every decision
+    pub async fn explain_token(&self, token_addr: &str) -> Result<()> {
+        Pubkey::from_str(token_addr).context(format!("Invalid mint {}", token_addr))?;
+        let initial_pools = self.fetch_candidate_pools().await?;
+        let (candidates, ignored) = candidate_tokens(&initial_pools);
+        if let Some(exclusion) = ignored.iter().find(|e| e.token_address == token_addr) {
+            info!("🚫 {}: {}", token_addr, exclusion.reason);
+            return Ok(());
+        }
+        if candidates.contains(token_addr) {
+            info!("✅ {} is in the GeckoTerminal trending/top pools", token_addr);
+        } else {
+            warn!("⚠️ {} is not in the GeckoTerminal trending/top pools, a discovery run would never consider it", token_addr);
+        }
+
+        let (token, exclusions) = Self::process_token(0, 1, &self.rpc_client, &self.config, &self.dexscreener_api_base, token_addr).await?;
+        for exclusion in &exclusions {
+            let numbers = match (exclusion.value, exclusion.threshold) {
+                (Some(value), Some(threshold)) => format!(" ({} vs {})", value, threshold),
+                _ => String::new(),
+            };
+            info!(
+                "🚫 {:?} {}: {}{}",
+                exclusion.stage,
+                exclusion.pool_address.as_deref().unwrap_or("token"),
+                exclusion.reason,
+                numbers
+            );
+        }
+        match token {
+            Some(token) => {
+                for pool in &token.pools {
+                    info!("✅ {} {}: ${:.0} liquidity, ${:.0} 24h volume", pool.dex_type, pool.pool_address, pool.liquidity_usd, pool.volume_h24);
+                }
+                info!("🏆 {} would be kept with {} pools", token.token_symbol, token.pools.len());
+            }
+            None => info!("🚫 {} would be excluded", token_addr),
+        }
+        Ok(())
     }
 
     /// Save discovery results to JSON file
     pub async fn save_results(&self, results: &DiscoveredPools) -> Result<()> {
         let path = &self.config.output_file;
-        let json = serde_json::to_string_pretty(results)
-            .context("Failed to serialize results")?;
-        
-        tokio::fs::write(path, json).await
-            .context(format!("Failed to write to {}", path))?;
-        
+        atomic_write_json(path, results).await?;
         info!("💾 Saved discovered pools to {}", path);
+
+        if self.config.archive_retention > 0 {
+            let compression = Compression::from_path(&self.config.output_file);
+            archive_results(&self.config.archive_dir, self.config.archive_retention, compression, results).await?;
+        }
         Ok(())
     }
 
@@ -274,13 +440,15 @@ impl DiscoveryEngine {
     pub async fn load_results(&self) -> Result<Option<DiscoveredPools>> {
         let path = &self.config.output_file;
         
-        match tokio::fs::read_to_string(path).await {
+        match read_to_string(path).await {
             Ok(content) => {
                 let pools: DiscoveredPools = serde_json::from_str(&content)
                     .context("Failed to parse discovered pools JSON")?;
                 info!("📂 Loaded {} tokens from {}", pools.token_count, path);
                 Ok(Some(pools))
             }
+            // A file that exists but fails to decompress is as broken as one that fails to parse
+            Err(e) if tokio::fs::metadata(path).await.is_ok() => Err(e),
             Err(_) => {
                 info!("⚠️ No existing discovered pools file found at {}", path);
                 Ok(None)
@@ -288,6 +456,52 @@ impl DiscoveryEngine {
         }
     }
 
+    pub fn output_file(&self) -> &str {
+        &self.config.output_file
+    }
+
+    /// Time since `results` were produced
+    pub fn results_age(&self, results: &DiscoveredPools) -> Duration {
+        Duration::from_secs(self.clock.now_unix().saturating_sub(results.timestamp))
+    }
+
+    /// Time left until `results` are too old to skip a fresh discovery, if any
+    pub fn results_freshness(&self, results: &DiscoveredPools) -> Option<Duration> {
+        Duration::from_secs(self.config.max_results_age_minutes * 60)
+            .checked_sub(self.results_age(results))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Results to start from and the delay before the first scheduled
+    /// discovery. `saved` is used as is while fresh; otherwise discovery runs
+    /// now and is saved, with `saved` as a fallback regardless of age if it
+    /// fails. None when there is nothing to start from.
+    pub async fn startup_results(&self, saved: Option<DiscoveredPools>, interval: Duration) -> (Option<DiscoveredPools>, Duration) {
+        if let Some(remaining) = saved.as_ref().and_then(|results| self.results_freshness(results)) {
+            info!("📂 Using saved discovery from {:?} ago, next discovery in {:?}", self.results_age(saved.as_ref().unwrap()), remaining);
+            return (saved, remaining);
+        }
+
+        info!("🔄 Running initial pool discovery...");
+        match self.run_discovery_with_fallback().await {
+            Ok(results) => {
+                if let Err(e) = self.save_results(&results).await {
+                    error!("Failed to save discovery results: {}", e);
+                }
+                (Some(results), interval)
+            }
+            Err(e) => {
+                // A stale discovery is still closer to the market than the static config
+                let saved = saved.filter(|results| !results.tokens.is_empty());
+                match &saved {
+                    Some(results) => error!("❌ Initial discovery failed: {}, falling back to saved discovery from {:?} ago", e, self.results_age(results)),
+                    None => error!("❌ Initial discovery failed: {}", e),
+                }
+                (saved, Duration::ZERO)
+            }
+        }
+    }
+
     /// Convert discovered pools to markets format for bot
     pub fn convert_to_markets(pools: &DiscoveredPools) -> Vec<String> {
         let mut market_addresses = Vec::new();
@@ -302,13 +516,30 @@ impl DiscoveryEngine {
         market_addresses
     }
 
+    /// Trending and top pools, the source of discovery candidates
+    async fn fetch_candidate_pools(&self) -> Result<Vec<GeckoPoolData>> {
+        let mut initial_pools = Vec::new();
+
+        info!("📡 Fetching trending pools...");
+        let mut trending = self.fetch_gecko_pools(&format!("networks/{}/trending_pools", SOLANA_NETWORK)).await?;
+        initial_pools.append(&mut trending);
+
+        info!("📡 Fetching top pools...");
+        let mut top = self.fetch_gecko_pools(&format!("networks/{}/pools", SOLANA_NETWORK)).await?;
+        initial_pools.append(&mut top);
+        Ok(initial_pools)
+    }
+
     async fn fetch_gecko_pools(&self, endpoint: &str) -> Result<Vec<GeckoPoolData>> {
-        let url = format!("{}/{}", GECKO_API_BASE, endpoint);
-        let resp = self.http_client.get(&url).send().await?.error_for_status()?;
-        
-        if resp.status() == 404 {
+        let url = format!("{}/{}", self.gecko_api_base, endpoint);
+        let resp = self.http_client.get(&url).send().await?;
+
+        // A missing endpoint yields no pools instead of aborting the whole discovery run
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            warn!("⚠️ GeckoTerminal endpoint {} not found, skipping", endpoint);
             return Ok(Vec::new());
         }
+        let resp = resp.error_for_status()?;
         
         sleep(Duration::from_millis(500)).await;
         let data: GeckoResponse = resp.json().await?;
@@ -321,9 +552,11 @@ impl DiscoveryEngine {
         total_tokens: usize,
         rpc_client: &Arc<RpcClient>,
         config: &DiscoveryConfig,
+        dexscreener_api_base: &str,
         token_addr: &str,
-    ) -> Result<Option<DiscoveredToken>> {
-        let dexscreener_url = format!("{}/{}/{}", DEXSCREENER_API_BASE, SOLANA_NETWORK, token_addr);
+    ) -> Result<(Option<DiscoveredToken>, Vec<Exclusion>)> {
+        let dexscreener_url = format!("{}/{}/{}", dexscreener_api_base, SOLANA_NETWORK, token_addr);
+        let mut exclusions = Vec::new();
         
         let pairs = match reqwest::get(&dexscreener_url).await {
             Ok(resp) => {
@@ -331,13 +564,15 @@ impl DiscoveryEngine {
                     Ok(p) => p.0,
                     Err(e) => {
                         error!("[{}/{}] Parse error for {}: {}", idx + 1, total_tokens, &token_addr[..8], e);
-                        return Ok(None);
+                        exclusions.push(Exclusion::token(token_addr, ExclusionStage::Parse, format!("Dexscreener response: {}", e)));
+                        return Ok((None, exclusions));
                     }
                 }
             }
             Err(e) => {
                 error!("[{}/{}] Fetch error for {}: {}", idx + 1, total_tokens, &token_addr[..8], e);
-                return Ok(None);
+                exclusions.push(Exclusion::token(token_addr, ExclusionStage::Fetch, format!("Dexscreener request: {}", e)));
+                return Ok((None, exclusions));
             }
         };
 
@@ -357,25 +592,71 @@ impl DiscoveryEngine {
                         token_symbol = base.symbol.clone().unwrap_or("UNK".to_string());
                         (true, "quote")
                     } else {
+                        exclusions.push(Exclusion::pool(
+                            token_addr,
+                            &pair.pair_address,
+                            ExclusionStage::NotSolPair,
+                            format!(
+                                "{}/{} pair on {}",
+                                base.symbol.as_deref().unwrap_or("?"),
+                                quote.symbol.as_deref().unwrap_or("?"),
+                                pair.dex_id
+                            ),
+                        ));
                         continue;
                     }
                 }
-                _ => continue,
+                _ => {
+                    exclusions.push(Exclusion::pool(token_addr, &pair.pair_address, ExclusionStage::NotSolPair, "pair lists no base or quote token"));
+                    continue;
+                }
             };
 
             if !is_sol_pair {
                 continue;
             }
 
-            let (dex_type, program_id) = match verify_pool_on_chain(rpc_client, &pair.pair_address).await? {
+            let owner = match verify_pool_on_chain(rpc_client, &pair.pair_address).await {
+                Ok(owner) => owner,
+                Err(e) => {
+                    exclusions.push(Exclusion::pool(token_addr, &pair.pair_address, ExclusionStage::Fetch, format!("pool account: {}", e)));
+                    continue;
+                }
+            };
+            let (dex_type, program_id) = match identify_specific_dex_type(&owner) {
                 Some(result) => result,
-                None => continue,
+                None => {
+                    exclusions.push(Exclusion::pool(
+                        token_addr,
+                        &pair.pair_address,
+                        ExclusionStage::UnsupportedDex,
+                        format!("{} pool owned by unsupported program {}", pair.dex_id, owner),
+                    ));
+                    continue;
+                }
             };
 
             let liq = pair.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
             let vol = pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0);
+            // priceNative is base priced in quote; normalize to token priced in SOL
+            let price_native = pair.price_native.as_deref().and_then(|p| p.parse::<f64>().ok()).unwrap_or(0.0);
+            let price_sol = match sol_side {
+                "quote" => price_native,
+                _ if price_native > 0.0 => 1.0 / price_native,
+                _ => 0.0,
+            };
 
-            if liq >= config.min_liquidity_usd && vol >= config.min_volume_h24 {
+            if liq < config.min_liquidity_usd {
+                exclusions.push(
+                    Exclusion::pool(token_addr, &pair.pair_address, ExclusionStage::Liquidity, "liquidity below min_liquidity_usd")
+                        .with_numbers(liq, config.min_liquidity_usd),
+                );
+            } else if vol < config.min_volume_h24 {
+                exclusions.push(
+                    Exclusion::pool(token_addr, &pair.pair_address, ExclusionStage::Volume, "24h volume below min_volume_h24")
+                        .with_numbers(vol, config.min_volume_h24),
+                );
+            } else {
                 verified_pools.push(DiscoveredPool {
                     pool_address: pair.pair_address.clone(),
                     dex_type,
@@ -383,12 +664,17 @@ impl DiscoveryEngine {
                     liquidity_usd: liq,
                     volume_h24: vol,
                     sol_side: sol_side.to_string(),
+                    price_sol,
                 });
             }
         }
 
         if verified_pools.len() < 2 {
-            return Ok(None);
+            exclusions.push(
+                Exclusion::token(token_addr, ExclusionStage::TooFewPools, "fewer than 2 SOL pools passed the filters")
+                    .with_numbers(verified_pools.len() as f64, 2.0),
+            );
+            return Ok((None, exclusions));
         }
 
         verified_pools.sort_by(|a, b| b.liquidity_usd.partial_cmp(&a.liquidity_usd).unwrap());
@@ -397,12 +683,346 @@ impl DiscoveryEngine {
         info!("[{}/{}] {}: {} pools, ${:.0} liquidity", 
             idx + 1, total_tokens, token_symbol, verified_pools.len(), total_liq);
 
-        Ok(Some(DiscoveredToken {
+        let transfer_hook_program = fetch_transfer_hook_program(rpc_client, token_addr);
+        if let Some(program) = &transfer_hook_program {
+            warn!("[{}/{}] {} has a Token-2022 transfer hook: {}", idx + 1, total_tokens, token_symbol, program);
+        }
+
+        let token = DiscoveredToken {
             token_address: token_addr.to_string(),
             token_name,
             token_symbol,
             total_liquidity: total_liq,
             pools: verified_pools,
-        }))
+            transfer_hook_program,
+        };
+        Ok((Some(token), exclusions))
     }
-}
\ No newline at end of file
+}
+
+/// `discovery-explain` subcommand: why a token is or is not in the discovery output
+pub async fn run_discovery_explain(rpc_url: &str, config: DiscoveryConfig, mint: &str) -> Result<()> {
+    info!(
+        "🔍 Explaining {} with min_liquidity_usd {} and min_volume_h24 {}",
+        mint, config.min_liquidity_usd, config.min_volume_h24
+    );
+    DiscoveryEngine::new(rpc_url.to_string(), config).explain_token(mint).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve GeckoTerminal-shaped responses on loopback: the status of each
+    /// route is fixed, unknown routes answer 404. Returns the API base URL.
+    async fn mock_gecko(routes: &'static [(&'static str, u16, &'static str)]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0u8; 4096];
+                let read = stream.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = routes
+                    .iter()
+                    .find(|(route, _, _)| path == format!("/api/v2/{}", route))
+                    .map_or((404, ""), |(_, status, body)| (*status, *body));
+                let response = format!(
+                    "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}/api/v2", address)
+    }
+
+    const POOL: &str = r#"{"data":[{"attributes":{"name":"BONK / SOL","address":"11111111111111111111111111111111","reserve_in_usd":"1000","volume_usd":{"h24":"500"}},"relationships":null}]}"#;
+
+    async fn engine() -> DiscoveryEngine {
+        let base = mock_gecko(&[("networks/solana/pools", 200, POOL), ("networks/solana/broken", 500, "")]).await;
+        DiscoveryEngine::new("http://127.0.0.1:1".to_string(), DiscoveryConfig::default()).with_gecko_api_base(base)
+    }
+
+    #[tokio::test]
+    async fn a_missing_endpoint_yields_an_empty_page() {
+        let pools = engine().await.fetch_gecko_pools("networks/solana/trending_pools").await.unwrap();
+        assert!(pools.is_empty());
+    }
+
+    #[tokio::test]
+    async fn other_error_statuses_still_fail_the_fetch() {
+        let error = engine().await.fetch_gecko_pools("networks/solana/broken").await.unwrap_err();
+        assert!(error.to_string().contains("500"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn a_found_endpoint_returns_its_pools() {
+        let pools = engine().await.fetch_gecko_pools("networks/solana/pools").await.unwrap();
+        assert_eq!(pools.len(), 1);
+        assert_eq!(pools[0].attributes.name, "BONK / SOL");
+    }
+
+    const NOW: u64 = 1_700_000_000;
+
+    /// Answers every request with a 500 and counts them. Returns the API base URL.
+    async fn failing_gecko() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::Relaxed);
+                let _ = stream.read(&mut [0u8; 4096]).await;
+                let _ = stream.write_all(b"HTTP/1.1 500 MOCK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        (format!("http://{}/api/v2", address), requests)
+    }
+
+    fn saved(age_minutes: u64) -> DiscoveredPools {
+        let pool = DiscoveredPool {
+            pool_address: Pubkey::new_unique().to_string(),
+            dex_type: "raydium".to_string(),
+            program_id: String::new(),
+            liquidity_usd: 10_000.0,
+            volume_h24: 10_000.0,
+            sol_side: "quote".to_string(),
+            price_sol: 0.001,
+        };
+        let token = DiscoveredToken {
+            token_address: Pubkey::new_unique().to_string(),
+            token_name: "SAVED".to_string(),
+            token_symbol: "SAVED".to_string(),
+            total_liquidity: pool.liquidity_usd,
+            pools: vec![pool],
+            transfer_hook_program: None,
+        };
+        DiscoveredPools { timestamp: NOW - age_minutes * 60, token_count: 1, tokens: vec![token] }
+    }
+
+    async fn startup_engine() -> (DiscoveryEngine, Arc<AtomicUsize>) {
+        let (base, requests) = failing_gecko().await;
+        let output_file = std::env::temp_dir().join(format!("{}_discovered_pools.json", Pubkey::new_unique()));
+        let config = DiscoveryConfig {
+            output_file: output_file.display().to_string(),
+            archive_retention: 0,
+            max_results_age_minutes: 15,
+            ..DiscoveryConfig::default()
+        };
+        let engine = DiscoveryEngine::new("http://127.0.0.1:1".to_string(), config)
+            .with_clock(Arc::new(crate::clock::MockClock::new(NOW)))
+            .with_gecko_api_base(base);
+        (engine, requests)
+    }
+
+    #[tokio::test]
+    async fn a_fresh_saved_discovery_is_used_without_running_discovery() {
+        let (engine, requests) = startup_engine().await;
+        let saved = saved(5);
+        let markets = DiscoveryEngine::convert_to_markets(&saved);
+
+        let (results, first_run) = engine.startup_results(Some(saved), Duration::from_secs(900)).await;
+        assert_eq!(DiscoveryEngine::convert_to_markets(&results.unwrap()), markets);
+        // The first scheduled discovery waits for the saved one to go stale
+        assert_eq!(first_run, Duration::from_secs(10 * 60));
+        assert_eq!(requests.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn a_stale_saved_discovery_is_only_the_fallback_of_a_failed_run() {
+        let (engine, requests) = startup_engine().await;
+        let saved = saved(20);
+        let markets = DiscoveryEngine::convert_to_markets(&saved);
+
+        let (results, first_run) = engine.startup_results(Some(saved), Duration::from_secs(900)).await;
+        assert!(requests.load(Ordering::Relaxed) > 0, "a stale file must not skip discovery");
+        assert_eq!(DiscoveryEngine::convert_to_markets(&results.unwrap()), markets);
+        // Retried right away rather than after a full interval
+        assert_eq!(first_run, Duration::ZERO);
+
+        let (results, _) = engine.startup_results(None, Duration::from_secs(900)).await;
+        assert!(results.is_none());
+    }
+
+    const TOKEN: &str = "Bonk1111111111111111111111111111111111111111";
+
+    fn leak<T: ?Sized>(value: Box<T>) -> &'static T {
+        Box::leak(value)
+    }
+
+    fn pair(quote: &str, liquidity_usd: f64, volume_h24: f64) -> String {
+        format!(
+            r#"{{"pairAddress":"{}","dexId":"raydium","liquidity":{{"usd":{}}},"volume":{{"h24":{}}},"baseToken":{{"address":"{}","symbol":"BONK"}},"quoteToken":{{"address":"{}","symbol":"Q"}},"priceNative":"0.001"}}"#,
+            Pubkey::new_unique(),
+            liquidity_usd,
+            volume_h24,
+            TOKEN,
+            quote
+        )
+    }
+
+    /// A client whose first account read returns an account owned by `owner`
+    fn rpc_with_owner(owner: Option<&str>) -> Arc<RpcClient> {
+        use solana_client::rpc_request::RpcRequest;
+        let mocks = owner.map(|owner| {
+            let account = serde_json::json!({
+                "context": { "slot": 1 },
+                "value": { "data": ["", "base64"], "executable": false, "lamports": 1, "owner": owner, "rentEpoch": 0, "space": 0 },
+            });
+            (RpcRequest::GetAccountInfo, account)
+        });
+        Arc::new(RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks.into_iter().collect()))
+    }
+
+    /// `process_token` for TOKEN against a Dexscreener answering `pairs`
+    async fn process(pairs: &[String], owner: Option<&str>) -> (Option<DiscoveredToken>, Vec<Exclusion>) {
+        let body = leak(format!("[{}]", pairs.join(",")).into_boxed_str());
+        let route = leak(format!("solana/{}", TOKEN).into_boxed_str());
+        let base = mock_gecko(leak(vec![(route, 200, body)].into_boxed_slice())).await;
+        DiscoveryEngine::process_token(0, 1, &rpc_with_owner(owner), &DiscoveryConfig::default(), &base, TOKEN).await.unwrap()
+    }
+
+    fn stages(exclusions: &[Exclusion]) -> Vec<ExclusionStage> {
+        exclusions.iter().map(|exclusion| exclusion.stage).collect()
+    }
+
+    #[test]
+    fn quote_mints_are_excluded_from_the_candidates_once() {
+        let gecko_pool = |mint: &str| -> GeckoPoolData {
+            serde_json::from_value(serde_json::json!({
+                "attributes": { "name": "", "address": "" },
+                "relationships": { "base_token": { "data": { "id": format!("solana_{}", mint) } } },
+            }))
+            .unwrap()
+        };
+        let (candidates, exclusions) = candidate_tokens(&[gecko_pool(SOL_MINT), gecko_pool(TOKEN), gecko_pool(SOL_MINT), gecko_pool(USDC_MINT)]);
+        assert_eq!(candidates, HashSet::from([TOKEN.to_string()]));
+        assert_eq!(stages(&exclusions), vec![ExclusionStage::IgnoredMint, ExclusionStage::IgnoredMint]);
+        assert_eq!(exclusions[0].token_address, SOL_MINT);
+        assert!(exclusions.iter().all(|exclusion| exclusion.pool_address.is_none()));
+    }
+
+    #[tokio::test]
+    async fn a_failed_or_unreadable_dexscreener_answer_drops_the_token() {
+        let config = DiscoveryConfig::default();
+        let (token, exclusions) =
+            DiscoveryEngine::process_token(0, 1, &rpc_with_owner(None), &config, "http://127.0.0.1:1", TOKEN).await.unwrap();
+        assert!(token.is_none());
+        assert_eq!(stages(&exclusions), vec![ExclusionStage::Fetch]);
+        assert!(exclusions[0].reason.starts_with("Dexscreener request"), "{}", exclusions[0].reason);
+
+        let route = leak(format!("solana/{}", TOKEN).into_boxed_str());
+        let base = mock_gecko(leak(vec![(route, 200, "not json")].into_boxed_slice())).await;
+        let (token, exclusions) = DiscoveryEngine::process_token(0, 1, &rpc_with_owner(None), &config, &base, TOKEN).await.unwrap();
+        assert!(token.is_none());
+        assert_eq!(stages(&exclusions), vec![ExclusionStage::Parse]);
+        assert_eq!(exclusions[0].token_address, TOKEN);
+    }
+
+    #[tokio::test(flavor = "multi_thread")] // The blocking RpcClient needs it
+    async fn each_pool_check_records_its_stage_and_numbers() {
+        let no_tokens = format!(r#"{{"pairAddress":"{}","dexId":"raydium"}}"#, Pubkey::new_unique());
+        let cases = [
+            (pair(USDC_MINT, 1e6, 1e6), None, ExclusionStage::NotSolPair, None),
+            (no_tokens, None, ExclusionStage::NotSolPair, None),
+            (pair(SOL_MINT, 1e6, 1e6), None, ExclusionStage::Fetch, None),
+            (pair(SOL_MINT, 1e6, 1e6), Some("11111111111111111111111111111111"), ExclusionStage::UnsupportedDex, None),
+            (pair(SOL_MINT, 100.0, 1e6), Some(RAYDIUM_V4_PROGRAM), ExclusionStage::Liquidity, Some((100.0, 5_000.0))),
+            (pair(SOL_MINT, 1e6, 10.0), Some(RAYDIUM_V4_PROGRAM), ExclusionStage::Volume, Some((10.0, 1_000.0))),
+        ];
+        for (pair, owner, stage, numbers) in cases {
+            let pool_address = serde_json::from_str::<serde_json::Value>(&pair).unwrap()["pairAddress"].as_str().unwrap().to_string();
+            let (token, exclusions) = process(&[pair], owner).await;
+            assert!(token.is_none());
+            // The pool's own record, then the token left without enough pools
+            assert_eq!(stages(&exclusions), vec![stage, ExclusionStage::TooFewPools], "{:?}", exclusions);
+            assert_eq!(exclusions[0].pool_address.as_deref(), Some(pool_address.as_str()));
+            assert_eq!(exclusions[0].value.zip(exclusions[0].threshold), numbers, "{:?}", stage);
+            assert_eq!((exclusions[1].pool_address.as_ref(), exclusions[1].value, exclusions[1].threshold), (None, Some(0.0), Some(2.0)));
+        }
+    }
+
+    #[tokio::test]
+    async fn closing_the_semaphore_mid_run_returns_the_checks_already_in_flight() {
+        // Dexscreener closes the semaphore on the first request it sees, then answers with no pairs
+        let semaphore = Arc::new(Semaphore::new(1));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let closer = semaphore.clone();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::Relaxed);
+                closer.close();
+                let _ = stream.read(&mut [0u8; 4096]).await;
+                let _ = stream.write_all(b"HTTP/1.1 200 MOCK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]").await;
+            }
+        });
+        let engine = DiscoveryEngine::new("http://127.0.0.1:1".to_string(), DiscoveryConfig::default())
+            .with_dexscreener_api_base(format!("http://{}", address));
+        let tokens: Vec<String> = (0..3).map(|_| Pubkey::new_unique().to_string()).collect();
+
+        let (results, exclusions, interrupted) = engine.verify_tokens(tokens.clone(), semaphore).await;
+        assert!(interrupted);
+        assert!(results.is_empty());
+        // Only the first token was scheduled, and its check still ran to completion
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+        assert_eq!(stages(&exclusions), vec![ExclusionStage::TooFewPools]);
+        assert_eq!(exclusions[0].token_address, tokens[0]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")] // The blocking RpcClient needs it
+    async fn a_failed_api_run_falls_back_to_the_saved_pools_still_on_chain() {
+        use crate::rpc::serve_accounts;
+        use solana_sdk::account::Account;
+        let owned_by = |owner: &str| Account { lamports: 1, owner: owner.parse().unwrap(), ..Account::default() };
+        let (live, moved, closed) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let node = serve_accounts(HashMap::from([
+            (live, owned_by(RAYDIUM_V4_PROGRAM)),
+            (moved, owned_by("11111111111111111111111111111111")),
+        ]));
+        // One token keeps two live pools, the other is left with one
+        let mut previous = saved(60);
+        let pool = |address: Pubkey| DiscoveredPool {
+            pool_address: address.to_string(),
+            program_id: RAYDIUM_V4_PROGRAM.to_string(),
+            ..previous.tokens[0].pools[0].clone()
+        };
+        let kept = DiscoveredToken { pools: vec![pool(live), pool(live), pool(closed)], ..previous.tokens[0].clone() };
+        let dropped = DiscoveredToken { pools: vec![pool(live), pool(moved)], ..previous.tokens[0].clone() };
+        previous.tokens = vec![kept, dropped];
+        previous.token_count = 2;
+
+        let (base, requests) = failing_gecko().await;
+        let output_file = std::env::temp_dir().join(format!("{}_discovered_pools.json", Pubkey::new_unique()));
+        let config = DiscoveryConfig {
+            output_file: output_file.display().to_string(),
+            onchain_fallback: true,
+            ..DiscoveryConfig::default()
+        };
+        atomic_write_json(&config.output_file, &previous).await.unwrap();
+        let engine = DiscoveryEngine::new(node, config.clone()).with_gecko_api_base(base.clone());
+
+        let results = engine.run_discovery_with_fallback().await.unwrap();
+        assert!(requests.load(Ordering::Relaxed) > 0, "the API is tried first");
+        assert_eq!(results.timestamp, previous.timestamp);
+        assert_eq!(results.token_count, 1);
+        assert_eq!(results.tokens[0].pools.len(), 2);
+        assert!(results.tokens[0].pools.iter().all(|pool| pool.pool_address == live.to_string()));
+
+        // Without the fallback the API error is returned as is
+        let engine = DiscoveryEngine::new("http://127.0.0.1:1".to_string(), DiscoveryConfig { onchain_fallback: false, ..config })
+            .with_gecko_api_base(base);
+        assert!(engine.run_discovery_with_fallback().await.is_err());
+        std::fs::remove_file(output_file).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
+pub mod archive;
 pub mod engine;
 pub mod types;
 
+pub use archive::*;
 pub use engine::*;
 pub use types::*;
\ No newline at end of file
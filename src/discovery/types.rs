@@ -15,6 +15,8 @@ pub struct DiscoveredToken {
     pub token_symbol: String,
     pub total_liquidity: f64,
     pub pools: Vec<DiscoveredPool>,
+    #[serde(default)]
+    pub transfer_hook_program: Option<String>, // Token-2022 transfer hook; excluded from routing unless allowed
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -24,7 +26,146 @@ pub struct DiscoveredPool {
     pub program_id: String,
     pub liquidity_usd: f64,
     pub volume_h24: f64,
+    /// "base" or "quote": SOL's side in Dexscreener's labeling of the pair,
+    /// used to normalize `price_sol`. This is not the on-chain mint order; pool
+    /// initialization reads the pool account's mints to find the SOL vault.
     pub sol_side: String,
+    #[serde(default)]
+    pub price_sol: f64, // Token price in SOL as reported by Dexscreener, 0 if unknown
+}
+
+/// Pipeline stage that dropped a token or one of its pools
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionStage {
+    IgnoredMint,    // SOL, USDC and USDT are quote tokens, not candidates
+    Fetch,          // Dexscreener or RPC request failed
+    Parse,          // Dexscreener response did not decode
+    NotSolPair,     // Pair is not quoted against SOL
+    UnsupportedDex, // Pool program is not one the bot can trade
+    Liquidity,      // Below min_liquidity_usd
+    Volume,         // Below min_volume_h24
+    TooFewPools,    // Fewer than 2 SOL pools left, nothing to arbitrage
+}
+
+/// Why a discovery candidate, or one of its pools, is missing from the output
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Exclusion {
+    pub token_address: String,
+    #[serde(default)]
+    pub pool_address: Option<String>, // None when the whole token was dropped
+    pub stage: ExclusionStage,
+    pub reason: String,
+    #[serde(default)]
+    pub value: Option<f64>, // The number that failed the check
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+impl Exclusion {
+    pub fn token(token_address: &str, stage: ExclusionStage, reason: impl Into<String>) -> Self {
+        Self {
+            token_address: token_address.to_string(),
+            pool_address: None,
+            stage,
+            reason: reason.into(),
+            value: None,
+            threshold: None,
+        }
+    }
+
+    pub fn pool(token_address: &str, pool_address: &str, stage: ExclusionStage, reason: impl Into<String>) -> Self {
+        Self {
+            pool_address: Some(pool_address.to_string()),
+            ..Self::token(token_address, stage, reason)
+        }
+    }
+
+    pub fn with_numbers(mut self, value: f64, threshold: f64) -> Self {
+        self.value = Some(value);
+        self.threshold = Some(threshold);
+        self
+    }
+}
+
+/// Exclusions of one discovery run, written next to the discovery output
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiscoveryExclusions {
+    pub timestamp: u64,
+    pub total: usize, // Exclusions recorded before the cap
+    pub exclusions: Vec<Exclusion>,
+}
+
+impl DiscoveryExclusions {
+    /// Keeps at most `cap` records, whole-token exclusions first since they
+    /// explain a missing token on their own
+    pub fn new(timestamp: u64, mut exclusions: Vec<Exclusion>, cap: usize) -> Self {
+        let total = exclusions.len();
+        exclusions.sort_by(|a, b| {
+            (a.pool_address.is_some(), &a.token_address, &a.pool_address)
+                .cmp(&(b.pool_address.is_some(), &b.token_address, &b.pool_address))
+        });
+        exclusions.truncate(cap);
+        Self { timestamp, total, exclusions }
+    }
+
+    pub fn for_token<'a>(&'a self, token_address: &'a str) -> impl Iterator<Item = &'a Exclusion> + 'a {
+        self.exclusions.iter().filter(move |e| e.token_address == token_address)
+    }
+}
+
+/// Arbitrage potential of a discovered token, see `DiscoveredPools::rank_by_arb_potential`
+#[derive(Debug, Clone)]
+pub struct ArbPotential {
+    pub token_address: String,
+    pub token_symbol: String,
+    pub pool_count: usize,
+    pub max_weighted_spread_bps: f64,
+    pub score: f64,
+}
+
+impl DiscoveredPools {
+    /// Rank tokens by how likely their pools are to diverge enough to arbitrage.
+    ///
+    /// For every pair of priced pools the spread `|p1 - p2| / min(p1, p2)` is
+    /// weighted by `min(liq) / max(liq)`, so a gap between a deep pool and a
+    /// dust pool counts for little. The score is the largest weighted spread
+    /// scaled by `1 + ln(pool_count)` to favour tokens with more venues; only
+    /// priced pools count, since an unpriced one cannot be compared.
+    pub fn rank_by_arb_potential(&self) -> Vec<ArbPotential> {
+        let mut ranked: Vec<ArbPotential> = self
+            .tokens
+            .iter()
+            .map(|token| {
+                let priced: Vec<&DiscoveredPool> = token
+                    .pools
+                    .iter()
+                    .filter(|p| p.price_sol > 0.0 && p.liquidity_usd > 0.0)
+                    .collect();
+
+                let mut max_weighted_spread_bps: f64 = 0.0;
+                for (i, a) in priced.iter().enumerate() {
+                    for b in &priced[i + 1..] {
+                        let spread = (a.price_sol - b.price_sol).abs() / a.price_sol.min(b.price_sol);
+                        let weight = a.liquidity_usd.min(b.liquidity_usd) / a.liquidity_usd.max(b.liquidity_usd);
+                        max_weighted_spread_bps = max_weighted_spread_bps.max(spread * weight * 10_000.0);
+                    }
+                }
+
+                let pool_count = priced.len();
+                ArbPotential {
+                    token_address: token.token_address.clone(),
+                    token_symbol: token.token_symbol.clone(),
+                    pool_count,
+                    max_weighted_spread_bps,
+                    score: max_weighted_spread_bps * (1.0 + (pool_count.max(1) as f64).ln()),
+                }
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        ranked
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +175,28 @@ pub struct DiscoveryConfig {
     pub min_liquidity_usd: f64,
     pub min_volume_h24: f64,
     pub output_file: String,
+    pub archive_dir: String,
+    pub archive_retention: usize,
+    pub max_results_age_minutes: u64,
+    pub exclusions_file: String,
+    pub onchain_fallback: bool,
+}
+
+impl From<&crate::config::DiscoveryConfig> for DiscoveryConfig {
+    fn from(config: &crate::config::DiscoveryConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            interval_minutes: config.interval_minutes,
+            min_liquidity_usd: config.min_liquidity_usd,
+            min_volume_h24: config.min_volume_h24,
+            output_file: config.output_file.clone(),
+            archive_dir: config.archive_dir.clone(),
+            archive_retention: config.archive_retention,
+            max_results_age_minutes: config.max_results_age_minutes,
+            exclusions_file: config.exclusions_file.clone(),
+            onchain_fallback: config.onchain_fallback,
+        }
+    }
 }
 
 impl Default for DiscoveryConfig {
@@ -44,6 +207,75 @@ impl Default for DiscoveryConfig {
             min_liquidity_usd: 5000.0,
             min_volume_h24: 1000.0,
             output_file: "discovered_pools.json".to_string(),
+            archive_dir: "discovery_archive".to_string(),
+            archive_retention: 96,
+            max_results_age_minutes: 15,
+            exclusions_file: "discovery_exclusions.json".to_string(),
+            onchain_fallback: false,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(price_sol: f64, liquidity_usd: f64) -> DiscoveredPool {
+        DiscoveredPool {
+            pool_address: String::new(),
+            dex_type: "raydium".to_string(),
+            program_id: String::new(),
+            liquidity_usd,
+            volume_h24: 10_000.0,
+            sol_side: "quote".to_string(),
+            price_sol,
+        }
+    }
+
+    fn token(symbol: &str, pools: Vec<DiscoveredPool>) -> DiscoveredToken {
+        DiscoveredToken {
+            token_address: symbol.to_string(),
+            token_name: symbol.to_string(),
+            token_symbol: symbol.to_string(),
+            total_liquidity: pools.iter().map(|p| p.liquidity_usd).sum(),
+            pools,
+            transfer_hook_program: None,
+        }
+    }
+
+    fn rank(tokens: Vec<DiscoveredToken>) -> Vec<ArbPotential> {
+        DiscoveredPools { timestamp: 0, token_count: tokens.len(), tokens }.rank_by_arb_potential()
+    }
+
+    #[test]
+    fn divergent_pools_rank_above_aligned_ones() {
+        let ranked = rank(vec![
+            token("ALIGNED", vec![pool(0.010, 50_000.0), pool(0.010, 50_000.0), pool(0.0100001, 40_000.0)]),
+            token("DIVERGENT", vec![pool(0.010, 50_000.0), pool(0.0103, 40_000.0)]),
+        ]);
+        assert_eq!(ranked[0].token_symbol, "DIVERGENT");
+        assert!((ranked[0].max_weighted_spread_bps - 300.0 * 0.8).abs() < 1e-6);
+        assert!(ranked[1].max_weighted_spread_bps < 1.0);
+    }
+
+    #[test]
+    fn dust_pool_gap_counts_for_little() {
+        let ranked = rank(vec![
+            token("DUST", vec![pool(0.010, 100_000.0), pool(0.020, 100.0)]),
+            token("DEEP", vec![pool(0.010, 100_000.0), pool(0.0105, 90_000.0)]),
+        ]);
+        assert_eq!(ranked[0].token_symbol, "DEEP");
+    }
+
+    #[test]
+    fn unpriced_pools_do_not_inflate_the_venue_bonus() {
+        let unpriced = || pool(0.0, 50_000.0);
+        let ranked = rank(vec![token(
+            "T",
+            vec![pool(0.010, 50_000.0), pool(0.0101, 50_000.0), unpriced(), unpriced(), unpriced()],
+        )]);
+        assert_eq!(ranked[0].pool_count, 2);
+        let spread_bps = ranked[0].max_weighted_spread_bps;
+        assert!((ranked[0].score - spread_bps * (1.0 + 2f64.ln())).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::engine::types::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::info;
+
+/// How closely the graph price for a venue tracks what a swap would actually return
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum QuoteKind {
+    Exact,       // Constant-product reserves read straight from the pool
+    Approximate, // Spot price only (tick/bin crossing, virtual reserves or proprietary curves ignored)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DexCapability {
+    pub quote: QuoteKind,
+    pub execute: bool,  // A swap instruction builder exists for this venue
+    pub simulate: bool, // Cycles through this venue can be simulated before sending
+    pub quote_haircut_bps: u64, // Deducted from every modeled output through this venue
+}
+
+impl DexCapability {
+    pub fn live(quote: QuoteKind) -> Self {
+        Self { quote, execute: true, simulate: true, quote_haircut_bps: 0 }
+    }
+
+    pub fn observational(quote: QuoteKind) -> Self {
+        Self { quote, execute: false, simulate: false, quote_haircut_bps: 0 }
+    }
+}
+
+/// How the graph prices each venue. Only plain constant-product reserves
+/// (and the pump.fun curve's virtual reserves) quote exactly; Heaven, Futarchy
+/// and Humidifi are priced from vault ratios that ignore their own curves.
+pub fn quote_kind(dex_type: DexType) -> QuoteKind {
+    match dex_type {
+        DexType::RaydiumV4 | DexType::RaydiumCp | DexType::Pump | DexType::MeteoraDamm | DexType::PumpBondingCurve => {
+            QuoteKind::Exact
+        }
+        DexType::RaydiumClmm
+        | DexType::MeteoraDlmm
+        | DexType::MeteoraDammV2
+        | DexType::Whirlpool
+        | DexType::Vertigo
+        | DexType::Heaven
+        | DexType::Futarchy
+        | DexType::Humidifi
+        | DexType::PancakeSwap
+        | DexType::Byreal
+        | DexType::Lifinity
+        | DexType::Phoenix => QuoteKind::Approximate,
+    }
+}
+
+/// Per-venue capabilities. Venues without an entry are treated as
+/// observational: they are priced in the graph but never executed.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityRegistry {
+    capabilities: HashMap<DexType, DexCapability>,
+}
+
+impl CapabilityRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry derived from the venues `create_swap_instruction` lays out
+    /// accounts for; every other venue is registered as observational
+    pub fn from_registered_builders() -> Self {
+        Self::from_executable(&crate::transaction::swap_instruction_venues())
+    }
+
+    pub fn from_executable(executable: &HashSet<DexType>) -> Self {
+        let mut registry = Self::new();
+        for dex_type in DexType::ALL {
+            let quote = quote_kind(dex_type);
+            let capability = if executable.contains(&dex_type) {
+                DexCapability::live(quote)
+            } else {
+                DexCapability::observational(quote)
+            };
+            registry.register(dex_type, capability);
+        }
+        registry
+    }
+
+    /// Haircut approximate quoters by `approximate_bps` and exact ones by
+    /// nothing, unless a venue has an explicit override.
+    pub fn apply_quote_haircuts(&mut self, approximate_bps: u64, overrides: &HashMap<DexType, u64>) {
+        for (dex_type, capability) in self.capabilities.iter_mut() {
+            capability.quote_haircut_bps = overrides.get(dex_type).copied().unwrap_or(match capability.quote {
+                QuoteKind::Exact => 0,
+                QuoteKind::Approximate => approximate_bps,
+            });
+        }
+    }
+
+    pub fn quote_haircuts(&self) -> HashMap<DexType, u64> {
+        self.capabilities
+            .iter()
+            .filter(|(_, capability)| capability.quote_haircut_bps > 0)
+            .map(|(dex_type, capability)| (*dex_type, capability.quote_haircut_bps))
+            .collect()
+    }
+
+    pub fn register(&mut self, dex_type: DexType, capability: DexCapability) {
+        self.capabilities.insert(dex_type, capability);
+    }
+
+    pub fn get(&self, dex_type: DexType) -> DexCapability {
+        self.capabilities
+            .get(&dex_type)
+            .copied()
+            .unwrap_or(DexCapability::observational(QuoteKind::Approximate))
+    }
+
+    pub fn is_executable(&self, cycle: &ArbitrageCycle) -> bool {
+        cycle.legs.iter().all(|leg| self.get(leg.dex_type).execute)
+    }
+
+    /// Tag cycles with non-executable legs as observational and move them
+    /// behind the actionable ones, keeping the existing order within each group.
+    pub fn classify(&self, cycles: &mut [ArbitrageCycle]) {
+        for cycle in cycles.iter_mut() {
+            cycle.observational = !self.is_executable(cycle);
+        }
+        cycles.sort_by_key(|cycle| cycle.observational);
+    }
+
+    /// Every registered venue, in a stable order for the status file
+    pub fn snapshot(&self) -> BTreeMap<DexType, DexCapability> {
+        self.capabilities.iter().map(|(dex_type, capability)| (*dex_type, *capability)).collect()
+    }
+
+    pub fn log_capabilities(&self) {
+        let mut entries: Vec<_> = self.capabilities.iter().collect();
+        entries.sort_by_key(|(dex_type, _)| format!("{:?}", dex_type));
+        for (dex_type, capability) in entries {
+            info!(
+                "🧩 {:?}: quote={:?}, execute={}, simulate={}, haircut={} bps",
+                dex_type, capability.quote, capability.execute, capability.simulate, capability.quote_haircut_bps
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_follows_the_swap_instruction_builder() {
+        let registry = CapabilityRegistry::from_registered_builders();
+
+        for dex_type in [DexType::RaydiumV4, DexType::MeteoraDlmm, DexType::Heaven, DexType::Futarchy, DexType::Humidifi] {
+            assert!(registry.get(dex_type).execute, "{:?} has a builder", dex_type);
+        }
+        // Priced in the graph, but the executor instruction has no accounts for them
+        for dex_type in [DexType::Lifinity, DexType::Phoenix, DexType::PumpBondingCurve] {
+            assert!(!registry.get(dex_type).execute, "{:?} has no builder", dex_type);
+        }
+        assert_eq!(registry.snapshot().len(), DexType::ALL.len());
+    }
+
+    #[test]
+    fn vault_ratio_venues_are_approximate() {
+        let registry = CapabilityRegistry::from_executable(&DexType::ALL.into_iter().collect());
+
+        for dex_type in [DexType::Heaven, DexType::Futarchy, DexType::Humidifi] {
+            assert_eq!(registry.get(dex_type).quote, QuoteKind::Approximate);
+        }
+        assert_eq!(registry.get(DexType::RaydiumV4).quote, QuoteKind::Exact);
+    }
+
+    #[test]
+    fn approximate_venues_take_the_default_haircut() {
+        let mut registry = CapabilityRegistry::from_executable(&HashSet::new());
+        registry.apply_quote_haircuts(25, &HashMap::from([(DexType::Heaven, 40)]));
+
+        assert_eq!(registry.get(DexType::RaydiumV4).quote_haircut_bps, 0);
+        assert_eq!(registry.get(DexType::Whirlpool).quote_haircut_bps, 25);
+        assert_eq!(registry.get(DexType::Heaven).quote_haircut_bps, 40);
+    }
+}
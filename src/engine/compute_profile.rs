@@ -0,0 +1,323 @@
+use crate::persist::atomic_write_json;
+use crate::engine::types::DexType;
+use crate::rpc::RpcLike;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionStatusMeta;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Weight of a new sample in the per-venue moving average
+const EMA_ALPHA: f64 = 0.2;
+/// Headroom added on top of the summed estimates when sizing a compute unit limit
+const LIMIT_HEADROOM_PERCENT: u64 = 20;
+/// Per-transaction compute unit cap enforced by the runtime
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// Compute units consumed by one program invocation, as reported in the
+/// transaction logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramConsumption {
+    pub program_id: Pubkey,
+    pub depth: usize,
+    pub consumed: u64,
+}
+
+/// Per-venue compute usage extracted from one landed transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComputeProfile {
+    pub legs: Vec<(DexType, u64)>,
+    /// Units spent by the outermost invocation that are not attributed to a venue leg
+    pub overhead: u64,
+}
+
+/// Parse `Program <id> consumed N of M compute units` lines, tracking the
+/// invoke depth from the surrounding `Program <id> invoke [d]` lines.
+pub fn parse_compute_logs(logs: &[String]) -> Vec<ProgramConsumption> {
+    let mut stack: Vec<Pubkey> = Vec::new();
+    let mut consumptions = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else { continue };
+        let mut parts = rest.split_whitespace();
+        let (Some(id), Some(verb)) = (parts.next(), parts.next()) else { continue };
+        let Ok(program_id) = id.parse::<Pubkey>() else { continue };
+
+        match verb {
+            "invoke" => stack.push(program_id),
+            "consumed" => {
+                if let Some(consumed) = parts.next().and_then(|n| n.parse::<u64>().ok()) {
+                    consumptions.push(ProgramConsumption {
+                        program_id,
+                        depth: stack.len(),
+                        consumed,
+                    });
+                }
+            }
+            "success" | "failed:" if stack.last() == Some(&program_id) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    consumptions
+}
+
+/// Attribute parsed consumption to venues. Every invocation of a known venue
+/// program is one leg; whatever the outermost non-venue invocations (the
+/// executor) spent beyond those legs is overhead.
+pub fn attribute_compute(consumptions: &[ProgramConsumption]) -> ComputeProfile {
+    let mut profile = ComputeProfile::default();
+    let mut outer_total = 0u64;
+
+    for consumption in consumptions {
+        match DexType::from_program_id(&consumption.program_id) {
+            Some(dex_type) => profile.legs.push((dex_type, consumption.consumed)),
+            None if consumption.depth == 1 && !is_compute_budget(&consumption.program_id) => {
+                outer_total += consumption.consumed;
+            }
+            None => {}
+        }
+    }
+
+    let leg_total: u64 = profile.legs.iter().map(|(_, units)| units).sum();
+    profile.overhead = outer_total.saturating_sub(leg_total);
+    profile
+}
+
+fn is_compute_budget(program_id: &Pubkey) -> bool {
+    *program_id == solana_sdk::compute_budget::id()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ComputeEstimate {
+    pub units: f64,
+    pub samples: u64,
+}
+
+impl ComputeEstimate {
+    fn record(&mut self, units: u64) {
+        self.units = if self.samples == 0 {
+            units as f64
+        } else {
+            EMA_ALPHA * units as f64 + (1.0 - EMA_ALPHA) * self.units
+        };
+        self.samples += 1;
+    }
+}
+
+/// Adaptive compute unit estimates per venue, learned from landed transactions
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ComputeUnitTable {
+    pub venues: BTreeMap<DexType, ComputeEstimate>,
+    pub overhead: Option<ComputeEstimate>,
+}
+
+impl ComputeUnitTable {
+    /// Load a persisted table, starting empty if the file does not exist yet
+    pub async fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .context(format!("Failed to read compute profile {}", path))?;
+        serde_json::from_str(&content).context(format!("Failed to parse compute profile {}", path))
+    }
+
+    pub async fn save(&self, path: &str) -> Result<()> {
+        atomic_write_json(path, self).await
+    }
+
+    pub fn record(&mut self, profile: &ComputeProfile) {
+        for (dex_type, units) in &profile.legs {
+            self.venues
+                .entry(*dex_type)
+                .or_insert(ComputeEstimate { units: 0.0, samples: 0 })
+                .record(*units);
+        }
+        if !profile.legs.is_empty() {
+            self.overhead
+                .get_or_insert(ComputeEstimate { units: 0.0, samples: 0 })
+                .record(profile.overhead);
+        }
+    }
+
+    pub fn estimate(&self, dex_type: DexType) -> Option<u64> {
+        self.venues.get(&dex_type).map(|e| e.units.ceil() as u64)
+    }
+
+    /// Compute unit limit for a route through `legs`. Falls back to
+    /// `fallback` until every venue on the route has been measured.
+    pub fn compute_unit_limit(&self, legs: &[DexType], fallback: u32) -> u32 {
+        let Some(leg_units) = legs
+            .iter()
+            .map(|dex_type| self.estimate(*dex_type))
+            .sum::<Option<u64>>()
+        else {
+            return fallback;
+        };
+        let overhead = self.overhead.map_or(0, |e| e.units.ceil() as u64);
+        let limit = (leg_units + overhead) * (100 + LIMIT_HEADROOM_PERCENT) / 100;
+        limit.min(MAX_COMPUTE_UNIT_LIMIT) as u32
+    }
+
+    pub fn log(&self) {
+        if self.venues.is_empty() {
+            info!("🧮 No measured compute usage yet, using configured compute unit limit");
+            return;
+        }
+        for (dex_type, estimate) in &self.venues {
+            info!("🧮 {:?}: ~{:.0} CU per leg ({} samples)", dex_type, estimate.units, estimate.samples);
+        }
+        if let Some(overhead) = &self.overhead {
+            info!("🧮 Executor overhead: ~{:.0} CU ({} samples)", overhead.units, overhead.samples);
+        }
+    }
+}
+
+/// Fetch a landed transaction and extract its per-venue compute usage
+pub fn profile_transaction(rpc_client: &dyn RpcLike, signature: &Signature) -> Result<ComputeProfile> {
+    let tx = rpc_client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: None,
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .context(format!("Failed to fetch transaction {}", signature))?;
+
+    let meta = tx
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} has no meta", signature))?;
+    profile_meta(&meta).context(format!("Transaction {}", signature))
+}
+
+/// Per-venue compute usage from a transaction's status meta
+pub fn profile_meta(meta: &UiTransactionStatusMeta) -> Result<ComputeProfile> {
+    let logs: Option<Vec<String>> = meta.log_messages.clone().into();
+    let logs = logs.ok_or_else(|| anyhow::anyhow!("no log messages"))?;
+    Ok(attribute_compute(&parse_compute_logs(&logs)))
+}
+
+/// `profile-compute` subcommand: fold landed transactions into the persisted table
+pub async fn run_profile_compute(rpc_url: &str, table_path: &str, signatures: &[&str]) -> Result<()> {
+    let rpc_client = RpcClient::new(rpc_url.to_string());
+    let mut table = ComputeUnitTable::load(table_path).await?;
+
+    for signature in signatures {
+        let signature = signature
+            .parse::<Signature>()
+            .map_err(|e| anyhow::anyhow!("Invalid signature {}: {}", signature, e))?;
+        match profile_transaction(&rpc_client, &signature) {
+            Ok(profile) if profile.legs.is_empty() => {
+                warn!("No venue invocations found in {}", signature);
+            }
+            Ok(profile) => {
+                info!("📊 {}: {:?}, overhead {} CU", signature, profile.legs, profile.overhead);
+                table.record(&profile);
+            }
+            Err(e) => warn!("Skipping {}: {}", signature, e),
+        }
+    }
+
+    table.save(table_path).await?;
+    info!("💾 Saved compute profile to {}", table_path);
+    table.log();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Status meta of a landed two-leg cycle in `getTransaction`'s JSON shape.
+    /// The log lines follow the runtime's format for the executor invoking a
+    /// Raydium V4 and a Raydium CP swap, each of which invokes the token program.
+    const LANDED_META: &str = r#"{
+        "err": null,
+        "status": { "Ok": null },
+        "fee": 5000,
+        "preBalances": [1000000000],
+        "postBalances": [999995000],
+        "innerInstructions": [],
+        "logMessages": [
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+            "Program ComputeBudget111111111111111111111111111111 success",
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+            "Program ComputeBudget111111111111111111111111111111 success",
+            "Program MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz invoke [1]",
+            "Program 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8 invoke [2]",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [3]",
+            "Program log: Instruction: Transfer",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA consumed 4645 of 356412 compute units",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+            "Program 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8 consumed 32106 of 383874 compute units",
+            "Program 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8 success",
+            "Program CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C invoke [2]",
+            "Program log: Instruction: SwapBaseInput",
+            "Program CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C consumed 41380 of 341210 compute units",
+            "Program CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C success",
+            "Program MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz consumed 96512 of 399700 compute units",
+            "Program MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz success"
+        ],
+        "preTokenBalances": [],
+        "postTokenBalances": [],
+        "rewards": [],
+        "computeUnitsConsumed": 96812
+    }"#;
+
+    fn landed_meta() -> UiTransactionStatusMeta {
+        serde_json::from_str(LANDED_META).unwrap()
+    }
+
+    #[test]
+    fn logs_are_attributed_to_venue_legs_and_executor_overhead() {
+        let logs: Option<Vec<String>> = landed_meta().log_messages.into();
+        let consumptions = parse_compute_logs(&logs.unwrap());
+
+        let depths: Vec<(u64, usize)> = consumptions.iter().map(|c| (c.consumed, c.depth)).collect();
+        assert_eq!(depths, vec![(4645, 3), (32106, 2), (41380, 2), (96512, 1)]);
+
+        let profile = attribute_compute(&consumptions);
+        assert_eq!(profile.legs, vec![(DexType::RaydiumV4, 32106), (DexType::RaydiumCp, 41380)]);
+        assert_eq!(profile.overhead, 96512 - 32106 - 41380);
+    }
+
+    #[test]
+    fn a_profiled_meta_updates_the_table_and_the_route_limit() {
+        let mut table = ComputeUnitTable::default();
+        let route = [DexType::RaydiumV4, DexType::RaydiumCp];
+        assert_eq!(table.compute_unit_limit(&route, 400_000), 400_000);
+
+        table.record(&profile_meta(&landed_meta()).unwrap());
+
+        assert_eq!(table.estimate(DexType::RaydiumV4), Some(32106));
+        assert_eq!(table.estimate(DexType::RaydiumCp), Some(41380));
+        assert_eq!(table.overhead.unwrap().units, 23026.0);
+        assert_eq!(table.compute_unit_limit(&route, 400_000), 96512 * 120 / 100);
+        // A venue that was never measured keeps the configured limit
+        assert_eq!(table.compute_unit_limit(&[DexType::RaydiumV4, DexType::Whirlpool], 400_000), 400_000);
+
+        table.record(&ComputeProfile { legs: vec![(DexType::RaydiumV4, 42106)], overhead: 23026 });
+        assert_eq!(table.estimate(DexType::RaydiumV4), Some(34106));
+        assert_eq!(table.venues[&DexType::RaydiumV4].samples, 2);
+    }
+
+    #[test]
+    fn a_meta_without_logs_cannot_be_profiled() {
+        let mut meta = landed_meta();
+        meta.log_messages = solana_transaction_status::option_serializer::OptionSerializer::None;
+
+        assert!(profile_meta(&meta).is_err());
+    }
+}
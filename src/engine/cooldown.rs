@@ -0,0 +1,118 @@
+use crate::engine::types::*;
+use solana_sdk::clock::Slot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Keeps cycles off pools that were traded through within the last
+/// `cooldown_slots`, whose state a second back-to-back swap would likely
+/// see stale.
+pub struct PoolCooldown {
+    cooldown_slots: u64,
+    last_used: HashMap<Pubkey, Slot>,
+}
+
+impl PoolCooldown {
+    pub fn new(cooldown_slots: u64) -> Self {
+        Self {
+            cooldown_slots,
+            last_used: HashMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.cooldown_slots > 0
+    }
+
+    /// First leg pool still cooling down at `slot`, with the slot it was used in
+    pub fn cooling_pool(&self, cycle: &ArbitrageCycle, slot: Slot) -> Option<(Pubkey, Slot)> {
+        cycle.legs.iter().find_map(|leg| {
+            self.last_used
+                .get(&leg.pool_pubkey)
+                .filter(|used| slot.saturating_sub(**used) < self.cooldown_slots)
+                .map(|used| (leg.pool_pubkey, *used))
+        })
+    }
+
+    /// Record a submission through every pool of `cycle` at `slot`
+    pub fn record(&mut self, cycle: &ArbitrageCycle, slot: Slot) {
+        if !self.enabled() {
+            return;
+        }
+        for leg in &cycle.legs {
+            self.last_used.insert(leg.pool_pubkey, slot);
+        }
+        let cooldown_slots = self.cooldown_slots;
+        self.last_used.retain(|_, used| slot.saturating_sub(*used) < cooldown_slots);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(pools: &[Pubkey]) -> ArbitrageCycle {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let legs: Vec<SwapLeg> = pools
+            .iter()
+            .enumerate()
+            .map(|(i, pool)| SwapLeg {
+                from_mint: if i % 2 == 0 { sol } else { token },
+                to_mint: if i % 2 == 0 { token } else { sol },
+                pool_pubkey: *pool,
+                dex_type: DexType::RaydiumV4,
+                program_id: DexType::RaydiumV4.program_id(),
+                amount_in: 0,
+                estimated_amount_out: 0,
+                quote_haircut_bps: 0,
+            })
+            .collect();
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 50,
+            estimated_profit_lamports: 0,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    #[test]
+    fn a_cycle_reusing_a_traded_pool_waits_out_the_cooldown() {
+        let (shared, first_only, second_only) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut cooldown = PoolCooldown::new(3);
+        cooldown.record(&cycle(&[first_only, shared]), 100);
+
+        let reuse = cycle(&[second_only, shared]);
+        assert_eq!(cooldown.cooling_pool(&reuse, 100), Some((shared, 100)));
+        assert_eq!(cooldown.cooling_pool(&reuse, 102), Some((shared, 100)));
+        assert_eq!(cooldown.cooling_pool(&reuse, 103), None);
+
+        let disjoint = cycle(&[second_only, Pubkey::new_unique()]);
+        assert_eq!(cooldown.cooling_pool(&disjoint, 100), None);
+    }
+
+    #[test]
+    fn a_zero_cooldown_never_holds_a_pool() {
+        let pool = Pubkey::new_unique();
+        let mut cooldown = PoolCooldown::new(0);
+        assert!(!cooldown.enabled());
+        cooldown.record(&cycle(&[pool, Pubkey::new_unique()]), 100);
+        assert_eq!(cooldown.cooling_pool(&cycle(&[pool, Pubkey::new_unique()]), 100), None);
+    }
+
+    #[test]
+    fn a_later_submission_restarts_the_window_and_expired_pools_are_dropped() {
+        let (pool, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut cooldown = PoolCooldown::new(3);
+        cooldown.record(&cycle(&[pool, other]), 100);
+        cooldown.record(&cycle(&[pool, Pubkey::new_unique()]), 102);
+
+        assert_eq!(cooldown.cooling_pool(&cycle(&[pool]), 104), Some((pool, 102)));
+        assert_eq!(cooldown.cooling_pool(&cycle(&[other]), 103), None);
+
+        cooldown.record(&cycle(&[Pubkey::new_unique()]), 110);
+        assert!(!cooldown.last_used.contains_key(&pool));
+        assert!(!cooldown.last_used.contains_key(&other));
+    }
+}
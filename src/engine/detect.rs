@@ -1,39 +1,126 @@
 use crate::engine::graph::PriceGraph;
 use crate::engine::types::*;
+use crate::math::bps_from_ratio;
 use dashmap::DashMap;
 use solana_sdk::pubkey::Pubkey;
-use std::collections::{HashMap, VecDeque};
-use tracing::{debug, info};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use tracing::debug;
 
 pub struct CycleDetector;
 
+/// A closed cycle that did not clear the profit threshold
+#[derive(Debug, Clone)]
+pub struct NearMiss {
+    pub cycle: ArbitrageCycle,
+    pub shortfall_bps: i64, // Threshold for its hop count minus its profit
+}
+
+impl NearMiss {
+    fn new(cycle: ArbitrageCycle, threshold: ProfitThreshold) -> Self {
+        let shortfall_bps = threshold.for_hops(cycle.total_hops) - cycle.total_profit_bps;
+        Self { cycle, shortfall_bps }
+    }
+}
+
+/// Cycles of one detection pass plus the best of those below the threshold
+#[derive(Debug, Clone, Default)]
+pub struct DetectionPass {
+    pub cycles: Vec<ArbitrageCycle>,
+    pub near_misses: Vec<NearMiss>, // Smallest shortfall first, at most the requested number
+    pub below_threshold: usize,     // Closed cycles rejected by the threshold
+}
+
+/// Distance and predecessor maps of the Bellman-Ford pass, both keyed by
+/// mint. Distances are sums of `PoolEdge::weight`. Kept between passes so the maps keep their capacity instead of
+/// regrowing every time.
+#[derive(Debug, Default)]
+pub struct DetectorScratch {
+    distances: HashMap<Pubkey, f64>,
+    predecessors: HashMap<Pubkey, (Pubkey, PoolEdge)>, // Key: mint reached, Value: (mint it was reached from, edge)
+}
+
+impl DetectorScratch {
+    fn clear(&mut self) {
+        self.distances.clear();
+        self.predecessors.clear();
+    }
+}
+
+/// Keep the `limit` smallest shortfalls, one entry per cycle
+fn keep_near_misses(near_misses: &mut Vec<NearMiss>, limit: usize) {
+    near_misses.sort_by(|a, b| {
+        a.shortfall_bps
+            .cmp(&b.shortfall_bps)
+            .then_with(|| a.cycle.cycle_id().cmp(&b.cycle.cycle_id()))
+    });
+    near_misses.dedup_by_key(|near_miss| near_miss.cycle.cycle_id());
+    near_misses.truncate(limit);
+}
+
 impl CycleDetector {
     pub fn find_negative_cycles(
         graph: &PriceGraph,
         start_mint: Pubkey,
         min_hops: usize,
         max_hops: usize,
-        min_profit_bps: i64,
+        threshold: ProfitThreshold,
     ) -> Vec<ArbitrageCycle> {
+        Self::detect_with_near_misses(graph, start_mint, min_hops, max_hops, threshold, 0).cycles
+    }
+
+    /// `find_negative_cycles` that also returns up to `near_miss_limit`
+    /// closed cycles that fell short of the threshold
+    pub fn detect_with_near_misses(
+        graph: &PriceGraph,
+        start_mint: Pubkey,
+        min_hops: usize,
+        max_hops: usize,
+        threshold: ProfitThreshold,
+        near_miss_limit: usize,
+    ) -> DetectionPass {
+        let mut scratch = DetectorScratch::default();
+        Self::detect_with_scratch(&mut scratch, graph, start_mint, min_hops, max_hops, threshold, near_miss_limit)
+    }
+
+    /// `detect_with_near_misses` reusing `scratch` from an earlier pass
+    pub fn detect_with_scratch(
+        scratch: &mut DetectorScratch,
+        graph: &PriceGraph,
+        start_mint: Pubkey,
+        min_hops: usize,
+        max_hops: usize,
+        threshold: ProfitThreshold,
+        near_miss_limit: usize,
+    ) -> DetectionPass {
         let mut cycles = Vec::new();
-        let mut distances: HashMap<Pubkey, f64> = HashMap::new();
-        let mut predecessors: HashMap<Pubkey, (Pubkey, PoolEdge)> = HashMap::new();
+        let mut near_misses = Vec::new();
+        let mut below_threshold = 0;
+        scratch.clear();
+        let DetectorScratch { distances, predecessors } = scratch;
         
-        distances.insert(start_mint, 1.0);
-        
-        for _ in 0..max_hops {
+        distances.insert(start_mint, 0.0);
+        let sorted_edges = graph.detectable_edges();
+        let node_count = sorted_edges
+            .iter()
+            .flat_map(|(from_mint, edges)| std::iter::once(*from_mint).chain(edges.iter().map(|edge| edge.to_mint)))
+            .collect::<HashSet<Pubkey>>()
+            .len();
+
+        // Shortest paths settle within V - 1 rounds unless a negative cycle is reachable
+        for _ in 0..node_count.saturating_sub(1) {
             let mut updated = false;
             
-            // <-- FIXED: Added type annotation
-            for entry in graph.edges.iter() {
-                let from_mint: Pubkey = *entry.key();
-                for edge in entry.value() {
-                    let current_dist = distances.get(&from_mint).copied().unwrap_or(f64::MAX);
-                    let new_dist = current_dist * edge.price;
+            for (from_mint, edges) in &sorted_edges {
+                let from_mint: Pubkey = *from_mint;
+                let Some(&current_dist) = distances.get(&from_mint) else {
+                    continue;
+                };
+                for edge in edges {
+                    let new_dist = current_dist + edge.weight();
                     
-                    if new_dist < distances.get(&edge.pool_pubkey).copied().unwrap_or(f64::MAX) {
-                        distances.insert(edge.pool_pubkey, new_dist);
-                        predecessors.insert(edge.pool_pubkey, (from_mint, edge.clone()));
+                    if new_dist < distances.get(&edge.to_mint).copied().unwrap_or(f64::INFINITY) {
+                        distances.insert(edge.to_mint, new_dist);
+                        predecessors.insert(edge.to_mint, (from_mint, edge.clone()));
                         updated = true;
                     }
                 }
@@ -44,25 +131,33 @@ impl CycleDetector {
             }
         }
         
-        // Check for negative cycles (profit opportunities)
-        // <-- FIXED: Added type annotation
-        for entry in graph.edges.iter() {
-            let from_mint: Pubkey = *entry.key();
-            for edge in entry.value() {
+        // An edge that still relaxes after V - 1 rounds closes a negative cycle (a profitable loop)
+        for (from_mint, edges) in &sorted_edges {
+            let from_mint: Pubkey = *from_mint;
+            for edge in edges {
                 if let Some(&start_dist) = distances.get(&from_mint) {
-                    let new_dist = start_dist * edge.price;
+                    let new_dist = start_dist + edge.weight();
                     
-                    if new_dist < distances.get(&edge.pool_pubkey).copied().unwrap_or(f64::MAX) {
-                        // <-- FIXED: Changed self.reconstruct_cycle to Self::reconstruct_cycle
+                    if new_dist < distances.get(&edge.to_mint).copied().unwrap_or(f64::INFINITY) {
                         if let Some(cycle) = Self::reconstruct_cycle(
-                            &predecessors,
+                            predecessors,
                             from_mint,
-                            edge.pool_pubkey,
+                            edge,
+                            start_mint,
                             min_hops,
                             max_hops,
                         ) {
-                            if cycle.total_profit_bps > min_profit_bps {
+                            if let Err(e) = cycle.validate_closed(start_mint) {
+                                debug!("Rejecting malformed cycle ending at pool {}: {}", edge.pool_pubkey, e);
+                                continue;
+                            }
+                            if cycle.total_profit_bps > threshold.for_hops(cycle.total_hops) {
                                 cycles.push(cycle);
+                            } else {
+                                below_threshold += 1;
+                                if near_miss_limit > 0 {
+                                    near_misses.push(NearMiss::new(cycle, threshold));
+                                }
                             }
                         }
                     }
@@ -70,64 +165,590 @@ impl CycleDetector {
             }
         }
         
-        cycles.sort_by(|a, b| b.total_profit_bps.cmp(&a.total_profit_bps));
-        cycles
+        // Every edge of a loop can close it; identical cycles sort next to each other
+        sort_cycles(&mut cycles);
+        cycles.dedup_by_key(|cycle| cycle.cycle_id());
+        keep_near_misses(&mut near_misses, near_miss_limit);
+        DetectionPass { cycles, near_misses, below_threshold }
     }
 
+    /// Close the loop through `closing_edge` (`from_mint -> closing_edge.to_mint`)
+    /// by walking predecessors back from `from_mint` to the closing edge's
+    /// destination, then rotate it to start at `start_mint`. None when the walk
+    /// does not close within `max_hops`, the loop does not pass `start_mint`,
+    /// or its length is outside the hop bounds.
     fn reconstruct_cycle(
         predecessors: &HashMap<Pubkey, (Pubkey, PoolEdge)>,
-        start: Pubkey,
-        end: Pubkey,
+        from_mint: Pubkey,
+        closing_edge: &PoolEdge,
+        start_mint: Pubkey,
         min_hops: usize,
         max_hops: usize,
     ) -> Option<ArbitrageCycle> {
-        let mut path = Vec::new();
-        let mut current = end;
-        let mut visited = HashMap::new();
-        
-        while let Some((prev, edge)) = predecessors.get(&current) {
-            if visited.contains_key(&current) {
-                break;
-            }
-            visited.insert(current, true);
-            path.push((prev, edge.clone()));
-            current = *prev;
-            
-            if current == start && path.len() >= min_hops {
-                break;
-            }
-            
-            if path.len() > max_hops {
+        let target = closing_edge.to_mint;
+        let mut path = vec![(from_mint, closing_edge.clone())];
+        let mut current = from_mint;
+        let mut visited = HashSet::new();
+
+        while current != target {
+            if path.len() > max_hops || !visited.insert(current) {
                 return None;
             }
+            let (prev, edge) = predecessors.get(&current)?;
+            path.push((*prev, edge.clone()));
+            current = *prev;
         }
-        
+
         if path.len() < min_hops || path.len() > max_hops {
             return None;
         }
-        
+
+        // The path was walked backwards from the closing edge; legs run in trade direction
+        path.reverse();
+        let start = path.iter().position(|(from, _)| *from == start_mint)?;
+        path.rotate_left(start);
+
         let mut total_price = 1.0;
         let mut legs = Vec::new();
-        
-        for (_, edge) in path.iter() {
-            total_price *= edge.price;
+
+        for (prev, edge) in &path {
+            total_price *= edge.net_rate();
             legs.push(SwapLeg {
-                from_mint: edge.pool_pubkey, // This will be corrected in optimization
-                to_mint: edge.pool_pubkey,
+                from_mint: *prev,
+                to_mint: edge.to_mint,
                 pool_pubkey: edge.pool_pubkey,
                 dex_type: edge.dex_type,
+                program_id: edge.program_id,
                 amount_in: 0,
                 estimated_amount_out: 0,
+                quote_haircut_bps: 0,
             });
         }
         
-        let profit_bps = ((total_price - 1.0) * 10_000.0) as i64;
+        let profit_bps = match bps_from_ratio(total_price) {
+            Ok(profit_bps) => profit_bps,
+            Err(e) => {
+                debug!("Dropping reconstructed cycle: {}", e);
+                return None;
+            }
+        };
         
         Some(ArbitrageCycle {
             legs,
             total_profit_bps: profit_bps,
             estimated_profit_lamports: 0,
             total_hops: path.len(),
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
         })
     }
-}
\ No newline at end of file
+}
+
+/// Rank by profit, breaking ties on `cycle_id` so the order is reproducible
+pub fn sort_cycles(cycles: &mut [ArbitrageCycle]) {
+    cycles.sort_by(|a, b| {
+        b.total_profit_bps
+            .cmp(&a.total_profit_bps)
+            .then_with(|| a.cycle_id().cmp(&b.cycle_id()))
+    });
+}
+
+/// Restricts cycles to allowed venue combinations. A cycle is kept when the
+/// set of venues its legs trade on equals one of the allowed sets; an empty
+/// filter keeps everything.
+#[derive(Debug, Clone, Default)]
+pub struct DexPairFilter {
+    allowed: Vec<BTreeSet<&'static str>>,
+}
+
+impl DexPairFilter {
+    pub fn from_config(required_dex_pairs: &[Vec<String>]) -> anyhow::Result<Self> {
+        let known: BTreeSet<&'static str> = DexType::ALL.iter().map(|dex_type| dex_type.venue()).collect();
+        let allowed = required_dex_pairs
+            .iter()
+            .map(|combination| {
+                combination
+                    .iter()
+                    .map(|name| {
+                        let name = name.to_lowercase();
+                        known.iter().copied().find(|venue| *venue == name).ok_or_else(|| {
+                            anyhow::anyhow!("Unknown DEX {} in required_dex_pairs, expected one of {:?}", name, known)
+                        })
+                    })
+                    .collect::<anyhow::Result<BTreeSet<_>>>()
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { allowed })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.allowed.is_empty()
+    }
+
+    pub fn allows(&self, cycle: &ArbitrageCycle) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        let venues: BTreeSet<&'static str> = cycle.legs.iter().map(|leg| leg.dex_type.venue()).collect();
+        self.allowed.contains(&venues)
+    }
+
+    pub fn apply(&self, cycles: &mut Vec<ArbitrageCycle>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let before = cycles.len();
+        cycles.retain(|cycle| self.allows(cycle));
+        if cycles.len() < before {
+            debug!("DEX filter dropped {} of {} cycles", before - cycles.len(), before);
+        }
+    }
+}
+
+/// Caches detected cycles between graph generations so that only cycles
+/// touching mints whose edges changed are re-evaluated. A full detection pass
+/// runs every `full_detection_interval` generations to pick up new cycles.
+pub struct IncrementalDetector {
+    full_detection_interval: u64,
+    last_full_generation: Option<u64>,
+    last_generation: u64,
+    cycles: Vec<ArbitrageCycle>,
+    cycles_by_mint: HashMap<Pubkey, Vec<usize>>, // Key: mint, Value: indexes into `cycles`
+    near_miss_limit: usize,
+    near_misses: Vec<NearMiss>,
+    below_threshold: usize, // Cycles the last pass rejected by the threshold
+    min_distinct_mints: usize,
+    scratch: DetectorScratch,
+}
+
+impl IncrementalDetector {
+    pub fn new(full_detection_interval: u64) -> Self {
+        Self {
+            full_detection_interval: full_detection_interval.max(1),
+            last_full_generation: None,
+            last_generation: 0,
+            cycles: Vec::new(),
+            cycles_by_mint: HashMap::new(),
+            near_miss_limit: 0,
+            near_misses: Vec::new(),
+            below_threshold: 0,
+            min_distinct_mints: 0,
+            scratch: DetectorScratch::default(),
+        }
+    }
+
+    /// Drop cycles that trade through fewer than `min` different mints
+    pub fn with_min_distinct_mints(mut self, min: usize) -> Self {
+        self.min_distinct_mints = min;
+        self
+    }
+
+    /// Also keep the best `limit` sub-threshold cycles of every pass
+    pub fn with_near_misses(mut self, limit: usize) -> Self {
+        self.near_miss_limit = limit;
+        self
+    }
+
+    /// Best sub-threshold cycles as of the last pass, smallest shortfall first
+    pub fn near_misses(&self) -> &[NearMiss] {
+        &self.near_misses
+    }
+
+    pub fn below_threshold(&self) -> usize {
+        self.below_threshold
+    }
+
+    pub fn detect(
+        &mut self,
+        graph: &PriceGraph,
+        start_mint: Pubkey,
+        min_hops: usize,
+        max_hops: usize,
+        threshold: ProfitThreshold,
+    ) -> Vec<ArbitrageCycle> {
+        let generation = graph.generation();
+        let needs_full = self
+            .last_full_generation
+            .is_none_or(|last| generation.saturating_sub(last) >= self.full_detection_interval);
+
+        if needs_full {
+            let pass = CycleDetector::detect_with_scratch(
+                &mut self.scratch,
+                graph,
+                start_mint,
+                min_hops,
+                max_hops,
+                threshold,
+                self.near_miss_limit,
+            );
+            self.cycles = pass.cycles;
+            let min_distinct_mints = self.min_distinct_mints;
+            let before = self.cycles.len();
+            self.cycles.retain(|cycle| cycle.distinct_mints() >= min_distinct_mints);
+            if self.cycles.len() < before {
+                debug!(
+                    "Dropped {} cycles through fewer than {} distinct mints",
+                    before - self.cycles.len(),
+                    min_distinct_mints
+                );
+            }
+            self.near_misses = pass.near_misses;
+            self.below_threshold = pass.below_threshold;
+            self.rebuild_index();
+            self.last_full_generation = Some(generation);
+            self.last_generation = generation;
+            debug!("Full detection at generation {}: {} cycles", generation, self.cycles.len());
+            return self.cycles.clone();
+        }
+
+        let changed_mints = graph.changed_mints_since(self.last_generation);
+        self.last_generation = generation;
+        self.below_threshold = 0;
+
+        if changed_mints.is_empty() {
+            return self.cycles.clone();
+        }
+
+        // Near misses through changed mints may have moved toward or away from the threshold
+        let touches_changed = |cycle: &ArbitrageCycle| {
+            cycle.legs.iter().any(|leg| changed_mints.contains(&leg.from_mint) || changed_mints.contains(&leg.to_mint))
+        };
+        self.near_misses.retain_mut(|near_miss| {
+            if !touches_changed(&near_miss.cycle) {
+                return true;
+            }
+            match Self::reprice_cycle(graph, &near_miss.cycle) {
+                Some(profit_bps) => {
+                    near_miss.cycle.total_profit_bps = profit_bps;
+                    near_miss.shortfall_bps = threshold.for_hops(near_miss.cycle.total_hops) - profit_bps;
+                    near_miss.shortfall_bps >= 0 // Now clears; picked up by the next full pass
+                }
+                None => false,
+            }
+        });
+
+        let mut affected: Vec<usize> = changed_mints
+            .iter()
+            .filter_map(|mint| self.cycles_by_mint.get(mint))
+            .flatten()
+            .copied()
+            .collect();
+        affected.sort_unstable();
+        affected.dedup();
+
+        let mut keep = vec![true; self.cycles.len()];
+        for &idx in &affected {
+            match Self::reprice_cycle(graph, &self.cycles[idx]) {
+                Some(profit_bps) if profit_bps > threshold.for_hops(self.cycles[idx].total_hops) => {
+                    self.cycles[idx].total_profit_bps = profit_bps;
+                }
+                Some(profit_bps) => {
+                    keep[idx] = false;
+                    self.below_threshold += 1;
+                    if self.near_miss_limit > 0 {
+                        let mut cycle = self.cycles[idx].clone();
+                        cycle.total_profit_bps = profit_bps;
+                        self.near_misses.push(NearMiss::new(cycle, threshold));
+                    }
+                }
+                None => keep[idx] = false,
+            }
+        }
+        // A pool can fall behind the slot head without its price changing
+        for (idx, cycle) in self.cycles.iter().enumerate() {
+            if cycle.legs.iter().any(|leg| graph.is_lagging(&leg.pool_pubkey)) {
+                keep[idx] = false;
+            }
+        }
+        keep_near_misses(&mut self.near_misses, self.near_miss_limit);
+
+        let mut keep_iter = keep.into_iter();
+        self.cycles.retain(|_| keep_iter.next().unwrap_or(false));
+        sort_cycles(&mut self.cycles);
+        self.rebuild_index();
+
+        debug!(
+            "Targeted detection at generation {}: {} changed mints, {} cycles re-evaluated, {} cycles kept",
+            generation,
+            changed_mints.len(),
+            affected.len(),
+            self.cycles.len()
+        );
+
+        self.cycles.clone()
+    }
+
+    /// Recompute a cycle's profit after fees from the current edges in the graph
+    fn reprice_cycle(graph: &PriceGraph, cycle: &ArbitrageCycle) -> Option<i64> {
+        let mut total_price = 1.0;
+        for leg in &cycle.legs {
+            let edges = graph.edges.get(&leg.from_mint)?;
+            let edge = edges
+                .iter()
+                .find(|e| e.pool_pubkey == leg.pool_pubkey && e.dex_type == leg.dex_type)?;
+            if graph.is_lagging(&edge.pool_pubkey) {
+                return None;
+            }
+            total_price *= edge.net_rate();
+        }
+        bps_from_ratio(total_price).ok()
+    }
+
+    fn rebuild_index(&mut self) {
+        self.cycles_by_mint.clear();
+        for (idx, cycle) in self.cycles.iter().enumerate() {
+            for leg in &cycle.legs {
+                for mint in [leg.from_mint, leg.to_mint] {
+                    let indexes = self.cycles_by_mint.entry(mint).or_default();
+                    if indexes.last() != Some(&idx) {
+                        indexes.push(idx);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn round_trip(venues: &[DexType]) -> ArbitrageCycle {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let legs: Vec<SwapLeg> = venues
+            .iter()
+            .enumerate()
+            .map(|(i, dex_type)| SwapLeg {
+                from_mint: if i % 2 == 0 { sol } else { token },
+                to_mint: if i % 2 == 0 { token } else { sol },
+                pool_pubkey: Pubkey::new_unique(),
+                dex_type: *dex_type,
+                program_id: dex_type.program_id(),
+                amount_in: 0,
+                estimated_amount_out: 0,
+                quote_haircut_bps: 0,
+            })
+            .collect();
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 50,
+            estimated_profit_lamports: 0,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn pairs(combinations: &[&[&str]]) -> Vec<Vec<String>> {
+        combinations
+            .iter()
+            .map(|combination| combination.iter().map(|name| name.to_string()).collect())
+            .collect()
+    }
+
+    fn quote(graph: &PriceGraph, from_mint: Pubkey, to_mint: Pubkey, price: f64) -> Pubkey {
+        let pool = Pubkey::new_unique();
+        graph.add_edge(from_mint, to_mint, PoolEdge::new(pool, to_mint, DexType::RaydiumV4, price, 1e6, 25, spl_token::id()));
+        pool
+    }
+
+    #[test]
+    fn a_profitable_triangle_is_detected_once_with_its_mints_in_order() {
+        let graph = PriceGraph::new();
+        let (start, a, b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        // 100 * 2 * 0.0052 = 1.04 before three 25 bps fees
+        quote(&graph, start, a, 100.0);
+        quote(&graph, a, b, 2.0);
+        quote(&graph, b, start, 0.0052);
+        // Fair way back the other direction, which is no cycle on its own
+        quote(&graph, a, start, 0.01);
+
+        let cycles = CycleDetector::find_negative_cycles(&graph, start, 2, 4, ProfitThreshold::flat(50));
+
+        assert_eq!(cycles.len(), 1);
+        let cycle = &cycles[0];
+        let mints: Vec<Pubkey> = std::iter::once(cycle.legs[0].from_mint).chain(cycle.legs.iter().map(|leg| leg.to_mint)).collect();
+        assert_eq!(mints, vec![start, a, b, start]);
+        assert!(cycle.legs.iter().all(|leg| leg.from_mint != leg.pool_pubkey && leg.to_mint != leg.pool_pubkey));
+        // 1.04 * 0.9975^3 - 1
+        assert!((cycle.total_profit_bps - 322).abs() <= 1, "{}", cycle.total_profit_bps);
+    }
+
+    #[test]
+    fn the_per_hop_increment_raises_the_bar_for_a_three_hop_cycle() {
+        let graph = PriceGraph::new();
+        let (start, a, b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        // 55 bps after three 25 bps fees
+        quote(&graph, start, a, 100.0);
+        quote(&graph, a, b, 2.0);
+        quote(&graph, b, start, 1.0055 / (200.0 * 0.9975f64.powi(3)));
+
+        let flat = CycleDetector::find_negative_cycles(&graph, start, 2, 4, ProfitThreshold { base_bps: 50, per_hop_increment_bps: 0 });
+        assert_eq!(flat.len(), 1);
+        assert_eq!(flat[0].total_profit_bps, 55);
+        // 50 + 10 for the third hop
+        let scaled = ProfitThreshold { base_bps: 50, per_hop_increment_bps: 10 };
+        assert_eq!(scaled.for_hops(3), 60);
+        assert!(CycleDetector::find_negative_cycles(&graph, start, 2, 4, scaled).is_empty());
+    }
+
+    #[test]
+    fn a_changed_edge_only_reprices_the_cycles_through_its_mints() {
+        let graph = PriceGraph::new();
+        let (start, a, b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let requote = |from: Pubkey, to: Pubkey, pool: Pubkey, price: f64| {
+            graph.add_edge(from, to, PoolEdge::new(pool, to, DexType::RaydiumV4, price, 1e6, 25, spl_token::id()));
+        };
+        graph.begin_generation();
+        quote(&graph, start, a, 100.0);
+        let a_b = quote(&graph, a, b, 2.0);
+        let b_start = quote(&graph, b, start, 0.0052);
+        let mut detector = IncrementalDetector::new(100);
+        let profit = |cycles: Vec<ArbitrageCycle>| {
+            assert_eq!(cycles.len(), 1);
+            cycles[0].total_profit_bps
+        };
+        let full = profit(detector.detect(&graph, start, 2, 4, ProfitThreshold::flat(50)));
+
+        // Repriced within the generation already detected, so not seen as a change
+        requote(a, b, a_b, 2.02);
+        graph.begin_generation();
+        quote(&graph, Pubkey::new_unique(), Pubkey::new_unique(), 1.0);
+        let unrelated = profit(detector.detect(&graph, start, 2, 4, ProfitThreshold::flat(50)));
+        assert_eq!(unrelated, full, "a change elsewhere leaves the cached cycle alone");
+
+        graph.begin_generation();
+        requote(b, start, b_start, 0.00521);
+        let touched = profit(detector.detect(&graph, start, 2, 4, ProfitThreshold::flat(50)));
+        assert!(touched > full + 90, "{} -> {}", full, touched);
+    }
+
+    /// Mints with random fair values, joined by pools quoting within a few
+    /// percent of fair at random fees, so some seeds hold real cycles
+    fn random_graph(rng: &mut StdRng) -> (PriceGraph, Pubkey) {
+        let graph = PriceGraph::new();
+        let mints: Vec<(Pubkey, f64)> = (0..rng.gen_range(3..7)).map(|_| (Pubkey::new_unique(), rng.gen_range(0.001..1000.0))).collect();
+        for _ in 0..rng.gen_range(4..16) {
+            let (from, from_value) = mints[rng.gen_range(0..mints.len())];
+            let (to, to_value) = mints[rng.gen_range(0..mints.len())];
+            if from == to {
+                continue;
+            }
+            let price = from_value / to_value * rng.gen_range(0.97..1.05);
+            let fee_bps = rng.gen_range(0..100);
+            graph.add_edge(from, to, PoolEdge::new(Pubkey::new_unique(), to, DexType::RaydiumV4, price, 1e6, fee_bps, spl_token::id()));
+        }
+        (graph, mints[0].0)
+    }
+
+    #[test]
+    fn every_reported_cycle_returns_more_than_it_takes_after_fees() {
+        let mut found = 0;
+        for seed in 0..500 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (graph, start) = random_graph(&mut rng);
+            let threshold = rng.gen_range(0..50);
+
+            for cycle in CycleDetector::find_negative_cycles(&graph, start, 2, 4, ProfitThreshold::flat(threshold)) {
+                found += 1;
+                assert_eq!(cycle.legs.first().unwrap().from_mint, start, "seed {}", seed);
+                assert_eq!(cycle.legs.last().unwrap().to_mint, start, "seed {}", seed);
+                let mut product = 1.0;
+                for (leg, next) in cycle.legs.iter().zip(cycle.legs.iter().cycle().skip(1)) {
+                    assert_eq!(leg.to_mint, next.from_mint, "seed {}: legs do not chain", seed);
+                    let (_, edge) = graph
+                        .neighbors(&leg.from_mint)
+                        .into_iter()
+                        .find(|(to_mint, edge)| *to_mint == leg.to_mint && edge.pool_pubkey == leg.pool_pubkey)
+                        .unwrap_or_else(|| panic!("seed {}: leg through {} has no edge", seed, leg.pool_pubkey));
+                    product *= edge.net_rate();
+                }
+                assert!(product > 1.0, "seed {}: net rate product {} around a reported cycle", seed, product);
+                assert!(cycle.total_profit_bps >= threshold, "seed {}", seed);
+                assert!(((product - 1.0) * 10_000.0 - cycle.total_profit_bps as f64).abs() <= 1.0, "seed {}", seed);
+            }
+        }
+        // The property must not hold only because nothing was reported
+        assert!(found >= 20, "only {} cycles over all seeds", found);
+    }
+
+    #[test]
+    fn a_cycle_profitable_only_before_fees_is_rejected_and_one_profitable_after_fees_is_detected() {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // 40 bps before two 25 bps fees, about 10 bps short after them
+        let graph = PriceGraph::new();
+        quote(&graph, sol, token, 100.4);
+        quote(&graph, token, sol, 0.01);
+        assert!(CycleDetector::find_negative_cycles(&graph, sol, 2, 4, ProfitThreshold::flat(0)).is_empty());
+
+        // 100 bps before fees, 1.01 * 0.9975^2 - 1 after them
+        let graph = PriceGraph::new();
+        quote(&graph, sol, token, 101.0);
+        quote(&graph, token, sol, 0.01);
+        let cycles = CycleDetector::find_negative_cycles(&graph, sol, 2, 4, ProfitThreshold::flat(10));
+        assert_eq!(cycles.len(), 1);
+        assert!((cycles[0].total_profit_bps - 50).abs() <= 1, "{}", cycles[0].total_profit_bps);
+    }
+
+    #[test]
+    fn only_the_configured_venue_combinations_are_kept() {
+        let filter = DexPairFilter::from_config(&pairs(&[&["Raydium", "meteora"]])).unwrap();
+        let raydium_meteora = round_trip(&[DexType::RaydiumV4, DexType::MeteoraDlmm]);
+        let raydium_pump = round_trip(&[DexType::RaydiumCp, DexType::Pump]);
+        // Every leg on one venue is not the Raydium + Meteora pair either
+        let raydium_only = round_trip(&[DexType::RaydiumV4, DexType::RaydiumClmm]);
+        assert!(filter.allows(&raydium_meteora));
+        assert!(!filter.allows(&raydium_pump));
+        assert!(!filter.allows(&raydium_only));
+
+        let mut cycles = vec![raydium_pump, raydium_meteora.clone(), raydium_only];
+        filter.apply(&mut cycles);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].cycle_id(), raydium_meteora.cycle_id());
+    }
+
+    #[test]
+    fn an_empty_filter_keeps_every_cycle() {
+        let filter = DexPairFilter::from_config(&[]).unwrap();
+        assert!(!filter.is_enabled());
+        assert!(filter.allows(&round_trip(&[DexType::RaydiumV4, DexType::Pump])));
+    }
+
+    #[test]
+    fn an_unknown_venue_is_a_config_error() {
+        assert!(DexPairFilter::from_config(&pairs(&[&["raydium", "uniswap"]])).is_err());
+    }
+
+    #[test]
+    fn a_two_pool_same_pair_arb_passes_the_distinct_mint_floor_and_a_single_mint_loop_does_not() {
+        let graph = PriceGraph::new();
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // The token is 2% cheaper in one pool than it sells for in the other
+        quote(&graph, sol, token, 102.0);
+        quote(&graph, token, sol, 0.01);
+        quote(&graph, sol, token, 100.0);
+
+        let mut detector = IncrementalDetector::new(1).with_min_distinct_mints(2);
+        let cycles = detector.detect(&graph, sol, 2, 2, ProfitThreshold::flat(50));
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].distinct_mints(), 2);
+        assert_ne!(cycles[0].legs[0].pool_pubkey, cycles[0].legs[1].pool_pubkey);
+
+        // The same route through a single mint, as a mis-keyed reconstruction would produce
+        let mut degenerate = cycles[0].clone();
+        for leg in degenerate.legs.iter_mut() {
+            leg.to_mint = sol;
+            leg.from_mint = sol;
+        }
+        assert_eq!(degenerate.distinct_mints(), 1, "below the default floor of 2");
+        assert_eq!(round_trip(&[DexType::RaydiumV4, DexType::Whirlpool]).distinct_mints(), 2);
+
+        // A floor above what the pair offers drops it
+        let mut strict = IncrementalDetector::new(1).with_min_distinct_mints(3);
+        assert!(strict.detect(&graph, sol, 2, 2, ProfitThreshold::flat(50)).is_empty());
+    }
+}
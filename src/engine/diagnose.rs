@@ -0,0 +1,320 @@
+use crate::engine::capability::CapabilityRegistry;
+use crate::engine::detect::NearMiss;
+use crate::engine::graph::{PriceGraph, UpdateStats};
+use crate::engine::spread::{CycleRejection, RejectionStage, SpreadMonitor};
+use tracing::warn;
+
+/// Sub-threshold cycles the detector keeps while the diagnostic is enabled
+pub const DRY_SPELL_NEAR_MISSES: usize = 3;
+
+/// Detected cycles turned down in one or more iterations, by stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RejectionCounts {
+    pub below_threshold: usize, // Closed cycles the detector rejected on profit
+    pub venue: usize,
+    pub config: usize,
+    pub cost: usize,
+    pub risk: usize,
+}
+
+impl RejectionCounts {
+    pub fn from_iteration(below_threshold: usize, rejections: &[CycleRejection]) -> Self {
+        let mut counts = Self { below_threshold, ..Default::default() };
+        for rejection in rejections {
+            match rejection.stage {
+                RejectionStage::Venue => counts.venue += 1,
+                RejectionStage::Config => counts.config += 1,
+                RejectionStage::Cost => counts.cost += 1,
+                RejectionStage::Risk => counts.risk += 1,
+            }
+        }
+        counts
+    }
+
+    pub fn add(&mut self, other: &RejectionCounts) {
+        self.below_threshold += other.below_threshold;
+        self.venue += other.venue;
+        self.config += other.config;
+        self.cost += other.cost;
+        self.risk += other.risk;
+    }
+
+    /// Cycles that cleared the threshold and were rejected afterwards
+    pub fn after_threshold(&self) -> usize {
+        self.venue + self.config + self.cost + self.risk
+    }
+}
+
+/// Why the bot found nothing over a run of iterations
+#[derive(Debug, Clone)]
+pub struct DrySpellReport {
+    pub iterations: u64,
+    pub rejections: RejectionCounts,
+    pub near_miss: Option<NearMiss>, // Closest sub-threshold cycle of the spell
+    pub edges: usize,
+    pub non_executable_edges: usize, // Edges through venues without an executor
+    pub refresh: UpdateStats,        // Last refresh; failed pools keep their previous edges
+    pub unreachable_mints: usize,    // Mints of the last refresh whose every pool failed
+    pub widest_spread_bps: Option<f64>,
+    pub min_threshold_bps: i64,
+}
+
+impl DrySpellReport {
+    /// Most likely explanation, checked from "the data is broken" down to "the market is quiet"
+    pub fn diagnosis(&self) -> &'static str {
+        if self.refresh.pools_failed > 0 && self.refresh.pools_failed * 2 >= self.refresh.pools() {
+            "stale graph: half or more of the pools failed their last refresh, check the RPC endpoint"
+        } else if self.edges == 0 {
+            "empty graph: every pool was dropped by the liquidity floor or failed to parse"
+        } else if self.rejections.config > 0 {
+            "config: cycles cleared the threshold but no capital is configured for their base mint"
+        } else if self.rejections.venue > 0 && self.rejections.venue >= self.rejections.cost + self.rejections.risk {
+            "config: cycles cleared the threshold but were excluded by required_dex_pairs or non-executable venues"
+        } else if self.rejections.after_threshold() > 0 {
+            "costs: cycles cleared the threshold but not fees, slippage, capital or risk checks"
+        } else if self.widest_spread_bps.is_some_and(|spread| spread > self.min_threshold_bps as f64) {
+            "routing: inter-venue spreads exceed the threshold but no closed cycle through them clears it"
+        } else {
+            "tight market: inter-venue spreads are below the profit threshold"
+        }
+    }
+
+    pub fn log(&self) {
+        warn!("🔍 No optimized cycle for {} iterations: {}", self.iterations, self.diagnosis());
+        warn!(
+            "   ↳ graph: {} edges, {} through non-executable venues; last refresh {} pools priced, {} failed (edges stale), {} skipped, {} edges below the liquidity floor, {} mints unreachable",
+            self.edges,
+            self.non_executable_edges,
+            self.refresh.pools_priced,
+            self.refresh.pools_failed,
+            self.refresh.pools_skipped,
+            self.refresh.edges_below_liquidity,
+            self.unreachable_mints
+        );
+        warn!(
+            "   ↳ cycles: {} below threshold, {} venue, {} config, {} cost, {} risk rejections",
+            self.rejections.below_threshold,
+            self.rejections.venue,
+            self.rejections.config,
+            self.rejections.cost,
+            self.rejections.risk
+        );
+        match &self.near_miss {
+            Some(near_miss) => warn!(
+                "   ↳ closest cycle {:016x}: {} hops, {} bps, {} bps short of the threshold",
+                near_miss.cycle.cycle_id(),
+                near_miss.cycle.total_hops,
+                near_miss.cycle.total_profit_bps,
+                near_miss.shortfall_bps
+            ),
+            None => warn!("   ↳ no closed cycle through the base mint was detected"),
+        }
+        match self.widest_spread_bps {
+            Some(spread) => warn!("   ↳ widest inter-venue spread {:.0} bps (threshold from {} bps)", spread, self.min_threshold_bps),
+            None => warn!("   ↳ no pair is quoted by two pools"),
+        }
+    }
+}
+
+/// Counts consecutive iterations without an optimized cycle and explains the
+/// spell every `iterations` of them, so an operator can tell a tight market
+/// from a broken config or a stale graph.
+pub struct DrySpellDiagnostic {
+    iterations: u64,
+    streak: u64,
+    rejections: RejectionCounts,
+    near_miss: Option<NearMiss>,
+}
+
+impl DrySpellDiagnostic {
+    /// `iterations` of 0 disables the diagnostic
+    pub fn new(iterations: u64) -> Self {
+        Self {
+            iterations,
+            streak: 0,
+            rejections: RejectionCounts::default(),
+            near_miss: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.iterations > 0
+    }
+
+    /// Feed one iteration. True when a report is due; build it with `report`.
+    pub fn observe(&mut self, optimized_cycles: usize, rejections: RejectionCounts, near_misses: &[NearMiss]) -> bool {
+        if !self.enabled() {
+            return false;
+        }
+        if optimized_cycles > 0 {
+            self.streak = 0;
+            self.rejections = RejectionCounts::default();
+            self.near_miss = None;
+            return false;
+        }
+        self.streak += 1;
+        self.rejections.add(&rejections);
+        if let Some(best) = near_misses.iter().min_by_key(|near_miss| near_miss.shortfall_bps) {
+            if self.near_miss.as_ref().is_none_or(|current| best.shortfall_bps < current.shortfall_bps) {
+                self.near_miss = Some(best.clone());
+            }
+        }
+        self.streak.is_multiple_of(self.iterations)
+    }
+
+    /// Breakdown of the current spell against the graph as it is now
+    pub fn report(
+        &self,
+        graph: &PriceGraph,
+        capabilities: &CapabilityRegistry,
+        refresh: UpdateStats,
+        unreachable_mints: usize,
+        min_threshold_bps: i64,
+    ) -> DrySpellReport {
+        let (mut edges, mut non_executable_edges) = (0, 0);
+        for entry in graph.edges.iter() {
+            edges += entry.value().len();
+            non_executable_edges += entry.value().iter().filter(|edge| !capabilities.get(edge.dex_type).execute).count();
+        }
+        let widest_spread_bps = SpreadMonitor::pair_spreads(graph)
+            .iter()
+            .map(|spread| spread.spread_bps)
+            .fold(None, |widest: Option<f64>, spread| Some(widest.map_or(spread, |w| w.max(spread))));
+        DrySpellReport {
+            iterations: self.streak,
+            rejections: self.rejections,
+            near_miss: self.near_miss.clone(),
+            edges,
+            non_executable_edges,
+            refresh,
+            unreachable_mints,
+            widest_spread_bps,
+            min_threshold_bps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::{ArbitrageCycle, DexType, PoolEdge, SwapLeg};
+    use solana_sdk::pubkey::Pubkey;
+    use std::collections::HashSet;
+
+    fn cycle(total_profit_bps: i64) -> ArbitrageCycle {
+        let leg = SwapLeg {
+            from_mint: Pubkey::new_unique(),
+            to_mint: Pubkey::new_unique(),
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg.clone(), leg],
+            total_profit_bps,
+            estimated_profit_lamports: 0,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn near_miss(shortfall_bps: i64) -> NearMiss {
+        NearMiss { cycle: cycle(10 - shortfall_bps), shortfall_bps }
+    }
+
+    fn rejections(stages: &[RejectionStage]) -> Vec<CycleRejection> {
+        stages.iter().map(|stage| CycleRejection::new(&cycle(20), *stage, "synthetic")).collect()
+    }
+
+    fn report(rejections: RejectionCounts, edges: usize, refresh: UpdateStats, widest_spread_bps: Option<f64>) -> DrySpellReport {
+        DrySpellReport {
+            iterations: 1,
+            rejections,
+            near_miss: None,
+            edges,
+            non_executable_edges: 0,
+            refresh,
+            unreachable_mints: 0,
+            widest_spread_bps,
+            min_threshold_bps: 10,
+        }
+    }
+
+    #[test]
+    fn rejections_are_counted_by_stage_and_summed_across_iterations() {
+        use RejectionStage::*;
+        let first = RejectionCounts::from_iteration(7, &rejections(&[Venue, Cost, Cost, Risk]));
+        assert_eq!(first, RejectionCounts { below_threshold: 7, venue: 1, config: 0, cost: 2, risk: 1 });
+        assert_eq!(first.after_threshold(), 4);
+
+        let mut total = first;
+        total.add(&RejectionCounts::from_iteration(3, &rejections(&[Config, Cost])));
+        assert_eq!(total, RejectionCounts { below_threshold: 10, venue: 1, config: 1, cost: 3, risk: 1 });
+        assert_eq!(total.after_threshold(), 6);
+    }
+
+    #[test]
+    fn a_spell_reports_every_iterations_with_its_totals_and_closest_miss() {
+        use RejectionStage::*;
+        let mut diagnostic = DrySpellDiagnostic::new(3);
+        assert!(!diagnostic.observe(0, RejectionCounts::from_iteration(5, &rejections(&[Cost])), &[near_miss(8), near_miss(4)]));
+        assert!(!diagnostic.observe(0, RejectionCounts::from_iteration(2, &rejections(&[Risk, Risk])), &[]));
+        assert!(diagnostic.observe(0, RejectionCounts::from_iteration(1, &[]), &[near_miss(6)]));
+
+        let graph = PriceGraph::new();
+        let refresh = UpdateStats { edges_added: 0, pools_priced: 3, pools_failed: 1, pools_skipped: 0, edges_below_liquidity: 0 };
+        let report = diagnostic.report(&graph, &CapabilityRegistry::new(), refresh, 0, 10);
+        assert_eq!(report.iterations, 3);
+        assert_eq!(report.rejections, RejectionCounts { below_threshold: 8, venue: 0, config: 0, cost: 1, risk: 2 });
+        assert_eq!(report.near_miss.unwrap().shortfall_bps, 4);
+        assert_eq!(report.refresh, refresh);
+
+        // An optimized cycle ends the spell and clears its totals
+        assert!(!diagnostic.observe(1, RejectionCounts::from_iteration(9, &[]), &[]));
+        assert!(!diagnostic.observe(0, RejectionCounts::default(), &[]));
+        let report = diagnostic.report(&graph, &CapabilityRegistry::new(), UpdateStats::default(), 0, 10);
+        assert_eq!((report.iterations, report.rejections, report.near_miss.is_none()), (1, RejectionCounts::default(), true));
+
+        assert!(!DrySpellDiagnostic::new(0).observe(0, RejectionCounts::default(), &[]));
+    }
+
+    #[test]
+    fn the_report_counts_non_executable_edges_and_the_widest_spread() {
+        let graph = PriceGraph::new();
+        // Spreads are quoted from the smaller mint of a pair
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (from, to) = (from.min(to), from.max(to));
+        for (dex_type, price) in [(DexType::RaydiumV4, 1.0), (DexType::Heaven, 1.02)] {
+            let pool = Pubkey::new_unique();
+            graph.add_edge(from, to, PoolEdge::new(pool, to, dex_type, price, 1e6, 25, spl_token::id()));
+            graph.add_edge(to, from, PoolEdge::new(pool, from, dex_type, 1.0 / price, 1e6, 25, spl_token::id()));
+        }
+        let capabilities = CapabilityRegistry::from_executable(&HashSet::from([DexType::RaydiumV4]));
+
+        let report = DrySpellDiagnostic::new(1).report(&graph, &capabilities, UpdateStats::default(), 0, 10);
+        assert_eq!((report.edges, report.non_executable_edges), (4, 2));
+        assert!((report.widest_spread_bps.unwrap() - 200.0).abs() < 1e-6);
+        assert!(report.diagnosis().starts_with("routing"));
+    }
+
+    #[test]
+    fn the_diagnosis_blames_the_data_before_the_config_before_the_market() {
+        let healthy = UpdateStats { edges_added: 8, pools_priced: 4, pools_failed: 0, pools_skipped: 0, edges_below_liquidity: 0 };
+        let half_failed = UpdateStats { pools_failed: 4, ..healthy };
+        let counts = |venue, config, cost| RejectionCounts { below_threshold: 50, venue, config, cost, risk: 0 };
+
+        assert!(report(counts(0, 3, 0), 8, half_failed, None).diagnosis().starts_with("stale graph"));
+        assert!(report(counts(0, 3, 0), 0, healthy, None).diagnosis().starts_with("empty graph"));
+        assert!(report(counts(5, 3, 9), 8, healthy, None).diagnosis().contains("no capital"));
+        assert!(report(counts(5, 0, 5), 8, healthy, None).diagnosis().contains("required_dex_pairs"));
+        assert!(report(counts(5, 0, 6), 8, healthy, None).diagnosis().starts_with("costs"));
+        assert!(report(counts(0, 0, 0), 8, healthy, Some(10.5)).diagnosis().starts_with("routing"));
+        assert!(report(counts(0, 0, 0), 8, healthy, Some(9.0)).diagnosis().starts_with("tight market"));
+        assert!(report(counts(0, 0, 0), 8, healthy, None).diagnosis().starts_with("tight market"));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::engine::graph::PriceGraph;
+use crate::engine::types::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+/// Node statement for a mint, labeled with the start of its address
+fn dot_node(out: &mut String, mint: &Pubkey) {
+    let address = mint.to_string();
+    let _ = writeln!(out, "  \"{}\" [label=\"{}\"];", address, &address[..address.len().min(8)]);
+}
+
+fn dot_edge(out: &mut String, from: &Pubkey, to: &Pubkey, label: &str) {
+    let _ = writeln!(out, "  \"{}\" -> \"{}\" [label=\"{}\"];", from, to, label);
+}
+
+impl ArbitrageCycle {
+    /// GraphViz DOT of the route: one node per mint, one edge per leg labeled
+    /// with the venue and the leg's sized price (output per input), or `-`
+    /// before the cycle is sized. Render with `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        let mut out = format!("digraph cycle_{:016x} {{\n  rankdir=LR;\n", self.cycle_id());
+        let mut mints = BTreeSet::new();
+        for leg in &self.legs {
+            if mints.insert(leg.from_mint) {
+                dot_node(&mut out, &leg.from_mint);
+            }
+        }
+        for (idx, leg) in self.legs.iter().enumerate() {
+            let price = if leg.amount_in > 0 {
+                format!("{:.6}", leg.estimated_amount_out as f64 / leg.amount_in as f64)
+            } else {
+                "-".to_string()
+            };
+            dot_edge(&mut out, &leg.from_mint, &leg.to_mint, &format!("{}. {:?} @ {}", idx + 1, leg.dex_type, price));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl PriceGraph {
+    /// GraphViz DOT of every edge in the graph, labeled with the venue and
+    /// spot price. Output is ordered by pool so two dumps can be diffed.
+    pub fn to_dot(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut out = format!("digraph price_graph_{} {{\n", snapshot.generation);
+        let mints: BTreeSet<Pubkey> = snapshot.edges.keys().flat_map(|(_, from, to)| [*from, *to]).collect();
+        for mint in &mints {
+            dot_node(&mut out, mint);
+        }
+        for ((_, from, to), edge) in &snapshot.edges {
+            dot_edge(&mut out, from, to, &format!("{:?} @ {:.6}", edge.dex_type, edge.spot_price()));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leg(from_mint: Pubkey, to_mint: Pubkey, dex_type: DexType, amount_in: u64, estimated_amount_out: u64) -> SwapLeg {
+        SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type,
+            program_id: dex_type.program_id(),
+            amount_in,
+            estimated_amount_out,
+            quote_haircut_bps: 0,
+        }
+    }
+
+    fn count(dot: &str, needle: &str) -> usize {
+        dot.lines().filter(|line| line.contains(needle)).count()
+    }
+
+    #[test]
+    fn a_cycle_has_a_node_per_mint_and_an_edge_per_leg() {
+        let (sol, usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let cycle = ArbitrageCycle {
+            legs: vec![
+                leg(sol, usdc, DexType::RaydiumV4, 1_000, 150_000),
+                leg(usdc, bonk, DexType::Whirlpool, 150_000, 600_000),
+                leg(bonk, sol, DexType::MeteoraDlmm, 600_000, 1_010),
+            ],
+            total_profit_bps: 100,
+            estimated_profit_lamports: 10,
+            total_hops: 3,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        };
+
+        let dot = cycle.to_dot();
+        assert!(dot.starts_with(&format!("digraph cycle_{:016x} {{", cycle.cycle_id())));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(count(&dot, " -> "), 3);
+        assert_eq!(count(&dot, "[label=\"") - count(&dot, " -> "), 3, "{}", dot);
+        for mint in [sol, usdc, bonk] {
+            let address = mint.to_string();
+            assert_eq!(count(&dot, &format!("  \"{}\" [label=\"{}\"];", address, &address[..8])), 1);
+        }
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"1. RaydiumV4 @ 150.000000\"]", sol, usdc)));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"2. Whirlpool @ 4.000000\"]", usdc, bonk)));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"3. MeteoraDlmm @ 0.001683\"]", bonk, sol)));
+    }
+
+    #[test]
+    fn an_unsized_leg_has_no_price() {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let cycle = ArbitrageCycle {
+            legs: vec![leg(sol, token, DexType::RaydiumV4, 0, 0), leg(token, sol, DexType::RaydiumV4, 0, 0)],
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        };
+        assert_eq!(count(&cycle.to_dot(), "@ -\""), 2);
+    }
+
+    #[test]
+    fn the_graph_has_a_node_per_mint_and_an_edge_per_pool_direction() {
+        let graph = PriceGraph::new();
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        for (dex_type, price) in [(DexType::RaydiumV4, 0.5), (DexType::Whirlpool, 0.51)] {
+            let pool = Pubkey::new_unique();
+            graph.add_edge(token, sol, PoolEdge::new(pool, sol, dex_type, price, 1e6, 25, spl_token::id()));
+            graph.add_edge(sol, token, PoolEdge::new(pool, token, dex_type, 1.0 / price, 1e6, 25, spl_token::id()));
+        }
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph price_graph_"));
+        assert_eq!(count(&dot, " -> "), 4);
+        assert_eq!(count(&dot, "[label=\"") - count(&dot, " -> "), 2);
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"Whirlpool @ 0.510000\"]", token, sol)));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"RaydiumV4 @ 2.000000\"]", sol, token)));
+    }
+}
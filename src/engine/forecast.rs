@@ -0,0 +1,246 @@
+use crate::engine::types::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// Smoothed opportunity yield of one token
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenYield {
+    pub score: f64, // EMA of yield per refresh in which the token was priced
+    pub detected: u64,
+    pub captured: u64,
+    pub refreshes: u64,
+    credit: f64,
+}
+
+/// Decides which tokens get repriced each refresh when the universe is larger
+/// than the per-refresh budget. Each token earns refresh slots in proportion
+/// to its smoothed opportunity yield; `exploration_floor` of the budget is
+/// spread evenly so tokens without history are still revisited.
+pub struct YieldForecaster {
+    tokens_per_refresh: usize,
+    exploration_floor: f64,
+    smoothing: f64,
+    tokens: HashMap<Pubkey, TokenYield>,
+}
+
+impl YieldForecaster {
+    pub fn new(tokens_per_refresh: usize, exploration_floor: f64, smoothing: f64) -> Self {
+        Self {
+            tokens_per_refresh,
+            exploration_floor: exploration_floor.clamp(0.0, 1.0),
+            smoothing: smoothing.clamp(0.0, 1.0),
+            tokens: HashMap::new(),
+        }
+    }
+
+    /// Fraction of the refresh budget each of `mints` is entitled to
+    pub fn shares(&self, mints: &[Pubkey]) -> Vec<(Pubkey, f64)> {
+        if mints.is_empty() {
+            return Vec::new();
+        }
+        let uniform = 1.0 / mints.len() as f64;
+        let total: f64 = mints.iter().map(|mint| self.score(mint)).sum();
+        mints
+            .iter()
+            .map(|mint| {
+                let share = if total > 0.0 {
+                    self.exploration_floor * uniform + (1.0 - self.exploration_floor) * self.score(mint) / total
+                } else {
+                    uniform
+                };
+                (*mint, share)
+            })
+            .collect()
+    }
+
+    /// Tokens to reprice this refresh. Every token accrues its share of the
+    /// budget as credit; the ones with the most credit are picked and pay
+    /// one slot each, so selection frequency converges to the shares.
+    pub fn select(&mut self, mints: &[Pubkey]) -> Vec<Pubkey> {
+        if self.tokens_per_refresh == 0 || mints.len() <= self.tokens_per_refresh {
+            return mints.to_vec();
+        }
+        let budget = self.tokens_per_refresh as f64;
+        let mut credits: Vec<(Pubkey, f64)> = self
+            .shares(mints)
+            .into_iter()
+            .map(|(mint, share)| {
+                let entry = self.tokens.entry(mint).or_default();
+                entry.credit += share * budget;
+                (mint, entry.credit)
+            })
+            .collect();
+        credits.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut selected: Vec<Pubkey> = credits
+            .into_iter()
+            .take(self.tokens_per_refresh)
+            .map(|(mint, _)| mint)
+            .collect();
+        for mint in &selected {
+            if let Some(entry) = self.tokens.get_mut(mint) {
+                entry.credit -= 1.0;
+            }
+        }
+        selected.sort();
+        debug!("🎯 Repricing {} of {} tokens this refresh", selected.len(), mints.len());
+        selected
+    }
+
+    /// Fold one refresh's outcome into the scores of the tokens it priced:
+    /// cycles detected through each token and cycles that cleared
+    /// optimization, capital and simulation.
+    pub fn record(&mut self, priced: &[Pubkey], detected: &[ArbitrageCycle], captured: &[&ArbitrageCycle]) {
+        let mut yields: HashMap<Pubkey, (u64, u64)> = HashMap::new();
+        for cycle in detected {
+            for mint in cycle_tokens(cycle) {
+                yields.entry(mint).or_default().0 += 1;
+            }
+        }
+        for cycle in captured {
+            for mint in cycle_tokens(cycle) {
+                yields.entry(mint).or_default().1 += 1;
+            }
+        }
+
+        for mint in priced {
+            let (detected, captured) = yields.get(mint).copied().unwrap_or_default();
+            let entry = self.tokens.entry(*mint).or_default();
+            let observed = (detected + captured) as f64;
+            entry.score = if entry.refreshes == 0 {
+                observed
+            } else {
+                self.smoothing * observed + (1.0 - self.smoothing) * entry.score
+            };
+            entry.detected += detected;
+            entry.captured += captured;
+            entry.refreshes += 1;
+        }
+    }
+
+    pub fn score(&self, mint: &Pubkey) -> f64 {
+        self.tokens.get(mint).map_or(0.0, |entry| entry.score)
+    }
+
+    pub fn token_yield(&self, mint: &Pubkey) -> Option<&TokenYield> {
+        self.tokens.get(mint)
+    }
+
+    /// Log the current allocation and realized yield of the top tokens
+    pub fn log(&self, mints: &[Pubkey], top: usize) {
+        let mut shares = self.shares(mints);
+        shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (mint, share) in shares.into_iter().take(top) {
+            let entry = self.tokens.get(&mint).copied().unwrap_or_default();
+            info!(
+                "🎯 {}: {:.1}% of refresh budget, score {:.2}, {} detected / {} captured over {} refreshes",
+                mint,
+                share * 100.0,
+                entry.score,
+                entry.detected,
+                entry.captured,
+                entry.refreshes
+            );
+        }
+    }
+}
+
+/// Distinct non-base mints a cycle trades through
+fn cycle_tokens(cycle: &ArbitrageCycle) -> Vec<Pubkey> {
+    let base_mint = cycle.base_mint();
+    let mut mints: Vec<Pubkey> = cycle
+        .legs
+        .iter()
+        .flat_map(|leg| [leg.from_mint, leg.to_mint])
+        .filter(|mint| *mint != base_mint)
+        .collect();
+    mints.sort();
+    mints.dedup();
+    mints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+
+    fn round_trip(token: Pubkey) -> ArbitrageCycle {
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg(sol_mint(), token), leg(token, sol_mint())],
+            total_profit_bps: 60,
+            estimated_profit_lamports: 0,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    /// `hot` yields three cycles a refresh (two detected, one captured),
+    /// `warm` one detected cycle, the cold tokens nothing
+    fn forecaster_with_history(hot: Pubkey, warm: Pubkey, cold: &[Pubkey]) -> YieldForecaster {
+        let mut forecaster = YieldForecaster::new(1, 0.2, 0.5);
+        let priced: Vec<Pubkey> = [hot, warm].iter().chain(cold).copied().collect();
+        let detected = vec![round_trip(hot), round_trip(hot), round_trip(warm)];
+        for _ in 0..10 {
+            forecaster.record(&priced, &detected, &[&detected[0]]);
+        }
+        forecaster
+    }
+
+    #[test]
+    fn shares_follow_the_smoothed_yield_above_the_exploration_floor() {
+        let (hot, warm, cold) = (Pubkey::new_unique(), Pubkey::new_unique(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        let forecaster = forecaster_with_history(hot, warm, &cold);
+        assert_eq!(forecaster.score(&hot), 3.0);
+        assert_eq!(forecaster.token_yield(&hot).map(|y| (y.detected, y.captured, y.refreshes)), Some((20, 10, 10)));
+
+        // 0.2 / 4 each from the floor, the remaining 0.8 split 3 : 1 : 0 : 0
+        let mints = [hot, warm, cold[0], cold[1]];
+        let shares: HashMap<Pubkey, f64> = forecaster.shares(&mints).into_iter().collect();
+        for (mint, expected) in [(hot, 0.65), (warm, 0.25), (cold[0], 0.05), (cold[1], 0.05)] {
+            assert!((shares[&mint] - expected).abs() < 1e-9, "{} vs {}", shares[&mint], expected);
+        }
+
+        // Without any history the budget is split evenly
+        let fresh = YieldForecaster::new(1, 0.2, 0.5);
+        assert!(fresh.shares(&mints).iter().all(|(_, share)| *share == 0.25));
+    }
+
+    #[test]
+    fn selection_frequency_converges_to_the_shares_and_revisits_cold_tokens() {
+        let (hot, warm, cold) = (Pubkey::new_unique(), Pubkey::new_unique(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        let mut forecaster = forecaster_with_history(hot, warm, &cold);
+        let mints = [hot, warm, cold[0], cold[1]];
+
+        let mut picks: HashMap<Pubkey, usize> = HashMap::new();
+        for _ in 0..100 {
+            let selected = forecaster.select(&mints);
+            assert_eq!(selected.len(), 1);
+            *picks.entry(selected[0]).or_default() += 1;
+        }
+
+        for (mint, expected) in [(hot, 65), (warm, 25), (cold[0], 5), (cold[1], 5)] {
+            let picked = picks.get(&mint).copied().unwrap_or(0);
+            assert!(picked.abs_diff(expected) <= 1, "picked {} times, expected {}", picked, expected);
+        }
+    }
+
+    #[test]
+    fn a_universe_within_the_budget_is_priced_in_full() {
+        let mut forecaster = YieldForecaster::new(4, 0.2, 0.5);
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        assert_eq!(forecaster.select(&mints), mints);
+    }
+}
@@ -1,25 +1,511 @@
+use crate::clock::{system_clock, SharedClock};
 use crate::dex::*;
+use crate::dex::meteora::damm_info::{MeteoraDammInfo, MeteoraVaultInfo};
+use crate::dex::whirlpool::quote::{swap_tick_array_addresses, WhirlpoolTicks, STANDARD_SWAP_TICK_ARRAYS};
+use crate::dex::whirlpool::state::TickArray;
+use crate::engine::prefetch::PrefetchedAccounts;
 use crate::engine::types::*;
+use crate::mint_registry::MintRegistry;
+use crate::units::{Lamports, Usd};
+use crate::constants::{usd1_mint, usdc_mint};
 use crate::pools::*;
+use crate::rpc::RpcLike;
+use crate::strict::account_dump;
+use crate::token_extensions::{token_2022_program_id, transfer_fee_bps};
 use dashmap::DashMap;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
 use solana_sdk::pubkey::Pubkey;  // <-- ADD THIS LINE
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, warn};
 
+/// Unconfirmed SOL/USD price of a graph built without a bootstrap price
+const DEFAULT_SOL_PRICE_USD: f64 = 150.0;
+/// Bonding curves with this share of their tokens sold are flagged as near graduation
+const NEAR_GRADUATION_BPS: u64 = 9_500;
+
 pub struct PriceGraph {
-    pub edges: Arc<DashMap<Pubkey, Vec<PoolEdge>>>, // Key: from_mint
+    pub(crate) edges: Arc<DashMap<Pubkey, Vec<PoolEdge>>>, // Key: from_mint
+    generation: AtomicU64,
+    mint_generations: DashMap<Pubkey, u64>, // Key: mint, Value: generation of last edge change
+    pool_generations: DashMap<Pubkey, u64>, // Key: pool, Value: generation of last edge change
+    sol_price_usd: AtomicU64,               // f64 bits
+    sol_price_confirmed: AtomicBool,
+    mint_decimals: DashMap<Pubkey, u8>,     // Read once per mint; decimals never change
+    rpc_token_balances: AtomicBool,
+    min_liquidity_usd: AtomicU64,           // f64 bits
+    unconfirmed_liquidity_multiplier: AtomicU64, // f64 bits
+    strict_mode: AtomicBool,
+    anomaly: Mutex<Option<String>>,             // First anomaly seen in strict mode
+    prefetched: DashMap<Pubkey, (Account, Slot)>, // Accounts batch-fetched for the current refresh
+    prefetch_missing: Mutex<HashSet<Pubkey>>,     // Accounts the batch found nonexistent, not read again
+    reported_missing: Mutex<HashSet<Pubkey>>,     // Missing accounts already warned about
+    pool_read_slot: Mutex<Option<Slot>>,        // Oldest slot read for the pool being priced
+    pool_slots: DashMap<Pubkey, Slot>,          // Key: pool, Value: oldest slot its edges were priced from
+    slot_head: AtomicU64,                       // Freshest slot any edge was priced from
+    max_slot_lag: AtomicU64,                    // 0: no slot filter
+    update_tracker: Mutex<Option<UpdateTracker>>, // Set while `try_update_from_mint_pool_data` runs
+    whirlpool_ticks: DashMap<Pubkey, WhirlpoolTicks>, // Key: pool, for tick-walked quotes
+    constant_product: DashMap<(Pubkey, Pubkey), ConstantProductQuote>, // Key: (pool, to_mint)
+    bonding_curves: DashMap<(Pubkey, Pubkey), BondingCurveQuote>,      // Key: (curve, to_mint)
+    prefetch_hits: AtomicU64,    // Account reads served from the prefetched batch
+    individual_reads: AtomicU64, // Account reads that went to the RPC one by one
+    transfer_fee_bps: DashMap<Pubkey, u64>, // Key: Token-2022 mint, read once per mint
+    quote_prices_usd: DashMap<Pubkey, f64>, // Key: non-SOL quote mint
+    clock: SharedClock,
+}
+
+/// How the account reads since the last `take_account_reads` were served
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountReads {
+    pub prefetched: u64,
+    pub individual: u64, // Each one an RPC round trip
+}
+
+/// What one mint's graph update did to its pools
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    pub edges_added: usize,   // Edges inserted or repriced, after the liquidity filter
+    pub pools_priced: usize,  // Pools that produced at least one edge
+    pub pools_failed: usize,  // Pools with an account the RPC could not return
+    pub pools_skipped: usize, // Pools dropped for liquidity, parse or price problems
+    pub edges_below_liquidity: usize, // Edges dropped by the liquidity floor
+}
+
+impl UpdateStats {
+    pub fn pools(&self) -> usize {
+        self.pools_priced + self.pools_failed + self.pools_skipped
+    }
+
+    /// True when there were pools and every one of them failed to fetch
+    pub fn all_failed(&self) -> bool {
+        self.pools_failed > 0 && self.pools_failed == self.pools()
+    }
+
+    pub fn merge(&mut self, other: &UpdateStats) {
+        self.edges_added += other.edges_added;
+        self.pools_priced += other.pools_priced;
+        self.pools_failed += other.pools_failed;
+        self.pools_skipped += other.pools_skipped;
+        self.edges_below_liquidity += other.edges_below_liquidity;
+    }
+}
+
+/// Attributes edges and fetch failures to the pool being processed
+#[derive(Debug, Default)]
+struct UpdateTracker {
+    stats: UpdateStats,
+    pool_open: bool,
+    pool_edges: usize,
+    pool_fetch_failed: bool,
+}
+
+impl UpdateTracker {
+    fn close_pool(&mut self) {
+        if !self.pool_open {
+            return;
+        }
+        if self.pool_edges > 0 {
+            self.stats.pools_priced += 1;
+        } else if self.pool_fetch_failed {
+            self.stats.pools_failed += 1;
+        } else {
+            self.stats.pools_skipped += 1;
+        }
+        self.pool_open = false;
+        self.pool_edges = 0;
+        self.pool_fetch_failed = false;
+    }
 }
 
 impl PriceGraph {
     pub fn new() -> Self {
+        Self::with_bootstrap_sol_price(DEFAULT_SOL_PRICE_USD)
+    }
+
+    /// Graph valuing liquidity at `bootstrap_price_usd` per SOL. The price is
+    /// unconfirmed, so the stricter liquidity filter applies, until
+    /// `set_sol_price` confirms a live one.
+    pub fn with_bootstrap_sol_price(bootstrap_price_usd: f64) -> Self {
         Self {
             edges: Arc::new(DashMap::new()),
+            generation: AtomicU64::new(0),
+            mint_generations: DashMap::new(),
+            pool_generations: DashMap::new(),
+            sol_price_usd: AtomicU64::new(bootstrap_price_usd.to_bits()),
+            sol_price_confirmed: AtomicBool::new(false),
+            mint_decimals: DashMap::new(),
+            rpc_token_balances: AtomicBool::new(false),
+            min_liquidity_usd: AtomicU64::new(0f64.to_bits()),
+            unconfirmed_liquidity_multiplier: AtomicU64::new(1f64.to_bits()),
+            strict_mode: AtomicBool::new(false),
+            anomaly: Mutex::new(None),
+            prefetched: DashMap::new(),
+            prefetch_missing: Mutex::new(HashSet::new()),
+            reported_missing: Mutex::new(HashSet::new()),
+            pool_read_slot: Mutex::new(None),
+            pool_slots: DashMap::new(),
+            slot_head: AtomicU64::new(0),
+            max_slot_lag: AtomicU64::new(0),
+            update_tracker: Mutex::new(None),
+            whirlpool_ticks: DashMap::new(),
+            constant_product: DashMap::new(),
+            bonding_curves: DashMap::new(),
+            prefetch_hits: AtomicU64::new(0),
+            individual_reads: AtomicU64::new(0),
+            transfer_fee_bps: DashMap::new(),
+            quote_prices_usd: [(usdc_mint(), 1.0), (usd1_mint(), 1.0)].into_iter().collect(),
+            clock: system_clock(),
         }
     }
 
-    pub fn update_from_mint_pool_data(&self, pool_data: &MintPoolData, rpc_client: &solana_client::rpc_client::RpcClient) {
+    /// Clock that times the release of Meteora vault profit
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Minimum edge liquidity, scaled by `unconfirmed_multiplier` while the
+    /// SOL price is unconfirmed since liquidity estimates depend on it.
+    pub fn set_liquidity_filter(&self, min_liquidity_usd: f64, unconfirmed_multiplier: f64) {
+        self.min_liquidity_usd.store(min_liquidity_usd.to_bits(), Ordering::Relaxed);
+        self.unconfirmed_liquidity_multiplier
+            .store(unconfirmed_multiplier.max(1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// In strict mode every parser or price anomaly is recorded for
+    /// `take_anomaly` instead of the pool being silently skipped.
+    pub fn set_strict_mode(&self, strict: bool) {
+        self.strict_mode.store(strict, Ordering::Relaxed);
+    }
+
+    /// Serve decimals of registered mints without reading their mint account
+    pub fn set_mint_registry(&self, registry: &MintRegistry) {
+        for (mint, info) in registry.iter() {
+            self.mint_decimals.insert(*mint, info.decimals);
+        }
+    }
+
+    /// Read vault balances with `getTokenAccountBalance` instead of decoding
+    /// the (possibly prefetched) token account
+    pub fn set_rpc_token_balances(&self, enabled: bool) {
+        self.rpc_token_balances.store(enabled, Ordering::Relaxed);
+    }
+
+    /// First anomaly recorded since the last call, if strict mode is on
+    pub fn take_anomaly(&self) -> Option<String> {
+        self.anomaly.lock().unwrap().take()
+    }
+
+    fn record_anomaly(&self, message: impl FnOnce() -> String) {
+        if !self.strict_mode.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut anomaly = self.anomaly.lock().unwrap();
+        if anomaly.is_none() {
+            *anomaly = Some(message());
+        }
+    }
+
+    /// Serve these accounts to the next refresh instead of fetching each one.
+    /// Accounts the batch found missing fail their read without another RPC
+    /// call; each is warned about the first time it goes missing.
+    pub fn set_prefetched(&self, prefetched: PrefetchedAccounts) {
+        self.prefetched.clear();
+        for (pubkey, account) in prefetched.accounts {
+            let slot = prefetched.slots.get(&pubkey).copied().unwrap_or(0);
+            self.prefetched.insert(pubkey, (account, slot));
+        }
+        let mut reported = self.reported_missing.lock().unwrap();
+        for pubkey in &prefetched.missing {
+            if reported.insert(*pubkey) {
+                warn!("⚠️ Account {} does not exist, skipping the pools that read it", pubkey);
+            }
+        }
+        *self.prefetch_missing.lock().unwrap() = prefetched.missing.into_iter().collect();
+    }
+
+    /// Account reads since the last call, resetting the counts
+    pub fn take_account_reads(&self) -> AccountReads {
+        AccountReads {
+            prefetched: self.prefetch_hits.swap(0, Ordering::Relaxed),
+            individual: self.individual_reads.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    /// Drop prefetched accounts so a later refresh never reads stale state
+    pub fn clear_prefetched(&self) {
+        self.prefetched.clear();
+        self.prefetch_missing.lock().unwrap().clear();
+    }
+
+    fn fetch_account(&self, pubkey: &Pubkey, rpc_client: &dyn RpcLike) -> anyhow::Result<Account> {
+        if let Some(entry) = self.prefetched.get(pubkey) {
+            let (account, slot) = entry.value();
+            self.record_read_slot(*slot);
+            self.prefetch_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(account.clone());
+        }
+        if self.prefetch_missing.lock().unwrap().contains(pubkey) {
+            self.mark_fetch_failed();
+            anyhow::bail!("Account {} not found", pubkey);
+        }
+        self.individual_reads.fetch_add(1, Ordering::Relaxed);
+        let response = rpc_client.get_account_with_commitment(pubkey, rpc_client.commitment());
+        match response {
+            Ok(response) => {
+                self.record_read_slot(response.context.slot);
+                match response.value {
+                    Some(account) => Ok(account),
+                    None => {
+                        self.mark_fetch_failed();
+                        Err(anyhow::anyhow!("Account {} not found", pubkey))
+                    }
+                }
+            }
+            Err(e) => {
+                self.mark_fetch_failed();
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Exact-input output of a Whirlpool swap into `to_mint`, walked through
+    /// the tick arrays read on the pool's last refresh. None when the pool's
+    /// ticks were never loaded.
+    pub fn whirlpool_quote(&self, pool: &Pubkey, to_mint: &Pubkey, amount_in: u64) -> Option<u64> {
+        let ticks = self.whirlpool_ticks.get(pool)?;
+        let a_to_b = *to_mint == ticks.whirlpool.token_mint_b;
+        Some(ticks.whirlpool_quote(amount_in, a_to_b))
+    }
+
+    /// Reserves of a constant-product pool in the direction into `to_mint`.
+    /// None for other venues.
+    pub fn constant_product_quote(&self, pool: &Pubkey, to_mint: &Pubkey) -> Option<ConstantProductQuote> {
+        self.constant_product.get(&(*pool, *to_mint)).map(|quote| *quote)
+    }
+
+    /// Virtual reserves of a bonding curve in the direction into `to_mint`
+    pub fn bonding_curve_quote(&self, curve: &Pubkey, to_mint: &Pubkey) -> Option<BondingCurveQuote> {
+        self.bonding_curves.get(&(*curve, *to_mint)).map(|quote| *quote)
+    }
+
+    /// Store both directions of a TOKEN/SOL constant-product pool
+    fn record_constant_product(&self, pool: Pubkey, token_mint: Pubkey, sol_mint: Pubkey, token_reserve: u64, sol_reserve: u64, fee_bps: u64) {
+        let quote = |reserve_in, reserve_out| ConstantProductQuote { reserve_in, reserve_out, fee_numerator: fee_bps, fee_denominator: 10_000 };
+        self.constant_product.insert((pool, sol_mint), quote(token_reserve, sol_reserve));
+        self.constant_product.insert((pool, token_mint), quote(sol_reserve, token_reserve));
+    }
+
+    /// Tick arrays a swap in one direction walks, stopping at the first one
+    /// that is missing. Uninitialized arrays have no account, so a miss is
+    /// not a failed fetch.
+    fn load_swap_tick_arrays(
+        &self,
+        whirlpool: &crate::dex::whirlpool::state::Whirlpool,
+        pool: &Pubkey,
+        a_to_b: bool,
+        rpc_client: &dyn RpcLike,
+    ) -> Vec<TickArray> {
+        let mut addresses = swap_tick_array_addresses(whirlpool, pool, &DexType::Whirlpool.program_id(), a_to_b, STANDARD_SWAP_TICK_ARRAYS);
+        addresses.dedup(); // Repeated past the end of the tick range
+        addresses
+            .iter()
+            .map_while(|address| {
+                let data = match self.prefetched.get(address) {
+                    Some(entry) => {
+                        self.prefetch_hits.fetch_add(1, Ordering::Relaxed);
+                        entry.value().0.data.clone()
+                    }
+                    None => {
+                        self.individual_reads.fetch_add(1, Ordering::Relaxed);
+                        rpc_client.get_account_with_commitment(address, rpc_client.commitment()).ok()?.value?.data
+                    }
+                };
+                TickArray::try_deserialize(&data).ok()
+            })
+            .collect()
+    }
+
+    /// The pool being priced is only as fresh as the oldest account it read
+    fn record_read_slot(&self, slot: Slot) {
+        if slot == 0 {
+            return;
+        }
+        let mut pool_slot = self.pool_read_slot.lock().unwrap();
+        *pool_slot = Some(pool_slot.map_or(slot, |current| current.min(slot)));
+    }
+
+    /// Exclude from detection edges priced more than `max_slot_lag` slots
+    /// behind the freshest edge; 0 disables
+    pub fn set_max_slot_lag(&self, max_slot_lag: u64) {
+        self.max_slot_lag.store(max_slot_lag, Ordering::Relaxed);
+    }
+
+    /// Whether `pool`'s edges were priced too far behind the freshest edge.
+    /// Edges with no recorded slot are never lagging.
+    pub fn is_lagging(&self, pool: &Pubkey) -> bool {
+        let max_slot_lag = self.max_slot_lag.load(Ordering::Relaxed);
+        if max_slot_lag == 0 {
+            return false;
+        }
+        let head = self.slot_head.load(Ordering::Relaxed);
+        self.pool_slots.get(pool).is_some_and(|slot| head.saturating_sub(*slot) > max_slot_lag)
+    }
+
+    /// `sorted_edges` without the edges of lagging pools
+    pub fn detectable_edges(&self) -> Vec<(Pubkey, Vec<PoolEdge>)> {
+        let mut edges = self.sorted_edges();
+        if self.max_slot_lag.load(Ordering::Relaxed) > 0 {
+            for (_, pool_edges) in edges.iter_mut() {
+                pool_edges.retain(|edge| !self.is_lagging(&edge.pool_pubkey));
+            }
+        }
+        edges
+    }
+
+    /// Count the current pool as failed rather than skipped
+    fn mark_fetch_failed(&self) {
+        if let Some(tracker) = self.update_tracker.lock().unwrap().as_mut() {
+            tracker.pool_fetch_failed = true;
+        }
+    }
+
+    /// Attribute what follows to the next pool, closing the previous one
+    fn begin_pool(&self) {
+        *self.pool_read_slot.lock().unwrap() = None;
+        if let Some(tracker) = self.update_tracker.lock().unwrap().as_mut() {
+            tracker.close_pool();
+            tracker.pool_open = true;
+        }
+    }
+
+    pub fn set_sol_price(&self, price_usd: f64, confirmed: bool) {
+        self.sol_price_usd.store(price_usd.to_bits(), Ordering::Relaxed);
+        self.sol_price_confirmed.store(confirmed, Ordering::Relaxed);
+    }
+
+    pub fn sol_price_usd(&self) -> f64 {
+        f64::from_bits(self.sol_price_usd.load(Ordering::Relaxed))
+    }
+
+    /// USD price of a non-SOL quote mint. USDC and USD1 start at par.
+    pub fn set_quote_price(&self, mint: Pubkey, price_usd: f64) {
+        self.quote_prices_usd.insert(mint, price_usd);
+    }
+
+    pub fn quote_price_usd(&self, mint: &Pubkey) -> Option<f64> {
+        self.quote_prices_usd.get(mint).map(|price| *price)
+    }
+
+    pub fn effective_min_liquidity_usd(&self) -> f64 {
+        let min = f64::from_bits(self.min_liquidity_usd.load(Ordering::Relaxed));
+        if self.sol_price_confirmed.load(Ordering::Relaxed) {
+            min
+        } else {
+            min * f64::from_bits(self.unconfirmed_liquidity_multiplier.load(Ordering::Relaxed))
+        }
+    }
+
+    /// Start a new update generation. Edge changes made after this call are
+    /// attributed to the returned generation.
+    pub fn begin_generation(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Mints with at least one edge added or repriced after `generation`
+    pub fn changed_mints_since(&self, generation: u64) -> HashSet<Pubkey> {
+        self.mint_generations
+            .iter()
+            .filter(|entry| *entry.value() > generation)
+            .map(|entry| *entry.key())
+            .collect()
+    }
+
+    /// SOL per raw unit of `mint`, from its most liquid direct edge into SOL
+    pub fn price_in_sol(&self, mint: &Pubkey) -> Option<f64> {
+        let sol_mint = crate::constants::sol_mint();
+        if *mint == sol_mint {
+            return Some(1.0);
+        }
+        let edges = self.edges.get(mint)?;
+        edges
+            .iter()
+            .filter(|edge| edge.to_mint == sol_mint && edge.spot_price().is_finite() && edge.spot_price() > 0.0)
+            .max_by(|a, b| a.liquidity_usd.total_cmp(&b.liquidity_usd))
+            .map(|edge| edge.spot_price())
+    }
+
+    /// USD per native unit of `mint`, through its SOL price
+    pub fn usd_per_unit(&self, mint: &Pubkey) -> Option<f64> {
+        self.price_in_sol(mint).map(|price| price * self.sol_price_usd() / 1e9)
+    }
+
+    /// Generation in which an edge of `pool` last changed, 0 if never
+    pub fn pool_generation(&self, pool: &Pubkey) -> u64 {
+        self.pool_generations.get(pool).map_or(0, |generation| *generation)
+    }
+
+    /// Outgoing edges of `mint` as `(to_mint, edge)`, copied out so the caller
+    /// holds no lock on the graph. Empty when the mint has no edges.
+    pub fn neighbors(&self, mint: &Pubkey) -> Vec<(Pubkey, PoolEdge)> {
+        self.edges
+            .get(mint)
+            .map(|edges| edges.iter().map(|edge| (edge.to_mint, edge.clone())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Snapshot of all edges in a stable order (from_mint, then pool pubkey), so
+    /// detection doesn't depend on DashMap iteration or insertion order.
+    pub fn sorted_edges(&self) -> Vec<(Pubkey, Vec<PoolEdge>)> {
+        let mut edges: Vec<(Pubkey, Vec<PoolEdge>)> = self
+            .edges
+            .iter()
+            .map(|entry| {
+                let mut pool_edges = entry.value().clone();
+                pool_edges.sort_by_key(|edge| edge.pool_pubkey);
+                (*entry.key(), pool_edges)
+            })
+            .collect();
+        edges.sort_by_key(|(from_mint, _)| *from_mint);
+        edges
+    }
+
+    /// Like `update_from_mint_pool_data`, but reports per-pool outcomes and
+    /// fails when every pool of the mint failed to fetch. Updates must not
+    /// run concurrently on the same graph for the stats to be accurate.
+    pub fn try_update_from_mint_pool_data(
+        &self,
+        pool_data: &MintPoolData,
+        rpc_client: &dyn RpcLike,
+    ) -> anyhow::Result<UpdateStats> {
+        *self.update_tracker.lock().unwrap() = Some(UpdateTracker::default());
+        self.update_from_mint_pool_data(pool_data, rpc_client);
+        let stats = match self.update_tracker.lock().unwrap().take() {
+            Some(mut tracker) => {
+                tracker.close_pool();
+                tracker.stats
+            }
+            None => UpdateStats::default(),
+        };
+        if stats.all_failed() {
+            anyhow::bail!("all {} pools of mint {} failed to fetch", stats.pools_failed, pool_data.mint);
+        }
+        Ok(stats)
+    }
+
+    pub fn update_from_mint_pool_data(&self, pool_data: &MintPoolData, rpc_client: &dyn RpcLike) {
         let sol_mint = crate::constants::sol_mint();
-        
+        if pool_data.token_program == token_2022_program_id() {
+            self.load_transfer_fee(&pool_data.mint, rpc_client);
+        }
+
         // Process all pool types
         self.process_raydium_pools(pool_data, sol_mint, rpc_client);
         self.process_raydium_cp_pools(pool_data, sol_mint, rpc_client);
@@ -35,156 +521,209 @@ impl PriceGraph {
         self.process_humidifi_pools(pool_data, sol_mint, rpc_client);
         self.process_pancakeswap_pools(pool_data, sol_mint, rpc_client);
         self.process_byreal_pools(pool_data, sol_mint, rpc_client);
+        self.process_lifinity_pools(pool_data, sol_mint, rpc_client);
+        self.process_phoenix_markets(pool_data, sol_mint, rpc_client);
+        self.process_pump_bonding_curves(pool_data, sol_mint, rpc_client);
     }
 
-    fn process_raydium_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_raydium_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.raydium_pools {
-            if let Ok(price) = self.get_amm_price(&pool.token_vault, &pool.sol_vault, rpc_client) {
-                let token_liquidity = self.get_token_balance(&pool.token_vault, rpc_client).unwrap_or(0);
-                let sol_liquidity = self.get_token_balance(&pool.sol_vault, rpc_client).unwrap_or(0);
-                let liquidity_usd = (sol_liquidity as f64 * 200.0) + (token_liquidity as f64 * price * 200.0);
+            self.begin_pool();
+            if let Some(price) = self.amm_price_or_anomaly(&pool.pool, &pool.token_vault, &pool.sol_vault, rpc_client) {
+                let token_balance = self.token_balance_or_zero(&pool.token_vault, rpc_client);
+                let sol_balance = self.token_balance_or_zero(&pool.sol_vault, rpc_client);
+                let liquidity_usd = self.pair_liquidity_usd(&token_balance, &sol_balance);
+                self.record_constant_product(pool.pool, pool_data.mint, sol_mint, token_balance.amount, sol_balance.amount, 25);
 
                 // TOKEN -> SOL
                 self.add_edge(pool_data.mint, sol_mint, PoolEdge {
                     pool_pubkey: pool.pool,
+                    to_mint: sol_mint,
                     dex_type: DexType::RaydiumV4,
+                    program_id: DexType::RaydiumV4.program_id(),
                     price,
                     liquidity_usd,
                     fee_bps: 25,
                     inverse_fee_bps: 25,
                     token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
                 });
 
                 // SOL -> TOKEN
                 self.add_edge(sol_mint, pool_data.mint, PoolEdge {
                     pool_pubkey: pool.pool,
+                    to_mint: pool_data.mint,
                     dex_type: DexType::RaydiumV4,
+                    program_id: DexType::RaydiumV4.program_id(),
                     price: 1.0 / price,
                     liquidity_usd,
                     fee_bps: 25,
                     inverse_fee_bps: 25,
                     token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
                 });
             }
         }
     }
 
-    fn process_raydium_clmm_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_raydium_clmm_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.raydium_clmm_pools {
-            if let Ok(pool_state) = crate::dex::raydium::clmm_info::PoolState::load_checked(&rpc_client.get_account(&pool.pool).unwrap().data) {
+            self.begin_pool();
+            let account = match self.fetch_account(&pool.pool, rpc_client) {
+                Ok(account) => account,
+                Err(e) => {
+                    warn!("Failed to fetch CLMM pool {}: {}", pool.pool, e);
+                    continue;
+                }
+            };
+            if let Ok(pool_state) = crate::dex::raydium::clmm_info::PoolState::load_checked(&account.data) {
+                // A freshly created pool decodes fine but has no depth at its current tick
+                if pool_state.liquidity == 0 {
+                    debug!("Skipping CLMM pool {}: no active liquidity", pool.pool);
+                    continue;
+                }
                 let price = self.calculate_clmm_price(pool_state.sqrt_price_x64);
                 let liquidity_usd = self.estimate_clmm_liquidity(&pool_state, rpc_client);
 
-                // Determine which mint is which
-                if pool.token_mint == pool_state.token_mint_0 {
-                    // TOKEN -> SOL
-                    self.add_edge(pool.token_mint, pool_state.token_mint_1, PoolEdge {
-                        pool_pubkey: pool.pool,
-                        dex_type: DexType::RaydiumClmm,
-                        price,
-                        liquidity_usd,
-                        fee_bps: 5,
-                        inverse_fee_bps: 5,
-                        token_program: pool_data.token_program,
-                    });
-                    // SOL -> TOKEN
-                    self.add_edge(pool_state.token_mint_1, pool.token_mint, PoolEdge {
-                        pool_pubkey: pool.pool,
-                        dex_type: DexType::RaydiumClmm,
-                        price: 1.0 / price,
-                        liquidity_usd,
-                        fee_bps: 5,
-                        inverse_fee_bps: 5,
-                        token_program: pool_data.token_program,
-                    });
-                } else {
-                    // TOKEN -> SOL (inverse)
-                    self.add_edge(pool.token_mint, pool_state.token_mint_0, PoolEdge {
-                        pool_pubkey: pool.pool,
-                        dex_type: DexType::RaydiumClmm,
-                        price: 1.0 / price,
-                        liquidity_usd,
-                        fee_bps: 5,
-                        inverse_fee_bps: 5,
-                        token_program: pool_data.token_program,
-                    });
-                    // SOL -> TOKEN
-                    self.add_edge(pool_state.token_mint_0, pool.token_mint, PoolEdge {
-                        pool_pubkey: pool.pool,
-                        dex_type: DexType::RaydiumClmm,
-                        price,
-                        liquidity_usd,
-                        fee_bps: 5,
-                        inverse_fee_bps: 5,
-                        token_program: pool_data.token_program,
-                    });
+                // sqrt_price is mint_1 per mint_0; neither side has to be SOL
+                let (mint_0, mint_1) = (pool_state.token_mint_0, pool_state.token_mint_1);
+                if pool.token_mint != mint_0 && pool.token_mint != mint_1 {
+                    self.record_anomaly(|| format!("CLMM pool {} trades {} / {}, not {}", pool.pool, mint_0, mint_1, pool.token_mint));
+                    warn!("Skipping CLMM pool {}: token {} is neither of its mints", pool.pool, pool.token_mint);
+                    continue;
                 }
+
+                // mint_0 -> mint_1
+                self.add_edge(mint_0, mint_1, PoolEdge {
+                    pool_pubkey: pool.pool,
+                    to_mint: mint_1,
+                    dex_type: DexType::RaydiumClmm,
+                    program_id: DexType::RaydiumClmm.program_id(),
+                    price,
+                    liquidity_usd,
+                    fee_bps: 5,
+                    inverse_fee_bps: 5,
+                    token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
+                });
+                // mint_1 -> mint_0
+                self.add_edge(mint_1, mint_0, PoolEdge {
+                    pool_pubkey: pool.pool,
+                    to_mint: mint_0,
+                    dex_type: DexType::RaydiumClmm,
+                    program_id: DexType::RaydiumClmm.program_id(),
+                    price: 1.0 / price,
+                    liquidity_usd,
+                    fee_bps: 5,
+                    inverse_fee_bps: 5,
+                    token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
+                });
             }
         }
     }
 
-    fn process_whirlpool_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_whirlpool_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.whirlpool_pools {
-            if let Ok(whirlpool) = crate::dex::whirlpool::state::Whirlpool::try_deserialize(&rpc_client.get_account(&pool.pool).unwrap().data) {
+            self.begin_pool();
+            let account = match self.fetch_account(&pool.pool, rpc_client) {
+                Ok(account) => account,
+                Err(e) => {
+                    warn!("Failed to fetch Whirlpool {}: {}", pool.pool, e);
+                    continue;
+                }
+            };
+            if let Ok(whirlpool) = crate::dex::whirlpool::state::Whirlpool::try_deserialize(&account.data) {
+                if whirlpool.liquidity == 0 {
+                    debug!("Skipping Whirlpool {}: no active liquidity", pool.pool);
+                    continue;
+                }
                 let price = self.calculate_clmm_price(whirlpool.sqrt_price);
-                let liquidity_usd = (whirlpool.liquidity as f64) * 200.0 / 1e9; // Approximate
+                let liquidity_usd = (whirlpool.liquidity as f64) * self.sol_price_usd() / 1e9; // Approximate
+                let ticks = WhirlpoolTicks {
+                    whirlpool,
+                    a_to_b: self.load_swap_tick_arrays(&whirlpool, &pool.pool, true, rpc_client),
+                    b_to_a: self.load_swap_tick_arrays(&whirlpool, &pool.pool, false, rpc_client),
+                };
+                self.whirlpool_ticks.insert(pool.pool, ticks);
 
                 if pool.token_mint == whirlpool.token_mint_a {
                     self.add_edge(pool.token_mint, whirlpool.token_mint_b, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: whirlpool.token_mint_b,
                         dex_type: DexType::Whirlpool,
+                        program_id: DexType::Whirlpool.program_id(),
                         price,
                         liquidity_usd,
                         fee_bps: 2,
                         inverse_fee_bps: 2,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                     self.add_edge(whirlpool.token_mint_b, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::Whirlpool,
+                        program_id: DexType::Whirlpool.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
                         fee_bps: 2,
                         inverse_fee_bps: 2,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 } else {
                     self.add_edge(pool.token_mint, whirlpool.token_mint_a, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: whirlpool.token_mint_a,
                         dex_type: DexType::Whirlpool,
+                        program_id: DexType::Whirlpool.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
                         fee_bps: 2,
                         inverse_fee_bps: 2,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                     self.add_edge(whirlpool.token_mint_a, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::Whirlpool,
+                        program_id: DexType::Whirlpool.program_id(),
                         price,
                         liquidity_usd,
                         fee_bps: 2,
                         inverse_fee_bps: 2,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 }
             }
         }
     }
 
-    fn get_amm_price(&self, token_vault: &Pubkey, sol_vault: &Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) -> anyhow::Result<f64> {
-        let token_account = rpc_client.get_account(token_vault)?;
-        let sol_account = rpc_client.get_account(sol_vault)?;
-        
-        // Parse token account data to get amount
-        let token_amount = self.parse_token_amount(&token_account.data);
-        let sol_amount = self.parse_token_amount(&sol_account.data);
-        
+    fn get_amm_price(&self, token_vault: &Pubkey, sol_vault: &Pubkey, rpc_client: &dyn RpcLike) -> anyhow::Result<f64> {
+        // Raw over raw: the price stays in base units, like every other edge
+        let token_amount = self.get_token_balance(token_vault, rpc_client)?.amount;
+        let sol_amount = self.get_token_balance(sol_vault, rpc_client)?.amount;
+
         if sol_amount == 0 {
             return Err(anyhow::anyhow!("Zero SOL liquidity"));
         }
-        
-        Ok(token_amount as f64 / sol_amount as f64)
+        if token_amount == 0 {
+            return Err(anyhow::anyhow!("Zero token liquidity"));
+        }
+
+        // SOL out per token in, the TOKEN -> SOL edge's amount_out/amount_in
+        Ok(sol_amount as f64 / token_amount as f64)
     }
 
     fn calculate_clmm_price(&self, sqrt_price_x64: u128) -> f64 {
@@ -193,100 +732,360 @@ impl PriceGraph {
         sqrt_price * sqrt_price
     }
 
-    fn estimate_clmm_liquidity(&self, pool_state: &crate::dex::raydium::clmm_info::PoolState, _rpc_client: &solana_client::rpc_client::RpcClient) -> f64 {
+    /// price = (1 + bin_step/10000)^active_id, computed in log-space so extreme
+    /// active ids don't overflow to inf or underflow to 0. Returns None if the
+    /// price or its inverse is not a normal positive float.
+    fn calculate_dlmm_price(&self, bin_step_bps: u16, active_id: i32) -> Option<f64> {
+        let bin_step = bin_step_bps as f64 / 10_000.0;
+        let price = (active_id as f64 * bin_step.ln_1p()).exp();
+        if price.is_normal() && (1.0 / price).is_normal() {
+            Some(price)
+        } else {
+            None
+        }
+    }
+
+    fn estimate_clmm_liquidity(&self, pool_state: &crate::dex::raydium::clmm_info::PoolState, _rpc_client: &dyn RpcLike) -> f64 {
         // Approximate: liquidity * sqrt_price gives USD value
-        (pool_state.liquidity as f64 * self.calculate_clmm_price(pool_state.sqrt_price_x64)) / 1e9 * 200.0
+        (pool_state.liquidity as f64 * self.calculate_clmm_price(pool_state.sqrt_price_x64)) / 1e9 * self.sol_price_usd()
     }
 
-    fn parse_token_amount(&self, data: &[u8]) -> u64 {
-        if data.len() < 64 {
-            return 0;
+    /// Mint and amount of a token account. Token-2022 accounts share the
+    /// first 165 bytes of the SPL layout, extensions come after.
+    fn parse_token_account(&self, vault: &Pubkey, data: &[u8]) -> anyhow::Result<(Pubkey, u64)> {
+        if data.len() < 72 {
+            anyhow::bail!("token account {} data too short ({} bytes)", vault, data.len());
+        }
+        // Standard token account layout: mint (32) + owner (32) + amount (8)
+        let mint = Pubkey::new_from_array(data[0..32].try_into().unwrap());
+        let amount = u64::from_le_bytes(data[64..72].try_into().unwrap());
+        Ok((mint, amount))
+    }
+
+    /// Read the TransferFeeConfig of a Token-2022 mint on first use. A failed
+    /// read is retried on the next update; until then the fee counts as 0.
+    fn load_transfer_fee(&self, mint: &Pubkey, rpc_client: &dyn RpcLike) {
+        if self.transfer_fee_bps.contains_key(mint) {
+            return;
+        }
+        match self.fetch_account(mint, rpc_client) {
+            Ok(account) => {
+                let fee_bps = transfer_fee_bps(&account.data) as u64;
+                if fee_bps > 0 {
+                    debug!("Mint {} charges a {} bps transfer fee", mint, fee_bps);
+                }
+                self.transfer_fee_bps.insert(*mint, fee_bps);
+            }
+            Err(e) => warn!("Failed to read transfer fee of mint {}: {}", mint, e),
         }
-        // Standard token account layout: owner (32) + amount (8)
-        let mut amount_bytes = [0u8; 8];
-        amount_bytes.copy_from_slice(&data[64..72]);
-        u64::from_le_bytes(amount_bytes)
     }
 
-    fn process_raydium_cp_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    /// Transfer fee of `mint` in bps, 0 for mints without one
+    pub fn transfer_fee(&self, mint: &Pubkey) -> u64 {
+        self.transfer_fee_bps.get(mint).map_or(0, |fee_bps| *fee_bps)
+    }
+
+    /// Decimals of `mint`, read from the mint account on first use
+    fn get_mint_decimals(&self, mint: &Pubkey, rpc_client: &dyn RpcLike) -> anyhow::Result<u8> {
+        if let Some(decimals) = self.mint_decimals.get(mint) {
+            return Ok(*decimals);
+        }
+        let data = self.fetch_account(mint, rpc_client)?.data;
+        // Mint layout: mint authority option (36) + supply (8) + decimals (1)
+        if data.len() < 45 {
+            anyhow::bail!("mint {} data too short ({} bytes)", mint, data.len());
+        }
+        self.mint_decimals.insert(*mint, data[44]);
+        Ok(data[44])
+    }
+
+    fn process_raydium_cp_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         // Implementation similar to Raydium V4
         for pool in &pool_data.raydium_cp_pools {
-            if let Ok(price) = self.get_amm_price(&pool.token_vault, &pool.sol_vault, rpc_client) {
-                let liquidity_usd = self.estimate_amm_liquidity(&pool.token_vault, &pool.sol_vault, rpc_client, price);
+            self.begin_pool();
+            if let Some(price) = self.amm_price_or_anomaly(&pool.pool, &pool.token_vault, &pool.sol_vault, rpc_client) {
+                let liquidity_usd = self.estimate_amm_liquidity(&pool.token_vault, &pool.sol_vault, rpc_client);
                 
                 self.add_edge(pool_data.mint, sol_mint, PoolEdge {
                     pool_pubkey: pool.pool,
+                    to_mint: sol_mint,
                     dex_type: DexType::RaydiumCp,
+                    program_id: DexType::RaydiumCp.program_id(),
                     price,
                     liquidity_usd,
                     fee_bps: 5,
                     inverse_fee_bps: 5,
                     token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
                 });
                 
                 self.add_edge(sol_mint, pool_data.mint, PoolEdge {
                     pool_pubkey: pool.pool,
+                    to_mint: pool_data.mint,
                     dex_type: DexType::RaydiumCp,
+                    program_id: DexType::RaydiumCp.program_id(),
                     price: 1.0 / price,
                     liquidity_usd,
                     fee_bps: 5,
                     inverse_fee_bps: 5,
                     token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
                 });
             }
         }
     }
 
-    fn process_pump_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_pump_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.pump_pools {
-            if let Ok(price) = self.get_amm_price(&pool.token_vault, &pool.sol_vault, rpc_client) {
-                let liquidity_usd = self.estimate_amm_liquidity(&pool.token_vault, &pool.sol_vault, rpc_client, price);
+            self.begin_pool();
+            if let Some(price) = self.amm_price_or_anomaly(&pool.pool, &pool.token_vault, &pool.sol_vault, rpc_client) {
+                let token_balance = self.token_balance_or_zero(&pool.token_vault, rpc_client);
+                let sol_balance = self.token_balance_or_zero(&pool.sol_vault, rpc_client);
+                let liquidity_usd = self.pair_liquidity_usd(&token_balance, &sol_balance);
+                self.record_constant_product(pool.pool, pool_data.mint, sol_mint, token_balance.amount, sol_balance.amount, 100);
                 
                 self.add_edge(pool_data.mint, sol_mint, PoolEdge {
                     pool_pubkey: pool.pool,
+                    to_mint: sol_mint,
                     dex_type: DexType::Pump,
+                    program_id: DexType::Pump.program_id(),
                     price,
                     liquidity_usd,
                     fee_bps: 100, // Pump has higher fees
                     inverse_fee_bps: 100,
                     token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
                 });
                 
                 self.add_edge(sol_mint, pool_data.mint, PoolEdge {
                     pool_pubkey: pool.pool,
+                    to_mint: pool_data.mint,
                     dex_type: DexType::Pump,
+                    program_id: DexType::Pump.program_id(),
                     price: 1.0 / price,
                     liquidity_usd,
                     fee_bps: 100,
                     inverse_fee_bps: 100,
                     token_program: pool_data.token_program,
+                    transfer_fee_bps: 0,
+                    near_graduation: false,
                 });
             }
         }
     }
 
-    fn estimate_amm_liquidity(&self, token_vault: &Pubkey, sol_vault: &Pubkey, rpc_client: &solana_client::rpc_client::RpcClient, price: f64) -> f64 {
-        let token_amount = self.get_token_balance(token_vault, rpc_client).unwrap_or(0);
-        let sol_amount = self.get_token_balance(sol_vault, rpc_client).unwrap_or(0);
-        (token_amount as f64 * price * 200.0) + (sol_amount as f64 * 200.0)
+    /// Pre-graduation pump.fun tokens, priced from the curve's virtual
+    /// reserves. Completed curves no longer trade and are skipped.
+    fn process_pump_bonding_curves(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
+        for pool in &pool_data.pump_bonding_curves {
+            self.begin_pool();
+            let curve = match self
+                .fetch_account(&pool.curve, rpc_client)
+                .and_then(|account| pump::BondingCurveInfo::load_checked(&account.data))
+            {
+                Ok(curve) => curve,
+                Err(e) => {
+                    self.record_anomaly(|| format!("failed to read bonding curve {}: {}", pool.curve, e));
+                    warn!("Failed to read pump.fun bonding curve {}: {}", pool.curve, e);
+                    continue;
+                }
+            };
+            if curve.complete {
+                debug!("Bonding curve {} has completed, skipping", pool.curve);
+                continue;
+            }
+            if curve.virtual_sol_reserves == 0 || curve.virtual_token_reserves == 0 {
+                self.record_anomaly(|| format!("bonding curve {} has empty virtual reserves", pool.curve));
+                continue;
+            }
+
+            let price = curve.virtual_sol_reserves as f64 / curve.virtual_token_reserves as f64; // SOL per token, base units
+            let liquidity_usd = self.sol_pair_liquidity_usd(curve.real_sol_reserves);
+            let near_graduation = curve.graduation_progress_bps() >= NEAR_GRADUATION_BPS;
+            let fee_bps = pump::BONDING_CURVE_FEE_BPS;
+            let quote = |buy| BondingCurveQuote {
+                virtual_sol_reserves: curve.virtual_sol_reserves,
+                virtual_token_reserves: curve.virtual_token_reserves,
+                real_token_reserves: curve.real_token_reserves,
+                fee_bps,
+                buy,
+            };
+            self.bonding_curves.insert((pool.curve, sol_mint), quote(false));
+            self.bonding_curves.insert((pool.curve, pool_data.mint), quote(true));
+
+            // TOKEN -> SOL (sell)
+            self.add_edge(pool_data.mint, sol_mint, PoolEdge {
+                pool_pubkey: pool.curve,
+                to_mint: sol_mint,
+                dex_type: DexType::PumpBondingCurve,
+                program_id: DexType::PumpBondingCurve.program_id(),
+                price,
+                liquidity_usd,
+                fee_bps,
+                inverse_fee_bps: fee_bps,
+                token_program: pool_data.token_program,
+                transfer_fee_bps: 0,
+                near_graduation,
+            });
+
+            // SOL -> TOKEN (buy)
+            self.add_edge(sol_mint, pool_data.mint, PoolEdge {
+                pool_pubkey: pool.curve,
+                to_mint: pool_data.mint,
+                dex_type: DexType::PumpBondingCurve,
+                program_id: DexType::PumpBondingCurve.program_id(),
+                price: 1.0 / price,
+                liquidity_usd,
+                fee_bps,
+                inverse_fee_bps: fee_bps,
+                token_program: pool_data.token_program,
+                transfer_fee_bps: 0,
+                near_graduation,
+            });
+        }
+    }
+
+    fn estimate_amm_liquidity(&self, token_vault: &Pubkey, sol_vault: &Pubkey, rpc_client: &dyn RpcLike) -> f64 {
+        let token_balance = self.token_balance_or_zero(token_vault, rpc_client);
+        let sol_balance = self.token_balance_or_zero(sol_vault, rpc_client);
+        self.pair_liquidity_usd(&token_balance, &sol_balance)
+    }
+
+    /// USD value of a TOKEN/SOL pool's two vaults. The SOL side is converted to
+    /// whole SOL with its mint's decimals before the SOL price is applied; at
+    /// the pool's own price the token side is worth as much, whatever its
+    /// decimals. An empty token side adds nothing.
+    fn pair_liquidity_usd(&self, token: &TokenBalance, sol: &TokenBalance) -> f64 {
+        let sol_usd = Usd(sol.ui_amount() * self.sol_price_usd());
+        if token.amount == 0 {
+            return sol_usd.value();
+        }
+        (sol_usd * 2.0).value()
+    }
+
+    /// Same as `pair_liquidity_usd` when only the SOL reserve in lamports is known
+    fn sol_pair_liquidity_usd(&self, sol_lamports: u64) -> f64 {
+        (Lamports(sol_lamports).to_usd(self.sol_price_usd()) * 2.0).value()
+    }
+
+    /// Two-sided pool value from its base reserve: SOL through the SOL price,
+    /// quote mints through their oracle price with their own decimals. Other
+    /// bases are not valued and come out as 0.
+    fn base_pair_liquidity_usd(
+        &self,
+        base_mint: &Pubkey,
+        sol_mint: Pubkey,
+        base_reserve: u64,
+        rpc_client: &dyn RpcLike,
+    ) -> f64 {
+        if *base_mint == sol_mint {
+            return self.sol_pair_liquidity_usd(base_reserve);
+        }
+        let Some(price_usd) = self.quote_price_usd(base_mint) else {
+            return 0.0;
+        };
+        match self.get_mint_decimals(base_mint, rpc_client) {
+            Ok(decimals) => 2.0 * TokenBalance { amount: base_reserve, decimals }.ui_amount() * price_usd,
+            Err(e) => {
+                self.record_anomaly(|| format!("failed to read decimals of base mint {}: {}", base_mint, e));
+                0.0
+            }
+        }
+    }
+
+    /// Vault balance, treating an unreadable vault as empty like the
+    /// non-strict path always has
+    fn token_balance_or_zero(&self, vault: &Pubkey, rpc_client: &dyn RpcLike) -> TokenBalance {
+        match self.get_token_balance(vault, rpc_client) {
+            Ok(balance) => balance,
+            Err(e) => {
+                self.record_anomaly(|| format!("failed to read token vault {}: {}", vault, e));
+                TokenBalance::default()
+            }
+        }
+    }
+
+    fn amm_price_or_anomaly(
+        &self,
+        pool: &Pubkey,
+        token_vault: &Pubkey,
+        sol_vault: &Pubkey,
+        rpc_client: &dyn RpcLike,
+    ) -> Option<f64> {
+        match self.get_amm_price(token_vault, sol_vault, rpc_client) {
+            Ok(price) => Some(price),
+            Err(e) => {
+                self.record_anomaly(|| format!("failed to price pool {} from vaults {} / {}: {}", pool, token_vault, sol_vault, e));
+                None
+            }
+        }
     }
 
-    fn get_token_balance(&self, vault: &Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) -> anyhow::Result<u64> {
-        let account = rpc_client.get_account(vault)?;
-        Ok(self.parse_token_amount(&account.data))
+    /// Tokens a DAMM v1 pool owns in a Meteora vault: the vault's unlocked
+    /// amount scaled by the pool's share of the vault's LP supply
+    fn damm_vault_reserve(
+        &self,
+        vault: &Pubkey,
+        pool_lp: &Pubkey,
+        lp_mint: &Pubkey,
+        rpc_client: &dyn RpcLike,
+    ) -> anyhow::Result<u64> {
+        let vault_info = MeteoraVaultInfo::load_checked(&self.fetch_account(vault, rpc_client)?.data)?;
+        let lp_amount = self.get_token_balance(pool_lp, rpc_client)?.amount;
+        let lp_mint_data = self.fetch_account(lp_mint, rpc_client)?.data;
+        if lp_mint_data.len() < 44 {
+            anyhow::bail!("LP mint {} data too short", lp_mint);
+        }
+        let lp_supply = u64::from_le_bytes(lp_mint_data[36..44].try_into().unwrap());
+        Ok(vault_info.share_of(lp_amount, lp_supply, self.clock.now_unix()))
+    }
+
+    /// Balance of a token account with its mint's decimals
+    fn get_token_balance(&self, vault: &Pubkey, rpc_client: &dyn RpcLike) -> anyhow::Result<TokenBalance> {
+        if self.rpc_token_balances.load(Ordering::Relaxed) {
+            self.individual_reads.fetch_add(1, Ordering::Relaxed);
+            let ui_amount = rpc_client.get_token_account_balance(vault).map_err(|e| {
+                self.mark_fetch_failed();
+                anyhow::anyhow!("getTokenAccountBalance failed for {}: {}", vault, e)
+            })?;
+            let amount = ui_amount
+                .amount
+                .parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("bad balance {:?} for {}: {}", ui_amount.amount, vault, e))?;
+            return Ok(TokenBalance { amount, decimals: ui_amount.decimals });
+        }
+        let account = self.fetch_account(vault, rpc_client)?;
+        let (mint, amount) = self.parse_token_account(vault, &account.data)?;
+        let decimals = self.get_mint_decimals(&mint, rpc_client)?;
+        Ok(TokenBalance { amount, decimals })
     }
 
     // Stub implementations for other DEX types - add full implementations in Phase 1.x
-    fn process_dlmm_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_dlmm_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pair in &pool_data.dlmm_pairs {
-            match rpc_client.get_account(&pair.pair) {
+            self.begin_pool();
+            match self.fetch_account(&pair.pair, rpc_client) {
                 Ok(account) => {
                     match crate::dex::meteora::dlmm_info::DlmmInfo::load_checked(&account.data) {
                         Ok(dlmm_info) => {
                             // Calculate price from active bin
-                            // price = (1 + bin_step/10000)^active_id
-                            let bin_step = dlmm_info.lb_pair.bin_step as f64 / 10_000.0;
-                            let price = (1.0 + bin_step).powi(dlmm_info.active_id);
+                            let price = match self.calculate_dlmm_price(dlmm_info.lb_pair.bin_step, dlmm_info.active_id) {
+                                Some(price) => price,
+                                None => {
+                                    self.record_anomaly(|| format!(
+                                        "DLMM active_id {} with bin_step {} is out of representable price range, {}",
+                                        dlmm_info.active_id, dlmm_info.lb_pair.bin_step, account_dump(&pair.pair, &account)
+                                    ));
+                                    warn!(
+                                        "Skipping DLMM pool {}: active_id {} with bin_step {} is out of representable price range",
+                                        pair.pair, dlmm_info.active_id, dlmm_info.lb_pair.bin_step
+                                    );
+                                    continue;
+                                }
+                            };
                             
                             // Estimate liquidity from bin arrays (simplified)
                             let liquidity_usd = (dlmm_info.active_id.abs() as f64) * 1000.0; // Approximate
@@ -296,47 +1095,66 @@ impl PriceGraph {
                                 // TOKEN_X -> TOKEN_Y
                                 self.add_edge(dlmm_info.token_x_mint, dlmm_info.token_y_mint, PoolEdge {
                                     pool_pubkey: pair.pair,
+                                    to_mint: dlmm_info.token_y_mint,
                                     dex_type: DexType::MeteoraDlmm,
+                                    program_id: DexType::MeteoraDlmm.program_id(),
                                     price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                                 // TOKEN_Y -> TOKEN_X
                                 self.add_edge(dlmm_info.token_y_mint, dlmm_info.token_x_mint, PoolEdge {
                                     pool_pubkey: pair.pair,
+                                    to_mint: dlmm_info.token_x_mint,
                                     dex_type: DexType::MeteoraDlmm,
+                                    program_id: DexType::MeteoraDlmm.program_id(),
                                     price: 1.0 / price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                             } else {
                                 // TOKEN_Y -> TOKEN_X
                                 self.add_edge(dlmm_info.token_y_mint, dlmm_info.token_x_mint, PoolEdge {
                                     pool_pubkey: pair.pair,
+                                    to_mint: dlmm_info.token_x_mint,
                                     dex_type: DexType::MeteoraDlmm,
+                                    program_id: DexType::MeteoraDlmm.program_id(),
                                     price: 1.0 / price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                                 // TOKEN_X -> TOKEN_Y
                                 self.add_edge(dlmm_info.token_x_mint, dlmm_info.token_y_mint, PoolEdge {
                                     pool_pubkey: pair.pair,
+                                    to_mint: dlmm_info.token_y_mint,
                                     dex_type: DexType::MeteoraDlmm,
+                                    program_id: DexType::MeteoraDlmm.program_id(),
                                     price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                             }
                         }
-                        Err(e) => warn!("Failed to parse DLMM pool {}: {}", pair.pair, e),
+                        Err(e) => {
+                            self.record_anomaly(|| format!("failed to parse DLMM pool: {}, {}", e, account_dump(&pair.pair, &account)));
+                            warn!("Failed to parse DLMM pool {}: {}", pair.pair, e)
+                        }
                     }
                 }
                 Err(e) => warn!("Failed to fetch DLMM pool {}: {}", pair.pair, e),
@@ -344,144 +1162,203 @@ impl PriceGraph {
         }
     }
 
-    fn process_meteora_damm_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_meteora_damm_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.meteora_damm_pools {
-            // Use token accounts for price calculation
+            self.begin_pool();
+            let info = match self.fetch_account(&pool.pool, rpc_client) {
+                Ok(account) => match MeteoraDammInfo::load_checked(&account.data) {
+                    Ok(info) => info,
+                    Err(e) => {
+                        self.record_anomaly(|| format!("failed to parse Meteora DAMM pool: {}, {}", e, account_dump(&pool.pool, &account)));
+                        warn!("Failed to parse Meteora DAMM pool {}: {}", pool.pool, e);
+                        continue;
+                    }
+                },
+                Err(e) => {
+                    warn!("Failed to fetch Meteora DAMM pool {}: {}", pool.pool, e);
+                    continue;
+                }
+            };
+            if !info.enabled {
+                debug!("Skipping disabled Meteora DAMM pool {}", pool.pool);
+                continue;
+            }
+            let fee_bps = info.fee_bps();
+
+            // Reserves are the pool's share of each Meteora vault, not the vault's token account
             if let (Ok(token_x_balance), Ok(sol_balance)) = (
-                self.get_token_balance(&pool.token_x_token_vault, rpc_client),
-                self.get_token_balance(&pool.token_sol_token_vault, rpc_client)
+                self.damm_vault_reserve(&pool.token_x_vault, &pool.token_x_pool_lp, &pool.token_x_lp_mint, rpc_client),
+                self.damm_vault_reserve(&pool.token_sol_vault, &pool.token_sol_pool_lp, &pool.token_sol_lp_mint, rpc_client)
             ) {
-                if sol_balance > 0 {
-                    let price = token_x_balance as f64 / sol_balance as f64;
-                    let liquidity_usd = (token_x_balance as f64 * price * 200.0) + (sol_balance as f64 * 200.0);
+                if sol_balance > 0 && token_x_balance > 0 {
+                    let price = sol_balance as f64 / token_x_balance as f64;
+                    // Vault shares carry no decimals; value both sides off the SOL reserve
+                    let liquidity_usd = self.sol_pair_liquidity_usd(sol_balance);
 
                     self.add_edge(pool.token_mint, sol_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: sol_mint,
                         dex_type: DexType::MeteoraDamm,
+                        program_id: DexType::MeteoraDamm.program_id(),
                         price,
                         liquidity_usd,
-                        fee_bps: 10,
-                        inverse_fee_bps: 10,
+                        fee_bps,
+                        inverse_fee_bps: fee_bps,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
 
                     self.add_edge(sol_mint, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::MeteoraDamm,
+                        program_id: DexType::MeteoraDamm.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
-                        fee_bps: 10,
-                        inverse_fee_bps: 10,
+                        fee_bps,
+                        inverse_fee_bps: fee_bps,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 }
             }
         }
     }
 
-    fn process_meteora_damm_v2_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_meteora_damm_v2_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.meteora_damm_v2_pools {
+            self.begin_pool();
             // DAMM v2 uses direct vault balances
             if let (Ok(token_x_balance), Ok(sol_balance)) = (
                 self.get_token_balance(&pool.token_x_vault, rpc_client),
                 self.get_token_balance(&pool.token_sol_vault, rpc_client)
             ) {
-                if sol_balance > 0 {
-                    let price = token_x_balance as f64 / sol_balance as f64;
-                    let liquidity_usd = (token_x_balance as f64 * price * 200.0) + (sol_balance as f64 * 200.0);
+                if sol_balance.amount > 0 && token_x_balance.amount > 0 {
+                    let price = sol_balance.amount as f64 / token_x_balance.amount as f64;
+                    let liquidity_usd = self.pair_liquidity_usd(&token_x_balance, &sol_balance);
 
                     self.add_edge(pool.token_mint, sol_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: sol_mint,
                         dex_type: DexType::MeteoraDammV2,
+                        program_id: DexType::MeteoraDammV2.program_id(),
                         price,
                         liquidity_usd,
                         fee_bps: 8,
                         inverse_fee_bps: 8,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
 
                     self.add_edge(sol_mint, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::MeteoraDammV2,
+                        program_id: DexType::MeteoraDammV2.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
                         fee_bps: 8,
                         inverse_fee_bps: 8,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 }
             }
         }
     }
 
-    fn process_vertigo_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_vertigo_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.vertigo_pools {
+            self.begin_pool();
             if let (Ok(token_x_balance), Ok(sol_balance)) = (
                 self.get_token_balance(&pool.token_x_vault, rpc_client),
                 self.get_token_balance(&pool.token_sol_vault, rpc_client)
             ) {
-                if sol_balance > 0 {
-                    let price = token_x_balance as f64 / sol_balance as f64;
-                    let liquidity_usd = (token_x_balance as f64 * price * 200.0) + (sol_balance as f64 * 200.0);
+                if sol_balance.amount > 0 && token_x_balance.amount > 0 {
+                    let price = sol_balance.amount as f64 / token_x_balance.amount as f64;
+                    let liquidity_usd = self.pair_liquidity_usd(&token_x_balance, &sol_balance);
+                    self.record_constant_product(pool.pool, pool.token_mint, sol_mint, token_x_balance.amount, sol_balance.amount, 15);
 
                     self.add_edge(pool.token_mint, sol_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: sol_mint,
                         dex_type: DexType::Vertigo,
+                        program_id: DexType::Vertigo.program_id(),
                         price,
                         liquidity_usd,
                         fee_bps: 15,
                         inverse_fee_bps: 15,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
 
                     self.add_edge(sol_mint, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::Vertigo,
+                        program_id: DexType::Vertigo.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
                         fee_bps: 15,
                         inverse_fee_bps: 15,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 }
             }
         }
     }
 
-fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.heaven_pools {
-            match rpc_client.get_account(&pool.pool) {
+            self.begin_pool();
+            match self.fetch_account(&pool.pool, rpc_client) {
                 Ok(account) => {
                     // <-- FIXED: Changed from `if let Ok` to `if let Some`
                     if let Some(heaven_state) = crate::dex::heaven::info::HeavenPoolState::parse(
                         &account.data
                     ) {
                         // Heaven uses reserve ratios
-                        if heaven_state.reserve_b > 0 {
-                            let price = heaven_state.reserve_a as f64 / heaven_state.reserve_b as f64;
-                            let liquidity_usd = (heaven_state.reserve_a as f64 * price * 200.0) + 
-                                               (heaven_state.reserve_b as f64 * 200.0);
+                        if heaven_state.reserve_a > 0 && heaven_state.reserve_b > 0 {
+                            let price = heaven_state.reserve_b as f64 / heaven_state.reserve_a as f64;
+                            let liquidity_usd = self.base_pair_liquidity_usd(&pool.base_mint, sol_mint, heaven_state.reserve_b, rpc_client);
 
                             self.add_edge(pool.token_mint, pool.base_mint, PoolEdge {
                                 pool_pubkey: pool.pool,
+                                to_mint: pool.base_mint,
                                 dex_type: DexType::Heaven,
+                                program_id: DexType::Heaven.program_id(),
                                 price,
                                 liquidity_usd,
                                 fee_bps: 20,
                                 inverse_fee_bps: 20,
                                 token_program: pool_data.token_program,
+                                transfer_fee_bps: 0,
+                                near_graduation: false,
                             });
 
                             self.add_edge(pool.base_mint, pool.token_mint, PoolEdge {
                                 pool_pubkey: pool.pool,
+                                to_mint: pool.token_mint,
                                 dex_type: DexType::Heaven,
+                                program_id: DexType::Heaven.program_id(),
                                 price: 1.0 / price,
                                 liquidity_usd,
                                 fee_bps: 20,
                                 inverse_fee_bps: 20,
                                 token_program: pool_data.token_program,
+                                transfer_fee_bps: 0,
+                                near_graduation: false,
                             });
                         }
+                    } else {
+                        self.record_anomaly(|| format!("failed to parse Heaven pool, {}", account_dump(&pool.pool, &account)));
                     }
                 }
                 Err(e) => warn!("Failed to fetch Heaven pool {}: {}", pool.pool, e),
@@ -489,82 +1366,214 @@ fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_c
         }
     }
 
-    fn process_futarchy_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_futarchy_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.futarchy_pools {
+            self.begin_pool();
             // Futarchy uses simple vault balances
             if let (Ok(token_x_balance), Ok(sol_balance)) = (
                 self.get_token_balance(&pool.token_x_vault, rpc_client),
                 self.get_token_balance(&pool.token_sol_vault, rpc_client)
             ) {
-                if sol_balance > 0 {
-                    let price = token_x_balance as f64 / sol_balance as f64;
-                    let liquidity_usd = (token_x_balance as f64 * price * 200.0) + (sol_balance as f64 * 200.0);
+                if sol_balance.amount > 0 && token_x_balance.amount > 0 {
+                    let price = sol_balance.amount as f64 / token_x_balance.amount as f64;
+                    let liquidity_usd = self.pair_liquidity_usd(&token_x_balance, &sol_balance);
+                    self.record_constant_product(pool.dao, pool.token_mint, sol_mint, token_x_balance.amount, sol_balance.amount, 25);
 
                     self.add_edge(pool.token_mint, sol_mint, PoolEdge {
                         pool_pubkey: pool.dao,
+                        to_mint: sol_mint,
                         dex_type: DexType::Futarchy,
+                        program_id: DexType::Futarchy.program_id(),
                         price,
                         liquidity_usd,
                         fee_bps: 25,
                         inverse_fee_bps: 25,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
 
                     self.add_edge(sol_mint, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.dao,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::Futarchy,
+                        program_id: DexType::Futarchy.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
                         fee_bps: 25,
                         inverse_fee_bps: 25,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 }
             }
         }
     }
 
-    fn process_humidifi_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_humidifi_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         for pool in &pool_data.humidifi_pools {
+            self.begin_pool();
             // Humidifi uses vault balances
             if let (Ok(token_x_balance), Ok(sol_balance)) = (
                 self.get_token_balance(&pool.token_x_vault, rpc_client),
                 self.get_token_balance(&pool.token_sol_vault, rpc_client)
             ) {
-                if sol_balance > 0 {
-                    let price = token_x_balance as f64 / sol_balance as f64;
-                    let liquidity_usd = (token_x_balance as f64 * price * 200.0) + (sol_balance as f64 * 200.0);
+                if sol_balance.amount > 0 && token_x_balance.amount > 0 {
+                    let price = sol_balance.amount as f64 / token_x_balance.amount as f64;
+                    let liquidity_usd = self.pair_liquidity_usd(&token_x_balance, &sol_balance);
 
                     self.add_edge(pool.token_mint, sol_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: sol_mint,
                         dex_type: DexType::Humidifi,
+                        program_id: DexType::Humidifi.program_id(),
                         price,
                         liquidity_usd,
                         fee_bps: 12,
                         inverse_fee_bps: 12,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
 
                     self.add_edge(sol_mint, pool.token_mint, PoolEdge {
                         pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
                         dex_type: DexType::Humidifi,
+                        program_id: DexType::Humidifi.program_id(),
                         price: 1.0 / price,
                         liquidity_usd,
                         fee_bps: 12,
                         inverse_fee_bps: 12,
                         token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
                     });
                 }
             }
         }
     }
 
-    fn process_pancakeswap_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_lifinity_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
+        for pool in &pool_data.lifinity_pools {
+            self.begin_pool();
+            // Lifinity quotes around its oracle price and rebalances toward it.
+            // That curve is not modeled: the vault ratio only approximates the
+            // executable price, which is one reason these legs stay observational
+            if let (Ok(token_x_balance), Ok(sol_balance)) = (
+                self.get_token_balance(&pool.token_x_vault, rpc_client),
+                self.get_token_balance(&pool.token_sol_vault, rpc_client)
+            ) {
+                if sol_balance.amount > 0 && token_x_balance.amount > 0 {
+                    let price = sol_balance.amount as f64 / token_x_balance.amount as f64;
+                    let liquidity_usd = self.pair_liquidity_usd(&token_x_balance, &sol_balance);
+
+                    self.add_edge(pool.token_mint, sol_mint, PoolEdge {
+                        pool_pubkey: pool.pool,
+                        to_mint: sol_mint,
+                        dex_type: DexType::Lifinity,
+                        program_id: DexType::Lifinity.program_id(),
+                        price,
+                        liquidity_usd,
+                        fee_bps: pool.fee_bps,
+                        inverse_fee_bps: pool.fee_bps,
+                        token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
+                    });
+
+                    self.add_edge(sol_mint, pool.token_mint, PoolEdge {
+                        pool_pubkey: pool.pool,
+                        to_mint: pool.token_mint,
+                        dex_type: DexType::Lifinity,
+                        program_id: DexType::Lifinity.program_id(),
+                        price: 1.0 / price,
+                        liquidity_usd,
+                        fee_bps: pool.fee_bps,
+                        inverse_fee_bps: pool.fee_bps,
+                        token_program: pool_data.token_program,
+                        transfer_fee_bps: 0,
+                        near_graduation: false,
+                    });
+                }
+            }
+        }
+    }
+
+    fn process_phoenix_markets(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
+        use crate::dex::phoenix::{PhoenixMarketInfo, TOP_OF_BOOK_LEVELS};
+
+        for market in &pool_data.phoenix_markets {
+            self.begin_pool();
+            let info = match self
+                .fetch_account(&market.market, rpc_client)
+                .and_then(|account| PhoenixMarketInfo::load_checked(&account.data))
+            {
+                Ok(info) => info,
+                Err(e) => {
+                    debug!("Skipping Phoenix market {}: {}", market.market, e);
+                    continue;
+                }
+            };
+            // Quote atoms per base atom; an empty side leaves nothing to trade against
+            let (Some(bid), Some(ask)) = (info.best_bid(), info.best_ask()) else {
+                debug!("Skipping Phoenix market {}: one side of the book is empty", market.market);
+                continue;
+            };
+
+            // Selling base fills at the bid, buying it at the ask. Prices are
+            // output per input, whichever side of the market SOL is on.
+            let sol_is_base = info.base_mint == sol_mint;
+            let (token_to_sol, sol_to_token, sol_depth) = if sol_is_base {
+                let depth = info.depth_base_atoms(&info.bids, TOP_OF_BOOK_LEVELS)
+                    + info.depth_base_atoms(&info.asks, TOP_OF_BOOK_LEVELS);
+                (1.0 / ask, bid, depth)
+            } else {
+                let depth = info.depth_quote_atoms(&info.bids, TOP_OF_BOOK_LEVELS)
+                    + info.depth_quote_atoms(&info.asks, TOP_OF_BOOK_LEVELS);
+                (bid, 1.0 / ask, depth as u64)
+            };
+            let liquidity_usd = Lamports(sol_depth).to_usd(self.sol_price_usd()).value();
+
+            self.add_edge(market.token_mint, sol_mint, PoolEdge {
+                pool_pubkey: market.market,
+                to_mint: sol_mint,
+                dex_type: DexType::Phoenix,
+                program_id: DexType::Phoenix.program_id(),
+                price: token_to_sol,
+                liquidity_usd,
+                fee_bps: info.taker_fee_bps,
+                inverse_fee_bps: info.taker_fee_bps,
+                token_program: pool_data.token_program,
+                transfer_fee_bps: 0,
+                near_graduation: false,
+            });
+
+            self.add_edge(sol_mint, market.token_mint, PoolEdge {
+                pool_pubkey: market.market,
+                to_mint: market.token_mint,
+                dex_type: DexType::Phoenix,
+                program_id: DexType::Phoenix.program_id(),
+                price: sol_to_token,
+                liquidity_usd,
+                fee_bps: info.taker_fee_bps,
+                inverse_fee_bps: info.taker_fee_bps,
+                token_program: pool_data.token_program,
+                transfer_fee_bps: 0,
+                near_graduation: false,
+            });
+        }
+    }
+
+    fn process_pancakeswap_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         // PancakeSwap uses same CLMM as Raydium - duplicate logic
         for pool in &pool_data.pancakeswap_pools {
-            match rpc_client.get_account(&pool.pool) {
+            self.begin_pool();
+            match self.fetch_account(&pool.pool, rpc_client) {
                 Ok(account) => {
                     if account.owner != crate::dex::pancakeswap::pancakeswap_program_id() {
+                        self.record_anomaly(|| format!("PancakeSwap pool owner mismatch, {}", account_dump(&pool.pool, &account)));
                         warn!("PancakeSwap pool owner mismatch: {}", pool.pool);
                         continue;
                     }
@@ -577,44 +1586,63 @@ fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_c
                             if pool.token_mint == pool_state.token_mint_0 {
                                 self.add_edge(pool.token_mint, pool_state.token_mint_1, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool_state.token_mint_1,
                                     dex_type: DexType::PancakeSwap,
+                                    program_id: DexType::PancakeSwap.program_id(),
                                     price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                                 self.add_edge(pool_state.token_mint_1, pool.token_mint, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool.token_mint,
                                     dex_type: DexType::PancakeSwap,
+                                    program_id: DexType::PancakeSwap.program_id(),
                                     price: 1.0 / price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                             } else {
                                 self.add_edge(pool.token_mint, pool_state.token_mint_0, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool_state.token_mint_0,
                                     dex_type: DexType::PancakeSwap,
+                                    program_id: DexType::PancakeSwap.program_id(),
                                     price: 1.0 / price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                                 self.add_edge(pool_state.token_mint_0, pool.token_mint, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool.token_mint,
                                     dex_type: DexType::PancakeSwap,
+                                    program_id: DexType::PancakeSwap.program_id(),
                                     price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                             }
                         }
-                        Err(e) => warn!("Failed to parse PancakeSwap pool {}: {}", pool.pool, e),
+                        Err(e) => {
+                            self.record_anomaly(|| format!("failed to parse PancakeSwap pool: {}, {}", e, account_dump(&pool.pool, &account)));
+                            warn!("Failed to parse PancakeSwap pool {}: {}", pool.pool, e)
+                        }
                     }
                 }
                 Err(e) => warn!("Failed to fetch PancakeSwap pool {}: {}", pool.pool, e),
@@ -622,12 +1650,14 @@ fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_c
         }
     }
 
-    fn process_byreal_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &solana_client::rpc_client::RpcClient) {
+    fn process_byreal_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_client: &dyn RpcLike) {
         // Byreal uses same CLMM as Raydium - duplicate logic
         for pool in &pool_data.byreal_pools {
-            match rpc_client.get_account(&pool.pool) {
+            self.begin_pool();
+            match self.fetch_account(&pool.pool, rpc_client) {
                 Ok(account) => {
                     if account.owner != crate::dex::byreal::byreal_program_id() {
+                        self.record_anomaly(|| format!("Byreal pool owner mismatch, {}", account_dump(&pool.pool, &account)));
                         warn!("Byreal pool owner mismatch: {}", pool.pool);
                         continue;
                     }
@@ -640,44 +1670,63 @@ fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_c
                             if pool.token_mint == pool_state.token_mint_0 {
                                 self.add_edge(pool.token_mint, pool_state.token_mint_1, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool_state.token_mint_1,
                                     dex_type: DexType::Byreal,
+                                    program_id: DexType::Byreal.program_id(),
                                     price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                                 self.add_edge(pool_state.token_mint_1, pool.token_mint, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool.token_mint,
                                     dex_type: DexType::Byreal,
+                                    program_id: DexType::Byreal.program_id(),
                                     price: 1.0 / price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                             } else {
                                 self.add_edge(pool.token_mint, pool_state.token_mint_0, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool_state.token_mint_0,
                                     dex_type: DexType::Byreal,
+                                    program_id: DexType::Byreal.program_id(),
                                     price: 1.0 / price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                                 self.add_edge(pool_state.token_mint_0, pool.token_mint, PoolEdge {
                                     pool_pubkey: pool.pool,
+                                    to_mint: pool.token_mint,
                                     dex_type: DexType::Byreal,
+                                    program_id: DexType::Byreal.program_id(),
                                     price,
                                     liquidity_usd,
                                     fee_bps: 5,
                                     inverse_fee_bps: 5,
                                     token_program: pool_data.token_program,
+                                    transfer_fee_bps: 0,
+                                    near_graduation: false,
                                 });
                             }
                         }
-                        Err(e) => warn!("Failed to parse Byreal pool {}: {}", pool.pool, e),
+                        Err(e) => {
+                            self.record_anomaly(|| format!("failed to parse Byreal pool: {}, {}", e, account_dump(&pool.pool, &account)));
+                            warn!("Failed to parse Byreal pool {}: {}", pool.pool, e)
+                        }
                     }
                 }
                 Err(e) => warn!("Failed to fetch Byreal pool {}: {}", pool.pool, e),
@@ -685,8 +1734,722 @@ fn process_heaven_pools(&self, pool_data: &MintPoolData, sol_mint: Pubkey, rpc_c
         }
     }
 
-    fn add_edge(&self, from_mint: Pubkey, to_mint: Pubkey, edge: PoolEdge) {
+    /// Insert or reprice the edge for `edge.pool_pubkey` from `from_mint` to `to_mint`
+    pub fn add_edge(&self, from_mint: Pubkey, to_mint: Pubkey, mut edge: PoolEdge) {
+        // Both transfers of the swap pay their mint's fee
+        edge.transfer_fee_bps = self.transfer_fee(&from_mint).saturating_add(self.transfer_fee(&to_mint));
         debug!("Adding edge: {} -> {} (price: {}, dex: {:?})", from_mint, to_mint, edge.price, edge.dex_type);
-        self.edges.entry(from_mint).or_insert_with(Vec::new).push(edge);
+        // A zero, negative or non-finite price has no finite -ln weight and
+        // would poison every cycle through it, so it never reaches the graph
+        let valid_price = edge.price.is_finite() && edge.price > 0.0;
+        if !valid_price {
+            self.record_anomaly(|| format!(
+                "{:?} pool {} produced price {} for {} -> {}",
+                edge.dex_type, edge.pool_pubkey, edge.price, from_mint, to_mint
+            ));
+            warn!(
+                "⚠️ Dropping edge for {:?} pool {}: invalid price {} for {} -> {}",
+                edge.dex_type, edge.pool_pubkey, edge.price, from_mint, to_mint
+            );
+        }
+        let mut edges = self.edges.entry(from_mint).or_insert_with(Vec::new);
+
+        let min_liquidity_usd = self.effective_min_liquidity_usd();
+        if !valid_price || edge.liquidity_usd < min_liquidity_usd {
+            if valid_price {
+                debug!(
+                    "Dropping edge for pool {}: liquidity ${:.0} below ${:.0}",
+                    edge.pool_pubkey, edge.liquidity_usd, min_liquidity_usd
+                );
+            }
+            let before = edges.len();
+            edges.retain(|e| e.pool_pubkey != edge.pool_pubkey);
+            let removed = edges.len() != before;
+            drop(edges);
+            if let Some(tracker) = self.update_tracker.lock().unwrap().as_mut() {
+                if valid_price {
+                    tracker.stats.edges_below_liquidity += 1;
+                }
+            }
+            if removed {
+                let generation = self.generation();
+                self.mint_generations.insert(from_mint, generation);
+                self.mint_generations.insert(to_mint, generation);
+                self.pool_generations.insert(edge.pool_pubkey, generation);
+            }
+            return;
+        }
+
+        // Replace the previous edge for this pool instead of accumulating duplicates
+        let pool = edge.pool_pubkey;
+        let changed = match edges.iter_mut().find(|e| e.pool_pubkey == edge.pool_pubkey) {
+            Some(existing) => {
+                let changed = existing.price != edge.price || existing.liquidity_usd != edge.liquidity_usd;
+                *existing = edge;
+                changed
+            }
+            None => {
+                edges.push(edge);
+                true
+            }
+        };
+        drop(edges);
+        if let Some(tracker) = self.update_tracker.lock().unwrap().as_mut() {
+            tracker.stats.edges_added += 1;
+            tracker.pool_edges += 1;
+        }
+        if let Some(slot) = *self.pool_read_slot.lock().unwrap() {
+            self.pool_slots.insert(pool, slot);
+            self.slot_head.fetch_max(slot, Ordering::Relaxed);
+        }
+
+        if changed {
+            let generation = self.generation();
+            self.mint_generations.insert(from_mint, generation);
+            self.mint_generations.insert(to_mint, generation);
+            self.pool_generations.insert(pool, generation);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+    use crate::engine::prefetch::PrefetchPlanner;
+    use crate::rpc::MockRpc;
+
+    fn token_account(mint: Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
     }
-}
\ No newline at end of file
+
+    fn mint_account(decimals: u8) -> Account {
+        let mut data = vec![0u8; 82];
+        data[44] = decimals;
+        data[45] = 1; // initialized
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    fn edge(graph: &PriceGraph, from_mint: &Pubkey, pool: &Pubkey) -> Option<PoolEdge> {
+        graph.neighbors(from_mint).into_iter().map(|(_, edge)| edge).find(|edge| edge.pool_pubkey == *pool)
+    }
+
+    /// A TOKEN/SOL Raydium V4 pool holding 4,000 token base units per lamport
+    struct RaydiumFixture {
+        rpc: MockRpc,
+        pool_data: MintPoolData,
+        token: Pubkey,
+        pool: Pubkey,
+        token_vault: Pubkey,
+    }
+
+    fn raydium_fixture() -> RaydiumFixture {
+        let (token, pool, token_vault, sol_vault) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new().with_slot(42);
+        rpc.set_account(token, mint_account(6));
+        rpc.set_account(sol_mint(), mint_account(9));
+        rpc.set_account(token_vault, token_account(token, 4_000_000_000_000));
+        rpc.set_account(sol_vault, token_account(sol_mint(), 1_000_000_000));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+        RaydiumFixture { rpc, pool_data, token, pool, token_vault }
+    }
+
+    #[test]
+    fn add_edge_rejects_unusable_prices() {
+        let graph = PriceGraph::new();
+        let (token, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        graph.add_edge(token, sol_mint(), PoolEdge::new(pool, sol_mint(), DexType::RaydiumV4, 0.5, 1e6, 25, spl_token::id()));
+        assert!(edge(&graph, &token, &pool).is_some());
+
+        for price in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            graph.add_edge(token, sol_mint(), PoolEdge::new(pool, sol_mint(), DexType::RaydiumV4, price, 1e6, 25, spl_token::id()));
+            // The stale edge goes too rather than outliving its pool's price
+            assert!(edge(&graph, &token, &pool).is_none(), "price {} reached the graph", price);
+        }
+    }
+
+    #[test]
+    fn neighbors_returns_every_outgoing_edge_of_a_mint() {
+        let graph = PriceGraph::new();
+        let (token, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        graph.add_edge(sol_mint(), token, PoolEdge::new(pools[0], token, DexType::RaydiumV4, 4_000.0, 1e6, 25, spl_token::id()));
+        graph.add_edge(sol_mint(), token, PoolEdge::new(pools[1], token, DexType::Whirlpool, 4_010.0, 1e6, 30, spl_token::id()));
+        graph.add_edge(sol_mint(), usdc, PoolEdge::new(pools[2], usdc, DexType::RaydiumCp, 150.0, 1e6, 25, spl_token::id()));
+        // Incoming edges of SOL are not its neighbors
+        graph.add_edge(token, sol_mint(), PoolEdge::new(pools[0], sol_mint(), DexType::RaydiumV4, 0.00025, 1e6, 25, spl_token::id()));
+
+        let mut neighbors: Vec<(Pubkey, Pubkey, DexType)> =
+            graph.neighbors(&sol_mint()).into_iter().map(|(to_mint, edge)| (to_mint, edge.pool_pubkey, edge.dex_type)).collect();
+        neighbors.sort();
+        let mut expected = vec![(token, pools[0], DexType::RaydiumV4), (token, pools[1], DexType::Whirlpool), (usdc, pools[2], DexType::RaydiumCp)];
+        expected.sort();
+        assert_eq!(neighbors, expected);
+        assert_eq!(graph.neighbors(&token).len(), 1);
+        assert!(graph.neighbors(&usdc).is_empty());
+    }
+
+    #[test]
+    fn vault_ratio_edges_store_amount_out_per_amount_in() {
+        let fixture = raydium_fixture();
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc);
+
+        let sell = edge(&graph, &fixture.token, &fixture.pool).expect("TOKEN -> SOL edge");
+        let buy = edge(&graph, &sol_mint(), &fixture.pool).expect("SOL -> TOKEN edge");
+        assert!((sell.spot_price() - 0.00025).abs() < 1e-15);
+        assert!((buy.spot_price() - 4_000.0).abs() < 1e-9);
+        // 1 SOL a side at the default price, both sides counted
+        assert!((sell.liquidity_usd - 2.0 * graph.sol_price_usd()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lifinity_pools_are_priced_both_ways_from_their_vaults_at_the_pool_fee() {
+        let (token, pool, token_vault, sol_vault) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        rpc.set_account(token, mint_account(6));
+        rpc.set_account(sol_mint(), mint_account(9));
+        rpc.set_account(token_vault, token_account(token, 300_000_000_000)); // 300,000 TOKEN
+        rpc.set_account(sol_vault, token_account(sol_mint(), 2_000_000_000_000)); // 2,000 SOL
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_lifinity_pool(pool, token_vault, sol_vault, token, sol_mint(), 20);
+
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        let sell = edge(&graph, &token, &pool).expect("TOKEN -> SOL edge");
+        let buy = edge(&graph, &sol_mint(), &pool).expect("SOL -> TOKEN edge");
+        assert_eq!((sell.dex_type, sell.to_mint), (DexType::Lifinity, sol_mint()));
+        assert_eq!((buy.dex_type, buy.to_mint), (DexType::Lifinity, token));
+        assert!((sell.spot_price() - 2_000_000_000_000.0 / 300_000_000_000.0).abs() < 1e-12);
+        assert!((buy.spot_price() - 0.15).abs() < 1e-12);
+        for edge in [&sell, &buy] {
+            assert_eq!((edge.fee_bps, edge.inverse_fee_bps), (20, 20));
+            assert!((edge.net_rate() - edge.spot_price() * 0.998).abs() < 1e-12);
+        }
+
+        // An empty vault leaves the pool unpriced rather than at a zero or infinite rate
+        rpc.set_account(sol_vault, token_account(sol_mint(), 0));
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        assert!(edge(&graph, &token, &pool).is_none());
+        assert!(edge(&graph, &sol_mint(), &pool).is_none());
+    }
+
+    #[test]
+    fn phoenix_sells_into_the_bid_and_buys_from_the_ask() {
+        use crate::dex::phoenix::test_market_data;
+
+        // Best bid 150 ticks, best ask 152, with deeper levels behind each
+        let bids = [(2, 0, 150, 10), (0, 0, 149, 40)];
+        let asks = [(0, 2, 152, 20), (0, 0, 153, 30)];
+        let (token, market) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let phoenix = |data: Vec<u8>| Account { lamports: 1, data, owner: DexType::Phoenix.program_id(), executable: false, rent_epoch: 0 };
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_phoenix_market(market, Pubkey::new_unique(), Pubkey::new_unique(), token, sol_mint());
+
+        // TOKEN base, SOL quote: prices are lamports per token atom
+        let rpc = MockRpc::new();
+        rpc.set_account(market, phoenix(test_market_data(token, sol_mint(), &bids, &asks)));
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        let sell = edge(&graph, &token, &market).expect("TOKEN -> SOL edge");
+        let buy = edge(&graph, &sol_mint(), &market).expect("SOL -> TOKEN edge");
+        assert!((sell.spot_price() - 0.150).abs() < 1e-12);
+        assert!((buy.spot_price() - 1.0 / 0.152).abs() < 1e-9);
+        assert_eq!((sell.dex_type, sell.fee_bps, buy.fee_bps), (DexType::Phoenix, 5, 5));
+        // Two levels a side: 50,000 atoms at 0.150-0.149 and 50,000 at 0.152-0.153, in lamports
+        let depth = 10_000.0 * 0.150 + 40_000.0 * 0.149 + 20_000.0 * 0.152 + 30_000.0 * 0.153;
+        let expected_usd = depth / 1e9 * graph.sol_price_usd();
+        assert!((sell.liquidity_usd - expected_usd).abs() < 1e-6, "{} vs {}", sell.liquidity_usd, expected_usd);
+
+        // SOL base, TOKEN quote: buying SOL with TOKEN pays the ask, selling SOL takes the bid
+        rpc.set_account(market, phoenix(test_market_data(sol_mint(), token, &bids, &asks)));
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        let sell = edge(&graph, &token, &market).expect("TOKEN -> SOL edge");
+        let buy = edge(&graph, &sol_mint(), &market).expect("SOL -> TOKEN edge");
+        assert!((sell.spot_price() - 1.0 / 0.152).abs() < 1e-9);
+        assert!((buy.spot_price() - 0.150).abs() < 1e-12);
+
+        // A one-sided book is not priced
+        rpc.set_account(market, phoenix(test_market_data(token, sol_mint(), &bids, &[])));
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        assert!(edge(&graph, &token, &market).is_none());
+        assert!(edge(&graph, &sol_mint(), &market).is_none());
+    }
+
+    /// Meteora vault with the fields DAMM v1 pricing reads set; its locked
+    /// profit was reported at `reported_at` and releases over six hours
+    fn meteora_vault_account(total_amount: u64, locked_profit: u64, reported_at: u64) -> Account {
+        let mut data = vec![0u8; 1227];
+        data[8] = 1; // enabled
+        data[11..19].copy_from_slice(&total_amount.to_le_bytes());
+        data[1203..1211].copy_from_slice(&locked_profit.to_le_bytes());
+        data[1211..1219].copy_from_slice(&reported_at.to_le_bytes());
+        data[1219..1227].copy_from_slice(&46_296_296u64.to_le_bytes());
+        Account { lamports: 1, data, owner: Pubkey::new_unique(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn a_damm_pool_is_priced_from_the_unlocked_share_of_each_vault() {
+        use crate::clock::{Clock, MockClock};
+        use std::time::Duration;
+
+        const NOW: u64 = 1_700_000_000;
+        let token = Pubkey::new_unique();
+        let [pool, token_vault, sol_vault, token_lp_mint, sol_lp_mint, token_pool_lp, sol_pool_lp] =
+            std::array::from_fn(|_| Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        let mut pool_account = vec![0u8; 944];
+        pool_account[233] = 1; // enabled
+        pool_account[330..338].copy_from_slice(&25u64.to_le_bytes());
+        pool_account[338..346].copy_from_slice(&10_000u64.to_le_bytes());
+        rpc.set_account(pool, Account { lamports: 1, data: pool_account, owner: DexType::MeteoraDamm.program_id(), executable: false, rent_epoch: 0 });
+        // 8,000 TOKEN fully unlocked; 2 SOL of which 0.4 SOL is profit reported just now
+        rpc.set_account(token_vault, meteora_vault_account(8_000_000_000, 0, NOW));
+        rpc.set_account(sol_vault, meteora_vault_account(2_000_000_000, 400_000_000, NOW));
+        // The pool holds half of each vault's LP supply
+        for (lp_mint, pool_lp) in [(token_lp_mint, token_pool_lp), (sol_lp_mint, sol_pool_lp)] {
+            let mut mint = mint_account(6);
+            mint.data[36..44].copy_from_slice(&1_000u64.to_le_bytes());
+            rpc.set_account(lp_mint, mint);
+            rpc.set_account(pool_lp, token_account(lp_mint, 500));
+        }
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        let unused = Pubkey::new_unique();
+        pool_data.add_meteora_damm_pool(
+            pool, token_vault, sol_vault, unused, unused, token_lp_mint, sol_lp_mint, token_pool_lp, sol_pool_lp, unused, unused, token, sol_mint(),
+        );
+
+        let clock = Arc::new(MockClock::new(NOW));
+        let graph = PriceGraph::new().with_clock(clock.clone());
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        // 0.8 SOL withdrawable for 4,000 TOKEN, not the 1 SOL the vault total suggests
+        let sell = edge(&graph, &token, &pool).expect("TOKEN -> SOL edge");
+        assert!((sell.spot_price() - 0.2).abs() < 1e-12, "{}", sell.spot_price());
+        assert_eq!(sell.fee_bps, 25);
+
+        // Once the profit is released the full total backs the shares. At
+        // 46,296,296 per 10^12 a second that takes just over six hours.
+        clock.advance(Duration::from_secs(21_601));
+        assert_eq!(clock.now_unix(), NOW + 21_601);
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        let sell = edge(&graph, &token, &pool).expect("TOKEN -> SOL edge");
+        let buy = edge(&graph, &sol_mint(), &pool).expect("SOL -> TOKEN edge");
+        assert!((sell.spot_price() - 0.25).abs() < 1e-12, "{}", sell.spot_price());
+        assert!((buy.spot_price() - 4.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn failing_oracle_still_builds_the_graph_at_the_bootstrap_price() {
+        use crate::engine::oracle::{SolPriceOracle, SolPriceSource};
+
+        // Nothing listens on port 1, so every fetch fails
+        let oracle = SolPriceOracle::new(vec![SolPriceSource::CoinGecko { url: "http://127.0.0.1:1/price".to_string() }], 100.0);
+        let graph = PriceGraph::with_bootstrap_sol_price(100.0);
+        graph.set_liquidity_filter(100.0, 2.0);
+        assert_eq!(graph.effective_min_liquidity_usd(), 200.0);
+
+        let sol_price = oracle.refresh().await;
+        assert!(!oracle.is_confirmed());
+        assert_eq!(sol_price, 100.0);
+        graph.set_sol_price(sol_price, oracle.is_confirmed());
+
+        let fixture = raydium_fixture();
+        graph.update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc);
+        let sell = edge(&graph, &fixture.token, &fixture.pool).expect("edge priced while the oracle is down");
+        assert!((sell.liquidity_usd - 200.0).abs() < 1e-9);
+        // Still unconfirmed, so the doubled filter stays in force
+        assert_eq!(graph.effective_min_liquidity_usd(), 200.0);
+        graph.set_sol_price(100.0, true);
+        assert_eq!(graph.effective_min_liquidity_usd(), 100.0);
+    }
+
+    #[test]
+    fn token_balance_rpc_prices_like_the_decoded_vaults() {
+        let fixture = raydium_fixture();
+        let (decoded, rpc_balances) = (PriceGraph::new(), PriceGraph::new());
+        rpc_balances.set_rpc_token_balances(true);
+        decoded.update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc);
+        rpc_balances.update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc);
+        let price = |graph: &PriceGraph| edge(graph, &fixture.token, &fixture.pool).unwrap().spot_price();
+        assert_eq!(price(&decoded), price(&rpc_balances));
+    }
+
+    #[test]
+    fn a_usdc_vault_is_valued_in_whole_tokens() {
+        let usdc = crate::constants::usdc_mint();
+        let (pool, usdc_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        rpc.set_account(usdc, mint_account(6));
+        rpc.set_account(sol_mint(), mint_account(9));
+        rpc.set_account(usdc_vault, token_account(usdc, 150_000_000_000)); // 150,000 USDC
+        rpc.set_account(sol_vault, token_account(sol_mint(), 1_000_000_000_000)); // 1,000 SOL
+        let mut pool_data = MintPoolData::new(usdc, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_pool(pool, usdc_vault, sol_vault, usdc, sol_mint());
+
+        for rpc_token_balances in [false, true] {
+            let graph = PriceGraph::new();
+            graph.set_sol_price(150.0, true);
+            graph.set_rpc_token_balances(rpc_token_balances);
+            let balance = graph.get_token_balance(&usdc_vault, &rpc).unwrap();
+            assert_eq!(balance.decimals, 6);
+            assert_eq!(balance.ui_amount(), 150_000.0);
+
+            graph.update_from_mint_pool_data(&pool_data, &rpc);
+            // 1,000 SOL at $150 a side, both sides counted: not 1000x off either way
+            let sell = edge(&graph, &usdc, &pool).expect("USDC -> SOL edge");
+            assert!((sell.liquidity_usd - 300_000.0).abs() < 1e-6, "{}", sell.liquidity_usd);
+        }
+
+        // A USDC base reserve goes through the USDC price with its own 6 decimals
+        let graph = PriceGraph::new();
+        graph.set_quote_price(usdc, 1.0);
+        let liquidity = graph.base_pair_liquidity_usd(&usdc, sol_mint(), 1_000_000_000, &rpc);
+        assert!((liquidity - 2_000.0).abs() < 1e-9, "{}", liquidity);
+    }
+
+    #[test]
+    fn a_depegged_quote_mint_lowers_the_pool_value() {
+        let (usdc, usdt) = (crate::constants::usdc_mint(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        rpc.set_account(usdc, mint_account(6));
+        rpc.set_account(usdt, mint_account(6));
+        let graph = PriceGraph::new();
+
+        // 1,000 of the quote mint, both sides counted
+        assert!((graph.base_pair_liquidity_usd(&usdc, sol_mint(), 1_000_000_000, &rpc) - 2_000.0).abs() < 1e-9);
+        graph.set_quote_price(usdc, 0.95);
+        let liquidity = graph.base_pair_liquidity_usd(&usdc, sol_mint(), 1_000_000_000, &rpc);
+        assert!((liquidity - 1_900.0).abs() < 1e-9, "{}", liquidity);
+
+        // A quote mint is only valued once it has a price
+        assert_eq!(graph.base_pair_liquidity_usd(&usdt, sol_mint(), 1_000_000_000, &rpc), 0.0);
+        graph.set_quote_price(usdt, 0.95);
+        assert!((graph.base_pair_liquidity_usd(&usdt, sol_mint(), 1_000_000_000, &rpc) - 1_900.0).abs() < 1e-9);
+    }
+
+    /// Raydium CLMM pool state with the fields the graph reads set
+    fn clmm_pool_account(mint_0: Pubkey, mint_1: Pubkey, liquidity: u128, sqrt_price_x64: u128) -> Account {
+        let mut data = vec![0u8; 1544];
+        data[73..105].copy_from_slice(mint_0.as_ref());
+        data[105..137].copy_from_slice(mint_1.as_ref());
+        data[235..237].copy_from_slice(&1u16.to_le_bytes()); // tick_spacing
+        data[237..253].copy_from_slice(&liquidity.to_le_bytes());
+        data[253..269].copy_from_slice(&sqrt_price_x64.to_le_bytes());
+        Account { lamports: 1, data, owner: DexType::RaydiumClmm.program_id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn a_clmm_pool_without_sol_gets_edges_between_its_own_mints() {
+        let (usdc, bonk, pool) = (crate::constants::usdc_mint(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        // sqrt price 2.0: 4 BONK base units per USDC base unit
+        rpc.set_account(pool, clmm_pool_account(usdc, bonk, 1_000_000, 2u128 << 64));
+        let mut pool_data = MintPoolData::new(bonk, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_clmm_pool(
+            pool,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            vec![],
+            None,
+            bonk,
+            usdc,
+        );
+
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+
+        let buy = edge(&graph, &usdc, &pool).expect("USDC -> BONK edge");
+        let sell = edge(&graph, &bonk, &pool).expect("BONK -> USDC edge");
+        assert_eq!((buy.to_mint, sell.to_mint), (bonk, usdc));
+        assert!((buy.spot_price() - 4.0).abs() < 1e-12);
+        assert!((sell.spot_price() - 0.25).abs() < 1e-12);
+        assert!(edge(&graph, &sol_mint(), &pool).is_none());
+        assert!(graph.neighbors(&bonk).iter().all(|(to_mint, _)| *to_mint != sol_mint()));
+    }
+
+    #[test]
+    fn a_clmm_pool_without_active_liquidity_gets_no_edge() {
+        let (token, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        rpc.set_account(pool, clmm_pool_account(sol_mint(), token, 0, 2u128 << 64));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_clmm_pool(
+            pool,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            vec![],
+            None,
+            token,
+            sol_mint(),
+        );
+
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        assert!(edge(&graph, &sol_mint(), &pool).is_none());
+        assert!(edge(&graph, &token, &pool).is_none());
+
+        // The same pool once it has depth at its tick is priced
+        rpc.set_account(pool, clmm_pool_account(sol_mint(), token, 1_000_000, 2u128 << 64));
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+        assert!(edge(&graph, &sol_mint(), &pool).is_some());
+    }
+
+    #[test]
+    fn registered_mint_decimals_are_served_without_an_rpc_call() {
+        let (bonk, unknown) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let path = std::env::temp_dir().join(format!("{}_mints.json", Pubkey::new_unique()));
+        std::fs::write(&path, format!(r#"{{ "{}": {{ "symbol": "BONK", "decimals": 5 }} }}"#, bonk)).unwrap();
+        let registry = MintRegistry::from_file(path.to_str().unwrap()).unwrap();
+        let rpc = MockRpc::new();
+        rpc.set_account(unknown, mint_account(9));
+
+        let graph = PriceGraph::new();
+        graph.set_mint_registry(&registry);
+
+        assert_eq!(graph.get_mint_decimals(&bonk, &rpc).unwrap(), 5);
+        assert_eq!(rpc.calls(), 0);
+        // An unregistered mint still falls back to its account
+        assert_eq!(graph.get_mint_decimals(&unknown, &rpc).unwrap(), 9);
+        assert_eq!(rpc.calls(), 1);
+    }
+
+    #[test]
+    fn prefetched_refresh_parses_pools_from_one_batch() {
+        let fixture = raydium_fixture();
+        let mut planner = PrefetchPlanner::new(false);
+        planner.add_mint(&fixture.pool_data);
+        let prefetched = planner.fetch(&fixture.rpc).unwrap();
+        assert_eq!(prefetched.rpc_calls, 1);
+        assert!(prefetched.missing.is_empty());
+
+        let graph = PriceGraph::new();
+        graph.set_prefetched(prefetched);
+        let calls = fixture.rpc.calls();
+        let stats = graph.try_update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc).unwrap();
+        assert_eq!(stats.pools_priced, 1);
+        assert!(edge(&graph, &fixture.token, &fixture.pool).is_some());
+        assert!(!graph.is_lagging(&fixture.pool));
+        // Only the mints, which pools don't declare, were read individually
+        assert_eq!(fixture.rpc.calls() - calls, graph.take_account_reads().individual as usize);
+    }
+
+    #[test]
+    fn missing_vault_skips_the_pool_without_another_read() {
+        let fixture = raydium_fixture();
+        fixture.rpc.remove_account(&fixture.token_vault);
+        let mut planner = PrefetchPlanner::new(false);
+        planner.add_mint(&fixture.pool_data);
+        let prefetched = planner.fetch(&fixture.rpc).unwrap();
+        assert_eq!(prefetched.missing, vec![fixture.token_vault]);
+
+        let graph = PriceGraph::new();
+        graph.set_prefetched(prefetched);
+        assert!(graph.try_update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc).is_err());
+        assert!(edge(&graph, &fixture.token, &fixture.pool).is_none());
+    }
+
+    #[test]
+    fn update_stats_count_priced_failed_and_skipped_pools() {
+        let mut fixture = raydium_fixture();
+        let (token, sol) = (fixture.token, sol_mint());
+        // Its token vault is gone
+        let (failing, missing_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        fixture.rpc.set_account(sol_vault, token_account(sol, 1_000_000_000));
+        fixture.pool_data.add_raydium_pool(failing, missing_vault, sol_vault, token, sol);
+        // Priced, but a few lamports deep
+        let (shallow, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        fixture.rpc.set_account(token_vault, token_account(token, 4_000));
+        fixture.rpc.set_account(sol_vault, token_account(sol, 1));
+        fixture.pool_data.add_raydium_pool(shallow, token_vault, sol_vault, token, sol);
+
+        let graph = PriceGraph::new();
+        graph.set_liquidity_filter(1.0, 1.0);
+        let stats = graph.try_update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc).unwrap();
+        assert_eq!(
+            stats,
+            UpdateStats { edges_added: 2, pools_priced: 1, pools_failed: 1, pools_skipped: 1, edges_below_liquidity: 2 }
+        );
+        assert_eq!(stats.pools(), 3);
+        assert!(!stats.all_failed());
+        assert!(edge(&graph, &token, &fixture.pool).is_some());
+        assert!(edge(&graph, &token, &failing).is_none());
+        assert!(edge(&graph, &token, &shallow).is_none());
+
+        // With the node down every pool fails, and the update says so
+        fixture.rpc.set_unavailable(true);
+        let error = graph.try_update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc).unwrap_err();
+        assert!(error.to_string().contains("all 3 pools"), "{}", error);
+    }
+
+    /// Meteora DLMM pair with its mints, bin step and active bin set
+    fn dlmm_pair_account(token_x: Pubkey, token_y: Pubkey, bin_step: u16, active_id: i32) -> Account {
+        use crate::dex::meteora::dlmm_info::LbPair;
+        use std::mem::{offset_of, size_of};
+        let mut data = vec![0u8; 8 + size_of::<LbPair>()];
+        let mut set = |offset: usize, bytes: &[u8]| data[8 + offset..8 + offset + bytes.len()].copy_from_slice(bytes);
+        set(offset_of!(LbPair, token_x_mint), token_x.as_ref());
+        set(offset_of!(LbPair, token_y_mint), token_y.as_ref());
+        set(offset_of!(LbPair, bin_step), &bin_step.to_le_bytes());
+        set(offset_of!(LbPair, active_id), &active_id.to_le_bytes());
+        Account { lamports: 1, data, owner: crate::dex::meteora::constants::dlmm_program_id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn a_dlmm_pair_with_an_extreme_active_id_gets_no_edge() {
+        let token = Pubkey::new_unique();
+        let rpc = MockRpc::new();
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        let mut pair_at = |active_id: i32| {
+            let pair = Pubkey::new_unique();
+            rpc.set_account(pair, dlmm_pair_account(token, sol_mint(), 100, active_id));
+            let (token_vault, sol_vault, oracle) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            pool_data.add_dlmm_pool(pair, token_vault, sol_vault, oracle, vec![], None, token, sol_mint());
+            pair
+        };
+        let sane = pair_at(100);
+        // 1.01^400000 overflows f64 and 1.01^-500000 underflows it
+        let overflowing = pair_at(400_000);
+        let underflowing = pair_at(-500_000);
+        let extreme = pair_at(i32::MIN);
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+
+        let priced = edge(&graph, &token, &sane).expect("an in-range bin is priced");
+        assert!((priced.spot_price() - 1.01f64.powi(100)).abs() < 1e-9, "{}", priced.spot_price());
+        for pair in [overflowing, underflowing, extreme] {
+            assert!(edge(&graph, &token, &pair).is_none());
+            assert!(edge(&graph, &sol_mint(), &pair).is_none());
+        }
+        assert!(graph.neighbors(&token).iter().chain(&graph.neighbors(&sol_mint())).all(|(_, edge)| edge.weight().is_finite()));
+    }
+
+    /// The Raydium fixture plus a DLMM pair whose account is too short to parse
+    fn corrupt_dlmm_fixture() -> (RaydiumFixture, Pubkey) {
+        let mut fixture = raydium_fixture();
+        let pair = Pubkey::new_unique();
+        let owner = crate::dex::meteora::constants::dlmm_program_id();
+        fixture.rpc.set_account(pair, Account { lamports: 1, data: vec![7u8; 100], owner, executable: false, rent_epoch: 0 });
+        fixture.pool_data.add_dlmm_pool(
+            pair,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            vec![],
+            None,
+            fixture.token,
+            sol_mint(),
+        );
+        (fixture, pair)
+    }
+
+    #[test]
+    fn strict_mode_reports_a_corrupt_account_with_its_dump() {
+        let (fixture, pair) = corrupt_dlmm_fixture();
+        let graph = PriceGraph::new();
+        graph.set_strict_mode(true);
+        graph.update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc);
+
+        let anomaly = graph.take_anomaly().expect("strict mode records the corrupt pair");
+        assert!(anomaly.contains("failed to parse DLMM pool"), "{}", anomaly);
+        assert!(anomaly.contains(&format!("account {} owner {}", pair, crate::dex::meteora::constants::dlmm_program_id())), "{}", anomaly);
+        assert!(anomaly.contains("data_len 100"), "{}", anomaly);
+        assert!(anomaly.contains(&format!("data_base64 {}", base64::Engine::encode(&base64::engine::general_purpose::STANDARD, [7u8; 100]))));
+        assert!(graph.take_anomaly().is_none(), "taking the anomaly clears it");
+    }
+
+    #[test]
+    fn outside_strict_mode_a_corrupt_account_is_skipped() {
+        let (fixture, pair) = corrupt_dlmm_fixture();
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc);
+
+        assert!(graph.take_anomaly().is_none());
+        assert!(edge(&graph, &fixture.token, &pair).is_none());
+        // The healthy pool of the same mint is still priced
+        assert!(edge(&graph, &fixture.token, &fixture.pool).is_some());
+    }
+
+    #[test]
+    fn strict_mode_reports_an_unusable_price() {
+        let graph = PriceGraph::new();
+        graph.set_strict_mode(true);
+        let (token, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        graph.add_edge(token, sol_mint(), PoolEdge::new(pool, sol_mint(), DexType::RaydiumV4, f64::NAN, 1e6, 25, spl_token::id()));
+        let anomaly = graph.take_anomaly().unwrap();
+        assert!(anomaly.contains(&pool.to_string()) && anomaly.contains("NaN"), "{}", anomaly);
+    }
+
+    #[test]
+    fn an_edge_lagging_the_freshest_slot_is_excluded_from_detection() {
+        use crate::engine::detect::CycleDetector;
+        let token = Pubkey::new_unique();
+        // The token is 5% cheaper in the pool read at the older slot
+        let price_at = |slot: u64, token_reserve: u64| {
+            let rpc = MockRpc::new().with_slot(slot);
+            let (pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            rpc.set_account(token, mint_account(6));
+            rpc.set_account(sol_mint(), mint_account(9));
+            rpc.set_account(token_vault, token_account(token, token_reserve));
+            rpc.set_account(sol_vault, token_account(sol_mint(), 100_000_000_000));
+            let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+            pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+            (rpc, pool_data, pool)
+        };
+        let (fresh_rpc, fresh_data, fresh_pool) = price_at(1_000, 1_000_000_000_000);
+        let (stale_rpc, stale_data, stale_pool) = price_at(900, 1_050_000_000_000);
+        let graph = PriceGraph::new();
+        graph.update_from_mint_pool_data(&fresh_data, &fresh_rpc);
+        graph.update_from_mint_pool_data(&stale_data, &stale_rpc);
+
+        let detect = |graph: &PriceGraph| CycleDetector::find_negative_cycles(graph, sol_mint(), 2, 2, ProfitThreshold::flat(10));
+        let cycles = detect(&graph);
+        assert_eq!(cycles.len(), 1);
+        assert!(cycles[0].legs.iter().any(|leg| leg.pool_pubkey == stale_pool));
+
+        // 100 slots behind the head is within a lag of 100, not of 50
+        graph.set_max_slot_lag(100);
+        assert!(!graph.is_lagging(&stale_pool));
+        assert_eq!(detect(&graph).len(), 1);
+        graph.set_max_slot_lag(50);
+        assert!(graph.is_lagging(&stale_pool));
+        assert!(!graph.is_lagging(&fresh_pool));
+        assert!(detect(&graph).is_empty());
+        let pools: Vec<Pubkey> = graph.detectable_edges().into_iter().flat_map(|(_, edges)| edges).map(|edge| edge.pool_pubkey).collect();
+        assert!(pools.contains(&fresh_pool) && !pools.contains(&stale_pool), "{:?}", pools);
+        // The edge is only hidden from detection, still priced in the graph
+        assert!(edge(&graph, &token, &stale_pool).is_some());
+    }
+
+    #[test]
+    fn a_clmm_or_whirlpool_account_that_cannot_be_fetched_leaves_the_other_pools_priced() {
+        let mut fixture = raydium_fixture();
+        let (token, sol) = (fixture.token, sol_mint());
+        // Neither pool account is on the node
+        let (whirlpool, clmm) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let unique = Pubkey::new_unique;
+        fixture.pool_data.add_whirlpool_pool(whirlpool, unique(), unique(), unique(), vec![unique(); 3], None, token, sol);
+        fixture.pool_data.add_raydium_clmm_pool(clmm, unique(), unique(), unique(), unique(), vec![unique(); 3], None, token, sol);
+
+        let graph = PriceGraph::new();
+        let stats = graph.try_update_from_mint_pool_data(&fixture.pool_data, &fixture.rpc).unwrap();
+        assert_eq!((stats.pools_priced, stats.pools_failed), (1, 2));
+        assert!(edge(&graph, &token, &fixture.pool).is_some());
+        assert!(edge(&graph, &sol, &fixture.pool).is_some());
+        for pool in [whirlpool, clmm] {
+            assert!(edge(&graph, &token, &pool).is_none());
+            assert!(edge(&graph, &sol, &pool).is_none());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::engine::graph::PriceGraph;
+use crate::engine::types::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Directed edge identity: a pool quoted from one mint into another
+pub type EdgeKey = (Pubkey, Pubkey, Pubkey); // (pool, from_mint, to_mint)
+
+/// Copy of the graph's edges at one update generation
+#[derive(Debug, Clone, Default)]
+pub struct GraphSnapshot {
+    pub generation: u64,
+    pub edges: BTreeMap<EdgeKey, PoolEdge>,
+}
+
+impl GraphSnapshot {
+    pub fn pools(&self) -> BTreeSet<Pubkey> {
+        self.edges.keys().map(|(pool, _, _)| *pool).collect()
+    }
+}
+
+/// An edge quoted in both snapshots whose spot price moved
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeMove {
+    pub edge: EdgeKey,
+    pub previous_price: f64,
+    pub price: f64,
+    pub move_bps: f64, // Relative to the previous price, signed
+}
+
+/// What changed between two snapshots
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiff {
+    pub from_generation: u64,
+    pub to_generation: u64,
+    pub added_pools: Vec<Pubkey>,
+    pub removed_pools: Vec<Pubkey>,
+    pub added_edges: Vec<EdgeKey>,
+    pub removed_edges: Vec<EdgeKey>,
+    pub moved_edges: Vec<EdgeMove>, // Largest move first
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_edges.is_empty() && self.removed_edges.is_empty() && self.moved_edges.is_empty()
+    }
+
+    /// Compare `previous` to `current`. Added and removed lists are ordered by
+    /// key; an edge whose previous price was not positive counts as moved
+    /// whenever its price changed.
+    pub fn between(previous: &GraphSnapshot, current: &GraphSnapshot, min_move_bps: f64) -> Self {
+        let (previous_pools, current_pools) = (previous.pools(), current.pools());
+        let mut diff = GraphDiff {
+            from_generation: previous.generation,
+            to_generation: current.generation,
+            added_pools: current_pools.difference(&previous_pools).copied().collect(),
+            removed_pools: previous_pools.difference(&current_pools).copied().collect(),
+            ..Default::default()
+        };
+
+        for (key, edge) in &current.edges {
+            let previous_edge = match previous.edges.get(key) {
+                Some(previous_edge) => previous_edge,
+                None => {
+                    diff.added_edges.push(*key);
+                    continue;
+                }
+            };
+            let (previous_price, price) = (previous_edge.spot_price(), edge.spot_price());
+            let move_bps = if previous_price > 0.0 && previous_price.is_finite() {
+                (price - previous_price) / previous_price * 10_000.0
+            } else if price == previous_price {
+                0.0
+            } else {
+                f64::INFINITY
+            };
+            if move_bps.abs() >= min_move_bps && price != previous_price {
+                diff.moved_edges.push(EdgeMove { edge: *key, previous_price, price, move_bps });
+            }
+        }
+        diff.removed_edges = previous.edges.keys().filter(|key| !current.edges.contains_key(*key)).copied().collect();
+        diff.moved_edges.sort_by(|a, b| b.move_bps.abs().total_cmp(&a.move_bps.abs()));
+        diff
+    }
+}
+
+impl PriceGraph {
+    /// Copy of the current edges, tagged with the current generation
+    pub fn snapshot(&self) -> GraphSnapshot {
+        let mut edges = BTreeMap::new();
+        for entry in self.edges.iter() {
+            for edge in entry.value() {
+                edges.insert((edge.pool_pubkey, *entry.key(), edge.to_mint), edge.clone());
+            }
+        }
+        GraphSnapshot { generation: self.generation(), edges }
+    }
+
+    /// Pools and edges added or removed since `previous`, and edges whose
+    /// spot price moved by at least `min_move_bps`
+    pub fn diff(&self, previous: &GraphSnapshot, min_move_bps: f64) -> GraphDiff {
+        GraphDiff::between(previous, &self.snapshot(), min_move_bps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+
+    fn quote(graph: &PriceGraph, token: Pubkey, pool: Pubkey, price: f64) {
+        graph.add_edge(token, sol_mint(), PoolEdge::new(pool, sol_mint(), DexType::RaydiumV4, price, 1e6, 25, spl_token::id()));
+    }
+
+    #[test]
+    fn diff_captures_an_added_pool_and_a_price_move() {
+        let graph = PriceGraph::new();
+        let token = Pubkey::new_unique();
+        let (moved, steady, added, removed) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        graph.begin_generation();
+        quote(&graph, token, moved, 0.001);
+        quote(&graph, token, steady, 0.002);
+        quote(&graph, token, removed, 0.003);
+        let previous = graph.snapshot();
+
+        graph.begin_generation();
+        quote(&graph, token, moved, 0.00102); // +200 bps
+        quote(&graph, token, steady, 0.0020002); // +1 bp, under the threshold
+        quote(&graph, token, added, 0.004);
+        quote(&graph, token, removed, 0.0); // An unusable price drops the edge
+        let diff = graph.diff(&previous, 50.0);
+
+        assert_eq!((diff.from_generation, diff.to_generation), (previous.generation, graph.generation()));
+        assert_eq!(diff.added_pools, vec![added]);
+        assert_eq!(diff.added_edges, vec![(added, token, sol_mint())]);
+        assert_eq!(diff.removed_pools, vec![removed]);
+        assert_eq!(diff.removed_edges, vec![(removed, token, sol_mint())]);
+        assert_eq!(diff.moved_edges.len(), 1);
+        let moved_edge = &diff.moved_edges[0];
+        assert_eq!(moved_edge.edge, (moved, token, sol_mint()));
+        assert!((moved_edge.move_bps - 200.0).abs() < 1e-6, "{}", moved_edge.move_bps);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn an_unchanged_graph_diffs_empty() {
+        let graph = PriceGraph::new();
+        quote(&graph, Pubkey::new_unique(), Pubkey::new_unique(), 0.001);
+        let previous = graph.snapshot();
+
+        let diff = graph.diff(&previous, 0.0);
+
+        assert!(diff.is_empty());
+        assert!(diff.added_pools.is_empty() && diff.removed_pools.is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Held for as long as one execution is in flight; dropping it frees the slot
+pub struct ExecutionPermit {
+    _permit: Option<OwnedSemaphorePermit>, // None when executions are not capped
+}
+
+/// Caps the number of executions in flight at once. Callers offer cycles in
+/// order of expected value, so when the cap is reached it is the least
+/// valuable cycles that are dropped (`try_acquire`) or queued (`acquire`).
+#[derive(Clone)]
+pub struct ExecutionLimiter {
+    permits: Option<Arc<Semaphore>>,
+    max_concurrent: usize,
+}
+
+impl ExecutionLimiter {
+    /// `max_concurrent` of 0 means no cap
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+            max_concurrent,
+        }
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Executions currently holding a permit
+    pub fn in_flight(&self) -> usize {
+        self.permits
+            .as_ref()
+            .map_or(0, |permits| self.max_concurrent - permits.available_permits())
+    }
+
+    /// A permit if a slot is free right now, None when the cap is reached
+    pub fn try_acquire(&self) -> Option<ExecutionPermit> {
+        match &self.permits {
+            Some(permits) => permits
+                .clone()
+                .try_acquire_owned()
+                .ok()
+                .map(|permit| ExecutionPermit { _permit: Some(permit) }),
+            None => Some(ExecutionPermit { _permit: None }),
+        }
+    }
+
+    /// Wait for a free slot
+    pub async fn acquire(&self) -> ExecutionPermit {
+        match &self.permits {
+            // The semaphore is never closed, so acquiring cannot fail
+            Some(permits) => ExecutionPermit {
+                _permit: Some(permits.clone().acquire_owned().await.expect("execution semaphore closed")),
+            },
+            None => ExecutionPermit { _permit: None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn with_a_cap_of_one_a_second_execution_waits_for_the_first() {
+        let limiter = ExecutionLimiter::new(1);
+        let first = limiter.acquire().await;
+        assert_eq!(limiter.in_flight(), 1);
+        assert!(limiter.try_acquire().is_none(), "the cap is reached");
+
+        let (acquired, mut second_acquired) = tokio::sync::oneshot::channel();
+        let queued = limiter.clone();
+        let second = tokio::spawn(async move {
+            let _permit = queued.acquire().await;
+            let _ = acquired.send(());
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(second_acquired.try_recv().is_err(), "the second execution went out while the first was in flight");
+
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(5), second).await.unwrap().unwrap();
+        assert!(second_acquired.try_recv().is_ok());
+        assert_eq!(limiter.in_flight(), 0);
+    }
+
+    #[test]
+    fn without_a_cap_every_execution_gets_a_permit() {
+        let limiter = ExecutionLimiter::new(0);
+        let permits: Vec<ExecutionPermit> = (0..100).filter_map(|_| limiter.try_acquire()).collect();
+        assert_eq!(permits.len(), 100);
+        assert_eq!((limiter.in_flight(), limiter.max_concurrent()), (0, 0));
+    }
+
+    #[test]
+    fn a_dropped_permit_frees_its_slot() {
+        let limiter = ExecutionLimiter::new(2);
+        let first = limiter.try_acquire().unwrap();
+        let _second = limiter.try_acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+        drop(first);
+        assert_eq!(limiter.in_flight(), 1);
+        assert!(limiter.try_acquire().is_some());
+    }
+}
@@ -1,11 +1,58 @@
 pub mod types;
 pub mod graph;
+pub mod graph_diff;
+pub mod dot;
 pub mod detect;
 pub mod optimize;
 pub mod simulate;
+pub mod capability;
+pub mod opportunity;
+pub mod reconcile;
+pub mod oracle;
+pub mod compute_profile;
+pub mod spread;
+pub mod prefetch;
+pub mod schedule;
+pub mod forecast;
+pub mod cooldown;
+pub mod settle;
+pub mod paper;
+pub mod reliability;
+pub mod diagnose;
+pub mod tip;
+pub mod split;
+pub mod inflight;
+pub mod tx_cost;
+pub mod session;
+pub mod stagger;
+pub mod stream;
+pub mod status;
 
 pub use types::*;
 pub use graph::*;
+pub use graph_diff::*;
 pub use detect::*;
 pub use optimize::*;
-pub use simulate::*;
\ No newline at end of file
+pub use simulate::*;
+pub use capability::*;
+pub use opportunity::*;
+pub use reconcile::*;
+pub use oracle::*;
+pub use compute_profile::*;
+pub use spread::*;
+pub use prefetch::*;
+pub use schedule::*;
+pub use forecast::*;
+pub use cooldown::*;
+pub use settle::*;
+pub use paper::*;
+pub use reliability::*;
+pub use diagnose::*;
+pub use tip::*;
+pub use split::*;
+pub use inflight::*;
+pub use tx_cost::*;
+pub use session::*;
+pub use stagger::*;
+pub use stream::*;
+pub use status::*;
\ No newline at end of file
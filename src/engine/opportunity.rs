@@ -0,0 +1,285 @@
+use crate::clock::{system_clock, SharedClock};
+use crate::engine::types::*;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Lifecycle events for an opportunity, emitted once per change instead of
+/// re-reporting the same cycle every iteration.
+#[derive(Debug, Clone)]
+pub enum OpportunityEvent {
+    Opened {
+        cycle_id: u64,
+        epoch: u64,
+        profit_bps: i64,
+        hops: usize,
+        observational: bool,
+    },
+    Updated {
+        cycle_id: u64,
+        epoch: u64,
+        profit_bps: i64,
+        profit_delta_bps: i64,
+    },
+    Closed {
+        cycle_id: u64,
+        epoch: u64,
+        last_profit_bps: i64,
+        iterations: u64,
+        open_for: Duration,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct OpportunityRecord {
+    pub cycle_id: u64,
+    pub epoch: u64,
+    pub opened_at: Instant,
+    pub last_profit_bps: i64,
+    pub iterations: u64,
+    pub observational: bool,
+    pub gross_profit_lamports: u64,       // As of the latest sizing
+    pub estimated_tx_cost_lamports: u64,
+    pub near_miss_shortfall_bps: Option<i64>, // Set when the latest sizing narrowly missed the profit gate
+    pub attempts: u64,                     // Paper fills or live transactions sent during this epoch
+}
+
+impl OpportunityRecord {
+    pub fn net_profit_lamports(&self) -> i64 {
+        self.gross_profit_lamports as i64 - self.estimated_tx_cost_lamports as i64
+    }
+}
+
+/// Groups consecutive iterations where the same `cycle_id` stays above
+/// threshold into one opportunity epoch.
+pub struct OpportunityTracker {
+    open: HashMap<u64, OpportunityRecord>,
+    next_epoch: u64,
+    clock: SharedClock,
+}
+
+impl OpportunityTracker {
+    pub fn new() -> Self {
+        Self::with_clock(system_clock())
+    }
+
+    pub fn with_clock(clock: SharedClock) -> Self {
+        Self {
+            open: HashMap::new(),
+            next_epoch: 1,
+            clock,
+        }
+    }
+
+    /// Feed the cycles detected this iteration (already above threshold) and
+    /// get back the opened/updated/closed events.
+    pub fn update(&mut self, cycles: &[ArbitrageCycle]) -> Vec<OpportunityEvent> {
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+
+        for cycle in cycles {
+            let cycle_id = cycle.cycle_id();
+            if !seen.insert(cycle_id) {
+                continue;
+            }
+
+            match self.open.get_mut(&cycle_id) {
+                Some(record) => {
+                    record.iterations += 1;
+                    let profit_delta_bps = cycle.total_profit_bps - record.last_profit_bps;
+                    if profit_delta_bps != 0 {
+                        record.last_profit_bps = cycle.total_profit_bps;
+                        events.push(OpportunityEvent::Updated {
+                            cycle_id,
+                            epoch: record.epoch,
+                            profit_bps: cycle.total_profit_bps,
+                            profit_delta_bps,
+                        });
+                    }
+                }
+                None => {
+                    let epoch = self.next_epoch;
+                    self.next_epoch += 1;
+                    self.open.insert(cycle_id, OpportunityRecord {
+                        cycle_id,
+                        epoch,
+                        opened_at: self.clock.now_instant(),
+                        last_profit_bps: cycle.total_profit_bps,
+                        iterations: 1,
+                        observational: cycle.observational,
+                        gross_profit_lamports: 0,
+                        estimated_tx_cost_lamports: 0,
+                        near_miss_shortfall_bps: None,
+                        attempts: 0,
+                    });
+                    events.push(OpportunityEvent::Opened {
+                        cycle_id,
+                        epoch,
+                        profit_bps: cycle.total_profit_bps,
+                        hops: cycle.total_hops,
+                        observational: cycle.observational,
+                    });
+                }
+            }
+        }
+
+        let mut closed: Vec<u64> = self
+            .open
+            .keys()
+            .filter(|cycle_id| !seen.contains(cycle_id))
+            .copied()
+            .collect();
+        closed.sort_unstable();
+        for cycle_id in closed {
+            if let Some(record) = self.open.remove(&cycle_id) {
+                events.push(OpportunityEvent::Closed {
+                    cycle_id,
+                    epoch: record.epoch,
+                    last_profit_bps: record.last_profit_bps,
+                    iterations: record.iterations,
+                    open_for: self.clock.elapsed_since(record.opened_at),
+                });
+            }
+        }
+
+        events
+    }
+
+    /// Epoch of the currently open opportunity for `cycle_id`, used to link
+    /// executions back to the opportunity they captured.
+    pub fn epoch_for(&self, cycle_id: u64) -> Option<u64> {
+        self.open.get(&cycle_id).map(|record| record.epoch)
+    }
+
+    pub fn get(&self, cycle_id: u64) -> Option<&OpportunityRecord> {
+        self.open.get(&cycle_id)
+    }
+
+    /// Attach the profit and transaction cost of a cycle that was just sized
+    pub fn record_sizing(&mut self, cycle: &ArbitrageCycle) {
+        if let Some(record) = self.open.get_mut(&cycle.cycle_id()) {
+            record.gross_profit_lamports = cycle.estimated_profit_lamports;
+            record.estimated_tx_cost_lamports = cycle.estimated_tx_cost_lamports;
+            record.near_miss_shortfall_bps = None;
+        }
+    }
+
+    /// Count an execution attempt against the open opportunity: a paper fill
+    /// or a live transaction that was sent, landed or not
+    pub fn record_attempt(&mut self, cycle: &ArbitrageCycle) {
+        if let Some(record) = self.open.get_mut(&cycle.cycle_id()) {
+            record.attempts += 1;
+        }
+    }
+
+    /// Tag an open opportunity whose sizing fell `shortfall_bps` short of the profit gate
+    pub fn record_near_miss(&mut self, cycle: &ArbitrageCycle, shortfall_bps: i64) {
+        if let Some(record) = self.open.get_mut(&cycle.cycle_id()) {
+            record.gross_profit_lamports = cycle.estimated_profit_lamports;
+            record.estimated_tx_cost_lamports = cycle.estimated_tx_cost_lamports;
+            record.near_miss_shortfall_bps = Some(shortfall_bps);
+        }
+    }
+}
+
+impl OpportunityEvent {
+    pub fn log(&self) {
+        match self {
+            OpportunityEvent::Opened { cycle_id, epoch, profit_bps, hops, observational } => {
+                info!(
+                    "🟢 Opportunity {:016x} opened (epoch {}): {} hops, {} bps{}",
+                    cycle_id, epoch, hops, profit_bps,
+                    if *observational { " [observational]" } else { "" }
+                );
+            }
+            OpportunityEvent::Updated { cycle_id, epoch, profit_bps, profit_delta_bps } => {
+                debug!(
+                    "🔁 Opportunity {:016x} updated (epoch {}): {} bps ({:+} bps)",
+                    cycle_id, epoch, profit_bps, profit_delta_bps
+                );
+            }
+            OpportunityEvent::Closed { cycle_id, epoch, last_profit_bps, iterations, open_for } => {
+                info!(
+                    "🔴 Opportunity {:016x} closed (epoch {}): last {} bps, open {} iterations / {:.1}s",
+                    cycle_id, epoch, last_profit_bps, iterations, open_for.as_secs_f64()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use solana_sdk::pubkey::Pubkey;
+    use std::sync::Arc;
+
+    fn cycle(profit_bps: i64) -> ArbitrageCycle {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg(sol, token), leg(token, sol)],
+            total_profit_bps: profit_bps,
+            estimated_profit_lamports: 0,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    /// Kind, cycle and epoch of each event, in emission order
+    fn summary(events: &[OpportunityEvent]) -> Vec<(&'static str, u64, u64)> {
+        events
+            .iter()
+            .map(|event| match event {
+                OpportunityEvent::Opened { cycle_id, epoch, .. } => ("opened", *cycle_id, *epoch),
+                OpportunityEvent::Updated { cycle_id, epoch, .. } => ("updated", *cycle_id, *epoch),
+                OpportunityEvent::Closed { cycle_id, epoch, .. } => ("closed", *cycle_id, *epoch),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_persistent_cycle_stays_one_epoch_while_a_transient_one_opens_and_closes() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut tracker = OpportunityTracker::with_clock(clock.clone());
+        let mut persistent = cycle(60);
+        let transient = cycle(80);
+        let (p, t) = (persistent.cycle_id(), transient.cycle_id());
+
+        let first = tracker.update(&[persistent.clone(), transient]);
+        assert_eq!(summary(&first), vec![("opened", p, 1), ("opened", t, 2)]);
+
+        clock.advance(Duration::from_secs(2));
+        persistent.total_profit_bps = 70;
+        let second = tracker.update(&[persistent.clone()]);
+        assert_eq!(summary(&second), vec![("updated", p, 1), ("closed", t, 2)]);
+        assert!(matches!(second[0], OpportunityEvent::Updated { profit_delta_bps: 10, .. }));
+
+        // Same cycle at the same profit: nothing to report
+        clock.advance(Duration::from_secs(2));
+        assert!(tracker.update(&[persistent]).is_empty());
+        assert_eq!(tracker.epoch_for(p), Some(1));
+
+        let last = tracker.update(&[]);
+        assert_eq!(summary(&last), vec![("closed", p, 1)]);
+        match &last[0] {
+            OpportunityEvent::Closed { iterations, open_for, last_profit_bps, .. } => {
+                assert_eq!((*iterations, *open_for, *last_profit_bps), (3, Duration::from_secs(4), 70));
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+        assert_eq!(tracker.epoch_for(p), None);
+    }
+}
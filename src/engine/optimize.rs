@@ -1,35 +1,378 @@
 use crate::engine::graph::PriceGraph;
 use crate::engine::types::*;
+use crate::math::ConversionError;
+use crate::persist::atomic_write_json;
+use crate::rpc::RpcLike;
+use crate::units::Usd;
+use anyhow::{Context, Result};
 use dashmap::DashMap;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tracing::{debug, info};
 
+/// Capital budget for cycles starting in one base mint, in that mint's native units
+#[derive(Debug, Clone, Copy)]
+pub struct CapitalLimits {
+    pub max_capital: u64,
+    pub per_cycle_percent: u64,
+    pub min_profit: u64,
+    pub min_input_fraction: f64, // Input band as fractions of `max_capital`
+    pub max_input_fraction: f64,
+}
+
+impl CapitalLimits {
+    /// Inputs a single cycle may be sized to: the configured fraction band of
+    /// `max_capital`, capped by the per-cycle share and the global minimum
+    pub fn input_band(&self) -> Option<(u64, u64)> {
+        let fraction_of_capital = |fraction: f64| self.max_capital as f64 * fraction.clamp(0.0, 1.0);
+        let low = (fraction_of_capital(self.min_input_fraction).ceil() as u64).max(MIN_INPUT_LAMPORTS);
+        let high = (fraction_of_capital(self.max_input_fraction).floor() as u64)
+            .min(self.max_capital * self.per_cycle_percent / 100);
+        (low <= high).then_some((low, high))
+    }
+}
+
+/// Capital limits per base mint. A cycle is sized and funded with the limits
+/// of the mint it starts in, so one currency's budget never funds another's.
+/// Once wallet balances are known, a mint's budget is also capped by what its
+/// associated token account actually holds.
+#[derive(Debug, Clone, Default)]
+pub struct CapitalBook {
+    limits: BTreeMap<Pubkey, CapitalLimits>,
+    balances: BTreeMap<Pubkey, u64>, // Base mint ATA balances as of the last refresh
+}
+
+impl CapitalBook {
+    pub fn new(limits: impl IntoIterator<Item = (Pubkey, CapitalLimits)>) -> Self {
+        Self { limits: limits.into_iter().collect(), balances: BTreeMap::new() }
+    }
+
+    pub fn set_balance(&mut self, base_mint: Pubkey, amount: u64) {
+        self.balances.insert(base_mint, amount);
+    }
+
+    /// Read the wallet's ATA of every base mint in one request; a missing ATA
+    /// holds nothing. On failure the previous balances are kept.
+    pub fn refresh_balances(&mut self, rpc: &dyn RpcLike, wallet: &Pubkey) -> Result<()> {
+        let base_mints = self.base_mints();
+        let atas: Vec<Pubkey> = base_mints.iter().map(|mint| get_associated_token_address(wallet, mint)).collect();
+        let accounts = rpc.get_multiple_accounts(&atas).context("Failed to read base mint balances")?;
+        for (base_mint, account) in base_mints.into_iter().zip(accounts) {
+            // SPL token account: mint, owner, then the amount
+            let amount = account
+                .filter(|account| account.data.len() >= 72)
+                .map_or(0, |account| u64::from_le_bytes(account.data[64..72].try_into().unwrap()));
+            self.set_balance(base_mint, amount);
+        }
+        Ok(())
+    }
+
+    /// Capital the cycles of `base_mint` may commit this tick: `max_capital`,
+    /// capped by the ATA balance when it is known
+    pub fn budget(&self, base_mint: &Pubkey) -> Option<u64> {
+        let limits = self.limits(base_mint)?;
+        Some(self.balances.get(base_mint).map_or(limits.max_capital, |&balance| limits.max_capital.min(balance)))
+    }
+
+    /// Every base mint with a budget, in a stable order
+    pub fn base_mints(&self) -> Vec<Pubkey> {
+        self.limits.keys().copied().collect()
+    }
+
+    pub fn limits(&self, base_mint: &Pubkey) -> Option<&CapitalLimits> {
+        self.limits.get(base_mint)
+    }
+
+    /// Limits of the mint `cycle` starts in, `None` when it has no budget
+    pub fn limits_for(&self, cycle: &ArbitrageCycle) -> Option<&CapitalLimits> {
+        self.limits(&cycle.base_mint())
+    }
+}
+
+/// Input amount granted to one cleared cycle by `AmountOptimizer::allocate_capital`
+#[derive(Debug, Clone)]
+pub struct CapitalAllocation {
+    pub cycle: ArbitrageCycle,
+    pub amount: u64,
+}
+
+/// Modeled slippage for one venue: `base_bps` on every trade plus the
+/// liquidity-ratio term, never above `cap_bps`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlippageBounds {
+    pub base_bps: u64,
+    pub cap_bps: u64,
+}
+
+impl Default for SlippageBounds {
+    fn default() -> Self {
+        Self { base_bps: DEFAULT_SLIPPAGE_BASE_BPS, cap_bps: DEFAULT_SLIPPAGE_CAP_BPS }
+    }
+}
+
+/// USD per base unit of a leg's input and of its output mint, as the graph
+/// prices them. A leg's input is in base units of whatever mint it spends:
+/// lamports, USDC micro-units or an intermediate token's.
+#[derive(Debug, Clone, Copy)]
+struct InputPrice {
+    from_usd_per_unit: Option<f64>,
+    to_usd_per_unit: Option<f64>,
+}
+
+impl InputPrice {
+    /// The input's own price, else what the leg turns one unit of it into
+    fn usd_per_unit(&self, edge: &PoolEdge) -> Option<f64> {
+        self.from_usd_per_unit
+            .or_else(|| self.to_usd_per_unit.map(|usd| usd * edge.spot_price()))
+            .filter(|usd| usd.is_finite() && *usd >= 0.0)
+    }
+}
+
+pub const DEFAULT_SLIPPAGE_BASE_BPS: u64 = 10;
+pub const DEFAULT_SLIPPAGE_CAP_BPS: u64 = 100;
+
+/// Smallest input the optimizer will size a cycle to
+const MIN_INPUT_LAMPORTS: u64 = 1_000_000;
+/// Smoothing factor for the per-pool realized slippage average
+const REALIZED_SLIPPAGE_EMA_ALPHA: f64 = 0.2;
+/// Upper bound on the extra fee applied to a pool for past underdelivery
+const MAX_REALIZED_SLIPPAGE_PENALTY_BPS: u64 = 100;
+
+/// How far a realized rate fell short of the modeled one, in bps; negative
+/// when it delivered more than modeled
+fn excess_slippage_bps(modeled_in: u64, modeled_out: u64, realized_in: u64, realized_out: u64) -> Option<f64> {
+    if modeled_in == 0 || modeled_out == 0 || realized_in == 0 {
+        return None;
+    }
+    let modeled_rate = modeled_out as f64 / modeled_in as f64;
+    let realized_rate = realized_out as f64 / realized_in as f64;
+    Some((1.0 - realized_rate / modeled_rate) * 10_000.0)
+}
+
 pub struct AmountOptimizer {
     graph: Arc<PriceGraph>,
+    input_granularity_lamports: u64,
+    realized_slippage_penalty_weight: f64,
+    excess_slippage_bps: DashMap<Pubkey, f64>, // Key: pool, Value: EMA of realized - modeled slippage
+    quote_haircut_bps: HashMap<DexType, u64>,
+    slippage_bounds: HashMap<DexType, SlippageBounds>,
+    conversion_rejections: AtomicU64, // Leg evaluations dropped because the modeled amount was not representable
+    near_miss_bps: Option<u64>,       // None while near misses are not recorded
 }
 
 impl AmountOptimizer {
-    pub fn new(graph: Arc<PriceGraph>) -> Self {
-        Self { graph }
+    pub fn new(
+        graph: Arc<PriceGraph>,
+        input_granularity_lamports: u64,
+        realized_slippage_penalty_weight: f64,
+    ) -> Self {
+        Self {
+            graph,
+            input_granularity_lamports: input_granularity_lamports.max(1),
+            realized_slippage_penalty_weight: realized_slippage_penalty_weight.max(0.0),
+            excess_slippage_bps: DashMap::new(),
+            quote_haircut_bps: HashMap::new(),
+            slippage_bounds: HashMap::new(),
+            conversion_rejections: AtomicU64::new(0),
+            near_miss_bps: None,
+        }
+    }
+
+    /// Per-venue haircuts deducted from modeled leg outputs, see
+    /// `CapabilityRegistry::apply_quote_haircuts`
+    pub fn set_quote_haircuts(&mut self, haircuts: HashMap<DexType, u64>) {
+        self.quote_haircut_bps = haircuts;
+    }
+
+    pub fn quote_haircut_bps(&self, dex_type: DexType) -> u64 {
+        self.quote_haircut_bps.get(&dex_type).copied().unwrap_or(0).min(10_000)
+    }
+
+    /// How far below the threshold a rejected cycle may fall and still be
+    /// reported as a near miss; `None` turns near-miss reporting off
+    pub fn set_near_miss_bps(&mut self, near_miss_bps: Option<u64>) {
+        self.near_miss_bps = near_miss_bps;
+    }
+
+    /// Per-venue slippage base and cap; venues without an entry use the defaults
+    pub fn set_slippage_bounds(&mut self, bounds: HashMap<DexType, SlippageBounds>) {
+        self.slippage_bounds = bounds;
+    }
+
+    pub fn slippage_bounds(&self, dex_type: DexType) -> SlippageBounds {
+        self.slippage_bounds.get(&dex_type).copied().unwrap_or_default()
+    }
+
+    /// Quote `amount_in` through `edge`: spot price less the effective fee
+    /// (fee, modeled slippage, realized-slippage penalty) and the venue's quote
+    /// haircut. An output that is not a representable amount is counted and
+    /// rejected instead of being saturated into a wrong number.
+    ///
+    /// Whirlpool legs whose tick arrays were loaded are walked tick by tick
+    /// instead, and constant-product and bonding-curve legs quoted on their
+    /// reserves, so only the penalty, transfer fees and haircut are deducted
+    /// on top.
+    pub fn quote(&self, from_mint: &Pubkey, edge: &PoolEdge, amount_in: u64) -> Result<QuoteResult, ConversionError> {
+        self.quote_priced(self.input_price(from_mint, &edge.to_mint), edge, amount_in)
+    }
+
+    /// USD prices a leg's input is valued at for slippage. Read from the
+    /// graph's edges, so resolve it before `with_edge` takes its guard.
+    fn input_price(&self, from_mint: &Pubkey, to_mint: &Pubkey) -> InputPrice {
+        InputPrice { from_usd_per_unit: self.graph.usd_per_unit(from_mint), to_usd_per_unit: self.graph.usd_per_unit(to_mint) }
+    }
+
+    fn quote_priced(&self, input: InputPrice, edge: &PoolEdge, amount_in: u64) -> Result<QuoteResult, ConversionError> {
+        let walked_out = match edge.dex_type {
+            DexType::Whirlpool => self.graph.whirlpool_quote(&edge.pool_pubkey, &edge.to_mint, amount_in),
+            DexType::PumpBondingCurve => self
+                .graph
+                .bonding_curve_quote(&edge.pool_pubkey, &edge.to_mint)
+                .map(|quote| quote.amount_out(amount_in)),
+            _ => self
+                .graph
+                .constant_product_quote(&edge.pool_pubkey, &edge.to_mint)
+                .map(|quote| quote.amount_out(amount_in)),
+        };
+        let quote = match walked_out {
+            Some(walked_out) => {
+                let costs_bps = [
+                    self.realized_slippage_penalty_bps(&edge.pool_pubkey),
+                    edge.transfer_fee_bps,
+                    self.quote_haircut_bps(edge.dex_type),
+                ];
+                edge.quote_walked(amount_in, walked_out, &costs_bps)
+            }
+            None => {
+                let costs_bps = [self.effective_fee_bps_priced(input, edge, amount_in), self.quote_haircut_bps(edge.dex_type)];
+                edge.quote_with_costs(amount_in, &costs_bps)
+            }
+        };
+        quote.map_err(|e| {
+            self.conversion_rejections.fetch_add(1, Ordering::Relaxed);
+            debug!("Rejecting leg through {}: {}", edge.pool_pubkey, e);
+            e
+        })
+    }
+
+    fn leg_amount_out(&self, input: InputPrice, edge: &PoolEdge, amount_in: u64) -> Result<u64, ConversionError> {
+        self.quote_priced(input, edge, amount_in).map(|quote| quote.amount_out)
+    }
+
+    /// Leg evaluations rejected so far because of an unrepresentable amount
+    pub fn conversion_rejections(&self) -> u64 {
+        self.conversion_rejections.load(Ordering::Relaxed)
+    }
+
+    /// Record the slippage a confirmed swap through `pool` actually incurred
+    /// against what the optimizer modeled for it.
+    pub fn record_realized_slippage(&self, pool: Pubkey, modeled_bps: u64, realized_bps: u64) {
+        self.record_excess_slippage(pool, realized_bps as f64 - modeled_bps as f64);
+    }
+
+    /// Record every leg of a filled cycle: the rate each leg delivered against
+    /// the rate it was sized at. `leg_outputs` are the amounts the legs
+    /// actually returned, in order.
+    pub fn record_fill(&self, cycle: &ArbitrageCycle, leg_outputs: &[u64]) {
+        let mut amount_in = cycle.legs.first().map_or(0, |leg| leg.amount_in);
+        for (leg, &amount_out) in cycle.legs.iter().zip(leg_outputs) {
+            if let Some(excess) = excess_slippage_bps(leg.amount_in, leg.estimated_amount_out, amount_in, amount_out) {
+                self.record_excess_slippage(leg.pool_pubkey, excess);
+            }
+            amount_in = amount_out;
+        }
+    }
+
+    /// Record a filled cycle whose leg outputs are not known, only what it
+    /// returned in total: the shortfall is spread evenly over its legs
+    pub fn record_cycle_fill(&self, cycle: &ArbitrageCycle, amount_in: u64, amount_out: u64) {
+        let (Some(first), Some(last)) = (cycle.legs.first(), cycle.legs.last()) else {
+            return;
+        };
+        if let Some(excess) = excess_slippage_bps(first.amount_in, last.estimated_amount_out, amount_in, amount_out) {
+            for leg in &cycle.legs {
+                self.record_excess_slippage(leg.pool_pubkey, excess / cycle.legs.len() as f64);
+            }
+        }
+    }
+
+    fn record_excess_slippage(&self, pool: Pubkey, excess_bps: f64) {
+        self.excess_slippage_bps
+            .entry(pool)
+            .and_modify(|avg| *avg += REALIZED_SLIPPAGE_EMA_ALPHA * (excess_bps - *avg))
+            .or_insert(excess_bps);
+    }
+
+    /// Restore the per-pool averages saved by `save_realized_slippage`,
+    /// returning how many pools were loaded. A missing file loads nothing.
+    pub async fn load_realized_slippage(&self, path: &str) -> Result<usize> {
+        if !Path::new(path).exists() {
+            return Ok(0);
+        }
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .context(format!("Failed to read realized slippage {}", path))?;
+        let averages: BTreeMap<String, f64> =
+            serde_json::from_str(&content).context(format!("Failed to parse realized slippage {}", path))?;
+        for (pool, average) in &averages {
+            let pool = Pubkey::from_str(pool).context(format!("Invalid pool {} in {}", pool, path))?;
+            self.excess_slippage_bps.insert(pool, *average);
+        }
+        Ok(averages.len())
+    }
+
+    /// Persist the per-pool averages as a JSON object keyed by pool address
+    pub async fn save_realized_slippage(&self, path: &str) -> Result<()> {
+        let averages: BTreeMap<String, f64> = self
+            .excess_slippage_bps
+            .iter()
+            .map(|average| (average.key().to_string(), *average.value()))
+            .collect();
+        atomic_write_json(path, &averages).await
+    }
+
+    /// Extra fee for a pool that has historically delivered worse than modeled
+    pub fn realized_slippage_penalty_bps(&self, pool: &Pubkey) -> u64 {
+        let excess = self.excess_slippage_bps.get(pool).map_or(0.0, |avg| *avg.value());
+        // Clamped before the cast, so NaN and huge averages cannot saturate
+        let penalty = (excess * self.realized_slippage_penalty_weight).clamp(0.0, MAX_REALIZED_SLIPPAGE_PENALTY_BPS as f64);
+        if penalty.is_nan() { 0 } else { penalty.round() as u64 }
+    }
+
+    /// Pool and transfer fees plus modeled slippage plus realized-slippage
+    /// penalty for a leg, at most 10000 bps. A leg that reaches it quotes 0 out and is rejected.
+    /// `amount_in` is in base units of `from_mint`.
+    pub fn effective_fee_bps(&self, from_mint: &Pubkey, edge: &PoolEdge, amount_in: u64) -> u64 {
+        self.effective_fee_bps_priced(self.input_price(from_mint, &edge.to_mint), edge, amount_in)
+    }
+
+    fn effective_fee_bps_priced(&self, input: InputPrice, edge: &PoolEdge, amount_in: u64) -> u64 {
+        let slippage_bps = self.calculate_slippage_bps(input, amount_in, edge);
+        let effective = edge
+            .fee_bps
+            .saturating_add(edge.transfer_fee_bps)
+            .saturating_add(slippage_bps)
+            .saturating_add(self.realized_slippage_penalty_bps(&edge.pool_pubkey));
+        effective.min(10_000)
     }
 
     pub fn optimize_amount(
         &self,
         cycle: &mut ArbitrageCycle,
-        max_capital_lamports: u64,
-        capital_per_cycle_percent: u64,
-        min_profit_lamports: u64,
+        limits: &CapitalLimits,
+        threshold: ProfitThreshold,
     ) -> Option<u64> {
-        let max_amount = (max_capital_lamports * capital_per_cycle_percent) / 100;
-        let mut low = 1_000_000; // 0.001 SOL min
+        let min_profit_lamports = limits.min_profit;
+        let (min_amount, max_amount) = limits.input_band()?;
+        let mut low = min_amount;
         let mut high = max_amount;
 
-        if high < low {
-            return None;
-        }
-
         let mut best_amount = 0;
         let mut best_profit = 0;
 
@@ -51,7 +394,12 @@ impl AmountOptimizer {
         }
 
         if best_amount > 0 && best_profit > min_profit_lamports {
+            let best_amount = self.round_to_granularity(cycle, best_amount, (min_amount, max_amount), min_profit_lamports)?;
             self.update_leg_amounts(cycle, best_amount);
+            if !Self::clears_threshold(cycle, best_amount, threshold) {
+                return None;
+            }
+
             info!("Optimized cycle: initial={} lamports, profit={} lamports", best_amount, cycle.estimated_profit_lamports);
             Some(best_amount)
         } else {
@@ -59,23 +407,144 @@ impl AmountOptimizer {
         }
     }
 
+    /// Re-apply the hop-scaled threshold to the profit net of fees and slippage
+    fn clears_threshold(cycle: &ArbitrageCycle, amount: u64, threshold: ProfitThreshold) -> bool {
+        let net_profit_bps = i64::try_from(cycle.estimated_profit_lamports as u128 * 10_000 / amount.max(1) as u128).unwrap_or(i64::MAX);
+        if net_profit_bps <= threshold.for_hops(cycle.total_hops) {
+            debug!(
+                "Cycle below {}-hop threshold after costs: {} bps net",
+                cycle.total_hops, net_profit_bps
+            );
+            return false;
+        }
+        true
+    }
+
+    /// For a cycle `optimize_amount` rejected: how far its profit net of fees
+    /// and slippage falls short of the threshold, if near misses are recorded
+    /// and it is short by at most the configured bps. Sized at the bottom of the input band, where slippage is smallest; the
+    /// legs are left sized there for reporting.
+    pub fn near_miss_shortfall(
+        &self,
+        cycle: &mut ArbitrageCycle,
+        limits: &CapitalLimits,
+        threshold: ProfitThreshold,
+    ) -> Option<i64> {
+        let within_bps = self.near_miss_bps?;
+        let (amount, _) = limits.input_band()?;
+        self.update_leg_amounts(cycle, amount);
+        if cycle.legs.iter().any(|leg| leg.estimated_amount_out == 0) {
+            return None;
+        }
+        let amount_out = cycle.legs.last()?.estimated_amount_out;
+        let net_profit_bps = ((amount_out as i128 - amount as i128) * 10_000 / amount as i128) as i64;
+        let shortfall_bps = threshold.for_hops(cycle.total_hops) - net_profit_bps;
+        (0..=within_bps as i64).contains(&shortfall_bps).then_some(shortfall_bps)
+    }
+
+    /// Distribute `limits.max_capital`, capped by `budget` (the wallet's
+    /// balance of the base mint, see `CapitalBook::budget`), across the cycles
+    /// cleared in one tick so their combined input never exceeds it. Cycles are funded in order of
+    /// expected profit, reduced by their priority penalty; one that no longer fits is shrunk to the remaining
+    /// budget if it still clears the thresholds at that size, otherwise it is
+    /// returned unfunded.
+    pub fn allocate_capital(
+        &self,
+        cleared: Vec<(ArbitrageCycle, u64)>,
+        limits: &CapitalLimits,
+        budget: u64,
+        threshold: ProfitThreshold,
+    ) -> (Vec<CapitalAllocation>, Vec<ArbitrageCycle>) {
+        let mut cleared = cleared;
+        let priority = |cycle: &ArbitrageCycle| {
+            cycle.estimated_profit_lamports as u128 * (10_000 - cycle.priority_penalty_bps.min(10_000)) as u128
+        };
+        cleared.sort_by(|(a, _), (b, _)| {
+            priority(b)
+                .cmp(&priority(a))
+                .then_with(|| a.cycle_id().cmp(&b.cycle_id()))
+        });
+
+        let mut remaining = limits.max_capital.min(budget);
+        let mut allocations = Vec::new();
+        let mut unfunded = Vec::new();
+        for (mut cycle, amount) in cleared {
+            if amount <= remaining {
+                remaining -= amount;
+                allocations.push(CapitalAllocation { cycle, amount });
+                continue;
+            }
+
+            let granularity = self.input_granularity_lamports;
+            let shrunk = remaining / granularity * granularity;
+            let min_amount = limits.input_band().map_or(MIN_INPUT_LAMPORTS, |(low, _)| low);
+            if shrunk >= min_amount && self.resize(&mut cycle, shrunk, limits, threshold) {
+                debug!("Shrunk cycle {:016x} from {} to {} lamports to fit capital", cycle.cycle_id(), amount, shrunk);
+                remaining -= shrunk;
+                allocations.push(CapitalAllocation { cycle, amount: shrunk });
+            } else {
+                unfunded.push(cycle);
+            }
+        }
+        (allocations, unfunded)
+    }
+
+    /// `allocate_capital` for each base mint in `book`: cleared cycles share
+    /// only the budget of the mint they start in. Cycles whose base mint has
+    /// no budget are returned unfunded.
+    pub fn allocate_by_base_mint(
+        &self,
+        cleared: Vec<(ArbitrageCycle, u64)>,
+        book: &CapitalBook,
+        threshold: ProfitThreshold,
+    ) -> (Vec<CapitalAllocation>, Vec<ArbitrageCycle>) {
+        let mut by_base_mint: BTreeMap<Pubkey, Vec<(ArbitrageCycle, u64)>> = BTreeMap::new();
+        for (cycle, amount) in cleared {
+            by_base_mint.entry(cycle.base_mint()).or_default().push((cycle, amount));
+        }
+
+        let mut allocations = Vec::new();
+        let mut unfunded = Vec::new();
+        for (base_mint, cleared) in by_base_mint {
+            match book.limits(&base_mint).zip(book.budget(&base_mint)) {
+                Some((limits, budget)) => {
+                    let (funded, left_over) = self.allocate_capital(cleared, limits, budget, threshold);
+                    allocations.extend(funded);
+                    unfunded.extend(left_over);
+                }
+                None => unfunded.extend(cleared.into_iter().map(|(cycle, _)| cycle)),
+            }
+        }
+        (allocations, unfunded)
+    }
+
+    /// Size `cycle` to exactly `amount`, returning whether it still clears
+    /// `limits.min_profit` and the hop threshold
+    fn resize(&self, cycle: &mut ArbitrageCycle, amount: u64, limits: &CapitalLimits, threshold: ProfitThreshold) -> bool {
+        match self.simulate_cycle_with_amount(cycle, amount) {
+            Some(profit) if profit > limits.min_profit => {
+                self.update_leg_amounts(cycle, amount);
+                Self::clears_threshold(cycle, amount, threshold)
+            }
+            _ => false,
+        }
+    }
+
+    /// Profit `cycle` returns on `amount` at the graph's current prices, or
+    /// `None` when it no longer returns more than it takes
+    pub fn expected_profit(&self, cycle: &ArbitrageCycle, amount: u64) -> Option<u64> {
+        self.simulate_cycle_with_amount(cycle, amount)
+    }
+
     fn simulate_cycle_with_amount(&self, cycle: &ArbitrageCycle, initial_amount: u64) -> Option<u64> {
         let mut current_amount = initial_amount;
         
         for leg in &cycle.legs {
-            // Look up the edge from graph to get price and fee
-            if let Some(edge) = self.find_edge_in_graph(leg) {
-                let slippage_bps = self.calculate_slippage_bps(current_amount, leg.pool_pubkey);
-                let effective_fee_bps = edge.fee_bps + slippage_bps;
-                let fee_multiplier = (10_000 - effective_fee_bps) as f64 / 10_000.0;
-                
-                current_amount = (current_amount as f64 * edge.price * fee_multiplier) as u64;
-                
-                if current_amount == 0 {
-                    return None;
-                }
-            } else {
-                return None; // Edge not found
+            let input = self.input_price(&leg.from_mint, &leg.to_mint);
+            // Price and fee come from the graph edge; a missing edge fails the cycle
+            current_amount = self.with_edge(leg, |edge| self.leg_amount_out(input, edge, current_amount))?.ok()?;
+            if current_amount == 0 {
+                return None;
             }
         }
         
@@ -86,47 +555,78 @@ impl AmountOptimizer {
         }
     }
 
-    /// Look up an edge in the graph using the leg information
-    fn find_edge_in_graph(&self, leg: &SwapLeg) -> Option<PoolEdge> {
-        if let Some(edges) = self.graph.edges.get(&leg.from_mint) {
-            for edge in edges.value().iter() {
-                if edge.pool_pubkey == leg.pool_pubkey && edge.dex_type == leg.dex_type {
-                    return Some(edge.clone());
-                }
-            }
+    /// Round the optimized amount to the configured input granularity, preferring
+    /// the nearest multiple and falling back to the other neighbour if rounding
+    /// pushed the cycle below the profit threshold.
+    fn round_to_granularity(
+        &self,
+        cycle: &ArbitrageCycle,
+        amount: u64,
+        (min_amount, max_amount): (u64, u64),
+        min_profit_lamports: u64,
+    ) -> Option<u64> {
+        let granularity = self.input_granularity_lamports;
+        if granularity <= 1 {
+            return Some(amount);
         }
-        None
+
+        let down = amount / granularity * granularity;
+        let up = down.checked_add(granularity).filter(|up| *up <= max_amount);
+        let candidates = if amount - down >= granularity / 2 {
+            [up, Some(down)]
+        } else {
+            [Some(down), up]
+        };
+
+        candidates
+            .into_iter()
+            .flatten()
+            .filter(|candidate| *candidate >= min_amount)
+            .find(|candidate| {
+                self.simulate_cycle_with_amount(cycle, *candidate)
+                    .map_or(false, |profit| profit > min_profit_lamports)
+            })
+    }
+
+    /// Run `f` on the graph edge for `leg` while holding the shard's read
+    /// guard, so the hot sizing loop never copies edges out of the graph.
+    /// `f` must not touch `self.graph.edges`.
+    fn with_edge<R>(&self, leg: &SwapLeg, f: impl FnOnce(&PoolEdge) -> R) -> Option<R> {
+        let edges = self.graph.edges.get(&leg.from_mint)?;
+        edges
+            .iter()
+            .find(|edge| edge.pool_pubkey == leg.pool_pubkey && edge.dex_type == leg.dex_type)
+            .map(f)
     }
 
     /// Calculate real slippage based on pool liquidity ratio
     /// 
-    /// Phase 1.x: Now uses actual pool liquidity from the graph instead of estimates
-    fn calculate_slippage_bps(&self, amount_in: u64, pool_pubkey: Pubkey) -> u64 {
-        // Iterate graph edges to find the pool and get liquidity
-        for entry in self.graph.edges.iter() {
-            for edge in entry.value() {
-                if edge.pool_pubkey == pool_pubkey {
-                    let trade_size_usd = (amount_in as f64) / 1e9 * 200.0; // Convert to USD
-                    let pool_liquidity_usd = edge.liquidity_usd.max(1.0); // Avoid division by zero
-                    
-                    let liquidity_ratio = trade_size_usd / pool_liquidity_usd;
-                    let dynamic_slippage = (liquidity_ratio * 0.5 * 100.0) as u64; // 0.5 bps per %
-                    
-                    let total_slippage = 10u64 + dynamic_slippage;
-                    
-                    debug!(
-                        "Pool {}: trade_size=${:.2}, pool_liq=${:.2}, ratio={:.4}%, slippage={} bps",
-                        pool_pubkey, trade_size_usd, pool_liquidity_usd, 
-                        liquidity_ratio * 100.0, total_slippage
-                    );
-                    
-                    return total_slippage.min(100); // Cap at 100 bps (1%)
-                }
-            }
-        }
-        
-        debug!("Pool {} not found in graph for slippage calculation", pool_pubkey);
-        50u64 // Default 50 bps slippage
+    /// Phase 1.x: Now uses actual pool liquidity from the graph instead of estimates.
+    /// Base and cap come from the edge's venue, see `set_slippage_bounds`.
+    /// `amount_in` is valued at the USD price of the leg's input mint, see
+    /// `InputPrice`; an input with no price slips at the venue cap.
+    fn calculate_slippage_bps(&self, input: InputPrice, amount_in: u64, edge: &PoolEdge) -> u64 {
+        let bounds = self.slippage_bounds(edge.dex_type);
+        let Some(usd_per_unit) = input.usd_per_unit(edge) else {
+            debug!("Pool {}: no USD price for the leg's input, slippage at cap", edge.pool_pubkey);
+            return bounds.cap_bps;
+        };
+        let trade_size = Usd(amount_in as f64 * usd_per_unit);
+        let pool_liquidity = Usd(edge.liquidity_usd.max(1.0)); // Avoid division by zero
+
+        let liquidity_ratio = trade_size / pool_liquidity;
+        // Clamped in f64 first: for dust pools the ratio is huge and a saturated
+        // `as u64` would overflow the addition below
+        let dynamic_slippage = (liquidity_ratio * 0.5 * 100.0).clamp(0.0, bounds.cap_bps as f64); // 0.5 bps per %
+        let total_slippage = if dynamic_slippage.is_nan() { bounds.cap_bps } else { bounds.base_bps + dynamic_slippage as u64 };
+
+        debug!(
+            "Pool {}: trade_size={}, pool_liq={}, ratio={:.4}%, slippage={} bps",
+            edge.pool_pubkey, trade_size, pool_liquidity,
+            liquidity_ratio * 100.0, total_slippage
+        );
+
+        total_slippage.min(bounds.cap_bps)
     }
 
     fn update_leg_amounts(&self, cycle: &mut ArbitrageCycle, initial_amount: u64) {
@@ -134,19 +634,509 @@ impl AmountOptimizer {
         
         for leg in cycle.legs.iter_mut() {
             leg.amount_in = current_amount;
+            let input = self.input_price(&leg.from_mint, &leg.to_mint);
             
-            if let Some(edge) = self.find_edge_in_graph(leg) {
-                let slippage_bps = self.calculate_slippage_bps(current_amount, leg.pool_pubkey);
-                let effective_fee_bps = edge.fee_bps + slippage_bps;
-                let fee_multiplier = (10_000 - effective_fee_bps) as f64 / 10_000.0;
-                
-                current_amount = (current_amount as f64 * edge.price * fee_multiplier) as u64;
+            // The haircut output is what min_amount_out is derived from
+            let quoted = self.with_edge(leg, |edge| {
+                Some((self.leg_amount_out(input, edge, current_amount).ok()?, self.quote_haircut_bps(edge.dex_type)))
+            });
+            if let Some((amount_out, haircut_bps)) = quoted.flatten() {
+                current_amount = amount_out;
                 leg.estimated_amount_out = current_amount;
+                leg.quote_haircut_bps = haircut_bps;
             } else {
                 leg.estimated_amount_out = 0;
+                leg.quote_haircut_bps = 0;
             }
         }
         
         cycle.estimated_profit_lamports = current_amount.saturating_sub(initial_amount);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{sol_mint, usdc_mint};
+    use crate::pools::MintPoolData;
+    use crate::rpc::MockRpc;
+    use crate::token_extensions::token_2022_program_id;
+    use solana_sdk::account::Account;
+
+    fn leg(pool_pubkey: Pubkey, amount_in: u64, estimated_amount_out: u64) -> SwapLeg {
+        SwapLeg {
+            from_mint: Pubkey::new_unique(),
+            to_mint: Pubkey::new_unique(),
+            pool_pubkey,
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in,
+            estimated_amount_out,
+            quote_haircut_bps: 0,
+        }
+    }
+
+    fn cycle(legs: Vec<SwapLeg>) -> ArbitrageCycle {
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn edge(pool: Pubkey) -> PoolEdge {
+        PoolEdge::new(pool, Pubkey::new_unique(), DexType::RaydiumV4, 1.0, 1_000_000.0, 25, spl_token::id())
+    }
+
+    #[test]
+    fn a_pool_that_underdelivers_gets_a_higher_effective_fee() {
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 1.0);
+        let (slipping, clean) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let filled = cycle(vec![leg(clean, 1_000_000, 2_000_000), leg(slipping, 2_000_000, 1_010_000)]);
+
+        // The first leg filled as quoted, the second returned 0.5% less
+        optimizer.record_fill(&filled, &[2_000_000, 1_004_950]);
+
+        assert_eq!(optimizer.realized_slippage_penalty_bps(&clean), 0);
+        assert_eq!(optimizer.realized_slippage_penalty_bps(&slipping), 50);
+        assert_eq!(
+            optimizer.effective_fee_bps(&sol_mint(), &edge(slipping), 1_000_000),
+            optimizer.effective_fee_bps(&sol_mint(), &edge(clean), 1_000_000) + 50
+        );
+    }
+
+    #[test]
+    fn a_leg_is_judged_on_the_amount_it_was_actually_given() {
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 1.0);
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let filled = cycle(vec![leg(first, 1_000_000, 2_000_000), leg(second, 2_000_000, 1_000_000)]);
+
+        // Only the first leg slipped; the second delivered its rate on less input
+        optimizer.record_fill(&filled, &[1_980_000, 990_000]);
+
+        assert_eq!(optimizer.realized_slippage_penalty_bps(&first), 100);
+        assert_eq!(optimizer.realized_slippage_penalty_bps(&second), 0);
+    }
+
+    #[test]
+    fn a_live_fill_spreads_its_shortfall_over_the_legs() {
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 1.0);
+        let pools = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let filled = cycle(vec![leg(pools[0], 1_000_000, 2_000_000), leg(pools[1], 2_000_000, 1_010_000)]);
+
+        optimizer.record_cycle_fill(&filled, 1_000_000, 1_005_950);
+
+        for pool in &pools {
+            assert_eq!(optimizer.realized_slippage_penalty_bps(pool), 20);
+        }
+    }
+
+    fn limits(max_capital: u64, min_profit: u64) -> CapitalLimits {
+        CapitalLimits { max_capital, per_cycle_percent: 100, min_profit, min_input_fraction: 0.0, max_input_fraction: 1.0 }
+    }
+
+    fn round_trip(base_mint: Pubkey, amount_in: u64) -> ArbitrageCycle {
+        let token = Pubkey::new_unique();
+        let mut cycle = cycle(vec![leg(Pubkey::new_unique(), amount_in, 0), leg(Pubkey::new_unique(), 0, amount_in)]);
+        cycle.legs[0].from_mint = base_mint;
+        cycle.legs[0].to_mint = token;
+        cycle.legs[1].from_mint = token;
+        cycle.legs[1].to_mint = base_mint;
+        cycle
+    }
+
+    #[test]
+    fn sol_and_usdc_cycles_resolve_their_own_capital_limits() {
+        let book = CapitalBook::new([(sol_mint(), limits(2_000_000_000, 500_000)), (usdc_mint(), limits(500_000_000, 500_000))]);
+
+        let sol_limits = book.limits_for(&round_trip(sol_mint(), 1_000_000)).unwrap();
+        let usdc_limits = book.limits_for(&round_trip(usdc_mint(), 1_000_000)).unwrap();
+
+        assert_eq!(sol_limits.max_capital, 2_000_000_000);
+        assert_eq!(usdc_limits.max_capital, 500_000_000);
+        assert!(book.limits_for(&round_trip(Pubkey::new_unique(), 1_000_000)).is_none());
+        assert_eq!(book.base_mints().len(), 2);
+    }
+
+    #[test]
+    fn each_base_mint_funds_only_its_own_cycles() {
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 0.0);
+        let book = CapitalBook::new([(sol_mint(), limits(1_000_000_000, 0)), (usdc_mint(), limits(500_000_000, 0))]);
+        let unbudgeted = round_trip(Pubkey::new_unique(), 100_000_000);
+        let cleared = vec![
+            (round_trip(sol_mint(), 600_000_000), 600_000_000),
+            (round_trip(sol_mint(), 600_000_000), 600_000_000),
+            (round_trip(usdc_mint(), 400_000_000), 400_000_000),
+            (unbudgeted.clone(), 100_000_000),
+        ];
+
+        let (allocations, unfunded) = optimizer.allocate_by_base_mint(cleared, &book, ProfitThreshold::flat(0));
+
+        // The second SOL cycle does not fit the SOL budget, although USDC capital is left over
+        let funded: Vec<(Pubkey, u64)> = allocations.iter().map(|a| (a.cycle.base_mint(), a.amount)).collect();
+        assert_eq!(funded.len(), 2);
+        assert!(funded.contains(&(sol_mint(), 600_000_000)));
+        assert!(funded.contains(&(usdc_mint(), 400_000_000)));
+        assert_eq!(unfunded.len(), 2);
+        assert!(unfunded.iter().any(|cycle| cycle.base_mint() == sol_mint()));
+        assert!(unfunded.iter().any(|cycle| cycle.cycle_id() == unbudgeted.cycle_id()));
+    }
+
+    #[test]
+    fn three_cleared_cycles_share_the_smaller_of_the_cap_and_the_wallet_balance() {
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 0.0);
+        let mut book = CapitalBook::new([(sol_mint(), limits(2_000_000_000, 0))]);
+        book.set_balance(sol_mint(), 700_000_000);
+        let cleared: Vec<(ArbitrageCycle, u64)> = [(400_000_000, 3_000_000), (300_000_000, 2_000_000), (200_000_000, 1_000_000)]
+            .into_iter()
+            .map(|(amount, profit)| {
+                let mut cycle = round_trip(sol_mint(), amount);
+                cycle.estimated_profit_lamports = profit;
+                (cycle, amount)
+            })
+            .collect();
+
+        let (allocations, unfunded) = optimizer.allocate_by_base_mint(cleared, &book, ProfitThreshold::flat(0));
+
+        // The wallet holds less than max_capital, so it is the wallet that runs out
+        let amounts: Vec<u64> = allocations.iter().map(|a| a.amount).collect();
+        assert_eq!(amounts, vec![400_000_000, 300_000_000]);
+        assert!(amounts.iter().sum::<u64>() <= 700_000_000);
+        assert_eq!(unfunded.len(), 1);
+        assert_eq!(unfunded[0].estimated_profit_lamports, 1_000_000);
+    }
+
+    #[test]
+    fn balances_are_read_from_the_base_mint_atas() {
+        let rpc = crate::rpc::MockRpc::new();
+        let wallet = Pubkey::new_unique();
+        let mut book = CapitalBook::new([(sol_mint(), limits(2_000_000_000, 0)), (usdc_mint(), limits(500_000_000, 0))]);
+        assert_eq!(book.budget(&sol_mint()), Some(2_000_000_000));
+
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(sol_mint().as_ref());
+        data[32..64].copy_from_slice(wallet.as_ref());
+        data[64..72].copy_from_slice(&750_000_000u64.to_le_bytes());
+        let ata = solana_sdk::account::Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 };
+        rpc.set_account(get_associated_token_address(&wallet, &sol_mint()), ata);
+        book.refresh_balances(&rpc, &wallet).unwrap();
+
+        assert_eq!(book.budget(&sol_mint()), Some(750_000_000));
+        // No USDC ATA: nothing to commit
+        assert_eq!(book.budget(&usdc_mint()), Some(0));
+        assert_eq!(rpc.batch_sizes(), vec![2]);
+
+        rpc.set_unavailable(true);
+        assert!(book.refresh_balances(&rpc, &wallet).is_err());
+        assert_eq!(book.budget(&sol_mint()), Some(750_000_000));
+    }
+
+    #[test]
+    fn legs_of_the_same_size_slip_per_their_venue_bounds() {
+        let mut optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 0.0);
+        optimizer.set_slippage_bounds(HashMap::from([
+            (DexType::RaydiumV4, SlippageBounds { base_bps: 20, cap_bps: 100 }),
+            (DexType::MeteoraDlmm, SlippageBounds { base_bps: 40, cap_bps: 250 }),
+        ]));
+        let venue_edge = |dex_type, liquidity_usd| {
+            PoolEdge::new(Pubkey::new_unique(), Pubkey::new_unique(), dex_type, 1.0, liquidity_usd, 25, spl_token::id())
+        };
+
+        // Same 1 SOL trade into the same depth
+        let sol_input = optimizer.input_price(&sol_mint(), &Pubkey::new_unique());
+        let amm = optimizer.calculate_slippage_bps(sol_input, 1_000_000_000, &venue_edge(DexType::RaydiumV4, 1_000_000.0));
+        let dlmm = optimizer.calculate_slippage_bps(sol_input, 1_000_000_000, &venue_edge(DexType::MeteoraDlmm, 1_000_000.0));
+        assert_eq!(dlmm, amm + 20);
+        // An unconfigured venue keeps the defaults
+        let whirlpool = optimizer.calculate_slippage_bps(sol_input, 1_000_000_000, &venue_edge(DexType::Whirlpool, 1_000_000.0));
+        assert_eq!(whirlpool, amm - 20 + DEFAULT_SLIPPAGE_BASE_BPS);
+
+        // Into a dust pool each venue stops at its own cap
+        assert_eq!(optimizer.calculate_slippage_bps(sol_input, 1_000_000_000, &venue_edge(DexType::RaydiumV4, 1.0)), 100);
+        assert_eq!(optimizer.calculate_slippage_bps(sol_input, 1_000_000_000, &venue_edge(DexType::MeteoraDlmm, 1.0)), 250);
+    }
+
+    #[test]
+    fn a_usdc_input_slips_like_sol_of_the_same_usd_value() {
+        let graph = Arc::new(PriceGraph::new());
+        graph.set_sol_price(150.0, true);
+        // 1 USDC (1e6 base units) buys 1/150 SOL, 6.67 lamports per base unit
+        let usdc_to_sol = PoolEdge::new(Pubkey::new_unique(), sol_mint(), DexType::RaydiumV4, 1e9 / 150.0 / 1e6, 1e7, 25, spl_token::id());
+        graph.add_edge(usdc_mint(), sol_mint(), usdc_to_sol.clone());
+        let optimizer = AmountOptimizer::new(graph.clone(), 1, 0.0);
+        let depth = PoolEdge::new(Pubkey::new_unique(), Pubkey::new_unique(), DexType::RaydiumV4, 1.0, 1_000_000.0, 25, spl_token::id());
+
+        // $100k of USDC into a $1M pool: a 10% ratio, 5 bps over the base
+        let usdc_in = optimizer.input_price(&usdc_mint(), &depth.to_mint);
+        assert_eq!(optimizer.calculate_slippage_bps(usdc_in, 100_000_000_000, &depth), DEFAULT_SLIPPAGE_BASE_BPS + 5);
+        let sol_in = optimizer.input_price(&sol_mint(), &depth.to_mint);
+        let same_value_in_sol = (100_000.0 / 150.0 * 1e9f64).ceil() as u64;
+        assert_eq!(
+            optimizer.calculate_slippage_bps(usdc_in, 100_000_000_000, &depth),
+            optimizer.calculate_slippage_bps(sol_in, same_value_in_sol, &depth)
+        );
+        assert_eq!(
+            optimizer.effective_fee_bps(&usdc_mint(), &depth, 100_000_000_000),
+            depth.fee_bps + DEFAULT_SLIPPAGE_BASE_BPS + 5
+        );
+
+        // An unpriced intermediate is valued through what the leg pays out: 2 USDC units per token unit
+        let token = Pubkey::new_unique();
+        let token_to_usdc = PoolEdge::new(Pubkey::new_unique(), usdc_mint(), DexType::RaydiumV4, 2.0, 1_000_000.0, 25, spl_token::id());
+        let token_in = optimizer.input_price(&token, &usdc_mint());
+        assert_eq!(optimizer.calculate_slippage_bps(token_in, 50_000_000_000, &token_to_usdc), DEFAULT_SLIPPAGE_BASE_BPS + 5);
+        // With no price on either side the leg slips at the cap
+        let unknown = optimizer.input_price(&token, &Pubkey::new_unique());
+        assert_eq!(optimizer.calculate_slippage_bps(unknown, 1, &depth), DEFAULT_SLIPPAGE_CAP_BPS);
+    }
+
+    fn spl_account(data: Vec<u8>) -> Account {
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    fn vault_account(mint: Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        spl_account(data)
+    }
+
+    fn mint_account(decimals: u8) -> Account {
+        let mut data = vec![0u8; 82];
+        data[44] = decimals;
+        spl_account(data)
+    }
+
+    /// SOL -> `token` -> SOL through two Raydium pools 1 SOL deep, the token
+    /// 2.5% cheaper in the first. `token`'s mint account must already be set.
+    fn raydium_round_trip(rpc: &MockRpc, token: Pubkey, token_program: Pubkey) -> (Arc<PriceGraph>, ArbitrageCycle) {
+        rpc.set_account(sol_mint(), mint_account(9));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), token_program);
+        let mut pools = Vec::new();
+        for token_reserve in [4_100_000_000_000u64, 4_000_000_000_000] {
+            let (pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            rpc.set_account(token_vault, vault_account(token, token_reserve));
+            rpc.set_account(sol_vault, vault_account(sol_mint(), 1_000_000_000));
+            pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+            pools.push(pool);
+        }
+        let graph = Arc::new(PriceGraph::new());
+        graph.update_from_mint_pool_data(&pool_data, rpc);
+        let mut cycle = round_trip(sol_mint(), 0);
+        cycle.legs[0].to_mint = token;
+        cycle.legs[1].from_mint = token;
+        cycle.legs[0].pool_pubkey = pools[0];
+        cycle.legs[1].pool_pubkey = pools[1];
+        (graph, cycle)
+    }
+
+    #[test]
+    fn profit_turns_negative_as_the_trade_approaches_the_reserves() {
+        let rpc = MockRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.set_account(token, mint_account(6));
+        let (graph, cycle) = raydium_round_trip(&rpc, token, spl_token::id());
+        let optimizer = AmountOptimizer::new(graph, 1, 0.0);
+
+        let small = optimizer.expected_profit(&cycle, 1_000_000).expect("a small trade captures the spread");
+        assert!(small > 0);
+        // Each leg walks the curve, so three times the input earns less than three times the profit
+        let larger = optimizer.expected_profit(&cycle, 3_000_000).unwrap();
+        assert!(larger < 3 * small);
+        // Trading the whole reserve moves both pools far past the spread
+        assert_eq!(optimizer.expected_profit(&cycle, 1_000_000_000), None);
+    }
+
+    #[test]
+    fn the_optimized_amount_is_a_profitable_multiple_of_the_granularity() {
+        let rpc = MockRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.set_account(token, mint_account(6));
+        let (graph, mut cycle) = raydium_round_trip(&rpc, token, spl_token::id());
+        let granularity = 7_000_000;
+        let optimizer = AmountOptimizer::new(graph, granularity, 0.0);
+
+        let amount = optimizer
+            .optimize_amount(&mut cycle, &limits(1_000_000_000, 10_000), ProfitThreshold::flat(10))
+            .expect("the spread is worth trading");
+        assert_eq!(amount % granularity, 0, "{}", amount);
+        assert!(optimizer.expected_profit(&cycle, amount).is_some_and(|profit| profit > 10_000));
+        assert_eq!(cycle.legs[0].amount_in, amount);
+    }
+
+    #[test]
+    fn the_optimized_amount_stays_inside_the_configured_band() {
+        let rpc = MockRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.set_account(token, mint_account(6));
+        let (graph, mut cycle) = raydium_round_trip(&rpc, token, spl_token::id());
+        let optimizer = AmountOptimizer::new(graph, 1, 0.0);
+        let banded = |min_input_fraction, max_input_fraction| CapitalLimits {
+            min_input_fraction,
+            max_input_fraction,
+            ..limits(10_000_000, 10_000)
+        };
+
+        // Profit on this pair peaks around 5M lamports, half of the capital, and is gone by 10M
+        for (low, high) in [(0.0, 1.0), (0.3, 0.7), (0.6, 0.9), (0.1, 0.3)] {
+            let amount = optimizer
+                .optimize_amount(&mut cycle, &banded(low, high), ProfitThreshold::flat(10))
+                .unwrap_or_else(|| panic!("nothing sized in {}-{}", low, high));
+            let band = (10_000_000.0 * low) as u64..=(10_000_000.0 * high) as u64;
+            assert!(band.contains(&amount), "{} outside {:?}", amount, band);
+            assert_eq!(cycle.legs[0].amount_in, amount);
+        }
+
+        // Only amounts well past the peak are left, and none of them is profitable
+        assert_eq!(optimizer.optimize_amount(&mut cycle, &banded(0.95, 1.0), ProfitThreshold::flat(10)), None);
+    }
+
+    #[test]
+    fn a_one_percent_transfer_fee_is_charged_on_both_legs() {
+        let rpc = MockRpc::new();
+        let plain = Pubkey::new_unique();
+        rpc.set_account(plain, mint_account(6));
+        let (graph, mut cycle) = raydium_round_trip(&rpc, plain, spl_token::id());
+        AmountOptimizer::new(graph, 1, 0.0).update_leg_amounts(&mut cycle, 1_000_000);
+        let plain_out = cycle.legs[1].estimated_amount_out;
+
+        // Token-2022 mint: base mint padded to 165 bytes, the account type,
+        // then a TransferFeeConfig whose older and newer schedules are 100 bps
+        let taxed = Pubkey::new_unique();
+        let mut data = mint_account(6).data;
+        data.resize(165, 0);
+        data.push(1);
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&108u16.to_le_bytes());
+        let mut config = [0u8; 108];
+        config[88..90].copy_from_slice(&100u16.to_le_bytes());
+        config[106..108].copy_from_slice(&100u16.to_le_bytes());
+        data.extend_from_slice(&config);
+        rpc.set_account(taxed, Account { owner: token_2022_program_id(), ..spl_account(data) });
+        let (graph, mut cycle) = raydium_round_trip(&rpc, taxed, token_2022_program_id());
+        assert_eq!(graph.transfer_fee(&taxed), 100);
+        AmountOptimizer::new(graph, 1, 0.0).update_leg_amounts(&mut cycle, 1_000_000);
+        let taxed_out = cycle.legs[1].estimated_amount_out;
+
+        // Both legs move the token, so about 1% is lost twice
+        let ratio = taxed_out as f64 / plain_out as f64;
+        assert!((0.978..0.981).contains(&ratio), "{} vs {}: {}", taxed_out, plain_out, ratio);
+    }
+
+    #[test]
+    fn a_fee_that_slippage_pushes_past_10000_bps_zeroes_the_leg() {
+        let graph = Arc::new(PriceGraph::new());
+        let mut cycle = round_trip(sol_mint(), 0);
+        let (first, second) = (&cycle.legs[0], &cycle.legs[1]);
+        let mut ruinous = PoolEdge::new(first.pool_pubkey, first.to_mint, DexType::RaydiumV4, 1.2, 1_000_000.0, 9_990, spl_token::id());
+        graph.add_edge(first.from_mint, first.to_mint, ruinous.clone());
+        graph.add_edge(second.from_mint, second.to_mint, edge(second.pool_pubkey));
+        let optimizer = AmountOptimizer::new(graph.clone(), 1, 0.0);
+
+        // 9990 bps plus the 10 bps slippage base reaches the cap, it never goes past it
+        assert_eq!(optimizer.effective_fee_bps(&first.from_mint, &ruinous, 1_000_000_000), 10_000);
+        ruinous.fee_bps = u64::MAX;
+        assert_eq!(optimizer.effective_fee_bps(&first.from_mint, &ruinous, 1_000_000_000), 10_000);
+
+        assert_eq!(optimizer.expected_profit(&cycle, 1_000_000_000), None);
+        optimizer.update_leg_amounts(&mut cycle, 1_000_000_000);
+        assert_eq!(cycle.legs[0].estimated_amount_out, 0);
+        assert_eq!(cycle.legs[1].amount_in, 0);
+        assert_eq!(cycle.legs[1].estimated_amount_out, 0);
+        assert_eq!(cycle.estimated_profit_lamports, 0);
+    }
+
+    #[test]
+    fn the_quote_haircut_is_taken_once_per_leg_of_a_flagged_venue_only() {
+        let graph = Arc::new(PriceGraph::new());
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let venues = [
+            (sol_mint(), a, DexType::Heaven, 100.0),
+            (a, b, DexType::RaydiumV4, 2.0),
+            (b, sol_mint(), DexType::Heaven, 0.0052),
+        ];
+        let mut cycle = cycle(
+            venues
+                .iter()
+                .map(|(from, to, dex_type, price)| {
+                    let pool = Pubkey::new_unique();
+                    graph.add_edge(*from, *to, PoolEdge::new(pool, *to, *dex_type, *price, 1_000_000.0, 25, spl_token::id()));
+                    SwapLeg { from_mint: *from, to_mint: *to, dex_type: *dex_type, program_id: dex_type.program_id(), ..leg(pool, 0, 0) }
+                })
+                .collect(),
+        );
+        let plain = AmountOptimizer::new(graph.clone(), 1, 0.0);
+        let mut haircut = AmountOptimizer::new(graph.clone(), 1, 0.0);
+        haircut.set_quote_haircuts(HashMap::from([(DexType::Heaven, 100)]));
+
+        haircut.update_leg_amounts(&mut cycle, 1_000_000_000);
+        assert_eq!(cycle.legs.iter().map(|leg| leg.quote_haircut_bps).collect::<Vec<_>>(), vec![100, 0, 100]);
+        assert_eq!(cycle.quote_haircut_bps(), 200);
+
+        // Each leg's output is the unhaircut quote of its own input with at most one haircut on top
+        for leg in &cycle.legs {
+            let (_, edge) = graph.neighbors(&leg.from_mint).into_iter().find(|(_, e)| e.pool_pubkey == leg.pool_pubkey).unwrap();
+            let expected = match leg.dex_type {
+                DexType::Heaven => {
+                    let costs_bps = [plain.effective_fee_bps(&leg.from_mint, &edge, leg.amount_in), 100];
+                    edge.quote_with_costs(leg.amount_in, &costs_bps).unwrap().amount_out
+                }
+                _ => plain.quote(&leg.from_mint, &edge, leg.amount_in).unwrap().amount_out,
+            };
+            assert_eq!(leg.estimated_amount_out, expected, "{:?} leg", leg.dex_type);
+        }
+
+        // The simulated profit, and so the threshold check, sees the same haircut outputs
+        let output = cycle.legs[2].estimated_amount_out;
+        assert_eq!(haircut.expected_profit(&cycle, 1_000_000_000), Some(output - 1_000_000_000));
+        assert!(plain.expected_profit(&cycle, 1_000_000_000).unwrap() > output - 1_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn realized_slippage_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("{}_slippage.json", Pubkey::new_unique()));
+        let path = path.to_str().unwrap();
+        let pool = Pubkey::new_unique();
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 1.0);
+        optimizer.record_realized_slippage(pool, 10, 40);
+        optimizer.save_realized_slippage(path).await.unwrap();
+
+        let restarted = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 1.0);
+        assert_eq!(restarted.load_realized_slippage(path).await.unwrap(), 1);
+
+        assert_eq!(restarted.realized_slippage_penalty_bps(&pool), 30);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_cycle_just_below_the_threshold_is_a_near_miss_only_while_recording_is_on() {
+        let rpc = MockRpc::new();
+        let token = Pubkey::new_unique();
+        rpc.set_account(token, mint_account(6));
+        let (graph, mut cycle) = raydium_round_trip(&rpc, token, spl_token::id());
+        let mut optimizer = AmountOptimizer::new(graph, 1, 0.0);
+        let limits = limits(1_000_000_000, 0);
+
+        // Measure the best net profit the pair offers, at the bottom of the band
+        optimizer.set_near_miss_bps(Some(10_000));
+        let net_profit_bps = 10_000 - optimizer.near_miss_shortfall(&mut cycle, &limits, ProfitThreshold::flat(10_000)).unwrap();
+        assert!(net_profit_bps > 0, "{}", net_profit_bps);
+
+        // Five bps more than that is never cleared at any size
+        let threshold = ProfitThreshold::flat(net_profit_bps + 5);
+        assert_eq!(optimizer.optimize_amount(&mut cycle, &limits, threshold), None);
+
+        optimizer.set_near_miss_bps(Some(20));
+        assert_eq!(optimizer.near_miss_shortfall(&mut cycle, &limits, threshold), Some(5));
+        assert_eq!(cycle.legs[0].amount_in, MIN_INPUT_LAMPORTS);
+        // A miss wider than the window is not near
+        optimizer.set_near_miss_bps(Some(4));
+        assert_eq!(optimizer.near_miss_shortfall(&mut cycle, &limits, threshold), None);
+        // With recording off, the same miss is dropped
+        optimizer.set_near_miss_bps(None);
+        assert_eq!(optimizer.near_miss_shortfall(&mut cycle, &limits, threshold), None);
+    }
+}
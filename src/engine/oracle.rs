@@ -0,0 +1,447 @@
+use crate::constants::sol_mint;
+use anyhow::Context;
+use reqwest::Client;
+use serde::Deserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Owner of Pyth pull-oracle `PriceUpdateV2` accounts
+const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// A Pyth update older than this is not used
+const PYTH_MAX_AGE_SECS: i64 = 60;
+
+#[derive(Deserialize, Debug)]
+struct CoinGeckoSimplePrice {
+    solana: CoinGeckoUsd,
+}
+
+#[derive(Deserialize, Debug)]
+struct CoinGeckoUsd {
+    usd: f64,
+}
+
+#[derive(Deserialize, Debug)]
+struct JupiterPrice {
+    #[serde(rename = "usdPrice")]
+    usd_price: f64,
+}
+
+/// Where a SOL/USD price comes from
+pub enum SolPriceSource {
+    Pyth { account: Pubkey, rpc_client: Arc<RpcClient> }, // PriceUpdateV2 account of the SOL/USD feed
+    CoinGecko { url: String },                             // Simple price format
+    Jupiter { url: String },                               // Price API v3 format, keyed by mint
+}
+
+impl SolPriceSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SolPriceSource::Pyth { .. } => "Pyth",
+            SolPriceSource::CoinGecko { .. } => "CoinGecko",
+            SolPriceSource::Jupiter { .. } => "Jupiter",
+        }
+    }
+
+    async fn fetch(&self, http_client: &Client) -> anyhow::Result<f64> {
+        let price = match self {
+            SolPriceSource::Pyth { account, rpc_client } => {
+                let account_data = rpc_client.get_account(account)?;
+                if account_data.owner != PYTH_RECEIVER_PROGRAM_ID {
+                    anyhow::bail!("{} is not a Pyth price update account", account);
+                }
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+                parse_pyth_price_update(&account_data.data, now)?
+            }
+            SolPriceSource::CoinGecko { url } => {
+                let resp: CoinGeckoSimplePrice = http_client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("Failed to parse SOL price response")?;
+                resp.solana.usd
+            }
+            SolPriceSource::Jupiter { url } => {
+                let resp = fetch_jupiter_prices(http_client, url).await?;
+                resp.get(&sol_mint().to_string())
+                    .map(|price| price.usd_price)
+                    .ok_or_else(|| anyhow::anyhow!("Jupiter response has no SOL price"))?
+            }
+        };
+        if !price.is_finite() || price <= 0.0 {
+            anyhow::bail!("Invalid SOL price {}", price);
+        }
+        Ok(price)
+    }
+}
+
+async fn fetch_jupiter_prices(http_client: &Client, url: &str) -> anyhow::Result<HashMap<String, JupiterPrice>> {
+    http_client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Failed to parse Jupiter price response")
+}
+
+/// Price from a Pyth `PriceUpdateV2` account: discriminator, write authority,
+/// a verification level of one or two bytes, then the price message
+fn parse_pyth_price_update(data: &[u8], now: i64) -> anyhow::Result<f64> {
+    let message = match data.get(40) {
+        Some(0) => 42, // Partial { num_signatures: u8 }
+        Some(1) => 41, // Full
+        _ => anyhow::bail!("Unknown Pyth verification level"),
+    };
+    // feed_id [32], price i64, conf u64, exponent i32, publish_time i64
+    if data.len() < message + 68 {
+        anyhow::bail!("Pyth price update too short: {} bytes", data.len());
+    }
+    let read_i64 = |offset: usize| i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let price = read_i64(message + 32);
+    let exponent = i32::from_le_bytes(data[message + 48..message + 52].try_into().unwrap());
+    let publish_time = read_i64(message + 52);
+    if now - publish_time > PYTH_MAX_AGE_SECS {
+        anyhow::bail!("Pyth price is {}s old", now - publish_time);
+    }
+    Ok(price as f64 * 10f64.powi(exponent))
+}
+
+/// SOL/USD price used for liquidity and trade-size estimates.
+///
+/// Until the first live fetch succeeds the oracle serves the configured
+/// bootstrap price and reports it as unconfirmed. A live price is reused
+/// for `ttl` before the next refresh fetches again. Sources are tried in
+/// order; the first valid price wins.
+pub struct SolPriceOracle {
+    http_client: Client,
+    sources: Vec<SolPriceSource>,
+    price_bits: AtomicU64,
+    confirmed: AtomicBool,
+    ttl: Duration,
+    fetched_at: Mutex<Option<Instant>>, // Last successful fetch
+}
+
+impl SolPriceOracle {
+    pub fn new(sources: Vec<SolPriceSource>, bootstrap_price_usd: f64) -> Self {
+        Self {
+            http_client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            sources,
+            price_bits: AtomicU64::new(bootstrap_price_usd.to_bits()),
+            confirmed: AtomicBool::new(false),
+            ttl: Duration::ZERO,
+            fetched_at: Mutex::new(None),
+        }
+    }
+
+    /// Reuse a live price for `ttl` instead of fetching on every refresh
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn price(&self) -> f64 {
+        f64::from_bits(self.price_bits.load(Ordering::Relaxed))
+    }
+
+    /// True once a live price has been fetched
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed.load(Ordering::Relaxed)
+    }
+
+    /// Fetch a live price unless the last one is younger than the TTL. On
+    /// failure the previous price (or the bootstrap price) stays in effect.
+    pub async fn refresh(&self) -> f64 {
+        let fresh = self.fetched_at.lock().unwrap().is_some_and(|at| at.elapsed() < self.ttl);
+        if fresh {
+            return self.price();
+        }
+        match self.fetch().await {
+            Ok((price, source)) => {
+                self.price_bits.store(price.to_bits(), Ordering::Relaxed);
+                *self.fetched_at.lock().unwrap() = Some(Instant::now());
+                if !self.confirmed.swap(true, Ordering::Relaxed) {
+                    info!("💵 SOL price confirmed: ${:.2} from {}", price, source);
+                } else {
+                    debug!("SOL price ${:.2} from {}", price, source);
+                }
+            }
+            Err(e) => {
+                if self.is_confirmed() {
+                    warn!("⚠️ SOL price fetch failed, keeping last price ${:.2}: {}", self.price(), e);
+                } else {
+                    warn!("⚠️ SOL price unavailable, using unconfirmed bootstrap price ${:.2}: {}", self.price(), e);
+                }
+            }
+        }
+        self.price()
+    }
+
+    async fn fetch(&self) -> anyhow::Result<(f64, &'static str)> {
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match source.fetch(&self.http_client).await {
+                Ok(price) => return Ok((price, source.name())),
+                Err(e) => {
+                    debug!("SOL price source {} failed: {}", source.name(), e);
+                    errors.push(format!("{}: {}", source.name(), e));
+                }
+            }
+        }
+        if errors.is_empty() {
+            anyhow::bail!("no price sources configured");
+        }
+        anyhow::bail!("{}", errors.join("; "))
+    }
+}
+
+/// USD prices of the non-SOL quote mints pools are valued against, such as
+/// stablecoins, so a depegged quote is not counted at $1.
+///
+/// Every mint is served at $1 until a fetch returns its price. Prices are
+/// reused for `ttl` like the SOL price; a failed fetch, or a mint missing
+/// from the response, keeps the previous price.
+pub struct QuotePriceOracle {
+    http_client: Client,
+    url: String,
+    mints: Vec<Pubkey>,
+    prices: Mutex<HashMap<Pubkey, f64>>,
+    ttl: Duration,
+    fetched_at: Mutex<Option<Instant>>, // Last successful fetch
+}
+
+impl QuotePriceOracle {
+    pub fn new(url: String, mints: Vec<Pubkey>) -> Self {
+        Self {
+            http_client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            url,
+            prices: Mutex::new(mints.iter().map(|mint| (*mint, 1.0)).collect()),
+            mints,
+            ttl: Duration::ZERO,
+            fetched_at: Mutex::new(None),
+        }
+    }
+
+    /// Reuse fetched prices for `ttl` instead of fetching on every refresh
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn prices(&self) -> HashMap<Pubkey, f64> {
+        self.prices.lock().unwrap().clone()
+    }
+
+    /// Fetch live prices unless the last ones are younger than the TTL
+    pub async fn refresh(&self) -> HashMap<Pubkey, f64> {
+        let fresh = self.fetched_at.lock().unwrap().is_some_and(|at| at.elapsed() < self.ttl);
+        if fresh || self.mints.is_empty() {
+            return self.prices();
+        }
+        let ids: Vec<String> = self.mints.iter().map(|mint| mint.to_string()).collect();
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}ids={}", self.url, separator, ids.join(","));
+        match fetch_jupiter_prices(&self.http_client, &url).await {
+            Ok(resp) => {
+                let mut prices = self.prices.lock().unwrap();
+                for mint in &self.mints {
+                    match resp.get(&mint.to_string()).map(|price| price.usd_price) {
+                        Some(price) if price.is_finite() && price > 0.0 => {
+                            if (price - 1.0).abs() >= 0.01 {
+                                debug!("Quote mint {} priced at ${:.4}", mint, price);
+                            }
+                            prices.insert(*mint, price);
+                        }
+                        price => warn!("⚠️ No valid price for quote mint {} ({:?}), keeping ${:.4}", mint, price, prices[mint]),
+                    }
+                }
+                *self.fetched_at.lock().unwrap() = Some(Instant::now());
+            }
+            Err(e) => warn!("⚠️ Quote price fetch failed, keeping last prices: {}", e),
+        }
+        self.prices()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::usdc_mint;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer every request on loopback with `body` as a Jupiter price
+    /// response. Returns the URL to query.
+    async fn mock_jupiter(body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0u8; 4096];
+                let _ = stream.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}/price/v3", address)
+    }
+
+    #[tokio::test]
+    async fn quote_mints_take_their_fetched_price_and_keep_par_without_one() {
+        let (usdc, usdt) = (usdc_mint(), Pubkey::new_unique());
+        let url = mock_jupiter(format!(r#"{{"{}":{{"usdPrice":0.95}}}}"#, usdc)).await;
+        let oracle = QuotePriceOracle::new(url, vec![usdc, usdt]);
+
+        let prices = oracle.refresh().await;
+        assert_eq!(prices[&usdc], 0.95);
+        assert_eq!(prices[&usdt], 1.0, "a mint missing from the response keeps its last price");
+    }
+
+    /// Serve `{"solana":{"usd":price}}` on loopback and count the requests.
+    /// Returns the URL to query.
+    async fn mock_coingecko(price: f64) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::Relaxed);
+                let _ = stream.read(&mut [0u8; 4096]).await;
+                let body = format!(r#"{{"solana":{{"usd":{}}}}}"#, price);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (format!("http://{}/simple/price", address), requests)
+    }
+
+    #[tokio::test]
+    async fn a_fixed_oracle_price_values_pool_liquidity_and_is_reused_within_the_ttl() {
+        use crate::engine::PriceGraph;
+        use crate::pools::MintPoolData;
+        use crate::rpc::MockRpc;
+        use solana_sdk::account::Account;
+        let (url, requests) = mock_coingecko(150.0).await;
+        let oracle = SolPriceOracle::new(vec![SolPriceSource::CoinGecko { url }], 200.0).with_ttl(Duration::from_secs(60));
+        assert_eq!(oracle.price(), 200.0);
+        assert!(!oracle.is_confirmed());
+
+        assert_eq!(oracle.refresh().await, 150.0);
+        assert!(oracle.is_confirmed());
+        assert_eq!(oracle.refresh().await, 150.0);
+        assert_eq!(requests.load(Ordering::Relaxed), 1, "the second refresh is inside the TTL");
+
+        // A pool holding 10 SOL a side is worth 20 SOL at the oracle's price, not the bootstrap one
+        let spl_account = |data: Vec<u8>| Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 };
+        let token_account = |mint: Pubkey, amount: u64| {
+            let mut data = vec![0u8; 165];
+            data[0..32].copy_from_slice(mint.as_ref());
+            data[64..72].copy_from_slice(&amount.to_le_bytes());
+            spl_account(data)
+        };
+        let mint_account = |decimals: u8| {
+            let mut data = vec![0u8; 82];
+            data[44] = decimals;
+            data[45] = 1;
+            spl_account(data)
+        };
+        let rpc = MockRpc::new();
+        let (token, pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.set_account(token, mint_account(6));
+        rpc.set_account(sol_mint(), mint_account(9));
+        rpc.set_account(token_vault, token_account(token, 40_000_000_000));
+        rpc.set_account(sol_vault, token_account(sol_mint(), 10_000_000_000));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+        let graph = PriceGraph::new();
+        graph.set_sol_price(oracle.price(), oracle.is_confirmed());
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+
+        let (_, edge) = graph.neighbors(&token).into_iter().find(|(_, edge)| edge.pool_pubkey == pool).unwrap();
+        assert!((edge.liquidity_usd - 20.0 * 150.0).abs() < 1e-6, "{}", edge.liquidity_usd);
+    }
+
+    /// `PriceUpdateV2` data with a Full verification level
+    fn pyth_update(price: i64, exponent: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0u8; 41];
+        data[40] = 1;
+        data.extend_from_slice(&[7u8; 32]); // Feed id
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // Confidence
+        data.extend_from_slice(&exponent.to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&(publish_time - 1).to_le_bytes()); // Previous publish time
+        data
+    }
+
+    #[test]
+    fn a_pyth_update_is_read_at_its_exponent_unless_stale() {
+        assert_eq!(parse_pyth_price_update(&pyth_update(15_012_345_678, -8, 1_000), 1_010).unwrap(), 150.12345678);
+        // Partial verification carries a one-byte signature count before the message
+        let mut partial = pyth_update(15_000_000_000, -8, 1_000);
+        partial[40] = 0;
+        partial.insert(41, 3);
+        assert_eq!(parse_pyth_price_update(&partial, 1_000).unwrap(), 150.0);
+        assert!(parse_pyth_price_update(&pyth_update(15_000_000_000, -8, 1_000), 1_000 + PYTH_MAX_AGE_SECS + 1).is_err());
+        assert!(parse_pyth_price_update(&[0u8; 60], 0).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")] // The blocking RpcClient needs it
+    async fn an_unreadable_pyth_account_falls_back_to_the_next_source() {
+        use crate::rpc::serve_accounts;
+        use solana_sdk::account::Account;
+        let account = Pubkey::new_unique();
+        // Owned by the receiver program, but no known verification level
+        let garbage = Account { lamports: 1, data: vec![9u8; 200], owner: PYTH_RECEIVER_PROGRAM_ID, ..Account::default() };
+        let rpc_client = Arc::new(RpcClient::new(serve_accounts(HashMap::from([(account, garbage)]))));
+        let (url, requests) = mock_coingecko(151.5).await;
+        let oracle = SolPriceOracle::new(vec![SolPriceSource::Pyth { account, rpc_client }, SolPriceSource::CoinGecko { url }], 200.0);
+
+        assert_eq!(oracle.refresh().await, 151.5);
+        assert!(oracle.is_confirmed());
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+        assert_eq!(oracle.fetch().await.unwrap().1, "CoinGecko");
+    }
+
+    #[tokio::test]
+    async fn an_expired_price_is_fetched_again_and_a_failed_fetch_keeps_it() {
+        let (url, requests) = mock_coingecko(140.0).await;
+        let oracle = SolPriceOracle::new(vec![SolPriceSource::CoinGecko { url }], 200.0).with_ttl(Duration::from_millis(50));
+        assert_eq!(oracle.refresh().await, 140.0);
+        assert_eq!(oracle.refresh().await, 140.0);
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        oracle.refresh().await;
+        assert_eq!(requests.load(Ordering::Relaxed), 2, "the TTL ran out");
+
+        // With every source down the last live price stays in effect
+        let down = SolPriceOracle::new(vec![SolPriceSource::CoinGecko { url: "http://127.0.0.1:1".to_string() }], 200.0);
+        assert_eq!(down.refresh().await, 200.0);
+        assert!(!down.is_confirmed());
+    }
+}
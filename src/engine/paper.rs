@@ -0,0 +1,237 @@
+use crate::engine::graph::PriceGraph;
+use crate::engine::reconcile::BalanceSnapshot;
+use crate::engine::types::*;
+use crate::ledger::{LedgerRecord, LedgerStatus};
+use crate::units::Usd;
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use tracing::debug;
+
+/// Simulated constant-product reserves for one pool
+#[derive(Debug, Clone)]
+pub struct SimulatedPool {
+    pub mints: (Pubkey, Pubkey),
+    pub reserves: (f64, f64), // Raw units of `mints.0` and `mints.1`
+    pub fee_bps: u64,
+}
+
+impl SimulatedPool {
+    /// Output for `amount_in` of `from_mint`, applied to the reserves
+    fn swap(&mut self, from_mint: &Pubkey, amount_in: f64) -> Option<f64> {
+        let (reserve_in, reserve_out) = if *from_mint == self.mints.0 {
+            (&mut self.reserves.0, &mut self.reserves.1)
+        } else if *from_mint == self.mints.1 {
+            (&mut self.reserves.1, &mut self.reserves.0)
+        } else {
+            return None;
+        };
+        let in_after_fee = amount_in * (10_000 - self.fee_bps.min(10_000)) as f64 / 10_000.0;
+        let amount_out = *reserve_out * in_after_fee / (*reserve_in + in_after_fee);
+        *reserve_in += amount_in; // The fee stays in the pool
+        *reserve_out -= amount_out;
+        Some(amount_out)
+    }
+
+    fn spot_price(&self, from_mint: &Pubkey) -> f64 {
+        if *from_mint == self.mints.0 {
+            self.reserves.1 / self.reserves.0
+        } else {
+            self.reserves.0 / self.reserves.1
+        }
+    }
+}
+
+/// Result of executing one cycle against the simulated market
+#[derive(Debug, Clone)]
+pub struct PaperFill {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub leg_outputs: Vec<u64>,
+}
+
+impl PaperFill {
+    pub fn base_delta(&self) -> i128 {
+        self.amount_out as i128 - self.amount_in as i128
+    }
+
+    /// Wallet balances a live fill of `cycle` would have left: the input
+    /// leaves the start mint, every leg's output arrives in its mint and is
+    /// passed on, and the network fee comes out of native SOL
+    pub fn balance_snapshots(&self, cycle: &ArbitrageCycle, fee_lamports: u64) -> (BalanceSnapshot, BalanceSnapshot) {
+        let mut pre = BalanceSnapshot { lamports: fee_lamports, tokens: HashMap::new() };
+        for leg in &cycle.legs {
+            pre.tokens.insert(leg.from_mint, 0);
+            pre.tokens.insert(leg.to_mint, 0);
+        }
+        if let Some(first) = cycle.legs.first() {
+            pre.tokens.insert(first.from_mint, self.amount_in);
+        }
+
+        let mut post = BalanceSnapshot { lamports: 0, tokens: pre.tokens.clone() };
+        let mut amount_in = self.amount_in;
+        for (leg, amount_out) in cycle.legs.iter().zip(&self.leg_outputs) {
+            let from = post.tokens.entry(leg.from_mint).or_insert(0);
+            *from = from.saturating_sub(amount_in);
+            *post.tokens.entry(leg.to_mint).or_insert(0) += amount_out;
+            amount_in = *amount_out;
+        }
+        (pre, post)
+    }
+
+    /// Ledger record in the same shape as a landed live cycle
+    pub fn ledger_record(&self, sequence: u64, cycle: &ArbitrageCycle, base_mint: &Pubkey, slot: u64, fee_lamports: u64, now_unix: u64) -> LedgerRecord {
+        LedgerRecord {
+            signature: format!("paper-{}", sequence),
+            slot,
+            block_time: Some(now_unix as i64),
+            status: LedgerStatus::Landed,
+            cycle_id: Some(cycle.cycle_id()),
+            venues: cycle.legs.iter().map(|leg| leg.dex_type).collect(),
+            base_mint: base_mint.to_string(),
+            base_delta: self.base_delta(),
+            fee_lamports,
+            token_deltas: BTreeMap::new(),
+            imported: false,
+            paper: true,
+            reconciliation: None,
+            bundle_attempts: Vec::new(),
+            fingerprint: None,
+        }
+    }
+}
+
+/// A market for paper trading. Pools are seeded once from the live graph,
+/// then owned here: executed cycles move their reserves, so repeating the
+/// same arbitrage decays it, and random shocks keep prices moving. The
+/// simulated prices are written back over the graph's edges every pass.
+pub struct MarketSimulator {
+    pools: BTreeMap<Pubkey, SimulatedPool>,
+    shock_probability: f64,
+    shock_bps: f64,
+    rng: StdRng,
+}
+
+impl MarketSimulator {
+    pub fn new(shock_probability: f64, shock_bps: f64, seed: u64) -> Self {
+        Self {
+            pools: BTreeMap::new(),
+            shock_probability: shock_probability.clamp(0.0, 1.0),
+            shock_bps: shock_bps.max(0.0),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn pool(&self, pool: &Pubkey) -> Option<&SimulatedPool> {
+        self.pools.get(pool)
+    }
+
+    pub fn pool_count(&self) -> usize {
+        self.pools.len()
+    }
+
+    /// Take over pools the graph has and the simulator does not. Each side
+    /// gets half of the edge's liquidity, valued through the graph's SOL prices.
+    pub fn seed_from_graph(&mut self, graph: &PriceGraph) {
+        let sol_price_usd = graph.sol_price_usd();
+        if sol_price_usd <= 0.0 {
+            return;
+        }
+        for (from_mint, edges) in graph.sorted_edges() {
+            let Some(from_price_in_sol) = graph.price_in_sol(&from_mint).filter(|p| *p > 0.0) else {
+                continue;
+            };
+            for edge in edges {
+                if self.pools.contains_key(&edge.pool_pubkey) {
+                    continue;
+                }
+                let Ok(side) = Usd(edge.liquidity_usd / 2.0).to_lamports(sol_price_usd) else {
+                    continue;
+                };
+                let side_lamports = side.0 as f64;
+                let reserve_from = side_lamports / from_price_in_sol;
+                let reserve_to = reserve_from * edge.spot_price();
+                if !(reserve_from.is_finite() && reserve_to.is_finite() && reserve_from > 0.0 && reserve_to > 0.0) {
+                    continue;
+                }
+                self.pools.insert(
+                    edge.pool_pubkey,
+                    SimulatedPool {
+                        mints: (from_mint, edge.to_mint),
+                        reserves: (reserve_from, reserve_to),
+                        fee_bps: edge.fee_bps,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Random-walk step: each pool is shocked with `shock_probability`,
+    /// moving its price by up to `shock_bps` either way
+    pub fn shock(&mut self) -> usize {
+        let mut shocked = 0;
+        for pool in self.pools.values_mut() {
+            if self.rng.gen::<f64>() >= self.shock_probability {
+                continue;
+            }
+            // Scaling both sides by sqrt(factor) keeps depth and moves the price by `factor`
+            let factor = (self.rng.gen_range(-1.0..=1.0) * self.shock_bps / 10_000.0).exp().sqrt();
+            pool.reserves.0 *= factor;
+            pool.reserves.1 /= factor;
+            shocked += 1;
+        }
+        shocked
+    }
+
+    /// Overwrite the price of every graph edge backed by a simulated pool
+    pub fn apply_to_graph(&self, graph: &PriceGraph) {
+        for (pool_pubkey, pool) in &self.pools {
+            for from_mint in [pool.mints.0, pool.mints.1] {
+                let edge = graph
+                    .edges
+                    .get(&from_mint)
+                    .and_then(|edges| edges.iter().find(|e| e.pool_pubkey == *pool_pubkey).cloned());
+                if let Some(mut edge) = edge {
+                    edge.price = pool.spot_price(&from_mint);
+                    graph.add_edge(from_mint, edge.to_mint, edge);
+                }
+            }
+        }
+    }
+
+    /// Execute `cycle` with `amount_in` against the simulated reserves.
+    /// Reserves are only changed if every leg's pool is simulated.
+    pub fn execute(&mut self, cycle: &ArbitrageCycle, amount_in: u64) -> Result<PaperFill> {
+        // Legs trade against copies, committed only once the whole cycle filled
+        let mut pools: HashMap<Pubkey, SimulatedPool> = HashMap::new();
+        let mut amount = amount_in as f64;
+        let mut leg_outputs = Vec::with_capacity(cycle.legs.len());
+        for leg in &cycle.legs {
+            let pool = match pools.entry(leg.pool_pubkey) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    self.pools
+                        .get(&leg.pool_pubkey)
+                        .ok_or_else(|| anyhow::anyhow!("pool {} is not simulated", leg.pool_pubkey))?
+                        .clone(),
+                ),
+            };
+            amount = pool
+                .swap(&leg.from_mint, amount)
+                .ok_or_else(|| anyhow::anyhow!("pool {} does not trade {}", leg.pool_pubkey, leg.from_mint))?;
+            leg_outputs.push(amount.max(0.0).floor() as u64);
+        }
+
+        self.pools.extend(pools);
+        let fill = PaperFill {
+            amount_in,
+            amount_out: leg_outputs.last().copied().unwrap_or(0),
+            leg_outputs,
+        };
+        debug!("📝 Paper fill for cycle {:016x}: {} in, {} out", cycle.cycle_id(), fill.amount_in, fill.amount_out);
+        Ok(fill)
+    }
+}
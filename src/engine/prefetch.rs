@@ -0,0 +1,271 @@
+use crate::pools::*;
+use crate::rpc::RpcLike;
+use anyhow::Result;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+
+/// getMultipleAccounts RPC limit
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Accounts read on behalf of one pool during a refresh
+pub trait AccountDependencies {
+    /// Accounts the pricing pass reads: the pool state or its vaults
+    fn primary(&self) -> Vec<Pubkey>;
+
+    /// Configs, observations, tick and bin arrays
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        Vec::new()
+    }
+}
+
+impl AccountDependencies for RaydiumPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_vault, self.sol_vault]
+    }
+}
+
+impl AccountDependencies for RaydiumCpPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_vault, self.sol_vault]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        vec![self.amm_config, self.observation]
+    }
+}
+
+impl AccountDependencies for PumpPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_vault, self.sol_vault]
+    }
+}
+
+impl AccountDependencies for DlmmPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.pair]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        std::iter::once(self.oracle).chain(self.bin_arrays.iter().copied()).collect()
+    }
+}
+
+impl AccountDependencies for WhirlpoolPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.pool]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        std::iter::once(self.oracle).chain(self.tick_arrays.iter().copied()).collect()
+    }
+}
+
+/// Raydium CLMM and its forks (PancakeSwap, Byreal) share the same layout
+fn clmm_auxiliary(amm_config: Pubkey, observation_state: Pubkey, bitmap_extension: Pubkey, tick_arrays: &[Pubkey]) -> Vec<Pubkey> {
+    [amm_config, observation_state, bitmap_extension]
+        .into_iter()
+        .chain(tick_arrays.iter().copied())
+        .collect()
+}
+
+impl AccountDependencies for RaydiumClmmPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.pool]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        clmm_auxiliary(self.amm_config, self.observation_state, self.bitmap_extension, &self.tick_arrays)
+    }
+}
+
+impl AccountDependencies for PancakeswapPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.pool]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        clmm_auxiliary(self.amm_config, self.observation_state, self.bitmap_extension, &self.tick_arrays)
+    }
+}
+
+impl AccountDependencies for ByrealPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.pool]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        clmm_auxiliary(self.amm_config, self.observation_state, self.bitmap_extension, &self.tick_arrays)
+    }
+}
+
+impl AccountDependencies for MeteoraDAmmPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![
+            self.pool,
+            self.token_x_vault,
+            self.token_sol_vault,
+            self.token_x_pool_lp,
+            self.token_sol_pool_lp,
+            self.token_x_lp_mint,
+            self.token_sol_lp_mint,
+        ]
+    }
+}
+
+impl AccountDependencies for MeteoraDAmmV2Pool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+}
+
+impl AccountDependencies for VertigoPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+}
+
+impl AccountDependencies for HeavenPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.pool]
+    }
+
+    fn auxiliary(&self) -> Vec<Pubkey> {
+        vec![self.protocol_config]
+    }
+}
+
+impl AccountDependencies for FutarchyPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+}
+
+impl AccountDependencies for HumidifiPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+}
+
+impl AccountDependencies for LifinityPool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+}
+
+impl AccountDependencies for PhoenixMarket {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.market]
+    }
+}
+
+impl AccountDependencies for PumpBondingCurvePool {
+    fn primary(&self) -> Vec<Pubkey> {
+        vec![self.curve]
+    }
+}
+
+/// Accounts fetched in one batched pass, served to the graph before it falls
+/// back to individual RPC reads
+#[derive(Debug, Default)]
+pub struct PrefetchedAccounts {
+    pub accounts: HashMap<Pubkey, Account>,
+    pub slots: HashMap<Pubkey, Slot>, // Context slot of the batch each account came from
+    pub requested: usize, // Dependencies before deduplication
+    pub missing: Vec<Pubkey>, // Requested accounts that do not exist
+    pub rpc_calls: usize,
+}
+
+impl PrefetchedAccounts {
+    /// Add accounts served from another source, keeping their slots
+    pub fn merge(&mut self, other: PrefetchedAccounts) {
+        self.accounts.extend(other.accounts);
+        self.slots.extend(other.slots);
+    }
+}
+
+/// Collects the accounts every pool of a refresh depends on, deduplicates
+/// them and fetches them with as few getMultipleAccounts calls as possible.
+#[derive(Debug, Default)]
+pub struct PrefetchPlanner {
+    include_auxiliary: bool,
+    accounts: BTreeSet<Pubkey>,
+    requested: usize,
+}
+
+impl PrefetchPlanner {
+    pub fn new(include_auxiliary: bool) -> Self {
+        Self {
+            include_auxiliary,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_pool(&mut self, pool: &impl AccountDependencies) {
+        let mut dependencies = pool.primary();
+        if self.include_auxiliary {
+            dependencies.extend(pool.auxiliary());
+        }
+        self.requested += dependencies.len();
+        self.accounts.extend(dependencies.into_iter().filter(|key| *key != Pubkey::default()));
+    }
+
+    fn add_pools<P: AccountDependencies>(&mut self, pools: &[P]) {
+        for pool in pools {
+            self.add_pool(pool);
+        }
+    }
+
+    pub fn add_mint(&mut self, pool_data: &MintPoolData) {
+        self.add_pools(&pool_data.raydium_pools);
+        self.add_pools(&pool_data.raydium_cp_pools);
+        self.add_pools(&pool_data.pump_pools);
+        self.add_pools(&pool_data.dlmm_pairs);
+        self.add_pools(&pool_data.whirlpool_pools);
+        self.add_pools(&pool_data.raydium_clmm_pools);
+        self.add_pools(&pool_data.meteora_damm_pools);
+        self.add_pools(&pool_data.meteora_damm_v2_pools);
+        self.add_pools(&pool_data.vertigo_pools);
+        self.add_pools(&pool_data.heaven_pools);
+        self.add_pools(&pool_data.futarchy_pools);
+        self.add_pools(&pool_data.humidifi_pools);
+        self.add_pools(&pool_data.pancakeswap_pools);
+        self.add_pools(&pool_data.byreal_pools);
+        self.add_pools(&pool_data.lifinity_pools);
+        self.add_pools(&pool_data.phoenix_markets);
+        self.add_pools(&pool_data.pump_bonding_curves);
+    }
+
+    pub fn accounts(&self) -> &BTreeSet<Pubkey> {
+        &self.accounts
+    }
+
+    /// Drop accounts already served from elsewhere, e.g. the account stream
+    pub fn retain_accounts(&mut self, keep: impl Fn(&Pubkey) -> bool) {
+        self.accounts.retain(|key| keep(key));
+    }
+
+    pub fn fetch(&self, rpc_client: &dyn RpcLike) -> Result<PrefetchedAccounts> {
+        let keys: Vec<Pubkey> = self.accounts.iter().copied().collect();
+        let mut prefetched = PrefetchedAccounts {
+            requested: self.requested,
+            ..Default::default()
+        };
+
+        for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = rpc_client.get_multiple_accounts_with_commitment(chunk, rpc_client.commitment())?;
+            prefetched.rpc_calls += 1;
+            for (key, account) in chunk.iter().zip(response.value) {
+                match account {
+                    Some(account) => {
+                        prefetched.accounts.insert(*key, account);
+                        prefetched.slots.insert(*key, response.context.slot);
+                    }
+                    None => prefetched.missing.push(*key),
+                }
+            }
+        }
+        Ok(prefetched)
+    }
+}
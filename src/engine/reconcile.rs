@@ -0,0 +1,524 @@
+use crate::constants::sol_mint;
+use crate::engine::graph::PriceGraph;
+use crate::engine::types::*;
+use crate::persist::{atomic_write_json, read_to_string};
+use crate::rpc::RpcLike;
+use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use tracing::{error, info, warn};
+
+/// Wallet balances captured immediately before and after sending a cycle
+#[derive(Debug, Clone, Default)]
+pub struct BalanceSnapshot {
+    pub lamports: u64,
+    pub tokens: HashMap<Pubkey, u64>, // Key: mint, Value: raw token amount
+}
+
+impl BalanceSnapshot {
+    /// Snapshot native SOL plus the given `(mint, token_account)` pairs in
+    /// one batched read. A token account that does not exist yet holds zero;
+    /// a failed read or an account that is not a token account of its mint
+    /// is an error, never a zero balance.
+    pub fn capture(
+        rpc_client: &dyn RpcLike,
+        wallet: &Pubkey,
+        token_accounts: &[(Pubkey, Pubkey)],
+    ) -> anyhow::Result<Self> {
+        let mut keys = vec![*wallet];
+        keys.extend(token_accounts.iter().map(|(_, account)| *account));
+        // Confirmed, so a snapshot taken right after a trade already sees it
+        let accounts = rpc_client.get_multiple_accounts_with_commitment(&keys, CommitmentConfig::confirmed())?.value;
+        if accounts.len() != keys.len() {
+            anyhow::bail!("Balance snapshot asked for {} accounts, got {}", keys.len(), accounts.len());
+        }
+
+        let lamports = accounts[0].as_ref().map_or(0, |account| account.lamports);
+        let mut tokens = HashMap::new();
+        for ((mint, address), account) in token_accounts.iter().zip(&accounts[1..]) {
+            let amount = match account {
+                None => 0,
+                Some(account) if account.data.len() >= 72 && account.data[0..32] == mint.to_bytes() => {
+                    u64::from_le_bytes(account.data[64..72].try_into().unwrap())
+                }
+                Some(_) => anyhow::bail!("{} is not a token account of mint {}", address, mint),
+            };
+            tokens.insert(*mint, amount);
+        }
+        Ok(Self { lamports, tokens })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationSeverity {
+    Clean,       // Every movement matched the cycle within tolerance
+    Slippage,    // Only predicted mints moved, but worse than expected
+    Unexplained, // A mint moved that the cycle never touches, or a loss beyond fees + tolerance
+}
+
+#[derive(Debug, Clone)]
+pub struct MintDiscrepancy {
+    pub mint: Pubkey,
+    pub expected_delta: Option<i128>, // None if the cycle should not move this mint
+    pub actual_delta: i128,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationReport {
+    pub severity: ReconciliationSeverity,
+    pub discrepancies: Vec<MintDiscrepancy>,
+    pub expected_fee_lamports: u64,
+    pub actual_lamport_delta: i128,
+    pub unexplained_loss_lamports: u64,
+    pub valuation: Option<ProfitValuation>, // Ending balances in SOL terms, once valued against the graph
+}
+
+impl ReconciliationReport {
+    pub fn should_trip_kill_switch(&self, max_unexplained_loss_lamports: u64) -> bool {
+        self.severity == ReconciliationSeverity::Unexplained
+            && self.unexplained_loss_lamports > max_unexplained_loss_lamports
+    }
+
+    /// The report in the shape the ledger and the journal store
+    pub fn summary(&self) -> ReconciliationSummary {
+        ReconciliationSummary {
+            severity: self.severity,
+            unexplained_loss_lamports: self.unexplained_loss_lamports,
+            actual_lamport_delta: self.actual_lamport_delta,
+            net_profit_sol_lamports: self.valuation.as_ref().map(|v| v.net_profit_lamports),
+            stranded_mints: self
+                .valuation
+                .iter()
+                .flat_map(|v| v.stranded.iter().filter(|s| s.raw_delta > 0))
+                .map(|s| s.mint.to_string())
+                .collect(),
+            discrepancies: self
+                .discrepancies
+                .iter()
+                .map(|d| DiscrepancySummary {
+                    mint: d.mint.to_string(),
+                    expected_delta: d.expected_delta,
+                    actual_delta: d.actual_delta,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn log(&self) {
+        match self.severity {
+            ReconciliationSeverity::Clean => {
+                info!("✅ Reconciliation clean: lamport delta {}", self.actual_lamport_delta);
+            }
+            ReconciliationSeverity::Slippage => {
+                warn!(
+                    "⚠️ Reconciliation slippage on {} mint(s), lamport delta {}",
+                    self.discrepancies.len(),
+                    self.actual_lamport_delta
+                );
+            }
+            ReconciliationSeverity::Unexplained => {
+                error!(
+                    "🚨 Unexplained balance movement: {} lamports unaccounted for",
+                    self.unexplained_loss_lamports
+                );
+                for d in &self.discrepancies {
+                    error!(
+                        "🚨   mint {}: expected {:?}, actual {}",
+                        d.mint, d.expected_delta, d.actual_delta
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Serializable form of a `ReconciliationReport`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationSummary {
+    pub severity: ReconciliationSeverity,
+    pub unexplained_loss_lamports: u64,
+    pub actual_lamport_delta: i128,
+    #[serde(default)]
+    pub net_profit_sol_lamports: Option<i128>, // Native + wSOL + priced token residue
+    #[serde(default)]
+    pub stranded_mints: Vec<String>, // Non-SOL mints the cycle left a positive balance in
+    pub discrepancies: Vec<DiscrepancySummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscrepancySummary {
+    pub mint: String,
+    pub expected_delta: Option<i128>,
+    pub actual_delta: i128,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KillSwitchState {
+    tripped_at: u64,
+    reason: String,
+}
+
+/// Stops every execution once a reconciliation finds an unexplained loss.
+/// The trip is written to `path`, so a restarted bot stays halted until an
+/// operator has looked at the wallet and deleted the file.
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitch {
+    path: Option<String>,
+    state: Option<KillSwitchState>,
+}
+
+impl KillSwitch {
+    pub async fn load(path: &str) -> anyhow::Result<Self> {
+        let state = if Path::new(path).exists() {
+            let content = read_to_string(path).await?;
+            Some(serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid kill switch file {}: {}", path, e))?)
+        } else {
+            None
+        };
+        Ok(Self { path: Some(path.to_string()), state })
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.state.is_some()
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.state.as_ref().map(|state| state.reason.as_str())
+    }
+
+    /// Halt execution. The switch is tripped even if it cannot be persisted.
+    pub async fn trip(&mut self, reason: String, now_unix: u64) -> anyhow::Result<()> {
+        error!("🛑 Kill switch tripped: {}", reason);
+        let state = self.state.insert(KillSwitchState { tripped_at: now_unix, reason });
+        match &self.path {
+            Some(path) => atomic_write_json(path, state).await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// A non-SOL balance a cycle left behind, valued in SOL terms
+#[derive(Debug, Clone)]
+pub struct StrandedBalance {
+    pub mint: Pubkey,
+    pub raw_delta: i128,
+    pub value_lamports: Option<i128>, // None when the graph has no SOL price for the mint
+}
+
+/// Cycle result with every ending balance normalized to lamports
+#[derive(Debug, Clone)]
+pub struct ProfitValuation {
+    pub lamport_delta: i128,
+    pub wsol_delta: i128,
+    pub stranded: Vec<StrandedBalance>,
+    pub net_profit_lamports: i128, // Native + wSOL + priced token residue
+}
+
+impl ProfitValuation {
+    /// Profit ended up in a token other than SOL. The executor holds its base
+    /// as wSOL, so wSOL counts as SOL and is never flagged.
+    pub fn is_flagged(&self) -> bool {
+        self.stranded.iter().any(|s| s.raw_delta > 0)
+    }
+
+    pub fn log(&self) {
+        for s in self.stranded.iter().filter(|s| s.raw_delta > 0) {
+            match s.value_lamports {
+                Some(value) => warn!("⚠️ Profit stranded in {}: {} raw units (~{} lamports)", s.mint, s.raw_delta, value),
+                None => warn!("⚠️ Profit stranded in {}: {} raw units (no SOL price)", s.mint, s.raw_delta),
+            }
+        }
+        info!("📒 Net profit in SOL terms: {} lamports", self.net_profit_lamports);
+    }
+}
+
+/// Value the change between two snapshots in lamports. wSOL counts 1:1;
+/// other mints are valued with `PriceGraph::price_in_sol`, so dust left in an
+/// intermediate token isn't mistaken for (or hidden from) the cycle's profit.
+pub fn value_in_sol(pre: &BalanceSnapshot, post: &BalanceSnapshot, graph: &PriceGraph) -> ProfitValuation {
+    let sol = sol_mint();
+    let lamport_delta = post.lamports as i128 - pre.lamports as i128;
+    let mut wsol_delta = 0;
+    let mut stranded = Vec::new();
+
+    let mints: BTreeSet<Pubkey> = pre.tokens.keys().chain(post.tokens.keys()).copied().collect();
+    for mint in mints {
+        let raw_delta = *post.tokens.get(&mint).unwrap_or(&0) as i128 - *pre.tokens.get(&mint).unwrap_or(&0) as i128;
+        if raw_delta == 0 {
+            continue;
+        }
+        if mint == sol {
+            wsol_delta = raw_delta;
+            continue;
+        }
+        let value_lamports = graph.price_in_sol(&mint).map(|price| (raw_delta as f64 * price) as i128);
+        stranded.push(StrandedBalance { mint, raw_delta, value_lamports });
+    }
+
+    let token_value: i128 = stranded.iter().filter_map(|s| s.value_lamports).sum();
+    ProfitValuation {
+        lamport_delta,
+        wsol_delta,
+        net_profit_lamports: lamport_delta + wsol_delta + token_value,
+        stranded,
+    }
+}
+
+/// Expected per-mint deltas for a cycle: the start mint gains the estimated
+/// profit and every intermediate mint nets to zero.
+pub fn expected_deltas(cycle: &ArbitrageCycle) -> HashMap<Pubkey, i128> {
+    let mut deltas = HashMap::new();
+    for leg in &cycle.legs {
+        deltas.entry(leg.from_mint).or_insert(0);
+        deltas.entry(leg.to_mint).or_insert(0);
+    }
+    if let Some(first) = cycle.legs.first() {
+        deltas.insert(first.from_mint, cycle.estimated_profit_lamports as i128);
+    }
+    deltas
+}
+
+/// Compare actual balance movements against what the cycle predicted.
+///
+/// `tolerance_lamports` applies to both the start-mint shortfall and native
+/// SOL spent beyond `expected_fee_lamports`.
+pub fn reconcile(
+    pre: &BalanceSnapshot,
+    post: &BalanceSnapshot,
+    cycle: &ArbitrageCycle,
+    expected_fee_lamports: u64,
+    tolerance_lamports: u64,
+) -> ReconciliationReport {
+    let expected = expected_deltas(cycle);
+    let start_mint = cycle.legs.first().map(|leg| leg.from_mint);
+    let tolerance = tolerance_lamports as i128;
+
+    let mut severity = ReconciliationSeverity::Clean;
+    let mut discrepancies = Vec::new();
+    let mut unexplained_loss: i128 = 0;
+
+    let mints: BTreeSet<Pubkey> = pre.tokens.keys().chain(post.tokens.keys()).copied().collect();
+    for mint in mints {
+        let before = *pre.tokens.get(&mint).unwrap_or(&0) as i128;
+        let after = *post.tokens.get(&mint).unwrap_or(&0) as i128;
+        let actual_delta = after - before;
+
+        match expected.get(&mint) {
+            None if actual_delta != 0 => {
+                severity = ReconciliationSeverity::Unexplained;
+                if actual_delta < 0 {
+                    unexplained_loss += -actual_delta;
+                }
+                discrepancies.push(MintDiscrepancy { mint, expected_delta: None, actual_delta });
+            }
+            None => {}
+            Some(&expected_delta) => {
+                let shortfall = expected_delta - actual_delta;
+                if shortfall <= 0 {
+                    continue;
+                }
+                discrepancies.push(MintDiscrepancy {
+                    mint,
+                    expected_delta: Some(expected_delta),
+                    actual_delta,
+                });
+                // Only the start mint is denominated in lamports; a net loss there
+                // beyond the tolerance is more than slippage can explain.
+                if Some(mint) == start_mint && actual_delta < -tolerance {
+                    severity = ReconciliationSeverity::Unexplained;
+                    unexplained_loss += -actual_delta - tolerance;
+                } else if severity < ReconciliationSeverity::Slippage {
+                    severity = ReconciliationSeverity::Slippage;
+                }
+            }
+        }
+    }
+
+    let actual_lamport_delta = post.lamports as i128 - pre.lamports as i128;
+    let lamport_overspend = -actual_lamport_delta - expected_fee_lamports as i128;
+    if lamport_overspend > tolerance {
+        severity = ReconciliationSeverity::Unexplained;
+        unexplained_loss += lamport_overspend - tolerance;
+    }
+
+    ReconciliationReport {
+        severity,
+        discrepancies,
+        expected_fee_lamports,
+        actual_lamport_delta,
+        unexplained_loss_lamports: unexplained_loss.max(0) as u64,
+        valuation: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::MockRpc;
+    use solana_sdk::account::Account;
+
+    fn leg(from_mint: Pubkey, to_mint: Pubkey) -> SwapLeg {
+        SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        }
+    }
+
+    /// SOL -> token -> SOL, expected to make `profit` lamports
+    fn round_trip(token: Pubkey, profit: u64) -> ArbitrageCycle {
+        ArbitrageCycle {
+            legs: vec![leg(sol_mint(), token), leg(token, sol_mint())],
+            total_profit_bps: 0,
+            estimated_profit_lamports: profit,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn snapshot(lamports: u64, tokens: &[(Pubkey, u64)]) -> BalanceSnapshot {
+        BalanceSnapshot { lamports, tokens: tokens.iter().copied().collect() }
+    }
+
+    #[test]
+    fn matching_deltas_reconcile_clean() {
+        let token = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let pre = snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0)]);
+        let post = snapshot(9_995_000, &[(sol_mint(), 1_000_050_000), (token, 0)]);
+
+        let report = reconcile(&pre, &post, &cycle, 5_000, 10_000);
+
+        assert_eq!(report.severity, ReconciliationSeverity::Clean);
+        assert!(report.discrepancies.is_empty());
+        assert!(!report.should_trip_kill_switch(0));
+    }
+
+    #[test]
+    fn a_smaller_profit_is_slippage() {
+        let token = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let pre = snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0)]);
+        let post = snapshot(9_995_000, &[(sol_mint(), 1_000_020_000), (token, 0)]);
+
+        let report = reconcile(&pre, &post, &cycle, 5_000, 10_000);
+
+        assert_eq!(report.severity, ReconciliationSeverity::Slippage);
+        assert_eq!(report.discrepancies.len(), 1);
+        assert_eq!(report.discrepancies[0].actual_delta, 20_000);
+        assert_eq!(report.unexplained_loss_lamports, 0);
+        assert!(!report.should_trip_kill_switch(0));
+    }
+
+    #[test]
+    fn an_unpredicted_mint_leaving_the_wallet_trips_the_kill_switch() {
+        let token = Pubkey::new_unique();
+        let bystander = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let pre = snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0), (bystander, 5_000_000)]);
+        let post = snapshot(9_995_000, &[(sol_mint(), 1_000_050_000), (token, 0), (bystander, 0)]);
+
+        let report = reconcile(&pre, &post, &cycle, 5_000, 10_000);
+
+        assert_eq!(report.severity, ReconciliationSeverity::Unexplained);
+        assert_eq!(report.discrepancies[0].mint, bystander);
+        assert_eq!(report.discrepancies[0].expected_delta, None);
+        assert_eq!(report.unexplained_loss_lamports, 5_000_000);
+        assert!(report.should_trip_kill_switch(1_000_000));
+        assert!(!report.should_trip_kill_switch(5_000_000));
+    }
+
+    #[test]
+    fn a_base_loss_beyond_the_tolerance_is_unexplained() {
+        let token = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let pre = snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0)]);
+        let post = snapshot(9_995_000, &[(sol_mint(), 998_000_000), (token, 0)]);
+
+        let report = reconcile(&pre, &post, &cycle, 5_000, 10_000);
+
+        assert_eq!(report.severity, ReconciliationSeverity::Unexplained);
+        assert_eq!(report.unexplained_loss_lamports, 2_000_000 - 10_000);
+        assert!(report.should_trip_kill_switch(1_000_000));
+        assert_eq!(report.summary().severity, ReconciliationSeverity::Unexplained);
+    }
+
+    fn token_account(mint: &Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account { lamports: 2_039_280, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn capture_reads_missing_token_accounts_as_zero() {
+        let (wallet, token, wsol_account, token_account_key) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        rpc.set_account(wallet, Account { lamports: 7_000_000, ..Account::default() });
+        rpc.set_account(wsol_account, token_account(&sol_mint(), 1_500));
+
+        let snapshot = BalanceSnapshot::capture(&rpc, &wallet, &[(sol_mint(), wsol_account), (token, token_account_key)]).unwrap();
+
+        assert_eq!(snapshot.lamports, 7_000_000);
+        assert_eq!(snapshot.tokens[&sol_mint()], 1_500);
+        assert_eq!(snapshot.tokens[&token], 0);
+        assert_eq!(rpc.calls(), 1);
+    }
+
+    #[test]
+    fn an_unpriced_stranded_token_is_flagged_without_a_value() {
+        let (priced, unpriced) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let graph = PriceGraph::new();
+        graph.add_edge(priced, sol_mint(), PoolEdge::new(Pubkey::new_unique(), sol_mint(), DexType::RaydiumV4, 2.0, 1e6, 25, spl_token::id()));
+        let pre = snapshot(1_000_000, &[(sol_mint(), 0), (priced, 100), (unpriced, 0)]);
+        let post = snapshot(995_000, &[(sol_mint(), 20_000), (priced, 0), (unpriced, 7)]);
+
+        let valuation = value_in_sol(&pre, &post, &graph);
+
+        // Spending the priced token costs its value; the unpriced residue adds nothing
+        assert_eq!(valuation.net_profit_lamports, -5_000 + 20_000 - 200);
+        assert!(valuation.is_flagged());
+        let stranded: Vec<(Pubkey, Option<i128>)> = valuation.stranded.iter().map(|s| (s.mint, s.value_lamports)).collect();
+        assert!(stranded.contains(&(priced, Some(-200))));
+        assert!(stranded.contains(&(unpriced, None)));
+    }
+
+    #[test]
+    fn capture_fails_instead_of_reading_zero() {
+        let (wallet, token, account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = MockRpc::new();
+        rpc.set_account(account, token_account(&token, 1_500));
+
+        rpc.set_unavailable(true);
+        assert!(BalanceSnapshot::capture(&rpc, &wallet, &[(token, account)]).is_err());
+
+        // An account of another mint is not this mint's balance
+        rpc.set_unavailable(false);
+        assert!(BalanceSnapshot::capture(&rpc, &wallet, &[(sol_mint(), account)]).is_err());
+    }
+
+    #[tokio::test]
+    async fn a_tripped_kill_switch_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("kill_switch_{}.json", Pubkey::new_unique()));
+        let path = path.to_str().unwrap();
+
+        let mut kill_switch = KillSwitch::load(path).await.unwrap();
+        assert!(!kill_switch.is_tripped());
+        kill_switch.trip("unexplained loss".to_string(), 1_700_000_000).await.unwrap();
+
+        let reloaded = KillSwitch::load(path).await.unwrap();
+        assert_eq!(reloaded.reason(), Some("unexplained loss"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use crate::engine::types::*;
+use crate::ledger::{LedgerRecord, LedgerStatus};
+use std::collections::{HashMap, VecDeque};
+use tracing::info;
+
+/// Recent execution outcomes per venue. Once a venue's success rate over the
+/// window falls below `min_success_rate`, cycles through it are penalized or
+/// filtered.
+pub struct DexReliability {
+    window: usize,
+    min_samples: usize,
+    min_success_rate: f64,
+    penalty_bps: u64,
+    outcomes: HashMap<DexType, VecDeque<bool>>,
+}
+
+impl DexReliability {
+    pub fn new(window: usize, min_samples: usize, min_success_rate: f64, penalty_bps: u64) -> Self {
+        Self {
+            window: window.max(1),
+            min_samples: min_samples.max(1),
+            min_success_rate,
+            penalty_bps: penalty_bps.min(10_000),
+            outcomes: HashMap::new(),
+        }
+    }
+
+    /// Record one attempt through every distinct venue of `venues`
+    pub fn record(&mut self, venues: impl IntoIterator<Item = DexType>, success: bool) {
+        let mut venues: Vec<DexType> = venues.into_iter().collect();
+        venues.sort();
+        venues.dedup();
+        for dex_type in venues {
+            let outcomes = self.outcomes.entry(dex_type).or_default();
+            outcomes.push_back(success);
+            if outcomes.len() > self.window {
+                outcomes.pop_front();
+            }
+        }
+    }
+
+    /// Seed the window from landed and failed cycles in the ledger
+    pub fn record_ledger(&mut self, records: &[LedgerRecord]) {
+        for record in records {
+            self.record(record.venues.iter().copied(), record.status == LedgerStatus::Landed);
+        }
+    }
+
+    /// Success rate over the window, once it holds `min_samples` outcomes
+    pub fn success_rate(&self, dex_type: DexType) -> Option<f64> {
+        let outcomes = self.outcomes.get(&dex_type)?;
+        if outcomes.len() < self.min_samples {
+            return None;
+        }
+        Some(outcomes.iter().filter(|success| **success).count() as f64 / outcomes.len() as f64)
+    }
+
+    pub fn is_unreliable(&self, dex_type: DexType) -> bool {
+        self.success_rate(dex_type).is_some_and(|rate| rate < self.min_success_rate)
+    }
+
+    /// Venues of `cycle` currently below the success-rate threshold
+    pub fn unreliable_venues(&self, cycle: &ArbitrageCycle) -> Vec<DexType> {
+        let mut venues: Vec<DexType> = cycle
+            .legs
+            .iter()
+            .map(|leg| leg.dex_type)
+            .filter(|dex_type| self.is_unreliable(*dex_type))
+            .collect();
+        venues.sort();
+        venues.dedup();
+        venues
+    }
+
+    /// Priority penalty for `cycle`: `penalty_bps` per unreliable venue
+    pub fn penalty_bps(&self, cycle: &ArbitrageCycle) -> u64 {
+        (self.unreliable_venues(cycle).len() as u64 * self.penalty_bps).min(10_000)
+    }
+
+    pub fn log(&self) {
+        let mut venues: Vec<_> = self.outcomes.keys().copied().collect();
+        venues.sort();
+        for dex_type in venues {
+            if let Some(rate) = self.success_rate(dex_type) {
+                let marker = if rate < self.min_success_rate { "⚠️" } else { "✅" };
+                info!("{} {:?}: {:.0}% success over the last {} attempts", marker, dex_type, rate * 100.0, self.outcomes[&dex_type].len());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::optimize::{AmountOptimizer, CapitalLimits};
+    use crate::engine::PriceGraph;
+    use solana_sdk::pubkey::Pubkey;
+    use std::sync::Arc;
+
+    fn cycle(dex_type: DexType, estimated_profit_lamports: u64) -> ArbitrageCycle {
+        let (base, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type,
+            program_id: dex_type.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg(base, token), leg(token, base)],
+            total_profit_bps: 0,
+            estimated_profit_lamports,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    #[test]
+    fn a_venue_needs_min_samples_before_it_is_judged() {
+        let mut reliability = DexReliability::new(10, 4, 0.5, 5_000);
+        reliability.record([DexType::Heaven, DexType::Heaven], false);
+        reliability.record([DexType::Heaven], false);
+        assert_eq!(reliability.success_rate(DexType::Heaven), None, "duplicate venues count once per attempt");
+        reliability.record([DexType::Heaven], true);
+        reliability.record([DexType::Heaven], true);
+        assert_eq!(reliability.success_rate(DexType::Heaven), Some(0.5));
+        assert!(!reliability.is_unreliable(DexType::Heaven));
+    }
+
+    #[test]
+    fn only_the_window_of_recent_attempts_counts() {
+        let mut reliability = DexReliability::new(4, 4, 0.5, 5_000);
+        for _ in 0..10 {
+            reliability.record([DexType::Heaven], false);
+        }
+        assert!(reliability.is_unreliable(DexType::Heaven));
+        for _ in 0..3 {
+            reliability.record([DexType::Heaven], true);
+        }
+        assert_eq!(reliability.success_rate(DexType::Heaven), Some(0.75));
+        assert!(!reliability.is_unreliable(DexType::Heaven));
+    }
+
+    #[test]
+    fn a_low_success_venue_is_deprioritized_for_capital() {
+        let mut reliability = DexReliability::new(20, 10, 0.5, 5_000);
+        for attempt in 0..10 {
+            reliability.record([DexType::Heaven], attempt < 2);
+            reliability.record([DexType::RaydiumV4], attempt < 9);
+        }
+        assert!(reliability.is_unreliable(DexType::Heaven));
+        assert!(!reliability.is_unreliable(DexType::RaydiumV4));
+
+        // The flaky venue quotes more, but after its penalty the reliable one is worth more
+        let mut flaky = cycle(DexType::Heaven, 1_500);
+        let mut steady = cycle(DexType::RaydiumV4, 1_000);
+        assert_eq!(reliability.unreliable_venues(&flaky), vec![DexType::Heaven]);
+        flaky.priority_penalty_bps = reliability.penalty_bps(&flaky);
+        steady.priority_penalty_bps = reliability.penalty_bps(&steady);
+        assert_eq!((flaky.priority_penalty_bps, steady.priority_penalty_bps), (5_000, 0));
+
+        // Capital for one cycle only: it goes to the reliable venue
+        let limits = CapitalLimits { max_capital: 1_000_000, per_cycle_percent: 100, min_profit: 0, min_input_fraction: 0.0, max_input_fraction: 1.0 };
+        let threshold = ProfitThreshold { base_bps: 0, per_hop_increment_bps: 0 };
+        let optimizer = AmountOptimizer::new(Arc::new(PriceGraph::new()), 1, 0.0);
+        let steady_id = steady.cycle_id();
+        let (funded, unfunded) = optimizer.allocate_capital(vec![(flaky, 1_000_000), (steady, 1_000_000)], &limits, u64::MAX, threshold);
+        assert_eq!(funded.len(), 1);
+        assert_eq!(funded[0].cycle.cycle_id(), steady_id);
+        assert_eq!(unfunded[0].legs[0].dex_type, DexType::Heaven);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::clock::SharedClock;
+use std::time::{Duration, Instant};
+
+/// A fully failed refresh at most stretches the refresh interval 2^3 times
+const MAX_REFRESH_BACKOFF_SHIFT: u32 = 3;
+
+/// Drives the main loop at the detection cadence and says when the pool
+/// refresh is due, so cycles are searched several times against the same
+/// graph snapshot between refreshes.
+pub struct LoopSchedule {
+    refresh_interval: Duration,
+    detection_interval: Duration,
+    last_refresh: Option<Instant>,
+    failed_refreshes: u32, // Consecutive refreshes that reached no pool
+    clock: SharedClock,
+}
+
+impl LoopSchedule {
+    /// A zero or longer-than-refresh `detection_interval` detects once per refresh
+    pub fn new(refresh_interval: Duration, detection_interval: Duration, clock: SharedClock) -> Self {
+        let detection_interval = if detection_interval.is_zero() || detection_interval > refresh_interval {
+            refresh_interval
+        } else {
+            detection_interval
+        };
+        Self {
+            refresh_interval,
+            detection_interval,
+            last_refresh: None,
+            failed_refreshes: 0,
+            clock,
+        }
+    }
+
+    /// Period of the main loop tick
+    pub fn tick_interval(&self) -> Duration {
+        self.detection_interval
+    }
+
+    /// Detection passes per refresh, including the one right after it
+    pub fn detections_per_refresh(&self) -> u64 {
+        (self.refresh_interval.as_millis() / self.detection_interval.as_millis().max(1)).max(1) as u64
+    }
+
+    /// Wait between refreshes, doubled for every consecutive failed refresh
+    pub fn refresh_backoff(&self) -> Duration {
+        self.refresh_interval * (1u32 << self.failed_refreshes.min(MAX_REFRESH_BACKOFF_SHIFT))
+    }
+
+    /// Report whether the refresh started by the last due tick reached any pool
+    pub fn record_refresh(&mut self, succeeded: bool) {
+        self.failed_refreshes = if succeeded { 0 } else { self.failed_refreshes.saturating_add(1) };
+    }
+
+    /// True on the first tick and once `refresh_backoff` has passed since the
+    /// last refresh. A failed refresh still counts, so it is retried at the
+    /// refresh cadence rather than on every detection tick.
+    pub fn begin_tick(&mut self) -> bool {
+        let now = self.clock.now_instant();
+        let backoff = self.refresh_backoff();
+        let due = self
+            .last_refresh
+            .is_none_or(|at| now.saturating_duration_since(at) >= backoff);
+        if due {
+            self.last_refresh = Some(now);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::sync::Arc;
+
+    /// Run `ticks` main loop ticks, each one `tick_interval` apart, and count
+    /// the refreshes among them; every tick is a detection pass
+    fn refreshes_over(schedule: &mut LoopSchedule, clock: &MockClock, ticks: usize) -> usize {
+        let mut refreshes = 0;
+        for _ in 0..ticks {
+            if schedule.begin_tick() {
+                refreshes += 1;
+            }
+            clock.advance(schedule.tick_interval());
+        }
+        refreshes
+    }
+
+    #[test]
+    fn detection_runs_at_the_configured_ratio_per_refresh() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut schedule = LoopSchedule::new(Duration::from_secs(30), Duration::from_secs(2), clock.clone());
+        assert_eq!(schedule.tick_interval(), Duration::from_secs(2));
+        assert_eq!(schedule.detections_per_refresh(), 15);
+
+        let detections = 45;
+        assert_eq!(refreshes_over(&mut schedule, &clock, detections), 3);
+    }
+
+    #[test]
+    fn an_unset_or_too_slow_detection_interval_detects_once_per_refresh() {
+        for detection_interval in [Duration::ZERO, Duration::from_secs(90)] {
+            let clock = Arc::new(MockClock::new(0));
+            let mut schedule = LoopSchedule::new(Duration::from_secs(60), detection_interval, clock.clone());
+            assert_eq!(schedule.tick_interval(), Duration::from_secs(60));
+            assert_eq!(schedule.detections_per_refresh(), 1);
+            assert_eq!(refreshes_over(&mut schedule, &clock, 4), 4);
+        }
+    }
+
+    #[test]
+    fn failed_refreshes_back_off_up_to_the_cap_and_reset_on_success() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut schedule = LoopSchedule::new(Duration::from_secs(10), Duration::from_secs(1), clock.clone());
+        assert!(schedule.begin_tick());
+
+        for failures in 1..=5u32 {
+            schedule.record_refresh(false);
+            let backoff = Duration::from_secs(10) * (1 << failures.min(MAX_REFRESH_BACKOFF_SHIFT));
+            assert_eq!(schedule.refresh_backoff(), backoff);
+            clock.advance(backoff - Duration::from_secs(1));
+            assert!(!schedule.begin_tick(), "refreshed early after {} failures", failures);
+            clock.advance(Duration::from_secs(1));
+            assert!(schedule.begin_tick());
+        }
+
+        schedule.record_refresh(true);
+        assert_eq!(schedule.refresh_backoff(), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::engine::types::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// Machine-readable account of one run, written at shutdown
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub started_at: u64, // Unix seconds
+    pub ended_at: u64,
+    pub runtime_secs: f64,
+    pub detection_passes: u64,
+    pub cycles_detected: u64, // Summed over passes, so a lasting cycle counts once per pass
+    pub opportunities_opened: u64,
+    pub profitable_cycles: u64, // Cleared every check and executed, paper fills included
+    pub projected_profit_lamports: u64,
+    pub projected_net_profit_lamports: i64, // After estimated transaction costs
+    pub opportunities_by_dex: BTreeMap<DexType, u64>, // Profitable cycles with a leg on each venue
+}
+
+/// Accumulates a `SessionSummary` over the main loop
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    summary: SessionSummary,
+}
+
+impl SessionStats {
+    pub fn new(started_at: u64) -> Self {
+        Self { summary: SessionSummary { started_at, ..Default::default() } }
+    }
+
+    pub fn record_pass(&mut self, cycles_detected: usize, opportunities_opened: usize) {
+        self.summary.detection_passes += 1;
+        self.summary.cycles_detected += cycles_detected as u64;
+        self.summary.opportunities_opened += opportunities_opened as u64;
+    }
+
+    /// Record a cycle that cleared every check and was executed
+    pub fn record_profitable(&mut self, cycle: &ArbitrageCycle) {
+        let summary = &mut self.summary;
+        summary.profitable_cycles += 1;
+        summary.projected_profit_lamports += cycle.estimated_profit_lamports;
+        summary.projected_net_profit_lamports += cycle.net_profit_lamports();
+        let venues: BTreeSet<DexType> = cycle.legs.iter().map(|leg| leg.dex_type).collect();
+        for venue in venues {
+            *summary.opportunities_by_dex.entry(venue).or_insert(0) += 1;
+        }
+    }
+
+    pub fn summary(&self, ended_at: u64, runtime: Duration) -> SessionSummary {
+        SessionSummary { ended_at, runtime_secs: runtime.as_secs_f64(), ..self.summary.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn cycle(venues: &[DexType], estimated_profit_lamports: u64, estimated_tx_cost_lamports: u64) -> ArbitrageCycle {
+        let legs: Vec<SwapLeg> = venues
+            .iter()
+            .map(|&dex_type| SwapLeg {
+                from_mint: Pubkey::new_unique(),
+                to_mint: Pubkey::new_unique(),
+                pool_pubkey: Pubkey::new_unique(),
+                dex_type,
+                program_id: dex_type.program_id(),
+                amount_in: 1_000_000,
+                estimated_amount_out: 1_000_000,
+                quote_haircut_bps: 0,
+            })
+            .collect();
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 0,
+            estimated_profit_lamports,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports,
+        }
+    }
+
+    #[test]
+    fn the_summary_reflects_the_recorded_sequence() {
+        let mut stats = SessionStats::new(1_700_000_000);
+        stats.record_pass(3, 2);
+        stats.record_profitable(&cycle(&[DexType::RaydiumV4, DexType::Whirlpool], 50_000, 10_000));
+        stats.record_pass(2, 0);
+        // Two legs on the same venue count once for it; this one costs more than it earns
+        stats.record_profitable(&cycle(&[DexType::RaydiumV4, DexType::RaydiumV4], 8_000, 12_000));
+        stats.record_pass(0, 0);
+
+        let summary = stats.summary(1_700_000_090, Duration::from_secs(90));
+        assert_eq!(
+            summary,
+            SessionSummary {
+                started_at: 1_700_000_000,
+                ended_at: 1_700_000_090,
+                runtime_secs: 90.0,
+                detection_passes: 3,
+                cycles_detected: 5,
+                opportunities_opened: 2,
+                profitable_cycles: 2,
+                projected_profit_lamports: 58_000,
+                projected_net_profit_lamports: 36_000,
+                opportunities_by_dex: BTreeMap::from([(DexType::RaydiumV4, 2), (DexType::Whirlpool, 1)]),
+            }
+        );
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["opportunities_by_dex"], serde_json::json!({ "RaydiumV4": 2, "Whirlpool": 1 }));
+        assert_eq!(json["projected_net_profit_lamports"], 36_000);
+    }
+
+    #[test]
+    fn an_empty_session_summarizes_to_zeroes() {
+        let summary = SessionStats::new(1_700_000_000).summary(1_700_000_000, Duration::ZERO);
+        assert_eq!(summary, SessionSummary { started_at: 1_700_000_000, ended_at: 1_700_000_000, ..Default::default() });
+    }
+}
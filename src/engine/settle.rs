@@ -0,0 +1,179 @@
+use crate::engine::graph::PriceGraph;
+use crate::engine::types::*;
+use crate::pools::MintPoolData;
+use crate::rpc::RpcLike;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Mints an execution traded through stay "settling" for `cooldown`. Their
+/// pools are re-read before every detection pass in that window, so the pass
+/// after a trade does not re-find the opportunity it just closed against the
+/// graph's pre-trade prices.
+pub struct SettleCooldown {
+    cooldown: Duration,
+    settling: HashMap<Pubkey, Instant>, // Value: when the mint stops settling
+}
+
+impl SettleCooldown {
+    pub fn new(cooldown: Duration) -> Self {
+        Self { cooldown, settling: HashMap::new() }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.cooldown.is_zero()
+    }
+
+    /// Record an execution of `cycle` at `now`
+    pub fn record(&mut self, cycle: &ArbitrageCycle, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        let until = now + self.cooldown;
+        for leg in &cycle.legs {
+            self.settling.insert(leg.from_mint, until);
+            self.settling.insert(leg.to_mint, until);
+        }
+    }
+
+    /// Mints still settling at `now`, sorted; expired ones are forgotten
+    pub fn settling_mints(&mut self, now: Instant) -> Vec<Pubkey> {
+        self.settling.retain(|_, until| now < *until);
+        let mut mints: Vec<Pubkey> = self.settling.keys().copied().collect();
+        mints.sort();
+        mints
+    }
+
+    /// Re-read the pools of every known mint still settling at `now` into a
+    /// new graph generation, ahead of the next detection pass. Returns the
+    /// mints re-read; fails on a strict-mode anomaly.
+    pub fn refresh_settling(
+        &mut self,
+        graph: &PriceGraph,
+        mint_pool_data: &HashMap<Pubkey, MintPoolData>,
+        rpc_client: &dyn RpcLike,
+        now: Instant,
+    ) -> anyhow::Result<Vec<Pubkey>> {
+        let settling: Vec<Pubkey> = self.settling_mints(now).into_iter().filter(|mint| mint_pool_data.contains_key(mint)).collect();
+        if settling.is_empty() {
+            return Ok(settling);
+        }
+        graph.begin_generation();
+        for mint in &settling {
+            if let Err(e) = graph.try_update_from_mint_pool_data(&mint_pool_data[mint], rpc_client) {
+                debug!("Settle refresh of {} failed: {}", mint, e);
+            }
+            if let Some(anomaly) = graph.take_anomaly() {
+                anyhow::bail!("Strict mode: anomaly while pricing mint {}: {}", mint, anomaly);
+            }
+        }
+        Ok(settling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+    use crate::engine::detect::CycleDetector;
+    use crate::rpc::MockRpc;
+    use solana_sdk::account::Account;
+
+    fn token_account(mint: Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    fn mint_account(decimals: u8) -> Account {
+        let mut data = vec![0u8; 82];
+        data[44] = decimals;
+        data[45] = 1;
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    /// A token with two Raydium V4 pools against SOL holding 1 SOL each and
+    /// `token_reserves` of the token; returns the token vaults
+    fn add_token(rpc: &MockRpc, mint_pool_data: &mut HashMap<Pubkey, MintPoolData>, token_reserves: [u64; 2]) -> (Pubkey, Vec<Pubkey>) {
+        let token = Pubkey::new_unique();
+        rpc.set_account(token, mint_account(6));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        let mut token_vaults = Vec::new();
+        for reserve in token_reserves {
+            let (pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            rpc.set_account(token_vault, token_account(token, reserve));
+            rpc.set_account(sol_vault, token_account(sol_mint(), 1_000_000_000));
+            pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+            token_vaults.push(token_vault);
+        }
+        mint_pool_data.insert(token, pool_data);
+        (token, token_vaults)
+    }
+
+    fn detect(graph: &PriceGraph) -> Vec<ArbitrageCycle> {
+        CycleDetector::find_negative_cycles(graph, sol_mint(), 2, 2, ProfitThreshold::flat(10))
+    }
+
+    #[test]
+    fn traded_mints_are_re_read_before_the_next_detection_pass() {
+        let rpc = MockRpc::new();
+        rpc.set_account(sol_mint(), mint_account(9));
+        let mut mint_pool_data = HashMap::new();
+        // 5% apart: an opportunity
+        let (traded, traded_vaults) = add_token(&rpc, &mut mint_pool_data, [4_000_000_000_000, 4_200_000_000_000]);
+        let (untouched, _) = add_token(&rpc, &mut mint_pool_data, [4_000_000_000_000, 4_000_000_000_000]);
+        let graph = PriceGraph::new();
+        for pool_data in mint_pool_data.values() {
+            graph.update_from_mint_pool_data(pool_data, &rpc);
+        }
+        let cycle = detect(&graph).into_iter().next().expect("the spread is detected");
+
+        let mut settle = SettleCooldown::new(Duration::from_secs(2));
+        let executed_at = Instant::now();
+        settle.record(&cycle, executed_at);
+        // The trade closed the spread on chain
+        rpc.set_account(traded_vaults[1], token_account(traded, 4_000_000_000_000));
+
+        let calls = rpc.calls();
+        let settling = settle.refresh_settling(&graph, &mint_pool_data, &rpc, executed_at + Duration::from_millis(400)).unwrap();
+        assert_eq!(settling, vec![traded], "only the mints the execution touched are re-read, not {}", untouched);
+        assert!(rpc.calls() > calls);
+        assert!(detect(&graph).is_empty(), "the closed opportunity is found again on pre-trade prices");
+
+        // Once the cooldown is over, nothing is re-read
+        let calls = rpc.calls();
+        assert!(settle.refresh_settling(&graph, &mint_pool_data, &rpc, executed_at + Duration::from_secs(2)).unwrap().is_empty());
+        assert_eq!(rpc.calls(), calls);
+    }
+
+    #[test]
+    fn a_zero_cooldown_never_settles() {
+        let mut settle = SettleCooldown::new(Duration::ZERO);
+        let (sol, token) = (sol_mint(), Pubkey::new_unique());
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        let cycle = ArbitrageCycle {
+            legs: vec![leg(sol, token), leg(token, sol)],
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        };
+        let now = Instant::now();
+        settle.record(&cycle, now);
+        assert!(!settle.enabled());
+        assert!(settle.settling_mints(now).is_empty());
+    }
+}
@@ -1,5 +1,10 @@
+use crate::clock::{system_clock, SharedClock};
+use crate::engine::graph::PriceGraph;
 use crate::engine::types::*;
 use solana_client::rpc_client::RpcClient;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use tracing::{debug, info};
 
 pub struct Simulator;
@@ -53,4 +58,216 @@ pub struct SimulationResult {
     pub actual_profit_lamports: u64,
     pub cu_consumed: u64,
     pub error: Option<String>,
-}
\ No newline at end of file
+}
+/// Key of a cached simulation: the same cycle, at the same edge versions,
+/// sized into the same amount bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SimulationKey {
+    pub cycle_id: u64,
+    pub snapshot_version: u64, // Latest generation in which any leg's edge changed
+    pub amount_bucket: u64,
+}
+
+/// TTL cache of simulation outcomes, so a cycle that stays profitable across
+/// passes isn't re-simulated while none of its edges changed. Failures are
+/// cached too, so a doomed cycle isn't retried every pass.
+pub struct SimulationCache {
+    ttl: Duration,
+    amount_bucket_lamports: u64,
+    entries: HashMap<SimulationKey, (Instant, SimulationResult)>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    clock: SharedClock,
+}
+
+impl SimulationCache {
+    pub fn new(ttl: Duration, amount_bucket_lamports: u64) -> Self {
+        Self::with_clock(ttl, amount_bucket_lamports, system_clock())
+    }
+
+    pub fn with_clock(ttl: Duration, amount_bucket_lamports: u64, clock: SharedClock) -> Self {
+        Self {
+            ttl,
+            amount_bucket_lamports: amount_bucket_lamports.max(1),
+            entries: HashMap::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// A leg's edge update moves `snapshot_version`, so stale entries are
+    /// simply never looked up again and age out with the TTL.
+    pub fn key(&self, graph: &PriceGraph, cycle: &ArbitrageCycle, amount: u64) -> SimulationKey {
+        SimulationKey {
+            cycle_id: cycle.cycle_id(),
+            snapshot_version: cycle
+                .legs
+                .iter()
+                .map(|leg| graph.pool_generation(&leg.pool_pubkey))
+                .max()
+                .unwrap_or(0),
+            amount_bucket: amount / self.amount_bucket_lamports,
+        }
+    }
+
+    pub fn get(&self, key: &SimulationKey) -> Option<&SimulationResult> {
+        let result = self
+            .entries
+            .get(key)
+            .filter(|(cached_at, _)| self.clock.elapsed_since(*cached_at) < self.ttl)
+            .map(|(_, result)| result);
+        match result {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        result
+    }
+
+    pub fn insert(&mut self, key: SimulationKey, result: SimulationResult) {
+        let (ttl, now) = (self.ttl, self.clock.now_instant());
+        self.entries
+            .retain(|_, (cached_at, _)| now.saturating_duration_since(*cached_at) < ttl);
+        self.entries.insert(key, (now, result));
+    }
+
+    /// Cached outcome for `cycle` at `amount`, running `simulate` on a miss
+    pub fn get_or_simulate(
+        &mut self,
+        graph: &PriceGraph,
+        cycle: &ArbitrageCycle,
+        amount: u64,
+        simulate: impl FnOnce() -> anyhow::Result<SimulationResult>,
+    ) -> anyhow::Result<SimulationResult> {
+        let key = self.key(graph, cycle, amount);
+        if let Some(result) = self.get(&key) {
+            debug!("Simulation cache hit for cycle {:016x}", key.cycle_id);
+            return Ok(result.clone());
+        }
+        let result = simulate()?;
+        self.insert(key, result.clone());
+        Ok(result)
+    }
+
+    /// `(hits, misses)` since startup
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::constants::sol_mint;
+    use solana_sdk::pubkey::Pubkey;
+    use std::sync::Arc;
+
+    struct Fixture {
+        graph: PriceGraph,
+        cycle: ArbitrageCycle,
+        token: Pubkey,
+    }
+
+    fn fixture() -> Fixture {
+        let graph = PriceGraph::new();
+        let token = Pubkey::new_unique();
+        let pools = [Pubkey::new_unique(), Pubkey::new_unique()];
+        graph.begin_generation();
+        graph.add_edge(sol_mint(), token, PoolEdge::new(pools[0], token, DexType::RaydiumV4, 100.0, 1e6, 25, spl_token::id()));
+        graph.add_edge(token, sol_mint(), PoolEdge::new(pools[1], sol_mint(), DexType::RaydiumCp, 0.0102, 1e6, 25, spl_token::id()));
+        let leg = |from_mint, to_mint, pool_pubkey| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey,
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        let cycle = ArbitrageCycle {
+            legs: vec![leg(sol_mint(), token, pools[0]), leg(token, sol_mint(), pools[1])],
+            total_profit_bps: 150,
+            estimated_profit_lamports: 1_000_000,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        };
+        Fixture { graph, cycle, token }
+    }
+
+    fn outcome(success: bool) -> anyhow::Result<SimulationResult> {
+        Ok(SimulationResult {
+            success,
+            actual_profit_lamports: if success { 1_000_000 } else { 0 },
+            cu_consumed: 200_000,
+            error: (!success).then(|| "slippage exceeded".to_string()),
+        })
+    }
+
+    #[test]
+    fn a_second_evaluation_within_the_snapshot_hits_the_cache() {
+        let f = fixture();
+        let mut cache = SimulationCache::new(Duration::from_secs(10), 1_000_000);
+        let mut simulations = 0;
+        for _ in 0..2 {
+            let result = cache.get_or_simulate(&f.graph, &f.cycle, 100_000_000, || {
+                simulations += 1;
+                outcome(true)
+            });
+            assert!(result.unwrap().success);
+        }
+        assert_eq!(simulations, 1);
+        assert_eq!(cache.stats(), (1, 1));
+    }
+
+    #[test]
+    fn an_edge_update_on_a_leg_invalidates_the_entry() {
+        let f = fixture();
+        let mut cache = SimulationCache::new(Duration::from_secs(10), 1_000_000);
+        let key = cache.key(&f.graph, &f.cycle, 100_000_000);
+        cache.insert(key, outcome(true).unwrap());
+
+        // An unrelated pool moving leaves the cycle's key alone
+        f.graph.begin_generation();
+        f.graph.add_edge(f.token, sol_mint(), PoolEdge::new(Pubkey::new_unique(), sol_mint(), DexType::Pump, 0.01, 1e6, 25, spl_token::id()));
+        assert!(cache.get(&cache.key(&f.graph, &f.cycle, 100_000_000)).is_some());
+
+        f.graph.begin_generation();
+        let pool = f.cycle.legs[1].pool_pubkey;
+        f.graph.add_edge(f.token, sol_mint(), PoolEdge::new(pool, sol_mint(), DexType::RaydiumCp, 0.0099, 1e6, 25, spl_token::id()));
+        assert!(cache.get(&cache.key(&f.graph, &f.cycle, 100_000_000)).is_none());
+    }
+
+    #[test]
+    fn failures_are_cached_until_the_ttl_runs_out() {
+        let f = fixture();
+        let clock = Arc::new(MockClock::new(0));
+        let mut cache = SimulationCache::with_clock(Duration::from_secs(5), 1_000_000, clock.clone());
+        let mut simulations = 0;
+        let mut evaluate = |cache: &mut SimulationCache| {
+            cache
+                .get_or_simulate(&f.graph, &f.cycle, 100_000_000, || {
+                    simulations += 1;
+                    outcome(false)
+                })
+                .unwrap()
+        };
+
+        assert_eq!(evaluate(&mut cache).error.as_deref(), Some("slippage exceeded"));
+        assert!(!evaluate(&mut cache).success);
+        clock.advance(Duration::from_secs(5));
+        evaluate(&mut cache);
+        assert_eq!(simulations, 2);
+    }
+
+    #[test]
+    fn amounts_in_another_bucket_are_simulated_separately() {
+        let f = fixture();
+        let cache = SimulationCache::new(Duration::from_secs(10), 1_000_000);
+        assert_eq!(cache.key(&f.graph, &f.cycle, 100_000_000), cache.key(&f.graph, &f.cycle, 100_999_999));
+        assert_ne!(cache.key(&f.graph, &f.cycle, 100_000_000), cache.key(&f.graph, &f.cycle, 101_000_000));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::engine::compute_profile::ComputeUnitTable;
+use crate::engine::types::*;
+use std::ops::Range;
+
+/// Partitions a cycle's legs into consecutive transactions that each fit a
+/// compute budget and a leg count (a stand-in for the account/size limit).
+/// The executor instruction cannot hand a leg's output to a later
+/// transaction, so only cycles that fit one part are sent, see
+/// `fit_one_transaction`.
+#[derive(Debug, Clone, Copy)]
+pub struct CycleSplitter {
+    pub max_compute_units: u64, // Per transaction, executor overhead included
+    pub max_legs_per_tx: usize,
+    pub unmeasured_leg_units: u64, // Assumed for venues the compute table has no estimate for
+}
+
+impl CycleSplitter {
+    /// Estimated compute units of one leg through `dex_type`
+    fn leg_units(&self, table: &ComputeUnitTable, dex_type: DexType) -> u64 {
+        table.estimate(dex_type).unwrap_or(self.unmeasured_leg_units)
+    }
+
+    /// Leg index ranges, in trade order, one per transaction. A cycle that
+    /// fits yields a single range; a leg that alone exceeds the budget is an error.
+    pub fn split(&self, cycle: &ArbitrageCycle, table: &ComputeUnitTable) -> anyhow::Result<Vec<Range<usize>>> {
+        let overhead = table.overhead.map_or(0, |estimate| estimate.units.ceil() as u64);
+        let max_legs = self.max_legs_per_tx.max(1);
+        let mut parts = Vec::new();
+        let mut start = 0;
+        let mut units = overhead;
+
+        for (idx, leg) in cycle.legs.iter().enumerate() {
+            let leg_units = self.leg_units(table, leg.dex_type);
+            if overhead + leg_units > self.max_compute_units {
+                anyhow::bail!(
+                    "leg {} through {:?} needs ~{} CU, over the {} CU budget on its own",
+                    idx, leg.dex_type, overhead + leg_units, self.max_compute_units
+                );
+            }
+            if idx > start && (units + leg_units > self.max_compute_units || idx - start >= max_legs) {
+                parts.push(start..idx);
+                start = idx;
+                units = overhead;
+            }
+            units += leg_units;
+        }
+        if start < cycle.legs.len() {
+            parts.push(start..cycle.legs.len());
+        }
+        Ok(parts)
+    }
+
+    /// Reject a cycle that would need more than one transaction
+    pub fn fit_one_transaction(&self, cycle: &ArbitrageCycle, table: &ComputeUnitTable) -> anyhow::Result<()> {
+        let parts = self.split(cycle, table)?;
+        if parts.len() > 1 {
+            anyhow::bail!(
+                "{}-leg cycle needs {} transactions; the executor cannot carry a leg's output into the next one",
+                cycle.legs.len(),
+                parts.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::compute_profile::ComputeEstimate;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn cycle(venues: &[DexType]) -> ArbitrageCycle {
+        let legs: Vec<SwapLeg> = venues
+            .iter()
+            .map(|dex_type| SwapLeg {
+                from_mint: Pubkey::new_unique(),
+                to_mint: Pubkey::new_unique(),
+                pool_pubkey: Pubkey::new_unique(),
+                dex_type: *dex_type,
+                program_id: dex_type.program_id(),
+                amount_in: 0,
+                estimated_amount_out: 0,
+                quote_haircut_bps: 0,
+            })
+            .collect();
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn table(overhead: u64, venues: &[(DexType, u64)]) -> ComputeUnitTable {
+        ComputeUnitTable {
+            venues: venues.iter().map(|(dex_type, units)| (*dex_type, ComputeEstimate { units: *units as f64, samples: 10 })).collect(),
+            overhead: Some(ComputeEstimate { units: overhead as f64, samples: 10 }),
+        }
+    }
+
+    #[test]
+    fn measured_costs_and_overhead_decide_where_a_cycle_is_cut() {
+        let splitter = CycleSplitter { max_compute_units: 300_000, max_legs_per_tx: 8, unmeasured_leg_units: 100_000 };
+        let table = table(50_000, &[(DexType::RaydiumV4, 60_000), (DexType::Whirlpool, 120_000)]);
+        let cycle = cycle(&[DexType::RaydiumV4, DexType::Whirlpool, DexType::RaydiumV4, DexType::Whirlpool, DexType::MeteoraDlmm]);
+
+        // 50k overhead + 60k + 120k + 60k fits; the second Whirlpool leg starts a
+        // new transaction, which the unmeasured DLMM leg still fits into at 270k
+        assert_eq!(splitter.split(&cycle, &table).unwrap(), vec![0..3, 3..5]);
+    }
+
+    #[test]
+    fn the_leg_count_cuts_a_cycle_that_fits_the_compute_budget() {
+        let splitter = CycleSplitter { max_compute_units: 1_400_000, max_legs_per_tx: 2, unmeasured_leg_units: 100_000 };
+        let cycle = cycle(&[DexType::RaydiumV4; 5]);
+        assert_eq!(splitter.split(&cycle, &ComputeUnitTable::default()).unwrap(), vec![0..2, 2..4, 4..5]);
+    }
+
+    #[test]
+    fn a_leg_over_the_budget_on_its_own_is_an_error() {
+        let splitter = CycleSplitter { max_compute_units: 200_000, max_legs_per_tx: 8, unmeasured_leg_units: 100_000 };
+        let table = table(50_000, &[(DexType::RaydiumClmm, 180_000)]);
+        let err = splitter.split(&cycle(&[DexType::RaydiumV4, DexType::RaydiumClmm]), &table).unwrap_err();
+        assert!(err.to_string().starts_with("leg 1 through RaydiumClmm"), "{}", err);
+    }
+
+    #[test]
+    fn only_a_cycle_that_fits_one_transaction_is_accepted() {
+        let splitter = CycleSplitter { max_compute_units: 350_000, max_legs_per_tx: 8, unmeasured_leg_units: 100_000 };
+        assert!(splitter.fit_one_transaction(&cycle(&[DexType::RaydiumV4; 3]), &ComputeUnitTable::default()).is_ok());
+        let err = splitter.fit_one_transaction(&cycle(&[DexType::RaydiumV4; 6]), &ComputeUnitTable::default()).unwrap_err();
+        assert!(err.to_string().starts_with("6-leg cycle needs 2 transactions"), "{}", err);
+    }
+}
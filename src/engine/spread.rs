@@ -0,0 +1,284 @@
+use crate::engine::graph::PriceGraph;
+use crate::engine::types::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use tracing::warn;
+
+/// Pipeline stage that turned a detected cycle down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionStage {
+    Venue,  // Venue combination not allowed or a leg has no executor
+    Config, // No capital configured for the base mint
+    Cost,   // Fees, slippage or the capital budget leave no profit
+    Risk,   // Pool cooldown, unreliable venue or failed simulation
+}
+
+/// Why a detected cycle was not sent this iteration
+#[derive(Debug, Clone)]
+pub struct CycleRejection {
+    pub cycle_id: u64,
+    pub pools: Vec<Pubkey>,
+    pub stage: RejectionStage,
+    pub reason: String,
+}
+
+impl CycleRejection {
+    pub fn new(cycle: &ArbitrageCycle, stage: RejectionStage, reason: impl Into<String>) -> Self {
+        Self {
+            cycle_id: cycle.cycle_id(),
+            pools: cycle.legs.iter().map(|leg| leg.pool_pubkey).collect(),
+            stage,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// One pool's quote for a pair, normalized to units of the larger mint per
+/// unit of the smaller mint
+#[derive(Debug, Clone, Copy)]
+pub struct PairQuote {
+    pub pool: Pubkey,
+    pub dex_type: DexType,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PairSpread {
+    pub mints: (Pubkey, Pubkey),
+    pub high: PairQuote,
+    pub low: PairQuote,
+    pub spread_bps: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpreadAlert {
+    pub mints: (Pubkey, Pubkey),
+    pub high: PairQuote,
+    pub low: PairQuote,
+    pub spread_bps: f64,
+    pub window_max_spread_bps: f64,
+    pub iterations: u64,
+    pub reasons: Vec<String>,
+}
+
+impl SpreadAlert {
+    pub fn log(&self) {
+        warn!(
+            "🚨 Uncaptured spread on {} / {} for {} iterations: {:.0} bps (window max {:.0} bps) between {:?} {} @ {:.9} and {:?} {} @ {:.9}",
+            self.mints.0,
+            self.mints.1,
+            self.iterations,
+            self.spread_bps,
+            self.window_max_spread_bps,
+            self.high.dex_type,
+            self.high.pool,
+            self.high.price,
+            self.low.dex_type,
+            self.low.pool,
+            self.low.price
+        );
+        for reason in &self.reasons {
+            warn!("   ↳ {}", reason);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PairState {
+    window: VecDeque<f64>,
+    persisted: u64,
+    alerted: bool,
+}
+
+/// Tracks the inter-venue spread of every mint pair in the graph. A spread
+/// above the threshold that persists without an execution attempt on either
+/// pool is either missed profit or a pricing bug, so it raises an alert with
+/// the rejection reasons of the cycles that did go through those pools.
+pub struct SpreadMonitor {
+    threshold_bps: f64,
+    persist_iterations: u64,
+    window: usize,
+    pairs: BTreeMap<(Pubkey, Pubkey), PairState>,
+}
+
+impl SpreadMonitor {
+    pub fn new(threshold_bps: f64, persist_iterations: u64, window: usize) -> Self {
+        Self {
+            threshold_bps,
+            persist_iterations: persist_iterations.max(1),
+            window: window.max(1),
+            pairs: BTreeMap::new(),
+        }
+    }
+
+    /// Widest spread between two pools of the same pair, for every pair quoted
+    /// by at least two pools
+    pub fn pair_spreads(graph: &PriceGraph) -> Vec<PairSpread> {
+        let mut quotes: BTreeMap<(Pubkey, Pubkey), Vec<PairQuote>> = BTreeMap::new();
+        for (from_mint, edges) in graph.sorted_edges() {
+            for edge in edges {
+                if edge.spot_price() <= 0.0 || !edge.spot_price().is_finite() {
+                    continue;
+                }
+                // Each pool contributes one quote per pair, taken from its small -> large edge
+                if from_mint >= edge.to_mint {
+                    continue;
+                }
+                quotes.entry((from_mint, edge.to_mint)).or_default().push(PairQuote {
+                    pool: edge.pool_pubkey,
+                    dex_type: edge.dex_type,
+                    price: edge.spot_price(),
+                });
+            }
+        }
+
+        quotes
+            .into_iter()
+            .filter(|(_, quotes)| quotes.len() >= 2)
+            .filter_map(|(mints, quotes)| {
+                let high = *quotes.iter().max_by(|a, b| a.price.total_cmp(&b.price))?;
+                let low = *quotes.iter().min_by(|a, b| a.price.total_cmp(&b.price))?;
+                let spread_bps = (high.price / low.price - 1.0) * 10_000.0;
+                Some(PairSpread { mints, high, low, spread_bps })
+            })
+            .collect()
+    }
+
+    /// Feed one iteration. `attempted_pools` are pools of cycles that were
+    /// actually executed this iteration (a paper fill or a sent transaction,
+    /// also counted on their opportunity epoch); sizing a cycle is not an
+    /// attempt. `rejections` explain the cycles that were not executed.
+    pub fn observe(
+        &mut self,
+        graph: &PriceGraph,
+        attempted_pools: &HashSet<Pubkey>,
+        rejections: &[CycleRejection],
+    ) -> Vec<SpreadAlert> {
+        let spreads = Self::pair_spreads(graph);
+        let seen: HashSet<(Pubkey, Pubkey)> = spreads.iter().map(|s| s.mints).collect();
+        self.pairs.retain(|mints, _| seen.contains(mints));
+
+        let mut alerts = Vec::new();
+        for spread in spreads {
+            let state = self.pairs.entry(spread.mints).or_default();
+            state.window.push_back(spread.spread_bps);
+            while state.window.len() > self.window {
+                state.window.pop_front();
+            }
+
+            let attempted = attempted_pools.contains(&spread.high.pool) || attempted_pools.contains(&spread.low.pool);
+            if spread.spread_bps < self.threshold_bps || attempted {
+                state.persisted = 0;
+                state.alerted = false;
+                continue;
+            }
+
+            state.persisted += 1;
+            if state.persisted < self.persist_iterations || state.alerted {
+                continue;
+            }
+            state.alerted = true;
+
+            let mut reasons: Vec<String> = rejections
+                .iter()
+                .filter(|r| r.pools.contains(&spread.high.pool) || r.pools.contains(&spread.low.pool))
+                .map(|r| format!("cycle {:016x}: {}", r.cycle_id, r.reason))
+                .collect();
+            reasons.sort();
+            reasons.dedup();
+            if reasons.is_empty() {
+                reasons.push("no detected cycle went through either pool".to_string());
+            }
+
+            alerts.push(SpreadAlert {
+                mints: spread.mints,
+                high: spread.high,
+                low: spread.low,
+                spread_bps: spread.spread_bps,
+                window_max_spread_bps: state.window.iter().copied().fold(f64::MIN, f64::max),
+                iterations: state.persisted,
+                reasons,
+            });
+        }
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+
+    struct Pair {
+        token: Pubkey,
+        raydium: Pubkey,
+        meteora: Pubkey,
+    }
+
+    /// A token quoted at 1% apart by a Raydium and a Meteora pool
+    fn wide_pair(graph: &PriceGraph) -> Pair {
+        let pair = Pair { token: Pubkey::new_unique(), raydium: Pubkey::new_unique(), meteora: Pubkey::new_unique() };
+        for (pool, dex_type, price) in [(pair.raydium, DexType::RaydiumV4, 0.0101), (pair.meteora, DexType::MeteoraDlmm, 0.0100)] {
+            graph.add_edge(pair.token, sol_mint(), PoolEdge::new(pool, sol_mint(), dex_type, price, 1e6, 25, spl_token::id()));
+            graph.add_edge(sol_mint(), pair.token, PoolEdge::new(pool, pair.token, dex_type, 1.0 / price, 1e6, 25, spl_token::id()));
+        }
+        pair
+    }
+
+    fn rejected_through(pool: Pubkey, reason: &str) -> CycleRejection {
+        CycleRejection { cycle_id: 0xabc, pools: vec![pool, Pubkey::new_unique()], stage: RejectionStage::Cost, reason: reason.to_string() }
+    }
+
+    #[test]
+    fn a_persistent_uncaptured_spread_alerts_once_with_its_rejections() {
+        let graph = PriceGraph::new();
+        let pair = wide_pair(&graph);
+        let mut monitor = SpreadMonitor::new(50.0, 3, 10);
+        let rejections = vec![
+            rejected_through(pair.raydium, "below min_profit_lamports after tx cost"),
+            rejected_through(Pubkey::new_unique(), "unrelated pool"),
+        ];
+
+        assert!(monitor.observe(&graph, &HashSet::new(), &rejections).is_empty());
+        assert!(monitor.observe(&graph, &HashSet::new(), &rejections).is_empty());
+        let alerts = monitor.observe(&graph, &HashSet::new(), &rejections);
+        assert_eq!(alerts.len(), 1);
+        let alert = &alerts[0];
+        let expected_mints = if pair.token < sol_mint() { (pair.token, sol_mint()) } else { (sol_mint(), pair.token) };
+        assert_eq!(alert.mints, expected_mints);
+        assert_eq!(alert.iterations, 3);
+        assert!((alert.spread_bps - 100.0).abs() < 0.5, "spread {} bps", alert.spread_bps);
+        assert_eq!(alert.window_max_spread_bps, alert.spread_bps);
+        let venues = [alert.high.dex_type, alert.low.dex_type];
+        assert!(venues.contains(&DexType::RaydiumV4) && venues.contains(&DexType::MeteoraDlmm));
+        assert_eq!(alert.reasons, vec![format!("cycle {:016x}: below min_profit_lamports after tx cost", 0xabc)]);
+
+        // One alert per episode
+        assert!(monitor.observe(&graph, &HashSet::new(), &rejections).is_empty());
+    }
+
+    #[test]
+    fn an_execution_attempt_on_either_pool_resets_the_episode() {
+        let graph = PriceGraph::new();
+        let pair = wide_pair(&graph);
+        let mut monitor = SpreadMonitor::new(50.0, 2, 10);
+
+        assert!(monitor.observe(&graph, &HashSet::new(), &[]).is_empty());
+        assert!(monitor.observe(&graph, &HashSet::from([pair.meteora]), &[]).is_empty());
+        assert!(monitor.observe(&graph, &HashSet::new(), &[]).is_empty());
+        let alerts = monitor.observe(&graph, &HashSet::new(), &[]);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].iterations, 2);
+        assert_eq!(alerts[0].reasons, vec!["no detected cycle went through either pool".to_string()]);
+    }
+
+    #[test]
+    fn a_spread_under_the_threshold_never_alerts() {
+        let graph = PriceGraph::new();
+        wide_pair(&graph);
+        let mut monitor = SpreadMonitor::new(150.0, 1, 10);
+        for _ in 0..5 {
+            assert!(monitor.observe(&graph, &HashSet::new(), &[]).is_empty());
+        }
+    }
+}
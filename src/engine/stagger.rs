@@ -0,0 +1,83 @@
+use solana_sdk::pubkey::Pubkey;
+
+/// Splits the steady-state refresh into `batches` slices so RPC load is
+/// spread over the refresh interval instead of landing on one tick. Mints are
+/// dealt round-robin by their position in the sorted list, so a slice never
+/// holds more than `ceil(mints / batches)` of them and every mint is covered
+/// once per `batches` refreshes.
+#[derive(Debug, Clone)]
+pub struct RefreshStagger {
+    batches: u64,
+    next: u64, // Slice the next refresh covers
+}
+
+impl RefreshStagger {
+    /// 0 or 1 batch refreshes every mint each time
+    pub fn new(batches: u64) -> Self {
+        Self { batches: batches.max(1), next: 0 }
+    }
+
+    pub fn batches(&self) -> u64 {
+        self.batches
+    }
+
+    /// Mints of the next slice, advancing to the one after it. `mints` must
+    /// be sorted so the slices stay stable between refreshes.
+    pub fn select(&mut self, mints: &[Pubkey]) -> Vec<Pubkey> {
+        if self.batches == 1 {
+            return mints.to_vec();
+        }
+        let slice = self.next;
+        self.next = (self.next + 1) % self.batches;
+        mints
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index as u64 % self.batches == slice)
+            .map(|(_, mint)| *mint)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn every_mint_is_refreshed_exactly_once_per_interval_in_bounded_batches() {
+        let mut mints: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        mints.sort();
+        let mut stagger = RefreshStagger::new(3);
+
+        let mut first_interval = Vec::new();
+        for _ in 0..2 {
+            let mut refreshed: HashMap<Pubkey, usize> = HashMap::new();
+            let slices: Vec<Vec<Pubkey>> = (0..stagger.batches()).map(|_| stagger.select(&mints)).collect();
+            for slice in &slices {
+                assert!(slice.len() <= 4, "slice of {} mints exceeds ceil(10 / 3)", slice.len());
+                for mint in slice {
+                    *refreshed.entry(*mint).or_default() += 1;
+                }
+            }
+            assert_eq!(refreshed.len(), mints.len());
+            assert!(refreshed.values().all(|count| *count == 1));
+            // The next interval deals the same slices in the same order
+            if first_interval.is_empty() {
+                first_interval = slices;
+            } else {
+                assert_eq!(slices, first_interval);
+            }
+        }
+    }
+
+    #[test]
+    fn a_single_batch_refreshes_every_mint_each_time() {
+        let mints: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        for batches in [0, 1] {
+            let mut stagger = RefreshStagger::new(batches);
+            assert_eq!(stagger.batches(), 1);
+            assert_eq!(stagger.select(&mints), mints);
+            assert_eq!(stagger.select(&mints), mints);
+        }
+    }
+}
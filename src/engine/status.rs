@@ -0,0 +1,91 @@
+use crate::confirm::VersionFingerprint;
+use crate::engine::capability::{CapabilityRegistry, DexCapability};
+use crate::engine::compute_profile::ComputeUnitTable;
+use crate::engine::types::DexType;
+use crate::partition::Partition;
+use crate::persist::atomic_write_json;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What the engine can do per venue and what each venue's swaps cost in
+/// compute units, written for operators and dashboards
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineStatus {
+    pub updated_at: u64,
+    pub fingerprint: String, // Commit and config hash the engine runs with
+    pub venues: BTreeMap<DexType, DexCapability>,
+    pub compute_units: ComputeUnitTable, // Measured from landed transactions
+    pub instance_index: u64,
+    pub instance_count: u64, // 1 when the universe is not partitioned
+}
+
+impl EngineStatus {
+    pub fn new(capabilities: &CapabilityRegistry, compute_units: &ComputeUnitTable, fingerprint: &VersionFingerprint, now_unix: u64) -> Self {
+        Self {
+            updated_at: now_unix,
+            fingerprint: fingerprint.to_string(),
+            venues: capabilities.snapshot(),
+            compute_units: compute_units.clone(),
+            instance_index: 0,
+            instance_count: 1,
+        }
+    }
+
+    /// Report which share of a partitioned universe this instance trades
+    pub fn with_partition(mut self, partition: Option<&Partition>) -> Self {
+        if let Some(partition) = partition {
+            self.instance_index = partition.index();
+            self.instance_count = partition.count();
+        }
+        self
+    }
+
+    pub async fn save(&self, path: &str) -> Result<()> {
+        atomic_write_json(path, self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::compute_profile::ComputeProfile;
+
+    #[test]
+    fn the_status_carries_each_venues_compute_estimate() {
+        let mut compute_units = ComputeUnitTable::default();
+        compute_units.record(&ComputeProfile { legs: vec![(DexType::Whirlpool, 61_000)], overhead: 15_000 });
+
+        let status = EngineStatus::new(&CapabilityRegistry::from_registered_builders(), &compute_units, &VersionFingerprint::new(b""), 1_700_000_000);
+        let json = serde_json::to_value(&status).unwrap();
+
+        assert_eq!(json["compute_units"]["venues"]["Whirlpool"]["units"], 61_000.0);
+        assert_eq!(json["compute_units"]["venues"]["Whirlpool"]["samples"], 1);
+        assert_eq!(json["compute_units"]["overhead"]["units"], 15_000.0);
+        assert!(json["venues"]["Whirlpool"].is_object());
+    }
+
+    #[test]
+    fn the_status_carries_the_version_fingerprint() {
+        let fingerprint = VersionFingerprint::new(b"[bot]");
+        let status = EngineStatus::new(&CapabilityRegistry::from_registered_builders(), &ComputeUnitTable::default(), &fingerprint, 0);
+        let json = serde_json::to_value(&status).unwrap();
+
+        assert_eq!(json["fingerprint"], fingerprint.to_string());
+        assert!(json["fingerprint"].as_str().unwrap().starts_with(&format!("{}+", fingerprint.code)));
+    }
+
+    #[test]
+    fn the_status_names_the_instance_of_a_partitioned_universe() {
+        let capabilities = CapabilityRegistry::from_registered_builders();
+        let compute_units = ComputeUnitTable::default();
+
+        let single = serde_json::to_value(EngineStatus::new(&capabilities, &compute_units, &VersionFingerprint::new(b""), 0).with_partition(None)).unwrap();
+        assert_eq!((single["instance_index"].as_u64(), single["instance_count"].as_u64()), (Some(0), Some(1)));
+
+        let partition = Partition::new(2, 3, false).unwrap();
+        let status = EngineStatus::new(&capabilities, &compute_units, &VersionFingerprint::new(b""), 0).with_partition(Some(&partition));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!((json["instance_index"].as_u64(), json["instance_count"].as_u64()), (Some(2), Some(3)));
+    }
+}
@@ -0,0 +1,412 @@
+use crate::engine::prefetch::PrefetchedAccounts;
+use dashmap::DashMap;
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{select_all, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{debug, info, warn};
+
+/// First wait before reconnecting, doubled per failed attempt up to the configured cap
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Latest state of one streamed account
+#[derive(Debug, Clone)]
+pub struct StreamedAccount {
+    pub account: Account,
+    pub slot: Slot,
+    pub received_at: Instant,
+}
+
+/// Updates of one connection as (account, state, slot); closed when the
+/// connection drops
+pub type AccountUpdates = mpsc::Receiver<AccountUpdate>;
+
+type AccountUpdate = (Pubkey, Account, Slot);
+
+/// Opens one connection subscribed to a set of accounts
+pub trait AccountSubscriber: Send + Sync + 'static {
+    fn subscribe(&self, accounts: BTreeSet<Pubkey>) -> BoxFuture<'static, anyhow::Result<AccountUpdates>>;
+}
+
+/// `accountSubscribe` over the node's WebSocket endpoint
+pub struct PubsubSubscriber {
+    ws_url: String,
+    commitment: CommitmentConfig,
+}
+
+impl PubsubSubscriber {
+    pub fn new(ws_url: String, commitment: CommitmentConfig) -> Self {
+        Self { ws_url, commitment }
+    }
+}
+
+impl AccountSubscriber for PubsubSubscriber {
+    fn subscribe(&self, accounts: BTreeSet<Pubkey>) -> BoxFuture<'static, anyhow::Result<AccountUpdates>> {
+        let (ws_url, commitment) = (self.ws_url.clone(), self.commitment);
+        async move {
+            let (ready, subscribed) = oneshot::channel();
+            let (updates, received) = mpsc::channel(1024);
+            tokio::spawn(forward_updates(ws_url, commitment, accounts, ready, updates));
+            subscribed.await??;
+            Ok(received)
+        }
+        .boxed()
+    }
+}
+
+/// Why one connection of the stream ended
+enum StreamEnd {
+    Retrack, // The tracked set changed; reconnect with it
+    Dropped, // The server closed the connection
+    Closed,  // The `AccountStream` was dropped
+}
+
+/// Pool and vault accounts pushed over `accountSubscribe`, served to the
+/// refresh instead of RPC reads. Accounts the stream has not delivered
+/// within `max_age` are read over RPC again, which also re-seeds quiet
+/// accounts. A dropped connection is re-established with exponential
+/// backoff and the cache is cleared, so the next refresh resyncs every
+/// account over RPC instead of trusting state that missed updates.
+pub struct AccountStream {
+    cache: Arc<DashMap<Pubkey, StreamedAccount>>,
+    tracked: watch::Sender<BTreeSet<Pubkey>>,
+    disconnects: Arc<AtomicU64>,
+    max_age: Duration,
+}
+
+impl AccountStream {
+    pub fn spawn(ws_url: String, commitment: CommitmentConfig, max_age: Duration, max_backoff: Duration) -> Self {
+        Self::spawn_with(PubsubSubscriber::new(ws_url, commitment), max_age, max_backoff)
+    }
+
+    /// Stream through `subscriber` instead of a WebSocket endpoint
+    pub fn spawn_with(subscriber: impl AccountSubscriber, max_age: Duration, max_backoff: Duration) -> Self {
+        let cache = Arc::new(DashMap::new());
+        let disconnects = Arc::new(AtomicU64::new(0));
+        let (tracked, tracked_rx) = watch::channel(BTreeSet::new());
+        tokio::spawn(run(subscriber, max_backoff.max(INITIAL_RECONNECT_BACKOFF), tracked_rx, cache.clone(), disconnects.clone()));
+        Self { cache, tracked, disconnects, max_age }
+    }
+
+    /// Subscribe to exactly `accounts`. A changed set reconnects with it.
+    pub fn track(&self, accounts: BTreeSet<Pubkey>) {
+        self.tracked.send_if_modified(|current| {
+            if *current == accounts {
+                return false;
+            }
+            *current = accounts;
+            true
+        });
+    }
+
+    /// Connections lost since startup
+    pub fn disconnects(&self) -> u64 {
+        self.disconnects.load(Ordering::Relaxed)
+    }
+
+    /// Cached accounts among `keys` received within the max age
+    pub fn fresh(&self, keys: &BTreeSet<Pubkey>) -> PrefetchedAccounts {
+        let mut fresh = PrefetchedAccounts::default();
+        for key in keys {
+            if let Some(entry) = self.cache.get(key) {
+                if entry.received_at.elapsed() <= self.max_age {
+                    fresh.accounts.insert(*key, entry.account.clone());
+                    fresh.slots.insert(*key, entry.slot);
+                }
+            }
+        }
+        fresh
+    }
+
+    /// Seed the cache with accounts read over RPC, so accounts that rarely
+    /// change are served until they age out
+    pub fn seed(&self, prefetched: &PrefetchedAccounts) {
+        for (key, account) in &prefetched.accounts {
+            let slot = prefetched.slots.get(key).copied().unwrap_or(0);
+            store(&self.cache, *key, account, slot);
+        }
+    }
+}
+
+/// Keep the newest state; an update from an older slot is ignored
+fn store(cache: &DashMap<Pubkey, StreamedAccount>, key: Pubkey, account: &Account, slot: Slot) {
+    let received = || StreamedAccount { account: account.clone(), slot, received_at: Instant::now() };
+    cache
+        .entry(key)
+        .and_modify(|entry| {
+            if slot >= entry.slot {
+                *entry = received();
+            }
+        })
+        .or_insert_with(received);
+}
+
+async fn run(
+    subscriber: impl AccountSubscriber,
+    max_backoff: Duration,
+    mut tracked: watch::Receiver<BTreeSet<Pubkey>>,
+    cache: Arc<DashMap<Pubkey, StreamedAccount>>,
+    disconnects: Arc<AtomicU64>,
+) {
+    let mut backoff = INITIAL_RECONNECT_BACKOFF;
+    loop {
+        let accounts = tracked.borrow_and_update().clone();
+        if accounts.is_empty() {
+            if tracked.changed().await.is_err() {
+                return;
+            }
+            continue;
+        }
+        let error = match stream_accounts(&subscriber, &accounts, &mut tracked, &cache).await {
+            Ok(StreamEnd::Retrack) => {
+                backoff = INITIAL_RECONNECT_BACKOFF;
+                continue;
+            }
+            Ok(StreamEnd::Closed) => return,
+            Ok(StreamEnd::Dropped) => {
+                backoff = INITIAL_RECONNECT_BACKOFF;
+                "connection closed by the server".to_string()
+            }
+            Err(e) => e.to_string(),
+        };
+        let count = disconnects.fetch_add(1, Ordering::Relaxed) + 1;
+        cache.clear();
+        warn!("⚠️ Account stream disconnected ({} so far), resubscribing in {:?}: {}", count, backoff, error);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(max_backoff);
+    }
+}
+
+async fn stream_accounts(
+    subscriber: &impl AccountSubscriber,
+    accounts: &BTreeSet<Pubkey>,
+    tracked: &mut watch::Receiver<BTreeSet<Pubkey>>,
+    cache: &DashMap<Pubkey, StreamedAccount>,
+) -> anyhow::Result<StreamEnd> {
+    let mut updates = subscriber.subscribe(accounts.clone()).await?;
+    info!("📡 Account stream subscribed to {} accounts", accounts.len());
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Some((pubkey, account, slot)) => store(cache, pubkey, &account, slot),
+                None => return Ok(StreamEnd::Dropped),
+            },
+            changed = tracked.changed() => return Ok(if changed.is_ok() { StreamEnd::Retrack } else { StreamEnd::Closed }),
+        }
+    }
+}
+
+/// Hold one PubSub connection, reporting whether every subscription was
+/// accepted and then forwarding updates until the server or the receiver
+/// closes
+async fn forward_updates(
+    ws_url: String,
+    commitment: CommitmentConfig,
+    accounts: BTreeSet<Pubkey>,
+    ready: oneshot::Sender<anyhow::Result<()>>,
+    updates: mpsc::Sender<AccountUpdate>,
+) {
+    let client = match PubsubClient::new(&ws_url).await {
+        Ok(client) => client,
+        Err(e) => {
+            let _ = ready.send(Err(e.into()));
+            return;
+        }
+    };
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(commitment),
+        ..Default::default()
+    };
+    {
+        let mut streams = Vec::with_capacity(accounts.len());
+        let mut subscribed = Ok(());
+        for account in &accounts {
+            match client.account_subscribe(account, Some(config.clone())).await {
+                Ok((stream, _unsubscribe)) => {
+                    let account = *account;
+                    streams.push(stream.map(move |update| (account, update)));
+                }
+                Err(e) => {
+                    subscribed = Err(e.into());
+                    break;
+                }
+            }
+        }
+        let failed = subscribed.is_err();
+        if ready.send(subscribed).is_ok() && !failed {
+            let mut stream = select_all(streams);
+            loop {
+                tokio::select! {
+                    update = stream.next() => match update {
+                        Some((pubkey, update)) => match update.value.decode::<Account>() {
+                            Some(account) => {
+                                if updates.send((pubkey, account, update.context.slot)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            None => debug!("Undecodable account update for {}", pubkey),
+                        },
+                        None => break,
+                    },
+                    _ = updates.closed() => break,
+                }
+            }
+        }
+    }
+    let _ = client.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+    use crate::engine::graph::PriceGraph;
+    use crate::engine::prefetch::PrefetchPlanner;
+    use crate::mint_registry::MintRegistry;
+    use crate::pools::MintPoolData;
+    use crate::rpc::MockRpc;
+    use std::sync::Mutex;
+
+    /// Hands out connections the test can feed and drop
+    #[derive(Clone, Default)]
+    struct MockSubscriber {
+        subscriptions: Arc<Mutex<Vec<BTreeSet<Pubkey>>>>,
+        connections: Arc<Mutex<Vec<mpsc::Sender<AccountUpdate>>>>,
+    }
+
+    impl MockSubscriber {
+        fn subscriptions(&self) -> Vec<BTreeSet<Pubkey>> {
+            self.subscriptions.lock().unwrap().clone()
+        }
+
+        /// Push an update over the latest connection
+        async fn push(&self, pubkey: Pubkey, lamports: u64, slot: Slot) {
+            self.push_account(pubkey, Account { lamports, ..Account::default() }, slot).await;
+        }
+
+        async fn push_account(&self, pubkey: Pubkey, account: Account, slot: Slot) {
+            let connection = self.connections.lock().unwrap().last().cloned().unwrap();
+            connection.send((pubkey, account, slot)).await.unwrap();
+        }
+
+        /// Close every open connection, as a server going away would
+        fn drop_connections(&self) {
+            self.connections.lock().unwrap().clear();
+        }
+    }
+
+    impl AccountSubscriber for MockSubscriber {
+        fn subscribe(&self, accounts: BTreeSet<Pubkey>) -> BoxFuture<'static, anyhow::Result<AccountUpdates>> {
+            let (updates, received) = mpsc::channel(16);
+            self.subscriptions.lock().unwrap().push(accounts);
+            self.connections.lock().unwrap().push(updates);
+            async move { Ok(received) }.boxed()
+        }
+    }
+
+    async fn eventually(mut reached: impl FnMut() -> bool) {
+        for _ in 0..300 {
+            if reached() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached within 3s");
+    }
+
+    #[tokio::test]
+    async fn a_dropped_connection_resubscribes_every_account_and_resyncs() {
+        let subscriber = MockSubscriber::default();
+        let stream = AccountStream::spawn_with(subscriber.clone(), Duration::from_secs(60), Duration::from_secs(5));
+        let (pool, vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let accounts = BTreeSet::from([pool, vault]);
+
+        stream.track(accounts.clone());
+        eventually(|| subscriber.subscriptions().len() == 1).await;
+        subscriber.push(pool, 7, 100).await;
+        eventually(|| stream.fresh(&accounts).accounts.len() == 1).await;
+        assert_eq!(stream.fresh(&accounts).slots[&pool], 100);
+
+        subscriber.drop_connections();
+        eventually(|| subscriber.subscriptions().len() == 2).await;
+        assert_eq!(subscriber.subscriptions()[1], accounts, "every tracked account is subscribed again");
+        assert_eq!(stream.disconnects(), 1);
+        // Nothing survives the drop, so the next refresh re-reads every account over RPC
+        assert!(stream.fresh(&accounts).accounts.is_empty());
+
+        subscriber.push(vault, 9, 101).await;
+        eventually(|| stream.fresh(&accounts).accounts.len() == 1).await;
+        assert_eq!(stream.fresh(&accounts).accounts[&vault].lamports, 9);
+    }
+
+    #[tokio::test]
+    async fn retracking_resubscribes_without_counting_a_disconnect() {
+        let subscriber = MockSubscriber::default();
+        let stream = AccountStream::spawn_with(subscriber.clone(), Duration::from_secs(60), Duration::from_secs(5));
+        let (pool, vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        stream.track(BTreeSet::from([pool]));
+        eventually(|| subscriber.subscriptions().len() == 1).await;
+        stream.track(BTreeSet::from([pool, vault]));
+        eventually(|| subscriber.subscriptions().len() == 2).await;
+
+        assert_eq!(subscriber.subscriptions()[1], BTreeSet::from([pool, vault]));
+        assert_eq!(stream.disconnects(), 0);
+    }
+
+    fn token_account(mint: Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[tokio::test]
+    async fn a_warm_stream_prices_the_graph_without_any_rpc_call() {
+        let (token, pool, token_vault, sol_vault) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+        let mut planner = PrefetchPlanner::new(false);
+        planner.add_mint(&pool_data);
+
+        let subscriber = MockSubscriber::default();
+        let stream = AccountStream::spawn_with(subscriber.clone(), Duration::from_secs(60), Duration::from_secs(5));
+        stream.track(planner.accounts().clone());
+        eventually(|| subscriber.subscriptions().len() == 1).await;
+        assert_eq!(*planner.accounts(), BTreeSet::from([token_vault, sol_vault]));
+        subscriber.push_account(token_vault, token_account(token, 4_000_000_000_000), 100).await;
+        subscriber.push_account(sol_vault, token_account(sol_mint(), 1_000_000_000), 100).await;
+        eventually(|| stream.fresh(planner.accounts()).accounts.len() == 2).await;
+
+        // The same steps as a refresh: only what the stream lacks goes to the node
+        let rpc = MockRpc::new().with_slot(100);
+        let streamed = stream.fresh(planner.accounts());
+        planner.retain_accounts(|key| !streamed.accounts.contains_key(key));
+        let mut prefetched = planner.fetch(&rpc).unwrap();
+        prefetched.merge(streamed);
+        // Decimals come from the registry, as mint accounts are not streamed
+        let path = std::env::temp_dir().join(format!("{}_mints.json", Pubkey::new_unique()));
+        let registry = format!(r#"{{ "{}": {{ "symbol": "TKN", "decimals": 6 }}, "{}": {{ "symbol": "SOL", "decimals": 9 }} }}"#, token, sol_mint());
+        std::fs::write(&path, registry).unwrap();
+        let graph = PriceGraph::new();
+        graph.set_mint_registry(&MintRegistry::from_file(path.to_str().unwrap()).unwrap());
+        graph.set_prefetched(prefetched);
+        graph.update_from_mint_pool_data(&pool_data, &rpc);
+
+        assert_eq!(rpc.calls(), 0);
+        let priced = graph.neighbors(&token).into_iter().any(|(_, edge)| edge.pool_pubkey == pool);
+        assert!(priced, "the pool was not priced from the streamed accounts");
+    }
+}
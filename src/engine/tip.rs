@@ -0,0 +1,52 @@
+use solana_sdk::clock::Slot;
+
+/// What to do with a bundle that has not been seen landing yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipDecision {
+    Wait,                  // Still within the inclusion window
+    Resubmit { tip: u64 }, // Window passed, resend with this tip
+    GiveUp,                // Out of attempts, or the escalated tip would eat too much profit
+}
+
+/// Escalates the tip of a bundle that lost the auction. A bundle not included
+/// within `inclusion_slots` is resent with its tip multiplied by `multiplier`,
+/// at most `max_attempts` times in total, and never with a tip above
+/// `max_profit_fraction` of the profit expected from the latest re-quote.
+#[derive(Debug, Clone, Copy)]
+pub struct TipEscalation {
+    pub inclusion_slots: u64,
+    pub multiplier: f64,
+    pub max_profit_fraction: f64,
+    pub max_attempts: u32,
+}
+
+impl TipEscalation {
+    /// Highest tip allowed against `expected_profit`
+    pub fn tip_cap(&self, expected_profit: u64) -> u64 {
+        (expected_profit as f64 * self.max_profit_fraction.clamp(0.0, 1.0)) as u64
+    }
+
+    /// Tip for the attempt after one tipped `previous_tip`, if still under the cap
+    pub fn escalated_tip(&self, previous_tip: u64, expected_profit: u64) -> Option<u64> {
+        let escalated = (previous_tip as f64 * self.multiplier.max(1.0)).ceil() as u64;
+        let escalated = escalated.max(previous_tip.saturating_add(1));
+        (escalated <= self.tip_cap(expected_profit)).then_some(escalated)
+    }
+
+    /// Decide on attempt number `attempt` (1 for the first submission), sent at
+    /// `sent_slot` with `tip`. `expected_profit` must come from a re-quote that
+    /// already passed the staleness and risk checks; a re-quote that fails
+    /// them should abandon the bundle instead of asking.
+    pub fn decide(&self, attempt: u32, tip: u64, sent_slot: Slot, current_slot: Slot, expected_profit: u64) -> TipDecision {
+        if current_slot.saturating_sub(sent_slot) < self.inclusion_slots {
+            return TipDecision::Wait;
+        }
+        if attempt >= self.max_attempts {
+            return TipDecision::GiveUp;
+        }
+        match self.escalated_tip(tip, expected_profit) {
+            Some(tip) => TipDecision::Resubmit { tip },
+            None => TipDecision::GiveUp,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::constants::{sol_mint, usd1_mint, usdc_mint};
+use crate::engine::compute_profile::ComputeUnitTable;
+use crate::engine::types::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+
+/// Network fee per signature; cycles are sent signed by the wallet alone
+pub const BASE_FEE_LAMPORTS: u64 = 5_000;
+/// Rent-exempt minimum of a 165-byte SPL token account
+pub const TOKEN_ACCOUNT_RENT_LAMPORTS: u64 = 2_039_280;
+
+/// What sending one cycle is expected to cost, by component
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxCostEstimate {
+    pub base_fee: u64,
+    pub priority_fee: u64,
+    pub tip: u64,
+    pub ata_rent: u64, // Token accounts created for intermediate mints
+}
+
+impl TxCostEstimate {
+    pub fn total(&self) -> u64 {
+        self.base_fee + self.priority_fee + self.tip + self.ata_rent
+    }
+}
+
+/// Prices the transaction that would carry a cycle. The priority fee is the
+/// compute unit price times the limit the route would request; base mint
+/// accounts are created at startup, so only intermediate mints pay rent.
+#[derive(Debug, Clone)]
+pub struct TxCostModel {
+    pub compute_unit_price: u64, // Micro-lamports per compute unit
+    pub fallback_compute_unit_limit: u32,
+    pub tip_lamports: u64,
+    pub ata_rent_lamports: u64,
+}
+
+impl TxCostModel {
+    pub fn estimate(&self, cycle: &ArbitrageCycle, compute_units: &ComputeUnitTable) -> TxCostEstimate {
+        let venues: Vec<DexType> = cycle.legs.iter().map(|leg| leg.dex_type).collect();
+        let limit = compute_units.compute_unit_limit(&venues, self.fallback_compute_unit_limit);
+        let new_accounts: BTreeSet<&Pubkey> = cycle
+            .legs
+            .iter()
+            .map(|leg| &leg.to_mint)
+            .filter(|mint| **mint != sol_mint() && **mint != usdc_mint() && **mint != usd1_mint())
+            .collect();
+        TxCostEstimate {
+            base_fee: BASE_FEE_LAMPORTS,
+            priority_fee: (limit as u128 * self.compute_unit_price as u128).div_ceil(1_000_000) as u64,
+            tip: self.tip_lamports,
+            ata_rent: new_accounts.len() as u64 * self.ata_rent_lamports,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::opportunity::OpportunityTracker;
+
+    fn cycle(token: Pubkey, profit: u64) -> ArbitrageCycle {
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg(sol_mint(), token), leg(token, usdc_mint()), leg(usdc_mint(), sol_mint())],
+            total_profit_bps: 40,
+            estimated_profit_lamports: profit,
+            total_hops: 3,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn model() -> TxCostModel {
+        TxCostModel {
+            compute_unit_price: 10_000,
+            fallback_compute_unit_limit: 400_000,
+            tip_lamports: 1_000,
+            ata_rent_lamports: TOKEN_ACCOUNT_RENT_LAMPORTS,
+        }
+    }
+
+    #[test]
+    fn the_estimate_adds_base_fee_priority_fee_tip_and_intermediate_rent() {
+        let estimate = model().estimate(&cycle(Pubkey::new_unique(), 0), &ComputeUnitTable::default());
+
+        // 400k units at 10k micro-lamports each; only the token needs an account
+        assert_eq!(estimate, TxCostEstimate { base_fee: 5_000, priority_fee: 4_000, tip: 1_000, ata_rent: TOKEN_ACCOUNT_RENT_LAMPORTS });
+        assert_eq!(estimate.total(), 5_000 + 4_000 + 1_000 + TOKEN_ACCOUNT_RENT_LAMPORTS);
+    }
+
+    #[test]
+    fn the_opportunity_record_nets_the_estimated_cost_from_gross_profit() {
+        let mut cycle = cycle(Pubkey::new_unique(), 3_000_000);
+        cycle.estimated_tx_cost_lamports = model().estimate(&cycle, &ComputeUnitTable::default()).total();
+        let mut tracker = OpportunityTracker::new();
+        tracker.update(std::slice::from_ref(&cycle));
+
+        tracker.record_sizing(&cycle);
+
+        let record = tracker.get(cycle.cycle_id()).unwrap();
+        assert_eq!(record.gross_profit_lamports, 3_000_000);
+        assert_eq!(record.estimated_tx_cost_lamports, cycle.estimated_tx_cost_lamports);
+        assert_eq!(record.net_profit_lamports(), 3_000_000 - cycle.estimated_tx_cost_lamports as i64);
+        assert_eq!(record.net_profit_lamports(), cycle.net_profit_lamports());
+    }
+}
@@ -1,23 +1,254 @@
+use crate::math::{mul_price, ConversionError};
 use solana_program::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::hashv;
 use std::fmt::Debug;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DexType {
     Pump, RaydiumV4, RaydiumCp, RaydiumClmm,
     MeteoraDlmm, MeteoraDamm, MeteoraDammV2,
     Whirlpool, Vertigo, Heaven, Futarchy, Humidifi,
-    PancakeSwap, Byreal,
+    PancakeSwap, Byreal, Lifinity, Phoenix, PumpBondingCurve,
 }
 
+impl DexType {
+    /// Program that owns pools of this venue
+    pub fn program_id(&self) -> Pubkey {
+        use crate::dex::*;
+        match self {
+            DexType::Pump => pump::pump_program_id(),
+            DexType::RaydiumV4 => raydium::raydium_program_id(),
+            DexType::RaydiumCp => raydium::raydium_cp_program_id(),
+            DexType::RaydiumClmm => raydium::raydium_clmm_program_id(),
+            DexType::MeteoraDlmm => meteora::constants::dlmm_program_id(),
+            DexType::MeteoraDamm => meteora::constants::damm_program_id(),
+            DexType::MeteoraDammV2 => meteora::constants::damm_v2_program_id(),
+            DexType::Whirlpool => whirlpool::constants::whirlpool_program_id(),
+            DexType::Vertigo => vertigo::vertigo_program_id(),
+            DexType::Heaven => heaven::heaven_program_id(),
+            DexType::Futarchy => futarchy::futarchy_program_id(),
+            DexType::Humidifi => humidifi::humidifi_program_id(),
+            DexType::PancakeSwap => pancakeswap::pancakeswap_program_id(),
+            DexType::Byreal => byreal::byreal_program_id(),
+            DexType::Lifinity => lifinity::lifinity_program_id(),
+            DexType::Phoenix => phoenix::phoenix_program_id(),
+            DexType::PumpBondingCurve => pump::pump_bonding_curve_program_id(),
+        }
+    }
+
+    /// Venue family name used in config, grouping pool types of the same DEX
+    pub fn venue(&self) -> &'static str {
+        match self {
+            DexType::Pump | DexType::PumpBondingCurve => "pump",
+            DexType::RaydiumV4 | DexType::RaydiumCp | DexType::RaydiumClmm => "raydium",
+            DexType::MeteoraDlmm | DexType::MeteoraDamm | DexType::MeteoraDammV2 => "meteora",
+            DexType::Whirlpool => "orca",
+            DexType::Vertigo => "vertigo",
+            DexType::Heaven => "heaven",
+            DexType::Futarchy => "futarchy",
+            DexType::Humidifi => "humidifi",
+            DexType::PancakeSwap => "pancakeswap",
+            DexType::Byreal => "byreal",
+            DexType::Lifinity => "lifinity",
+            DexType::Phoenix => "phoenix",
+        }
+    }
+
+    /// Venue is a central limit order book: edges quote the top of book and
+    /// a swap through it needs an order book instruction, not an AMM swap
+    pub fn is_order_book(&self) -> bool {
+        matches!(self, DexType::Phoenix)
+    }
+
+    /// Venue whose pools are owned by `program_id`
+    pub fn from_program_id(program_id: &Pubkey) -> Option<Self> {
+        Self::ALL.iter().copied().find(|dex_type| dex_type.program_id() == *program_id)
+    }
+
+    pub const ALL: [DexType; 17] = [
+        DexType::Pump, DexType::RaydiumV4, DexType::RaydiumCp, DexType::RaydiumClmm,
+        DexType::MeteoraDlmm, DexType::MeteoraDamm, DexType::MeteoraDammV2,
+        DexType::Whirlpool, DexType::Vertigo, DexType::Heaven, DexType::Futarchy, DexType::Humidifi,
+        DexType::PancakeSwap, DexType::Byreal, DexType::Lifinity, DexType::Phoenix, DexType::PumpBondingCurve,
+    ];
+}
+
+/// Token account balance in base units, with its mint's decimals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenBalance {
+    /// Balance in whole tokens, e.g. 1.5 for 1_500_000 of a 6-decimal mint
+    pub fn ui_amount(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+/// Modeled result of trading an amount along an edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuoteResult {
+    pub amount_out: u64,
+    pub effective_price: f64, // amount_out / amount_in
+    pub impact_bps: u64,      // How far effective_price falls short of the spot price
+}
+
+/// Reserves and fee for one direction of an x*y=k pool, as read on the
+/// pool's last refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantProductQuote {
+    pub reserve_in: u64,
+    pub reserve_out: u64,
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+}
+
+impl ConstantProductQuote {
+    /// Exact-input output in integer math: the fee is taken from the input,
+    /// rounded up, and the output rounded down, as Raydium's swap_base_in does
+    pub fn amount_out(&self, amount_in: u64) -> u64 {
+        if self.fee_denominator == 0 {
+            return 0;
+        }
+        let amount_in = amount_in as u128;
+        let fee = (amount_in * self.fee_numerator as u128).div_ceil(self.fee_denominator as u128);
+        let amount_in_less_fee = amount_in.saturating_sub(fee);
+        let denominator = self.reserve_in as u128 + amount_in_less_fee;
+        if denominator == 0 {
+            return 0;
+        }
+        (self.reserve_out as u128 * amount_in_less_fee / denominator) as u64
+    }
+}
+
+/// Virtual reserves of a pump.fun bonding curve for one direction, as read
+/// on the curve's last refresh. Unlike a constant-product pool the fee is
+/// always taken in SOL, on top of a buy's input and out of a sell's output,
+/// and a buy cannot take more tokens than the curve has left for sale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BondingCurveQuote {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_token_reserves: u64,
+    pub fee_bps: u64,
+    pub buy: bool, // SOL in, tokens out
+}
+
+impl BondingCurveQuote {
+    /// Exact-input output in integer math, rounded down
+    pub fn amount_out(&self, amount_in: u64) -> u64 {
+        let (sol, token) = (self.virtual_sol_reserves as u128, self.virtual_token_reserves as u128);
+        let amount_in = amount_in as u128;
+        if self.buy {
+            let sol_in = amount_in * 10_000 / (10_000 + self.fee_bps as u128);
+            if sol + sol_in == 0 {
+                return 0;
+            }
+            let tokens_out = token * sol_in / (sol + sol_in);
+            tokens_out.min(self.real_token_reserves as u128) as u64
+        } else {
+            if token + amount_in == 0 {
+                return 0;
+            }
+            let sol_out = sol * amount_in / (token + amount_in);
+            let fee = (sol_out * self.fee_bps as u128).div_ceil(10_000);
+            sol_out.saturating_sub(fee) as u64
+        }
+    }
+}
+
+/// A directed quote through one pool. The spot price is only valid for an
+/// infinitesimal trade: size a trade with `AmountOptimizer::quote`, never by
+/// multiplying an amount by `spot_price`.
 #[derive(Debug, Clone)]
 pub struct PoolEdge {
     pub pool_pubkey: Pubkey,
+    pub to_mint: Pubkey,         // Mint received when trading along this edge
     pub dex_type: DexType,
-    pub price: f64,              // price = output_mint / input_mint
+    pub program_id: Pubkey,      // Program expected to own the pool account
+    pub(crate) price: f64,       // Spot price = output_mint / input_mint, raw for the detector's weights
     pub liquidity_usd: f64,      // Available liquidity depth
     pub fee_bps: u64,            // Fee in basis points
     pub inverse_fee_bps: u64,    // Fee for reverse direction
     pub token_program: Pubkey,   // Token or Token-2022
+    pub transfer_fee_bps: u64,   // Token-2022 transfer fees of both mints, set by `PriceGraph::add_edge`
+    pub near_graduation: bool,   // Bonding curve close to completing; a swap may land after it has
+}
+
+impl PoolEdge {
+    pub fn new(
+        pool_pubkey: Pubkey,
+        to_mint: Pubkey,
+        dex_type: DexType,
+        spot_price: f64,
+        liquidity_usd: f64,
+        fee_bps: u64,
+        token_program: Pubkey,
+    ) -> Self {
+        Self {
+            pool_pubkey,
+            to_mint,
+            dex_type,
+            program_id: dex_type.program_id(),
+            price: spot_price,
+            liquidity_usd,
+            fee_bps,
+            inverse_fee_bps: fee_bps,
+            token_program,
+            transfer_fee_bps: 0,
+            near_graduation: false,
+        }
+    }
+
+    /// Output mint per input mint for an infinitesimal trade
+    pub fn spot_price(&self) -> f64 {
+        self.price
+    }
+
+    /// Spot price after the pool fee and any transfer fees
+    pub fn net_rate(&self) -> f64 {
+        let fee_bps = self.fee_bps.saturating_add(self.transfer_fee_bps).min(10_000);
+        self.price * (10_000 - fee_bps) as f64 / 10_000.0
+    }
+
+    /// Detector weight, -ln(net_rate): a loop whose weights sum below zero
+    /// returns more than it puts in after fees
+    pub fn weight(&self) -> f64 {
+        -self.net_rate().ln()
+    }
+
+    /// Quote `amount_in` at the spot price less each of `costs_bps` (fees,
+    /// slippage, haircuts) deducted in turn. Callers own the cost model; see
+    /// `AmountOptimizer::quote`.
+    pub fn quote_with_costs(&self, amount_in: u64, costs_bps: &[u64]) -> Result<QuoteResult, ConversionError> {
+        let cost_multiplier: f64 = costs_bps
+            .iter()
+            .map(|cost_bps| (10_000 - (*cost_bps).min(10_000)) as f64 / 10_000.0)
+            .product();
+        let amount_out = mul_price(amount_in, self.price * cost_multiplier)?;
+        Ok(self.quote_result(amount_in, amount_out))
+    }
+
+    /// Like `quote_with_costs`, but from an output already walked through the
+    /// pool's liquidity (fee and price impact included) instead of the spot price
+    pub fn quote_walked(&self, amount_in: u64, walked_out: u64, costs_bps: &[u64]) -> Result<QuoteResult, ConversionError> {
+        let cost_multiplier: f64 = costs_bps
+            .iter()
+            .map(|cost_bps| (10_000 - (*cost_bps).min(10_000)) as f64 / 10_000.0)
+            .product();
+        let amount_out = mul_price(walked_out, cost_multiplier)?;
+        Ok(self.quote_result(amount_in, amount_out))
+    }
+
+    fn quote_result(&self, amount_in: u64, amount_out: u64) -> QuoteResult {
+        let effective_price = if amount_in == 0 { 0.0 } else { amount_out as f64 / amount_in as f64 };
+        let shortfall = (1.0 - effective_price / self.price) * 10_000.0;
+        let impact_bps = if shortfall.is_finite() { shortfall.clamp(0.0, 10_000.0).round() as u64 } else { 10_000 };
+        QuoteResult { amount_out, effective_price, impact_bps }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,19 +257,261 @@ pub struct SwapLeg {
     pub to_mint: Pubkey,
     pub pool_pubkey: Pubkey,
     pub dex_type: DexType,
+    pub program_id: Pubkey, // Program the pool was priced as owned by, from its edge
     pub amount_in: u64,
     pub estimated_amount_out: u64,
+    pub quote_haircut_bps: u64, // Haircut applied to this leg's modeled output
 }
 
 #[derive(Debug, Clone)]
 pub struct ArbitrageCycle {
     pub legs: Vec<SwapLeg>,
-    pub total_profit_bps: i64,   // Profit in basis points
+    pub total_profit_bps: i64,   // Profit in basis points, after pool fees
     pub estimated_profit_lamports: u64,
     pub total_hops: usize,
+    pub observational: bool,     // Contains a leg with no executor support; reported, never sent
+    pub priority_penalty_bps: u64, // Lowers the cycle's claim on capital (unreliable venues), not its profit
+    pub estimated_tx_cost_lamports: u64, // Fees, tip and account rent to send it; set once sized
+}
+
+impl ArbitrageCycle {
+    /// Mint the cycle starts and ends in; its amounts and profit are in this
+    /// mint's native units
+    pub fn base_mint(&self) -> Pubkey {
+        self.legs.first().map(|leg| leg.from_mint).unwrap_or_default()
+    }
+
+    /// Sized profit minus the estimated transaction cost
+    pub fn net_profit_lamports(&self) -> i64 {
+        self.estimated_profit_lamports as i64 - self.estimated_tx_cost_lamports as i64
+    }
+
+    /// Sum of the quote haircuts applied across legs
+    pub fn quote_haircut_bps(&self) -> u64 {
+        self.legs.iter().map(|leg| leg.quote_haircut_bps).sum()
+    }
+
+    /// Stable identifier for the economic opportunity: a hash of each leg's
+    /// (from_mint, pool) in trade order, rotated to start at the smallest
+    /// pair so the same loop entered at a different leg maps to the same id.
+    /// The input mints keep the two directions of a loop apart.
+    pub fn cycle_id(&self) -> u64 {
+        let legs: Vec<(&Pubkey, &Pubkey)> = self.legs.iter().map(|leg| (&leg.pool_pubkey, &leg.from_mint)).collect();
+        let start = legs
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, leg)| **leg)
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(2 * legs.len());
+        for i in 0..legs.len() {
+            let (pool, from_mint) = legs[(start + i) % legs.len()];
+            parts.push(from_mint.as_ref());
+            parts.push(pool.as_ref());
+        }
+
+        let hash = hashv(&parts);
+        let mut id = [0u8; 8];
+        id.copy_from_slice(&hash.as_ref()[..8]);
+        u64::from_le_bytes(id)
+    }
+
+    /// Check that the legs chain mint to mint and return to `start_mint`:
+    /// each leg must start where the previous one ended, the first leg must
+    /// start at `start_mint` and the last must end there.
+    pub fn validate_closed(&self, start_mint: Pubkey) -> anyhow::Result<()> {
+        let (Some(first), Some(last)) = (self.legs.first(), self.legs.last()) else {
+            anyhow::bail!("cycle has no legs");
+        };
+        if first.from_mint != start_mint {
+            anyhow::bail!(
+                "first leg through pool {} starts at {}, expected {}",
+                first.pool_pubkey, first.from_mint, start_mint
+            );
+        }
+        for (i, pair) in self.legs.windows(2).enumerate() {
+            if pair[0].to_mint != pair[1].from_mint {
+                anyhow::bail!(
+                    "leg {} through pool {} ends at {} but leg {} through pool {} starts at {}",
+                    i, pair[0].pool_pubkey, pair[0].to_mint, i + 1, pair[1].pool_pubkey, pair[1].from_mint
+                );
+            }
+        }
+        if last.to_mint != start_mint {
+            anyhow::bail!(
+                "last leg through pool {} ends at {}, expected {}",
+                last.pool_pubkey, last.to_mint, start_mint
+            );
+        }
+        if self.total_hops != self.legs.len() {
+            anyhow::bail!("cycle reports {} hops but has {} legs", self.total_hops, self.legs.len());
+        }
+        Ok(())
+    }
+
+    /// A leg trades through an order book, so executing the cycle needs a
+    /// swap-via-CLOB instruction for it
+    pub fn needs_clob_swap(&self) -> bool {
+        self.legs.iter().any(|leg| leg.dex_type.is_order_book())
+    }
+
+    /// Number of different mints the legs trade through. TOKEN -> SOL -> TOKEN
+    /// over two pools counts 2; a loop that never leaves its start mint counts 1.
+    pub fn distinct_mints(&self) -> usize {
+        let mut mints: Vec<Pubkey> = self.legs.iter().flat_map(|leg| [leg.from_mint, leg.to_mint]).collect();
+        mints.sort_unstable();
+        mints.dedup();
+        mints.len()
+    }
+}
+
+/// Minimum profit required of a cycle, scaled by hop count:
+/// `base_bps + per_hop_increment_bps * (hops - 2)`
+#[derive(Debug, Clone, Copy)]
+pub struct ProfitThreshold {
+    pub base_bps: i64,
+    pub per_hop_increment_bps: i64,
+}
+
+impl ProfitThreshold {
+    pub fn flat(base_bps: i64) -> Self {
+        Self { base_bps, per_hop_increment_bps: 0 }
+    }
+
+    pub fn for_hops(&self, hops: usize) -> i64 {
+        self.base_bps + self.per_hop_increment_bps * hops.saturating_sub(2) as i64
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct TokenNode {
     pub mint: Pubkey,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leg(from_mint: Pubkey, to_mint: Pubkey, pool_pubkey: Pubkey) -> SwapLeg {
+        SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey,
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        }
+    }
+
+    fn cycle(legs: Vec<SwapLeg>) -> ArbitrageCycle {
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    #[test]
+    fn cycle_id_ignores_the_entry_leg() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (p1, p2, p3) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let from_a = cycle(vec![leg(a, b, p1), leg(b, c, p2), leg(c, a, p3)]);
+        let from_b = cycle(vec![leg(b, c, p2), leg(c, a, p3), leg(a, b, p1)]);
+        assert_eq!(from_a.cycle_id(), from_b.cycle_id());
+    }
+
+    #[test]
+    fn cycle_id_tells_directions_apart() {
+        let (sol, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (p1, p2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // Buy in p1 and sell in p2, or the other way round: same pools, opposite trades
+        let forward = cycle(vec![leg(sol, token, p1), leg(token, sol, p2)]);
+        let reverse = cycle(vec![leg(sol, token, p2), leg(token, sol, p1)]);
+        assert_ne!(forward.cycle_id(), reverse.cycle_id());
+    }
+
+    #[test]
+    fn constant_product_quotes_match_raydium_swap_base_in() {
+        let raydium = |reserve_in, reserve_out| ConstantProductQuote { reserve_in, reserve_out, fee_numerator: 25, fee_denominator: 10_000 };
+        // Fee ceil(amount_in * 25 / 10_000) off the input, output floored, as the program computes them
+        for (reserve_in, reserve_out, amount_in, amount_out) in [
+            (1_000_000_000, 4_000_000_000_000, 1_000_000, 3_986_023_941),
+            (500_000, 2_000_000, 10_000, 39_119),
+            (1_000_000_000, 1_000_000_000, 1, 0), // The 1-unit fee eats the whole input
+            (85_000_000_000, 3_100_000_000_000_000, 85_000_000_000, 1_548_060_075_093_867),
+        ] {
+            assert_eq!(raydium(reserve_in, reserve_out).amount_out(amount_in), amount_out, "{} in against {} / {}", amount_in, reserve_in, reserve_out);
+        }
+
+        // Walking the curve: each further unit buys less, and the pool is never drained
+        let quote = raydium(1_000_000_000, 4_000_000_000_000);
+        let outs: Vec<u64> = [1_000_000u64, 100_000_000, 1_000_000_000, u64::MAX].iter().map(|&amount| quote.amount_out(amount)).collect();
+        assert!(outs.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(outs[1] < 100 * outs[0]);
+        assert!(*outs.last().unwrap() < quote.reserve_out);
+        assert_eq!(ConstantProductQuote { fee_denominator: 0, ..quote }.amount_out(1_000_000), 0);
+    }
+
+    #[test]
+    fn bonding_curve_quotes_take_the_fee_in_sol_and_cap_buys_at_the_tokens_left() {
+        let curve = |buy, real_token_reserves| BondingCurveQuote {
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_token_reserves,
+            fee_bps: 100,
+            buy,
+        };
+
+        // A 1 SOL buy spends 1 SOL / 1.01 on the curve
+        assert_eq!(curve(true, 793_100_000_000_000).amount_out(1_000_000_000), 34_281_150_129_545);
+        // A sell takes 1% out of the SOL the curve pays
+        assert_eq!(curve(false, 793_100_000_000_000).amount_out(35_000_000_000_000), 938_176_894);
+        // Near graduation only the tokens left for sale can be bought
+        assert_eq!(curve(true, 1_000_000).amount_out(1_000_000_000), 1_000_000);
+    }
+
+    #[test]
+    fn a_contiguous_loop_back_to_the_start_is_closed() {
+        let (sol, usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (p1, p2, p3) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let triangle = cycle(vec![leg(sol, usdc, p1), leg(usdc, bonk, p2), leg(bonk, sol, p3)]);
+        triangle.validate_closed(sol).unwrap();
+        let round_trip = cycle(vec![leg(sol, usdc, p1), leg(usdc, sol, p2)]);
+        round_trip.validate_closed(sol).unwrap();
+    }
+
+    #[test]
+    fn malformed_cycles_are_rejected_with_the_offending_leg() {
+        let (sol, usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (p1, p2, p3) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let rejection = |cycle: ArbitrageCycle| cycle.validate_closed(sol).unwrap_err().to_string();
+
+        assert_eq!(rejection(cycle(vec![])), "cycle has no legs");
+
+        let wrong_start = rejection(cycle(vec![leg(usdc, bonk, p1), leg(bonk, sol, p2)]));
+        assert!(wrong_start.starts_with("first leg"), "{}", wrong_start);
+        assert!(wrong_start.contains(&p1.to_string()), "{}", wrong_start);
+
+        let gap = rejection(cycle(vec![leg(sol, usdc, p1), leg(bonk, sol, p2)]));
+        assert!(gap.starts_with("leg 0"), "{}", gap);
+        assert!(gap.contains(&p2.to_string()), "{}", gap);
+
+        let open = rejection(cycle(vec![leg(sol, usdc, p1), leg(usdc, bonk, p2)]));
+        assert!(open.starts_with("last leg"), "{}", open);
+
+        // The pre-fix reconstruction wrote the pool pubkey into both mints
+        let pool_as_mint = rejection(cycle(vec![leg(p1, p1, p1), leg(p2, p2, p2), leg(p3, p3, p3)]));
+        assert!(pool_as_mint.starts_with("first leg"), "{}", pool_as_mint);
+
+        let mut miscounted = cycle(vec![leg(sol, usdc, p1), leg(usdc, sol, p2)]);
+        miscounted.total_hops = 3;
+        assert_eq!(rejection(miscounted), "cycle reports 3 hops but has 2 legs");
+    }
+}
@@ -0,0 +1,895 @@
+use crate::bundle::BundleSender;
+use crate::config::Config;
+use crate::confirm::VersionFingerprint;
+use crate::constants::sol_mint;
+use crate::engine::compute_profile::{profile_transaction, ComputeProfile, ComputeUnitTable};
+use crate::engine::graph::PriceGraph;
+use crate::engine::reconcile::{reconcile, value_in_sol, BalanceSnapshot, KillSwitch, ReconciliationReport};
+use crate::engine::types::ArbitrageCycle;
+use crate::journal::{ExecutionJournal, JournalEntry, JournalEvent};
+use crate::ledger::{append_records, BundleAttempt, LedgerRecord, LedgerStatus};
+use crate::pools::MintPoolData;
+use crate::rpc::RpcLike;
+use crate::transaction::{build_and_send_transaction, send_rpc_clients, TransactionSender};
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How often a sent signature's status is polled while waiting for confirmation
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A sent cycle once its signature confirmed, with the wallet's balances
+/// either side of it
+#[derive(Debug, Clone)]
+pub struct LiveFill {
+    pub signature: Signature,
+    pub slot: u64,
+    pub success: bool, // false: landed with an error, so the executor reverted
+    pub pre: BalanceSnapshot,
+    pub post: BalanceSnapshot,
+    pub compute: Option<ComputeProfile>, // Per-venue compute usage, for a landed transaction whose logs could be read
+    pub bundle_attempts: Vec<BundleAttempt>, // Empty unless the cycle was sent as a bundle
+}
+
+impl LiveFill {
+    /// Ledger record in the importer's shape: the base mint's change before
+    /// the fee, with all native SOL the wallet spent counted as the fee
+    pub fn ledger_record(&self, cycle: &ArbitrageCycle, base_mint: &Pubkey, now_unix: u64) -> LedgerRecord {
+        let delta = |mint: &Pubkey| {
+            *self.post.tokens.get(mint).unwrap_or(&0) as i128 - *self.pre.tokens.get(mint).unwrap_or(&0) as i128
+        };
+        let lamport_delta = self.post.lamports as i128 - self.pre.lamports as i128;
+        let fee_lamports = (-lamport_delta).max(0);
+        let token_deltas: BTreeMap<String, i128> = self
+            .pre
+            .tokens
+            .keys()
+            .chain(self.post.tokens.keys())
+            .filter(|mint| *mint != base_mint)
+            .map(|mint| (mint.to_string(), delta(mint)))
+            .collect();
+        LedgerRecord {
+            signature: self.signature.to_string(),
+            slot: self.slot,
+            block_time: Some(now_unix as i64),
+            status: if self.success { LedgerStatus::Landed } else { LedgerStatus::Failed },
+            cycle_id: Some(cycle.cycle_id()),
+            venues: cycle.legs.iter().map(|leg| leg.dex_type).collect(),
+            base_mint: base_mint.to_string(),
+            base_delta: delta(base_mint) + lamport_delta + fee_lamports,
+            fee_lamports: fee_lamports as u64,
+            token_deltas,
+            imported: false,
+            paper: false,
+            reconciliation: None,
+            bundle_attempts: self.bundle_attempts.clone(),
+            fingerprint: None,
+        }
+    }
+}
+
+/// The wallet's token account for every mint `cycle` trades through, as
+/// `(mint, token_account)`. The base mint is held as wSOL.
+pub fn cycle_token_accounts(cycle: &ArbitrageCycle, pool_data: &MintPoolData) -> Vec<(Pubkey, Pubkey)> {
+    let mints: HashSet<Pubkey> = cycle.legs.iter().flat_map(|leg| [leg.from_mint, leg.to_mint]).collect();
+    let mut accounts: Vec<(Pubkey, Pubkey)> = mints
+        .into_iter()
+        .map(|mint| {
+            let token_program = if mint == pool_data.mint { pool_data.token_program } else { spl_token::id() };
+            let account = spl_associated_token_account::get_associated_token_address_with_program_id(
+                &pool_data.wallet_account,
+                &mint,
+                &token_program,
+            );
+            (mint, account)
+        })
+        .collect();
+    accounts.sort();
+    accounts
+}
+
+/// Sends cleared cycles through the on-chain executor and waits for them to
+/// confirm. The executor instruction is built for one token mint, so only
+/// cycles between the base mint and a single token can be sent.
+pub struct LiveExecutor {
+    send_clients: Vec<Arc<dyn TransactionSender>>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    confirm_timeout: Duration,
+    journal: ExecutionJournal,
+    rng: StdRng, // CU jitter and fee account choice
+    bundles: Option<BundleSender>, // Send cycles as tipped bundles instead of plain transactions
+}
+
+impl LiveExecutor {
+    pub fn new(config: &Config, rpc_client: &dyn RpcLike, confirm_timeout: Duration, journal: ExecutionJournal, seed: u64) -> Result<Self> {
+        let lookup_tables = config
+            .routing
+            .markets
+            .lookup_table_accounts
+            .iter()
+            .flatten()
+            .map(|address| load_lookup_table(rpc_client, address))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            send_clients: send_rpc_clients(config).into_iter().map(|client| client as Arc<dyn TransactionSender>).collect(),
+            lookup_tables,
+            confirm_timeout,
+            journal,
+            rng: StdRng::seed_from_u64(seed),
+            bundles: None,
+        })
+    }
+
+    pub fn with_bundles(mut self, bundles: BundleSender) -> Self {
+        self.bundles = Some(bundles);
+        self
+    }
+
+    /// Submit through `send_clients` instead of the configured endpoints
+    pub fn with_send_clients(mut self, send_clients: Vec<Arc<dyn TransactionSender>>) -> Self {
+        self.send_clients = send_clients;
+        self
+    }
+
+    /// Snapshot the wallet, send `cycle`, wait for it to confirm and snapshot
+    /// again; a landed transaction is also profiled for its compute usage.
+    /// `Ok(None)` when it was sent but never confirmed in time, or when its
+    /// bundle was abandoned. `requote` is only asked by the bundle path, see
+    /// `submit_with_escalation`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn execute(
+        &mut self,
+        wallet_kp: &Keypair,
+        config: &Config,
+        cycle: &ArbitrageCycle,
+        amount: u64,
+        mint_pool_data: &HashMap<Pubkey, MintPoolData>,
+        compute_units: &ComputeUnitTable,
+        rpc_client: &dyn RpcLike,
+        now_unix: u64,
+        requote: &mut (dyn FnMut() -> Option<u64> + Send),
+    ) -> Result<Option<LiveFill>> {
+        // The executor swaps out of and back into wSOL
+        let base_mint = cycle.base_mint();
+        if base_mint != sol_mint() {
+            anyhow::bail!("the executor starts cycles in SOL, cycle starts in {}", base_mint);
+        }
+        let token_mints: HashSet<Pubkey> = cycle.legs.iter().map(|leg| leg.to_mint).filter(|mint| *mint != base_mint).collect();
+        let token_mint = match token_mints.iter().next() {
+            Some(mint) if token_mints.len() == 1 => *mint,
+            _ => anyhow::bail!("the executor routes through one token mint, cycle touches {}", token_mints.len()),
+        };
+        let pool_data = mint_pool_data
+            .get(&token_mint)
+            .ok_or_else(|| anyhow::anyhow!("no pool data for mint {}", token_mint))?;
+        let routed_pools: HashSet<Pubkey> = cycle.legs.iter().map(|leg| leg.pool_pubkey).collect();
+        let routed = pool_data.routed(&routed_pools);
+
+        let token_accounts = cycle_token_accounts(cycle, pool_data);
+        let pre = BalanceSnapshot::capture(rpc_client, &wallet_kp.pubkey(), &token_accounts).context("pre-trade snapshot")?;
+        let blockhash = rpc_client.get_latest_blockhash()?;
+        if let Some(bundles) = &self.bundles {
+            let (outcome, txs) = bundles
+                .send(wallet_kp, config, cycle, pool_data, compute_units, rpc_client, blockhash, &self.lookup_tables, &mut self.rng, requote)
+                .await?;
+            let signature = txs.first().map(|tx| tx.signatures[0]).unwrap_or_default();
+            self.journal(cycle, now_unix, JournalEvent::Sent {
+                signatures: txs.iter().map(|tx| tx.signatures[0].to_string()).collect(),
+                amount_in: amount,
+            })
+            .await;
+            let Some(slot) = outcome.landed_slot else {
+                let reason = outcome.abandoned.unwrap_or_default();
+                self.journal(cycle, now_unix, JournalEvent::BundleAbandoned { attempts: outcome.attempts, reason }).await;
+                return Ok(None);
+            };
+            // A bundle lands whole or not at all, so a landed one succeeded
+            info!("📬 Cycle {:016x}: bundle landed in slot {} after {} attempts", cycle.cycle_id(), slot, outcome.attempts.len());
+            self.journal(cycle, now_unix, JournalEvent::Confirmed { signature: signature.to_string(), slot, success: true }).await;
+            let post = BalanceSnapshot::capture(rpc_client, &wallet_kp.pubkey(), &token_accounts).context("post-trade snapshot")?;
+            let compute = profile_transaction(rpc_client, &signature)
+                .map_err(|e| warn!("⚠️ Failed to profile compute usage of {}: {}", signature, e))
+                .ok();
+            return Ok(Some(LiveFill { signature, slot, success: true, pre, post, compute, bundle_attempts: outcome.attempts }));
+        }
+        let signatures = build_and_send_transaction(
+            wallet_kp,
+            config,
+            cycle,
+            &routed,
+            compute_units,
+            rpc_client,
+            &self.send_clients,
+            blockhash,
+            &self.lookup_tables,
+            &mut self.rng,
+        )
+        .await?;
+        let Some(signature) = signatures.first().copied() else {
+            anyhow::bail!("no send endpoint accepted the transaction");
+        };
+        self.journal(cycle, now_unix, JournalEvent::Sent {
+            signatures: signatures.iter().map(|signature| signature.to_string()).collect(),
+            amount_in: amount,
+        })
+        .await;
+
+        let Some((slot, success)) = wait_for_confirmation(rpc_client, &signature, self.confirm_timeout).await? else {
+            warn!("⚠️ Cycle {:016x}: {} not confirmed within {:?}", cycle.cycle_id(), signature, self.confirm_timeout);
+            self.journal(cycle, now_unix, JournalEvent::Unconfirmed { signature: signature.to_string() }).await;
+            return Ok(None);
+        };
+        info!("📬 Cycle {:016x}: {} confirmed in slot {} ({})", cycle.cycle_id(), signature, slot, if success { "landed" } else { "reverted" });
+        self.journal(cycle, now_unix, JournalEvent::Confirmed { signature: signature.to_string(), slot, success }).await;
+
+        let post = BalanceSnapshot::capture(rpc_client, &wallet_kp.pubkey(), &token_accounts).context("post-trade snapshot")?;
+        let compute = success
+            .then(|| profile_transaction(rpc_client, &signature))
+            .and_then(|profile| profile.map_err(|e| warn!("⚠️ Failed to profile compute usage of {}: {}", signature, e)).ok());
+        Ok(Some(LiveFill { signature, slot, success, pre, post, compute, bundle_attempts: Vec::new() }))
+    }
+
+    async fn journal(&self, cycle: &ArbitrageCycle, now_unix: u64, event: JournalEvent) {
+        let entry = JournalEntry { timestamp: now_unix, cycle_id: cycle.cycle_id(), paper: false, event };
+        if let Err(e) = self.journal.append(&entry).await {
+            warn!("⚠️ Failed to write the execution journal: {}", e);
+        }
+    }
+}
+
+fn load_lookup_table(rpc_client: &dyn RpcLike, address: &str) -> Result<AddressLookupTableAccount> {
+    let key = address
+        .parse::<Pubkey>()
+        .map_err(|e| anyhow::anyhow!("Invalid lookup table {}: {}", address, e))?;
+    let account = rpc_client.get_account(&key).context(format!("Failed to read lookup table {}", key))?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| anyhow::anyhow!("Invalid lookup table {}: {}", key, e))?;
+    Ok(AddressLookupTableAccount { key, addresses: table.addresses.to_vec() })
+}
+
+/// Poll until `signature` is confirmed, returning its slot and whether it
+/// succeeded, or `None` once `timeout` has passed
+async fn wait_for_confirmation(rpc_client: &dyn RpcLike, signature: &Signature, timeout: Duration) -> Result<Option<(u64, bool)>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let statuses = rpc_client.get_signature_statuses(&[*signature])?.value;
+        if let Some(Some(status)) = statuses.first() {
+            if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                return Ok(Some((status.slot, status.err.is_none())));
+            }
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+    }
+}
+
+/// Checks every fill, live or on paper, against what its cycle predicted:
+/// reconciles the balances, records the result in the ledger and the
+/// journal, stamped with the run's version fingerprint, and trips the kill
+/// switch on an unexplained loss
+pub struct PostTradeReview {
+    tolerance_lamports: u64,
+    max_unexplained_loss_lamports: u64,
+    kill_switch: KillSwitch,
+    ledger_file: String,
+    journal: ExecutionJournal,
+    fingerprint: String,
+}
+
+impl PostTradeReview {
+    pub fn new(
+        tolerance_lamports: u64,
+        max_unexplained_loss_lamports: u64,
+        kill_switch: KillSwitch,
+        ledger_file: String,
+        journal: ExecutionJournal,
+        fingerprint: &VersionFingerprint,
+    ) -> Self {
+        Self {
+            tolerance_lamports,
+            max_unexplained_loss_lamports,
+            kill_switch,
+            ledger_file,
+            journal,
+            fingerprint: fingerprint.to_string(),
+        }
+    }
+
+    /// Why execution is halted, if the kill switch has tripped
+    pub fn halted(&self) -> Option<&str> {
+        self.kill_switch.reason()
+    }
+
+    /// Reconcile a fill, value its ending balances in SOL terms against
+    /// `graph`, and record the result in the ledger and the journal
+    #[allow(clippy::too_many_arguments)]
+    pub async fn review(
+        &mut self,
+        cycle: &ArbitrageCycle,
+        pre: &BalanceSnapshot,
+        post: &BalanceSnapshot,
+        expected_fee_lamports: u64,
+        graph: &PriceGraph,
+        mut record: LedgerRecord,
+        now_unix: u64,
+    ) -> ReconciliationReport {
+        let mut report = reconcile(pre, post, cycle, expected_fee_lamports, self.tolerance_lamports);
+        report.log();
+        let valuation = value_in_sol(pre, post, graph);
+        valuation.log();
+        report.valuation = Some(valuation);
+        let summary = report.summary();
+        record.reconciliation = Some(summary.clone());
+        record.fingerprint = Some(self.fingerprint.clone());
+        let paper = record.paper;
+        if let Err(e) = append_records(&self.ledger_file, &[record]).await {
+            warn!("⚠️ Failed to record cycle {:016x} in the ledger: {}", cycle.cycle_id(), e);
+        }
+        self.journal(cycle, paper, now_unix, JournalEvent::Reconciled { reconciliation: summary }).await;
+
+        if report.should_trip_kill_switch(self.max_unexplained_loss_lamports) {
+            let reason = format!(
+                "cycle {:016x} lost {} lamports the reconciliation cannot explain (limit {})",
+                cycle.cycle_id(),
+                report.unexplained_loss_lamports,
+                self.max_unexplained_loss_lamports
+            );
+            if let Err(e) = self.kill_switch.trip(reason.clone(), now_unix).await {
+                warn!("⚠️ Failed to persist the kill switch: {}", e);
+            }
+            self.journal(cycle, paper, now_unix, JournalEvent::KillSwitchTripped { reason }).await;
+        }
+        report
+    }
+
+    async fn journal(&self, cycle: &ArbitrageCycle, paper: bool, now_unix: u64, event: JournalEvent) {
+        let entry = JournalEntry { timestamp: now_unix, cycle_id: cycle.cycle_id(), paper, event };
+        if let Err(e) = self.journal.append(&entry).await {
+            warn!("⚠️ Failed to write the execution journal: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::reconcile::ReconciliationSeverity;
+    use crate::bundle::BlockEngine;
+    use crate::config::JitoConfig;
+    use crate::dex::raydium::raydium_program_id;
+    use crate::engine::types::{DexType, SwapLeg};
+    use crate::ledger::load_ledger;
+    use crate::pools::RaydiumPool;
+    use crate::rpc::MockRpc;
+    use base64::Engine;
+    use serde_json::json;
+    use solana_sdk::account::Account;
+    use solana_sdk::clock::Slot;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+    use std::sync::Mutex;
+
+    fn round_trip(token: Pubkey, profit: u64) -> ArbitrageCycle {
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+        ArbitrageCycle {
+            legs: vec![leg(sol_mint(), token), leg(token, sol_mint())],
+            total_profit_bps: 0,
+            estimated_profit_lamports: profit,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 5_000,
+        }
+    }
+
+    fn snapshot(lamports: u64, tokens: &[(Pubkey, u64)]) -> BalanceSnapshot {
+        BalanceSnapshot { lamports, tokens: tokens.iter().copied().collect() }
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("{}_{}", Pubkey::new_unique(), name))
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn live_ledger_record_counts_native_sol_spent_as_the_fee() {
+        let token = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let fill = LiveFill {
+            signature: Signature::default(),
+            slot: 7,
+            success: true,
+            pre: snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0)]),
+            post: snapshot(9_995_000, &[(sol_mint(), 1_000_050_000), (token, 3)]),
+            compute: None,
+            bundle_attempts: Vec::new(),
+        };
+
+        let record = fill.ledger_record(&cycle, &sol_mint(), 1_700_000_000);
+
+        assert_eq!(record.status, LedgerStatus::Landed);
+        assert_eq!(record.fee_lamports, 5_000);
+        assert_eq!(record.base_delta, 50_000);
+        assert_eq!(record.net_delta(), 45_000);
+        assert_eq!(record.token_deltas[&token.to_string()], 3);
+        assert!(!record.paper);
+    }
+
+    #[tokio::test]
+    async fn an_unexplained_loss_is_recorded_and_halts_execution() {
+        let (ledger_file, journal_file, kill_switch_file) =
+            (temp_path("ledger.jsonl"), temp_path("journal.jsonl"), temp_path("kill_switch.json"));
+        let mut review = PostTradeReview::new(
+            10_000,
+            1_000_000,
+            KillSwitch::load(&kill_switch_file).await.unwrap(),
+            ledger_file.clone(),
+            ExecutionJournal::new(journal_file.clone()),
+            &VersionFingerprint::new(b"config"),
+        );
+        let token = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let fill = LiveFill {
+            signature: Signature::default(),
+            slot: 7,
+            success: true,
+            pre: snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0)]),
+            post: snapshot(9_995_000, &[(sol_mint(), 997_000_000), (token, 0)]),
+            compute: None,
+            bundle_attempts: Vec::new(),
+        };
+
+        let report = review
+            .review(&cycle, &fill.pre, &fill.post, 5_000, &PriceGraph::new(), fill.ledger_record(&cycle, &sol_mint(), 0), 0)
+            .await;
+
+        assert_eq!(report.severity, ReconciliationSeverity::Unexplained);
+        assert!(review.halted().is_some());
+        assert!(KillSwitch::load(&kill_switch_file).await.unwrap().is_tripped());
+        let ledger = load_ledger(&ledger_file).await.unwrap();
+        assert_eq!(ledger[0].reconciliation.as_ref().unwrap().severity, ReconciliationSeverity::Unexplained);
+        let journal = std::fs::read_to_string(&journal_file).unwrap();
+        let events: Vec<serde_json::Value> = journal.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events[0]["event"], "reconciled");
+        assert_eq!(events[1]["event"], "kill_switch_tripped");
+
+        for path in [ledger_file, journal_file, kill_switch_file] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn a_clean_paper_fill_leaves_execution_running() {
+        let (ledger_file, journal_file, kill_switch_file) =
+            (temp_path("ledger.jsonl"), temp_path("journal.jsonl"), temp_path("kill_switch.json"));
+        let mut review = PostTradeReview::new(
+            10_000,
+            1_000_000,
+            KillSwitch::load(&kill_switch_file).await.unwrap(),
+            ledger_file.clone(),
+            ExecutionJournal::new(journal_file.clone()),
+            &VersionFingerprint::new(b"config"),
+        );
+        let token = Pubkey::new_unique();
+        let cycle = round_trip(token, 50_000);
+        let fill = crate::engine::paper::PaperFill {
+            amount_in: 1_000_000_000,
+            amount_out: 1_000_050_000,
+            leg_outputs: vec![40_000_000, 1_000_050_000],
+        };
+        let (pre, post) = fill.balance_snapshots(&cycle, 5_000);
+        let record = fill.ledger_record(1, &cycle, &sol_mint(), 0, 5_000, 0);
+
+        let report = review.review(&cycle, &pre, &post, 5_000, &PriceGraph::new(), record, 0).await;
+
+        assert_eq!(report.severity, ReconciliationSeverity::Clean);
+        assert!(review.halted().is_none());
+        assert!(!std::path::Path::new(&kill_switch_file).exists());
+        let ledger = load_ledger(&ledger_file).await.unwrap();
+        assert!(ledger[0].paper);
+        assert_eq!(ledger[0].fingerprint, Some(VersionFingerprint::new(b"config").to_string()));
+        // The paper wallet pays the fee in native SOL and gains the profit as wSOL
+        assert_eq!(report.valuation.unwrap().net_profit_lamports, 50_000 - 5_000);
+
+        for path in [ledger_file, journal_file] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn token_dust_is_valued_in_sol_and_flagged_in_the_cycle_report() {
+        let (ledger_file, journal_file, kill_switch_file) =
+            (temp_path("ledger.jsonl"), temp_path("journal.jsonl"), temp_path("kill_switch.json"));
+        let mut review = PostTradeReview::new(
+            10_000,
+            1_000_000,
+            KillSwitch::load(&kill_switch_file).await.unwrap(),
+            ledger_file.clone(),
+            ExecutionJournal::new(journal_file.clone()),
+            &VersionFingerprint::new(b"config"),
+        );
+        let token = Pubkey::new_unique();
+        let graph = PriceGraph::new();
+        // 25 lamports per raw unit of the token
+        graph.add_edge(token, sol_mint(), crate::engine::types::PoolEdge::new(
+            Pubkey::new_unique(), sol_mint(), DexType::RaydiumV4, 25.0, 1e6, 25, spl_token::id(),
+        ));
+        let cycle = round_trip(token, 50_000);
+        let fill = LiveFill {
+            signature: Signature::default(),
+            slot: 7,
+            success: true,
+            // The sell leg left 400 raw units of the token behind
+            pre: snapshot(10_000_000, &[(sol_mint(), 1_000_000_000), (token, 0)]),
+            post: snapshot(9_995_000, &[(sol_mint(), 1_000_040_000), (token, 400)]),
+            compute: None,
+            bundle_attempts: Vec::new(),
+        };
+
+        let report = review
+            .review(&cycle, &fill.pre, &fill.post, 5_000, &graph, fill.ledger_record(&cycle, &sol_mint(), 0), 0)
+            .await;
+
+        let valuation = report.valuation.clone().unwrap();
+        assert!(valuation.is_flagged());
+        assert_eq!(valuation.stranded[0].value_lamports, Some(10_000));
+        // -5_000 fee + 40_000 wSOL + 10_000 worth of token
+        assert_eq!(valuation.net_profit_lamports, 45_000);
+        let summary = load_ledger(&ledger_file).await.unwrap()[0].reconciliation.clone().unwrap();
+        assert_eq!(summary.net_profit_sol_lamports, Some(45_000));
+        assert_eq!(summary.stranded_mints, vec![token.to_string()]);
+
+        for path in [ledger_file, journal_file] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    const FEE: u64 = 5_000;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [bot]
+            compute_unit_limit = 400000
+
+            [routing.markets]
+            markets = []
+            process_delay = 0
+
+            [rpc]
+            url = "http://127.0.0.1:8899"
+
+            [wallet]
+            private_key = "unused"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account { lamports: 2_039_280, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    fn system_account(lamports: u64) -> Account {
+        Account { lamports, data: Vec::new(), owner: solana_sdk::system_program::id(), executable: false, rent_epoch: 0 }
+    }
+
+    /// A wallet about to trade a SOL -> token -> SOL cycle through two
+    /// Raydium V4 pools on `rpc`: the pools are owned by the Raydium program
+    /// and the wallet holds 0.01 SOL, 1 wSOL and none of the token
+    struct Market {
+        wallet: Keypair,
+        token: Pubkey,
+        cycle: ArbitrageCycle,
+        pool_data: HashMap<Pubkey, MintPoolData>,
+        accounts: Vec<(Pubkey, Pubkey)>, // (mint, wallet token account)
+    }
+
+    impl Market {
+        fn new(rpc: &MockRpc) -> Self {
+            let wallet = Keypair::new();
+            let token = Pubkey::new_unique();
+            let mut data = MintPoolData::new(token, &wallet.pubkey(), spl_token::id());
+            let legs = [(sol_mint(), token), (token, sol_mint())]
+                .into_iter()
+                .map(|(from_mint, to_mint)| {
+                    let pool = RaydiumPool {
+                        pool: Pubkey::new_unique(),
+                        token_vault: Pubkey::new_unique(),
+                        sol_vault: Pubkey::new_unique(),
+                        token_mint: token,
+                        base_mint: sol_mint(),
+                    };
+                    rpc.set_account(pool.pool, Account { lamports: 1, data: vec![0; 8], owner: raydium_program_id(), executable: false, rent_epoch: 0 });
+                    let leg = SwapLeg { from_mint, to_mint, pool_pubkey: pool.pool, ..round_trip(token, 0).legs[0].clone() };
+                    data.raydium_pools.push(pool);
+                    leg
+                })
+                .collect();
+            let cycle = ArbitrageCycle { legs, ..round_trip(token, 50_000) };
+            let accounts = cycle_token_accounts(&cycle, &data);
+            let market = Self { wallet, token, cycle, pool_data: HashMap::from([(token, data)]), accounts };
+            market.set_balances(rpc, 10_000_000, 1_000_000_000, 0);
+            market
+        }
+
+        fn set_balances(&self, rpc: &MockRpc, lamports: u64, wsol: u64, token: u64) {
+            rpc.set_account(self.wallet.pubkey(), system_account(lamports));
+            for (mint, account) in &self.accounts {
+                let amount = if *mint == sol_mint() { wsol } else { token };
+                rpc.set_account(*account, token_account(mint, &self.wallet.pubkey(), amount));
+            }
+        }
+    }
+
+    /// How a sent transaction plays out on the mock node
+    #[derive(Clone, Copy)]
+    enum Outcome {
+        Lands { slot: Slot },
+        Reverts { slot: Slot },
+        Dropped,
+    }
+
+    /// A send endpoint that settles what it is sent on `rpc`: a landed
+    /// transaction pays the fee and returns 50_000 lamports of profit as wSOL,
+    /// a reverted one only pays the fee, a dropped one never shows up
+    struct SettlingSender {
+        rpc: Arc<MockRpc>,
+        market: (Pubkey, Vec<(Pubkey, Pubkey)>), // Wallet and its token accounts
+        outcome: Outcome,
+        sent: Mutex<Vec<VersionedTransaction>>,
+    }
+
+    impl SettlingSender {
+        fn new(rpc: &Arc<MockRpc>, market: &Market, outcome: Outcome) -> Arc<Self> {
+            let market = (market.wallet.pubkey(), market.accounts.clone());
+            Arc::new(Self { rpc: rpc.clone(), market, outcome, sent: Mutex::new(Vec::new()) })
+        }
+
+        fn set_balances(&self, lamports: u64, wsol: u64) {
+            let (wallet, accounts) = &self.market;
+            self.rpc.set_account(*wallet, system_account(lamports));
+            for (mint, account) in accounts.iter().filter(|(mint, _)| *mint == sol_mint()) {
+                self.rpc.set_account(*account, token_account(mint, wallet, wsol));
+            }
+        }
+    }
+
+    impl TransactionSender for SettlingSender {
+        fn send_transaction(&self, tx: &VersionedTransaction, _max_retries: u64) -> anyhow::Result<Signature> {
+            let signature = tx.signatures[0];
+            self.sent.lock().unwrap().push(tx.clone());
+            match self.outcome {
+                Outcome::Lands { slot } => {
+                    self.set_balances(10_000_000 - FEE, 1_000_050_000);
+                    self.rpc.confirm_signature(signature, slot, None);
+                    self.rpc.set_transaction(signature, landed_transaction(tx));
+                }
+                Outcome::Reverts { slot } => {
+                    self.set_balances(10_000_000 - FEE, 1_000_000_000);
+                    let err = TransactionError::InstructionError(2, InstructionError::Custom(6001));
+                    self.rpc.confirm_signature(signature, slot, Some(err));
+                }
+                Outcome::Dropped => {}
+            }
+            Ok(signature)
+        }
+    }
+
+    /// `tx` as `getTransaction` returns it once landed, with the executor
+    /// spending 80_000 units of which each Raydium leg took 30_000
+    fn landed_transaction(tx: &VersionedTransaction) -> solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta {
+        let executor = "MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz";
+        let raydium = raydium_program_id().to_string();
+        let mut logs = vec![format!("Program {} invoke [1]", executor)];
+        for _ in 0..2 {
+            logs.push(format!("Program {} invoke [2]", raydium));
+            logs.push(format!("Program {} consumed 30000 of 350000 compute units", raydium));
+            logs.push(format!("Program {} success", raydium));
+        }
+        logs.push(format!("Program {} consumed 80000 of 400000 compute units", executor));
+        logs.push(format!("Program {} success", executor));
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(tx).unwrap());
+        serde_json::from_value(json!({
+            "slot": 0,
+            "blockTime": null,
+            "transaction": [encoded, "base64"],
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": FEE,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": logs,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn executor(rpc: &MockRpc, sender: Arc<SettlingSender>, confirm_timeout: Duration, journal_file: &str) -> LiveExecutor {
+        LiveExecutor::new(&config(), rpc, confirm_timeout, ExecutionJournal::new(journal_file), 7)
+            .unwrap()
+            .with_send_clients(vec![sender as Arc<dyn TransactionSender>])
+    }
+
+    async fn execute(executor: &mut LiveExecutor, rpc: &MockRpc, market: &Market, requote: &mut (dyn FnMut() -> Option<u64> + Send)) -> Option<LiveFill> {
+        executor
+            .execute(&market.wallet, &config(), &market.cycle, 1_000_000_000, &market.pool_data, &ComputeUnitTable::default(), rpc, 1_700_000_000, requote)
+            .await
+            .unwrap()
+    }
+
+    fn journal_events(journal_file: &str) -> Vec<serde_json::Value> {
+        let journal = std::fs::read_to_string(journal_file).unwrap();
+        std::fs::remove_file(journal_file).unwrap();
+        journal.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn a_landed_send_is_confirmed_snapshotted_and_profiled() {
+        let rpc = Arc::new(MockRpc::new());
+        let market = Market::new(&rpc);
+        let sender = SettlingSender::new(&rpc, &market, Outcome::Lands { slot: 321 });
+        let journal_file = temp_path("journal.jsonl");
+        let mut executor = executor(&rpc, sender.clone(), Duration::from_secs(5), &journal_file);
+
+        let fill = execute(&mut executor, &rpc, &market, &mut || None).await.expect("the send confirms");
+
+        // One signed transaction went to the send endpoint, and it is the fill
+        let sent = sender.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].verify_with_results().iter().all(|ok| *ok));
+        assert_eq!(sent[0].message.static_account_keys()[0], market.wallet.pubkey());
+        assert_eq!((fill.signature, fill.slot, fill.success), (sent[0].signatures[0], 321, true));
+        // Balances before the send and after it confirmed
+        assert_eq!(fill.pre.lamports, 10_000_000);
+        assert_eq!(fill.pre.tokens, HashMap::from([(sol_mint(), 1_000_000_000), (market.token, 0)]));
+        assert_eq!(fill.post.lamports, 10_000_000 - FEE);
+        assert_eq!(fill.post.tokens, HashMap::from([(sol_mint(), 1_000_050_000), (market.token, 0)]));
+        let record = fill.ledger_record(&market.cycle, &sol_mint(), 0);
+        assert_eq!((record.base_delta, record.fee_lamports, record.status), (50_000, FEE, LedgerStatus::Landed));
+        assert_eq!(fill.compute, Some(ComputeProfile { legs: vec![(DexType::RaydiumV4, 30_000); 2], overhead: 20_000 }));
+
+        let events = journal_events(&journal_file);
+        let kinds: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec!["sent", "confirmed"]);
+        assert_eq!(events[0]["signatures"], json!([fill.signature.to_string()]));
+        assert_eq!(events[0]["amount_in"], 1_000_000_000u64);
+        assert_eq!((events[1]["slot"].as_u64(), events[1]["success"].as_bool()), (Some(321), Some(true)));
+    }
+
+    #[tokio::test]
+    async fn a_send_that_never_confirms_times_out_without_a_fill() {
+        let rpc = Arc::new(MockRpc::new());
+        let market = Market::new(&rpc);
+        let sender = SettlingSender::new(&rpc, &market, Outcome::Dropped);
+        let journal_file = temp_path("journal.jsonl");
+        let mut executor = executor(&rpc, sender.clone(), Duration::from_millis(600), &journal_file);
+
+        let started = Instant::now();
+        assert!(execute(&mut executor, &rpc, &market, &mut || None).await.is_none());
+
+        // Polled until the timeout, not a moment less
+        assert!(started.elapsed() >= Duration::from_millis(600));
+        let signature = sender.sent.lock().unwrap()[0].signatures[0].to_string();
+        let events = journal_events(&journal_file);
+        let kinds: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec!["sent", "unconfirmed"]);
+        assert_eq!(events[1]["signature"], signature);
+    }
+
+    #[tokio::test]
+    async fn a_reverted_send_is_a_failed_fill_that_only_paid_the_fee() {
+        let rpc = Arc::new(MockRpc::new());
+        let market = Market::new(&rpc);
+        let sender = SettlingSender::new(&rpc, &market, Outcome::Reverts { slot: 322 });
+        let journal_file = temp_path("journal.jsonl");
+        let mut executor = executor(&rpc, sender, Duration::from_secs(5), &journal_file);
+
+        let fill = execute(&mut executor, &rpc, &market, &mut || None).await.expect("a revert still confirms");
+
+        assert_eq!((fill.slot, fill.success), (322, false));
+        assert_eq!(fill.post.lamports, fill.pre.lamports - FEE);
+        assert_eq!(fill.post.tokens, fill.pre.tokens);
+        // A reverted transaction is not profiled
+        assert_eq!(fill.compute, None);
+        let record = fill.ledger_record(&market.cycle, &sol_mint(), 0);
+        assert_eq!((record.status, record.base_delta, record.fee_lamports), (LedgerStatus::Failed, 0, FEE));
+        let events = journal_events(&journal_file);
+        assert_eq!(events[1]["event"], "confirmed");
+        assert_eq!(events[1]["success"], false);
+    }
+
+    /// Takes every bundle and never reports one landing
+    #[derive(Default)]
+    struct DroppingBlockEngine {
+        bundles: Mutex<Vec<Vec<VersionedTransaction>>>,
+    }
+
+    impl BlockEngine for DroppingBlockEngine {
+        fn send_bundle(&self, txs: &[VersionedTransaction]) -> Result<String> {
+            let mut bundles = self.bundles.lock().unwrap();
+            bundles.push(txs.to_vec());
+            Ok(format!("bundle-{}", bundles.len()))
+        }
+
+        fn bundle_landed_slot(&self, _bundle_id: &str) -> Result<Option<Slot>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn a_bundle_that_fails_its_requote_is_abandoned_without_a_fill() {
+        let rpc = Arc::new(MockRpc::new().with_slot(500));
+        let market = Market::new(&rpc);
+        let sender = SettlingSender::new(&rpc, &market, Outcome::Lands { slot: 501 });
+        let journal_file = temp_path("journal.jsonl");
+        let tip_account = Pubkey::new_unique();
+        // The inclusion window is already over at the first poll
+        let jito: JitoConfig = toml::from_str(&format!(
+            "enabled = true\ntip_accounts = [\"{}\"]\ntip_lamports = 10000\ninclusion_slots = 0\npoll_interval_ms = 0",
+            tip_account
+        ))
+        .unwrap();
+        let engine = Arc::new(DroppingBlockEngine::default());
+        let bundles = BundleSender::new(&jito, engine.clone()).unwrap();
+        let mut executor = executor(&rpc, sender.clone(), Duration::from_secs(5), &journal_file).with_bundles(bundles);
+
+        let mut requotes = 0;
+        let fill = execute(&mut executor, &rpc, &market, &mut || {
+            requotes += 1;
+            None
+        })
+        .await;
+
+        assert!(fill.is_none());
+        assert_eq!(requotes, 1);
+        // Submitted once to the block engine, never to the send endpoint
+        assert!(sender.sent.lock().unwrap().is_empty());
+        let bundles = engine.bundles.lock().unwrap().clone();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].len(), 1);
+        let tx = &bundles[0][0];
+        let tip = tx.message.instructions().last().unwrap();
+        assert_eq!(tx.message.static_account_keys()[tip.program_id_index as usize], solana_sdk::system_program::id());
+        assert_eq!(tx.message.static_account_keys()[tip.accounts[1] as usize], tip_account);
+
+        let events = journal_events(&journal_file);
+        let kinds: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec!["sent", "bundle_abandoned"]);
+        assert_eq!(events[0]["signatures"], json!([tx.signatures[0].to_string()]));
+        assert_eq!(events[1]["attempts"][0]["bundle_id"], "bundle-1");
+        assert_eq!(events[1]["attempts"][0]["tip_lamports"], 10_000);
+        assert_eq!(events[1]["attempts"][0]["sent_slot"], 500);
+        assert_eq!(events[1]["reason"], "re-quote failed the staleness or risk checks");
+    }
+}
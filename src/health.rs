@@ -0,0 +1,241 @@
+use crate::clock::{system_clock, SharedClock};
+use crate::confirm::ConfirmationGate;
+use solana_client::rpc_client::RpcClient;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{error, info, warn};
+
+/// Liveness state shared between the main loop and the health server
+pub struct HealthState {
+    last_update: Mutex<Option<Instant>>,
+    max_update_age: Duration,
+    clock: SharedClock,
+    confirmations: Option<Arc<Mutex<ConfirmationGate>>>,
+    confirmation_token: Option<String>,
+}
+
+impl HealthState {
+    pub fn new(max_update_age: Duration) -> Self {
+        Self::with_clock(max_update_age, system_clock())
+    }
+
+    pub fn with_clock(max_update_age: Duration, clock: SharedClock) -> Self {
+        Self {
+            last_update: Mutex::new(None),
+            max_update_age,
+            clock,
+            confirmations: None,
+            confirmation_token: None,
+        }
+    }
+
+    /// Also serve `GET /confirmations` and `POST /confirm/{cycle_id}`, to
+    /// requests carrying `Authorization: Bearer <api_token>`, or only to
+    /// loopback clients when no token is configured
+    pub fn with_confirmations(mut self, gate: Arc<Mutex<ConfirmationGate>>, api_token: Option<String>) -> Self {
+        self.confirmations = Some(gate);
+        self.confirmation_token = api_token.filter(|token| !token.is_empty());
+        self
+    }
+
+    /// Whether a request from `peer` may list or confirm held cycles
+    fn confirmation_authorized(&self, peer: &SocketAddr, request: &str) -> bool {
+        let Some(token) = &self.confirmation_token else {
+            return peer.ip().is_loopback();
+        };
+        request
+            .lines()
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+            .filter_map(|(_, value)| value.trim().strip_prefix("Bearer "))
+            .any(|presented| constant_time_eq(presented.trim().as_bytes(), token.as_bytes()))
+    }
+
+    /// Record a successful graph update
+    pub fn record_update(&self) {
+        *self.last_update.lock().unwrap() = Some(self.clock.now_instant());
+    }
+
+    /// Ok if the last graph update is within the allowed window, otherwise the reason
+    pub fn check_freshness(&self) -> Result<(), String> {
+        let last_update = *self.last_update.lock().unwrap();
+        match last_update.map(|at| self.clock.elapsed_since(at)) {
+            Some(age) if age <= self.max_update_age => Ok(()),
+            Some(age) => Err(format!(
+                "last graph update {}s ago (max {}s)",
+                age.as_secs(),
+                self.max_update_age.as_secs()
+            )),
+            None => Err("no successful graph update yet".to_string()),
+        }
+    }
+}
+
+/// Serve `GET /health`: 200 when the graph is fresh and the RPC preflight passes, 503 otherwise.
+/// With a confirmation gate, also list held cycles and confirm them.
+pub async fn run_health_server(
+    bind_address: String,
+    state: Arc<HealthState>,
+    rpc_client: Arc<RpcClient>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(&bind_address).await?;
+    info!("🩺 Health endpoint listening on http://{}/health", bind_address);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Health endpoint accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        let rpc_client = rpc_client.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, peer, state, rpc_client).await {
+                error!("Health endpoint request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    state: Arc<HealthState>,
+    rpc_client: Arc<RpcClient>,
+) -> anyhow::Result<()> {
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut request_line = request.split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("");
+
+    let (status, body) = if path == "/health" {
+        match check_health(&state, rpc_client).await {
+            Ok(()) => ("200 OK", "ok".to_string()),
+            Err(reason) => ("503 Service Unavailable", reason),
+        }
+    } else if state.confirmations.is_some()
+        && (path == "/confirmations" || path.starts_with("/confirm/"))
+        && !state.confirmation_authorized(&peer, &request)
+    {
+        warn!("⚠️ Rejected unauthorized {} {} from {}", method, path, peer);
+        ("401 Unauthorized", "unauthorized".to_string())
+    } else if let Some(gate) = &state.confirmations {
+        match (method, path.strip_prefix("/confirm/")) {
+            ("GET", None) if path == "/confirmations" => ("200 OK", gate.lock().unwrap().pending_report()),
+            ("POST", Some(cycle_id)) => match u64::from_str_radix(cycle_id, 16) {
+                Ok(cycle_id) => match gate.lock().unwrap().confirm(cycle_id) {
+                    Ok(()) => {
+                        info!("✅ Cycle {:016x} confirmed, sending on its next detection", cycle_id);
+                        ("200 OK", "confirmed".to_string())
+                    }
+                    Err(reason) => ("404 Not Found", reason),
+                },
+                Err(_) => ("400 Bad Request", format!("invalid cycle id {}", cycle_id)),
+            },
+            _ => ("404 Not Found", "not found".to_string()),
+        }
+    } else {
+        ("404 Not Found", "not found".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+async fn check_health(state: &HealthState, rpc_client: Arc<RpcClient>) -> Result<(), String> {
+    state.check_freshness()?;
+
+    // Stringify inside the task so the large ClientError never crosses it
+    tokio::task::spawn_blocking(move || rpc_client.get_health().map_err(|e| format!("rpc preflight failed: {}", e)))
+        .await
+        .map_err(|e| format!("rpc preflight panicked: {}", e))?
+}
+
+/// Compare without returning early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::confirm::{ConfirmationState, VersionFingerprint};
+
+    fn state(api_token: Option<&str>) -> HealthState {
+        let gate = ConfirmationGate::new(
+            ConfirmationState { fingerprint: VersionFingerprint::new(b""), remaining: 1 },
+            Duration::from_secs(30),
+        );
+        HealthState::new(Duration::from_secs(60))
+            .with_confirmations(Arc::new(Mutex::new(gate)), api_token.map(str::to_string))
+    }
+
+    const REMOTE: &str = "203.0.113.7:51000";
+    const LOCAL: &str = "127.0.0.1:51000";
+
+    #[test]
+    fn without_a_token_only_loopback_may_confirm() {
+        let state = state(None);
+        let request = "POST /confirm/00000000000000aa HTTP/1.1\r\nHost: bot\r\n\r\n";
+        assert!(state.confirmation_authorized(&LOCAL.parse().unwrap(), request));
+        assert!(!state.confirmation_authorized(&REMOTE.parse().unwrap(), request));
+    }
+
+    #[test]
+    fn with_a_token_every_client_needs_it() {
+        let state = state(Some("s3cret"));
+        let with = |header: &str| format!("GET /confirmations HTTP/1.1\r\nHost: bot\r\n{}\r\n\r\n", header);
+        assert!(state.confirmation_authorized(&REMOTE.parse().unwrap(), &with("Authorization: Bearer s3cret")));
+        assert!(state.confirmation_authorized(&REMOTE.parse().unwrap(), &with("authorization:  Bearer s3cret ")));
+        assert!(!state.confirmation_authorized(&REMOTE.parse().unwrap(), &with("Authorization: Bearer wrong")));
+        assert!(!state.confirmation_authorized(&LOCAL.parse().unwrap(), &with("X-Token: s3cret")));
+    }
+
+    /// Send `request` through `handle_connection` over loopback and return the raw response
+    async fn respond(state: Arc<HealthState>, request: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (server, peer) = listener.accept().await.unwrap();
+        // Never reached while the graph is stale
+        let rpc_client = Arc::new(RpcClient::new("http://127.0.0.1:1".to_string()));
+        client.write_all(request.as_bytes()).await.unwrap();
+        handle_connection(server, peer, state, rpc_client).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn a_stale_graph_update_answers_503() {
+        let clock = Arc::new(MockClock::new(0));
+        let state = Arc::new(HealthState::with_clock(Duration::from_secs(60), clock.clone()));
+        let request = "GET /health HTTP/1.1\r\nHost: bot\r\n\r\n";
+
+        let response = respond(state.clone(), request).await;
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        assert!(response.ends_with("no successful graph update yet"), "{}", response);
+
+        state.record_update();
+        clock.advance(Duration::from_secs(61));
+        let response = respond(state, request).await;
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        assert!(response.ends_with("last graph update 61s ago (max 60s)"), "{}", response);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::engine::reconcile::ReconciliationSummary;
+use crate::ledger::BundleAttempt;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+/// One step of an execution
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEvent {
+    Sent { signatures: Vec<String>, amount_in: u64 },
+    Confirmed { signature: String, slot: u64, success: bool },
+    Unconfirmed { signature: String },
+    Reconciled { reconciliation: ReconciliationSummary },
+    KillSwitchTripped { reason: String },
+    BundleAbandoned { attempts: Vec<BundleAttempt>, reason: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub timestamp: u64,
+    pub cycle_id: u64,
+    pub paper: bool,
+    #[serde(flatten)]
+    pub event: JournalEvent,
+}
+
+/// Append-only JSON-lines log of every execution step. Unlike the ledger it
+/// also keeps sends that never confirmed, for post-mortems.
+#[derive(Debug, Clone)]
+pub struct ExecutionJournal {
+    path: String,
+}
+
+impl ExecutionJournal {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub async fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry).context("Failed to serialize journal entry")?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .context(format!("Failed to open journal {}", self.path))?;
+        file.write_all(line.as_bytes())
+            .await
+            .context(format!("Failed to append to journal {}", self.path))?;
+        file.flush().await?;
+        Ok(())
+    }
+}
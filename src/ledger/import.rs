@@ -0,0 +1,455 @@
+use crate::constants::sol_mint;
+use crate::engine::compute_profile::{attribute_compute, parse_compute_logs};
+use crate::engine::types::DexType;
+use crate::ledger::{append_records, ledger_signatures, LedgerRecord, LedgerStatus};
+use crate::persist::atomic_write_json;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding, UiTransactionTokenBalance};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+/// What an imported wallet transaction looked like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryClass {
+    Arbitrage,
+    FailedArbitrage,
+    Other,
+}
+
+/// The parts of a landed wallet transaction the classifier looks at
+#[derive(Debug, Clone, Default)]
+pub struct TransactionSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    pub fee_lamports: u64,
+    pub venues: Vec<DexType>,                 // One entry per venue program invocation
+    pub base_delta: i128,                     // SOL + wSOL change of the wallet, before the fee
+    pub token_deltas: BTreeMap<String, i128>, // Non-SOL mints
+}
+
+/// An arbitrage swaps through at least two venue programs and leaves no
+/// residue in any other token: the only lasting effect is on the base mint.
+pub fn classify(summary: &TransactionSummary) -> HistoryClass {
+    if summary.venues.len() < 2 {
+        return HistoryClass::Other;
+    }
+    if summary.failed {
+        return HistoryClass::FailedArbitrage;
+    }
+    if summary.token_deltas.values().all(|delta| *delta == 0) {
+        HistoryClass::Arbitrage
+    } else {
+        HistoryClass::Other
+    }
+}
+
+/// Build the ledger record for an arbitrage-shaped transaction
+pub fn to_ledger_record(summary: &TransactionSummary, class: HistoryClass) -> Option<LedgerRecord> {
+    let status = match class {
+        HistoryClass::Arbitrage => LedgerStatus::Landed,
+        HistoryClass::FailedArbitrage => LedgerStatus::Failed,
+        HistoryClass::Other => return None,
+    };
+    Some(LedgerRecord {
+        signature: summary.signature.clone(),
+        slot: summary.slot,
+        block_time: summary.block_time,
+        status,
+        cycle_id: None,
+        venues: summary.venues.clone(),
+        base_mint: sol_mint().to_string(),
+        base_delta: summary.base_delta,
+        fee_lamports: summary.fee_lamports,
+        token_deltas: summary.token_deltas.clone(),
+        imported: true,
+        paper: false,
+        reconciliation: None,
+        bundle_attempts: Vec::new(),
+        fingerprint: None,
+    })
+}
+
+/// Summarize a fetched transaction from `wallet`'s point of view
+pub fn summarize_transaction(
+    wallet: &Pubkey,
+    signature: &str,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<TransactionSummary> {
+    let meta = tx
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} has no meta", signature))?;
+    let decoded = tx
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| anyhow::anyhow!("Failed to decode transaction {}", signature))?;
+
+    let logs: Option<Vec<String>> = meta.log_messages.clone().into();
+    let venues = attribute_compute(&parse_compute_logs(&logs.unwrap_or_default()))
+        .legs
+        .into_iter()
+        .map(|(dex_type, _)| dex_type)
+        .collect();
+
+    // Native SOL change of the wallet, with the fee added back when it paid it
+    let keys = decoded.message.static_account_keys();
+    let mut base_delta: i128 = 0;
+    if let Some(index) = keys.iter().position(|key| key == wallet) {
+        if let (Some(pre), Some(post)) = (meta.pre_balances.get(index), meta.post_balances.get(index)) {
+            base_delta += *post as i128 - *pre as i128;
+        }
+        if index == 0 {
+            base_delta += meta.fee as i128;
+        }
+    }
+
+    let wallet = wallet.to_string();
+    let mut token_deltas: BTreeMap<String, i128> = BTreeMap::new();
+    let mut apply = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>, sign: i128| {
+        if let OptionSerializer::Some(balances) = balances {
+            for balance in balances {
+                let owner: Option<&String> = balance.owner.as_ref().into();
+                if owner != Some(&wallet) {
+                    continue;
+                }
+                let amount = balance.ui_token_amount.amount.parse::<i128>().unwrap_or(0);
+                *token_deltas.entry(balance.mint.clone()).or_default() += sign * amount;
+            }
+        }
+    };
+    apply(&meta.pre_token_balances, -1);
+    apply(&meta.post_token_balances, 1);
+
+    // Wrapped SOL counts toward the base mint, not as a token residue
+    if let Some(wsol) = token_deltas.remove(&sol_mint().to_string()) {
+        base_delta += wsol;
+    }
+
+    Ok(TransactionSummary {
+        signature: signature.to_string(),
+        slot: tx.slot,
+        block_time: tx.block_time,
+        failed: meta.err.is_some(),
+        fee_lamports: meta.fee,
+        venues,
+        base_delta,
+        token_deltas,
+    })
+}
+
+/// Progress of an import, so an interrupted run continues where it stopped
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportCursor {
+    pub before: Option<String>, // Oldest signature already processed; paging continues below it
+    pub done: bool,
+}
+
+impl ImportCursor {
+    pub async fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .context(format!("Failed to read import cursor {}", path))?;
+        serde_json::from_str(&content).context(format!("Failed to parse import cursor {}", path))
+    }
+
+    pub async fn save(&self, path: &str) -> Result<()> {
+        atomic_write_json(path, self).await
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date as the unix timestamp of its midnight UTC
+pub fn parse_date(date: &str) -> Result<i64> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        anyhow::bail!("Invalid date {}, expected YYYY-MM-DD", date);
+    };
+    let (year, month, day): (i64, i64, i64) = (year.parse()?, month.parse()?, day.parse()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        anyhow::bail!("Invalid date {}, expected YYYY-MM-DD", date);
+    }
+
+    // Days since the unix epoch for a proleptic Gregorian date
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Ok((era * 146_097 + doe - 719_468) * 86_400)
+}
+
+pub struct ImportOptions {
+    pub since: Option<i64>, // Inclusive unix timestamp
+    pub until: Option<i64>, // Exclusive unix timestamp
+    pub requests_per_second: u32,
+}
+
+/// `import-history` subcommand: page backwards through the wallet's
+/// signatures, classify each transaction and append arbitrage-shaped ones to
+/// the ledger marked as imported.
+pub async fn run_import_history(
+    rpc_url: &str,
+    wallet: &Pubkey,
+    ledger_file: &str,
+    cursor_file: &str,
+    options: &ImportOptions,
+) -> Result<()> {
+    let rpc_client = RpcClient::new(rpc_url.to_string());
+    let delay = Duration::from_secs_f64(1.0 / options.requests_per_second.max(1) as f64);
+    let mut cursor = ImportCursor::load(cursor_file).await?;
+    if cursor.done {
+        info!("📜 History import already complete per {}; delete it to re-run", cursor_file);
+        return Ok(());
+    }
+    let mut known = ledger_signatures(ledger_file).await?;
+    let (mut imported, mut failed, mut skipped) = (0usize, 0usize, 0usize);
+
+    'pages: loop {
+        let before = cursor
+            .before
+            .as_deref()
+            .map(|s| s.parse::<Signature>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid cursor signature: {}", e))?;
+        let page = rpc_client
+            .get_signatures_for_address_with_config(
+                wallet,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(SIGNATURE_PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .context("Failed to fetch wallet signatures")?;
+        tokio::time::sleep(delay).await;
+
+        if page.is_empty() {
+            break;
+        }
+
+        for entry in page {
+            // Signatures arrive newest first, so everything past `since` is older still
+            if let (Some(since), Some(block_time)) = (options.since, entry.block_time) {
+                if block_time < since {
+                    break 'pages;
+                }
+            }
+            let in_range = match (options.until, entry.block_time) {
+                (Some(until), Some(block_time)) => block_time < until,
+                _ => true,
+            };
+
+            if in_range && !known.contains(&entry.signature) {
+                match fetch_and_classify(&rpc_client, wallet, &entry.signature) {
+                    Ok(Some(record)) => {
+                        if record.status == LedgerStatus::Failed {
+                            failed += 1;
+                        } else {
+                            imported += 1;
+                        }
+                        append_records(ledger_file, std::slice::from_ref(&record)).await?;
+                        known.insert(record.signature);
+                    }
+                    Ok(None) => skipped += 1,
+                    Err(e) => {
+                        // Leave the cursor here so a re-run retries this transaction
+                        warn!("Stopping import at {}: {}", entry.signature, e);
+                        return Err(e);
+                    }
+                }
+                tokio::time::sleep(delay).await;
+            }
+
+            cursor.before = Some(entry.signature);
+            cursor.save(cursor_file).await?;
+        }
+    }
+
+    cursor.done = true;
+    cursor.save(cursor_file).await?;
+    info!(
+        "📜 History import complete: {} arbitrages, {} failed arbitrages imported, {} other transactions skipped",
+        imported, failed, skipped
+    );
+    Ok(())
+}
+
+fn fetch_and_classify(rpc_client: &RpcClient, wallet: &Pubkey, signature: &str) -> Result<Option<LedgerRecord>> {
+    let parsed = signature
+        .parse::<Signature>()
+        .map_err(|e| anyhow::anyhow!("Invalid signature {}: {}", signature, e))?;
+    let tx = rpc_client
+        .get_transaction_with_config(
+            &parsed,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .context(format!("Failed to fetch transaction {}", signature))?;
+
+    let summary = summarize_transaction(wallet, signature, &tx)?;
+    let class = classify(&summary);
+    debug!("{}: {:?} ({} venue invocations)", signature, class, summary.venues.len());
+    Ok(to_ledger_record(&summary, class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use serde_json::{json, Value};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+
+    const FEE: u64 = 5_000;
+
+    /// Log lines of an executor invocation wrapping one CPI per venue; the
+    /// venue at `failing` aborts the transaction
+    fn logs(executor: &Pubkey, venues: &[DexType], failing: Option<usize>) -> Vec<String> {
+        let mut lines = vec![format!("Program {} invoke [1]", executor)];
+        for (i, venue) in venues.iter().enumerate() {
+            let id = venue.program_id();
+            lines.push(format!("Program {} invoke [2]", id));
+            lines.push(format!("Program {} consumed 30000 of 1300000 compute units", id));
+            if failing == Some(i) {
+                lines.push(format!("Program {} failed: custom program error: 0x1771", id));
+                lines.push(format!("Program {} consumed 70000 of 1400000 compute units", executor));
+                lines.push(format!("Program {} failed: custom program error: 0x1771", executor));
+                return lines;
+            }
+            lines.push(format!("Program {} success", id));
+        }
+        lines.push(format!("Program {} consumed 90000 of 1400000 compute units", executor));
+        lines.push(format!("Program {} success", executor));
+        lines
+    }
+
+    fn token_balance(wallet: &Pubkey, mint: &Pubkey, amount: u64) -> Value {
+        json!({
+            "accountIndex": 1,
+            "mint": mint.to_string(),
+            "uiTokenAmount": { "uiAmount": null, "decimals": 9, "amount": amount.to_string(), "uiAmountString": "0" },
+            "owner": wallet.to_string(),
+        })
+    }
+
+    /// A landed transaction paid by `wallet`, as `getTransaction` returns it in base64
+    fn fixture(
+        wallet: &Pubkey,
+        logs: Vec<String>,
+        lamports: (u64, u64),
+        token_balances: &[(Pubkey, u64, u64)],
+        failed: bool,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let transfer = system_instruction::transfer(wallet, &Pubkey::new_unique(), 1);
+        let tx = Transaction::new_with_payer(&[transfer], Some(wallet));
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap());
+        let err = json!({ "InstructionError": [0, { "Custom": 6001 }] });
+
+        serde_json::from_value(json!({
+            "slot": 250_000_000,
+            "blockTime": 1_700_000_000,
+            "transaction": [encoded, "base64"],
+            "meta": {
+                "err": if failed { err.clone() } else { Value::Null },
+                "status": if failed { json!({ "Err": err }) } else { json!({ "Ok": null }) },
+                "fee": FEE,
+                "preBalances": [lamports.0, 1_000_000, 1],
+                "postBalances": [lamports.1, 1_000_000, 1],
+                "logMessages": logs,
+                "preTokenBalances": token_balances.iter().map(|(mint, pre, _)| token_balance(wallet, mint, *pre)).collect::<Vec<_>>(),
+                "postTokenBalances": token_balances.iter().map(|(mint, _, post)| token_balance(wallet, mint, *post)).collect::<Vec<_>>(),
+            },
+        }))
+        .unwrap()
+    }
+
+    fn summarize(wallet: &Pubkey, tx: &EncodedConfirmedTransactionWithStatusMeta) -> TransactionSummary {
+        summarize_transaction(wallet, "fixture", tx).unwrap()
+    }
+
+    #[test]
+    fn a_two_venue_round_trip_back_to_wsol_is_an_arbitrage() {
+        let (wallet, executor, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let tx = fixture(
+            &wallet,
+            logs(&executor, &[DexType::RaydiumV4, DexType::Whirlpool], None),
+            (1_000_000_000, 1_000_000_000 - FEE),
+            // Bonk is bought and sold in full; wSOL comes back 0.012 SOL up
+            &[(sol_mint(), 2_000_000_000, 2_012_000_000), (bonk, 500, 500)],
+            false,
+        );
+
+        let summary = summarize(&wallet, &tx);
+        assert_eq!(summary.venues, vec![DexType::RaydiumV4, DexType::Whirlpool]);
+        assert_eq!(summary.base_delta, 12_000_000);
+        assert_eq!(summary.token_deltas[&bonk.to_string()], 0);
+        assert_eq!(classify(&summary), HistoryClass::Arbitrage);
+
+        let record = to_ledger_record(&summary, classify(&summary)).unwrap();
+        assert_eq!(record.status, LedgerStatus::Landed);
+        assert_eq!((record.base_delta, record.fee_lamports), (12_000_000, FEE));
+        assert!(record.imported);
+    }
+
+    #[test]
+    fn a_plain_transfer_is_not_imported() {
+        let wallet = Pubkey::new_unique();
+        let system = solana_sdk::system_program::id();
+        let tx = fixture(
+            &wallet,
+            vec![format!("Program {} invoke [1]", system), format!("Program {} success", system)],
+            (1_000_000_000, 500_000_000 - FEE),
+            &[],
+            false,
+        );
+
+        let summary = summarize(&wallet, &tx);
+        assert!(summary.venues.is_empty());
+        assert_eq!(summary.base_delta, -500_000_000);
+        assert_eq!(classify(&summary), HistoryClass::Other);
+        assert!(to_ledger_record(&summary, classify(&summary)).is_none());
+    }
+
+    #[test]
+    fn a_reverted_round_trip_is_a_failed_arbitrage_that_only_cost_the_fee() {
+        let (wallet, executor, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let tx = fixture(
+            &wallet,
+            logs(&executor, &[DexType::RaydiumV4, DexType::Whirlpool], Some(1)),
+            (1_000_000_000, 1_000_000_000 - FEE),
+            &[(sol_mint(), 2_000_000_000, 2_000_000_000), (bonk, 0, 0)],
+            true,
+        );
+
+        let summary = summarize(&wallet, &tx);
+        assert!(summary.failed);
+        assert_eq!(summary.venues.len(), 2);
+        assert_eq!(classify(&summary), HistoryClass::FailedArbitrage);
+
+        let record = to_ledger_record(&summary, classify(&summary)).unwrap();
+        assert_eq!(record.status, LedgerStatus::Failed);
+        assert_eq!(record.net_delta(), -(FEE as i128));
+        assert!(record.imported);
+    }
+}
@@ -0,0 +1,210 @@
+pub mod import;
+
+pub use import::*;
+
+use crate::engine::reconcile::ReconciliationSummary;
+use crate::constants::sol_mint;
+use crate::engine::types::DexType;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerStatus {
+    Landed,
+    Failed,
+}
+
+/// One executed (or attempted) cycle in the P&L ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub status: LedgerStatus,
+    pub cycle_id: Option<u64>,
+    pub venues: Vec<DexType>,
+    pub base_mint: String,
+    pub base_delta: i128,                    // Change in the base mint before the network fee, native units
+    pub fee_lamports: u64,
+    pub token_deltas: BTreeMap<String, i128>, // Key: mint, Value: wallet balance change in native units
+    #[serde(default)]
+    pub imported: bool,                      // Reconstructed from wallet history rather than recorded live
+    #[serde(default)]
+    pub paper: bool,                         // Filled by the paper market simulator, never sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconciliation: Option<ReconciliationSummary>, // Post-trade balance check, when the cycle was reconciled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bundle_attempts: Vec<BundleAttempt>, // Every submission of a cycle sent as a bundle, with its tip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,          // Commit and config hash of the run that filled it; absent when imported
+}
+
+/// One submission of a bundle to the block engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleAttempt {
+    pub attempt: u32, // 1 for the first submission
+    pub bundle_id: String,
+    pub tip_lamports: u64,
+    pub sent_slot: u64,
+}
+
+impl LedgerRecord {
+    /// Base mint result after the network fee. Only meaningful in lamports when the base mint is SOL.
+    pub fn net_delta(&self) -> i128 {
+        self.base_delta - self.fee_lamports as i128
+    }
+}
+
+/// Load every record from a JSON-lines ledger, returning nothing if it does not exist yet
+pub async fn load_ledger(path: &str) -> Result<Vec<LedgerRecord>> {
+    if !Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .context(format!("Failed to read ledger {}", path))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| serde_json::from_str(line).context(format!("Invalid ledger record on line {}", i + 1)))
+        .collect()
+}
+
+pub async fn ledger_signatures(path: &str) -> Result<HashSet<String>> {
+    Ok(load_ledger(path).await?.into_iter().map(|record| record.signature).collect())
+}
+
+/// Append records to a JSON-lines ledger, one record per line
+pub async fn append_records(path: &str, records: &[LedgerRecord]) -> Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&serde_json::to_string(record).context("Failed to serialize ledger record")?);
+        lines.push('\n');
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .context(format!("Failed to open ledger {}", path))?;
+    file.write_all(lines.as_bytes())
+        .await
+        .context(format!("Failed to append to ledger {}", path))?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// P&L per base mint. Each mint's result stays in its own native units and
+/// fees, always paid in lamports, are kept apart; only the USD total mixes them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PnlReport {
+    pub base_deltas: BTreeMap<String, i128>, // Key: base mint, Value: summed base_delta before fees
+    pub fee_lamports: u64,
+    pub records: usize,
+}
+
+impl PnlReport {
+    pub fn from_records(records: &[LedgerRecord]) -> Self {
+        let mut report = Self::default();
+        for record in records {
+            report.add(record);
+        }
+        report
+    }
+
+    pub fn add(&mut self, record: &LedgerRecord) {
+        *self.base_deltas.entry(record.base_mint.clone()).or_default() += record.base_delta;
+        self.fee_lamports += record.fee_lamports;
+        self.records += 1;
+    }
+
+    /// Net result in USD: each base mint valued with `usd_per_unit` (USD per
+    /// native unit) and fees at SOL's. `None` if any of them has no price.
+    pub fn usd_total(&self, usd_per_unit: impl Fn(&Pubkey) -> Option<f64>) -> Option<f64> {
+        let fees = self.fee_lamports as f64 * usd_per_unit(&sol_mint())?;
+        self.base_deltas.iter().try_fold(-fees, |total, (mint, delta)| {
+            let mint = Pubkey::from_str(mint).ok()?;
+            Some(total + *delta as f64 * usd_per_unit(&mint)?)
+        })
+    }
+
+    pub fn log(&self, usd_total: Option<f64>) {
+        let per_mint: Vec<String> = self.base_deltas.iter().map(|(mint, delta)| format!("{} {}", delta, mint)).collect();
+        let usd = usd_total.map_or_else(|| "unpriced".to_string(), |usd| format!("${:.2}", usd));
+        info!("📒 P&L over {} records: {} before {} lamports of fees, {} net", self.records, per_mint.join(", "), self.fee_lamports, usd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::usdc_mint;
+
+    fn record(base_mint: Pubkey, base_delta: i128, fee_lamports: u64) -> LedgerRecord {
+        LedgerRecord {
+            signature: String::new(),
+            slot: 0,
+            block_time: None,
+            status: LedgerStatus::Landed,
+            cycle_id: None,
+            venues: Vec::new(),
+            base_mint: base_mint.to_string(),
+            base_delta,
+            fee_lamports,
+            token_deltas: BTreeMap::new(),
+            imported: false,
+            paper: true,
+            reconciliation: None,
+            bundle_attempts: Vec::new(),
+            fingerprint: None,
+        }
+    }
+
+    // $150 per SOL, USDC at par with 6 decimals
+    fn usd_per_unit(mint: &Pubkey) -> Option<f64> {
+        if *mint == sol_mint() {
+            Some(150.0 / 1e9)
+        } else if *mint == usdc_mint() {
+            Some(1.0 / 1e6)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn pnl_is_kept_per_base_mint_and_totalled_in_usd() {
+        let report = PnlReport::from_records(&[
+            record(sol_mint(), 20_000_000, 10_000),
+            record(usdc_mint(), 3_000_000, 10_000),
+            record(sol_mint(), -5_000_000, 10_000),
+            record(usdc_mint(), -500_000, 10_000),
+        ]);
+
+        assert_eq!(report.base_deltas[&sol_mint().to_string()], 15_000_000);
+        assert_eq!(report.base_deltas[&usdc_mint().to_string()], 2_500_000);
+        assert_eq!(report.fee_lamports, 40_000);
+        assert_eq!(report.records, 4);
+        // 0.015 SOL + 2.5 USDC - 0.00004 SOL of fees
+        let usd = report.usd_total(usd_per_unit).unwrap();
+        assert!((usd - (2.25 + 2.5 - 0.006)).abs() < 1e-9, "{}", usd);
+    }
+
+    #[test]
+    fn an_unpriced_base_mint_leaves_the_usd_total_unknown() {
+        let report = PnlReport::from_records(&[record(sol_mint(), 1_000, 0), record(Pubkey::new_unique(), 1_000, 0)]);
+
+        assert_eq!(report.usd_total(usd_per_unit), None);
+    }
+}
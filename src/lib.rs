@@ -1,11 +1,28 @@
 pub mod ata;
 pub mod bot;
+pub mod bundle;
+pub mod clock;
 pub mod config;
+pub mod confirm;
 pub mod constants;
 pub mod dex;
 pub mod engine;
+pub mod execute;
 pub mod discovery;
+pub mod health;
+pub mod journal;
+pub mod ledger;
+pub mod math;
+pub mod mint_registry;
+pub mod partition;
+pub mod persist;
 pub mod pool_refreshers;
 pub mod pools;
+#[cfg(feature = "profiling")]
+pub mod profiling;
 pub mod refresh;
-pub mod transaction;
\ No newline at end of file
+pub mod rpc;
+pub mod strict;
+pub mod token_extensions;
+pub mod transaction;
+pub mod units;
\ No newline at end of file
@@ -1,4 +1,10 @@
-use solana_onchain_arbitrage_bot::bot::run_bot;
+use solana_onchain_arbitrage_bot::bot::{load_keypair, run_bot};
+use solana_onchain_arbitrage_bot::config::Config;
+use solana_onchain_arbitrage_bot::dex::selftest::run_capture_decoder_fixtures;
+use solana_onchain_arbitrage_bot::discovery::{run_discovery_explain, run_discovery_rank, run_discovery_trends, DiscoveryConfig};
+use solana_onchain_arbitrage_bot::engine::run_profile_compute;
+use solana_onchain_arbitrage_bot::ledger::{parse_date, run_import_history, ImportOptions};
+use solana_sdk::signer::Signer;
 use clap::{App, Arg};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
@@ -26,12 +32,235 @@ async fn main() -> anyhow::Result<()> {
                 .takes_value(true)
                 .default_value("config.toml"),
         )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for randomized components (compute unit jitter, fee account choice)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("Stop on the first parser or price anomaly, dumping the offending account (overrides [bot] strict_mode)"),
+        )
+        .subcommand(
+            App::new("discovery-trends")
+                .about("Report token universe trends from archived discovery runs as CSV")
+                .arg(
+                    Arg::with_name("archive-dir")
+                        .long("archive-dir")
+                        .value_name("DIR")
+                        .help("Directory holding archived discovery runs")
+                        .takes_value(true)
+                        .default_value("discovery_archive"),
+                )
+                .arg(
+                    Arg::with_name("report")
+                        .long("report")
+                        .value_name("REPORT")
+                        .help("Report to produce")
+                        .takes_value(true)
+                        .possible_values(&["universe", "liquidity", "flaps"])
+                        .default_value("universe"),
+                )
+                .arg(
+                    Arg::with_name("min-transitions")
+                        .long("min-transitions")
+                        .value_name("N")
+                        .help("Minimum entries + exits for a token to be listed as a flap candidate")
+                        .takes_value(true)
+                        .default_value("2"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the CSV to a file instead of stdout")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("discovery-rank")
+                .about("Rank discovered tokens by arbitrage potential as CSV")
+                .arg(
+                    Arg::with_name("input")
+                        .long("input")
+                        .value_name("FILE")
+                        .help("Discovery output to rank")
+                        .takes_value(true)
+                        .default_value("discovered_pools.json"),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .value_name("N")
+                        .help("Number of tokens to report")
+                        .takes_value(true)
+                        .default_value("50"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the CSV to a file instead of stdout")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("discovery-explain")
+                .about("Run discovery for a single token against live APIs and log why each of its pools is kept or excluded")
+                .arg(
+                    Arg::with_name("mint")
+                        .value_name("MINT")
+                        .help("Token mint to explain")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("profile-compute")
+                .about("Attribute compute units of landed transactions to venues and update the compute profile")
+                .arg(
+                    Arg::with_name("signature")
+                        .value_name("SIGNATURE")
+                        .help("Signatures of landed arbitrage transactions")
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            App::new("capture-decoder-fixture")
+                .about("Snapshot the known pool accounts into the decoder self-test fixtures")
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .value_name("PUBKEY")
+                        .help("Only snapshot this known account (default: all of them)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("import-history")
+                .about("Import arbitrage transactions from the wallet's history into the P&L ledger")
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("YYYY-MM-DD")
+                        .help("Only import transactions from this date on (UTC)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .value_name("YYYY-MM-DD")
+                        .help("Only import transactions before this date (UTC)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("rps")
+                        .long("rps")
+                        .value_name("N")
+                        .help("Maximum RPC requests per second")
+                        .takes_value(true)
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::with_name("cursor-file")
+                        .long("cursor-file")
+                        .value_name("FILE")
+                        .help("Progress file used to resume an interrupted import")
+                        .takes_value(true)
+                        .default_value("import_history_cursor.json"),
+                ),
+        )
         .get_matches();
 
+    if let Some(rank) = matches.subcommand_matches("discovery-rank") {
+        let top = rank
+            .value_of("top")
+            .unwrap()
+            .parse::<usize>()
+            .map_err(|e| anyhow::anyhow!("Invalid --top: {}", e))?;
+        return run_discovery_rank(rank.value_of("input").unwrap(), top, rank.value_of("output")).await;
+    }
+
+    if let Some(explain) = matches.subcommand_matches("discovery-explain") {
+        let config = Config::load(matches.value_of("config").unwrap())?;
+        let discovery_config = config.discovery.as_ref().map(DiscoveryConfig::from).unwrap_or_default();
+        return run_discovery_explain(&config.rpc.url, discovery_config, explain.value_of("mint").unwrap()).await;
+    }
+
+    if let Some(capture) = matches.subcommand_matches("capture-decoder-fixture") {
+        let config = Config::load(matches.value_of("config").unwrap())?;
+        let selftest_config = config.selftest.clone().unwrap_or_default();
+        return run_capture_decoder_fixtures(
+            &config.rpc.url,
+            &selftest_config.fixtures_file,
+            &selftest_config.accounts_file,
+            capture.value_of("account"),
+        )
+        .await;
+    }
+
+    if let Some(import) = matches.subcommand_matches("import-history") {
+        let config = Config::load(matches.value_of("config").unwrap())?;
+        let wallet = load_keypair(&config.wallet.private_key)?.pubkey();
+        let ledger_config = config.ledger.clone().unwrap_or_default();
+        let options = ImportOptions {
+            since: import.value_of("since").map(parse_date).transpose()?,
+            until: import.value_of("until").map(parse_date).transpose()?,
+            requests_per_second: import
+                .value_of("rps")
+                .unwrap()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid --rps: {}", e))?,
+        };
+        return run_import_history(
+            &config.rpc.url,
+            &wallet,
+            &ledger_config.file,
+            import.value_of("cursor-file").unwrap(),
+            &options,
+        )
+        .await;
+    }
+
+    if let Some(profile) = matches.subcommand_matches("profile-compute") {
+        let config = Config::load(matches.value_of("config").unwrap())?;
+        let engine_config = config.engine.clone().unwrap_or_default();
+        let signatures: Vec<&str> = profile.values_of("signature").unwrap().collect();
+        return run_profile_compute(&config.rpc.url, &engine_config.compute_profile_file, &signatures).await;
+    }
+
+    if let Some(trends) = matches.subcommand_matches("discovery-trends") {
+        let min_transitions = trends
+            .value_of("min-transitions")
+            .unwrap()
+            .parse::<usize>()
+            .map_err(|e| anyhow::anyhow!("Invalid --min-transitions: {}", e))?;
+        return run_discovery_trends(
+            trends.value_of("archive-dir").unwrap(),
+            trends.value_of("report").unwrap(),
+            min_transitions,
+            trends.value_of("output"),
+        )
+        .await;
+    }
+
     let config_path = matches.value_of("config").unwrap();
     info!("Using config file: {}", config_path);
 
-    run_bot(config_path).await?;
+    let seed = matches
+        .value_of("seed")
+        .map(|s| s.parse::<u64>())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Invalid --seed: {}", e))?;
+
+    run_bot(config_path, seed, matches.is_present("strict")).await?;
 
     Ok(())
 }
\ No newline at end of file
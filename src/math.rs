@@ -0,0 +1,146 @@
+use std::fmt;
+
+/// 2^64 as f64; every finite f64 below it truncates into a u64
+const U64_LIMIT: f64 = 18_446_744_073_709_551_616.0;
+/// 2^63 as f64, the same bound for i64
+const I64_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+/// Why a float could not be turned into an integer amount
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConversionError {
+    NotFinite(f64),
+    Negative(f64),
+    Overflow(f64),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::NotFinite(value) => write!(f, "non-finite amount {}", value),
+            ConversionError::Negative(value) => write!(f, "negative amount {}", value),
+            ConversionError::Overflow(value) => write!(f, "amount {:e} overflows 64 bits", value),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Floor a modeled amount to integer base units. `as u64` would turn NaN into
+/// 0 and saturate out-of-range values; both are errors here. Above 2^53 the
+/// f64 itself carries a relative error of about 1e-16, which is far below any
+/// fee or slippage the amount is modeled with.
+pub fn lamports_from_f64(value: f64) -> Result<u64, ConversionError> {
+    if !value.is_finite() {
+        return Err(ConversionError::NotFinite(value));
+    }
+    if value < 0.0 {
+        return Err(ConversionError::Negative(value));
+    }
+    if value >= U64_LIMIT {
+        return Err(ConversionError::Overflow(value));
+    }
+    Ok(value as u64)
+}
+
+/// `amount * factor`, floored to base units
+pub fn mul_price(amount: u64, factor: f64) -> Result<u64, ConversionError> {
+    if !factor.is_finite() {
+        return Err(ConversionError::NotFinite(factor));
+    }
+    lamports_from_f64(amount as f64 * factor)
+}
+
+/// Basis points gained by multiplying an amount by `ratio`, truncated toward zero
+pub fn bps_from_ratio(ratio: f64) -> Result<i64, ConversionError> {
+    let bps = (ratio - 1.0) * 10_000.0;
+    if !bps.is_finite() {
+        return Err(ConversionError::NotFinite(ratio));
+    }
+    if bps.abs() >= I64_LIMIT {
+        return Err(ConversionError::Overflow(ratio));
+    }
+    Ok(bps as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Finite and non-finite values spread over every magnitude an f64 can
+    /// hold, plus the edges of the u64 range
+    fn extreme_values(rng: &mut StdRng) -> Vec<f64> {
+        let mut values: Vec<f64> = (0..20_000).map(|_| f64::from_bits(rng.gen())).collect();
+        values.extend((0..2_000).map(|_| rng.gen_range(0.0..1.0) * 2f64.powi(rng.gen_range(-60..80))));
+        values.extend([
+            0.0,
+            -0.0,
+            f64::MIN_POSITIVE,
+            2f64.powi(53),
+            2f64.powi(53) + 2.0,
+            U64_LIMIT,
+            U64_LIMIT - 2048.0, // Largest f64 below 2^64
+            u64::MAX as f64,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+        ]);
+        values
+    }
+
+    #[test]
+    fn lamports_are_the_exact_floor_or_an_error_never_a_saturated_value() {
+        let mut rng = StdRng::seed_from_u64(226);
+        for value in extreme_values(&mut rng) {
+            match lamports_from_f64(value) {
+                Ok(lamports) => {
+                    assert!((0.0..U64_LIMIT).contains(&value), "{} accepted", value);
+                    assert_eq!(lamports as f64, value.trunc(), "{} floored to {}", value, lamports);
+                }
+                Err(ConversionError::NotFinite(_)) => assert!(!value.is_finite()),
+                Err(ConversionError::Negative(_)) => assert!(value < 0.0),
+                Err(ConversionError::Overflow(_)) => assert!(value >= U64_LIMIT),
+            }
+        }
+        assert_eq!(lamports_from_f64(U64_LIMIT - 2048.0), Ok(u64::MAX - 2047));
+        assert!(matches!(lamports_from_f64(u64::MAX as f64), Err(ConversionError::Overflow(_))));
+        assert!(matches!(lamports_from_f64(f64::NAN), Err(ConversionError::NotFinite(_))));
+    }
+
+    #[test]
+    fn a_price_product_past_u64_is_an_overflow_not_a_wraparound() {
+        let mut rng = StdRng::seed_from_u64(2262);
+        let amounts = [0, 1, 1_000_000_000, 1 << 53, u64::MAX / 2, u64::MAX];
+        for factor in extreme_values(&mut rng) {
+            for amount in amounts {
+                let product = amount as f64 * factor;
+                match mul_price(amount, factor) {
+                    Ok(out) => assert_eq!(out as f64, product.trunc(), "{} * {}", amount, factor),
+                    Err(ConversionError::NotFinite(_)) => assert!(!factor.is_finite() || !product.is_finite()),
+                    Err(ConversionError::Negative(_)) => assert!(product < 0.0),
+                    Err(ConversionError::Overflow(_)) => assert!(product >= U64_LIMIT),
+                }
+            }
+        }
+        assert!(matches!(mul_price(u64::MAX, 1.5), Err(ConversionError::Overflow(_))));
+        assert!(matches!(mul_price(0, f64::INFINITY), Err(ConversionError::NotFinite(_))));
+    }
+
+    #[test]
+    fn basis_points_truncate_toward_zero_within_i64() {
+        let mut rng = StdRng::seed_from_u64(22626);
+        for ratio in extreme_values(&mut rng) {
+            let bps = (ratio - 1.0) * 10_000.0;
+            match bps_from_ratio(ratio) {
+                Ok(out) => assert_eq!(out as f64, bps.trunc(), "ratio {}", ratio),
+                Err(ConversionError::NotFinite(_)) => assert!(!bps.is_finite()),
+                Err(ConversionError::Overflow(_)) => assert!(bps.abs() >= I64_LIMIT),
+                Err(ConversionError::Negative(_)) => panic!("ratio {} reported negative", ratio),
+            }
+        }
+        assert_eq!(bps_from_ratio(1.0123), Ok(122));
+        assert_eq!(bps_from_ratio(0.9877), Ok(-122));
+    }
+}
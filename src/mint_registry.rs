@@ -0,0 +1,121 @@
+use crate::engine::types::SwapLeg;
+use anyhow::Context;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Static metadata of one mint
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MintInfo {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Mint metadata preloaded from a file, consulted before any network lookup.
+/// The file is a JSON object keyed by mint address:
+/// `{ "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v": { "symbol": "USDC", "decimals": 6 } }`
+#[derive(Debug, Clone, Default)]
+pub struct MintRegistry {
+    mints: HashMap<Pubkey, MintInfo>,
+}
+
+impl MintRegistry {
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).context(format!("Failed to read mint registry {}", path))?;
+        let raw: HashMap<String, MintInfo> =
+            serde_json::from_str(&content).context(format!("Failed to parse mint registry {}", path))?;
+        let mints = raw
+            .into_iter()
+            .map(|(mint, info)| {
+                Pubkey::from_str(&mint)
+                    .map(|mint| (mint, info))
+                    .map_err(|e| anyhow::anyhow!("Invalid mint {} in {}: {}", mint, path, e))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { mints })
+    }
+
+    pub fn len(&self) -> usize {
+        self.mints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mints.is_empty()
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<&MintInfo> {
+        self.mints.get(mint)
+    }
+
+    pub fn decimals(&self, mint: &Pubkey) -> Option<u8> {
+        self.get(mint).map(|info| info.decimals)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, &MintInfo)> {
+        self.mints.iter()
+    }
+
+    /// Symbol for logs, falling back to the first characters of the address
+    pub fn label(&self, mint: &Pubkey) -> String {
+        match self.get(mint) {
+            Some(info) => info.symbol.clone(),
+            None => mint.to_string().chars().take(8).collect(),
+        }
+    }
+
+    /// Mints a cycle passes through, e.g. `SOL -> BONK -> SOL`
+    pub fn route(&self, legs: &[SwapLeg]) -> String {
+        let mut route: Vec<String> = legs.iter().map(|leg| self.label(&leg.from_mint)).collect();
+        if let Some(last) = legs.last() {
+            route.push(self.label(&last.to_mint));
+        }
+        route.join(" -> ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+    use crate::engine::types::DexType;
+
+    fn registry(content: &str) -> anyhow::Result<MintRegistry> {
+        let path = std::env::temp_dir().join(format!("{}_mints.json", Pubkey::new_unique()));
+        std::fs::write(&path, content).unwrap();
+        MintRegistry::from_file(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn registered_mints_are_labelled_by_symbol_in_routes() {
+        let bonk = Pubkey::new_unique();
+        let registry = registry(&format!(
+            r#"{{ "{}": {{ "symbol": "SOL", "decimals": 9 }}, "{}": {{ "symbol": "BONK", "decimals": 5 }} }}"#,
+            sol_mint(),
+            bonk
+        ))
+        .unwrap();
+        let leg = |from_mint, to_mint| SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey: Pubkey::new_unique(),
+            dex_type: DexType::RaydiumV4,
+            program_id: DexType::RaydiumV4.program_id(),
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        };
+
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.decimals(&bonk), Some(5));
+        assert_eq!(registry.route(&[leg(sol_mint(), bonk), leg(bonk, sol_mint())]), "SOL -> BONK -> SOL");
+        let unknown = Pubkey::new_unique();
+        assert_eq!(registry.label(&unknown), unknown.to_string()[..8]);
+    }
+
+    #[test]
+    fn an_invalid_mint_address_fails_the_load() {
+        let error = registry(r#"{ "not-a-mint": { "symbol": "X", "decimals": 6 } }"#).unwrap_err();
+        assert!(error.to_string().contains("not-a-mint"), "{}", error);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::constants::{usd1_mint, usdc_mint};
+use crate::discovery::types::DiscoveredPools;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::str::FromStr;
+
+/// Splits the token universe between `count` bot instances by a hash of each
+/// token mint, so instances trade disjoint markets. The hash only depends on
+/// the mint bytes, so the assignment is the same across restarts and builds.
+/// Stablecoin base pairs (USDC/SOL, USD1/SOL) either go to instance 0 or are
+/// traded by every instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Partition {
+    index: u64,
+    count: u64,
+    share_base_mints: bool,
+}
+
+impl Partition {
+    pub fn new(index: u64, count: u64, share_base_mints: bool) -> anyhow::Result<Self> {
+        if count == 0 || index >= count {
+            anyhow::bail!("partition instance_index {} out of range for instance_count {}", index, count);
+        }
+        Ok(Self { index, count, share_base_mints })
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_base_mint(mint: &Pubkey) -> bool {
+        *mint == usdc_mint() || *mint == usd1_mint()
+    }
+
+    /// Instance a mint is assigned to; base mints belong to instance 0
+    pub fn owner(&self, mint: &Pubkey) -> u64 {
+        if Self::is_base_mint(mint) {
+            return 0;
+        }
+        // FNV-1a: stable, unlike the std hasher which may change between releases
+        let hash = mint
+            .to_bytes()
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3));
+        hash % self.count
+    }
+
+    /// Whether this instance trades `mint`
+    pub fn owns(&self, mint: &Pubkey) -> bool {
+        (self.share_base_mints && Self::is_base_mint(mint)) || self.owner(mint) == self.index
+    }
+
+    /// Discovered tokens this instance trades. Tokens with an unparsable mint
+    /// are kept by instance 0 so they are not silently dropped everywhere.
+    pub fn filter_discovered(&self, results: &DiscoveredPools) -> DiscoveredPools {
+        let tokens: Vec<_> = results
+            .tokens
+            .iter()
+            .filter(|token| match Pubkey::from_str(&token.token_address) {
+                Ok(mint) => self.owns(&mint),
+                Err(_) => self.index == 0,
+            })
+            .cloned()
+            .collect();
+        DiscoveredPools {
+            timestamp: results.timestamp,
+            token_count: tokens.len(),
+            tokens,
+        }
+    }
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instance {}/{}", self.index, self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::types::DiscoveredToken;
+    use std::collections::HashSet;
+
+    fn partitions(count: u64, share_base_mints: bool) -> Vec<Partition> {
+        (0..count).map(|index| Partition::new(index, count, share_base_mints).unwrap()).collect()
+    }
+
+    fn token(token_address: String) -> DiscoveredToken {
+        DiscoveredToken {
+            token_address,
+            token_name: String::new(),
+            token_symbol: String::new(),
+            total_liquidity: 0.0,
+            pools: Vec::new(),
+            transfer_hook_program: None,
+        }
+    }
+
+    #[test]
+    fn an_index_outside_the_count_is_rejected() {
+        assert!(Partition::new(0, 0, false).is_err());
+        assert!(Partition::new(3, 3, false).is_err());
+        assert_eq!(Partition::new(2, 3, false).unwrap().to_string(), "instance 2/3");
+    }
+
+    #[test]
+    fn every_token_mint_is_traded_by_exactly_one_instance() {
+        let instances = partitions(5, true);
+        let mut per_instance = vec![0usize; instances.len()];
+        for _ in 0..2_000 {
+            let mint = Pubkey::new_unique();
+            let owners: Vec<usize> = (0..instances.len()).filter(|i| instances[*i].owns(&mint)).collect();
+            assert_eq!(owners.len(), 1, "{} is traded by {:?}", mint, owners);
+            per_instance[owners[0]] += 1;
+        }
+        // Roughly even: no instance is left idle or given the lot
+        assert!(per_instance.iter().all(|count| (200..=600).contains(count)), "{:?}", per_instance);
+    }
+
+    #[test]
+    fn the_assignment_is_fixed_by_the_mint_bytes() {
+        // Literal expectations: a change to the hash would reshuffle live instances
+        let mints = [Pubkey::new_from_array([0; 32]), Pubkey::new_from_array([1; 32]), Pubkey::new_from_array([255; 32])];
+        let owners: Vec<u64> = mints.iter().map(|mint| Partition::new(0, 7, false).unwrap().owner(mint)).collect();
+        assert_eq!(owners, vec![4, 1, 1]);
+        // A restart builds a new partition with the same answer
+        for mint in mints {
+            assert_eq!(Partition::new(3, 7, false).unwrap().owner(&mint), Partition::new(3, 7, false).unwrap().owner(&mint));
+        }
+    }
+
+    #[test]
+    fn base_mints_go_to_instance_zero_unless_shared() {
+        for mint in [usdc_mint(), usd1_mint()] {
+            let owning: Vec<bool> = partitions(3, false).iter().map(|partition| partition.owns(&mint)).collect();
+            assert_eq!(owning, vec![true, false, false]);
+            assert!(partitions(3, true).iter().all(|partition| partition.owns(&mint)));
+        }
+    }
+
+    #[test]
+    fn filtered_discoveries_cover_the_universe_without_overlap() {
+        let mut tokens: Vec<DiscoveredToken> = (0..300).map(|_| token(Pubkey::new_unique().to_string())).collect();
+        tokens.push(token("not a mint".to_string()));
+        let universe = DiscoveredPools { timestamp: 42, token_count: tokens.len(), tokens };
+
+        let mut seen = HashSet::new();
+        for (index, partition) in partitions(4, false).iter().enumerate() {
+            let share = partition.filter_discovered(&universe);
+            assert_eq!((share.timestamp, share.token_count), (42, share.tokens.len()));
+            for token in share.tokens {
+                assert!(seen.insert(token.token_address.clone()), "{} assigned twice", token.token_address);
+                if token.token_address == "not a mint" {
+                    assert_eq!(index, 0);
+                }
+            }
+        }
+        assert_eq!(seen.len(), universe.tokens.len());
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression of a persisted file, chosen by its extension: `.gz` is gzip,
+/// `.zst` is zstd, anything else is written as is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Extension appended to a file name to select this compression
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    pub fn encode(&self, contents: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(contents.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(contents).context("Failed to gzip contents")?;
+                encoder.finish().context("Failed to gzip contents")
+            }
+            Compression::Zstd => zstd::encode_all(contents, ZSTD_LEVEL).context("Failed to zstd-compress contents"),
+        }
+    }
+
+    pub fn decode(&self, contents: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(contents.to_vec()),
+            Compression::Gzip => {
+                let mut decoded = Vec::new();
+                flate2::read::GzDecoder::new(contents)
+                    .read_to_end(&mut decoded)
+                    .context("Failed to gunzip contents")?;
+                Ok(decoded)
+            }
+            Compression::Zstd => zstd::decode_all(contents).context("Failed to zstd-decompress contents"),
+        }
+    }
+}
+
+/// Read `path` as text, decompressing it according to its extension
+pub async fn read_to_string(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let raw = tokio::fs::read(path)
+        .await
+        .context(format!("Failed to read {}", path.display()))?;
+    let decoded = Compression::from_path(path)
+        .decode(&raw)
+        .context(format!("Failed to decompress {}", path.display()))?;
+    String::from_utf8(decoded).context(format!("{} is not valid UTF-8", path.display()))
+}
+
+/// Write `contents` to `path` via `path.tmp` and a rename, so a crash or kill
+/// mid-write leaves either the old file or the new one, never a truncated
+/// one. A leftover temp file from an interrupted write is simply overwritten
+/// on the next attempt. Contents are compressed according to the extension
+/// of `path`.
+pub async fn atomic_write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let contents = Compression::from_path(path).encode(contents.as_ref())?;
+
+    if let Err(e) = write_synced(&tmp, &contents).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&tmp, path)
+        .await
+        .context(format!("Failed to rename {} to {}", tmp.display(), path.display()))?;
+
+    // Persist the rename itself; not every platform supports syncing a directory
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Ok(dir) = tokio::fs::File::open(dir).await {
+            let _ = dir.sync_all().await;
+        }
+    }
+    Ok(())
+}
+
+/// Serialize `value` as pretty JSON and write it with `atomic_write`
+pub async fn atomic_write_json<T: Serialize + ?Sized>(path: impl AsRef<Path>, value: &T) -> Result<()> {
+    let path = path.as_ref();
+    let json = serde_json::to_string_pretty(value).context(format!("Failed to serialize {}", path.display()))?;
+    atomic_write(path, json).await
+}
+
+async fn write_synced(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .context(format!("Failed to create {}", path.display()))?;
+    file.write_all(contents)
+        .await
+        .context(format!("Failed to write to {}", path.display()))?;
+    file.sync_all()
+        .await
+        .context(format!("Failed to sync {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}_{}", Pubkey::new_unique(), name))
+    }
+
+    #[tokio::test]
+    async fn a_write_interrupted_before_the_rename_leaves_the_original_intact() {
+        let path = temp_path("results.json");
+        atomic_write_json(&path, &vec![1, 2, 3]).await.unwrap();
+
+        // A killed write leaves a partial temp file behind; the target is untouched
+        let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+        std::fs::write(&tmp, "[4, 5").unwrap();
+        let original: Vec<u32> = serde_json::from_str(&read_to_string(&path).await.unwrap()).unwrap();
+        assert_eq!(original, vec![1, 2, 3]);
+
+        // The next write replaces the leftover and lands whole
+        atomic_write_json(&path, &vec![4, 5, 6]).await.unwrap();
+        let rewritten: Vec<u32> = serde_json::from_str(&read_to_string(&path).await.unwrap()).unwrap();
+        assert_eq!(rewritten, vec![4, 5, 6]);
+        assert!(!tmp.exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_failed_temp_write_never_reaches_the_target() {
+        let path = temp_path("snapshot.json");
+        atomic_write_json(&path, &"kept").await.unwrap();
+
+        // A directory in the temp file's place makes the write fail before any rename
+        let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+        std::fs::create_dir(&tmp).unwrap();
+        assert!(atomic_write_json(&path, &"lost").await.is_err());
+        assert_eq!(read_to_string(&path).await.unwrap(), "\"kept\"");
+
+        std::fs::remove_dir(&tmp).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn compressed_files_round_trip_by_extension() {
+        for name in ["state.json.gz", "state.json.zst"] {
+            let path = temp_path(name);
+            atomic_write_json(&path, &vec!["a", "b"]).await.unwrap();
+            let raw = std::fs::read(&path).unwrap();
+            assert!(!raw.starts_with(b"["), "{} was not compressed", name);
+            assert_eq!(read_to_string(&path).await.unwrap(), "[\n  \"a\",\n  \"b\"\n]");
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
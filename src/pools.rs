@@ -5,11 +5,15 @@ use crate::{
         pancakeswap::pancakeswap_program_id,
         raydium::{clmm_info::POOL_TICK_ARRAY_BITMAP_SEED, raydium_clmm_program_id},
     },
+    engine::prefetch::AccountDependencies,
+    token_extensions::TransferHook,
 };
 
 const POOL_TICK_ARRAY_BITMAP_SEED_CLMM: &str = "pool_tick_array_bitmap_extension";
 use solana_program::instruction::AccountMeta;
 use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
+use tracing::warn;
 
 #[derive(Debug, Clone)]
 pub struct RaydiumPool {
@@ -148,6 +152,33 @@ pub struct HumidifiPool {
     pub base_mint: Pubkey,
 }
 
+#[derive(Debug, Clone)]
+pub struct LifinityPool {
+    pub pool: Pubkey,
+    pub token_x_vault: Pubkey,
+    pub token_sol_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub base_mint: Pubkey,
+    pub fee_bps: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PhoenixMarket {
+    pub market: Pubkey,
+    pub base_vault: Pubkey,  // Phoenix base side, not necessarily `base_mint`
+    pub quote_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub base_mint: Pubkey,
+}
+
+#[derive(Debug, Clone)]
+pub struct PumpBondingCurvePool {
+    pub curve: Pubkey,       // Holds the virtual reserves and the SOL side
+    pub token_vault: Pubkey, // Associated token account of the curve
+    pub token_mint: Pubkey,
+    pub base_mint: Pubkey,   // Always SOL
+}
+
 #[derive(Debug, Clone)]
 pub struct PancakeswapPool {
     pub pool: Pubkey,
@@ -180,6 +211,7 @@ pub struct ByrealPool {
 pub struct MintPoolData {
     pub mint: Pubkey,
     pub token_program: Pubkey, // Support for both Token and Token 2022
+    pub transfer_hook: Option<TransferHook>, // Accounts every transfer of `mint` must carry
     pub wallet_account: Pubkey,
     pub wallet_wsol_account: Pubkey,
     pub raydium_pools: Vec<RaydiumPool>,
@@ -196,6 +228,9 @@ pub struct MintPoolData {
     pub humidifi_pools: Vec<HumidifiPool>,
     pub pancakeswap_pools: Vec<PancakeswapPool>,
     pub byreal_pools: Vec<ByrealPool>,
+    pub lifinity_pools: Vec<LifinityPool>,
+    pub phoenix_markets: Vec<PhoenixMarket>,
+    pub pump_bonding_curves: Vec<PumpBondingCurvePool>,
 }
 
 impl MintPoolData {
@@ -206,6 +241,7 @@ impl MintPoolData {
         Self {
             mint,
             token_program,
+            transfer_hook: None,
             wallet_account: *wallet_account,
             wallet_wsol_account: wallet_wsol_pk,
             raydium_pools: Vec::new(),
@@ -222,6 +258,9 @@ impl MintPoolData {
             humidifi_pools: Vec::new(),
             pancakeswap_pools: Vec::new(),
             byreal_pools: Vec::new(),
+            lifinity_pools: Vec::new(),
+            phoenix_markets: Vec::new(),
+            pump_bonding_curves: Vec::new(),
         }
     }
 
@@ -336,6 +375,19 @@ impl MintPoolData {
         });
     }
 
+    /// Replace the tick arrays a Whirlpool swap passes when the final amount
+    /// walks past the standard three (see `whirlpool::quote`). Returns false
+    /// if `pool` is not one of this mint's Whirlpool pools.
+    pub fn set_whirlpool_swap_tick_arrays(&mut self, pool: &Pubkey, tick_arrays: Vec<Pubkey>) -> bool {
+        match self.whirlpool_pools.iter_mut().find(|p| p.pool == *pool) {
+            Some(whirlpool) => {
+                whirlpool.tick_arrays = tick_arrays;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn add_raydium_clmm_pool(
         &mut self,
         pool: Pubkey,
@@ -495,6 +547,51 @@ impl MintPoolData {
         });
     }
 
+    pub fn add_lifinity_pool(
+        &mut self,
+        pool: Pubkey,
+        token_x_vault: Pubkey,
+        token_sol_vault: Pubkey,
+        token_mint: Pubkey,
+        base_mint: Pubkey,
+        fee_bps: u64,
+    ) {
+        self.lifinity_pools.push(LifinityPool {
+            pool,
+            token_x_vault,
+            token_sol_vault,
+            token_mint,
+            base_mint,
+            fee_bps,
+        });
+    }
+
+    pub fn add_phoenix_market(
+        &mut self,
+        market: Pubkey,
+        base_vault: Pubkey,
+        quote_vault: Pubkey,
+        token_mint: Pubkey,
+        base_mint: Pubkey,
+    ) {
+        self.phoenix_markets.push(PhoenixMarket {
+            market,
+            base_vault,
+            quote_vault,
+            token_mint,
+            base_mint,
+        });
+    }
+
+    pub fn add_pump_bonding_curve(&mut self, curve: Pubkey, token_vault: Pubkey) {
+        self.pump_bonding_curves.push(PumpBondingCurvePool {
+            curve,
+            token_vault,
+            token_mint: self.mint,
+            base_mint: sol_mint(),
+        });
+    }
+
     pub fn add_pancakeswap_pool(
         &mut self,
         pool: Pubkey,
@@ -558,4 +655,139 @@ impl MintPoolData {
             base_mint,
         });
     }
+
+    /// Copy holding only the pools in `pools`, e.g. the ones a cycle routes through
+    pub fn routed(&self, pools: &HashSet<Pubkey>) -> MintPoolData {
+        let mut data = self.clone();
+        data.raydium_pools.retain(|p| pools.contains(&p.pool));
+        data.raydium_cp_pools.retain(|p| pools.contains(&p.pool));
+        data.pump_pools.retain(|p| pools.contains(&p.pool));
+        data.dlmm_pairs.retain(|p| pools.contains(&p.pair));
+        data.whirlpool_pools.retain(|p| pools.contains(&p.pool));
+        data.raydium_clmm_pools.retain(|p| pools.contains(&p.pool));
+        data.meteora_damm_pools.retain(|p| pools.contains(&p.pool));
+        data.meteora_damm_v2_pools.retain(|p| pools.contains(&p.pool));
+        data.vertigo_pools.retain(|p| pools.contains(&p.pool));
+        data.heaven_pools.retain(|p| pools.contains(&p.pool));
+        data.futarchy_pools.retain(|p| pools.contains(&p.dao));
+        data.humidifi_pools.retain(|p| pools.contains(&p.pool));
+        data.pancakeswap_pools.retain(|p| pools.contains(&p.pool));
+        data.byreal_pools.retain(|p| pools.contains(&p.pool));
+        data.lifinity_pools.retain(|p| pools.contains(&p.pool));
+        data.phoenix_markets.retain(|p| pools.contains(&p.market));
+        data.pump_bonding_curves.retain(|p| pools.contains(&p.curve));
+        data
+    }
+}
+
+/// Builds a `MintPoolData` through the `add_*` methods and checks it before
+/// it is used: the mint and token program must be set, and no pool may have a
+/// zero pool, vault or market account, since such a pool would quietly price
+/// nothing. Pools listed twice (same accounts) are kept once.
+pub struct MintPoolDataBuilder {
+    data: MintPoolData,
+}
+
+impl MintPoolDataBuilder {
+    pub fn new(mint: Pubkey, wallet_account: &Pubkey, token_program: Pubkey) -> Self {
+        Self { data: MintPoolData::new(mint, wallet_account, token_program) }
+    }
+
+    pub fn build(mut self) -> anyhow::Result<MintPoolData> {
+        let data = &mut self.data;
+        if data.mint == Pubkey::default() {
+            anyhow::bail!("Pool data has a zero mint");
+        }
+        if data.token_program == Pubkey::default() {
+            anyhow::bail!("Pool data for mint {} has a zero token program", data.mint);
+        }
+        let mint = data.mint;
+        check_pools(&mint, "Raydium", &mut data.raydium_pools)?;
+        check_pools(&mint, "Raydium CP", &mut data.raydium_cp_pools)?;
+        check_pools(&mint, "Pump", &mut data.pump_pools)?;
+        check_pools(&mint, "DLMM", &mut data.dlmm_pairs)?;
+        check_pools(&mint, "Whirlpool", &mut data.whirlpool_pools)?;
+        check_pools(&mint, "Raydium CLMM", &mut data.raydium_clmm_pools)?;
+        check_pools(&mint, "Meteora DAMM", &mut data.meteora_damm_pools)?;
+        check_pools(&mint, "Meteora DAMM V2", &mut data.meteora_damm_v2_pools)?;
+        check_pools(&mint, "Vertigo", &mut data.vertigo_pools)?;
+        check_pools(&mint, "Heaven", &mut data.heaven_pools)?;
+        check_pools(&mint, "Futarchy", &mut data.futarchy_pools)?;
+        check_pools(&mint, "Humidifi", &mut data.humidifi_pools)?;
+        check_pools(&mint, "PancakeSwap", &mut data.pancakeswap_pools)?;
+        check_pools(&mint, "Byreal", &mut data.byreal_pools)?;
+        check_pools(&mint, "Lifinity", &mut data.lifinity_pools)?;
+        check_pools(&mint, "Phoenix", &mut data.phoenix_markets)?;
+        check_pools(&mint, "Pump bonding curve", &mut data.pump_bonding_curves)?;
+        Ok(self.data)
+    }
+}
+
+impl std::ops::Deref for MintPoolDataBuilder {
+    type Target = MintPoolData;
+
+    fn deref(&self) -> &MintPoolData {
+        &self.data
+    }
+}
+
+impl std::ops::DerefMut for MintPoolDataBuilder {
+    fn deref_mut(&mut self) -> &mut MintPoolData {
+        &mut self.data
+    }
+}
+
+/// Reject pools whose priced accounts include a zero pubkey and drop repeats.
+/// The accounts the graph reads identify a pool, see `AccountDependencies::primary`.
+fn check_pools<P: AccountDependencies>(mint: &Pubkey, kind: &str, pools: &mut Vec<P>) -> anyhow::Result<()> {
+    let mut seen = HashSet::new();
+    for pool in pools.iter() {
+        let accounts = pool.primary();
+        if accounts.iter().any(|account| *account == Pubkey::default()) {
+            anyhow::bail!("{} pool for mint {} has a zero account among {:?}", kind, mint, accounts);
+        }
+    }
+    let before = pools.len();
+    pools.retain(|pool| seen.insert(pool.primary()));
+    if pools.len() < before {
+        warn!("⚠️ Dropped {} duplicate {} pools for mint {}", before - pools.len(), kind, mint);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+
+    fn builder(mint: Pubkey) -> MintPoolDataBuilder {
+        MintPoolDataBuilder::new(mint, &Pubkey::new_unique(), spl_token::id())
+    }
+
+    #[test]
+    fn a_pool_with_a_zero_vault_is_rejected() {
+        let mint = Pubkey::new_unique();
+        let mut pools = builder(mint);
+        pools.add_raydium_pool(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), mint, sol_mint());
+        pools.add_raydium_pool(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::default(), mint, sol_mint());
+
+        let error = pools.build().unwrap_err().to_string();
+        assert!(error.contains("Raydium pool") && error.contains("zero account"), "{}", error);
+    }
+
+    #[test]
+    fn a_pool_listed_twice_is_kept_once() {
+        let mint = Pubkey::new_unique();
+        let (pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pools = builder(mint);
+        pools.add_raydium_pool(pool, token_vault, sol_vault, mint, sol_mint());
+        pools.add_raydium_pool(pool, token_vault, sol_vault, mint, sol_mint());
+
+        assert_eq!(pools.build().unwrap().raydium_pools.len(), 1);
+    }
+
+    #[test]
+    fn a_zero_mint_is_rejected() {
+        assert!(builder(Pubkey::default()).build().is_err());
+    }
 }
@@ -0,0 +1,166 @@
+//! Allocation and RSS profiling of the refresh/detect hot path.
+//!
+//! Only compiled with the `profiling` feature, which also installs
+//! `CountingAllocator` as the global allocator. Counters are process-wide, so
+//! a stage's figures include allocations made by other threads meanwhile.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+static PEAK_LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// System allocator that counts allocations and tracks live bytes
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn record_alloc(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+        let live = LIVE_BYTES.fetch_add(size as u64, Ordering::Relaxed) + size as u64;
+        PEAK_LIVE_BYTES.fetch_max(live, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+    }
+
+    // A reallocation counts as one allocation of the new size
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+            Self::record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Process-wide allocator counters at one point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocCounters {
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    pub live_bytes: u64,
+    pub peak_live_bytes: u64,
+}
+
+pub fn alloc_counters() -> AllocCounters {
+    AllocCounters {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        peak_live_bytes: PEAK_LIVE_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Resident set size in KiB from /proc/self/status, None off Linux.
+/// `peak` reads the high-water mark (VmHWM) instead of the current value.
+pub fn rss_kb(peak: bool) -> Option<u64> {
+    let field = if peak { "VmHWM:" } else { "VmRSS:" };
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// What one stage of an iteration cost
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageProfile {
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    pub peak_growth_bytes: u64, // Live bytes at the stage's peak above where it started
+    pub elapsed: Duration,
+    pub rss_kb: Option<u64>,     // Sampled when the stage ended
+}
+
+/// Start of a stage; `finish` turns it into a `StageProfile`
+pub struct StageMark {
+    counters: AllocCounters,
+    started: Instant,
+}
+
+impl StageMark {
+    pub fn start() -> Self {
+        // Restart the peak so it reflects this stage only
+        let counters = alloc_counters();
+        PEAK_LIVE_BYTES.store(counters.live_bytes, Ordering::Relaxed);
+        Self { counters, started: Instant::now() }
+    }
+
+    pub fn finish(self) -> StageProfile {
+        let elapsed = self.started.elapsed();
+        let now = alloc_counters();
+        StageProfile {
+            allocations: now.allocations - self.counters.allocations,
+            allocated_bytes: now.allocated_bytes - self.counters.allocated_bytes,
+            peak_growth_bytes: now.peak_live_bytes.saturating_sub(self.counters.live_bytes),
+            elapsed,
+            rss_kb: rss_kb(false),
+        }
+    }
+}
+
+/// Stage profiles of one iteration, in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct IterationProfile {
+    pub stages: Vec<(&'static str, StageProfile)>,
+}
+
+impl IterationProfile {
+    pub fn record(&mut self, stage: &'static str, profile: StageProfile) {
+        self.stages.push((stage, profile));
+    }
+
+    pub fn total_allocations(&self) -> u64 {
+        self.stages.iter().map(|(_, profile)| profile.allocations).sum()
+    }
+
+    pub fn log(&self) {
+        let mut line = String::new();
+        for (stage, profile) in &self.stages {
+            let _ = write!(
+                line,
+                " {} {} allocs / {} KiB / {:.1} ms;",
+                stage,
+                profile.allocations,
+                profile.allocated_bytes / 1024,
+                profile.elapsed.as_secs_f64() * 1e3
+            );
+        }
+        info!(
+            "🧮 Iteration profile:{} RSS {} KiB (peak {} KiB)",
+            line,
+            rss_kb(false).unwrap_or(0),
+            rss_kb(true).unwrap_or(0)
+        );
+    }
+}
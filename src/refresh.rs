@@ -4,11 +4,17 @@ use crate::dex::byreal::{byreal_program_id, byreal_authority};
 use crate::dex::futarchy::{futarchy_program_id, FutarchyInfo};
 use crate::dex::heaven::{heaven_program_id, HeavenPoolState};
 use crate::dex::humidifi::{humidifi_program_id, HumidifiInfo};
+use crate::dex::lifinity::{lifinity_program_id, LifinityPoolInfo};
 use crate::dex::meteora::constants::{damm_program_id, damm_v2_program_id};
+use crate::dex::meteora::damm_info::{MeteoraDammInfo, MeteoraVaultInfo};
 use crate::dex::meteora::dammv2_info::MeteoraDAmmV2Info;
 use crate::dex::meteora::{constants::dlmm_program_id, dlmm_info::DlmmInfo};
 use crate::dex::pancakeswap::{pancakeswap_program_id, pancakeswap_authority};
-use crate::dex::pump::{pump_fee_wallet, pump_mayhem_fee_wallet, pump_program_id, PumpAmmInfo};
+use crate::dex::phoenix::{phoenix_program_id, PhoenixMarketInfo};
+use crate::dex::pump::{
+    bonding_curve_address, pump_bonding_curve_program_id, pump_fee_wallet, pump_mayhem_fee_wallet, pump_program_id,
+    BondingCurveInfo, PumpAmmInfo,
+};
 use crate::dex::raydium::{
     get_tick_array_pubkeys, raydium_clmm_program_id, raydium_cp_program_id, raydium_program_id,
     PoolState, RaydiumAmmInfo, RaydiumCpAmmInfo,
@@ -18,13 +24,23 @@ use crate::dex::whirlpool::{
     constants::whirlpool_program_id, state::Whirlpool, update_tick_array_accounts_for_onchain,
 };
 use crate::pools::*;
+use crate::rpc::RpcLike;
+use crate::strict::account_dump;
+use crate::token_extensions::{
+    extra_account_metas_address, resolve_transfer_hook, token_2022_program_id, transfer_hook_program,
+};
+use futures::stream::{FuturesUnordered, StreamExt};
 use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
 use spl_associated_token_account;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{error, info, warn};
 
+/// Maximum accounts per getMultipleAccounts request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
 /// Enum representing the different DEX pool types
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MarketPoolKind {
@@ -42,25 +58,43 @@ pub enum MarketPoolKind {
     Humidifi,
     PancakeSwap,
     Byreal,
+    Lifinity,
+    Phoenix,
+}
+
+/// One mint's pool addresses by venue, grouped during detection
+#[derive(Debug, Default, Clone)]
+pub struct MintPoolAddresses {
+    pub pump_pools: Vec<Pubkey>,
+    pub raydium_pools: Vec<Pubkey>,
+    pub raydium_cp_pools: Vec<Pubkey>,
+    pub raydium_clmm_pools: Vec<Pubkey>,
+    pub dlmm_pools: Vec<Pubkey>,
+    pub damm_pools: Vec<Pubkey>,
+    pub damm_v2_pools: Vec<Pubkey>,
+    pub whirlpool_pools: Vec<Pubkey>,
+    pub vertigo_pools: Vec<Pubkey>,
+    pub heaven_pools: Vec<Pubkey>,
+    pub futarchy_pools: Vec<Pubkey>,
+    pub humidifi_pools: Vec<Pubkey>,
+    pub pancakeswap_pools: Vec<Pubkey>,
+    pub byreal_pools: Vec<Pubkey>,
+    pub lifinity_pools: Vec<Pubkey>,
+    pub phoenix_markets: Vec<Pubkey>,
+    pub pump_bonding_curves: Vec<Pubkey>,
+}
+
+/// What `initialize_pool_data` resolves into one mint's `MintPoolData`
+#[derive(Debug, Clone)]
+pub struct PoolInitContext {
+    pub mint: Pubkey,
+    pub wallet_account: Pubkey,
+    pub pools: MintPoolAddresses,
+    pub allow_transfer_hook_mints: bool,
 }
 
-/// Internal structure for grouping pools by mint during detection
-#[derive(Default)]
-struct MintPoolsBuilder {
-    pump_pools: Vec<Pubkey>,
-    raydium_pools: Vec<Pubkey>,
-    raydium_cp_pools: Vec<Pubkey>,
-    raydium_clmm_pools: Vec<Pubkey>,
-    dlmm_pools: Vec<Pubkey>,
-    damm_pools: Vec<Pubkey>,
-    damm_v2_pools: Vec<Pubkey>,
-    whirlpool_pools: Vec<Pubkey>,
-    vertigo_pools: Vec<Pubkey>,
-    heaven_pools: Vec<Pubkey>,
-    futarchy_pools: Vec<Pubkey>,
-    humidifi_pools: Vec<Pubkey>,
-    pancakeswap_pools: Vec<Pubkey>,
-    byreal_pools: Vec<Pubkey>,
+fn non_empty(pools: &[Pubkey]) -> Option<&[Pubkey]> {
+    (!pools.is_empty()).then_some(pools)
 }
 
 /// Detect the pool kind based on the account owner (program ID)
@@ -93,6 +127,10 @@ pub fn detect_pool_kind(owner: &Pubkey) -> Option<MarketPoolKind> {
         Some(MarketPoolKind::PancakeSwap)
     } else if *owner == byreal_program_id() {
         Some(MarketPoolKind::Byreal)
+    } else if *owner == lifinity_program_id() {
+        Some(MarketPoolKind::Lifinity)
+    } else if *owner == phoenix_program_id() {
+        Some(MarketPoolKind::Phoenix)
     } else {
         None
     }
@@ -163,7 +201,7 @@ fn extract_token_mint(
             Ok(Some(token_mint))
         }
         MarketPoolKind::MeteoraDamm => {
-            let pool = meteora_damm_cpi::Pool::deserialize_unchecked(data)?;
+            let pool = MeteoraDammInfo::load_checked(data)?;
             let token_mint = if pool.token_a_mint == sol {
                 pool.token_b_mint
             } else if pool.token_b_mint == sol {
@@ -242,6 +280,28 @@ fn extract_token_mint(
             };
             Ok(Some(token_mint))
         }
+        MarketPoolKind::Lifinity => {
+            let info = LifinityPoolInfo::load_checked(data)?;
+            let token_mint = if info.token_a_mint == sol {
+                info.token_b_mint
+            } else if info.token_b_mint == sol {
+                info.token_a_mint
+            } else {
+                return Ok(None);
+            };
+            Ok(Some(token_mint))
+        }
+        MarketPoolKind::Phoenix => {
+            let info = PhoenixMarketInfo::load_checked(data)?;
+            let token_mint = if info.base_mint == sol {
+                info.quote_mint
+            } else if info.quote_mint == sol {
+                info.base_mint
+            } else {
+                return Ok(None);
+            };
+            Ok(Some(token_mint))
+        }
         MarketPoolKind::PancakeSwap | MarketPoolKind::Byreal => {
             // PancakeSwap and Byreal share the same CLMM layout as Raydium
             let info = PoolState::load_checked(data)?;
@@ -257,6 +317,42 @@ fn extract_token_mint(
     }
 }
 
+/// Read `keys` with getMultipleAccounts in batches of `batch_size` (capped at
+/// the RPC limit of 100) on the blocking pool, at most `concurrency` requests
+/// in flight. Accounts come back in the order of `keys`.
+pub async fn fetch_market_accounts(
+    rpc_client: Arc<dyn RpcLike>,
+    keys: &[Pubkey],
+    batch_size: usize,
+    concurrency: usize,
+) -> anyhow::Result<Vec<(Pubkey, Option<Account>)>> {
+    let batch_size = batch_size.clamp(1, MAX_MULTIPLE_ACCOUNTS);
+    let mut chunks = keys.chunks(batch_size).map(<[Pubkey]>::to_vec).enumerate();
+    let fetch = |(idx, chunk): (usize, Vec<Pubkey>)| {
+        let rpc_client = rpc_client.clone();
+        tokio::task::spawn_blocking(move || {
+            let accounts = rpc_client.get_multiple_accounts(&chunk);
+            (idx, chunk, accounts)
+        })
+    };
+    let mut futures: FuturesUnordered<_> = chunks.by_ref().take(concurrency.max(1)).map(fetch).collect();
+
+    let mut batches = Vec::new();
+    while let Some(result) = futures.next().await {
+        let (idx, chunk, accounts) = result?;
+        batches.push((idx, chunk, accounts?));
+        if let Some(next) = chunks.next() {
+            futures.push(fetch(next));
+        }
+    }
+    // Keep the order independent of which batch finished first
+    batches.sort_by_key(|(idx, _, _)| *idx);
+    Ok(batches
+        .into_iter()
+        .flat_map(|(_, chunk, accounts)| chunk.into_iter().zip(accounts))
+        .collect())
+}
+
 /// Initialize pools from a simplified markets config
 /// This function:
 /// 1. Fetches all market accounts
@@ -264,10 +360,14 @@ fn extract_token_mint(
 /// 3. Extracts the token mint
 /// 4. Groups pools by mint
 /// 5. Initializes MintPoolData for each mint
+///
+/// With `strict_mode`, a market that cannot be resolved is an error carrying
+/// the raw account instead of a skipped pool.
 pub async fn initialize_pools_from_markets(
     markets_config: &MarketsConfig,
     wallet_account: &Pubkey,
     rpc_client: Arc<RpcClient>,
+    strict_mode: bool,
 ) -> anyhow::Result<HashMap<Pubkey, MintPoolData>> {
     info!("Initializing pools from {} markets", markets_config.markets.len());
 
@@ -286,140 +386,179 @@ pub async fn initialize_pools_from_markets(
         })
         .collect();
 
-    if market_pubkeys.is_empty() {
+    if market_pubkeys.is_empty() && markets_config.pump_bonding_curve_mints.is_empty() {
         return Ok(HashMap::new());
     }
 
-    // Fetch all accounts in batches
-    let mut mint_pools: HashMap<Pubkey, MintPoolsBuilder> = HashMap::new();
-
-    // Process in batches of 100 (RPC limit for getMultipleAccounts)
-    for chunk in market_pubkeys.chunks(100) {
-        let accounts = rpc_client.get_multiple_accounts(chunk)?;
-
-        for (i, maybe_account) in accounts.iter().enumerate() {
-            let pool_pubkey = chunk[i];
-
-            let account = match maybe_account {
-                Some(acc) => acc,
-                None => {
-                    warn!("Market account {} not found", pool_pubkey);
-                    continue;
+    let concurrency = markets_config.init_concurrency.max(1);
+    let market_accounts = fetch_market_accounts(
+        rpc_client.clone(),
+        &market_pubkeys,
+        markets_config.init_batch_size,
+        concurrency,
+    )
+    .await?;
+
+    let mut mint_pools: HashMap<Pubkey, MintPoolAddresses> = HashMap::new();
+
+    for (pool_pubkey, maybe_account) in &market_accounts {
+        let pool_pubkey = *pool_pubkey;
+        let account = match maybe_account {
+            Some(acc) => acc,
+            None => {
+                if strict_mode {
+                    anyhow::bail!("Strict mode: market account {} not found", pool_pubkey);
                 }
-            };
-
-            // Detect pool kind
-            let kind = match detect_pool_kind(&account.owner) {
-                Some(k) => k,
-                None => {
-                    warn!(
-                        "Unknown pool program {} for market {}",
-                        account.owner, pool_pubkey
-                    );
-                    continue;
+                warn!("Market account {} not found", pool_pubkey);
+                continue;
+            }
+        };
+
+        // Detect pool kind
+        let kind = match detect_pool_kind(&account.owner) {
+            Some(k) => k,
+            None => {
+                if strict_mode {
+                    anyhow::bail!("Strict mode: unknown pool program, {}", account_dump(&pool_pubkey, account));
                 }
-            };
-
-            info!("Detected {:?} pool: {}", kind, pool_pubkey);
-
-            // Extract token mint
-            let token_mint = match extract_token_mint(kind, &account.data, &pool_pubkey) {
-                Ok(Some(mint)) => mint,
-                Ok(None) => {
-                    warn!("Pool {} does not have SOL as one side, skipping", pool_pubkey);
-                    continue;
+                warn!(
+                    "Unknown pool program {} for market {}",
+                    account.owner, pool_pubkey
+                );
+                continue;
+            }
+        };
+
+        info!("Detected {:?} pool: {}", kind, pool_pubkey);
+
+        // Extract token mint
+        let token_mint = match extract_token_mint(kind, &account.data, &pool_pubkey) {
+            Ok(Some(mint)) => mint,
+            Ok(None) => {
+                if strict_mode {
+                    anyhow::bail!(
+                        "Strict mode: {:?} pool does not have SOL as one side, {}",
+                        kind,
+                        account_dump(&pool_pubkey, account)
+                    );
                 }
-                Err(e) => {
-                    error!("Failed to parse pool {}: {}", pool_pubkey, e);
-                    continue;
+                warn!("Pool {} does not have SOL as one side, skipping", pool_pubkey);
+                continue;
+            }
+            Err(e) => {
+                if strict_mode {
+                    anyhow::bail!(
+                        "Strict mode: failed to parse {:?} pool: {}, {}",
+                        kind,
+                        e,
+                        account_dump(&pool_pubkey, account)
+                    );
                 }
-            };
-
-            info!("  Token mint: {}", token_mint);
-
-            // Group by mint
-            let builder = mint_pools.entry(token_mint).or_default();
-
-            match kind {
-                MarketPoolKind::Pump => builder.pump_pools.push(pool_pubkey),
-                MarketPoolKind::RaydiumV4 => builder.raydium_pools.push(pool_pubkey),
-                MarketPoolKind::RaydiumCp => builder.raydium_cp_pools.push(pool_pubkey),
-                MarketPoolKind::RaydiumClmm => builder.raydium_clmm_pools.push(pool_pubkey),
-                MarketPoolKind::MeteoraDlmm => builder.dlmm_pools.push(pool_pubkey),
-                MarketPoolKind::MeteoraDamm => builder.damm_pools.push(pool_pubkey),
-                MarketPoolKind::MeteoraDammV2 => builder.damm_v2_pools.push(pool_pubkey),
-                MarketPoolKind::Whirlpool => builder.whirlpool_pools.push(pool_pubkey),
-                MarketPoolKind::Vertigo => builder.vertigo_pools.push(pool_pubkey),
-                MarketPoolKind::Heaven => builder.heaven_pools.push(pool_pubkey),
-                MarketPoolKind::Futarchy => builder.futarchy_pools.push(pool_pubkey),
-                MarketPoolKind::Humidifi => builder.humidifi_pools.push(pool_pubkey),
-                MarketPoolKind::PancakeSwap => builder.pancakeswap_pools.push(pool_pubkey),
-                MarketPoolKind::Byreal => builder.byreal_pools.push(pool_pubkey),
+                error!("Failed to parse pool {}: {}", pool_pubkey, e);
+                continue;
             }
+        };
+
+        info!("  Token mint: {}", token_mint);
+
+        // Group by mint
+        let builder = mint_pools.entry(token_mint).or_default();
+
+        match kind {
+            MarketPoolKind::Pump => builder.pump_pools.push(pool_pubkey),
+            MarketPoolKind::RaydiumV4 => builder.raydium_pools.push(pool_pubkey),
+            MarketPoolKind::RaydiumCp => builder.raydium_cp_pools.push(pool_pubkey),
+            MarketPoolKind::RaydiumClmm => builder.raydium_clmm_pools.push(pool_pubkey),
+            MarketPoolKind::MeteoraDlmm => builder.dlmm_pools.push(pool_pubkey),
+            MarketPoolKind::MeteoraDamm => builder.damm_pools.push(pool_pubkey),
+            MarketPoolKind::MeteoraDammV2 => builder.damm_v2_pools.push(pool_pubkey),
+            MarketPoolKind::Whirlpool => builder.whirlpool_pools.push(pool_pubkey),
+            MarketPoolKind::Vertigo => builder.vertigo_pools.push(pool_pubkey),
+            MarketPoolKind::Heaven => builder.heaven_pools.push(pool_pubkey),
+            MarketPoolKind::Futarchy => builder.futarchy_pools.push(pool_pubkey),
+            MarketPoolKind::Humidifi => builder.humidifi_pools.push(pool_pubkey),
+            MarketPoolKind::PancakeSwap => builder.pancakeswap_pools.push(pool_pubkey),
+            MarketPoolKind::Byreal => builder.byreal_pools.push(pool_pubkey),
+            MarketPoolKind::Lifinity => builder.lifinity_pools.push(pool_pubkey),
+            MarketPoolKind::Phoenix => builder.phoenix_markets.push(pool_pubkey),
+        }
+    }
+
+    // A bonding curve account does not name its mint, so curves are configured by mint
+    for mint in &markets_config.pump_bonding_curve_mints {
+        match mint.parse::<Pubkey>() {
+            Ok(mint) => mint_pools.entry(mint).or_default().pump_bonding_curves.push(bonding_curve_address(&mint)),
+            Err(e) => error!("Invalid pump bonding curve mint {}: {}", mint, e),
         }
     }
 
     info!("Found {} unique token mints", mint_pools.len());
 
-    // Initialize MintPoolData for each mint
-    let mut result: HashMap<Pubkey, MintPoolData> = HashMap::new();
+    // Initialize MintPoolData for each mint. The blocking RpcClient runs on
+    // the blocking pool, at most `init_concurrency` mints at a time.
+    let mut futures = FuturesUnordered::new();
+    let mut pending = mint_pools.into_iter().map(|(mint, pools)| PoolInitContext {
+        mint,
+        wallet_account: *wallet_account,
+        pools,
+        allow_transfer_hook_mints: markets_config.allow_transfer_hook_mints,
+    });
+    let spawn = |context: PoolInitContext| {
+        let rpc_client = rpc_client.clone();
+        tokio::task::spawn_blocking(move || {
+            info!("Initializing pools for mint: {}", context.mint);
+            (context.mint, initialize_pool_data(&context, &rpc_client))
+        })
+    };
+    for context in pending.by_ref().take(concurrency) {
+        futures.push(spawn(context));
+    }
 
-    for (mint, builder) in mint_pools {
-        info!("Initializing pools for mint: {}", mint);
-
-        let pool_data = initialize_pool_data(
-            mint,
-            wallet_account,
-            if builder.raydium_pools.is_empty() { None } else { Some(&builder.raydium_pools) },
-            if builder.raydium_cp_pools.is_empty() { None } else { Some(&builder.raydium_cp_pools) },
-            if builder.pump_pools.is_empty() { None } else { Some(&builder.pump_pools) },
-            if builder.dlmm_pools.is_empty() { None } else { Some(&builder.dlmm_pools) },
-            if builder.whirlpool_pools.is_empty() { None } else { Some(&builder.whirlpool_pools) },
-            if builder.raydium_clmm_pools.is_empty() { None } else { Some(&builder.raydium_clmm_pools) },
-            if builder.damm_pools.is_empty() { None } else { Some(&builder.damm_pools) },
-            if builder.damm_v2_pools.is_empty() { None } else { Some(&builder.damm_v2_pools) },
-            if builder.vertigo_pools.is_empty() { None } else { Some(&builder.vertigo_pools) },
-            if builder.heaven_pools.is_empty() { None } else { Some(&builder.heaven_pools) },
-            if builder.futarchy_pools.is_empty() { None } else { Some(&builder.futarchy_pools) },
-            if builder.humidifi_pools.is_empty() { None } else { Some(&builder.humidifi_pools) },
-            if builder.pancakeswap_pools.is_empty() { None } else { Some(&builder.pancakeswap_pools) },
-            if builder.byreal_pools.is_empty() { None } else { Some(&builder.byreal_pools) },
-            rpc_client.clone(),
-        )
-        .await?;
-
-        result.insert(mint, pool_data);
+    let mut result: HashMap<Pubkey, MintPoolData> = HashMap::new();
+    while let Some(joined) = futures.next().await {
+        let (mint, pool_data) = joined?;
+        if let Some(pool_data) = pool_data? {
+            result.insert(mint, pool_data);
+        }
+        if let Some(context) = pending.next() {
+            futures.push(spawn(context));
+        }
     }
 
     Ok(result)
 }
 
-pub async fn initialize_pool_data(
-    mint: Pubkey,
-    wallet_account: &Pubkey,
-    raydium_pools: Option<&Vec<Pubkey>>,
-    raydium_cp_pools: Option<&Vec<Pubkey>>,
-    pump_pools: Option<&Vec<Pubkey>>,
-    dlmm_pools: Option<&Vec<Pubkey>>,
-    whirlpool_pools: Option<&Vec<Pubkey>>,
-    raydium_clmm_pools: Option<&Vec<Pubkey>>,
-    meteora_damm_pools: Option<&Vec<Pubkey>>,
-    meteora_damm_v2_pools: Option<&Vec<Pubkey>>,
-    vertigo_pools: Option<&Vec<Pubkey>>,
-    heaven_pools: Option<&Vec<Pubkey>>,
-    futarchy_pools: Option<&Vec<Pubkey>>,
-    humidifi_pools: Option<&Vec<Pubkey>>,
-    pancakeswap_pools: Option<&Vec<Pubkey>>,
-    byreal_pools: Option<&Vec<Pubkey>>,
-    rpc_client: Arc<RpcClient>,
-) -> anyhow::Result<MintPoolData> {
+/// Resolve the vaults and auxiliary accounts of one mint's pools. Blocks on
+/// RPC; async callers run it with `spawn_blocking`.
+pub fn initialize_pool_data(context: &PoolInitContext, rpc_client: &RpcClient) -> anyhow::Result<Option<MintPoolData>> {
+    let mint = context.mint;
+    let wallet_account = &context.wallet_account;
+    let allow_transfer_hook_mints = context.allow_transfer_hook_mints;
+    let pools = &context.pools;
+    let pump_pools = non_empty(&pools.pump_pools);
+    let raydium_pools = non_empty(&pools.raydium_pools);
+    let raydium_cp_pools = non_empty(&pools.raydium_cp_pools);
+    let dlmm_pools = non_empty(&pools.dlmm_pools);
+    let whirlpool_pools = non_empty(&pools.whirlpool_pools);
+    let raydium_clmm_pools = non_empty(&pools.raydium_clmm_pools);
+    let meteora_damm_pools = non_empty(&pools.damm_pools);
+    let meteora_damm_v2_pools = non_empty(&pools.damm_v2_pools);
+    let vertigo_pools = non_empty(&pools.vertigo_pools);
+    let heaven_pools = non_empty(&pools.heaven_pools);
+    let futarchy_pools = non_empty(&pools.futarchy_pools);
+    let humidifi_pools = non_empty(&pools.humidifi_pools);
+    let pancakeswap_pools = non_empty(&pools.pancakeswap_pools);
+    let byreal_pools = non_empty(&pools.byreal_pools);
+    let lifinity_pools = non_empty(&pools.lifinity_pools);
+    let phoenix_markets = non_empty(&pools.phoenix_markets);
+    let pump_bonding_curves = non_empty(&pools.pump_bonding_curves);
     info!("Initializing pool data for mint: {}", mint);
 
     // Fetch mint account to determine token program
     let mint_account = rpc_client.get_account(&mint)?;
 
     // Determine token program based on mint account owner
-    let token_2022_program_id: Pubkey = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb".parse().unwrap();
+    let token_2022_program_id = token_2022_program_id();
     let token_program = if mint_account.owner == spl_token::ID {
         spl_token::ID
     } else if mint_account.owner == token_2022_program_id {
@@ -429,7 +568,30 @@ pub async fn initialize_pool_data(
     };
 
     info!("Detected token program: {}", token_program);
-    let mut pool_data = MintPoolData::new(mint, wallet_account, token_program);
+    let mut pool_data = MintPoolDataBuilder::new(mint, wallet_account, token_program);
+
+    // Every transfer of a hooked mint needs the hook's extra accounts, or the swap fails
+    if let Some(hook_program) = transfer_hook_program(&mint_account.data) {
+        if !allow_transfer_hook_mints {
+            warn!("⚠️ Excluding mint {}: Token-2022 transfer hook {} (set allow_transfer_hook_mints to route it)", mint, hook_program);
+            return Ok(None);
+        }
+        let validation_account = extra_account_metas_address(&mint, &hook_program);
+        let resolved = rpc_client
+            .get_account_data(&validation_account)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| resolve_transfer_hook(&mint, &hook_program, &data));
+        match resolved {
+            Ok(hook) => {
+                info!("🪝 Mint {} transfer hook {} resolved with {} extra accounts", mint, hook_program, hook.extra_accounts.len());
+                pool_data.transfer_hook = Some(hook);
+            }
+            Err(e) => {
+                warn!("⚠️ Rejecting mint {}: cannot resolve transfer hook {} accounts: {}", mint, hook_program, e);
+                return Ok(None);
+            }
+        }
+    }
     info!("Pool data initialized for mint: {}", mint);
 
     if let Some(pools) = pump_pools {
@@ -1039,7 +1201,7 @@ pub async fn initialize_pool_data(
                         ));
                     }
 
-                    match meteora_damm_cpi::Pool::deserialize_unchecked(&account.data) {
+                    match MeteoraDammInfo::load_checked(&account.data) {
                         Ok(pool) => {
                             if pool.token_a_mint != pool_data.mint
                                 && pool.token_b_mint != pool_data.mint
@@ -1076,12 +1238,8 @@ pub async fn initialize_pool_data(
                             let x_vault_data = rpc_client.get_account(&x_vault)?;
                             let sol_vault_data = rpc_client.get_account(&sol_vault)?;
 
-                            let x_vault_obj = meteora_vault_cpi::Vault::deserialize_unchecked(
-                                &mut x_vault_data.data.as_slice(),
-                            )?;
-                            let sol_vault_obj = meteora_vault_cpi::Vault::deserialize_unchecked(
-                                &mut sol_vault_data.data.as_slice(),
-                            )?;
+                            let x_vault_obj = MeteoraVaultInfo::load_checked(&x_vault_data.data)?;
+                            let sol_vault_obj = MeteoraVaultInfo::load_checked(&sol_vault_data.data)?;
 
                             let x_token_vault = x_vault_obj.token_vault;
                             let sol_token_vault = sol_vault_obj.token_vault;
@@ -1707,5 +1865,386 @@ pub async fn initialize_pool_data(
         }
     }
 
-    Ok(pool_data)
+    if let Some(pools) = lifinity_pools {
+        for &pool_pubkey in pools {
+            match rpc_client.get_account(&pool_pubkey) {
+                Ok(account) => {
+                    if account.owner != lifinity_program_id() {
+                        error!(
+                            "Lifinity pool {} is not owned by the Lifinity v2 program, skipping",
+                            pool_pubkey
+                        );
+                        continue;
+                    }
+
+                    match LifinityPoolInfo::load_checked(&account.data) {
+                        Ok(lifinity_info) => {
+                            info!("Lifinity pool added: {}", pool_pubkey);
+                            info!("    Token A mint: {}", lifinity_info.token_a_mint);
+                            info!("    Token B mint: {}", lifinity_info.token_b_mint);
+                            info!("    Token A vault: {}", lifinity_info.token_a_vault);
+                            info!("    Token B vault: {}", lifinity_info.token_b_vault);
+                            info!("    Oracle: {}", lifinity_info.oracle_main);
+                            info!("    Trade fee: {} bps", lifinity_info.fee_bps());
+
+                            let sol = sol_mint();
+                            let (token_x_vault, token_sol_vault) = if sol == lifinity_info.token_a_mint {
+                                (lifinity_info.token_b_vault, lifinity_info.token_a_vault)
+                            } else {
+                                (lifinity_info.token_a_vault, lifinity_info.token_b_vault)
+                            };
+
+                            let (token_mint, base_mint) = if mint == lifinity_info.token_a_mint {
+                                (lifinity_info.token_a_mint, lifinity_info.token_b_mint)
+                            } else {
+                                (lifinity_info.token_b_mint, lifinity_info.token_a_mint)
+                            };
+
+                            pool_data.add_lifinity_pool(
+                                pool_pubkey,
+                                token_x_vault,
+                                token_sol_vault,
+                                token_mint,
+                                base_mint,
+                                lifinity_info.fee_bps(),
+                            );
+
+                            info!("    Initialized Lifinity pool: {}\n", pool_pubkey);
+                        }
+                        Err(e) => {
+                            error!(
+                                "Error parsing Lifinity pool data from pool {}: {:?}",
+                                pool_pubkey, e
+                            );
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Error fetching Lifinity pool account {}: {:?}",
+                        pool_pubkey, e
+                    );
+                    continue;
+                }
+            }
+        }
+    }
+
+    if let Some(markets) = phoenix_markets {
+        for &market_pubkey in markets {
+            match rpc_client.get_account(&market_pubkey) {
+                Ok(account) => {
+                    if account.owner != phoenix_program_id() {
+                        error!(
+                            "Phoenix market {} is not owned by the Phoenix program, skipping",
+                            market_pubkey
+                        );
+                        continue;
+                    }
+
+                    match PhoenixMarketInfo::load_checked(&account.data) {
+                        Ok(phoenix_info) => {
+                            info!("Phoenix market added: {}", market_pubkey);
+                            info!("    Base mint: {}", phoenix_info.base_mint);
+                            info!("    Quote mint: {}", phoenix_info.quote_mint);
+                            info!("    Base vault: {}", phoenix_info.base_vault);
+                            info!("    Quote vault: {}", phoenix_info.quote_vault);
+                            info!("    Taker fee: {} bps", phoenix_info.taker_fee_bps);
+                            info!("    Levels: {} bids, {} asks", phoenix_info.bids.len(), phoenix_info.asks.len());
+
+                            let (token_mint, base_mint) = if mint == phoenix_info.base_mint {
+                                (phoenix_info.base_mint, phoenix_info.quote_mint)
+                            } else {
+                                (phoenix_info.quote_mint, phoenix_info.base_mint)
+                            };
+
+                            pool_data.add_phoenix_market(
+                                market_pubkey,
+                                phoenix_info.base_vault,
+                                phoenix_info.quote_vault,
+                                token_mint,
+                                base_mint,
+                            );
+
+                            info!("    Initialized Phoenix market: {}\n", market_pubkey);
+                        }
+                        Err(e) => {
+                            error!(
+                                "Error parsing Phoenix market data from market {}: {:?}",
+                                market_pubkey, e
+                            );
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Error fetching Phoenix market account {}: {:?}",
+                        market_pubkey, e
+                    );
+                    continue;
+                }
+            }
+        }
+    }
+
+    if let Some(curves) = pump_bonding_curves {
+        for &curve_pubkey in curves {
+            match rpc_client.get_account(&curve_pubkey) {
+                Ok(account) => {
+                    if account.owner != pump_bonding_curve_program_id() {
+                        error!(
+                            "Pump bonding curve {} is not owned by the pump.fun program, skipping",
+                            curve_pubkey
+                        );
+                        continue;
+                    }
+
+                    match BondingCurveInfo::load_checked(&account.data) {
+                        Ok(curve_info) => {
+                            if curve_info.complete {
+                                warn!("Pump bonding curve {} of mint {} has graduated, skipping", curve_pubkey, mint);
+                                continue;
+                            }
+                            let token_vault = spl_associated_token_account::get_associated_token_address_with_program_id(
+                                &curve_pubkey,
+                                &mint,
+                                &token_program,
+                            );
+                            info!("Pump bonding curve added: {}", curve_pubkey);
+                            info!("    Token vault: {}", token_vault);
+                            info!("    Graduation progress: {} bps", curve_info.graduation_progress_bps());
+
+                            pool_data.add_pump_bonding_curve(curve_pubkey, token_vault);
+
+                            info!("    Initialized Pump bonding curve: {}\n", curve_pubkey);
+                        }
+                        Err(e) => {
+                            error!(
+                                "Error parsing Pump bonding curve data from curve {}: {:?}",
+                                curve_pubkey, e
+                            );
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "Error fetching Pump bonding curve account {}: {:?}",
+                        curve_pubkey, e
+                    );
+                    continue;
+                }
+            }
+        }
+    }
+
+    Ok(Some(pool_data.build()?))
+}
+
+/// Steady-state refresh of pools that are already initialized: re-derive the
+/// tick and bin arrays of concentrated-liquidity pools from their current
+/// state, keeping every pool and vault address as it is. Prices are re-read by
+/// the graph update that follows. Only `mints` are refreshed. Returns how
+/// many of them failed to refresh; those keep their previous arrays.
+pub fn refresh_pools_in_place(mint_pool_data: &mut HashMap<Pubkey, MintPoolData>, mints: &[Pubkey], rpc_client: &RpcClient) -> usize {
+    let refresher = crate::pool_refreshers::PoolDataRefresher::new();
+    let mut failed = 0;
+    for mint in mints {
+        let Some(pool_data) = mint_pool_data.get_mut(mint) else {
+            continue;
+        };
+        if let Err(e) = refresher.refresh_all_pools(pool_data, rpc_client, true) {
+            warn!("Failed to refresh pools of mint {} in place: {}", mint, e);
+            failed += 1;
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{serve_accounts, MockRpc};
+    use base64::Engine;
+    use solana_client::rpc_request::RpcRequest;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn batch_size_bounds_every_request() {
+        let rpc = Arc::new(MockRpc::new());
+        let keys: Vec<Pubkey> = (0..250).map(|_| Pubkey::new_unique()).collect();
+        for key in keys.iter().step_by(2) {
+            rpc.set_account(*key, Account { lamports: 1, ..Account::default() });
+        }
+
+        let accounts = fetch_market_accounts(rpc.clone(), &keys, 40, 3).await.unwrap();
+        let mut sizes = rpc.batch_sizes();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![10, 40, 40, 40, 40, 40, 40]);
+        // Whatever order the batches finished in, accounts line up with their keys
+        assert_eq!(accounts.iter().map(|(key, _)| *key).collect::<Vec<_>>(), keys);
+        assert!(accounts.iter().enumerate().all(|(i, (_, account))| account.is_some() == (i % 2 == 0)));
+    }
+
+    #[tokio::test]
+    async fn batch_size_is_capped_at_the_rpc_limit() {
+        let rpc = Arc::new(MockRpc::new());
+        let keys: Vec<Pubkey> = (0..150).map(|_| Pubkey::new_unique()).collect();
+        fetch_market_accounts(rpc.clone(), &keys, 1_000, 4).await.unwrap();
+        assert!(rpc.batch_sizes().iter().all(|size| *size <= MAX_MULTIPLE_ACCOUNTS));
+        assert_eq!(rpc.batch_sizes().len(), 2);
+    }
+
+    /// Token-2022 mint, with a TransferHook extension when `hook_program` is given
+    fn token_2022_mint(hook_program: Option<Pubkey>) -> Vec<u8> {
+        let mut data = vec![0u8; 165];
+        data[44] = 6; // Decimals
+        data.push(1); // Account type: mint
+        if let Some(hook_program) = hook_program {
+            data.extend_from_slice(&14u16.to_le_bytes());
+            data.extend_from_slice(&64u16.to_le_bytes());
+            data.extend_from_slice(Pubkey::new_unique().as_ref());
+            data.extend_from_slice(hook_program.as_ref());
+        }
+        data
+    }
+
+    /// A client whose first `getAccountInfo` returns `mint_data`; every later
+    /// account read finds nothing
+    fn serving_mint(mint_data: &[u8]) -> RpcClient {
+        let account = serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "data": [base64::engine::general_purpose::STANDARD.encode(mint_data), "base64"],
+                "executable": false,
+                "lamports": 1_461_600,
+                "owner": token_2022_program_id().to_string(),
+                "rentEpoch": 0,
+                "space": mint_data.len(),
+            },
+        });
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), HashMap::from([(RpcRequest::GetAccountInfo, account)]))
+    }
+
+    fn context(allow_transfer_hook_mints: bool) -> PoolInitContext {
+        PoolInitContext {
+            mint: Pubkey::new_unique(),
+            wallet_account: Pubkey::new_unique(),
+            pools: MintPoolAddresses::default(),
+            allow_transfer_hook_mints,
+        }
+    }
+
+    #[test]
+    fn a_hooked_mint_is_excluded_unless_allowed_and_resolvable() {
+        let hooked = token_2022_mint(Some(Pubkey::new_unique()));
+        let markets: MarketsConfig = toml::from_str("markets = []\nprocess_delay = 0").unwrap();
+        assert!(!markets.allow_transfer_hook_mints, "hooked mints are excluded by default");
+
+        assert!(initialize_pool_data(&context(false), &serving_mint(&hooked)).unwrap().is_none());
+        // Allowed, but the hook's validation account does not exist
+        assert!(initialize_pool_data(&context(true), &serving_mint(&hooked)).unwrap().is_none());
+
+        let plain = initialize_pool_data(&context(false), &serving_mint(&token_2022_mint(None))).unwrap().unwrap();
+        assert_eq!(plain.token_program, token_2022_program_id());
+        assert!(plain.transfer_hook.is_none());
+    }
+
+    fn token_account(mint: Pubkey, amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    fn mint_account(decimals: u8) -> Account {
+        let mut data = vec![0u8; 82];
+        data[44] = decimals;
+        data[45] = 1; // initialized
+        Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn an_in_place_refresh_reprices_a_pool_without_changing_its_identity() {
+        use crate::engine::PriceGraph;
+        let rpc = MockRpc::new();
+        let token = Pubkey::new_unique();
+        let (pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.set_account(token, mint_account(6));
+        rpc.set_account(sol_mint(), mint_account(9));
+        rpc.set_account(token_vault, token_account(token, 1_000_000_000_000));
+        rpc.set_account(sol_vault, token_account(sol_mint(), 100_000_000_000));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+        let mut mint_pool_data = HashMap::from([(token, pool_data)]);
+        let graph = PriceGraph::new();
+        let price = |graph: &PriceGraph| graph.neighbors(&sol_mint()).into_iter().find(|(_, edge)| edge.pool_pubkey == pool).unwrap().1.price;
+        graph.update_from_mint_pool_data(&mint_pool_data[&token], &rpc);
+        let before = price(&graph);
+
+        // Half of the token reserve is bought out between two refreshes
+        rpc.set_account(token_vault, token_account(token, 500_000_000_000));
+        assert_eq!(refresh_pools_in_place(&mut mint_pool_data, &[token], &RpcClient::new_mock("succeeds".to_string())), 0);
+        graph.update_from_mint_pool_data(&mint_pool_data[&token], &rpc);
+
+        assert!((price(&graph) / before - 0.5).abs() < 1e-9, "{} -> {}", before, price(&graph));
+        let refreshed = &mint_pool_data[&token].raydium_pools;
+        assert_eq!(refreshed.len(), 1);
+        assert_eq!((refreshed[0].pool, refreshed[0].token_vault, refreshed[0].sol_vault), (pool, token_vault, sol_vault));
+    }
+
+    /// Raydium AMM state with the given coin and pc mints and vaults
+    fn raydium_amm(coin: (Pubkey, Pubkey), pc: (Pubkey, Pubkey)) -> Account {
+        let mut data = vec![0u8; 752];
+        data[336..368].copy_from_slice(coin.1.as_ref());
+        data[368..400].copy_from_slice(pc.1.as_ref());
+        data[400..432].copy_from_slice(coin.0.as_ref());
+        data[432..464].copy_from_slice(pc.0.as_ref());
+        Account { lamports: 1, data, owner: raydium_program_id(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn a_pool_with_sol_on_the_quote_side_builds_correctly_oriented_edges() {
+        use crate::discovery::DiscoveredPool;
+        use crate::engine::PriceGraph;
+        let (token, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        // Dexscreener lists the token as base and SOL as quote: 1M tokens against 100 SOL
+        let discovered = DiscoveredPool {
+            pool_address: Pubkey::new_unique().to_string(),
+            dex_type: "RaydiumV4".to_string(),
+            program_id: raydium_program_id().to_string(),
+            liquidity_usd: 40_000.0,
+            volume_h24: 10_000.0,
+            sol_side: "quote".to_string(),
+            price_sol: 0.0001,
+        };
+        let pool = discovered.pool_address.parse().unwrap();
+        let vaults = MockRpc::new();
+        vaults.set_account(token, mint_account(6));
+        vaults.set_account(sol_mint(), mint_account(9));
+        vaults.set_account(token_vault, token_account(token, 1_000_000_000_000));
+        vaults.set_account(sol_vault, token_account(sol_mint(), 100_000_000_000));
+
+        // The account's mint order decides the SOL vault, whichever way the API labeled the pair
+        for (coin, pc) in [((token, token_vault), (sol_mint(), sol_vault)), ((sol_mint(), sol_vault), (token, token_vault))] {
+            let node = RpcClient::new(serve_accounts(HashMap::from([(token, mint_account(6)), (pool, raydium_amm(coin, pc))])));
+            let context = PoolInitContext {
+                pools: MintPoolAddresses { raydium_pools: vec![pool], ..Default::default() },
+                mint: token,
+                ..context(false)
+            };
+            let pool_data = initialize_pool_data(&context, &node).unwrap().unwrap();
+            let raydium = &pool_data.raydium_pools[0];
+            assert_eq!((raydium.token_vault, raydium.sol_vault), (token_vault, sol_vault));
+
+            let graph = PriceGraph::new();
+            graph.update_from_mint_pool_data(&pool_data, &vaults);
+            let price = |from_mint: &Pubkey| graph.neighbors(from_mint).into_iter().find(|(_, edge)| edge.pool_pubkey == pool).unwrap().1.price;
+            // Raw units: lamports per base unit of the token is the SOL price scaled by 10^(9 - 6)
+            assert!((price(&token) - discovered.price_sol * 1e3).abs() < 1e-9, "{}", price(&token));
+            assert!((price(&sol_mint()) - 1.0 / (discovered.price_sol * 1e3)).abs() < 1e-6, "{}", price(&sol_mint()));
+        }
+    }
 }
@@ -0,0 +1,327 @@
+use solana_account_decoder::parse_token::UiTokenAmount;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_request::RpcError;
+use solana_client::rpc_response::{Response, RpcResponseContext, RpcResult};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, TransactionConfirmationStatus, TransactionStatus};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The reads the price graph, the prefetcher and the live executor make, so
+/// they can be driven by `MockRpc` instead of a live node. Signatures mirror
+/// `RpcClient`.
+#[allow(clippy::result_large_err)] // ClientError is solana_client's
+pub trait RpcLike: Send + Sync {
+    fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account>;
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>>;
+
+    fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount>;
+
+    fn get_account_with_commitment(&self, pubkey: &Pubkey, commitment: CommitmentConfig) -> RpcResult<Option<Account>>;
+
+    fn get_multiple_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> RpcResult<Vec<Option<Account>>>;
+
+    fn commitment(&self) -> CommitmentConfig;
+
+    fn get_slot(&self) -> ClientResult<Slot>;
+
+    fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+    fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>>;
+
+    fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta>;
+}
+
+#[allow(clippy::result_large_err)]
+impl RpcLike for RpcClient {
+    fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        RpcClient::get_account(self, pubkey)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+        RpcClient::get_multiple_accounts(self, pubkeys)
+    }
+
+    fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount> {
+        RpcClient::get_token_account_balance(self, pubkey)
+    }
+
+    fn get_account_with_commitment(&self, pubkey: &Pubkey, commitment: CommitmentConfig) -> RpcResult<Option<Account>> {
+        RpcClient::get_account_with_commitment(self, pubkey, commitment)
+    }
+
+    fn get_multiple_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> RpcResult<Vec<Option<Account>>> {
+        RpcClient::get_multiple_accounts_with_commitment(self, pubkeys, commitment)
+    }
+
+    fn commitment(&self) -> CommitmentConfig {
+        RpcClient::commitment(self)
+    }
+
+    fn get_slot(&self) -> ClientResult<Slot> {
+        RpcClient::get_slot(self)
+    }
+
+    fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        RpcClient::get_latest_blockhash(self)
+    }
+
+    fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        RpcClient::get_signature_statuses(self, signatures)
+    }
+
+    fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        RpcClient::get_transaction_with_config(self, signature, config)
+    }
+}
+
+/// An in-memory node: serves the accounts, signature statuses and
+/// transactions it was given at a fixed slot and counts the calls made to it
+#[derive(Debug, Default)]
+pub struct MockRpc {
+    accounts: Mutex<HashMap<Pubkey, Account>>,
+    statuses: Mutex<HashMap<Signature, TransactionStatus>>,
+    transactions: Mutex<HashMap<Signature, serde_json::Value>>, // As JSON: the SDK type is not `Clone`
+    slot: AtomicU64,
+    calls: AtomicUsize,
+    batch_sizes: Mutex<Vec<usize>>,
+    unavailable: AtomicBool,
+}
+
+impl MockRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_slot(self, slot: u64) -> Self {
+        self.slot.store(slot, Ordering::Relaxed);
+        self
+    }
+
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) {
+        self.accounts.lock().unwrap().insert(pubkey, account);
+    }
+
+    pub fn remove_account(&self, pubkey: &Pubkey) {
+        self.accounts.lock().unwrap().remove(pubkey);
+    }
+
+    /// Report `signature` as confirmed in `slot`, reverted with `err` if any
+    pub fn confirm_signature(&self, signature: Signature, slot: Slot, err: Option<TransactionError>) {
+        let status = TransactionStatus {
+            slot,
+            confirmations: None,
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+        };
+        self.statuses.lock().unwrap().insert(signature, status);
+    }
+
+    /// Serve `transaction` to `getTransaction` for `signature`
+    pub fn set_transaction(&self, signature: Signature, transaction: EncodedConfirmedTransactionWithStatusMeta) {
+        self.transactions.lock().unwrap().insert(signature, serde_json::to_value(transaction).unwrap());
+    }
+
+    /// Fail every following request, as a node that stopped answering would
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.unavailable.store(unavailable, Ordering::Relaxed);
+    }
+
+    /// RPC requests served so far; a batch counts once
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Key count of every multi-account request, in call order
+    pub fn batch_sizes(&self) -> Vec<usize> {
+        self.batch_sizes.lock().unwrap().clone()
+    }
+
+    fn read_batch(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>, Unavailable> {
+        self.request()?;
+        self.batch_sizes.lock().unwrap().push(pubkeys.len());
+        Ok(pubkeys.iter().map(|pubkey| self.read(pubkey)).collect())
+    }
+
+    /// Count one request, failing it while the node is unavailable
+    fn request(&self) -> Result<(), Unavailable> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if self.unavailable.load(Ordering::Relaxed) {
+            return Err(Unavailable);
+        }
+        Ok(())
+    }
+
+    fn read(&self, pubkey: &Pubkey) -> Option<Account> {
+        self.accounts.lock().unwrap().get(pubkey).cloned()
+    }
+
+    fn response<T>(&self, value: T) -> Response<T> {
+        Response {
+            context: RpcResponseContext { slot: self.slot.load(Ordering::Relaxed), api_version: None },
+            value,
+        }
+    }
+}
+
+/// A request `MockRpc` refused; becomes a `ClientError` at the `RpcLike` boundary
+#[derive(Debug)]
+struct Unavailable;
+
+impl From<Unavailable> for ClientError {
+    fn from(_: Unavailable) -> Self {
+        ClientErrorKind::Custom("mock RPC unavailable".to_string()).into()
+    }
+}
+
+fn not_found(pubkey: &Pubkey) -> ClientError {
+    ClientErrorKind::RpcError(RpcError::ForUser(format!("AccountNotFound: pubkey={}", pubkey))).into()
+}
+
+#[allow(clippy::result_large_err)]
+impl RpcLike for MockRpc {
+    fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        self.request()?;
+        self.read(pubkey).ok_or_else(|| not_found(pubkey))
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
+        Ok(self.read_batch(pubkeys)?)
+    }
+
+    /// Decoded from the SPL layout of the stored token account and its mint
+    fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount> {
+        self.request()?;
+        let account = self.read(pubkey).ok_or_else(|| not_found(pubkey))?;
+        let invalid = || ClientError::from(ClientErrorKind::Custom(format!("{} is not a token account", pubkey)));
+        if account.data.len() < 72 {
+            return Err(invalid());
+        }
+        let mint = Pubkey::new_from_array(account.data[0..32].try_into().unwrap());
+        let amount = u64::from_le_bytes(account.data[64..72].try_into().unwrap());
+        let decimals = self.read(&mint).filter(|mint| mint.data.len() >= 45).ok_or_else(invalid)?.data[44];
+        let ui_amount = amount as f64 / 10f64.powi(decimals as i32);
+        Ok(UiTokenAmount {
+            ui_amount: Some(ui_amount),
+            decimals,
+            amount: amount.to_string(),
+            ui_amount_string: ui_amount.to_string(),
+        })
+    }
+
+    fn get_account_with_commitment(&self, pubkey: &Pubkey, _commitment: CommitmentConfig) -> RpcResult<Option<Account>> {
+        self.request()?;
+        Ok(self.response(self.read(pubkey)))
+    }
+
+    fn get_multiple_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        _commitment: CommitmentConfig,
+    ) -> RpcResult<Vec<Option<Account>>> {
+        Ok(self.response(self.read_batch(pubkeys)?))
+    }
+
+    fn commitment(&self) -> CommitmentConfig {
+        CommitmentConfig::confirmed()
+    }
+
+    fn get_slot(&self) -> ClientResult<Slot> {
+        self.request()?;
+        Ok(self.slot.load(Ordering::Relaxed))
+    }
+
+    fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        self.request()?;
+        Ok(Hash::default())
+    }
+
+    fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        self.request()?;
+        let statuses = self.statuses.lock().unwrap();
+        Ok(self.response(signatures.iter().map(|signature| statuses.get(signature).cloned()).collect()))
+    }
+
+    fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        _config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        self.request()?;
+        let transaction = self.transactions.lock().unwrap().get(signature).cloned();
+        let transaction = transaction.ok_or_else(|| ClientErrorKind::Custom(format!("transaction {} not found", signature)))?;
+        Ok(serde_json::from_value(transaction)?)
+    }
+}
+
+/// A JSON-RPC node on loopback answering `getVersion` and `getAccountInfo`
+/// from `accounts`, one request per connection; unknown accounts are not
+/// found. Returns its URL, for code that takes a live `RpcClient`.
+#[cfg(test)]
+pub(crate) fn serve_accounts(accounts: HashMap<Pubkey, Account>) -> String {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::str::FromStr;
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut line, mut content_length) = (String::new(), 0);
+            while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = length.trim().parse().unwrap();
+                }
+                line.clear();
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let account = request["params"][0].as_str().and_then(|key| accounts.get(&Pubkey::from_str(key).unwrap()));
+            let value = account.map(|account| {
+                serde_json::json!({
+                    "data": [base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &account.data), "base64"],
+                    "executable": false,
+                    "lamports": account.lamports,
+                    "owner": account.owner.to_string(),
+                    "rentEpoch": 0,
+                    "space": account.data.len(),
+                })
+            });
+            let result = match request["method"].as_str() {
+                Some("getVersion") => serde_json::json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+                _ => serde_json::json!({ "context": { "slot": 1 }, "value": value }),
+            };
+            let response = serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+            let response = response.to_string();
+            let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", response.len(), response);
+        }
+    });
+    url
+}
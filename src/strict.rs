@@ -0,0 +1,16 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+
+/// Render the raw account behind a parser or price anomaly so a strict-mode
+/// failure can be reproduced offline against the exact bytes that tripped it.
+pub fn account_dump(pubkey: &Pubkey, account: &Account) -> String {
+    format!(
+        "account {} owner {} data_len {} data_base64 {}",
+        pubkey,
+        account.owner,
+        account.data.len(),
+        STANDARD.encode(&account.data)
+    )
+}
@@ -0,0 +1,214 @@
+use anyhow::Result;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// Token-2022 mints are padded to the base token account length, followed by
+/// the account type byte and the extension TLV entries
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_UNINITIALIZED: u16 = 0;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+/// Discriminator, TLV length and slice count ahead of the metas
+const EXTRA_ACCOUNT_METAS_HEADER_LEN: usize = 16;
+const EXTRA_ACCOUNT_META_LEN: usize = 35;
+
+pub fn token_2022_program_id() -> Pubkey {
+    Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap()
+}
+
+/// Program of the mint's TransferHook extension, if it has one with a program set
+pub fn transfer_hook_program(mint_data: &[u8]) -> Option<Pubkey> {
+    if mint_data.get(ACCOUNT_TYPE_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+        return None;
+    }
+    let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+    while offset + 4 <= mint_data.len() {
+        let extension_type = u16::from_le_bytes([mint_data[offset], mint_data[offset + 1]]);
+        let length = u16::from_le_bytes([mint_data[offset + 2], mint_data[offset + 3]]) as usize;
+        let value = mint_data.get(offset + 4..offset + 4 + length)?;
+        match extension_type {
+            EXTENSION_UNINITIALIZED => return None,
+            // Value: authority, then program id; an all-zero program means unset
+            EXTENSION_TRANSFER_HOOK if length >= 64 => {
+                let program = Pubkey::new_from_array(value[32..64].try_into().ok()?);
+                return (program != Pubkey::default()).then_some(program);
+            }
+            _ => offset += 4 + length,
+        }
+    }
+    None
+}
+
+/// Transfer fee of the mint's TransferFeeConfig extension in basis points,
+/// 0 without one. The older and newer fee schedules are both in effect
+/// around an epoch switch, so the higher of the two is returned; the
+/// per-transfer `maximum_fee` cap is ignored, which overstates the fee on
+/// large transfers rather than understating it.
+pub fn transfer_fee_bps(mint_data: &[u8]) -> u16 {
+    if mint_data.get(ACCOUNT_TYPE_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+        return 0;
+    }
+    let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+    while offset + 4 <= mint_data.len() {
+        let extension_type = u16::from_le_bytes([mint_data[offset], mint_data[offset + 1]]);
+        let length = u16::from_le_bytes([mint_data[offset + 2], mint_data[offset + 3]]) as usize;
+        let Some(value) = mint_data.get(offset + 4..offset + 4 + length) else {
+            return 0;
+        };
+        match extension_type {
+            EXTENSION_UNINITIALIZED => return 0,
+            // Value: two authorities and the withheld amount (72), then the
+            // older and newer fees, each epoch (8) + maximum fee (8) + bps (2)
+            EXTENSION_TRANSFER_FEE_CONFIG if length >= 108 => {
+                let older = u16::from_le_bytes([value[88], value[89]]);
+                let newer = u16::from_le_bytes([value[106], value[107]]);
+                return older.max(newer);
+            }
+            _ => offset += 4 + length,
+        }
+    }
+    0
+}
+
+/// Validation account holding the hook's extra account metas for `mint`
+pub fn extra_account_metas_address(mint: &Pubkey, hook_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EXTRA_ACCOUNT_METAS_SEED, mint.as_ref()], hook_program).0
+}
+
+/// Accounts a transfer of a hooked mint must carry for the hook's execute CPI
+#[derive(Debug, Clone)]
+pub struct TransferHook {
+    pub program_id: Pubkey,
+    pub validation_account: Pubkey,
+    pub extra_accounts: Vec<AccountMeta>,
+}
+
+impl TransferHook {
+    /// Extra accounts, then the hook program and its validation account
+    pub fn account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = self.extra_accounts.clone();
+        metas.push(AccountMeta::new_readonly(self.program_id, false));
+        metas.push(AccountMeta::new_readonly(self.validation_account, false));
+        metas
+    }
+}
+
+/// Resolve the extra accounts listed in a hook's validation account. Only
+/// fixed addresses can be resolved ahead of the swap; seed-derived entries
+/// depend on the transfer itself and fail resolution.
+pub fn resolve_transfer_hook(mint: &Pubkey, hook_program: &Pubkey, validation_data: &[u8]) -> Result<TransferHook> {
+    if validation_data.len() < EXTRA_ACCOUNT_METAS_HEADER_LEN {
+        anyhow::bail!("extra account metas for {} too short: {} bytes", mint, validation_data.len());
+    }
+    let count = u32::from_le_bytes(validation_data[12..16].try_into().unwrap()) as usize;
+    let end = EXTRA_ACCOUNT_METAS_HEADER_LEN + count * EXTRA_ACCOUNT_META_LEN;
+    if validation_data.len() < end {
+        anyhow::bail!("extra account metas for {} list {} entries but hold {} bytes", mint, count, validation_data.len());
+    }
+
+    let extra_accounts = validation_data[EXTRA_ACCOUNT_METAS_HEADER_LEN..end]
+        .chunks_exact(EXTRA_ACCOUNT_META_LEN)
+        .enumerate()
+        .map(|(i, meta)| {
+            if meta[0] != 0 {
+                anyhow::bail!("extra account {} of {}'s hook is seed-derived (discriminator {})", i, mint, meta[0]);
+            }
+            Ok(AccountMeta {
+                pubkey: Pubkey::new_from_array(meta[1..33].try_into().unwrap()),
+                is_signer: meta[33] != 0,
+                is_writable: meta[34] != 0,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TransferHook {
+        program_id: *hook_program,
+        validation_account: extra_account_metas_address(mint, hook_program),
+        extra_accounts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Token-2022 mint padded to the token account length, with a
+    /// TransferHook extension naming `hook_program`
+    fn hooked_mint_data(hook_program: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; ACCOUNT_TYPE_OFFSET];
+        data.push(ACCOUNT_TYPE_MINT);
+        data.extend_from_slice(&EXTENSION_TRANSFER_HOOK.to_le_bytes());
+        data.extend_from_slice(&64u16.to_le_bytes());
+        data.extend_from_slice(Pubkey::new_unique().as_ref()); // Authority
+        data.extend_from_slice(hook_program.as_ref());
+        data
+    }
+
+    /// Validation account listing `metas` as `(discriminator, address, signer, writable)`
+    fn validation_data(metas: &[(u8, Pubkey, bool, bool)]) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(&((4 + metas.len() * EXTRA_ACCOUNT_META_LEN) as u32).to_le_bytes());
+        data.extend_from_slice(&(metas.len() as u32).to_le_bytes());
+        for (discriminator, address, signer, writable) in metas {
+            data.push(*discriminator);
+            data.extend_from_slice(address.as_ref());
+            data.extend_from_slice(&[*signer as u8, *writable as u8]);
+        }
+        data
+    }
+
+    #[test]
+    fn only_a_mint_with_a_hook_program_set_is_hooked() {
+        let hook_program = Pubkey::new_unique();
+        assert_eq!(transfer_hook_program(&hooked_mint_data(&hook_program)), Some(hook_program));
+        // An extension whose program was cleared, and a plain SPL mint
+        assert_eq!(transfer_hook_program(&hooked_mint_data(&Pubkey::default())), None);
+        assert_eq!(transfer_hook_program(&[0u8; 82]), None);
+
+        // The hook is found behind another extension too
+        let mut data = vec![0u8; ACCOUNT_TYPE_OFFSET];
+        data.push(ACCOUNT_TYPE_MINT);
+        data.extend_from_slice(&EXTENSION_TRANSFER_FEE_CONFIG.to_le_bytes());
+        data.extend_from_slice(&108u16.to_le_bytes());
+        data.extend_from_slice(&[0u8; 108]);
+        data.extend_from_slice(&hooked_mint_data(&hook_program)[ACCOUNT_TYPE_OFFSET + 1..]);
+        assert_eq!(transfer_hook_program(&data), Some(hook_program));
+    }
+
+    #[test]
+    fn fixed_extra_accounts_resolve_ahead_of_the_hook_program_and_validation_account() {
+        let (mint, hook_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (config, ledger) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = validation_data(&[(0, config, false, false), (0, ledger, false, true)]);
+
+        let hook = resolve_transfer_hook(&mint, &hook_program, &data).unwrap();
+        assert_eq!(hook.validation_account, extra_account_metas_address(&mint, &hook_program));
+        assert_eq!(
+            hook.account_metas(),
+            vec![
+                AccountMeta::new_readonly(config, false),
+                AccountMeta::new(ledger, false),
+                AccountMeta::new_readonly(hook_program, false),
+                AccountMeta::new_readonly(hook.validation_account, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn seed_derived_or_truncated_extra_accounts_fail_resolution() {
+        let (mint, hook_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let seeded = validation_data(&[(0, Pubkey::new_unique(), false, false), (1, Pubkey::new_unique(), false, true)]);
+        let error = resolve_transfer_hook(&mint, &hook_program, &seeded).unwrap_err();
+        assert!(error.to_string().contains("seed-derived"), "{}", error);
+
+        let mut truncated = validation_data(&[(0, Pubkey::new_unique(), false, false)]);
+        truncated.truncate(truncated.len() - 1);
+        assert!(resolve_transfer_hook(&mint, &hook_program, &truncated).is_err());
+        assert!(resolve_transfer_hook(&mint, &hook_program, &[0u8; 4]).is_err());
+    }
+}
@@ -6,7 +6,12 @@ use crate::dex::humidifi::humidifi_program_id;
 use crate::dex::pancakeswap::pancakeswap_program_id;
 use crate::dex::raydium::{raydium_authority, raydium_cp_authority};
 use crate::dex::vertigo::constants::vertigo_program_id;
-use crate::pools::MintPoolData;
+use crate::engine::compute_profile::ComputeUnitTable;
+use crate::engine::split::CycleSplitter;
+use crate::engine::types::{ArbitrageCycle, DexType};
+use crate::pools::*;
+use crate::rpc::RpcLike;
+use rand::Rng;
 use solana_client::rpc_client::RpcClient;
 use solana_program::instruction::Instruction;
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
@@ -17,6 +22,7 @@ use solana_sdk::message::v0::Message;
 use solana_sdk::signature::{Keypair, Signature};
 use solana_sdk::signer::Signer;
 use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
@@ -37,20 +43,146 @@ use spl_associated_token_account::ID as associated_token_program_id;
 use spl_token::ID as token_program_id;
 use std::str::FromStr;
 
+/// Submits signed transactions. Kept apart from `RpcLike` so an endpoint
+/// that only sends never serves a read and the other way round.
+pub trait TransactionSender: Send + Sync {
+    fn send_transaction(&self, tx: &VersionedTransaction, max_retries: u64) -> anyhow::Result<Signature>;
+}
+
+impl TransactionSender for RpcClient {
+    fn send_transaction(&self, tx: &VersionedTransaction, max_retries: u64) -> anyhow::Result<Signature> {
+        Ok(self.send_transaction_with_config(
+            tx,
+            solana_client::rpc_config::RpcSendTransactionConfig {
+                skip_preflight: true,
+                max_retries: Some(max_retries as usize),
+                preflight_commitment: Some(CommitmentLevel::Confirmed),
+                ..Default::default()
+            },
+        )?)
+    }
+}
+
+/// Clients that receive transaction submissions: the spam endpoints when
+/// spamming is enabled, otherwise the `[rpc]` send endpoint. Reads never go
+/// through these.
+pub fn send_rpc_clients(config: &Config) -> Vec<Arc<RpcClient>> {
+    match config.spam.as_ref().filter(|s| s.enabled && !s.sending_rpc_urls.is_empty()) {
+        Some(spam) => spam
+            .sending_rpc_urls
+            .iter()
+            .map(|url| Arc::new(RpcClient::new(url.clone())))
+            .collect(),
+        None => vec![Arc::new(RpcClient::new(config.rpc.send_url().to_string()))],
+    }
+}
+
+/// Build, sign and submit the swap for `cycle`. `mint_pool_data` holds the
+/// pools it routes through; `data_client` serves the reads needed before
+/// sending; the signed transaction goes only to `send_clients`.
+#[allow(clippy::too_many_arguments)]
 pub async fn build_and_send_transaction(
     wallet_kp: &Keypair,
     config: &Config,
+    cycle: &ArbitrageCycle,
     mint_pool_data: &MintPoolData,
-    rpc_clients: &[Arc<RpcClient>],
+    compute_units: &ComputeUnitTable,
+    data_client: &dyn RpcLike,
+    send_clients: &[Arc<impl TransactionSender + ?Sized>],
     blockhash: Hash,
     address_lookup_table_accounts: &[AddressLookupTableAccount],
+    rng: &mut (impl Rng + Send),
 ) -> anyhow::Result<Vec<Signature>> {
+    let all_instructions = build_swap_instructions(wallet_kp, config, cycle, mint_pool_data, compute_units, data_client, rng)?;
+    let tx = sign_transaction(wallet_kp, &all_instructions, address_lookup_table_accounts, blockhash)?;
+
+    let max_retries = config
+        .spam
+        .as_ref()
+        .and_then(|s| s.max_retries)
+        .unwrap_or(3);
+
+    let mut signatures = Vec::new();
+
+    for (i, client) in send_clients.iter().enumerate() {
+        debug!("Sending transaction through RPC client {}", i);
+
+        let signature = match client.send_transaction(&tx, max_retries) {
+            Ok(sig) => sig,
+            Err(e) => {
+                error!("Failed to send transaction through RPC client {}: {}", i, e);
+                continue;
+            }
+        };
+
+        info!(
+            "Transaction sent successfully through RPC client {}: {}",
+            i, signature
+        );
+        signatures.push(signature);
+    }
+
+    Ok(signatures)
+}
+
+/// Build `cycle` as a bundle's single transaction with `tip` as its last
+/// instruction. The executor instruction routes the whole round trip itself
+/// and cannot pick up an intermediate balance left by an earlier transaction,
+/// so a cycle over the per-transaction budget `splitter` enforces is rejected
+/// rather than cut into parts.
+#[allow(clippy::too_many_arguments)]
+pub fn build_bundle_txs(
+    wallet_kp: &Keypair,
+    config: &Config,
+    cycle: &ArbitrageCycle,
+    mint_pool_data: &MintPoolData,
+    compute_units: &ComputeUnitTable,
+    splitter: &CycleSplitter,
+    data_client: &dyn RpcLike,
+    blockhash: Hash,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+    tip: Option<Instruction>,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Vec<VersionedTransaction>> {
+    splitter.fit_one_transaction(cycle, compute_units)?;
+    let mut instructions = build_swap_instructions(wallet_kp, config, cycle, mint_pool_data, compute_units, data_client, rng)?;
+    instructions.extend(tip);
+    Ok(vec![sign_transaction(wallet_kp, &instructions, address_lookup_table_accounts, blockhash)?])
+}
+
+fn sign_transaction(
+    wallet_kp: &Keypair,
+    instructions: &[Instruction],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> anyhow::Result<VersionedTransaction> {
+    let message = Message::try_compile(&wallet_kp.pubkey(), instructions, address_lookup_table_accounts, blockhash)?;
+    Ok(VersionedTransaction::try_new(
+        solana_sdk::message::VersionedMessage::V0(message),
+        &[wallet_kp],
+    )?)
+}
+
+/// Compute budget and swap instructions for `cycle`, built only after every
+/// pool it routes through is confirmed to be owned by the program its edge
+/// was priced for. The compute unit limit is sized from the measured cost of
+/// the cycle's venues, or `bot.compute_unit_limit` until all were measured.
+pub fn build_swap_instructions(
+    wallet_kp: &Keypair,
+    config: &Config,
+    cycle: &ArbitrageCycle,
+    mint_pool_data: &MintPoolData,
+    compute_units: &ComputeUnitTable,
+    data_client: &dyn RpcLike,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Vec<Instruction>> {
     let enable_flashloan = config.flashloan.as_ref().map_or(false, |k| k.enabled);
-    let compute_unit_limit = config.bot.compute_unit_limit;
+    let venues: Vec<DexType> = cycle.legs.iter().map(|leg| leg.dex_type).collect();
+    let compute_unit_limit = compute_units.compute_unit_limit(&venues, config.bot.compute_unit_limit);
     let mut instructions = vec![];
     // Add a random number here to make each transaction unique
     let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(
-        compute_unit_limit + rand::random::<u32>() % 1000,
+        compute_unit_limit + rng.gen_range(0..1000),
     );
     instructions.push(compute_budget_ix);
 
@@ -59,73 +191,155 @@ pub async fn build_and_send_transaction(
         ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price);
     instructions.push(compute_budget_price_ix);
 
+    // Refuse to route through a pool whose live owner no longer matches the program it was priced for
+    verify_pool_owners(data_client, &route_pool_owners(cycle))?;
+
     let swap_ix = create_swap_instruction(
         wallet_kp,
         mint_pool_data,
         compute_unit_limit,
         enable_flashloan,
+        rng,
     )?;
 
-    let mut all_instructions = instructions.clone();
-
     debug!("Adding swap instruction");
-    all_instructions.push(swap_ix);
+    instructions.push(swap_ix);
+    Ok(instructions)
+}
 
-    let message = Message::try_compile(
-        &wallet_kp.pubkey(),
-        &all_instructions,
-        address_lookup_table_accounts,
-        blockhash,
-    )?;
+/// Each pool `cycle` routes through, once, paired with the program its edge
+/// recorded as the owner
+pub fn route_pool_owners(cycle: &ArbitrageCycle) -> Vec<(Pubkey, Pubkey)> {
+    let mut seen = HashSet::new();
+    cycle
+        .legs
+        .iter()
+        .filter(|leg| seen.insert(leg.pool_pubkey))
+        .map(|leg| (leg.pool_pubkey, leg.program_id))
+        .collect()
+}
 
-    let tx = VersionedTransaction::try_new(
-        solana_sdk::message::VersionedMessage::V0(message),
-        &[wallet_kp],
-    )?;
+/// Every pool account in `mint_pool_data` with its venue
+pub fn pool_accounts(mint_pool_data: &MintPoolData) -> Vec<(DexType, Pubkey)> {
+    let mut pools = Vec::new();
+    pools.extend(mint_pool_data.raydium_pools.iter().map(|p| (DexType::RaydiumV4, p.pool)));
+    pools.extend(mint_pool_data.raydium_cp_pools.iter().map(|p| (DexType::RaydiumCp, p.pool)));
+    pools.extend(mint_pool_data.pump_pools.iter().map(|p| (DexType::Pump, p.pool)));
+    pools.extend(mint_pool_data.dlmm_pairs.iter().map(|p| (DexType::MeteoraDlmm, p.pair)));
+    pools.extend(mint_pool_data.whirlpool_pools.iter().map(|p| (DexType::Whirlpool, p.pool)));
+    pools.extend(mint_pool_data.raydium_clmm_pools.iter().map(|p| (DexType::RaydiumClmm, p.pool)));
+    pools.extend(mint_pool_data.meteora_damm_pools.iter().map(|p| (DexType::MeteoraDamm, p.pool)));
+    pools.extend(mint_pool_data.meteora_damm_v2_pools.iter().map(|p| (DexType::MeteoraDammV2, p.pool)));
+    pools.extend(mint_pool_data.vertigo_pools.iter().map(|p| (DexType::Vertigo, p.pool)));
+    pools.extend(mint_pool_data.heaven_pools.iter().map(|p| (DexType::Heaven, p.pool)));
+    pools.extend(mint_pool_data.futarchy_pools.iter().map(|p| (DexType::Futarchy, p.dao)));
+    pools.extend(mint_pool_data.humidifi_pools.iter().map(|p| (DexType::Humidifi, p.pool)));
+    pools.extend(mint_pool_data.pancakeswap_pools.iter().map(|p| (DexType::PancakeSwap, p.pool)));
+    pools.extend(mint_pool_data.byreal_pools.iter().map(|p| (DexType::Byreal, p.pool)));
+    pools.extend(mint_pool_data.lifinity_pools.iter().map(|p| (DexType::Lifinity, p.pool)));
+    pools.extend(mint_pool_data.phoenix_markets.iter().map(|p| (DexType::Phoenix, p.market)));
+    pools.extend(mint_pool_data.pump_bonding_curves.iter().map(|p| (DexType::PumpBondingCurve, p.curve)));
+    pools
+}
 
-    let max_retries = config
-        .spam
-        .as_ref()
-        .and_then(|s| s.max_retries)
-        .unwrap_or(3);
+/// Venues `create_swap_instruction` lays out accounts for: the instruction is
+/// built for one placeholder pool of every venue, and a venue counts when its
+/// pool account is carried by the result.
+pub fn swap_instruction_venues() -> HashSet<DexType> {
+    let wallet = Keypair::new();
+    let probe = probe_pool_data(&wallet.pubkey());
+    let instruction = match create_swap_instruction(&wallet, &probe, 0, false, &mut rand::thread_rng()) {
+        Ok(instruction) => instruction,
+        Err(e) => {
+            error!("Failed to build the probe swap instruction: {}", e);
+            return HashSet::new();
+        }
+    };
+    let carried: HashSet<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+    pool_accounts(&probe)
+        .into_iter()
+        .filter(|(_, pool)| carried.contains(pool))
+        .map(|(dex_type, _)| dex_type)
+        .collect()
+}
 
-    let mut signatures = Vec::new();
+/// One SOL-based pool of every venue, each account a distinct placeholder key
+fn probe_pool_data(wallet: &Pubkey) -> MintPoolData {
+    let key = Pubkey::new_unique;
+    let base_mint = sol_mint();
+    let mut data = MintPoolData::new(key(), wallet, token_program_id);
+    data.raydium_pools.push(RaydiumPool { pool: key(), token_vault: key(), sol_vault: key(), token_mint: data.mint, base_mint });
+    data.raydium_cp_pools.push(RaydiumCpPool {
+        pool: key(), token_vault: key(), sol_vault: key(), amm_config: key(), observation: key(), token_mint: data.mint, base_mint,
+    });
+    data.pump_pools.push(PumpPool {
+        pool: key(), token_vault: key(), sol_vault: key(), fee_wallet: key(), fee_token_wallet: key(),
+        coin_creator_vault_ata: key(), coin_creator_vault_authority: key(), token_mint: data.mint, base_mint, is_mayhem_mode: false,
+    });
+    data.dlmm_pairs.push(DlmmPool {
+        pair: key(), token_vault: key(), sol_vault: key(), oracle: key(), bin_arrays: vec![key()], memo_program: None, token_mint: data.mint, base_mint,
+    });
+    data.whirlpool_pools.push(WhirlpoolPool {
+        pool: key(), oracle: key(), x_vault: key(), y_vault: key(), tick_arrays: vec![key()], memo_program: None, token_mint: data.mint, base_mint,
+    });
+    data.raydium_clmm_pools.push(RaydiumClmmPool {
+        pool: key(), amm_config: key(), observation_state: key(), bitmap_extension: key(), x_vault: key(), y_vault: key(),
+        tick_arrays: vec![key()], memo_program: None, token_mint: data.mint, base_mint,
+    });
+    data.meteora_damm_pools.push(MeteoraDAmmPool {
+        pool: key(), token_x_vault: key(), token_sol_vault: key(), token_x_token_vault: key(), token_sol_token_vault: key(),
+        token_x_lp_mint: key(), token_sol_lp_mint: key(), token_x_pool_lp: key(), token_sol_pool_lp: key(),
+        admin_token_fee_x: key(), admin_token_fee_sol: key(), token_mint: data.mint, base_mint,
+    });
+    data.meteora_damm_v2_pools.push(MeteoraDAmmV2Pool { pool: key(), token_x_vault: key(), token_sol_vault: key(), token_mint: data.mint, base_mint });
+    data.vertigo_pools.push(VertigoPool {
+        pool: key(), pool_owner: key(), token_x_vault: key(), token_sol_vault: key(), token_mint: data.mint, base_mint,
+    });
+    data.heaven_pools.push(HeavenPool {
+        pool: key(), protocol_config: key(), token_x_vault: key(), token_base_vault: key(), token_mint: data.mint, base_mint,
+        token_program: token_program_id,
+    });
+    data.futarchy_pools.push(FutarchyPool { dao: key(), token_x_vault: key(), token_sol_vault: key(), token_mint: data.mint, base_mint });
+    data.humidifi_pools.push(HumidifiPool { pool: key(), token_x_vault: key(), token_sol_vault: key(), token_mint: data.mint, base_mint });
+    data.pancakeswap_pools.push(PancakeswapPool {
+        pool: key(), amm_config: key(), observation_state: key(), bitmap_extension: key(), x_vault: key(), y_vault: key(),
+        tick_arrays: vec![key()], memo_program: None, token_mint: data.mint, base_mint,
+    });
+    data.byreal_pools.push(ByrealPool {
+        pool: key(), amm_config: key(), observation_state: key(), bitmap_extension: key(), x_vault: key(), y_vault: key(),
+        tick_arrays: vec![key()], memo_program: None, token_mint: data.mint, base_mint,
+    });
+    data.lifinity_pools.push(LifinityPool {
+        pool: key(), token_x_vault: key(), token_sol_vault: key(), token_mint: data.mint, base_mint, fee_bps: 0,
+    });
+    data.phoenix_markets.push(PhoenixMarket { market: key(), base_vault: key(), quote_vault: key(), token_mint: data.mint, base_mint });
+    data.pump_bonding_curves.push(PumpBondingCurvePool { curve: key(), token_vault: key(), token_mint: data.mint, base_mint });
+    data
+}
 
-    for (i, client) in rpc_clients.iter().enumerate() {
-        debug!("Sending transaction through RPC client {}", i);
+/// Fetch each `(account, expected_owner)` and fail if any account is missing
+/// or owned by a different program.
+pub fn verify_pool_owners(rpc_client: &dyn RpcLike, expected: &[(Pubkey, Pubkey)]) -> anyhow::Result<()> {
+    for chunk in expected.chunks(100) {
+        let keys: Vec<Pubkey> = chunk.iter().map(|(pool, _)| *pool).collect();
+        let accounts = rpc_client.get_multiple_accounts(&keys)?;
 
-        let signature = match send_transaction_with_retries(client, &tx, max_retries).await {
-            Ok(sig) => sig,
-            Err(e) => {
-                error!("Failed to send transaction through RPC client {}: {}", i, e);
-                continue;
+        for ((pool, expected_owner), account) in chunk.iter().zip(accounts) {
+            match account {
+                Some(account) => check_pool_owner(pool, &account.owner, expected_owner)?,
+                None => anyhow::bail!("Pool account {} not found", pool),
             }
-        };
-
-        info!(
-            "Transaction sent successfully through RPC client {}: {}",
-            i, signature
-        );
-        signatures.push(signature);
+        }
     }
-
-    Ok(signatures)
+    Ok(())
 }
 
-async fn send_transaction_with_retries(
-    client: &RpcClient,
-    tx: &VersionedTransaction,
-    max_retries: u64,
-) -> anyhow::Result<Signature> {
-    Ok(client.send_transaction_with_config(
-        tx,
-        solana_client::rpc_config::RpcSendTransactionConfig {
-            skip_preflight: true,
-            max_retries: Some(max_retries as usize),
-            preflight_commitment: Some(CommitmentLevel::Confirmed),
-            ..Default::default()
-        },
-    )?)
+pub fn check_pool_owner(pool: &Pubkey, actual_owner: &Pubkey, expected_owner: &Pubkey) -> anyhow::Result<()> {
+    if actual_owner != expected_owner {
+        error!("Pool {} is owned by {}, expected {}", pool, actual_owner, expected_owner);
+        anyhow::bail!("Pool {} is owned by {}, expected {}", pool, actual_owner, expected_owner);
+    }
+    Ok(())
 }
 
 /// Helper function to derive the vault token account PDA address for a given mint
@@ -139,6 +353,7 @@ fn create_swap_instruction(
     mint_pool_data: &MintPoolData,
     compute_unit_limit: u32,
     use_flashloan: bool,
+    rng: &mut impl Rng,
 ) -> anyhow::Result<Instruction> {
     debug!("Creating swap instruction for all DEX types");
 
@@ -194,6 +409,9 @@ fn create_swap_instruction(
         check_pool_base_mints!(&mint_pool_data.humidifi_pools);
         check_pool_base_mints!(&mint_pool_data.pancakeswap_pools);
         check_pool_base_mints!(&mint_pool_data.byreal_pools);
+        check_pool_base_mints!(&mint_pool_data.lifinity_pools);
+        check_pool_base_mints!(&mint_pool_data.phoenix_markets);
+        check_pool_base_mints!(&mint_pool_data.pump_bonding_curves);
 
         if all_sol_base {
             sol_mint_pubkey
@@ -232,7 +450,7 @@ fn create_swap_instruction(
             Pubkey::from_str("J6c7noBHvWju4mMA3wXt3igbBSp2m9ATbA6cjMtAUged").unwrap(),
             Pubkey::from_str("BjsfwxDu7GX7RRW6oSRTpMkASdXAgCcHnXEcatqSfuuY").unwrap(),
         ];
-        fee_accounts[rand::random::<usize>() % fee_accounts.len()]
+        fee_accounts[rng.gen_range(0..fee_accounts.len())]
     };
 
     // Step 4: Build accounts vector with dynamic base_mint and wallet_base_account
@@ -256,7 +474,7 @@ fn create_swap_instruction(
         let vault_index = if flashloan_base_mint == usdc_mint {
             0
         } else {
-            rand::random::<usize>() % vault_authorities.len()
+            rng.gen_range(0..vault_authorities.len())
         };
         let vault_authority = vault_authorities[vault_index];
         accounts.push(AccountMeta::new_readonly(vault_authority, false));
@@ -308,6 +526,9 @@ fn create_swap_instruction(
     check_for_stable_base!(&mint_pool_data.humidifi_pools);
     check_for_stable_base!(&mint_pool_data.pancakeswap_pools);
     check_for_stable_base!(&mint_pool_data.byreal_pools);
+    check_for_stable_base!(&mint_pool_data.lifinity_pools);
+    check_for_stable_base!(&mint_pool_data.phoenix_markets);
+    check_for_stable_base!(&mint_pool_data.pump_bonding_curves);
 
     // Mixed mode is ONLY supported when base_mint is SOL
     // If base_mint is USDC, all pools should already be USDC-based (no mixing needed)
@@ -590,6 +811,11 @@ fn create_swap_instruction(
         }
     }
 
+    // A hooked mint's transfers need the hook's accounts; they go after the pools
+    if let Some(hook) = &mint_pool_data.transfer_hook {
+        accounts.extend(hook.account_metas());
+    }
+
     // Create instruction data
     let mut data = vec![28u8];
 
@@ -609,3 +835,338 @@ fn create_swap_instruction(
         data,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::types::SwapLeg;
+    use crate::rpc::MockRpc;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use solana_sdk::account::Account;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [bot]
+            compute_unit_limit = 400000
+
+            [routing.markets]
+            markets = []
+            process_delay = 0
+
+            [rpc]
+            url = "http://127.0.0.1:8899"
+
+            [wallet]
+            private_key = "unused"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn leg(from_mint: Pubkey, to_mint: Pubkey, pool_pubkey: Pubkey, dex_type: DexType, program_id: Pubkey) -> SwapLeg {
+        SwapLeg {
+            from_mint,
+            to_mint,
+            pool_pubkey,
+            dex_type,
+            program_id,
+            amount_in: 0,
+            estimated_amount_out: 0,
+            quote_haircut_bps: 0,
+        }
+    }
+
+    /// SOL -> token through the probe's Raydium V4 pool, back through its CP pool
+    fn cycle(pool_data: &MintPoolData, cp_program: Pubkey) -> ArbitrageCycle {
+        let (v4, cp) = (pool_data.raydium_pools[0].pool, pool_data.raydium_cp_pools[0].pool);
+        ArbitrageCycle {
+            legs: vec![
+                leg(sol_mint(), pool_data.mint, v4, DexType::RaydiumV4, DexType::RaydiumV4.program_id()),
+                leg(pool_data.mint, sol_mint(), cp, DexType::RaydiumCp, cp_program),
+            ],
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            total_hops: 2,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    fn owned_by(owner: Pubkey) -> Account {
+        Account { lamports: 1, data: vec![0; 8], owner, executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn only_the_routed_pools_are_checked_against_their_edge_owner() {
+        let wallet = Keypair::new();
+        let pool_data = probe_pool_data(&wallet.pubkey());
+        // A fork deployment: the edge recorded the pool's real owner, not the venue default
+        let fork_program = Pubkey::new_unique();
+        let cycle = cycle(&pool_data, fork_program);
+        let rpc = MockRpc::new();
+        rpc.set_account(cycle.legs[0].pool_pubkey, owned_by(DexType::RaydiumV4.program_id()));
+        rpc.set_account(cycle.legs[1].pool_pubkey, owned_by(fork_program));
+
+        // The other 15 probe pools do not exist and are never read
+        let instructions =
+            build_swap_instructions(&wallet, &config(), &cycle, &pool_data, &ComputeUnitTable::default(), &rpc, &mut rand::thread_rng()).unwrap();
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(rpc.batch_sizes(), vec![2]);
+    }
+
+    #[test]
+    fn the_compute_unit_limit_comes_from_the_measured_venues() {
+        let wallet = Keypair::new();
+        let pool_data = probe_pool_data(&wallet.pubkey());
+        let cycle = cycle(&pool_data, DexType::RaydiumCp.program_id());
+        let rpc = MockRpc::new();
+        rpc.set_account(cycle.legs[0].pool_pubkey, owned_by(DexType::RaydiumV4.program_id()));
+        rpc.set_account(cycle.legs[1].pool_pubkey, owned_by(DexType::RaydiumCp.program_id()));
+        let mut compute_units = ComputeUnitTable::default();
+        compute_units.record(&crate::engine::compute_profile::ComputeProfile {
+            legs: vec![(DexType::RaydiumV4, 30_000), (DexType::RaydiumCp, 40_000)],
+            overhead: 20_000,
+        });
+
+        let instructions =
+            build_swap_instructions(&wallet, &config(), &cycle, &pool_data, &compute_units, &rpc, &mut rand::thread_rng()).unwrap();
+
+        // SetComputeUnitLimit: tag 2, then the limit plus up to 1000 units of jitter
+        assert_eq!(instructions[0].data[0], 2);
+        let limit = u32::from_le_bytes(instructions[0].data[1..5].try_into().unwrap());
+        assert!((108_000..109_000).contains(&limit), "{}", limit);
+    }
+
+    #[test]
+    fn a_mismatched_owner_aborts_the_build() {
+        let wallet = Keypair::new();
+        let pool_data = probe_pool_data(&wallet.pubkey());
+        let cycle = cycle(&pool_data, DexType::RaydiumCp.program_id());
+        let rpc = MockRpc::new();
+        rpc.set_account(cycle.legs[0].pool_pubkey, owned_by(DexType::RaydiumV4.program_id()));
+        let impostor = Pubkey::new_unique();
+        rpc.set_account(cycle.legs[1].pool_pubkey, owned_by(impostor));
+
+        let err = build_swap_instructions(&wallet, &config(), &cycle, &pool_data, &ComputeUnitTable::default(), &rpc, &mut rand::thread_rng()).unwrap_err();
+
+        assert!(err.to_string().contains(&format!("owned by {}", impostor)), "{}", err);
+    }
+
+    #[test]
+    fn a_missing_routed_pool_aborts_the_build() {
+        let wallet = Keypair::new();
+        let pool_data = probe_pool_data(&wallet.pubkey());
+        let cycle = cycle(&pool_data, DexType::RaydiumCp.program_id());
+        let rpc = MockRpc::new();
+        rpc.set_account(cycle.legs[0].pool_pubkey, owned_by(DexType::RaydiumV4.program_id()));
+
+        let err = build_swap_instructions(&wallet, &config(), &cycle, &pool_data, &ComputeUnitTable::default(), &rpc, &mut rand::thread_rng()).unwrap_err();
+
+        assert!(err.to_string().contains("not found"), "{}", err);
+    }
+
+    /// Send endpoint that records what it was given and can read nothing
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: std::sync::Mutex<Vec<Signature>>,
+        fail: bool,
+    }
+
+    impl TransactionSender for RecordingSender {
+        fn send_transaction(&self, tx: &VersionedTransaction, _max_retries: u64) -> anyhow::Result<Signature> {
+            if self.fail {
+                anyhow::bail!("send endpoint down");
+            }
+            self.sent.lock().unwrap().push(tx.signatures[0]);
+            Ok(tx.signatures[0])
+        }
+    }
+
+    #[tokio::test]
+    async fn submissions_go_to_the_send_clients_and_reads_to_the_data_client() {
+        let wallet = Keypair::new();
+        let pool_data = probe_pool_data(&wallet.pubkey());
+        let cycle = cycle(&pool_data, DexType::RaydiumCp.program_id());
+        let data_client = MockRpc::new();
+        data_client.set_account(cycle.legs[0].pool_pubkey, owned_by(DexType::RaydiumV4.program_id()));
+        data_client.set_account(cycle.legs[1].pool_pubkey, owned_by(DexType::RaydiumCp.program_id()));
+        let send_clients = vec![
+            Arc::new(RecordingSender::default()),
+            Arc::new(RecordingSender { fail: true, ..Default::default() }),
+            Arc::new(RecordingSender::default()),
+        ];
+
+        let signatures = build_and_send_transaction(
+            &wallet,
+            &config(),
+            &cycle,
+            &pool_data,
+            &ComputeUnitTable::default(),
+            &data_client,
+            &send_clients,
+            Hash::default(),
+            &[],
+            &mut StdRng::seed_from_u64(7),
+        )
+        .await
+        .unwrap();
+
+        // The pool owner check is the only read, and it went to the data client
+        assert_eq!(data_client.calls(), 1);
+        // Every healthy send endpoint got the same signed transaction; a failing one is skipped
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(*send_clients[0].sent.lock().unwrap(), vec![signatures[0]]);
+        assert!(send_clients[1].sent.lock().unwrap().is_empty());
+        assert_eq!(*send_clients[2].sent.lock().unwrap(), vec![signatures[0]]);
+    }
+
+    #[test]
+    fn the_send_url_receives_submissions_when_configured() {
+        let mut config = config();
+        assert_eq!(send_rpc_clients(&config)[0].url(), "http://127.0.0.1:8899");
+        config.rpc.send_url = Some("http://127.0.0.1:9999".to_string());
+        let send_clients = send_rpc_clients(&config);
+        assert_eq!(send_clients.len(), 1);
+        assert_eq!(send_clients[0].url(), "http://127.0.0.1:9999");
+    }
+
+    /// A `hops`-leg RaydiumV4 cycle alternating SOL and the token, each
+    /// pool owned by the Raydium program on `rpc`
+    fn raydium_cycle(pool_data: &mut MintPoolData, rpc: &MockRpc, hops: usize) -> ArbitrageCycle {
+        let legs: Vec<SwapLeg> = (0..hops)
+            .map(|i| {
+                let pool = RaydiumPool {
+                    pool: Pubkey::new_unique(),
+                    token_vault: Pubkey::new_unique(),
+                    sol_vault: Pubkey::new_unique(),
+                    token_mint: pool_data.mint,
+                    base_mint: sol_mint(),
+                };
+                pool_data.raydium_pools.push(pool.clone());
+                rpc.set_account(pool.pool, owned_by(raydium_program_id()));
+                let (from, to) = if i % 2 == 0 { (sol_mint(), pool_data.mint) } else { (pool_data.mint, sol_mint()) };
+                leg(from, to, pool.pool, DexType::RaydiumV4, raydium_program_id())
+            })
+            .collect();
+        ArbitrageCycle {
+            total_hops: legs.len(),
+            legs,
+            total_profit_bps: 0,
+            estimated_profit_lamports: 0,
+            observational: false,
+            priority_penalty_bps: 0,
+            estimated_tx_cost_lamports: 0,
+        }
+    }
+
+    #[test]
+    fn a_bundled_cycle_is_one_transaction_ending_in_the_tip() {
+        let wallet = Keypair::new();
+        let mut pool_data = MintPoolData::new(Pubkey::new_unique(), &wallet.pubkey(), token_program_id);
+        let rpc = MockRpc::new();
+        let cycle = raydium_cycle(&mut pool_data, &rpc, 3);
+        let splitter = CycleSplitter { max_compute_units: 350_000, max_legs_per_tx: 8, unmeasured_leg_units: 100_000 };
+        let tip_account = Pubkey::new_unique();
+        let tip = solana_sdk::system_instruction::transfer(&wallet.pubkey(), &tip_account, 10_000);
+
+        let txs = build_bundle_txs(
+            &wallet,
+            &config(),
+            &cycle,
+            &pool_data,
+            &ComputeUnitTable::default(),
+            &splitter,
+            &rpc,
+            Hash::default(),
+            &[],
+            Some(tip.clone()),
+            &mut StdRng::seed_from_u64(7),
+        )
+        .unwrap();
+
+        assert_eq!(txs.len(), 1);
+        let tx = &txs[0];
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+        let keys = tx.message.static_account_keys();
+        let instructions = tx.message.instructions();
+        let program = |idx: usize| keys[instructions[idx].program_id_index as usize];
+        assert_eq!(instructions.len(), 4);
+
+        // Executor call: opcode, zero minimum profit, the configured limit,
+        // no-failure mode off, reserved, no flashloan
+        let executor = Pubkey::from_str("MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz").unwrap();
+        assert_eq!(program(2), executor);
+        let mut expected = vec![28u8];
+        expected.extend_from_slice(&0u64.to_le_bytes());
+        expected.extend_from_slice(&400_000u32.to_le_bytes());
+        expected.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(instructions[2].data, expected);
+        let swap_accounts: Vec<Pubkey> = instructions[2].accounts.iter().map(|idx| keys[*idx as usize]).collect();
+        let pool_positions: Vec<usize> = cycle
+            .legs
+            .iter()
+            .map(|leg| swap_accounts.iter().position(|key| *key == leg.pool_pubkey).expect("a leg's pool is missing"))
+            .collect();
+        assert!(pool_positions.windows(2).all(|pair| pair[0] < pair[1]), "pools out of leg order: {:?}", pool_positions);
+
+        // The tip is the last instruction
+        assert_eq!(program(3), solana_sdk::system_program::id());
+        assert_eq!(instructions[3].data, tip.data);
+        assert_eq!(keys[instructions[3].accounts[1] as usize], tip_account);
+    }
+
+    #[test]
+    fn a_cycle_over_the_transaction_budget_is_not_bundled() {
+        let wallet = Keypair::new();
+        let mut pool_data = MintPoolData::new(Pubkey::new_unique(), &wallet.pubkey(), token_program_id);
+        let rpc = MockRpc::new();
+        let cycle = raydium_cycle(&mut pool_data, &rpc, 6);
+        // Three unmeasured legs fit a transaction, six do not
+        let splitter = CycleSplitter { max_compute_units: 350_000, max_legs_per_tx: 8, unmeasured_leg_units: 100_000 };
+
+        let err = build_bundle_txs(
+            &wallet,
+            &config(),
+            &cycle,
+            &pool_data,
+            &ComputeUnitTable::default(),
+            &splitter,
+            &rpc,
+            Hash::default(),
+            &[],
+            None,
+            &mut StdRng::seed_from_u64(7),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().starts_with("6-leg cycle needs 2 transactions"), "{}", err);
+        // Rejected before any pool owner was read
+        assert!(rpc.batch_sizes().is_empty());
+    }
+
+    #[test]
+    fn a_hooked_mint_appends_its_hook_accounts_after_the_pools() {
+        let wallet = Keypair::new();
+        let plain = probe_pool_data(&wallet.pubkey());
+        let mut hooked = plain.clone();
+        let hook = crate::token_extensions::TransferHook {
+            program_id: Pubkey::new_unique(),
+            validation_account: Pubkey::new_unique(),
+            extra_accounts: vec![AccountMeta::new(Pubkey::new_unique(), false), AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+        };
+        hooked.transfer_hook = Some(hook.clone());
+
+        let plain_ix = create_swap_instruction(&wallet, &plain, 400_000, false, &mut StdRng::seed_from_u64(3)).unwrap();
+        let hooked_ix = create_swap_instruction(&wallet, &hooked, 400_000, false, &mut StdRng::seed_from_u64(3)).unwrap();
+
+        let (pools, appended) = hooked_ix.accounts.split_at(plain_ix.accounts.len());
+        assert_eq!(pools, &plain_ix.accounts[..]);
+        assert_eq!(appended, &hook.account_metas()[..]);
+        assert_eq!(appended[appended.len() - 2].pubkey, hook.program_id);
+        assert_eq!(appended[appended.len() - 1].pubkey, hook.validation_account);
+    }
+}
@@ -0,0 +1,169 @@
+//! Typed amounts for values that are easy to mix up as bare numbers: SOL in
+//! lamports and dollar values. Conversions between them go through the SOL
+//! price explicitly; arithmetic only combines values of the same unit.
+//!
+//! ```
+//! use solana_onchain_arbitrage_bot::units::{Lamports, Usd};
+//! let fee = Lamports(5_000) + Lamports(10_000);
+//! assert_eq!(fee.to_usd(200.0), Usd(0.003));
+//! assert_eq!(Usd(3.0).to_lamports(200.0), Ok(Lamports(15_000_000)));
+//! ```
+//!
+//! ```compile_fail
+//! use solana_onchain_arbitrage_bot::units::{Lamports, Usd};
+//! // A dollar value cannot be added to lamports, nor a raw number to either
+//! let total = Lamports(5_000) + Usd(1.0);
+//! ```
+//!
+//! ```compile_fail
+//! use solana_onchain_arbitrage_bot::units::Lamports;
+//! let total = Lamports(5_000) + 5_000u64;
+//! ```
+
+use crate::math::{lamports_from_f64, ConversionError};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul, Sub};
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// An amount of SOL in lamports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lamports(pub u64);
+
+impl Lamports {
+    /// Whole SOL to lamports, floored; fails on NaN, negative and overflowing amounts
+    pub fn from_sol(sol: f64) -> Result<Self, ConversionError> {
+        lamports_from_f64(sol * LAMPORTS_PER_SOL as f64).map(Lamports)
+    }
+
+    pub fn as_sol(self) -> f64 {
+        self.0 as f64 / LAMPORTS_PER_SOL as f64
+    }
+
+    pub fn to_usd(self, sol_price_usd: f64) -> Usd {
+        Usd(self.as_sol() * sol_price_usd)
+    }
+}
+
+impl Add for Lamports {
+    type Output = Lamports;
+    fn add(self, rhs: Lamports) -> Lamports {
+        Lamports(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Lamports {
+    type Output = Lamports;
+    fn sub(self, rhs: Lamports) -> Lamports {
+        Lamports(self.0 - rhs.0)
+    }
+}
+
+impl Sum for Lamports {
+    fn sum<I: Iterator<Item = Lamports>>(iter: I) -> Lamports {
+        Lamports(iter.map(|lamports| lamports.0).sum())
+    }
+}
+
+impl fmt::Display for Lamports {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} SOL", self.as_sol())
+    }
+}
+
+/// A value in US dollars
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Usd(pub f64);
+
+impl Usd {
+    pub fn value(self) -> f64 {
+        self.0
+    }
+
+    /// Whole SOL worth this much at `sol_price_usd`; not finite for a zero price
+    pub fn to_sol(self, sol_price_usd: f64) -> f64 {
+        self.0 / sol_price_usd
+    }
+
+    pub fn to_lamports(self, sol_price_usd: f64) -> Result<Lamports, ConversionError> {
+        Lamports::from_sol(self.to_sol(sol_price_usd))
+    }
+}
+
+impl Add for Usd {
+    type Output = Usd;
+    fn add(self, rhs: Usd) -> Usd {
+        Usd(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Usd {
+    type Output = Usd;
+    fn sub(self, rhs: Usd) -> Usd {
+        Usd(self.0 - rhs.0)
+    }
+}
+
+/// Scaling, e.g. both sides of a pool from one side's value
+impl Mul<f64> for Usd {
+    type Output = Usd;
+    fn mul(self, rhs: f64) -> Usd {
+        Usd(self.0 * rhs)
+    }
+}
+
+/// Ratio of two dollar values
+impl Div for Usd {
+    type Output = f64;
+    fn div(self, rhs: Usd) -> f64 {
+        self.0 / rhs.0
+    }
+}
+
+impl fmt::Display for Usd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${:.2}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lamports_and_dollars_convert_through_the_sol_price() {
+        assert_eq!(Lamports::from_sol(1.5), Ok(Lamports(1_500_000_000)));
+        assert_eq!(Lamports(2_500_000_000).as_sol(), 2.5);
+        assert_eq!(Lamports(500_000_000).to_usd(200.0), Usd(100.0));
+        assert_eq!(Usd(100.0).to_sol(200.0), 0.5);
+        assert_eq!(Usd(100.0).to_lamports(200.0), Ok(Lamports(500_000_000)));
+        // A round trip at any price lands back on the same lamports
+        for price in [0.5, 150.0, 237.19] {
+            let lamports = Lamports(123_456_000);
+            assert!(lamports.to_usd(price).to_lamports(price).unwrap().0.abs_diff(lamports.0) <= 1, "{}", price);
+        }
+    }
+
+    #[test]
+    fn unrepresentable_amounts_are_conversion_errors() {
+        assert_eq!(Lamports::from_sol(-1.0), Err(ConversionError::Negative(-1e9)));
+        assert!(matches!(Lamports::from_sol(f64::NAN), Err(ConversionError::NotFinite(_))));
+        assert!(matches!(Lamports::from_sol(1e12), Err(ConversionError::Overflow(_))));
+        // No SOL price to convert at
+        assert!(matches!(Usd(1.0).to_lamports(0.0), Err(ConversionError::NotFinite(_))));
+    }
+
+    #[test]
+    fn same_unit_arithmetic_keeps_the_unit() {
+        assert_eq!(Lamports(7) + Lamports(3), Lamports(10));
+        assert_eq!(Lamports(7) - Lamports(3), Lamports(4));
+        assert_eq!([Lamports(1), Lamports(2), Lamports(3)].into_iter().sum::<Lamports>(), Lamports(6));
+        assert_eq!(Usd(1.5) + Usd(2.0), Usd(3.5));
+        assert_eq!(Usd(2.0) - Usd(0.5), Usd(1.5));
+        assert_eq!(Usd(2.0) * 2.0, Usd(4.0));
+        assert_eq!(Usd(3.0) / Usd(1.5), 2.0);
+        assert_eq!(Lamports(1_500_000_000).to_string(), "1.5 SOL");
+        assert_eq!(Usd(12.345).to_string(), "$12.35");
+    }
+}
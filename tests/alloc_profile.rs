@@ -0,0 +1,89 @@
+//! Checks on the `profiling` feature's counters and on the allocations the
+//! detector saves by reusing its scratch maps.
+//!
+//! The counters are process-wide, so the tests take turns.
+//!
+//! Run with `cargo test --features profiling --test alloc_profile`.
+#![cfg(feature = "profiling")]
+
+use solana_onchain_arbitrage_bot::constants::sol_mint;
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_onchain_arbitrage_bot::profiling::{alloc_counters, IterationProfile, StageMark};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Mutex;
+
+const MINTS: usize = 50;
+const PASSES: usize = 50;
+
+static COUNTERS: Mutex<()> = Mutex::new(());
+
+/// `MINTS` token mints with two SOL pools each, one quoting 2% above the other
+fn fixture_graph() -> PriceGraph {
+    let graph = PriceGraph::new();
+    let sol = sol_mint();
+    for index in 0..MINTS {
+        let mint = Pubkey::new_unique();
+        let price = 0.5 + index as f64 / MINTS as f64;
+        for price in [price, price * 1.02] {
+            let pool = Pubkey::new_unique();
+            let edge = |to_mint: Pubkey, price: f64| PoolEdge::new(pool, to_mint, DexType::RaydiumCp, price, 100_000.0, 25, spl_token::id());
+            graph.add_edge(mint, sol, edge(sol, price));
+            graph.add_edge(sol, mint, edge(mint, 1.0 / price));
+        }
+    }
+    graph
+}
+
+#[test]
+fn a_stage_reports_what_it_allocated() {
+    let _turn = COUNTERS.lock().unwrap();
+    let before = alloc_counters();
+    let mark = StageMark::start();
+    let buffers: Vec<Vec<u8>> = (0..10).map(|_| Vec::with_capacity(4_096)).collect();
+    let profile = mark.finish();
+    drop(buffers);
+
+    assert!(profile.allocations >= 11, "{:?}", profile);
+    assert!(profile.allocated_bytes >= 10 * 4_096, "{:?}", profile);
+    assert!(profile.peak_growth_bytes >= 10 * 4_096, "{:?}", profile);
+    let after = alloc_counters();
+    assert!(after.allocations > before.allocations);
+    assert!(after.allocated_bytes >= before.allocated_bytes + 10 * 4_096);
+
+    let mut iteration = IterationProfile::default();
+    iteration.record("first", profile);
+    iteration.record("second", profile);
+    assert_eq!(iteration.total_allocations(), 2 * profile.allocations);
+}
+
+#[test]
+fn reusing_the_detector_scratch_allocates_less_on_the_fixture_workload() {
+    let _turn = COUNTERS.lock().unwrap();
+    let graph = fixture_graph();
+    let threshold = ProfitThreshold::flat(0);
+
+    let mark = StageMark::start();
+    let mut fresh_cycles = 0;
+    for _ in 0..PASSES {
+        fresh_cycles += CycleDetector::detect_with_near_misses(&graph, sol_mint(), 2, 5, threshold, 0).cycles.len();
+    }
+    let fresh = mark.finish();
+
+    let mut scratch = DetectorScratch::default();
+    let mark = StageMark::start();
+    let mut reused_cycles = 0;
+    for _ in 0..PASSES {
+        reused_cycles += CycleDetector::detect_with_scratch(&mut scratch, &graph, sol_mint(), 2, 5, threshold, 0).cycles.len();
+    }
+    let reused = mark.finish();
+
+    assert!(fresh_cycles > 0);
+    assert_eq!(reused_cycles, fresh_cycles, "reuse must not change what is detected");
+    assert!(
+        reused.allocations < fresh.allocations,
+        "reused scratch made {} allocations, fresh maps {}",
+        reused.allocations,
+        fresh.allocations
+    );
+    assert!(reused.allocated_bytes < fresh.allocated_bytes);
+}
@@ -0,0 +1,105 @@
+use solana_onchain_arbitrage_bot::constants::{sol_mint, usdc_mint};
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_onchain_arbitrage_bot::pools::MintPoolData;
+use solana_onchain_arbitrage_bot::rpc::MockRpc;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+
+/// One Raydium pool quoting `price` of `to_mint` per `from_mint`, 1M USD deep
+fn quote(graph: &PriceGraph, from_mint: Pubkey, to_mint: Pubkey, price: f64) -> Pubkey {
+    let pool = Pubkey::new_unique();
+    graph.add_edge(from_mint, to_mint, PoolEdge::new(pool, to_mint, DexType::RaydiumV4, price, 1e6, 25, spl_token::id()));
+    pool
+}
+
+#[test]
+fn a_three_hop_cycle_through_sol_is_detected_and_sized_over_its_own_pools() {
+    let graph = Arc::new(PriceGraph::new());
+    let (sol, usdc, bonk) = (sol_mint(), usdc_mint(), Pubkey::new_unique());
+    // 150 * 20_000 * 0.000_000_347 = 1.041 before three 25 bps fees
+    let sol_usdc = quote(&graph, sol, usdc, 150.0);
+    let usdc_bonk = quote(&graph, usdc, bonk, 20_000.0);
+    let bonk_sol = quote(&graph, bonk, sol, 0.000_000_347);
+    // Fairly priced ways back, no cycle on their own
+    quote(&graph, usdc, sol, 1.0 / 150.0);
+    quote(&graph, bonk, usdc, 1.0 / 20_000.0);
+
+    let cycles = CycleDetector::find_negative_cycles(&graph, sol, 2, 4, ProfitThreshold::flat(50));
+
+    assert_eq!(cycles.len(), 1);
+    let cycle = &cycles[0];
+    let route: Vec<(Pubkey, Pubkey, Pubkey)> = cycle.legs.iter().map(|leg| (leg.from_mint, leg.pool_pubkey, leg.to_mint)).collect();
+    assert_eq!(route, vec![(sol, sol_usdc, usdc), (usdc, usdc_bonk, bonk), (bonk, bonk_sol, sol)]);
+    for leg in &cycle.legs {
+        assert!(
+            graph.neighbors(&leg.from_mint).iter().any(|(to_mint, edge)| *to_mint == leg.to_mint && edge.pool_pubkey == leg.pool_pubkey),
+            "leg through {} has no graph edge",
+            leg.pool_pubkey
+        );
+    }
+
+    // Every leg resolves to its edge, so the optimizer can price the whole cycle
+    let optimizer = AmountOptimizer::new(graph.clone(), 1, 0.0);
+    assert!(optimizer.expected_profit(cycle, 100_000_000).is_some_and(|profit| profit > 0));
+}
+
+fn spl_account(data: Vec<u8>) -> Account {
+    Account { lamports: 1, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+}
+
+fn vault(mint: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; 165];
+    data[0..32].copy_from_slice(mint.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    spl_account(data)
+}
+
+fn mint(decimals: u8) -> Account {
+    let mut data = vec![0u8; 82];
+    data[44] = decimals;
+    spl_account(data)
+}
+
+/// Detect from SOL and size every cycle, reporting the result as text
+fn detect_and_size(graph: Arc<PriceGraph>) -> String {
+    let limits = CapitalLimits { max_capital: 1_000_000_000, per_cycle_percent: 100, min_profit: 0, min_input_fraction: 0.0, max_input_fraction: 1.0 };
+    let mut cycles = CycleDetector::find_negative_cycles(&graph, sol_mint(), 2, 4, ProfitThreshold::flat(10));
+    let optimizer = AmountOptimizer::new(graph, 1_000_000, 0.0);
+    let amounts: Vec<Option<u64>> = cycles.iter_mut().map(|cycle| optimizer.optimize_amount(cycle, &limits, ProfitThreshold::flat(10))).collect();
+    format!("{:#?}\n{:?}", cycles, amounts)
+}
+
+#[test]
+fn detection_and_sizing_are_reproducible_for_one_snapshot() {
+    let rpc = MockRpc::new();
+    rpc.set_account(sol_mint(), mint(9));
+    // Three tokens, each in two Raydium pools 1 SOL deep with the token cheaper in one
+    let mut snapshot = Vec::new();
+    for spread_bps in [150u64, 250, 400] {
+        let token = Pubkey::new_unique();
+        rpc.set_account(token, mint(6));
+        let mut pool_data = MintPoolData::new(token, &Pubkey::new_unique(), spl_token::id());
+        for token_reserve in [4_000_000_000_000u64, 4_000_000_000_000 * (10_000 + spread_bps) / 10_000] {
+            let (pool, token_vault, sol_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            rpc.set_account(token_vault, vault(token, token_reserve));
+            rpc.set_account(sol_vault, vault(sol_mint(), 1_000_000_000));
+            pool_data.add_raydium_pool(pool, token_vault, sol_vault, token, sol_mint());
+        }
+        snapshot.push(pool_data);
+    }
+
+    let run = |order: &[usize]| {
+        let graph = Arc::new(PriceGraph::new());
+        graph.begin_generation();
+        for &i in order {
+            graph.update_from_mint_pool_data(&snapshot[i], &rpc);
+        }
+        detect_and_size(graph)
+    };
+    let first = run(&[0, 1, 2]);
+    assert!(first.contains("Some("), "the snapshot holds at least one sized cycle: {}", first);
+    assert_eq!(run(&[0, 1, 2]), first);
+    // Loading the same pools in another order changes nothing either
+    assert_eq!(run(&[2, 0, 1]), first);
+}
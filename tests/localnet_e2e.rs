@@ -0,0 +1,337 @@
+//! End-to-end capture of a seeded arbitrage on a local validator.
+//!
+//! Starts `solana-test-validator` with the on-chain executor and the Raydium
+//! CP-Swap program loaded from cached binaries, seeds two constant-product
+//! pools on the same SOL/TOKEN pair at different prices, and runs the bot's
+//! pipeline against them in live mode: refresh, detect, optimize, build and
+//! send, then the post-trade review that writes the ledger.
+//!
+//! The fixtures directory (`LOCALNET_FIXTURES_DIR`, default
+//! `fixtures/localnet`) must hold:
+//! - `executor.so` and `raydium_cp.so`, dumped with `solana program dump`
+//! - `accounts/*.json`: two CP-Swap AMM configs and the create-pool fee
+//!   receiver, dumped with `solana account --output json`
+//!
+//! `scripts/fetch_localnet_fixtures.sh` dumps all of them from mainnet. Run
+//! with `cargo test --features localnet-e2e --test localnet_e2e`.
+#![cfg(feature = "localnet-e2e")]
+
+use solana_client::rpc_client::RpcClient;
+use solana_onchain_arbitrage_bot::config::Config;
+use solana_onchain_arbitrage_bot::confirm::VersionFingerprint;
+use solana_onchain_arbitrage_bot::constants::sol_mint;
+use solana_onchain_arbitrage_bot::dex::raydium::raydium_cp_program_id;
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_onchain_arbitrage_bot::execute::{LiveExecutor, PostTradeReview};
+use solana_onchain_arbitrage_bot::journal::ExecutionJournal;
+use solana_onchain_arbitrage_bot::ledger::{load_ledger, LedgerStatus};
+use solana_onchain_arbitrage_bot::refresh::initialize_pools_from_markets;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::hashv;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::{system_instruction, system_program, sysvar};
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const EXECUTOR_PROGRAM_ID: &str = "MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz";
+const RPC_PORT: u16 = 18899;
+const FAUCET_PORT: u16 = 19900;
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(90);
+const TOKEN_DECIMALS: u8 = 6;
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// The validator process, killed when the test ends however it ends
+struct Validator {
+    child: Child,
+    ledger: PathBuf,
+}
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.ledger);
+    }
+}
+
+fn fixtures_dir() -> PathBuf {
+    std::env::var("LOCALNET_FIXTURES_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/localnet"))
+}
+
+fn start_validator(fixtures: &Path, scratch: &Path) -> Validator {
+    for required in ["executor.so", "raydium_cp.so", "accounts"] {
+        assert!(
+            fixtures.join(required).exists(),
+            "missing {} in {}, run scripts/fetch_localnet_fixtures.sh",
+            required,
+            fixtures.display()
+        );
+    }
+    let ledger = scratch.join("ledger");
+    let child = Command::new("solana-test-validator")
+        .arg("--reset")
+        .arg("--quiet")
+        .args(["--ledger".as_ref(), ledger.as_os_str()])
+        .args(["--rpc-port", &RPC_PORT.to_string()])
+        .args(["--faucet-port", &FAUCET_PORT.to_string()])
+        .args(["--bpf-program", EXECUTOR_PROGRAM_ID])
+        .arg(fixtures.join("executor.so"))
+        .args(["--bpf-program", &raydium_cp_program_id().to_string()])
+        .arg(fixtures.join("raydium_cp.so"))
+        .arg("--account-dir")
+        .arg(fixtures.join("accounts"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("solana-test-validator must be on PATH");
+    Validator { child, ledger }
+}
+
+fn wait_until_healthy(rpc: &RpcClient) {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while rpc.get_health().is_err() {
+        assert!(Instant::now() < deadline, "validator not healthy after {:?}", STARTUP_TIMEOUT);
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, instructions: &[Instruction], extra_signers: &[&Keypair]) {
+    let mut signers = vec![payer];
+    signers.extend_from_slice(extra_signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &signers, rpc.get_latest_blockhash().unwrap());
+    rpc.send_and_confirm_transaction(&tx).unwrap();
+}
+
+/// Pubkeys of the dumped accounts owned by the CP-Swap program: its AMM configs
+fn amm_configs(fixtures: &Path) -> Vec<Pubkey> {
+    let mut configs: Vec<Pubkey> = std::fs::read_dir(fixtures.join("accounts"))
+        .unwrap()
+        .filter_map(|entry| {
+            let dump: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(entry.ok()?.path()).ok()?).ok()?;
+            let owner = dump["account"]["owner"].as_str()?;
+            (owner == raydium_cp_program_id().to_string()).then(|| dump["pubkey"].as_str()?.parse().ok())?
+        })
+        .collect();
+    configs.sort();
+    configs
+}
+
+/// Create a CP-Swap pool for `mint_a`/`mint_b` under `amm_config`, funded
+/// from the creator's associated token accounts
+fn create_cp_pool(
+    rpc: &RpcClient,
+    creator: &Keypair,
+    amm_config: Pubkey,
+    (mint_a, amount_a): (Pubkey, u64),
+    (mint_b, amount_b): (Pubkey, u64),
+) -> Pubkey {
+    let program = raydium_cp_program_id();
+    let ((mint_0, amount_0), (mint_1, amount_1)) =
+        if mint_a < mint_b { ((mint_a, amount_a), (mint_b, amount_b)) } else { ((mint_b, amount_b), (mint_a, amount_a)) };
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &program).0;
+    let authority = pda(&[b"vault_and_lp_mint_auth_seed"]);
+    let pool = pda(&[b"pool", amm_config.as_ref(), mint_0.as_ref(), mint_1.as_ref()]);
+    let lp_mint = pda(&[b"pool_lp_mint", pool.as_ref()]);
+    let vault_0 = pda(&[b"pool_vault", pool.as_ref(), mint_0.as_ref()]);
+    let vault_1 = pda(&[b"pool_vault", pool.as_ref(), mint_1.as_ref()]);
+    let observation = pda(&[b"observation", pool.as_ref()]);
+    let create_pool_fee: Pubkey = "DNXgeM9EiiaAbaWvwjHj9fQQLAX5ZsfHyxmYUNRAftam".parse().unwrap();
+
+    let mut data = hashv(&[b"global:initialize"]).to_bytes()[..8].to_vec();
+    data.extend_from_slice(&amount_0.to_le_bytes());
+    data.extend_from_slice(&amount_1.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes()); // open_time: as soon as possible
+    let initialize = Instruction {
+        program_id: program,
+        accounts: vec![
+            AccountMeta::new(creator.pubkey(), true),
+            AccountMeta::new_readonly(amm_config, false),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new_readonly(mint_0, false),
+            AccountMeta::new_readonly(mint_1, false),
+            AccountMeta::new(lp_mint, false),
+            AccountMeta::new(get_associated_token_address(&creator.pubkey(), &mint_0), false),
+            AccountMeta::new(get_associated_token_address(&creator.pubkey(), &mint_1), false),
+            AccountMeta::new(get_associated_token_address(&creator.pubkey(), &lp_mint), false),
+            AccountMeta::new(vault_0, false),
+            AccountMeta::new(vault_1, false),
+            AccountMeta::new(create_pool_fee, false),
+            AccountMeta::new(observation, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data,
+    };
+    send(rpc, creator, &[solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(400_000), initialize], &[]);
+    pool
+}
+
+/// Fund `wallet`, mint TOKEN to it, wrap SOL and create two SOL/TOKEN pools:
+/// the cheap one sells 100k TOKEN per SOL, the dear one 80k
+fn seed_market(rpc: &RpcClient, wallet: &Keypair, fixtures: &Path) -> (Pubkey, [Pubkey; 2]) {
+    let airdrop = rpc.request_airdrop(&wallet.pubkey(), 1_000 * LAMPORTS_PER_SOL).unwrap();
+    while !rpc.confirm_transaction(&airdrop).unwrap() {
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    let mint = Keypair::new();
+    let owner = wallet.pubkey();
+    let token_ata = get_associated_token_address(&owner, &mint.pubkey());
+    let wsol_ata = get_associated_token_address(&owner, &sol_mint());
+    let rent = rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN).unwrap();
+    send(
+        rpc,
+        wallet,
+        &[
+            system_instruction::create_account(&owner, &mint.pubkey(), rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+            spl_token::instruction::initialize_mint2(&spl_token::id(), &mint.pubkey(), &owner, None, TOKEN_DECIMALS).unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(&owner, &owner, &mint.pubkey(), &spl_token::id()),
+            spl_token::instruction::mint_to(&spl_token::id(), &mint.pubkey(), &token_ata, &owner, &[], 100_000_000 * 10u64.pow(TOKEN_DECIMALS as u32)).unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(&owner, &owner, &sol_mint(), &spl_token::id()),
+            system_instruction::transfer(&owner, &wsol_ata, 500 * LAMPORTS_PER_SOL),
+            spl_token::instruction::sync_native(&spl_token::id(), &wsol_ata).unwrap(),
+        ],
+        &[&mint],
+    );
+
+    let configs = amm_configs(fixtures);
+    assert!(configs.len() >= 2, "need two CP-Swap AMM configs in the fixtures, found {}", configs.len());
+    let token = |whole: u64| whole * 10u64.pow(TOKEN_DECIMALS as u32);
+    let cheap = create_cp_pool(rpc, wallet, configs[0], (sol_mint(), 100 * LAMPORTS_PER_SOL), (mint.pubkey(), token(10_000_000)));
+    let dear = create_cp_pool(rpc, wallet, configs[1], (sol_mint(), 100 * LAMPORTS_PER_SOL), (mint.pubkey(), token(8_000_000)));
+    // Swaps open one second after the pool's creation time
+    std::thread::sleep(Duration::from_secs(3));
+    (mint.pubkey(), [cheap, dear])
+}
+
+fn write_config(scratch: &Path, wallet: &Keypair, pools: &[Pubkey]) -> Config {
+    let file = |name: &str| scratch.join(name).display().to_string();
+    let markets: Vec<String> = pools.iter().map(|pool| format!("\"{}\"", pool)).collect();
+    let config = format!(
+        r#"
+        [bot]
+        compute_unit_limit = 400000
+        strict_mode = true
+
+        [routing.markets]
+        markets = [{markets}]
+        process_delay = 0
+
+        [rpc]
+        url = "http://127.0.0.1:{RPC_PORT}"
+
+        [wallet]
+        private_key = "{private_key}"
+
+        [ledger]
+        file = "{ledger}"
+
+        [execution]
+        enabled = true
+        confirm_timeout_secs = 30
+        journal_file = "{journal}"
+        kill_switch_file = "{kill_switch}"
+        "#,
+        markets = markets.join(", "),
+        private_key = wallet.to_base58_string(),
+        ledger = file("ledger.jsonl"),
+        journal = file("journal.jsonl"),
+        kill_switch = file("kill_switch.json"),
+    );
+    let path = file("config.toml");
+    std::fs::write(&path, config).unwrap();
+    Config::load(&path).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_seeded_two_pool_arbitrage_is_captured_and_recorded() {
+    let fixtures = fixtures_dir();
+    let scratch = std::env::temp_dir().join(format!("localnet-e2e-{}", Pubkey::new_unique()));
+    std::fs::create_dir_all(&scratch).unwrap();
+    let _validator = start_validator(&fixtures, &scratch);
+    let rpc = Arc::new(RpcClient::new_with_commitment(format!("http://127.0.0.1:{}", RPC_PORT), CommitmentConfig::confirmed()));
+    wait_until_healthy(&rpc);
+
+    let wallet = Keypair::new();
+    let (token_mint, pools) = seed_market(&rpc, &wallet, &fixtures);
+    let config = write_config(&scratch, &wallet, &pools);
+
+    // Refresh: decode both pools and price them into the graph
+    let mint_pool_data = initialize_pools_from_markets(&config.routing.markets, &wallet.pubkey(), rpc.clone(), true)
+        .await
+        .unwrap();
+    assert_eq!(mint_pool_data[&token_mint].raydium_cp_pools.len(), 2);
+    let graph = Arc::new(PriceGraph::with_bootstrap_sol_price(150.0));
+    graph.set_liquidity_filter(0.0, 1.0);
+    graph.set_strict_mode(true);
+    graph.begin_generation();
+    graph.try_update_from_mint_pool_data(&mint_pool_data[&token_mint], &*rpc).unwrap();
+
+    // Detect: buying TOKEN in the cheap pool and selling it in the dear one
+    let threshold = ProfitThreshold { base_bps: 10, per_hop_increment_bps: 0 };
+    let mut cycles = IncrementalDetector::new(1).detect(&graph, sol_mint(), 2, 2, threshold);
+    CapabilityRegistry::from_registered_builders().classify(&mut cycles);
+    let mut cycle = cycles
+        .into_iter()
+        .find(|cycle| !cycle.observational && cycle.legs[0].pool_pubkey == pools[0] && cycle.legs[1].pool_pubkey == pools[1])
+        .expect("the cheap-to-dear cycle is detected");
+
+    // Optimize: size the input within a 10 SOL budget
+    let limits = CapitalLimits {
+        max_capital: 10 * LAMPORTS_PER_SOL,
+        per_cycle_percent: 100,
+        min_profit: LAMPORTS_PER_SOL / 100,
+        min_input_fraction: 0.01,
+        max_input_fraction: 1.0,
+    };
+    let amount = AmountOptimizer::new(graph.clone(), 1, 0.0)
+        .optimize_amount(&mut cycle, &limits, threshold)
+        .expect("the seeded spread clears the profit threshold");
+
+    // Build, send and confirm through the on-chain executor
+    let journal = ExecutionJournal::new(scratch.join("journal.jsonl").display().to_string());
+    let mut executor = LiveExecutor::new(&config, rpc.as_ref(), Duration::from_secs(30), journal.clone(), 7).unwrap();
+    let fill = executor
+        .execute(&wallet, &config, &cycle, amount, &mint_pool_data, &ComputeUnitTable::default(), rpc.as_ref(), 0, &mut || None)
+        .await
+        .unwrap()
+        .expect("the transaction confirms");
+    assert!(fill.success, "transaction {} reverted", fill.signature);
+
+    // Review: the wallet gained at least the modeled minimum and the ledger has the fill
+    let ledger_file = config.ledger.clone().unwrap().file;
+    let kill_switch = KillSwitch::load(&config.execution.clone().unwrap().kill_switch_file).await.unwrap();
+    let mut review = PostTradeReview::new(10_000, LAMPORTS_PER_SOL, kill_switch, ledger_file.clone(), journal, &VersionFingerprint::new(b""));
+    let record = fill.ledger_record(&cycle, &sol_mint(), 0);
+    assert!(
+        record.base_delta >= limits.min_profit as i128,
+        "wallet gained {} lamports, modeled at least {}",
+        record.base_delta,
+        limits.min_profit
+    );
+    let report = review.review(&cycle, &fill.pre, &fill.post, cycle.estimated_tx_cost_lamports, &graph, record, 0).await;
+    assert_ne!(report.severity, ReconciliationSeverity::Unexplained);
+    assert!(review.halted().is_none());
+
+    let ledger = load_ledger(&ledger_file).await.unwrap();
+    assert_eq!(ledger.len(), 1);
+    assert_eq!(ledger[0].signature, fill.signature.to_string());
+    assert_eq!(ledger[0].status, LedgerStatus::Landed);
+    assert_eq!(ledger[0].cycle_id, Some(cycle.cycle_id()));
+
+    let _ = std::fs::remove_dir_all(&scratch);
+}
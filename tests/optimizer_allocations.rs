@@ -0,0 +1,79 @@
+//! Benchmark-style check that sizing a cycle reads graph edges in place: the
+//! optimizer's hot loop should not allocate, however large the graph is.
+//!
+//! Not built with the `profiling` feature, whose allocator replaces this one.
+#![cfg(not(feature = "profiling"))]
+
+use solana_onchain_arbitrage_bot::constants::sol_mint;
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_sdk::pubkey::Pubkey;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Counts allocations made by the current thread, so the test harness's own
+/// threads do not show up in the numbers
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn quote(graph: &PriceGraph, from_mint: Pubkey, to_mint: Pubkey, price: f64) {
+    graph.add_edge(from_mint, to_mint, PoolEdge::new(Pubkey::new_unique(), to_mint, DexType::RaydiumV4, price, 1e6, 25, spl_token::id()));
+}
+
+#[test]
+fn many_sizing_evaluations_allocate_nothing() {
+    let graph = Arc::new(PriceGraph::new());
+    let sol = sol_mint();
+    // One mispriced token among 500 fairly priced ones
+    let target = Pubkey::new_unique();
+    quote(&graph, sol, target, 100.0);
+    quote(&graph, target, sol, 0.0102);
+    for _ in 0..500 {
+        let token = Pubkey::new_unique();
+        quote(&graph, sol, token, 100.0);
+        quote(&graph, token, sol, 0.01);
+    }
+    let cycles = CycleDetector::find_negative_cycles(&graph, sol, 2, 2, ProfitThreshold::flat(50));
+    assert_eq!(cycles.len(), 1);
+    let optimizer = AmountOptimizer::new(graph.clone(), 1, 0.0);
+
+    const ITERATIONS: u64 = 20_000;
+    let (before, started) = (allocations(), Instant::now());
+    let mut profitable = 0;
+    for i in 0..ITERATIONS {
+        let amount = 10_000_000 + i * 10_000;
+        profitable += optimizer.expected_profit(&cycles[0], amount).is_some() as u64;
+    }
+    let (allocated, elapsed) = (allocations() - before, started.elapsed());
+
+    assert_eq!(profitable, ITERATIONS);
+    assert_eq!(
+        allocated, 0,
+        "{} allocations over {} evaluations ({:?} per evaluation)",
+        allocated,
+        ITERATIONS,
+        elapsed / ITERATIONS as u32
+    );
+}
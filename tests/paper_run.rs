@@ -0,0 +1,103 @@
+//! One simulated hour of paper trading.
+//!
+//! Seeds the market simulator from a fixture universe of SOL pairs, then runs
+//! the loop's paper path once per simulated second: shock the market, write
+//! its prices over the graph, detect, size, fill against the simulated
+//! reserves and record each fill in the ledger the way the bot does.
+//!
+//! Run with `cargo test --features paper-run --test paper_run`.
+#![cfg(feature = "paper-run")]
+
+use solana_onchain_arbitrage_bot::clock::{Clock, MockClock};
+use solana_onchain_arbitrage_bot::constants::sol_mint;
+use solana_onchain_arbitrage_bot::engine::*;
+use solana_onchain_arbitrage_bot::ledger::{append_records, load_ledger, PnlReport};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SECONDS: u64 = 3_600;
+const FEE_LAMPORTS: u64 = 10_000;
+
+/// Eight tokens, each traded against SOL on three $200k pools quoted at the same price
+fn fixture_universe() -> Arc<PriceGraph> {
+    let graph = Arc::new(PriceGraph::new());
+    graph.set_sol_price(150.0, true);
+    for i in 0..8 {
+        let token = Pubkey::new_unique();
+        let tokens_per_lamport = 0.5 + i as f64;
+        for _ in 0..3 {
+            let pool = Pubkey::new_unique();
+            graph.add_edge(sol_mint(), token, PoolEdge::new(pool, token, DexType::RaydiumV4, tokens_per_lamport, 200_000.0, 25, spl_token::id()));
+            graph.add_edge(token, sol_mint(), PoolEdge::new(pool, sol_mint(), DexType::RaydiumV4, 1.0 / tokens_per_lamport, 200_000.0, 25, spl_token::id()));
+        }
+    }
+    graph
+}
+
+#[tokio::test]
+async fn an_hour_of_paper_trading_books_a_plausible_pnl_curve() {
+    let graph = fixture_universe();
+    let clock = MockClock::new(1_700_000_000);
+    let mut market = MarketSimulator::new(0.05, 40.0, 227);
+    let optimizer = AmountOptimizer::new(graph.clone(), 1_000_000, 0.0);
+    let limits = CapitalLimits {
+        max_capital: 2_000_000_000,
+        per_cycle_percent: 100,
+        min_profit: 100_000,
+        min_input_fraction: 0.0,
+        max_input_fraction: 1.0,
+    };
+    let threshold = ProfitThreshold::flat(50);
+
+    let mut records = Vec::new();
+    let mut minute_pnl = Vec::new(); // Cumulative net lamports at the end of each minute
+    let mut cumulative: i128 = 0;
+    for second in 0..SECONDS {
+        graph.begin_generation();
+        market.seed_from_graph(&graph);
+        market.shock();
+        market.apply_to_graph(&graph);
+
+        for mut cycle in CycleDetector::find_negative_cycles(&graph, sol_mint(), 2, 3, threshold) {
+            let Some(amount) = optimizer.optimize_amount(&mut cycle, &limits, threshold) else {
+                continue;
+            };
+            let fill = market.execute(&cycle, amount).expect("every fixture pool is simulated");
+            let record = fill.ledger_record(records.len() as u64 + 1, &cycle, &sol_mint(), second, FEE_LAMPORTS, clock.now_unix());
+            cumulative += record.net_delta();
+            optimizer.record_fill(&cycle, &fill.leg_outputs);
+            records.push(record);
+            // Later cycles of this pass see the reserves the fill left behind
+            market.apply_to_graph(&graph);
+        }
+
+        clock.advance(Duration::from_secs(1));
+        if (second + 1) % 60 == 0 {
+            minute_pnl.push(cumulative);
+        }
+    }
+    assert_eq!(market.pool_count(), 24);
+    assert_eq!(minute_pnl.len(), 60);
+
+    // Shocks keep opening spreads, and each fill closes the one it took
+    let winners = records.iter().filter(|record| record.net_delta() > 0).count();
+    assert!(records.len() >= 60, "only {} fills in an hour", records.len());
+    assert!(winners * 10 >= records.len() * 9, "{} of {} fills were net positive", winners, records.len());
+    for record in &records {
+        assert!(record.paper && !record.imported);
+        // No single fill returns more than 5% of the capital it could use
+        assert!(record.base_delta < limits.max_capital as i128 / 20, "{} returned {}", record.signature, record.base_delta);
+    }
+    assert!(minute_pnl.iter().all(|pnl| *pnl >= 0));
+    assert!(minute_pnl[59] > minute_pnl[29] && minute_pnl[29] > 0, "P&L by minute: {:?}", minute_pnl);
+
+    // The ledger holds the paper run exactly as the loop booked it
+    let ledger = std::env::temp_dir().join(format!("{}_paper_ledger.jsonl", Pubkey::new_unique()));
+    let ledger = ledger.to_str().unwrap();
+    append_records(ledger, &records).await.unwrap();
+    let report = PnlReport::from_records(&load_ledger(ledger).await.unwrap());
+    assert_eq!(report.records, records.len());
+    assert_eq!(report.base_deltas[&sol_mint().to_string()] - report.fee_lamports as i128, cumulative);
+    std::fs::remove_file(ledger).unwrap();
+}